@@ -0,0 +1,121 @@
+use crossterm::style::Print;
+use crossterm::terminal::SetTitle;
+use crossterm::QueueableCommand;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+
+/// xterm's title stack: save the title before changing it, put it back on
+/// exit. Terminals without the stack ignore these.
+const PUSH_TITLE: &str = "\x1b[22;0t";
+const POP_TITLE: &str = "\x1b[23;0t";
+
+/// Signals for noticing the TUI from another window. Both are off unless
+/// turned on, as some terminals render titles poorly.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AttentionSettings {
+    /// Ring the terminal bell when background work finishes.
+    #[serde(default)]
+    pub bell: bool,
+    /// Show a short status in the terminal (and tmux) window title.
+    #[serde(default)]
+    pub title: bool,
+}
+
+/// Something worth a glance from another window.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttentionEvent {
+    /// Work going on, e.g. "import 40%".
+    Progress(String),
+    Done(String),
+    Failed(String),
+    /// A job the watchdog thinks is hung.
+    Stalled(String),
+}
+
+impl AttentionEvent {
+    /// E.g. "MSST: batch done ✔".
+    pub fn title(&self) -> String {
+        match self {
+            AttentionEvent::Progress(status) => format!("MSST: {}", status),
+            AttentionEvent::Done(what) => format!("MSST: {} done ✔", what),
+            AttentionEvent::Failed(what) => format!("MSST: {} failed ✘", what),
+            AttentionEvent::Stalled(what) => format!("MSST: {} possibly hung ⚠", what),
+        }
+    }
+
+    fn rings(&self) -> bool {
+        !matches!(self, AttentionEvent::Progress(_))
+    }
+}
+
+/// Writes the bell and title escape sequences. They are queued through
+/// crossterm like the rest of the output and neither move the cursor nor
+/// draw anything, so the alternate screen is left alone. The settings are
+/// passed on every call, so turning a signal off takes effect at once.
+#[derive(Debug, Default)]
+pub struct Attention {
+    shown: Option<String>,
+}
+
+impl Attention {
+    pub fn new() -> Self {
+        Attention::default()
+    }
+
+    pub fn signal(&mut self, out: &mut impl Write, settings: &AttentionSettings, event: &AttentionEvent) -> io::Result<()> {
+        let title = event.title();
+        if !settings.title {
+            self.clear(out)?;
+        } else if self.shown.as_ref() != Some(&title) {
+            if self.shown.is_none() {
+                out.queue(Print(PUSH_TITLE))?;
+            }
+            out.queue(SetTitle(&title))?;
+            self.shown = Some(title);
+        }
+        if settings.bell && event.rings() {
+            out.queue(Print('\x07'))?;
+        }
+        out.flush()
+    }
+
+    /// Clears the title, restoring the one from before where the terminal
+    /// can. Called on exit.
+    pub fn clear(&mut self, out: &mut impl Write) -> io::Result<()> {
+        if self.shown.take().is_some() {
+            out.queue(SetTitle(""))?;
+            out.queue(Print(POP_TITLE))?;
+        }
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_only_what_is_enabled_and_changed() {
+        let mut out = vec![];
+        let mut attention = Attention::new();
+        let off = AttentionSettings::default();
+        attention.signal(&mut out, &off, &AttentionEvent::Done("batch".into())).unwrap();
+        attention.clear(&mut out).unwrap();
+        assert!(out.is_empty());
+
+        let on = AttentionSettings { bell: true, title: true };
+        let progress = AttentionEvent::Progress("training ep 42, SDR 9.1".into());
+        attention.signal(&mut out, &on, &progress).unwrap();
+        attention.signal(&mut out, &on, &progress).unwrap();
+        attention.signal(&mut out, &on, &AttentionEvent::Done("batch".into())).unwrap();
+        assert_eq!(
+            String::from_utf8(std::mem::take(&mut out)).unwrap(),
+            "\x1b[22;0t\x1b]0;MSST: training ep 42, SDR 9.1\x07\x1b]0;MSST: batch done ✔\x07\x07"
+        );
+
+        // Turned off while shown: the title is cleared right away.
+        attention.signal(&mut out, &off, &progress).unwrap();
+        attention.clear(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "\x1b]0;\x07\x1b[23;0t");
+    }
+}
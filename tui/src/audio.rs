@@ -0,0 +1,366 @@
+use anyhow::{anyhow, Context, Result};
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use symphonia::core::audio::{Channels, SampleBuffer};
+use symphonia::core::codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::{Error as SymphoniaError, SeekErrorKind};
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioInfo {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub layout: Channels,
+    pub total_frames: Option<u64>,
+}
+
+impl AudioInfo {
+    pub fn duration_secs(&self) -> Option<f64> {
+        self.total_frames
+            .map(|frames| frames as f64 / self.sample_rate as f64)
+    }
+}
+
+/// Streaming decoder producing interleaved `f32` samples.
+///
+/// Seeking is sample-accurate for wav and flac: after `seek(n)` the next
+/// chunk starts exactly at frame `n`. The container only gets us to a packet
+/// boundary at or before `n`, so the frames in between are decoded and
+/// dropped. Seeking to the last frame or beyond leaves the decoder at end of
+/// stream instead of failing.
+pub struct AudioDecoder {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    info: AudioInfo,
+    samples: Vec<f32>,
+    skip_until: Option<u64>,
+    finished: bool,
+}
+
+impl AudioDecoder {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open audio file {}", path.display()))?;
+        let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+        let mut hint = Hint::new();
+        if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
+            hint.with_extension(ext);
+        }
+
+        let probed = symphonia::default::get_probe()
+            .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+            .with_context(|| format!("Unsupported audio format: {}", path.display()))?;
+        let format = probed.format;
+
+        let track = format.tracks()
+            .iter()
+            .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or_else(|| anyhow!("No audio track in {}", path.display()))?;
+
+        let params = &track.codec_params;
+        let sample_rate = params.sample_rate
+            .ok_or_else(|| anyhow!("Unknown sample rate in {}", path.display()))?;
+        let layout = params.channels
+            .ok_or_else(|| anyhow!("Unknown channel layout in {}", path.display()))?;
+
+        let decoder = symphonia::default::get_codecs()
+            .make(params, &DecoderOptions::default())
+            .context("Failed to create audio decoder")?;
+
+        Ok(AudioDecoder {
+            track_id: track.id,
+            info: AudioInfo {
+                sample_rate,
+                channels: layout.count() as u16,
+                layout,
+                total_frames: params.n_frames,
+            },
+            format,
+            decoder,
+            samples: Vec::new(),
+            skip_until: None,
+            finished: false,
+        })
+    }
+
+    pub fn info(&self) -> AudioInfo {
+        self.info
+    }
+
+    pub fn seek(&mut self, frame: u64) -> Result<()> {
+        let to = SeekTo::TimeStamp { ts: frame, track_id: self.track_id };
+        match self.format.seek(SeekMode::Accurate, to) {
+            Ok(seeked) => {
+                self.decoder.reset();
+                self.skip_until = Some(seeked.required_ts);
+                self.finished = false;
+                Ok(())
+            }
+            Err(SymphoniaError::SeekError(SeekErrorKind::OutOfRange)) => {
+                self.finished = true;
+                Ok(())
+            }
+            Err(e) => Err(e).context("Failed to seek audio stream"),
+        }
+    }
+
+    /// Decodes the next packet. Returns `None` at end of stream.
+    pub fn next_chunk(&mut self) -> Result<Option<&[f32]>> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(e)) if e.kind() == ErrorKind::UnexpectedEof => {
+                    self.finished = true;
+                    return Ok(None);
+                }
+                Err(e) => return Err(e).context("Failed to read audio packet"),
+            };
+
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                Err(SymphoniaError::DecodeError(_)) => continue,
+                Err(e) => return Err(e).context("Failed to decode audio packet"),
+            };
+
+            let frames = decoded.frames() as u64;
+            let mut skip = 0;
+            if let Some(target) = self.skip_until {
+                if packet.ts() + frames <= target {
+                    continue;
+                }
+                skip = target.saturating_sub(packet.ts()) as usize;
+                self.skip_until = None;
+            }
+
+            let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, *decoded.spec());
+            buffer.copy_interleaved_ref(decoded);
+
+            let channels = self.info.channels as usize;
+            self.samples.clear();
+            self.samples.extend_from_slice(&buffer.samples()[skip * channels..]);
+            return Ok(Some(&self.samples));
+        }
+    }
+}
+
+pub fn probe(path: &Path) -> Result<AudioInfo> {
+    Ok(AudioDecoder::open(path)?.info())
+}
+
+/// Decodes a whole file into one sample vector per channel.
+pub fn decode_planar(path: &Path) -> Result<(AudioInfo, Vec<Vec<f32>>)> {
+    let mut decoder = AudioDecoder::open(path)?;
+    let info = decoder.info();
+    let channels = info.channels as usize;
+
+    let mut planar: Vec<Vec<f32>> = vec![Vec::new(); channels];
+    while let Some(chunk) = decoder.next_chunk()? {
+        for frame in chunk.chunks_exact(channels) {
+            for (channel, value) in frame.iter().enumerate() {
+                planar[channel].push(*value);
+            }
+        }
+    }
+    Ok((info, planar))
+}
+
+/// Writes planar audio as a 32-bit float wav.
+pub fn write_wav(path: &Path, sample_rate: u32, planar: &[Vec<f32>]) -> Result<()> {
+    let spec = hound::WavSpec {
+        channels: planar.len() as u16,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let mut writer = hound::WavWriter::create(path, spec)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    let frames = planar.first().map_or(0, |channel| channel.len());
+    for frame in 0..frames {
+        for channel in planar {
+            writer.write_sample(channel[frame])?;
+        }
+    }
+    writer.finalize()
+        .with_context(|| format!("Failed to finalize {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const RATE: u32 = 44100;
+    const FRAMES: u32 = 20_000;
+
+    // Left channel counts frames, right channel counts down, so any decoded
+    // frame tells us exactly where in the stream it came from.
+    fn sample(frame: u32, channel: u32) -> i16 {
+        let value = (frame % 16_000) as i16;
+        if channel == 0 { value } else { -value }
+    }
+
+    fn write_wav(dir: &Path) -> PathBuf {
+        let path = dir.join("ramp.wav");
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: RATE,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for frame in 0..FRAMES {
+            for channel in 0..2 {
+                writer.write_sample(sample(frame, channel)).unwrap();
+            }
+        }
+        writer.finalize().unwrap();
+        path
+    }
+
+    // Minimal FLAC writer using verbatim subframes, enough to exercise the
+    // demuxer's frame search without shipping binary fixtures.
+    fn write_flac(dir: &Path) -> PathBuf {
+        const BLOCK: u32 = 4096;
+
+        fn crc8(data: &[u8]) -> u8 {
+            let mut crc = 0u8;
+            for byte in data {
+                crc ^= byte;
+                for _ in 0..8 {
+                    crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+                }
+            }
+            crc
+        }
+
+        fn crc16(data: &[u8]) -> u16 {
+            let mut crc = 0u16;
+            for byte in data {
+                crc ^= (*byte as u16) << 8;
+                for _ in 0..8 {
+                    crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x8005 } else { crc << 1 };
+                }
+            }
+            crc
+        }
+
+        fn utf8_number(value: u32) -> Vec<u8> {
+            if value < 0x80 {
+                vec![value as u8]
+            } else {
+                assert!(value < 0x800);
+                vec![0xC0 | (value >> 6) as u8, 0x80 | (value & 0x3F) as u8]
+            }
+        }
+
+        let mut out = b"fLaC".to_vec();
+        out.extend_from_slice(&[0x80, 0, 0, 34]);
+        out.extend_from_slice(&(BLOCK as u16).to_be_bytes());
+        out.extend_from_slice(&(BLOCK as u16).to_be_bytes());
+        out.extend_from_slice(&[0; 6]);
+        let packed = ((RATE as u64) << 44) | (1 << 41) | (15 << 36) | FRAMES as u64;
+        out.extend_from_slice(&packed.to_be_bytes());
+        out.extend_from_slice(&[0; 16]);
+
+        let mut start = 0;
+        let mut number = 0;
+        while start < FRAMES {
+            let len = BLOCK.min(FRAMES - start);
+            // Fixed blocking, 16-bit block size at end of header, 44.1 kHz,
+            // independent stereo, 16-bit samples.
+            let mut frame = vec![0xFF, 0xF8, 0x79, 0x18];
+            frame.extend(utf8_number(number));
+            frame.extend_from_slice(&((len - 1) as u16).to_be_bytes());
+            frame.push(crc8(&frame));
+            for channel in 0..2 {
+                frame.push(0x02);
+                for i in start..start + len {
+                    frame.extend_from_slice(&sample(i, channel).to_be_bytes());
+                }
+            }
+            let crc = crc16(&frame);
+            frame.extend_from_slice(&crc.to_be_bytes());
+            out.extend(frame);
+            start += len;
+            number += 1;
+        }
+
+        let path = dir.join("ramp.flac");
+        std::fs::write(&path, out).unwrap();
+        path
+    }
+
+    fn first_frame(decoder: &mut AudioDecoder) -> Option<(i16, i16)> {
+        let chunk = decoder.next_chunk().unwrap()?;
+        let to_i16 = |value: f32| (value * 32768.0).round() as i16;
+        Some((to_i16(chunk[0]), to_i16(chunk[1])))
+    }
+
+    fn check_seeks(path: &Path) {
+        let mut decoder = AudioDecoder::open(path).unwrap();
+        let info = decoder.info();
+        assert_eq!(info.sample_rate, RATE);
+        assert_eq!(info.channels, 2);
+        assert_eq!(info.total_frames, Some(FRAMES as u64));
+
+        assert_eq!(first_frame(&mut decoder), Some((0, 0)));
+
+        for target in [12_345u32, 5, 4096, 4097, 19_999, 0] {
+            decoder.seek(target as u64).unwrap();
+            assert_eq!(
+                first_frame(&mut decoder),
+                Some((sample(target, 0), sample(target, 1))),
+                "seek to {} in {}",
+                target,
+                path.display()
+            );
+        }
+
+        decoder.seek(FRAMES as u64 + 10).unwrap();
+        assert_eq!(first_frame(&mut decoder), None);
+
+        decoder.seek(100).unwrap();
+        assert_eq!(first_frame(&mut decoder), Some((100, -100)));
+    }
+
+    #[test]
+    fn wav_seek_is_sample_accurate() {
+        let dir = tempfile::tempdir().unwrap();
+        check_seeks(&write_wav(dir.path()));
+    }
+
+    #[test]
+    fn flac_seek_is_sample_accurate() {
+        let dir = tempfile::tempdir().unwrap();
+        check_seeks(&write_flac(dir.path()));
+    }
+
+    #[test]
+    fn decodes_every_frame_once() {
+        let dir = tempfile::tempdir().unwrap();
+        for path in [write_wav(dir.path()), write_flac(dir.path())] {
+            let mut decoder = AudioDecoder::open(&path).unwrap();
+            let mut total = 0;
+            while let Some(chunk) = decoder.next_chunk().unwrap() {
+                total += chunk.len() / 2;
+            }
+            assert_eq!(total, FRAMES as usize, "{}", path.display());
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::audio;
+use crate::cost::{CostSettings, RunCost};
+use crate::history::RunHistory;
+use crate::inference::InferenceManager;
+use crate::manifest::ResultsManifest;
+use crate::model::{InferenceConfig, ModelType};
+use crate::run_dir::{run_name, unique_run_path};
+use crate::secret::redact;
+use crate::task::TaskContext;
+use crate::validset::ValidSetStamp;
+
+pub const BAKEOFF_FILE: &str = "bakeoff.json";
+
+/// One model and checkpoint taking part in a bake-off.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BakeOffCandidate {
+    pub model_type: ModelType,
+    pub config_path: String,
+    pub start_checkpoint: String,
+}
+
+impl BakeOffCandidate {
+    /// `<model>` plus the checkpoint's file stem, for folder names and labels.
+    pub fn key(&self) -> String {
+        let checkpoint = Path::new(&self.start_checkpoint)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let key: String = format!("{}_{}", self.model_type.key(), checkpoint)
+            .chars()
+            .map(|c| if c.is_alphanumeric() || "-_.".contains(c) { c } else { '_' })
+            .collect();
+        key.trim_end_matches('_').to_string()
+    }
+}
+
+/// Every candidate separates the same input folder into its own store_dir
+/// under one bake-off folder.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BakeOffConfig {
+    pub label: Option<String>,
+    pub input_folder: String,
+    /// The bake-off folder is created in here.
+    pub output_root: String,
+    pub candidates: Vec<BakeOffCandidate>,
+    /// Ground truth as `<track>/<stem>.wav`; enables SDR per candidate.
+    #[serde(default)]
+    pub reference_folder: Option<String>,
+    /// Policies and output options applied to every candidate.
+    #[serde(default)]
+    pub options: Option<InferenceConfig>,
+}
+
+impl BakeOffConfig {
+    pub fn inference_config(&self, candidate: &BakeOffCandidate, store_dir: &Path) -> InferenceConfig {
+        let mut config = self.options.clone()
+            .unwrap_or_else(|| InferenceConfig::new(candidate.model_type.clone()));
+        config.model_type = candidate.model_type.clone();
+        config.config_path = candidate.config_path.clone();
+        config.start_checkpoint = candidate.start_checkpoint.clone();
+        config.input_folder = self.input_folder.clone();
+        config.store_dir = store_dir.to_string_lossy().to_string();
+        config
+    }
+}
+
+/// Store_dir name for the candidate at `index`. The index keeps two
+/// checkpoints of the same model with the same file name apart.
+pub fn store_dir_name(index: usize, candidate: &BakeOffCandidate) -> String {
+    format!("{:02}_{}", index + 1, candidate.key())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CandidateOutcome {
+    pub candidate: BakeOffCandidate,
+    /// Relative to the bake-off folder.
+    pub store_dir: String,
+    pub seconds: Option<f64>,
+    pub success: bool,
+    pub error: Option<String>,
+    /// Mean SDR over every track and stem with a reference.
+    pub sdr: Option<f64>,
+}
+
+/// `bakeoff.json` in the bake-off folder.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BakeOffReport {
+    pub name: String,
+    pub label: Option<String>,
+    pub input_folder: String,
+    pub reference_folder: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub outcomes: Vec<CandidateOutcome>,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<RunCost>,
+    /// What the reference folder held when the SDRs were measured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference_set: Option<ValidSetStamp>,
+}
+
+impl BakeOffReport {
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(BAKEOFF_FILE);
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content)
+            .context("Failed to parse bake-off report")
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)
+            .context("Failed to serialize bake-off report")?;
+        fs::write(dir.join(BAKEOFF_FILE), redact(&content))
+            .context("Failed to write bake-off report")?;
+        Ok(())
+    }
+
+    pub fn set_notes(&mut self, dir: &Path, notes: &str) -> Result<()> {
+        self.notes = notes.trim_end().to_string();
+        self.save(dir)
+    }
+
+    pub fn all_succeeded(&self) -> bool {
+        self.outcomes.iter().all(|outcome| outcome.success)
+    }
+}
+
+/// Runs every candidate one after another into its own store_dir under a new
+/// bake-off folder in `output_root`, timing each. A failed candidate is
+/// recorded and the rest still run. The bake-off goes into the run history
+/// as one entry, priced at `cost` if given. Returns the folder and its
+/// report.
+pub async fn run_bake_off(
+    manager: &mut InferenceManager,
+    config: &BakeOffConfig,
+    history_path: &Path,
+    cost: Option<&CostSettings>,
+) -> Result<(PathBuf, BakeOffReport)> {
+    if config.candidates.len() < 2 {
+        bail!("A bake-off needs at least two models");
+    }
+    let name = run_name("{date}_bakeoff_{label}", Local::now(), &config.candidates[0].model_type, config.label.as_deref());
+    let dir = unique_run_path(Path::new(&config.output_root), &name);
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create bake-off folder {}", dir.display()))?;
+
+    let mut report = BakeOffReport {
+        name: dir.file_name().unwrap_or_default().to_string_lossy().to_string(),
+        label: config.label.clone(),
+        input_folder: config.input_folder.clone(),
+        reference_folder: config.reference_folder.clone(),
+        started_at: Utc::now(),
+        finished_at: None,
+        outcomes: vec![],
+        notes: String::new(),
+        cost: cost.map(RunCost::new),
+        reference_set: config.reference_folder.as_deref().and_then(|folder| ValidSetStamp::take(folder).ok()),
+    };
+    report.save(&dir)?;
+    record_history(history_path, &dir, &report)?;
+
+    for (index, candidate) in config.candidates.iter().enumerate() {
+        let store_name = store_dir_name(index, candidate);
+        let store_dir = dir.join(&store_name);
+        let inference = config.inference_config(candidate, &store_dir);
+
+        let started = Instant::now();
+        let result = manager.run_inference(&inference).await;
+        let seconds = Some(started.elapsed().as_secs_f64());
+
+        let mut outcome = CandidateOutcome {
+            candidate: candidate.clone(),
+            store_dir: store_name,
+            seconds,
+            success: false,
+            error: None,
+            sdr: None,
+        };
+        match result {
+            Ok(result) => {
+                outcome.success = result.success;
+                outcome.error = result.error_line().map(String::from);
+            }
+            Err(e) => outcome.error = Some(format!("{:#}", e)),
+        }
+        if outcome.success
+            && let Some(reference) = &config.reference_folder
+        {
+            let reference = PathBuf::from(reference);
+            let measured = tokio::task::spawn_blocking(move || mean_sdr(&store_dir, &reference))
+                .await
+                .context("SDR task failed")?;
+            match measured {
+                Ok(sdr) => outcome.sdr = sdr,
+                Err(e) => outcome.error = Some(format!("SDR not measured: {:#}", e)),
+            }
+        }
+        report.outcomes.push(outcome);
+        report.save(&dir)?;
+    }
+
+    report.finished_at = Some(Utc::now());
+    report.save(&dir)?;
+    record_history(history_path, &dir, &report)?;
+    Ok((dir, report))
+}
+
+fn record_history(history_path: &Path, dir: &Path, report: &BakeOffReport) -> Result<()> {
+    let mut history = RunHistory::load(history_path)?;
+    history.record_bake_off(dir, report);
+    history.save()
+}
+
+/// Signal-to-distortion ratio in dB over all channels, as valid.py computes
+/// it. The estimate is cut or zero-padded to the reference length.
+pub fn sdr(reference: &[Vec<f32>], estimate: &[Vec<f32>]) -> Option<f64> {
+    const EPS: f64 = 1e-8;
+    let mut signal = 0.0;
+    let mut noise = 0.0;
+    for (channel, ref_channel) in reference.iter().enumerate() {
+        let est_channel = estimate.get(channel).or(estimate.first())?;
+        for (i, r) in ref_channel.iter().enumerate() {
+            let r = *r as f64;
+            let e = est_channel.get(i).copied().unwrap_or(0.0) as f64;
+            signal += r * r;
+            noise += (r - e) * (r - e);
+        }
+    }
+    (signal > 0.0).then(|| 10.0 * ((signal + EPS) / (noise + EPS)).log10())
+}
+
+/// Mean SDR over every output stem that has `<reference>/<track>/<stem>.wav`.
+pub fn mean_sdr(store_dir: &Path, reference_folder: &Path) -> Result<Option<f64>> {
+    let manifest = ResultsManifest::load(store_dir)?
+        .context("No results manifest to measure")?;
+    let mut values = vec![];
+    for track in &manifest.tracks {
+        for stem in &track.stems {
+            let reference = reference_folder.join(&track.name).join(format!("{}.wav", stem.name));
+            if !reference.is_file() {
+                continue;
+            }
+            let (_, reference) = audio::decode_planar(&reference)?;
+            let (_, estimate) = audio::decode_planar(&store_dir.join(&stem.path))?;
+            if let Some(value) = sdr(&reference, &estimate) {
+                values.push(value);
+            }
+        }
+    }
+    Ok((!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64))
+}
+
+/// Measures every successful candidate again against the reference folder
+/// as it is now, and stamps the report with it. Outputs are kept, so no
+/// inference runs again.
+pub fn revalidate(dir: &Path, report: &mut BakeOffReport, ctx: &TaskContext) -> Result<()> {
+    let Some(reference) = report.reference_folder.clone() else {
+        bail!("Bake-off {} has no reference folder", report.name);
+    };
+    let stamp = ValidSetStamp::take(&reference)?;
+    for outcome in report.outcomes.iter_mut().filter(|outcome| outcome.success) {
+        ctx.check()?;
+        outcome.sdr = mean_sdr(&dir.join(&outcome.store_dir), Path::new(&reference))?;
+    }
+    report.reference_set = Some(stamp);
+    report.save(dir)
+}
+
+/// One stem of one track, as every candidate produced it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StemComparison {
+    pub track: String,
+    pub stem: String,
+    /// Per candidate, in report order; `None` where it has no such stem.
+    pub outputs: Vec<Option<PathBuf>>,
+}
+
+/// Lines up corresponding stems across the candidates' store_dirs.
+pub fn pair_stems(dir: &Path, report: &BakeOffReport) -> Vec<StemComparison> {
+    let manifests: Vec<Option<ResultsManifest>> = report.outcomes.iter()
+        .map(|outcome| ResultsManifest::load(&dir.join(&outcome.store_dir)).ok().flatten())
+        .collect();
+
+    let mut pairs: Vec<StemComparison> = vec![];
+    for (candidate, manifest) in manifests.iter().enumerate() {
+        let Some(manifest) = manifest else {
+            continue;
+        };
+        let store_dir = dir.join(&report.outcomes[candidate].store_dir);
+        for track in &manifest.tracks {
+            for stem in &track.stems {
+                let index = match pairs.iter().position(|p| p.track == track.name && p.stem == stem.name) {
+                    Some(index) => index,
+                    None => {
+                        pairs.push(StemComparison {
+                            track: track.name.clone(),
+                            stem: stem.name.clone(),
+                            outputs: vec![None; report.outcomes.len()],
+                        });
+                        pairs.len() - 1
+                    }
+                };
+                pairs[index].outputs[candidate] = Some(store_dir.join(&stem.path));
+            }
+        }
+    }
+    pairs.sort_by(|a, b| (&a.track, &a.stem).cmp(&(&b.track, &b.stem)));
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::{ManifestStem, ManifestTrack};
+
+    fn candidate(model_type: ModelType, checkpoint: &str) -> BakeOffCandidate {
+        BakeOffCandidate {
+            model_type,
+            config_path: "configs/model.yaml".into(),
+            start_checkpoint: checkpoint.into(),
+        }
+    }
+
+    #[test]
+    fn store_dirs_encode_the_model() {
+        let a = candidate(ModelType::BsRoformer, "ckpts/model_bs_roformer_ep_317.ckpt");
+        let b = candidate(ModelType::ScNet, "ckpts/scnet (final).ckpt");
+        assert_eq!(store_dir_name(0, &a), "01_bs_roformer_model_bs_roformer_ep_317");
+        assert_eq!(store_dir_name(1, &b), "02_scnet_scnet__final");
+        assert_ne!(store_dir_name(0, &a), store_dir_name(2, &a));
+    }
+
+    #[test]
+    fn sdr_matches_the_definition() {
+        let reference = vec![vec![1.0, -1.0, 1.0, -1.0]];
+        assert!(sdr(&reference, &reference).unwrap() > 70.0);
+        let half = vec![vec![0.5, -0.5, 0.5, -0.5]];
+        assert!((sdr(&reference, &half).unwrap() - 6.0206).abs() < 1e-3);
+        assert_eq!(sdr(&[vec![0.0; 4]], &half), None);
+    }
+
+    #[test]
+    fn stems_are_paired_across_candidates() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut report = BakeOffReport {
+            name: "bakeoff".into(),
+            label: None,
+            input_folder: "in".into(),
+            reference_folder: None,
+            started_at: Utc::now(),
+            finished_at: None,
+            outcomes: vec![],
+            notes: String::new(),
+            cost: None,
+            reference_set: None,
+        };
+        for (index, stems) in [vec!["vocals", "other"], vec!["vocals"]].into_iter().enumerate() {
+            let store_dir = format!("{:02}_model", index + 1);
+            fs::create_dir(dir.path().join(&store_dir)).unwrap();
+            let manifest = ResultsManifest {
+                model_type: None,
+                tracks: vec![ManifestTrack {
+                    name: "song".into(),
+                    input_path: "in/song.wav".into(),
+                    stems: stems.iter()
+                        .map(|stem| ManifestStem { name: stem.to_string(), path: format!("song/{}.wav", stem), renamed_from: None })
+                        .collect(),
+                    adjustments: vec![],
+                    content_hash: None,
+                }],
+                excluded: vec![],
+                start_checkpoint: None,
+            };
+            manifest.save(&dir.path().join(&store_dir)).unwrap();
+            report.outcomes.push(CandidateOutcome {
+                candidate: candidate(ModelType::ScNet, "x.ckpt"),
+                store_dir,
+                seconds: None,
+                success: true,
+                error: None,
+                sdr: None,
+            });
+        }
+
+        let pairs = pair_stems(dir.path(), &report);
+        assert_eq!(pairs.len(), 2);
+        assert_eq!(pairs[0].stem, "other");
+        assert_eq!(pairs[0].outputs, vec![Some(dir.path().join("01_model/song/other.wav")), None]);
+        assert_eq!(
+            pairs[1].outputs,
+            vec![Some(dir.path().join("01_model/song/vocals.wav")), Some(dir.path().join("02_model/song/vocals.wav"))]
+        );
+    }
+}
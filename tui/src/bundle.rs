@@ -0,0 +1,334 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::path::Path;
+
+use crate::config::AppConfig;
+use crate::secret::SecretRef;
+use crate::trash::{Trash, TrashedItem};
+
+/// Format of bundles written by this version. Older ones are migrated on
+/// load, see `migrate`.
+pub const BUNDLE_VERSION: u64 = 1;
+pub const DEFAULT_BUNDLE_FILE: &str = "msst_tui_bundle.yaml";
+
+/// The whole app setup in one portable file, for moving to another
+/// machine. Secrets are only ever `${ENV_VAR}` placeholders in here.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigBundle {
+    pub version: u64,
+    #[serde(default)]
+    pub exported_at: Option<DateTime<Utc>>,
+    pub app_config: AppConfig,
+    /// Contents of the custom log parser rules file (`parser_rules_path`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parser_rules: Option<String>,
+}
+
+impl ConfigBundle {
+    /// Bundles `config` and the parser rules file it points at. Keychain
+    /// secrets become `${NAME}` placeholders, since the keychain stays
+    /// behind; set those variables on the new machine. A relative rules
+    /// path is under `root`.
+    pub fn export(config: &AppConfig, root: &Path) -> Result<Self> {
+        let mut app_config = config.clone();
+        for secret in app_config.secrets.values_mut() {
+            *secret = portable(secret);
+        }
+        let parser_rules = match &config.parser_rules_path {
+            Some(path) if root.join(path).is_file() => Some(
+                fs::read_to_string(root.join(path)).with_context(|| format!("Failed to read parser rules {}", path))?,
+            ),
+            _ => None,
+        };
+        Ok(ConfigBundle { version: BUNDLE_VERSION, exported_at: Some(Utc::now()), app_config, parser_rules })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_yaml::to_string(self).context("Failed to serialize settings bundle")?;
+        fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let value: Value = serde_yaml::from_str(&content).context("Failed to parse settings bundle")?;
+        serde_yaml::from_value(migrate(value)?).context("Failed to read settings bundle")
+    }
+}
+
+/// `keychain:<name>` as `${NAME}`; env placeholders stay as they are.
+fn portable(secret: &SecretRef) -> SecretRef {
+    match secret.as_str().strip_prefix("keychain:") {
+        Some(name) => {
+            let var: String = name.chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+                .collect();
+            SecretRef::env(&var)
+        }
+        None => secret.clone(),
+    }
+}
+
+/// Brings a bundle of any older format up to `BUNDLE_VERSION`, one version
+/// at a time. Version 0 is a bare `tui_config.yaml` copied from another
+/// machine: it has no `version` key and becomes the bundle's app config.
+pub fn migrate(mut value: Value) -> Result<Value> {
+    let Value::Mapping(mapping) = &value else {
+        bail!("Not a settings bundle: expected a YAML mapping");
+    };
+    let mut version = match mapping.get("version") {
+        None => 0,
+        Some(version) => version.as_u64().context("Bundle version is not a number")?,
+    };
+    if version > BUNDLE_VERSION {
+        bail!(
+            "Bundle format {} was written by a newer version of the TUI; this one reads up to format {}",
+            version,
+            BUNDLE_VERSION
+        );
+    }
+    while version < BUNDLE_VERSION {
+        value = match version {
+            0 => {
+                let mut bundle = Mapping::new();
+                bundle.insert("version".into(), 1.into());
+                bundle.insert("app_config".into(), value);
+                Value::Mapping(bundle)
+            }
+            _ => unreachable!("every older version has a migration"),
+        };
+        version += 1;
+    }
+    Ok(value)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportMode {
+    /// Keeps what is set here; the bundle's entries win where both have one.
+    Merge,
+    /// The bundle's settings replace these entirely.
+    Replace,
+}
+
+/// What importing a bundle would do, for the preview before it does it.
+#[derive(Debug, Clone)]
+pub struct ImportPlan {
+    pub mode: ImportMode,
+    pub result: AppConfig,
+    /// One line per setting that would change, e.g. "theme: Dark → Light".
+    pub changes: Vec<String>,
+    /// Paths in the bundle that don't exist on this machine. They are
+    /// imported as they are, so fix them up afterwards.
+    pub missing_paths: Vec<String>,
+    pub parser_rules: Option<String>,
+}
+
+impl ImportPlan {
+    pub fn new(current: &AppConfig, bundle: &ConfigBundle, mode: ImportMode, root: &Path) -> Result<Self> {
+        let before = serde_yaml::to_value(current).context("Failed to serialize config")?;
+        let incoming = serde_yaml::to_value(&bundle.app_config).context("Failed to serialize bundle")?;
+        let after = match mode {
+            ImportMode::Merge => merge(&before, &incoming),
+            ImportMode::Replace => incoming,
+        };
+        let result: AppConfig = serde_yaml::from_value(after.clone()).context("Failed to build imported config")?;
+
+        let mut missing_paths: Vec<String> = path_entries(&bundle.app_config)
+            .into_iter()
+            .filter(|(_, path)| !root.join(path).exists())
+            .map(|(what, path)| format!("{} {} does not exist here", what, path))
+            .collect();
+        if bundle.parser_rules.is_some() {
+            // Written by the import itself.
+            missing_paths.retain(|line| !line.starts_with("parser_rules_path "));
+        }
+
+        Ok(ImportPlan {
+            mode,
+            result,
+            changes: changes(&before, &after),
+            missing_paths,
+            parser_rules: bundle.parser_rules.clone(),
+        })
+    }
+
+    /// Writes the bundled parser rules to the imported `parser_rules_path`,
+    /// keeping the file it replaces in `trash`.
+    pub fn write_parser_rules(&self, trash: &Trash, root: &Path) -> Result<Option<TrashedItem>> {
+        let (Some(rules), Some(path)) = (&self.parser_rules, &self.result.parser_rules_path) else {
+            return Ok(None);
+        };
+        let path = &root.join(path);
+        let kept = trash.keep_copy(path, "parser rules overwrite")?;
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(path, rules).with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(kept)
+    }
+}
+
+/// Top-level settings of `incoming` over `current`; maps are merged by
+/// key and lists gain the entries they lack. Unset incoming settings keep
+/// the current ones.
+fn merge(current: &Value, incoming: &Value) -> Value {
+    let (Value::Mapping(current), Value::Mapping(incoming)) = (current, incoming) else {
+        return incoming.clone();
+    };
+    let mut merged = current.clone();
+    for (key, value) in incoming {
+        let combined = match (current.get(key), value) {
+            (_, Value::Null) => continue,
+            (Some(Value::Mapping(ours)), Value::Mapping(theirs)) => {
+                let mut map = ours.clone();
+                for (entry, setting) in theirs {
+                    map.insert(entry.clone(), setting.clone());
+                }
+                Value::Mapping(map)
+            }
+            (Some(Value::Sequence(ours)), Value::Sequence(theirs)) => {
+                let mut list = ours.clone();
+                list.extend(theirs.iter().filter(|item| !ours.contains(item)).cloned());
+                Value::Sequence(list)
+            }
+            _ => value.clone(),
+        };
+        merged.insert(key.clone(), combined);
+    }
+    Value::Mapping(merged)
+}
+
+fn changes(before: &Value, after: &Value) -> Vec<String> {
+    let (Value::Mapping(before), Value::Mapping(after)) = (before, after) else {
+        return vec![];
+    };
+    let mut keys: Vec<&Value> = after.keys().collect();
+    keys.extend(before.keys().filter(|key| !after.contains_key(*key)));
+    keys.into_iter()
+        .filter_map(|key| {
+            let name = key.as_str().unwrap_or_default();
+            let (old, new) = (before.get(key).unwrap_or(&Value::Null), after.get(key).unwrap_or(&Value::Null));
+            if old == new {
+                return None;
+            }
+            Some(match (old, new) {
+                (Value::Mapping(old), Value::Mapping(new)) => {
+                    let added = new.keys().filter(|entry| !old.contains_key(*entry)).count();
+                    let changed = new.iter().filter(|(entry, value)| old.get(*entry).is_some_and(|old| old != *value)).count();
+                    let removed = old.keys().filter(|entry| !new.contains_key(*entry)).count();
+                    format!("{}: {} added, {} changed, {} removed", name, added, changed, removed)
+                }
+                (Value::Sequence(old), Value::Sequence(new)) => format!("{}: {} → {} entries", name, old.len(), new.len()),
+                _ => format!("{}: {} → {}", name, describe(old), describe(new)),
+            })
+        })
+        .collect()
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Null => "(unset)".to_string(),
+        Value::Bool(value) => value.to_string(),
+        Value::Number(value) => value.to_string(),
+        Value::String(value) => value.clone(),
+        Value::Sequence(items) => format!("{} entries", items.len()),
+        Value::Mapping(_) | Value::Tagged(_) => "(set)".to_string(),
+    }
+}
+
+/// Every file or folder path `config` refers to, with what it is for.
+fn path_entries(config: &AppConfig) -> Vec<(String, String)> {
+    let mut paths = vec![];
+    let mut push = |what: &str, path: &str| {
+        if !path.is_empty() {
+            paths.push((what.to_string(), path.to_string()));
+        }
+    };
+    for recent in &config.recent_configs {
+        push("recent config", &recent.path);
+    }
+    for (what, path) in [
+        ("scratch_dir", &config.scratch_dir),
+        ("parser_rules_path", &config.parser_rules_path),
+        ("history_path", &config.history_path),
+        ("trash_dir", &config.trash_dir),
+        ("weights_dir", &config.weights_dir),
+    ] {
+        if let Some(path) = path {
+            push(what, path);
+        }
+    }
+    if let Some(binding) = &config.model_binding {
+        push("bound checkpoint", &binding.checkpoint);
+        push("bound config", &binding.config);
+    }
+    for (model, presets) in &config.inference_presets {
+        for preset in presets {
+            let what = format!("preset '{}' ({})", preset.name, model);
+            for path in preset.config_path.iter().chain(&preset.start_checkpoint) {
+                push(&what, path);
+            }
+        }
+    }
+    for folder in config.exclude_patterns.keys() {
+        push("exclude patterns folder", folder);
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{RecentConfig, RecentKind, Theme};
+    use crate::model::ModelType;
+    use crate::preset::InferencePreset;
+
+    #[test]
+    fn bare_configs_migrate_and_newer_bundles_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let bare = dir.path().join("tui_config.yaml");
+        fs::write(&bare, "selected_model: ScNet\nrecent_configs: []\ntheme: Light\n").unwrap();
+        let bundle = ConfigBundle::load(&bare).unwrap();
+        assert_eq!(bundle.version, BUNDLE_VERSION);
+        assert_eq!(bundle.app_config.theme, Theme::Light);
+        assert!(bundle.exported_at.is_none());
+
+        let newer = dir.path().join("newer.yaml");
+        fs::write(&newer, "version: 99\napp_config: {recent_configs: [], theme: Dark}\n").unwrap();
+        assert!(format!("{:#}", ConfigBundle::load(&newer).unwrap_err()).contains("newer version"));
+    }
+
+    #[test]
+    fn import_previews_merge_and_flags_missing_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut theirs = AppConfig { theme: Theme::HighContrast, ..Default::default() };
+        theirs.secrets.insert("wandb".into(), SecretRef::keychain("wandb-key"));
+        theirs.scratch_dir = Some("/no/such/scratch".into());
+        let recent = |path: &str| RecentConfig::new(path, RecentKind::Training, ModelType::ScNet);
+        let paths = |config: &AppConfig| config.recent_configs.iter().map(|recent| recent.path.clone()).collect::<Vec<_>>();
+        theirs.recent_configs = vec![recent("a.yaml")];
+        theirs.inference_presets.insert("scnet".into(), vec![InferencePreset { name: "fast".into(), ..Default::default() }]);
+
+        let path = dir.path().join("bundle.yaml");
+        ConfigBundle::export(&theirs, Path::new("")).unwrap().save(&path).unwrap();
+        let bundle = ConfigBundle::load(&path).unwrap();
+        assert_eq!(bundle.app_config.secrets["wandb"], SecretRef::env("WANDB_KEY"));
+
+        let mut ours = AppConfig { recent_configs: vec![recent("b.yaml")], ..Default::default() };
+        ours.inference_presets.insert("mdx23c".into(), vec![]);
+        let merged = ImportPlan::new(&ours, &bundle, ImportMode::Merge, Path::new("")).unwrap();
+        assert_eq!(paths(&merged.result), ["b.yaml", "a.yaml"]);
+        assert_eq!(merged.result.inference_presets.len(), 2);
+        assert_eq!(merged.result.theme, Theme::HighContrast);
+        assert!(merged.changes.contains(&"theme: Dark → HighContrast".to_string()));
+        assert!(merged.changes.contains(&"inference_presets: 1 added, 0 changed, 0 removed".to_string()));
+        assert!(merged.missing_paths.contains(&"scratch_dir /no/such/scratch does not exist here".to_string()));
+
+        let replaced = ImportPlan::new(&ours, &bundle, ImportMode::Replace, Path::new("")).unwrap();
+        assert_eq!(paths(&replaced.result), ["a.yaml"]);
+        assert!(replaced.changes.contains(&"recent_configs: 1 → 1 entries".to_string()));
+        assert!(!replaced.result.inference_presets.contains_key("mdx23c"));
+    }
+}
@@ -0,0 +1,150 @@
+use chrono::{DateTime, Local};
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::file_picker::find_checkpoints;
+
+/// A checkpoint file and what its name and metadata say about it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckpointInfo {
+    pub path: PathBuf,
+    pub file_name: String,
+    pub size: u64,
+    pub modified: Option<DateTime<Local>>,
+    pub epoch: Option<usize>,
+    /// Only from names ending in `_sdr_<value>`: other metrics (`si_sdr`,
+    /// `l1_freq`, ...) aren't comparable with it.
+    pub sdr: Option<f64>,
+}
+
+impl CheckpointInfo {
+    pub fn read(path: &Path) -> Self {
+        let file_name = path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().to_string());
+        let (epoch, sdr) = parse_checkpoint_name(&file_name);
+        let meta = fs::metadata(path).ok();
+        CheckpointInfo {
+            path: path.to_path_buf(),
+            file_name,
+            size: meta.as_ref().map_or(0, |meta| meta.len()),
+            modified: meta.and_then(|meta| meta.modified().ok()).map(DateTime::from),
+            epoch,
+            sdr,
+        }
+    }
+
+    /// E.g. "model_scnet_ep_9_sdr_9.2000.ckpt (epoch 9)"; `last_*` names
+    /// carry no epoch.
+    pub fn label(&self) -> String {
+        match self.epoch {
+            Some(epoch) => format!("{} (epoch {})", self.file_name, epoch),
+            None => self.file_name.clone(),
+        }
+    }
+
+    /// E.g. "ep 112  SDR 9.3411  1.2 GB  2026-10-17 14:03".
+    pub fn metadata_line(&self) -> String {
+        let epoch = self.epoch.map_or("ep -".to_string(), |epoch| format!("ep {}", epoch));
+        let sdr = self.sdr.map_or("SDR -".to_string(), |sdr| format!("SDR {:.4}", sdr));
+        let modified = self.modified.map_or("-".to_string(), |time| time.format("%Y-%m-%d %H:%M").to_string());
+        format!("{:<8}  {:<11}  {:>8}  {}", epoch, sdr, file_size(self.size), modified)
+    }
+}
+
+pub fn file_size(bytes: u64) -> String {
+    match bytes {
+        0..1_000_000 => format!("{} KB", bytes / 1000),
+        1_000_000..1_000_000_000 => format!("{:.0} MB", bytes as f64 / 1e6),
+        _ => format!("{:.1} GB", bytes as f64 / 1e9),
+    }
+}
+
+/// Epoch and SDR from the names train.py gives checkpoints:
+/// `model_<type>_ep_<epoch>_<metric>_<value>.ckpt`, with `sdr` the default
+/// metric, e.g. `model_bs_roformer_ep_112_sdr_9.3411.ckpt`. `last_<type>.ckpt`
+/// and pretrained weights carry neither.
+pub fn parse_checkpoint_name(file_name: &str) -> (Option<usize>, Option<f64>) {
+    let stem = Path::new(file_name).file_stem().map_or(file_name.to_string(), |stem| stem.to_string_lossy().to_string());
+    let Some((_, after)) = stem.rsplit_once("_ep_") else {
+        return (None, None);
+    };
+    let mut parts = after.split('_');
+    let epoch = parts.next().and_then(|epoch| epoch.parse().ok());
+    let rest: Vec<&str> = parts.collect();
+    let sdr = match rest.as_slice() {
+        ["sdr", value] => value.parse().ok(),
+        _ => None,
+    };
+    (epoch, sdr)
+}
+
+/// Checkpoints under `roots` (nested run folders included), best SDR
+/// first; those without an SDR follow, newest first.
+pub fn list_checkpoints(roots: &[PathBuf]) -> Vec<CheckpointInfo> {
+    let mut checkpoints: Vec<CheckpointInfo> = find_checkpoints(roots).iter().map(|path| CheckpointInfo::read(path)).collect();
+    checkpoints.sort_by(|a, b| match (a.sdr, b.sdr) {
+        (Some(a_sdr), Some(b_sdr)) => b_sdr.partial_cmp(&a_sdr).unwrap_or(Ordering::Equal).then_with(|| b.modified.cmp(&a.modified)),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => b.modified.cmp(&a.modified),
+    });
+    checkpoints
+}
+
+/// What a crashed or stopped `model_key` run in `results_path` can go on
+/// from: the newest `last_<model_key>` checkpoint, which train.py writes
+/// every epoch, or else the newest `model_<model_key>_ep_*` one. Run
+/// folders under `results_path` are searched too.
+pub fn resume_checkpoint(results_path: &Path, model_key: &str) -> Option<CheckpointInfo> {
+    let found = find_checkpoints(&[results_path.to_path_buf()]);
+    let stem = |path: &&PathBuf| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let last = format!("last_{}", model_key);
+    let best = format!("model_{}_ep_", model_key);
+    found.iter()
+        .find(|path| stem(path) == last)
+        .or_else(|| found.iter().find(|path| stem(path).starts_with(&best)))
+        .map(|path| CheckpointInfo::read(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn train_py_names_are_parsed() {
+        assert_eq!(parse_checkpoint_name("model_bs_roformer_ep_112_sdr_9.3411.ckpt"), (Some(112), Some(9.3411)));
+        assert_eq!(parse_checkpoint_name("model_mel_band_roformer_ep_0_sdr_-1.2000.ckpt"), (Some(0), Some(-1.2)));
+        assert_eq!(parse_checkpoint_name("model_htdemucs_ep_7_si_sdr_6.5000.ckpt"), (Some(7), None));
+        assert_eq!(parse_checkpoint_name("model_scnet_ep_3_l1_freq_-0.0312.th"), (Some(3), None));
+        assert_eq!(parse_checkpoint_name("last_bs_roformer.ckpt"), (None, None));
+        assert_eq!(parse_checkpoint_name("MelBandRoformer.ckpt"), (None, None));
+    }
+
+    #[test]
+    fn best_sdr_first_then_newest() {
+        let dir = tempfile::tempdir().unwrap();
+        let run = dir.path().join("2026-10-01_scnet/weights");
+        fs::create_dir_all(&run).unwrap();
+        for name in ["model_scnet_ep_3_sdr_8.1000.ckpt", "model_scnet_ep_9_sdr_9.2000.ckpt", "notes.txt"] {
+            fs::write(run.join(name), "").unwrap();
+        }
+        fs::write(dir.path().join("old.pt"), "").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        fs::write(run.join("last_scnet.ckpt"), "x").unwrap();
+
+        let checkpoints = list_checkpoints(&[dir.path().to_path_buf()]);
+        let names: Vec<&str> = checkpoints.iter().map(|checkpoint| checkpoint.file_name.as_str()).collect();
+        assert_eq!(names, ["model_scnet_ep_9_sdr_9.2000.ckpt", "model_scnet_ep_3_sdr_8.1000.ckpt", "last_scnet.ckpt", "old.pt"]);
+        assert_eq!(checkpoints[2].size, 1);
+        assert!(checkpoints[0].metadata_line().starts_with("ep 9      SDR 9.2000"));
+
+        let resume = resume_checkpoint(dir.path(), "scnet").unwrap();
+        assert_eq!(resume.file_name, "last_scnet.ckpt", "last_ wins over a best-SDR checkpoint");
+        fs::remove_file(run.join("last_scnet.ckpt")).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        fs::write(run.join("model_scnet_ep_9_sdr_9.2000.ckpt"), "").unwrap();
+        fs::write(run.join("model_bs_roformer_ep_40_sdr_9.9000.ckpt"), "").unwrap();
+        assert_eq!(resume_checkpoint(dir.path(), "scnet").unwrap().epoch, Some(9), "only the newest of this model's");
+        assert!(resume_checkpoint(dir.path(), "htdemucs").is_none());
+    }
+}
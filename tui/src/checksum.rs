@@ -0,0 +1,59 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::task::TaskContext;
+
+const CHUNK_BYTES: usize = 1 << 20;
+
+/// SHA-256 of a file as lowercase hex, reporting bytes read as progress.
+/// Stops with `Cancelled` when the task is cancelled.
+pub fn sha256_file(path: &Path, ctx: &TaskContext) -> Result<String> {
+    let mut file = File::open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let len = file.metadata().map(|meta| meta.len()).unwrap_or(0);
+    ctx.set_total(len);
+
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; CHUNK_BYTES];
+    loop {
+        ctx.check()?;
+        let read = file.read(&mut buffer)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        ctx.advance(read as u64);
+    }
+    Ok(hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::{CancelToken, Cancelled, OnCancel, Task, TaskOutcome};
+
+    #[test]
+    fn hashes_files_and_discards_cancelled_hashes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.ckpt");
+        std::fs::write(&path, "abc").unwrap();
+        // Half a hash is useless, so hashes run as `Discard` tasks.
+        let task_path = path.clone();
+        let hash = Task::spawn("Hashing model.ckpt", OnCancel::Discard, move |ctx| sha256_file(&task_path, ctx));
+        assert_eq!(
+            hash.join().unwrap(),
+            TaskOutcome::Completed("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".to_string())
+        );
+
+        let token = CancelToken::new();
+        let ctx = TaskContext::new(token.clone());
+        std::fs::write(&path, vec![7u8; 3 * CHUNK_BYTES]).unwrap();
+        assert_eq!(sha256_file(&path, &ctx).unwrap().len(), 64);
+        token.cancel();
+        assert!(sha256_file(&path, &ctx).unwrap_err().is::<Cancelled>());
+    }
+}
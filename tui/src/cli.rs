@@ -0,0 +1,195 @@
+use anyhow::{Context, Result};
+use clap::builder::{PossibleValue, PossibleValuesParser};
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command, ValueHint};
+use clap_complete::Shell;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::model::ModelType;
+
+pub const BIN_NAME: &str = "mss_tui";
+
+/// What the command line asked for.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CliOptions {
+    pub plain: bool,
+    pub dashboard: bool,
+    /// Model selected for this session, ahead of the saved choice.
+    pub model: Option<ModelType>,
+    /// Print a completion script instead of starting the TUI.
+    pub completions: Option<Shell>,
+    /// Run a script to the end without the TUI.
+    pub headless: Option<HeadlessRun>,
+    /// MSST checkout to use instead of searching for one.
+    pub project_root: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HeadlessCommand {
+    Train,
+    Infer,
+    Validate,
+}
+
+impl HeadlessCommand {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HeadlessCommand::Train => "train",
+            HeadlessCommand::Infer => "infer",
+            HeadlessCommand::Validate => "validate",
+        }
+    }
+}
+
+/// A `train`, `infer` or `validate` subcommand and its YAML config.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeadlessRun {
+    pub command: HeadlessCommand,
+    pub config: PathBuf,
+    /// `train --force`: start even with other MSST processes on its GPUs.
+    pub force: bool,
+}
+
+impl CliOptions {
+    pub fn parse() -> Self {
+        Self::from_matches(&command().get_matches())
+    }
+
+    pub fn try_parse_from<I, T>(args: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        let matches = command().try_get_matches_from(args).context("Failed to parse arguments")?;
+        Ok(Self::from_matches(&matches))
+    }
+
+    fn from_matches(matches: &ArgMatches) -> Self {
+        let model = matches.get_one::<String>("model")
+            .and_then(|key| ModelType::all_models().into_iter().find(|model| model.key() == key));
+        CliOptions {
+            plain: matches.get_flag("plain"),
+            dashboard: matches.get_flag("dashboard"),
+            model,
+            completions: matches.subcommand_matches("completions")
+                .and_then(|completions| completions.get_one::<Shell>("shell").copied()),
+            headless: [HeadlessCommand::Train, HeadlessCommand::Infer, HeadlessCommand::Validate]
+                .into_iter()
+                .find_map(|command| {
+                    let subcommand = matches.subcommand_matches(command.name())?;
+                    let config = subcommand.get_one::<PathBuf>("config")?;
+                    let force = command == HeadlessCommand::Train && subcommand.get_flag("force");
+                    Some(HeadlessRun { command, config: config.clone(), force })
+                }),
+            project_root: matches.get_one::<PathBuf>("project-root").cloned(),
+        }
+    }
+}
+
+/// The command line, also the source of the completion scripts. Model keys
+/// are possible values, which bash, zsh and fish complete; PowerShell's
+/// script completes flags and subcommands only. Config paths complete as
+/// files, not from the recent configs.
+pub fn command() -> Command {
+    let models: Vec<PossibleValue> = ModelType::all_models().iter()
+        .map(|model| PossibleValue::new(model.key()).help(model.name()))
+        .collect();
+    Command::new(BIN_NAME)
+        .about("Terminal UI for Music Source Separation training and inference")
+        .arg(Arg::new("plain")
+            .long("plain")
+            .action(ArgAction::SetTrue)
+            .help("Linear, labeled text output for screen readers"))
+        .arg(Arg::new("dashboard")
+            .long("dashboard")
+            .action(ArgAction::SetTrue)
+            .help("Read-only instance watching the runs another instance owns"))
+        .arg(Arg::new("model")
+            .long("model")
+            .value_name("KEY")
+            .value_parser(PossibleValuesParser::new(models))
+            .help("Model to select for this session"))
+        .arg(Arg::new("project-root")
+            .long("project-root")
+            .value_name("PATH")
+            .global(true)
+            .value_parser(value_parser!(PathBuf))
+            .value_hint(ValueHint::DirPath)
+            .help("MSST checkout to run in, instead of searching for one"))
+        .subcommand(headless_command("train", "Run training to the end without the TUI (a TrainingConfig YAML)")
+            .arg(Arg::new("force")
+                .long("force")
+                .action(ArgAction::SetTrue)
+                .help("Start even if other MSST processes are on its GPUs")))
+        .subcommand(headless_command("infer", "Run inference without the TUI (an InferenceConfig YAML)"))
+        .subcommand(headless_command("validate", "Run validation without the TUI (a ValidationConfig YAML)"))
+        .subcommand(Command::new("completions")
+            .about("Print a shell completion script")
+            .arg(Arg::new("shell")
+                .required(true)
+                .value_parser(value_parser!(Shell))))
+}
+
+fn headless_command(name: &'static str, about: &'static str) -> Command {
+    Command::new(name)
+        .about(about)
+        .arg(Arg::new("config")
+            .long("config")
+            .value_name("PATH")
+            .required(true)
+            .value_parser(value_parser!(PathBuf))
+            .value_hint(ValueHint::FilePath)
+            .help("YAML file with the run's settings"))
+}
+
+pub fn write_completions(shell: Shell, out: &mut dyn Write) {
+    clap_complete::generate(shell, &mut command(), BIN_NAME, out);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_flags_and_model_keys() {
+        let options = CliOptions::try_parse_from([BIN_NAME, "--plain", "--model", "mel_band_roformer"]).unwrap();
+        assert_eq!(options, CliOptions { plain: true, model: Some(ModelType::MelBandRoformer), ..Default::default() });
+        assert!(CliOptions::try_parse_from([BIN_NAME, "--model", "unet"]).is_err());
+        assert_eq!(
+            CliOptions::try_parse_from([BIN_NAME, "completions", "fish"]).unwrap().completions,
+            Some(Shell::Fish)
+        );
+        assert_eq!(
+            CliOptions::try_parse_from([BIN_NAME, "validate", "--config", "valid.yaml"]).unwrap().headless,
+            Some(HeadlessRun { command: HeadlessCommand::Validate, config: PathBuf::from("valid.yaml"), force: false })
+        );
+        assert!(CliOptions::try_parse_from([BIN_NAME, "train", "--config", "train.yaml", "--force"]).unwrap().headless.unwrap().force);
+        assert!(CliOptions::try_parse_from([BIN_NAME, "validate", "--config", "valid.yaml", "--force"]).is_err());
+        assert!(CliOptions::try_parse_from([BIN_NAME, "train"]).is_err(), "--config is required");
+        assert_eq!(CliOptions::try_parse_from([BIN_NAME]).unwrap().headless, None);
+        let options = CliOptions::try_parse_from([BIN_NAME, "infer", "--config", "infer.yaml", "--project-root", "/srv/msst"]).unwrap();
+        assert_eq!(options.project_root, Some(PathBuf::from("/srv/msst")));
+    }
+
+    #[test]
+    fn every_shell_script_names_the_subcommand_and_flags() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+            let mut script = vec![];
+            write_completions(shell, &mut script);
+            let script = String::from_utf8(script).unwrap();
+            // Fish spells flags `-l dashboard`, so names only.
+            for token in ["completions", "dashboard", "model", "config", "project-root"] {
+                assert!(script.contains(token), "{} script lacks {}", shell, token);
+            }
+            let keys = script.contains("mel_band_roformer");
+            assert_eq!(keys, shell != Shell::PowerShell, "{} script and the model keys", shell);
+        }
+        let mut script = vec![];
+        write_completions(Shell::PowerShell, &mut script);
+        let script = String::from_utf8(script).unwrap();
+        for subcommand in ["'train'", "'infer'", "'validate'", "'completions'"] {
+            assert!(script.contains(&format!("[CompletionResult]::new({}, {}, [CompletionResultType]::ParameterValue", subcommand, subcommand)), "{}", subcommand);
+        }
+        assert!(script.contains("[CompletionResult]::new('--model', '--model', [CompletionResultType]::ParameterName, 'Model to select for this session')"));
+    }
+}
@@ -0,0 +1,46 @@
+use crossterm::style::Print;
+use crossterm::QueueableCommand;
+use std::io::{self, Write};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Puts `text` on the system clipboard with the OSC 52 escape sequence,
+/// which works over SSH and in tmux (with `set-clipboard on`). Terminals
+/// without it ignore the sequence, so there is no error to report then.
+pub fn copy(out: &mut impl Write, text: &str) -> io::Result<()> {
+    out.queue(Print(osc52(text)))?;
+    out.flush()
+}
+
+pub fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64(text.as_bytes()))
+}
+
+fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, byte)| n | (*byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_is_base64_encoded_in_the_sequence() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(osc52("python train.py 'my songs'"), "\x1b]52;c;cHl0aG9uIHRyYWluLnB5ICdteSBzb25ncyc=\x07");
+    }
+}
@@ -0,0 +1,96 @@
+use crate::history::RunDetail;
+use crate::training_chart::{best_mean_sdr, instrument_rows};
+
+/// The row for the mean SDR over instruments, always listed first.
+pub const MEAN_ROW: &str = "mean";
+
+/// The best SDR of one instrument, or of the mean, in each of two runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdrDelta {
+    pub name: String,
+    pub a: Option<f64>,
+    pub b: Option<f64>,
+}
+
+impl SdrDelta {
+    /// How much better run B did; positive is an improvement.
+    pub fn delta(&self) -> Option<f64> {
+        Some(self.b? - self.a?)
+    }
+}
+
+/// Two runs from the history side by side, A the one marked first.
+#[derive(Debug, Clone)]
+pub struct RunComparison {
+    pub a: RunDetail,
+    pub b: RunDetail,
+    pub rows: Vec<SdrDelta>,
+}
+
+impl RunComparison {
+    /// Compares the best SDR of every instrument either run validated, or
+    /// only the mean when one of them has no SDR per instrument.
+    pub fn new(a: RunDetail, b: RunDetail) -> Self {
+        let (history_a, history_b) = (a.metrics.progress(), b.metrics.progress());
+        let mut rows = vec![SdrDelta {
+            name: MEAN_ROW.to_string(),
+            a: best_mean_sdr(history_a).map(|(sdr, _)| sdr),
+            b: best_mean_sdr(history_b).map(|(sdr, _)| sdr),
+        }];
+        let (instruments_a, instruments_b) = (instrument_rows(history_a), instrument_rows(history_b));
+        if !instruments_a.is_empty() && !instruments_b.is_empty() {
+            for row in &instruments_a {
+                let b = instruments_b.iter().find(|other| other.name == row.name).map(|other| other.best);
+                rows.push(SdrDelta { name: row.name.clone(), a: Some(row.best), b });
+            }
+            for row in instruments_b.iter().filter(|row| !instruments_a.iter().any(|other| other.name == row.name)) {
+                rows.push(SdrDelta { name: row.name.clone(), a: None, b: Some(row.best) });
+            }
+        }
+        RunComparison { a, b, rows }
+    }
+
+    pub fn per_instrument(&self) -> bool {
+        self.rows.len() > 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::HistoryEntry;
+    use crate::metrics::TrainingHistory;
+    use crate::model::{ModelType, TrainingProgress};
+
+    fn run(name: &str, epochs: &[(f64, Vec<(&str, f64)>)]) -> RunDetail {
+        let mut metrics = TrainingHistory::new();
+        for (epoch, (sdr, instruments)) in epochs.iter().enumerate() {
+            metrics.push(TrainingProgress {
+                epoch,
+                sdr: Some(*sdr),
+                instrument_sdr: instruments.iter().map(|(name, sdr)| (name.to_string(), *sdr)).collect(),
+                ..Default::default()
+            });
+        }
+        let entry: HistoryEntry = serde_json::from_value(serde_json::json!({
+            "name": name, "label": null, "model_type": ModelType::ScNet, "anchor": name,
+            "started_at": "2026-10-18T09:00:00Z", "finished_at": null, "exit_code": null,
+        }))
+        .unwrap();
+        RunDetail { entry, lines: vec![], metrics, folder: None, remote: None }
+    }
+
+    #[test]
+    fn instruments_are_compared_unless_a_run_lacks_them() {
+        let a = run("a", &[(5.0, vec![("vocals", 8.0), ("other", 2.0)]), (6.0, vec![("vocals", 9.0), ("other", 3.0)])]);
+        let b = run("b", &[(6.5, vec![("vocals", 8.5), ("drums", 4.0)])]);
+        let comparison = RunComparison::new(a.clone(), b);
+        let rows: Vec<(&str, Option<f64>)> = comparison.rows.iter().map(|row| (row.name.as_str(), row.delta())).collect();
+        assert_eq!(rows, [(MEAN_ROW, Some(0.5)), ("vocals", Some(-0.5)), ("other", None), ("drums", None)]);
+        assert!(comparison.per_instrument());
+
+        let overall_only = RunComparison::new(a, run("old", &[(7.0, vec![]), (4.0, vec![])]));
+        assert_eq!(overall_only.rows, [SdrDelta { name: MEAN_ROW.to_string(), a: Some(6.0), b: Some(7.0) }]);
+        assert!(!overall_only.per_instrument());
+    }
+}
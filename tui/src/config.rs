@@ -1,36 +1,330 @@
-use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
-use crate::model::{ModelType, TrainingConfig, InferenceConfig, ValidationConfig};
+use crate::attention::AttentionSettings;
+use crate::cost::CostSettings;
+use crate::download::DownloadEntry;
+use crate::gpu::DEFAULT_MIN_FREE_MIB;
+use crate::integrity;
+use crate::keymap::KeyBindings;
+use crate::model::{InferenceConfig, ModelType, TrainingConfig, ValidationConfig};
+use crate::output_view::DEFAULT_PLAYER;
+use crate::parse_health::ParseHealthSettings;
+use crate::preset::InferencePreset;
+use crate::pretrained::{ModelBinding, DEFAULT_WEIGHTS_DIR};
+use crate::remote::RemoteHost;
+use crate::secret::SecretRef;
+use crate::watchdog::WatchdogSettings;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub const DEFAULT_CONFIG_FILE: &str = "tui_config.yaml";
+
+/// Where the model configs are, relative to the project root.
+pub const DEFAULT_CONFIGS_DIR: &str = "configs";
+
+/// The training config edited on the Config screen.
+pub const DEFAULT_TRAINING_CONFIG_FILE: &str = "tui_training.yaml";
+
+/// Format of `tui_config.yaml` written by this version; older files are
+/// migrated by `migrate_config` on load.
+pub const CONFIG_SCHEMA_VERSION: u32 = 1;
+
+/// Entries kept in `recent_configs`.
+pub const MAX_RECENT_CONFIGS: usize = 10;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AppConfig {
+    #[serde(default)]
     pub selected_model: Option<ModelType>,
-    pub recent_configs: Vec<String>,
+    /// Checkpoint and config of the selected model, if one is bound.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_binding: Option<ModelBinding>,
+    /// Where registry weights are looked for; defaults to `pretrained`.
+    #[serde(default)]
+    pub weights_dir: Option<String>,
+    /// Checkpoints offered for download besides the built-in registry.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub downloads: Vec<DownloadEntry>,
+    /// Configs runs were launched with, most recent first.
+    #[serde(default, deserialize_with = "recent_configs")]
+    pub recent_configs: Vec<RecentConfig>,
+    #[serde(default)]
     pub theme: Theme,
+    /// Terminal bell and window title for noticing the TUI from elsewhere.
+    #[serde(default)]
+    pub attention: AttentionSettings,
+    /// Run when training, validation or an inference batch ends, e.g.
+    /// `notify-send "MSST {status}" "{summary} ({duration})"`; `{status}`,
+    /// `{model}`, `{summary}` and `{duration}` are filled in per argument
+    /// and no shell is involved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify_command: Option<String>,
+    /// Hourly GPU price; runs are priced only when it is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<CostSettings>,
+    /// Linear text output for screen readers, like `--plain`.
+    #[serde(default)]
+    pub plain: bool,
+    /// Preview gain in dB per stem name, applied to the stem during A/B.
+    #[serde(default)]
+    pub preview_gains: HashMap<String, f32>,
+    /// Name for new training run directories, see `run_dir::run_name`.
+    #[serde(default)]
+    pub run_name_template: Option<String>,
+    /// Where temporary folders go; defaults to the system temp folder.
+    #[serde(default)]
+    pub scratch_dir: Option<String>,
+    /// Orphaned scratch folders older than this are removed at startup.
+    #[serde(default)]
+    pub scratch_max_age_hours: Option<u64>,
+    /// YAML file with log parser rules merged over the built-in ones.
+    #[serde(default)]
+    pub parser_rules_path: Option<String>,
+    /// Run history file; defaults to `tui_history.json` in the project root.
+    #[serde(default)]
+    pub history_path: Option<String>,
+    /// Queued training runs; defaults to `tui_training_queue.yaml` in the
+    /// project root.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub training_queue_path: Option<String>,
+    /// The inference batch; defaults to `tui_inference_batch.yaml` in the
+    /// project root.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inference_batch_path: Option<String>,
+    /// Sweeps with more runs than this are refused; 32 by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_sweep_runs: Option<usize>,
+    /// Tokens, keys and passwords by name, each as `${ENV_VAR}` or
+    /// `keychain:<name>`; see `secret::SecretRef`.
+    #[serde(default)]
+    pub secrets: HashMap<String, SecretRef>,
+    /// Inference presets by model key (`ModelType::key`).
+    #[serde(default)]
+    pub inference_presets: HashMap<String, Vec<InferencePreset>>,
+    /// File name globs left out of every batch, by input folder.
+    #[serde(default)]
+    pub exclude_patterns: HashMap<String, Vec<String>>,
+    /// Where deleted and overwritten files go; defaults to `.msst_trash`.
+    #[serde(default)]
+    pub trash_dir: Option<String>,
+    /// The trash is purged down to this size, oldest items first.
+    #[serde(default)]
+    pub trash_max_mb: Option<u64>,
+    /// Trashed items older than this are purged at startup.
+    #[serde(default)]
+    pub trash_max_age_days: Option<u64>,
+    /// When a watched run's output counts as unparseable.
+    #[serde(default)]
+    pub parse_health: ParseHealthSettings,
+    /// Seconds a stopped training run gets to save its checkpoint before
+    /// it is killed; 30 by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub training_stop_grace_secs: Option<u64>,
+    /// A GPU with less free memory than this gets a warning when it is
+    /// picked for training; 2048 by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu_min_free_mib: Option<u64>,
+    /// Interpreter for the python scripts, e.g.
+    /// `/opt/conda/envs/msst/bin/python`; `python3`, then `python`, on
+    /// PATH when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub python_executable: Option<String>,
+    /// Folder the scripts are in; the project root when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scripts_dir: Option<String>,
+    /// Machines training and inference can run on over ssh, picked with
+    /// `H` on the Training and Inference screens.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remote_hosts: Vec<RemoteHost>,
+    /// What opens a stem from the outputs view, with its path appended,
+    /// e.g. `mpv --no-video`; `xdg-open` when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player_command: Option<String>,
+    /// Runs only log the command they would start.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Training resumes from the newest checkpoint in `results_path`
+    /// without asking, in the TUI and headless; see
+    /// `checkpoints::resume_checkpoint`.
+    #[serde(default)]
+    pub auto_resume: bool,
+    /// Clicks select and the wheel scrolls. Off by default, as capturing
+    /// the mouse takes over the terminal's own text selection.
+    #[serde(default)]
+    pub mouse: bool,
+    /// Lists of configs, checkpoints and runs follow changes under the
+    /// configs and results folders. On unless set to false, e.g. on a
+    /// network filesystem where inotify misbehaves.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub watch_files: Option<bool>,
+    /// When a supervised job counts as possibly hung.
+    #[serde(default)]
+    pub watchdog: WatchdogSettings,
+    /// Keys for quit, help and navigation, over the defaults.
+    #[serde(default, skip_serializing_if = "KeyBindings::is_default")]
+    pub keybindings: KeyBindings,
+}
+
+impl AppConfig {
+    /// Selects `model_type`, dropping the binding if it was for another
+    /// architecture.
+    pub fn select_model(&mut self, model_type: ModelType) {
+        if self.model_binding.as_ref().is_some_and(|binding| binding.model_type != model_type) {
+            self.model_binding = None;
+        }
+        self.selected_model = Some(model_type);
+    }
+
+    /// Binds `binding` and selects its architecture.
+    pub fn bind_model(&mut self, binding: ModelBinding) {
+        self.selected_model = Some(binding.model_type.clone());
+        self.model_binding = Some(binding);
+    }
+
+    pub fn weights_dir(&self) -> &Path {
+        Path::new(self.weights_dir.as_deref().unwrap_or(DEFAULT_WEIGHTS_DIR))
+    }
+
+    pub fn gpu_min_free_mib(&self) -> u64 {
+        self.gpu_min_free_mib.unwrap_or(DEFAULT_MIN_FREE_MIB)
+    }
+
+    /// The remote host called `name`.
+    pub fn remote_host(&self, name: &str) -> Option<&RemoteHost> {
+        self.remote_hosts.iter().find(|host| host.name == name)
+    }
+
+    pub fn watch_files(&self) -> bool {
+        self.watch_files.unwrap_or(true)
+    }
+
+    pub fn player_command(&self) -> &str {
+        self.player_command.as_deref().unwrap_or(DEFAULT_PLAYER)
+    }
+
+    pub fn presets(&self, model_type: &ModelType) -> &[InferencePreset] {
+        self.inference_presets.get(model_type.key()).map_or(&[], Vec::as_slice)
+    }
+
+    /// Adds a preset for `model_type`, replacing one with the same name.
+    pub fn save_preset(&mut self, model_type: &ModelType, preset: InferencePreset) {
+        let presets = self.inference_presets.entry(model_type.key().to_string()).or_default();
+        match presets.iter_mut().find(|existing| existing.name == preset.name) {
+            Some(existing) => *existing = preset,
+            None => presets.push(preset),
+        }
+    }
+
+    pub fn exclude_patterns(&self, input_folder: &Path) -> &[String] {
+        self.exclude_patterns.get(&folder_key(input_folder)).map_or(&[], Vec::as_slice)
+    }
+
+    /// Remembers the patterns for `input_folder`; none forgets the folder.
+    pub fn set_exclude_patterns(&mut self, input_folder: &Path, patterns: &[String]) {
+        let key = folder_key(input_folder);
+        if patterns.is_empty() {
+            self.exclude_patterns.remove(&key);
+        } else {
+            self.exclude_patterns.insert(key, patterns.to_vec());
+        }
+    }
+}
+
+/// What a recent config was launched as.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecentKind {
+    /// A training config, e.g. `tui_training.yaml`.
+    #[default]
+    Training,
+    /// The model config of an inference run.
+    Inference,
+}
+
+/// A config a run was launched with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecentConfig {
+    pub path: String,
+    #[serde(default)]
+    pub kind: RecentKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_type: Option<ModelType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub used_at: Option<DateTime<Local>>,
+}
+
+impl RecentConfig {
+    pub fn new(path: &str, kind: RecentKind, model_type: ModelType) -> Self {
+        RecentConfig { path: path.to_string(), kind, model_type: Some(model_type), used_at: Some(Local::now()) }
+    }
+
+    /// Whether the config is still there; relative paths are under `root`.
+    pub fn exists(&self, root: &Path) -> bool {
+        root.join(&self.path).is_file()
+    }
+
+    /// E.g. "training  scnet  2026-10-18 09:30  tui_training.yaml".
+    pub fn line(&self, root: &Path) -> String {
+        let kind = match self.kind {
+            RecentKind::Training => "training",
+            RecentKind::Inference => "inference",
+        };
+        let model = self.model_type.as_ref().map_or("-", ModelType::key);
+        let used_at = self.used_at.map_or("-".to_string(), |time| time.format("%Y-%m-%d %H:%M").to_string());
+        let missing = if self.exists(root) { "" } else { "  (missing)" };
+        format!("{:<9}  {:<18}  {:<16}  {}{}", kind, model, used_at, self.path, missing)
+    }
+}
+
+impl AppConfig {
+    /// Puts `entry` first, dropping an older entry for the same file and
+    /// kind and anything past `MAX_RECENT_CONFIGS`.
+    pub fn push_recent_config(&mut self, entry: RecentConfig) {
+        self.recent_configs.retain(|recent| recent.path != entry.path || recent.kind != entry.kind);
+        self.recent_configs.insert(0, entry);
+        self.recent_configs.truncate(MAX_RECENT_CONFIGS);
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// `recent_configs` entries, also as the bare paths older versions wrote.
+fn recent_configs<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Vec<RecentConfig>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Stored {
+        Path(String),
+        Entry(RecentConfig),
+    }
+    let stored = Vec::<Stored>::deserialize(deserializer)?;
+    Ok(stored.into_iter()
+        .map(|entry| match entry {
+            Stored::Path(path) => RecentConfig { path, kind: RecentKind::Training, model_type: None, used_at: None },
+            Stored::Entry(entry) => entry,
+        })
+        .collect())
+}
+
+/// Absolute, so `songs` and `./songs` share their patterns.
+fn folder_key(folder: &Path) -> String {
+    std::path::absolute(folder).unwrap_or_else(|_| folder.to_path_buf()).to_string_lossy().to_string()
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub enum Theme {
+    #[default]
     Dark,
     Light,
     HighContrast,
 }
 
-impl Default for Theme {
-    fn default() -> Self {
-        Theme::Dark
-    }
-}
-
-impl Default for AppConfig {
-    fn default() -> Self {
-        AppConfig {
-            selected_model: None,
-            recent_configs: vec![],
-            theme: Theme::default(),
+impl Theme {
+    /// The theme after this one, for cycling through them.
+    pub fn next(&self) -> Theme {
+        match self {
+            Theme::Dark => Theme::Light,
+            Theme::Light => Theme::HighContrast,
+            Theme::HighContrast => Theme::Dark,
         }
     }
 }
@@ -52,10 +346,12 @@ impl ConfigManager {
             return Ok(AppConfig::default());
         }
 
-        let content = fs::read_to_string(&path)
+        let content = fs::read_to_string(path)
             .context("Failed to read config file")?;
-        
-        serde_yaml::from_str(&content)
+        let value = serde_yaml::from_str(&content)
+            .context("Failed to parse config file")?;
+        let value = integrity::migrate(value, CONFIG_SCHEMA_VERSION, "Config file", migrate_config)?;
+        serde_yaml::from_value(value)
             .context("Failed to parse config file")
     }
 
@@ -66,15 +362,33 @@ impl ConfigManager {
                 .context("Failed to create config directory")?;
         }
 
-        let content = serde_yaml::to_string(config)
+        let mut value = serde_yaml::to_value(config)
+            .context("Failed to serialize config")?;
+        integrity::stamp(&mut value, CONFIG_SCHEMA_VERSION);
+        let content = serde_yaml::to_string(&value)
             .context("Failed to serialize config")?;
         
-        fs::write(&path, content)
+        fs::write(path, content)
             .context("Failed to write config file")?;
 
         Ok(())
     }
 
+    /// Saves `config` unless the file already holds the same settings, so
+    /// that a save at exit doesn't rewrite an unchanged file. A file that
+    /// can't be read is left alone, as it may be from a newer version.
+    pub fn save_if_changed(&self, config: &AppConfig) -> Result<bool> {
+        let Ok(saved) = self.load_config() else {
+            return Ok(false);
+        };
+        let current = serde_yaml::to_value(config).context("Failed to serialize config")?;
+        if serde_yaml::to_value(&saved).context("Failed to serialize config")? == current {
+            return Ok(false);
+        }
+        self.save_config(config)?;
+        Ok(true)
+    }
+
     pub fn load_training_config(&self, path: &str) -> Result<TrainingConfig> {
         let content = fs::read_to_string(path)
             .context("Failed to read training config")?;
@@ -83,6 +397,20 @@ impl ConfigManager {
             .context("Failed to parse training config")
     }
 
+    pub fn load_inference_config(&self, path: &str) -> Result<InferenceConfig> {
+        let content = fs::read_to_string(path)
+            .context("Failed to read inference config")?;
+        serde_yaml::from_str(&content)
+            .context("Failed to parse inference config")
+    }
+
+    pub fn load_validation_config(&self, path: &str) -> Result<ValidationConfig> {
+        let content = fs::read_to_string(path)
+            .context("Failed to read validation config")?;
+        serde_yaml::from_str(&content)
+            .context("Failed to parse validation config")
+    }
+
     pub fn save_training_config(&self, path: &str, config: &TrainingConfig) -> Result<()> {
         let content = serde_yaml::to_string(config)
             .context("Failed to serialize training config")?;
@@ -106,14 +434,285 @@ impl ConfigManager {
             let entry = entry.context("Failed to read directory entry")?;
             let file_path = entry.path();
             
-            if file_path.extension().map_or(false, |ext| ext == "yaml" || ext == "yml") {
-                if let Some(name) = file_path.file_name() {
-                    configs.push(name.to_string_lossy().to_string());
-                }
+            if file_path.extension().is_some_and(|ext| ext == "yaml" || ext == "yml")
+                && let Some(name) = file_path.file_name()
+            {
+                configs.push(name.to_string_lossy().to_string());
             }
         }
 
         configs.sort();
         Ok(configs)
     }
+
+    /// The config in `configs_dir` most likely meant for `model_type`: one
+    /// of its `default_config_candidates`, else a config whose name has the
+    /// model's key (ending in it first), e.g. `config_dnr_bandit_v2_mus64.yaml`.
+    /// Names with a longer key containing this one, like `bs_conformer` for
+    /// `conformer`, belong to that other model.
+    pub fn discover_config(&self, configs_dir: &str, model_type: &ModelType) -> Result<Option<String>> {
+        let configs = self.list_configs(configs_dir)?;
+        let exact = model_type.default_config_candidates().into_iter().find(|candidate| configs.contains(candidate));
+        let found = exact.or_else(|| {
+            let mut matches: Vec<(bool, &String)> = configs.iter()
+                .filter_map(|name| {
+                    let stem = Path::new(name).file_stem()?.to_string_lossy().to_string();
+                    let (key, at_end) = config_model_key(&stem)?;
+                    (key == model_type.key()).then_some((at_end, name))
+                })
+                .collect();
+            matches.sort_by_key(|(at_end, name)| (!at_end, *name));
+            matches.first().map(|(_, name)| (*name).clone())
+        });
+        Ok(found.map(|name| Path::new(configs_dir).join(name).to_string_lossy().to_string()))
+    }
+}
+
+/// The longest model key in `stem` as whole `_`-separated words, and
+/// whether the stem ends with it.
+fn config_model_key(stem: &str) -> Option<(&'static str, bool)> {
+    let words: Vec<&str> = stem.split('_').collect();
+    ModelType::all_models()
+        .iter()
+        .filter_map(|model_type| {
+            let key: Vec<&str> = model_type.key().split('_').collect();
+            let at = words.windows(key.len()).rposition(|window| window == key.as_slice())?;
+            Some((model_type.key(), at + key.len() == words.len()))
+        })
+        .max_by_key(|(key, _)| key.len())
+}
+
+/// Migrates a config file from format `from` to the next one.
+fn migrate_config(from: u32, value: serde_yaml::Value) -> Result<serde_yaml::Value> {
+    match from {
+        // Format 1 only added `schema_version`.
+        0 => Ok(value),
+        _ => bail!("No migration from config format {}", from),
+    }
+}
+
+/// Input format a model expects, from the `audio` section of its YAML config.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ModelAudio {
+    pub sample_rate: Option<u32>,
+    pub num_channels: Option<u16>,
+}
+
+/// Reads `audio.sample_rate` and `audio.num_channels` from a model YAML config.
+pub fn model_audio(path: &str) -> Result<ModelAudio> {
+    let content = fs::read_to_string(path)
+        .context("Failed to read model config")?;
+    let value: serde_yaml::Value = serde_yaml::from_str(&content)
+        .context("Failed to parse model config")?;
+
+    let field = |name: &str| value.get("audio")
+        .and_then(|audio| audio.get(name))
+        .and_then(|field| field.as_u64());
+    Ok(ModelAudio {
+        sample_rate: field("sample_rate").map(|rate| rate as u32),
+        num_channels: field("num_channels").map(|channels| channels as u16),
+    })
+}
+
+/// Reads `training.num_epochs` from a model YAML config.
+pub fn model_num_epochs(path: &str) -> Result<Option<usize>> {
+    let content = fs::read_to_string(path)
+        .context("Failed to read model config")?;
+    let value: serde_yaml::Value = serde_yaml::from_str(&content)
+        .context("Failed to parse model config")?;
+    Ok(value.get("training")
+        .and_then(|training| training.get("num_epochs"))
+        .and_then(|epochs| epochs.as_u64())
+        .map(|epochs| epochs as usize))
+}
+
+/// The stems a model config separates into: `training.instruments`, or
+/// just `training.target_instrument` when it names one.
+pub fn model_instruments(path: &str) -> Result<Vec<String>> {
+    let content = fs::read_to_string(path)
+        .context("Failed to read model config")?;
+    let value: serde_yaml::Value = serde_yaml::from_str(&content)
+        .context("Failed to parse model config")?;
+    let Some(training) = value.get("training") else {
+        return Ok(vec![]);
+    };
+    if let Some(target) = training.get("target_instrument").and_then(|target| target.as_str()) {
+        return Ok(vec![target.to_string()]);
+    }
+    Ok(training.get("instruments")
+        .and_then(|instruments| instruments.as_sequence())
+        .map(|instruments| instruments.iter().filter_map(|name| name.as_str()).map(String::from).collect())
+        .unwrap_or_default())
+}
+
+/// Writes a copy of a model config with `inference.normalize` overridden.
+///
+/// The YAML is patched as text: parsing and re-serializing would drop the
+/// comments and the `!!python/tuple` tags the configs rely on.
+pub fn write_normalize_override(path: &str, normalize: bool, to: &Path) -> Result<()> {
+    let content = fs::read_to_string(path)
+        .context("Failed to read model config")?;
+    let setting = format!("normalize: {}", normalize);
+
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let section = lines.iter().position(|line| {
+        line.split('#').next().unwrap_or_default().trim_end() == "inference:"
+    });
+    match section {
+        Some(section) => {
+            let body: Vec<usize> = (section + 1..lines.len())
+                .take_while(|&i| {
+                    let line = &lines[i];
+                    line.trim().is_empty() || line.starts_with(' ') || line.starts_with('\t') || line.starts_with('#')
+                })
+                .collect();
+            let indent = body.iter()
+                .map(|&i| &lines[i])
+                .find(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+                .map(|line| line[..line.len() - line.trim_start().len()].to_string())
+                .unwrap_or_else(|| "  ".to_string());
+            match body.iter().find(|&&i| lines[i].trim_start().starts_with("normalize:")) {
+                Some(&i) => lines[i] = format!("{}{}", indent, setting),
+                None => lines.insert(section + 1, format!("{}{}", indent, setting)),
+            }
+        }
+        None => {
+            lines.push("inference:".to_string());
+            lines.push(format!("  {}", setting));
+        }
+    }
+    let patched = lines.join("\n") + "\n";
+
+    let check: serde_yaml::Value = serde_yaml::from_str(&patched)
+        .context("Failed to patch model config")?;
+    if check.get("inference").and_then(|inference| inference.get("normalize")) != Some(&normalize.into()) {
+        bail!("Failed to patch inference.normalize in model config");
+    }
+
+    fs::write(to, patched)
+        .context("Failed to write model config override")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_configs_are_found_best_match_first() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "config_musdb18_scnet.yaml",
+            "config_musdb18_scnet_large.yaml",
+            "config_vocals_scnet.yaml",
+            "config_apollo.yaml",
+            "config_dnr_bandit_bsrnn_multi_mus64.yaml",
+            "config_dnr_bandit_v2_mus64.yaml",
+            "config_vocals_bandit_bsrnn_multi_mus64.yaml",
+            "config_vocals_bs_conformer.yaml",
+            "config_musdb18_mel_band_conformer.yaml",
+            "notes.txt",
+        ] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+        let configs_dir = dir.path().to_string_lossy().to_string();
+        let manager = ConfigManager::new(DEFAULT_CONFIG_FILE);
+        let found = |model_type: ModelType| {
+            manager.discover_config(&configs_dir, &model_type).unwrap()
+                .map(|path| Path::new(&path).file_name().unwrap().to_string_lossy().to_string())
+        };
+        assert_eq!(found(ModelType::ScNet).as_deref(), Some("config_musdb18_scnet.yaml"));
+        assert_eq!(found(ModelType::Apollo).as_deref(), Some("config_apollo.yaml"));
+        assert_eq!(found(ModelType::BandIt).as_deref(), Some("config_dnr_bandit_bsrnn_multi_mus64.yaml"));
+        assert_eq!(found(ModelType::BandItV2).as_deref(), Some("config_dnr_bandit_v2_mus64.yaml"));
+        assert_eq!(found(ModelType::BsConformer).as_deref(), Some("config_vocals_bs_conformer.yaml"));
+        assert_eq!(found(ModelType::Conformer).as_deref(), Some("config_musdb18_mel_band_conformer.yaml"));
+        assert_eq!(found(ModelType::HtDemucs), None);
+
+        fs::remove_file(dir.path().join("config_musdb18_scnet.yaml")).unwrap();
+        assert_eq!(found(ModelType::ScNet).as_deref(), Some("config_vocals_scnet.yaml"));
+        assert_eq!(manager.discover_config("/no/such/configs", &ModelType::ScNet).unwrap(), None);
+    }
+
+    #[test]
+    fn recent_configs_are_deduplicated_capped_and_read_from_old_files() {
+        let mut config: AppConfig = serde_yaml::from_str("recent_configs: [tui_training.yaml]\n").unwrap();
+        assert_eq!(config.recent_configs[0].path, "tui_training.yaml");
+        assert_eq!(config.recent_configs[0].model_type, None);
+
+        for i in 0..12 {
+            config.push_recent_config(RecentConfig::new(&format!("run{}.yaml", i), RecentKind::Training, ModelType::ScNet));
+        }
+        config.push_recent_config(RecentConfig::new("run5.yaml", RecentKind::Training, ModelType::HtDemucs));
+        config.push_recent_config(RecentConfig::new("run5.yaml", RecentKind::Inference, ModelType::HtDemucs));
+        let paths: Vec<&str> = config.recent_configs.iter().map(|recent| recent.path.as_str()).collect();
+        assert_eq!(paths, ["run5.yaml", "run5.yaml", "run11.yaml", "run10.yaml", "run9.yaml", "run8.yaml", "run7.yaml", "run6.yaml", "run4.yaml", "run3.yaml"]);
+        assert_eq!(config.recent_configs[1].model_type, Some(ModelType::HtDemucs));
+        assert!(config.recent_configs[0].line(Path::new("")).ends_with("run5.yaml  (missing)"));
+    }
+
+    #[test]
+    fn normalize_override_keeps_the_rest_of_the_config() {
+        let original = concat!(env!("CARGO_MANIFEST_DIR"), "/../configs/config_musdb18_scnet.yaml");
+        let dir = tempfile::tempdir().unwrap();
+        let patched = dir.path().join("patched.yaml");
+        write_normalize_override(original, false, &patched).unwrap();
+
+        let read = |path: &str| -> serde_yaml::Value {
+            serde_yaml::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+        };
+        let before = read(original);
+        let after = read(patched.to_str().unwrap());
+        assert_eq!(after["inference"]["normalize"], serde_yaml::Value::Bool(false));
+        assert_eq!(after["training"], before["training"]);
+        assert_eq!(after["augmentations"], before["augmentations"]);
+        assert!(fs::read_to_string(&patched).unwrap().contains("!!python/tuple"));
+        assert_eq!(model_audio(patched.to_str().unwrap()).unwrap(), model_audio(original).unwrap());
+        assert_eq!(model_instruments(original).unwrap(), ["drums", "bass", "other", "vocals"]);
+
+        let bare = dir.path().join("bare.yaml");
+        fs::write(&bare, "audio:\n  sample_rate: 44100\n").unwrap();
+        write_normalize_override(bare.to_str().unwrap(), true, &patched).unwrap();
+        assert_eq!(read(patched.to_str().unwrap())["inference"]["normalize"], serde_yaml::Value::Bool(true));
+    }
+
+    #[test]
+    fn config_round_trips_and_old_files_get_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tui_config.yaml");
+        let manager = ConfigManager::new(path.to_str().unwrap());
+        assert!(manager.load_config().unwrap().selected_model.is_none(), "no file, defaults");
+
+        fs::write(&path, "selected_model: ScNet\n").unwrap();
+        let old = manager.load_config().unwrap();
+        assert_eq!(old.selected_model, Some(ModelType::ScNet));
+        assert_eq!(old.theme, Theme::Dark);
+        assert!(old.recent_configs.is_empty());
+        assert!(!manager.save_if_changed(&old).unwrap(), "same settings, file left as is");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "selected_model: ScNet\n");
+
+        let mut config = old;
+        config.theme = Theme::Light;
+        config.push_recent_config(RecentConfig::new("configs/config_musdb18_scnet.yaml", RecentKind::Training, ModelType::ScNet));
+        assert!(manager.save_if_changed(&config).unwrap());
+        let loaded = manager.load_config().unwrap();
+        assert_eq!(loaded.theme, Theme::Light);
+        assert_eq!(loaded.recent_configs, config.recent_configs);
+        assert_eq!(loaded.selected_model, Some(ModelType::ScNet));
+    }
+
+    #[test]
+    fn binding_survives_only_the_same_architecture() {
+        let mut config = AppConfig::default();
+        config.bind_model(ModelBinding::manual(ModelType::BsRoformer, "a.ckpt"));
+        config.select_model(ModelType::BsRoformer);
+        assert!(config.model_binding.is_some());
+
+        let saved: AppConfig = serde_yaml::from_str(&serde_yaml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(saved.model_binding, config.model_binding);
+
+        config.select_model(ModelType::ScNet);
+        assert_eq!(config.selected_model, Some(ModelType::ScNet));
+        assert!(config.model_binding.is_none());
+    }
 }
@@ -0,0 +1,236 @@
+use std::fs;
+use std::path::Path;
+
+use crate::lint::Severity;
+use crate::model::{InferenceConfig, TrainingConfig, ValidationConfig};
+
+/// Written and removed again to see whether a folder takes new files.
+const WRITE_PROBE: &str = ".msst_tui_write_test";
+
+const CHECKPOINT_EXTENSIONS: [&str; 3] = ["ckpt", "th", "pt"];
+
+/// Something wrong with a run's config that would only show up as a
+/// traceback once the python process gets to it. Errors stop the run;
+/// warnings can be started through.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigIssue {
+    /// The config field at fault, e.g. `data_paths`.
+    pub field: &'static str,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl ConfigIssue {
+    pub fn error(field: &'static str, message: String) -> Self {
+        ConfigIssue { field, severity: Severity::Error, message }
+    }
+
+    pub fn warning(field: &'static str, message: String) -> Self {
+        ConfigIssue { field, severity: Severity::Warning, message }
+    }
+
+    /// E.g. "✗ data_paths: /data/musdb not found".
+    pub fn line(&self) -> String {
+        let mark = match self.severity {
+            Severity::Error => "✗",
+            Severity::Warning => "!",
+        };
+        format!("{} {}: {}", mark, self.field, self.message)
+    }
+}
+
+pub fn has_errors(issues: &[ConfigIssue]) -> bool {
+    issues.iter().any(|issue| issue.severity == Severity::Error)
+}
+
+/// Checks a training config before train.py is started with it. Creates
+/// `results_path` if it doesn't exist yet.
+pub fn validate(config: &TrainingConfig) -> Vec<ConfigIssue> {
+    let mut issues = vec![];
+    check_model_config(&mut issues, &config.config_path);
+    if let Some(checkpoint) = &config.start_checkpoint {
+        check_checkpoint(&mut issues, "start_checkpoint", checkpoint);
+    }
+    if config.data_paths.is_empty() {
+        issues.push(ConfigIssue::error("data_paths", "no dataset folder set".to_string()));
+    }
+    for data_path in &config.data_paths {
+        check_folder(&mut issues, "data_paths", data_path);
+    }
+    if let Some(valid_path) = &config.valid_path {
+        check_folder(&mut issues, "valid_path", valid_path);
+    }
+    if config.device_ids.as_ref().is_some_and(Vec::is_empty) {
+        issues.push(ConfigIssue::error("device_ids", "empty; leave it unset to use the default GPU".to_string()));
+    }
+    check_writable(&mut issues, "results_path", &config.results_path);
+    issues
+}
+
+/// Checks a training config for a run on a remote host. Its folders are
+/// the host's, so of the files only the model config, which is read here
+/// too, is looked at.
+pub fn validate_remote(config: &TrainingConfig) -> Vec<ConfigIssue> {
+    let mut issues = vec![];
+    check_model_config(&mut issues, &config.config_path);
+    if config.data_paths.is_empty() {
+        issues.push(ConfigIssue::error("data_paths", "no dataset folder set".to_string()));
+    }
+    if config.device_ids.as_ref().is_some_and(Vec::is_empty) {
+        issues.push(ConfigIssue::error("device_ids", "empty; leave it unset to use the default GPU".to_string()));
+    }
+    issues
+}
+
+/// Checks an inference config before inference.py is started with it.
+/// Creates `store_dir` if it doesn't exist yet.
+pub fn validate_inference(config: &InferenceConfig) -> Vec<ConfigIssue> {
+    let mut issues = vec![];
+    check_model_config(&mut issues, &config.config_path);
+    check_checkpoint(&mut issues, "start_checkpoint", &config.start_checkpoint);
+    check_folder(&mut issues, "input_folder", &config.input_folder);
+    check_writable(&mut issues, "store_dir", &config.store_dir);
+    issues
+}
+
+/// Checks a validation config before valid.py is started with it.
+pub fn validate_validation(config: &ValidationConfig) -> Vec<ConfigIssue> {
+    let mut issues = vec![];
+    check_model_config(&mut issues, &config.config_path);
+    check_checkpoint(&mut issues, "start_checkpoint", &config.start_checkpoint);
+    check_folder(&mut issues, "valid_path", &config.valid_path);
+    issues
+}
+
+fn check_model_config(issues: &mut Vec<ConfigIssue>, path: &str) {
+    if path.trim().is_empty() {
+        issues.push(ConfigIssue::error("config_path", "not set".to_string()));
+        return;
+    }
+    match fs::read_to_string(path) {
+        Ok(content) => {
+            if let Err(e) = serde_yaml::from_str::<serde_yaml::Value>(&content) {
+                issues.push(ConfigIssue::error("config_path", format!("{} is not valid YAML: {}", path, e)));
+            }
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            issues.push(ConfigIssue::error("config_path", format!("{} not found", path)));
+        }
+        Err(e) => issues.push(ConfigIssue::error("config_path", format!("Failed to read {}: {}", path, e))),
+    }
+}
+
+fn check_checkpoint(issues: &mut Vec<ConfigIssue>, field: &'static str, path: &str) {
+    if path.trim().is_empty() {
+        issues.push(ConfigIssue::error(field, "not set".to_string()));
+        return;
+    }
+    let path = Path::new(path);
+    if !path.is_file() {
+        issues.push(ConfigIssue::error(field, format!("{} not found", path.display())));
+        return;
+    }
+    let extension = path.extension().map(|extension| extension.to_string_lossy().to_lowercase());
+    if !extension.is_some_and(|extension| CHECKPOINT_EXTENSIONS.contains(&extension.as_str())) {
+        issues.push(ConfigIssue::warning(field, format!(
+            "{} is not a .{} file",
+            path.display(),
+            CHECKPOINT_EXTENSIONS.join(", .")
+        )));
+    }
+}
+
+fn check_folder(issues: &mut Vec<ConfigIssue>, field: &'static str, path: &str) {
+    if path.trim().is_empty() {
+        issues.push(ConfigIssue::error(field, "not set".to_string()));
+        return;
+    }
+    let path = Path::new(path);
+    if !path.exists() {
+        issues.push(ConfigIssue::error(field, format!("{} not found", path.display())));
+    } else if !path.is_dir() {
+        issues.push(ConfigIssue::error(field, format!("{} is not a folder", path.display())));
+    } else if fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_none()) {
+        issues.push(ConfigIssue::warning(field, format!("{} is empty", path.display())));
+    }
+}
+
+fn check_writable(issues: &mut Vec<ConfigIssue>, field: &'static str, path: &str) {
+    if path.trim().is_empty() {
+        issues.push(ConfigIssue::error(field, "not set".to_string()));
+        return;
+    }
+    let path = Path::new(path);
+    if let Err(e) = fs::create_dir_all(path) {
+        issues.push(ConfigIssue::error(field, format!("Failed to create {}: {}", path.display(), e)));
+        return;
+    }
+    let probe = path.join(WRITE_PROBE);
+    match fs::write(&probe, "") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+        }
+        Err(e) => issues.push(ConfigIssue::error(field, format!("{} is not writable: {}", path.display(), e))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ModelType;
+
+    #[test]
+    fn training_config_paths_are_checked() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name).to_string_lossy().to_string();
+        fs::write(path("scnet.yaml"), "audio:\n  chunk_size: 485100\n").unwrap();
+        fs::write(path("broken.yaml"), "audio: [chunk_size\n").unwrap();
+        fs::create_dir_all(path("musdb/train/song")).unwrap();
+        fs::create_dir_all(path("valid")).unwrap();
+        fs::write(path("model.bin"), "").unwrap();
+
+        let mut config = TrainingConfig::new(ModelType::ScNet);
+        config.config_path = path("scnet.yaml");
+        config.data_paths = vec![path("musdb/train")];
+        config.results_path = path("results/scnet");
+        assert_eq!(validate(&config), []);
+        assert!(Path::new(&path("results/scnet")).is_dir(), "results_path is created");
+
+        config.config_path = path("broken.yaml");
+        config.data_paths.push(path("musdb/tets"));
+        config.valid_path = Some(path("valid"));
+        config.start_checkpoint = Some(path("model.bin"));
+        config.device_ids = Some(vec![]);
+        let issues = validate(&config);
+        let fields: Vec<(&str, Severity)> = issues.iter().map(|issue| (issue.field, issue.severity)).collect();
+        assert_eq!(fields, [
+            ("config_path", Severity::Error),
+            ("start_checkpoint", Severity::Warning),
+            ("data_paths", Severity::Error),
+            ("valid_path", Severity::Warning),
+            ("device_ids", Severity::Error),
+        ]);
+        assert_eq!(issues[2].line(), format!("✗ data_paths: {} not found", path("musdb/tets")));
+        assert!(has_errors(&issues));
+        assert!(!has_errors(&issues[1..2]));
+    }
+
+    #[test]
+    fn inference_and_validation_need_their_inputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = InferenceConfig::new(ModelType::MelBandRoformer);
+        config.store_dir = dir.path().join("separated").to_string_lossy().to_string();
+        let fields: Vec<&str> = validate_inference(&config).iter().map(|issue| issue.field).collect();
+        assert_eq!(fields, ["config_path", "start_checkpoint", "input_folder"]);
+
+        let validation = ValidationConfig {
+            model_type: ModelType::MelBandRoformer,
+            config_path: String::new(),
+            start_checkpoint: "missing.ckpt".to_string(),
+            valid_path: dir.path().to_string_lossy().to_string(),
+        };
+        let issues = validate_validation(&validation);
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[1].message, "missing.ckpt not found");
+    }
+}
@@ -0,0 +1,150 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use crate::config::ConfigManager;
+use crate::model::{ModelType, TrainingConfig};
+use crate::template;
+
+/// The training config fields on the Config screen, in display order.
+pub const FORM_FIELDS: [&str; 7] = [
+    "config_path",
+    "results_path",
+    "data_paths",
+    "valid_path",
+    "num_workers",
+    "device_ids",
+    "extra_args",
+];
+
+/// The working training config behind the Config screen. Text typed into a
+/// field and left with Esc is kept as a draft until it is set with Enter or
+/// the form is saved.
+#[derive(Debug, Clone)]
+pub struct ConfigForm {
+    pub path: String,
+    pub config: TrainingConfig,
+    /// `config_path` was filled in by `auto_select_config` and hasn't been
+    /// set by hand since.
+    pub auto_config: bool,
+    drafts: [Option<String>; FORM_FIELDS.len()],
+}
+
+impl ConfigForm {
+    /// The config saved at `path`, or a new one for `model_type` when there
+    /// is none yet.
+    pub fn open(path: &str, model_type: ModelType) -> Result<Self> {
+        let config = if Path::new(path).exists() {
+            ConfigManager::new(path).load_training_config(path)?
+        } else {
+            TrainingConfig::new(model_type)
+        };
+        Ok(ConfigForm { path: path.to_string(), config, auto_config: false, drafts: Default::default() })
+    }
+
+    /// What to start editing `field` with: its draft, or its value.
+    pub fn edit_text(&self, field: usize) -> String {
+        self.drafts[field].clone().unwrap_or_else(|| template::field_value(&self.config, FORM_FIELDS[field]))
+    }
+
+    pub fn keep_draft(&mut self, field: usize, text: &str) {
+        self.drafts[field] = Some(text.to_string());
+    }
+
+    /// Sets `field` from `text`; the draft stays if the text is invalid.
+    pub fn set(&mut self, field: usize, text: &str) -> Result<()> {
+        template::set_field(&mut self.config, FORM_FIELDS[field], text)?;
+        self.drafts[field] = None;
+        if field == 0 {
+            self.auto_config = false;
+        }
+        Ok(())
+    }
+
+    /// Uses `found`, the config discovered for `model_type`, unless a
+    /// config path was set or typed by hand. True if it was used.
+    pub fn auto_select_config(&mut self, model_type: &ModelType, found: &str) -> bool {
+        let by_hand = !self.config.config_path.is_empty() && !self.auto_config;
+        if by_hand || self.drafts[0].is_some() || (self.auto_config && self.config.config_path == found) {
+            return false;
+        }
+        self.config.model_type = model_type.clone();
+        self.config.config_path = found.to_string();
+        self.auto_config = true;
+        true
+    }
+
+    pub fn has_drafts(&self) -> bool {
+        self.drafts.iter().any(Option::is_some)
+    }
+
+    /// Sets every draft, then writes the config to `path`.
+    pub fn save(&mut self, manager: &ConfigManager) -> Result<()> {
+        for (field, name) in FORM_FIELDS.iter().enumerate() {
+            if let Some(draft) = self.drafts[field].clone() {
+                self.set(field, &draft).with_context(|| format!("Invalid {}", name))?;
+            }
+        }
+        manager.save_training_config(&self.path, &self.config)
+    }
+
+    /// One line per field, "~" marking the ones with an unset draft.
+    pub fn lines(&self) -> Vec<String> {
+        (0..FORM_FIELDS.len())
+            .map(|field| match &self.drafts[field] {
+                Some(draft) => format!("~ {:<14}{}  (not set yet)", FORM_FIELDS[field], draft),
+                None if field == 0 && self.auto_config => format!("  {:<14}{}  (auto-selected)", FORM_FIELDS[field], self.config.config_path),
+                None => format!("  {:<14}{}", FORM_FIELDS[field], template::field_value(&self.config, FORM_FIELDS[field])),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_CONFIG_FILE;
+
+    #[test]
+    fn drafts_survive_until_set_and_empty_fields_save_as_none() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("train.yaml").to_string_lossy().to_string();
+        let mut form = ConfigForm::open(&path, ModelType::ScNet).unwrap();
+
+        form.keep_draft(4, "eight");
+        assert_eq!(form.edit_text(4), "eight");
+        assert!(form.lines()[4].starts_with("~ num_workers"));
+        assert!(form.set(4, "eight").is_err());
+        assert!(form.has_drafts(), "an invalid value stays a draft");
+        form.set(4, "8").unwrap();
+        form.set(0, "configs/scnet.yaml").unwrap();
+        form.set(1, "results").unwrap();
+        form.keep_draft(2, "data/a, data/b");
+        form.keep_draft(3, "  ");
+
+        let manager = ConfigManager::new(DEFAULT_CONFIG_FILE);
+        form.save(&manager).unwrap();
+        assert!(!form.has_drafts());
+        let saved = manager.load_training_config(&path).unwrap();
+        assert_eq!(saved.data_paths, ["data/a", "data/b"]);
+        assert_eq!((saved.valid_path, saved.num_workers, saved.device_ids), (None, Some(8), None));
+
+        let reopened = ConfigForm::open(&path, ModelType::HtDemucs).unwrap();
+        assert_eq!(reopened.config.model_type, ModelType::ScNet);
+        assert_eq!(reopened.edit_text(0), "configs/scnet.yaml");
+    }
+
+    #[test]
+    fn discovered_config_never_replaces_one_set_by_hand() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut form = ConfigForm::open(&dir.path().join("train.yaml").to_string_lossy(), ModelType::ScNet).unwrap();
+        assert!(form.auto_select_config(&ModelType::ScNet, "configs/config_musdb18_scnet.yaml"));
+        assert!(form.lines()[0].ends_with("configs/config_musdb18_scnet.yaml  (auto-selected)"));
+        assert!(form.auto_select_config(&ModelType::HtDemucs, "configs/config_musdb18_htdemucs.yaml"), "follows the model");
+        assert_eq!(form.config.model_type, ModelType::HtDemucs);
+
+        form.set(0, "configs/mine.yaml").unwrap();
+        assert!(!form.auto_config);
+        assert!(!form.auto_select_config(&ModelType::ScNet, "configs/config_musdb18_scnet.yaml"));
+        assert_eq!(form.config.config_path, "configs/mine.yaml");
+    }
+}
@@ -0,0 +1,108 @@
+use chrono::{DateTime, Datelike, Local, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::history::HistoryEntry;
+
+/// What renting the GPU costs. Without it runs aren't priced.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostSettings {
+    pub hourly_rate: f64,
+    /// Any label, e.g. "USD" or "credits".
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    /// Whether time a run spent stopped (SIGSTOP) is billed too, as it is
+    /// by providers that bill for the instance rather than its use.
+    #[serde(default)]
+    pub bill_paused: bool,
+}
+
+fn default_currency() -> String {
+    "USD".to_string()
+}
+
+/// The price a run was started at, kept in its metadata so a later change
+/// of rate doesn't reprice old runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunCost {
+    pub hourly_rate: f64,
+    pub currency: String,
+    pub bill_paused: bool,
+    /// Seconds the run was stopped.
+    #[serde(default)]
+    pub paused_secs: f64,
+}
+
+impl RunCost {
+    pub fn new(settings: &CostSettings) -> Self {
+        RunCost {
+            hourly_rate: settings.hourly_rate,
+            currency: settings.currency.clone(),
+            bill_paused: settings.bill_paused,
+            paused_secs: 0.0,
+        }
+    }
+
+    /// Wall-clock time from `started_at` to `finished_at`, or to now while
+    /// the run is going, times the rate.
+    pub fn amount(&self, started_at: DateTime<Utc>, finished_at: Option<DateTime<Utc>>) -> f64 {
+        let end = finished_at.unwrap_or_else(Utc::now);
+        let mut secs = (end - started_at).num_milliseconds() as f64 / 1000.0;
+        if !self.bill_paused {
+            secs -= self.paused_secs;
+        }
+        secs.max(0.0) / 3600.0 * self.hourly_rate
+    }
+}
+
+/// E.g. "3.20 USD".
+pub fn format_amount(amount: f64, currency: &str) -> String {
+    format!("{:.2} {}", amount, currency)
+}
+
+/// SDR in dB per unit of currency; `None` for free runs.
+pub fn sdr_per_cost(sdr: f64, amount: f64) -> Option<f64> {
+    (amount > 0.0).then(|| sdr / amount)
+}
+
+/// The cost of the priced runs started in the month of `now`, one total
+/// per currency in the order they first appear.
+pub fn monthly_totals(entries: &[HistoryEntry], now: DateTime<Local>) -> Vec<(String, f64)> {
+    let mut totals: Vec<(String, f64)> = vec![];
+    for entry in entries {
+        let started = entry.started_at.with_timezone(&Local);
+        let Some(cost) = entry.cost.as_ref().filter(|_| (started.year(), started.month()) == (now.year(), now.month())) else {
+            continue;
+        };
+        let amount = cost.amount(entry.started_at, entry.finished_at);
+        match totals.iter_mut().find(|(currency, _)| *currency == cost.currency) {
+            Some((_, total)) => *total += amount,
+            None => totals.push((cost.currency.clone(), amount)),
+        }
+    }
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn paused_time_is_billed_only_when_configured() {
+        let settings = CostSettings { hourly_rate: 2.0, currency: "EUR".into(), bill_paused: false };
+        let mut cost = RunCost::new(&settings);
+        cost.paused_secs = 1800.0;
+        let started = Utc::now() - Duration::hours(3);
+        let finished = started + Duration::hours(2);
+
+        assert!((cost.amount(started, Some(finished)) - 3.0).abs() < 1e-9);
+        cost.bill_paused = true;
+        assert!((cost.amount(started, Some(finished)) - 4.0).abs() < 1e-9);
+        // Still running: priced up to now.
+        assert!((cost.amount(started, None) - 6.0).abs() < 1e-3);
+
+        assert_eq!(format_amount(3.0, "EUR"), "3.00 EUR");
+        assert_eq!(sdr_per_cost(9.0, 3.0), Some(3.0));
+        assert_eq!(sdr_per_cost(9.0, 0.0), None);
+    }
+}
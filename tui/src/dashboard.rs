@@ -0,0 +1,233 @@
+use chrono::Utc;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::history::{RunHistory, RunKind};
+use crate::log_buffer::{LogBuffer, TimeDisplay};
+use crate::model::TrainingProgress;
+use crate::owner::{owner, Owner};
+use crate::parse_health::{ParseHealth, ParseHealthSettings, ParseMonitor};
+use crate::parser::ParserRules;
+use crate::run_dir::LOGS_DIR;
+use crate::tail::{LogTail, TailEvent};
+use crate::training::ProgressParser;
+use crate::watchdog::{Watchdog, WatchdogSettings};
+
+/// How often the watched logs are read.
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Log lines shown under the selected run.
+const TAIL_LINES: usize = 15;
+
+/// Log lines shown once the run's progress can't be parsed, when the log
+/// is all there is to go by.
+pub const LOG_FORWARD_LINES: usize = 50;
+
+/// A run another TUI instance owns, followed through its log files.
+pub struct WatchedRun {
+    pub name: String,
+    pub dir: PathBuf,
+    pub owner: Owner,
+    pub log: LogBuffer,
+    pub progress: Option<TrainingProgress>,
+    /// Set once the owner is gone or the log can't be read.
+    pub status: Option<String>,
+    pub parse_health: ParseMonitor,
+    /// Fed the lines of its log; the owner's GPU readings aren't seen here.
+    pub watchdog: Watchdog,
+    parser: ProgressParser,
+    tail: Option<LogTail>,
+}
+
+impl WatchedRun {
+    fn new(name: String, dir: PathBuf, owner: Owner, started_at: chrono::DateTime<Utc>, settings: ParseHealthSettings, watchdog: WatchdogSettings) -> Self {
+        let log = dir.join(LOGS_DIR).join("stdout.log");
+        let (tail, status) = match LogTail::open(&log, true) {
+            Ok(tail) => (Some(tail), None),
+            Err(e) => (None, Some(format!("{:#}", e))),
+        };
+        WatchedRun {
+            name,
+            dir,
+            owner,
+            log: LogBuffer::since(started_at),
+            progress: None,
+            status,
+            parse_health: ParseMonitor::new(settings, started_at),
+            watchdog: Watchdog::new(watchdog, Instant::now()),
+            parser: ProgressParser::new(),
+            tail,
+        }
+    }
+
+    fn refresh(&mut self, rules: &ParserRules) {
+        if !self.owner.is_alive() {
+            self.status = Some("owner exited".to_string());
+        }
+        self.parse_health.check(Utc::now());
+        self.watchdog.check(Instant::now());
+        let Some(tail) = self.tail.as_mut() else {
+            return;
+        };
+        let events = match tail.poll() {
+            Ok(events) => events,
+            Err(e) => {
+                self.status = Some(format!("{:#}", e));
+                return;
+            }
+        };
+        for event in events {
+            match event {
+                TailEvent::Line(line) => {
+                    let (at, text) = LogBuffer::parse_exported(&line);
+                    let at = at.unwrap_or_else(Utc::now);
+                    self.log.push_at(text, at);
+                    self.watchdog.line(rules, text, Instant::now());
+                    // The open epoch is shown as it fills in, not only once it's over.
+                    let finished = self.parser.push(rules, text);
+                    self.parse_health.observe(text, self.parser.last_matched(), at);
+                    if let Some(progress) = finished.or_else(|| self.parser.pending().cloned()) {
+                        self.progress = Some(progress);
+                    }
+                }
+                TailEvent::Missing => self.status = Some("log file missing".to_string()),
+                TailEvent::Reappeared => self.status = None,
+                TailEvent::Truncated | TailEvent::Rotated => {}
+            }
+        }
+    }
+
+    /// E.g. "run_a  epoch 12, loss 0.0412, SDR 9.10  (PID 4242 on gpu-box since 14:03)".
+    pub fn summary(&self) -> String {
+        let progress = match &self.progress {
+            Some(progress) => {
+                let sdr = progress.sdr.map(|sdr| format!(", SDR {:.2}", sdr)).unwrap_or_default();
+                format!("epoch {}, loss {:.4}{}", progress.epoch, progress.train_loss, sdr)
+            }
+            None if self.parse_health.state() == ParseHealth::Unrecognized => "progress not recognized".to_string(),
+            None => "no progress yet".to_string(),
+        };
+        let status = [self.status.clone(), self.watchdog.warning()].into_iter()
+            .flatten()
+            .map(|status| format!(", {}", status))
+            .collect::<String>();
+        format!("{}  {}  ({}{})", self.name, progress, self.owner.describe(), status)
+    }
+}
+
+/// What a read-only instance watches: the training runs that a live TUI
+/// instance owns, found through the history and each run's owner file.
+/// Nothing here writes to a run or to the history.
+#[derive(Default)]
+pub struct Dashboard {
+    pub runs: Vec<WatchedRun>,
+    last_refresh: Option<Instant>,
+}
+
+impl Dashboard {
+    pub fn discover(history: &mut RunHistory, settings: ParseHealthSettings, watchdog: WatchdogSettings) -> Self {
+        let mut runs = vec![];
+        for index in 0..history.entries().len() {
+            let entry = &history.entries()[index];
+            if entry.kind != RunKind::Training || entry.finished_at.is_some() {
+                continue;
+            }
+            let (name, started_at) = (entry.name.clone(), entry.started_at);
+            let Ok(dir) = history.locate(index) else {
+                continue;
+            };
+            if let Some(owner) = owner(&dir).filter(Owner::is_alive) {
+                runs.push(WatchedRun::new(name, dir, owner, started_at, settings, watchdog));
+            }
+        }
+        Dashboard { runs, last_refresh: None }
+    }
+
+    /// Reads what the logs got since the last time, at most every
+    /// `REFRESH_INTERVAL`; cheap to call on every tick.
+    pub fn refresh(&mut self, rules: &ParserRules) {
+        if self.last_refresh.is_some_and(|last| last.elapsed() < REFRESH_INTERVAL) {
+            return;
+        }
+        self.last_refresh = Some(Instant::now());
+        for run in &mut self.runs {
+            run.refresh(rules);
+        }
+    }
+
+    /// The end of the log of run `selected`, with receive times; longer
+    /// when the log is forward because progress isn't recognized.
+    pub fn log_lines(&self, selected: usize, display: TimeDisplay) -> Vec<String> {
+        let Some(run) = self.runs.get(selected) else {
+            return vec![];
+        };
+        let now = Utc::now();
+        let count = if self.log_forward(selected) { LOG_FORWARD_LINES } else { TAIL_LINES };
+        let skip = run.log.len().saturating_sub(count);
+        run.log.lines()
+            .skip(skip)
+            .map(|line| format!("{}  {}", run.log.time_label(line, display, now), line.text))
+            .collect()
+    }
+
+    /// Whether run `selected` has output but no recognized progress, so
+    /// its raw log should lead.
+    pub fn log_forward(&self, selected: usize) -> bool {
+        self.runs.get(selected).is_some_and(|run| run.parse_health.state() == ParseHealth::Unrecognized)
+    }
+
+    /// The notice and unmatched sample for run `selected`, if its progress
+    /// isn't recognized.
+    pub fn parse_notice(&self, selected: usize) -> Option<Vec<String>> {
+        self.runs.get(selected).and_then(|run| run.parse_health.notice())
+    }
+
+    pub fn run_dir(&self, selected: usize) -> Option<&Path> {
+        self.runs.get(selected).map(|run| run.dir.as_path())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{CommandSpec, ModelType};
+    use crate::owner::RunLock;
+    use crate::run_dir::RunDir;
+    use std::fs;
+    use std::io::Write;
+
+    #[test]
+    fn finds_owned_runs_and_follows_their_logs() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("model.yaml");
+        fs::write(&config, "audio: {}\n").unwrap();
+        let mut history = RunHistory::load(&dir.path().join("tui_history.json")).unwrap();
+        let owned = RunDir::create(&dir.path().join("owned"), &config.to_string_lossy(), None, ModelType::ScNet, CommandSpec::new("python")).unwrap();
+        let orphaned = RunDir::create(&dir.path().join("orphaned"), &config.to_string_lossy(), None, ModelType::ScNet, CommandSpec::new("python")).unwrap();
+        history.record(&owned);
+        history.record(&orphaned);
+        let _lock = RunLock::acquire(owned.path()).unwrap();
+        let mut log = fs::File::create(owned.log_path("stdout.log")).unwrap();
+        let at = chrono::Local::now() + chrono::TimeDelta::minutes(1);
+        writeln!(log, "[{}] Epoch 3", at.format("%Y-%m-%d %H:%M:%S")).unwrap();
+
+        let quiet = WatchdogSettings { enabled: true, quiet_minutes: 0, first_epoch_minutes: 0 };
+        let mut dashboard = Dashboard::discover(&mut history, ParseHealthSettings::default(), quiet);
+        assert_eq!(dashboard.runs.iter().map(|run| run.name.as_str()).collect::<Vec<_>>(), ["owned"]);
+        let rules = ParserRules::builtin();
+        dashboard.refresh(&rules);
+        writeln!(log, "Training loss: 0.0412").unwrap();
+        dashboard.last_refresh = None;
+        dashboard.refresh(&rules);
+
+        let lines = dashboard.log_lines(0, TimeDisplay::Absolute);
+        assert_eq!(lines[0], format!("{}  Epoch 3", at.format("%H:%M:%S")));
+        assert!(lines[1].ends_with("  Training loss: 0.0412"));
+        assert!(dashboard.runs[0].progress.is_some());
+        // Nothing new since: quiet past the (zero) threshold.
+        dashboard.last_refresh = None;
+        dashboard.refresh(&rules);
+        assert!(dashboard.runs[0].summary().contains(", possibly hung, quiet for 0 min)"), "{}", dashboard.runs[0].summary());
+        assert!(!dir.path().join("tui_history.json").exists(), "watching writes nothing");
+    }
+}
@@ -0,0 +1,90 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::manifest::is_audio_file;
+use crate::task::{OnCancel, Task, TaskContext};
+
+/// What a walk over a dataset folder found.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DatasetScan {
+    pub files: u64,
+    pub audio_files: u64,
+    pub bytes: u64,
+    /// Folders holding at least one audio file, i.e. tracks in the
+    /// `<track>/<stem>.wav` layout train.py reads.
+    pub track_dirs: u64,
+    /// False when the scan was cancelled before it saw everything.
+    pub complete: bool,
+}
+
+/// Walks `root` counting files, reporting files seen as progress (the total
+/// isn't known up front). On cancel it stops and returns what it has, with
+/// `complete` false.
+pub fn scan_dataset(root: &Path, ctx: &TaskContext) -> Result<DatasetScan> {
+    let mut scan = DatasetScan::default();
+    let mut pending: Vec<PathBuf> = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        if ctx.is_cancelled() {
+            return Ok(scan);
+        }
+        let mut has_audio = false;
+        for entry in fs::read_dir(&dir)
+            .with_context(|| format!("Failed to read {}", dir.display()))?
+        {
+            let entry = entry.context("Failed to read directory entry")?;
+            let path = entry.path();
+            let meta = entry.metadata().context("Failed to read file metadata")?;
+            if meta.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            scan.files += 1;
+            scan.bytes += meta.len();
+            if is_audio_file(&path) {
+                scan.audio_files += 1;
+                has_audio = true;
+            }
+            ctx.advance(1);
+        }
+        if has_audio {
+            scan.track_dirs += 1;
+        }
+    }
+    scan.complete = true;
+    Ok(scan)
+}
+
+/// Scans a dataset in the background; a cancelled scan keeps its partial
+/// counts.
+pub fn scan_in_background(root: &Path) -> Task<DatasetScan> {
+    let root = root.to_path_buf();
+    Task::spawn(&format!("Scanning {}", root.display()), OnCancel::KeepPartial, move |ctx| scan_dataset(&root, ctx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::{CancelToken, TaskOutcome};
+
+    #[test]
+    fn scans_tracks_and_keeps_partial_counts_on_cancel() {
+        let dir = tempfile::tempdir().unwrap();
+        for track in ["a", "b"] {
+            fs::create_dir_all(dir.path().join("train").join(track)).unwrap();
+            fs::write(dir.path().join("train").join(track).join("vocals.wav"), [0u8; 10]).unwrap();
+            fs::write(dir.path().join("train").join(track).join("notes.txt"), [0u8; 5]).unwrap();
+        }
+
+        let outcome = scan_in_background(dir.path()).join().unwrap();
+        assert_eq!(
+            outcome,
+            TaskOutcome::Completed(DatasetScan { files: 4, audio_files: 2, bytes: 30, track_dirs: 2, complete: true })
+        );
+
+        let token = CancelToken::new();
+        token.cancel();
+        let partial = scan_dataset(dir.path(), &TaskContext::new(token)).unwrap();
+        assert_eq!(partial, DatasetScan::default());
+    }
+}
@@ -0,0 +1,268 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::manifest::{ManifestStem, ManifestTrack, ResultsManifest};
+use crate::model::{DuplicatePolicy, InputAdjustment, InputNote};
+use crate::staging::scan_inputs;
+
+/// Bytes hashed from each end of an input.
+pub const HASH_WINDOW: u64 = 4 * 1024 * 1024;
+
+/// xxh3-128 of the file size plus its first and last `HASH_WINDOW` bytes.
+/// Stays fast on long recordings; two audio files that agree on size, start
+/// and end are the same file in practice.
+pub fn content_hash(path: &Path) -> Result<String> {
+    let mut file = File::open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let size = file.metadata().context("Failed to read file size")?.len();
+
+    let mut hasher = Xxh3::new();
+    hasher.update(&size.to_le_bytes());
+    let mut buffer = vec![];
+    (&mut file).take(HASH_WINDOW).read_to_end(&mut buffer)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    hasher.update(&buffer);
+
+    if size > HASH_WINDOW {
+        // Never hash a byte twice when the two windows overlap.
+        let tail = (size - HASH_WINDOW).max(HASH_WINDOW);
+        file.seek(SeekFrom::Start(tail)).context("Failed to seek in input")?;
+        buffer.clear();
+        file.take(HASH_WINDOW).read_to_end(&mut buffer)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        hasher.update(&buffer);
+    }
+    Ok(format!("{:032x}", hasher.digest128()))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Duplicate {
+    pub input: PathBuf,
+    /// Track whose content the input repeats: one already in the store_dir,
+    /// or an earlier input of the same batch.
+    pub of: String,
+}
+
+/// Content hashes of one batch of inputs, and which of them repeat content
+/// that was, or is about to be, separated.
+#[derive(Debug, Clone, Default)]
+pub struct DuplicateScan {
+    pub hashes: HashMap<PathBuf, String>,
+    pub duplicates: Vec<Duplicate>,
+}
+
+impl DuplicateScan {
+    pub fn hash_of(&self, input: &Path) -> Option<&String> {
+        self.hashes.get(input)
+    }
+
+    /// Notes for the inputs to hold back from inference.py under `policy`.
+    pub fn notes(&self, policy: DuplicatePolicy) -> Vec<InputNote> {
+        self.duplicates.iter()
+            .filter_map(|duplicate| {
+                let adjustment = match policy {
+                    DuplicatePolicy::Process => return None,
+                    DuplicatePolicy::Skip => InputAdjustment::Excluded {
+                        reason: format!("same content as {}", duplicate.of),
+                    },
+                    DuplicatePolicy::Link | DuplicatePolicy::Copy => {
+                        InputAdjustment::Reused { of: duplicate.of.clone() }
+                    }
+                };
+                Some(InputNote { input: duplicate.input.to_string_lossy().to_string(), adjustment })
+            })
+            .collect()
+    }
+}
+
+/// Hashes every input in `input_folder` and matches it against the tracks
+/// of `previous` (the store_dir's manifest, if it was made by the same
+/// model) and against earlier inputs of the batch. Inputs in `held_back`
+/// are neither checked nor count as the original of another input.
+pub fn find_duplicates(input_folder: &Path, store_dir: &Path, previous: Option<&ResultsManifest>, held_back: &[InputNote]) -> Result<DuplicateScan> {
+    let mut scan = DuplicateScan::default();
+    let mut batch: HashMap<String, String> = HashMap::new();
+    for input in scan_inputs(input_folder)? {
+        if held_back.iter().any(|note| Path::new(&note.input) == input.path) {
+            continue;
+        }
+        let hash = content_hash(&input.path)?;
+        let name = input.path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        let stored = previous.and_then(|manifest| manifest.find_hash(store_dir, &hash));
+        let of = match stored {
+            Some(track) => Some(track.name.clone()),
+            None => batch.get(&hash).cloned(),
+        };
+        match of {
+            Some(of) => scan.duplicates.push(Duplicate { input: input.path.clone(), of }),
+            None => {
+                batch.insert(hash.clone(), name);
+            }
+        }
+        scan.hashes.insert(input.path, hash);
+    }
+    Ok(scan)
+}
+
+/// Gives every input held back with `InputAdjustment::Reused` a folder of
+/// its own holding the stems of the track it repeats, and a manifest entry
+/// for them. Runs before the manifest is saved, so a crash part-way leaves
+/// at worst stems the manifest doesn't list, never entries without stems.
+pub fn reuse_outputs(store_dir: &Path, manifest: &mut ResultsManifest, notes: &[InputNote], scan: &DuplicateScan, policy: DuplicatePolicy) -> Result<()> {
+    for note in notes {
+        let InputAdjustment::Reused { of } = &note.adjustment else {
+            continue;
+        };
+        let input = Path::new(&note.input);
+        let name = input.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        if &name == of {
+            // Re-queued under the same name: its stems are already in place.
+            continue;
+        }
+        let Some(source) = manifest.tracks.iter().find(|track| &track.name == of && track.outputs_exist(store_dir)).cloned() else {
+            manifest.excluded.push(InputNote {
+                input: note.input.clone(),
+                adjustment: InputAdjustment::Excluded { reason: format!("outputs of {} are missing", of) },
+            });
+            continue;
+        };
+
+        fs::create_dir_all(store_dir.join(&name))
+            .context("Failed to create track output directory")?;
+        let mut stems = vec![];
+        for stem in &source.stems {
+            let from = store_dir.join(&stem.path);
+            let path = format!("{}/{}", name, from.file_name().unwrap_or_default().to_string_lossy());
+            reuse_file(&from, &store_dir.join(&path), policy)?;
+            stems.push(ManifestStem { name: stem.name.clone(), path, renamed_from: None });
+        }
+
+        manifest.tracks.retain(|track| track.name != name);
+        manifest.tracks.push(ManifestTrack {
+            name,
+            input_path: note.input.clone(),
+            stems,
+            adjustments: vec![note.adjustment.clone()],
+            content_hash: scan.hash_of(input).cloned(),
+        });
+    }
+    manifest.tracks.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(())
+}
+
+fn reuse_file(from: &Path, to: &Path, policy: DuplicatePolicy) -> Result<()> {
+    if to.exists() {
+        fs::remove_file(to)
+            .with_context(|| format!("Failed to replace {}", to.display()))?;
+    }
+    if policy == DuplicatePolicy::Link && fs::hard_link(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)
+        .with_context(|| format!("Failed to copy {}", from.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ModelAudio;
+    use crate::model::{ChannelPolicy, ModelType, SampleRatePolicy};
+    use crate::scratch::Scratch;
+    use crate::staging::Staging;
+
+    #[test]
+    fn hash_covers_size_and_both_ends() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, content: &[u8]| {
+            let path = dir.path().join(name);
+            fs::write(&path, content).unwrap();
+            content_hash(&path).unwrap()
+        };
+        let window = HASH_WINDOW as usize;
+        let mut long = vec![7u8; 2 * window + 10];
+        let original = write("a.wav", &long);
+        assert_eq!(write("renamed.wav", &long), original);
+
+        long[window + 5] = 8;
+        assert_eq!(write("middle.wav", &long), original, "only the ends are hashed");
+        long[2 * window + 9] = 8;
+        assert_ne!(write("tail.wav", &long), original);
+        assert_ne!(write("longer.wav", &vec![7u8; 2 * window + 11]), original);
+    }
+
+    #[test]
+    fn duplicates_reuse_existing_stems() {
+        let dir = tempfile::tempdir().unwrap();
+        let (inputs, store_dir) = (dir.path().join("in"), dir.path().join("out"));
+        fs::create_dir_all(&inputs).unwrap();
+        fs::create_dir_all(store_dir.join("song")).unwrap();
+        fs::write(inputs.join("song.wav"), "song").unwrap();
+        fs::write(inputs.join("song copy.wav"), "song").unwrap();
+        fs::write(inputs.join("new.wav"), "new").unwrap();
+        fs::write(inputs.join("new_again.wav"), "new").unwrap();
+        fs::write(store_dir.join("song/vocals.wav"), "vocals").unwrap();
+
+        let mut manifest = ResultsManifest {
+            model_type: Some(ModelType::ScNet),
+            start_checkpoint: Some("model.ckpt".into()),
+            tracks: vec![ManifestTrack {
+                name: "song".into(),
+                input_path: "elsewhere/song.wav".into(),
+                stems: vec![ManifestStem { name: "vocals".into(), path: "song/vocals.wav".into(), renamed_from: None }],
+                adjustments: vec![],
+                content_hash: Some(content_hash(&inputs.join("song.wav")).unwrap()),
+            }],
+            excluded: vec![],
+        };
+
+        let scan = find_duplicates(&inputs, &store_dir, Some(&manifest), &[]).unwrap();
+        let of: Vec<(String, &str)> = scan.duplicates.iter()
+            .map(|d| (d.input.file_name().unwrap().to_string_lossy().to_string(), d.of.as_str()))
+            .collect();
+        assert_eq!(of, [("new_again.wav".into(), "new"), ("song copy.wav".into(), "song"), ("song.wav".into(), "song")]);
+        assert!(scan.notes(DuplicatePolicy::Process).is_empty());
+
+        // Only "new.wav" is left for inference.py.
+        let notes = scan.notes(DuplicatePolicy::Link);
+        let scratch = Scratch::new(dir.path().join("scratch"), 24);
+        let model = ModelAudio { sample_rate: None, num_channels: None };
+        let staging = Staging::prepare(&scratch, "test", &inputs, model, SampleRatePolicy::Warn, ChannelPolicy::Convert, notes).unwrap();
+        for name in ["song.wav", "song copy.wav", "new_again.wav"] {
+            assert!(!staging.input_folder().join(name).exists(), "{}", name);
+        }
+        assert!(staging.input_folder().join("new.wav").exists());
+
+        // Pretend it ran, then link the repeats.
+        fs::create_dir_all(store_dir.join("new")).unwrap();
+        fs::write(store_dir.join("new/vocals.wav"), "new vocals").unwrap();
+        manifest.tracks.push(ManifestTrack {
+            name: "new".into(),
+            input_path: inputs.join("new.wav").to_string_lossy().to_string(),
+            stems: vec![ManifestStem { name: "vocals".into(), path: "new/vocals.wav".into(), renamed_from: None }],
+            adjustments: vec![],
+            content_hash: scan.hash_of(&inputs.join("new.wav")).cloned(),
+        });
+        reuse_outputs(&store_dir, &mut manifest, &staging.notes, &scan, DuplicatePolicy::Link).unwrap();
+        staging.cleanup().unwrap();
+
+        let names: Vec<&str> = manifest.tracks.iter().map(|track| track.name.as_str()).collect();
+        assert_eq!(names, ["new", "new_again", "song", "song copy"]);
+        assert_eq!(fs::read_to_string(store_dir.join("song copy/vocals.wav")).unwrap(), "vocals");
+        assert_eq!(fs::read_to_string(store_dir.join("new_again/vocals.wav")).unwrap(), "new vocals");
+        let copy = &manifest.tracks[3];
+        assert_eq!(copy.adjustments, [InputAdjustment::Reused { of: "song".into() }]);
+        assert_eq!(copy.content_hash, manifest.tracks[2].content_hash);
+
+        // The manifest is the index: a later run finds the copies too.
+        manifest.save(&store_dir).unwrap();
+        let reloaded = ResultsManifest::load(&store_dir).unwrap().unwrap();
+        assert!(reloaded.same_model(&ModelType::ScNet, "model.ckpt"));
+        assert_eq!(reloaded.find_hash(&store_dir, copy.content_hash.as_ref().unwrap()).unwrap().name, "song");
+        assert!(!store_dir.join("msst_results.json.partial").exists());
+    }
+}
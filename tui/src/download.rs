@@ -0,0 +1,293 @@
+use anyhow::{bail, Context, Result};
+use reqwest::header::RANGE;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc::{self, UnboundedSender};
+
+use crate::checksum;
+use crate::model::ModelType;
+use crate::pretrained::{registry, ModelBinding};
+use crate::task::TaskContext;
+
+/// Bytes of a download so far; `total` is `None` when the server doesn't
+/// say.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DownloadProgress {
+    pub done: u64,
+    pub total: Option<u64>,
+}
+
+/// A checkpoint to download, from the built-in registry or `downloads` in
+/// `tui_config.yaml`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DownloadEntry {
+    pub name: String,
+    pub model_type: ModelType,
+    /// URL of the model config, or its path relative to the project root
+    /// for configs the repo ships.
+    pub config: String,
+    pub weights_url: String,
+    /// Lowercase hex; the download is refused when it doesn't match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+impl DownloadEntry {
+    /// Where the weights go in `weights_dir`, named as in the URL.
+    pub fn weights_path(&self, weights_dir: &Path) -> PathBuf {
+        weights_dir.join(url_file_name(&self.weights_url))
+    }
+
+    /// Where the config is read from once downloaded.
+    pub fn config_path(&self, weights_dir: &Path) -> PathBuf {
+        if is_url(&self.config) {
+            weights_dir.join(url_file_name(&self.config))
+        } else {
+            PathBuf::from(&self.config)
+        }
+    }
+
+    /// The binding once both files are in place.
+    pub fn binding(&self, weights_dir: &Path) -> ModelBinding {
+        ModelBinding {
+            model_type: self.model_type.clone(),
+            checkpoint: self.weights_path(weights_dir).to_string_lossy().into_owned(),
+            config: self.config_path(weights_dir).to_string_lossy().into_owned(),
+            label: Some(self.name.clone()),
+        }
+    }
+
+    /// E.g. "BS RoFormer — viperx 1297 (downloaded, unverified)";
+    /// unverified when there is no `sha256` to check the weights against.
+    pub fn line(&self, weights_dir: &Path) -> String {
+        let weights = self.weights_path(weights_dir);
+        let mut state = vec![];
+        if weights.is_file() {
+            state.push("downloaded");
+        } else if weights.with_file_name(part_name(&weights)).is_file() {
+            state.push("partly downloaded");
+        }
+        if self.sha256.is_none() {
+            state.push("unverified");
+        }
+        let mut line = format!("{} — {}", self.model_type.name(), self.name);
+        if !state.is_empty() {
+            line.push_str(&format!(" ({})", state.join(", ")));
+        }
+        line
+    }
+}
+
+/// The registry's checkpoints, then the user's own.
+pub fn entries(user: &[DownloadEntry]) -> Vec<DownloadEntry> {
+    registry()
+        .into_iter()
+        .map(|variant| DownloadEntry {
+            name: variant.label.to_string(),
+            model_type: variant.model_type,
+            config: variant.config.to_string(),
+            weights_url: variant.weights_url.to_string(),
+            sha256: variant.sha256.map(str::to_string),
+        })
+        .chain(user.iter().cloned())
+        .collect()
+}
+
+fn is_url(text: &str) -> bool {
+    text.starts_with("https://") || text.starts_with("http://")
+}
+
+/// The last path segment, without the query.
+fn url_file_name(url: &str) -> &str {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    path.rsplit('/').find(|segment| !segment.is_empty()).unwrap_or("download")
+}
+
+fn part_name(dest: &Path) -> String {
+    format!("{}.part", dest.file_name().unwrap_or_default().to_string_lossy())
+}
+
+/// Downloads `url` to `<dest>.part` and returns that path; `install`
+/// moves it into place. A `.part` left by an earlier attempt is resumed
+/// with a range request where the server allows it, and started over where
+/// it doesn't.
+pub async fn download_checkpoint(url: &str, dest: &Path, progress_tx: UnboundedSender<DownloadProgress>) -> Result<PathBuf> {
+    let part = dest.with_file_name(part_name(dest));
+    if let Some(dir) = dest.parent() {
+        tokio::fs::create_dir_all(dir).await
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("mss_tui/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .context("Failed to set up the HTTP client")?;
+    let mut offset = std::fs::metadata(&part).map(|meta| meta.len()).unwrap_or(0);
+    let mut request = client.get(url);
+    if offset > 0 {
+        request = request.header(RANGE, format!("bytes={}-", offset));
+    }
+    let mut response = request.send().await.with_context(|| format!("Failed to download {}", url))?;
+    if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        // The file changed on the server, or the part is already whole.
+        response = client.get(url).send().await.with_context(|| format!("Failed to download {}", url))?;
+    }
+    if !response.status().is_success() {
+        bail!("Failed to download {}: HTTP {}", url, response.status());
+    }
+    if response.status() != StatusCode::PARTIAL_CONTENT {
+        offset = 0;
+    }
+
+    let mut file = if offset > 0 {
+        tokio::fs::OpenOptions::new().append(true).open(&part).await
+    } else {
+        tokio::fs::File::create(&part).await
+    }
+    .with_context(|| format!("Failed to write {}", part.display()))?;
+    let total = response.content_length().map(|len| len + offset);
+    let mut done = offset;
+    let _ = progress_tx.send(DownloadProgress { done, total });
+    while let Some(chunk) = response.chunk().await.with_context(|| format!("Download of {} broke off", url))? {
+        file.write_all(&chunk).await.with_context(|| format!("Failed to write {}", part.display()))?;
+        done += chunk.len() as u64;
+        let _ = progress_tx.send(DownloadProgress { done, total });
+    }
+    file.flush().await.with_context(|| format!("Failed to write {}", part.display()))?;
+    drop(file);
+    if let Some(total) = total
+        && done < total
+    {
+        bail!("Download of {} broke off at {} of {} bytes; downloading again resumes it", url, done, total);
+    }
+    Ok(part)
+}
+
+/// Moves a finished `part` of `url` to `dest` once it matches `sha256`.
+/// The hash is read back from disk as a task of its own, so a resumed part
+/// is checked whole and a multi-gigabyte hash shows progress and can be
+/// cancelled, keeping the part. A mismatch removes it.
+pub fn install(url: &str, part: &Path, dest: &Path, sha256: Option<&str>, ctx: &TaskContext) -> Result<()> {
+    if let Some(expected) = sha256 {
+        ctx.set_done(0);
+        let hash = checksum::sha256_file(part, ctx)?;
+        if !expected.eq_ignore_ascii_case(&hash) {
+            let _ = std::fs::remove_file(part);
+            bail!("{} has SHA-256 {}, not the expected {}; the download was removed", url, hash, expected);
+        }
+    }
+    std::fs::rename(part, dest).with_context(|| format!("Failed to move the download to {}", dest.display()))
+}
+
+/// Downloads what `entry` still lacks into `weights_dir` and returns its
+/// binding, reporting bytes as task progress. Cancelling keeps the
+/// `.part` file for the next attempt to resume.
+pub fn fetch(entry: &DownloadEntry, weights_dir: &Path, ctx: &TaskContext) -> Result<ModelBinding> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .context("Failed to start the download runtime")?;
+    let mut downloads = vec![];
+    if is_url(&entry.config) {
+        downloads.push((entry.config.as_str(), entry.config_path(weights_dir), None));
+    }
+    downloads.push((entry.weights_url.as_str(), entry.weights_path(weights_dir), entry.sha256.as_deref()));
+    runtime.block_on(async {
+        for (url, dest, sha256) in downloads {
+            if dest.is_file() {
+                continue;
+            }
+            let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+            let download = download_checkpoint(url, &dest, progress_tx);
+            tokio::pin!(download);
+            let part = loop {
+                ctx.check()?;
+                tokio::select! {
+                    result = &mut download => break result?,
+                    Some(progress) = progress_rx.recv() => {
+                        if let Some(total) = progress.total {
+                            ctx.set_total(total);
+                        }
+                        ctx.set_done(progress.done);
+                    }
+                    _ = tokio::time::sleep(Duration::from_millis(100)) => {}
+                }
+            };
+            install(url, &part, &dest, sha256, ctx)?;
+        }
+        Ok(entry.binding(weights_dir))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    /// Serves `body` to `requests` requests, honouring `Range: bytes=N-`.
+    fn serve(body: &'static [u8], requests: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/weights/model.ckpt?download=1", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut from = None;
+                for line in BufReader::new(&stream).lines() {
+                    let line = line.unwrap();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some(range) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+                        from = range.trim_end_matches('-').parse::<usize>().ok();
+                    }
+                }
+                let (status, rest) = match from {
+                    Some(from) => (format!("206 Partial Content\r\nContent-Range: bytes {}-{}/{}", from, body.len() - 1, body.len()), &body[from..]),
+                    None => ("200 OK".to_string(), body),
+                };
+                write!(stream, "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", status, rest.len()).unwrap();
+                stream.write_all(rest).unwrap();
+            }
+        });
+        url
+    }
+
+    fn download(url: &str, dest: &Path, sha256: Option<&str>) -> (Result<()>, Vec<DownloadProgress>) {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let result = runtime.block_on(download_checkpoint(url, dest, tx))
+            .and_then(|part| install(url, &part, dest, sha256, &TaskContext::default()));
+        let mut progress = vec![];
+        while let Ok(update) = rx.try_recv() {
+            progress.push(update);
+        }
+        (result, progress)
+    }
+
+    #[test]
+    fn downloads_resume_from_the_part_file_and_are_checked() {
+        const BODY: &[u8] = b"abcdefghij";
+        const HASH: &str = "72399361da6a7754fec986dca5b7cbaf1c810a28ded4abaf56b2106d06cb78b0";
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("pretrained").join("model.ckpt");
+        let url = serve(BODY, 2);
+        assert_eq!(url_file_name(&url), "model.ckpt");
+
+        std::fs::create_dir_all(dest.parent().unwrap()).unwrap();
+        std::fs::write(dest.with_file_name("model.ckpt.part"), &BODY[..4]).unwrap();
+        let (result, progress) = download(&url, &dest, Some(HASH));
+        result.unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), BODY);
+        assert!(!dest.with_file_name("model.ckpt.part").exists());
+        assert_eq!(progress.first(), Some(&DownloadProgress { done: 4, total: Some(10) }));
+        assert_eq!(progress.last(), Some(&DownloadProgress { done: 10, total: Some(10) }));
+
+        let other = dir.path().join("other.ckpt");
+        let (result, _) = download(&url, &other, Some("00"));
+        assert!(format!("{:#}", result.unwrap_err()).contains("not the expected 00"));
+        assert!(!other.exists() && !dir.path().join("other.ckpt.part").exists());
+    }
+}
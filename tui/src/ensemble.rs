@@ -0,0 +1,247 @@
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use tokio::io::BufReader;
+
+use crate::log_buffer::LogBuffer;
+use crate::model::{CommandSpec, EnsembleConfig};
+use crate::process::{ProcessState, StderrTail};
+use crate::runner::RunnerEnv;
+use crate::step_progress::Segments;
+use crate::training::piped_output;
+
+/// Ensemble entry point of an MSST checkout.
+pub const ENSEMBLE_SCRIPT: &str = "ensemble.py";
+
+/// What ensemble.py's `--type` takes: how the weighted stems are combined,
+/// sample by sample or per frequency bin.
+pub const ENSEMBLE_TYPES: [&str; 8] = [
+    "avg_wave",
+    "median_wave",
+    "min_wave",
+    "max_wave",
+    "avg_fft",
+    "median_fft",
+    "min_fft",
+    "max_fft",
+];
+
+/// The type after `current` in `ENSEMBLE_TYPES`, wrapping around.
+pub fn next_type(current: &str) -> &'static str {
+    let index = ENSEMBLE_TYPES.iter().position(|name| *name == current).map_or(0, |index| index + 1);
+    ENSEMBLE_TYPES[index % ENSEMBLE_TYPES.len()]
+}
+
+/// Weights typed as e.g. "1 1.5 2" or "1, 1.5, 2".
+pub fn parse_weights(text: &str) -> Result<Vec<f64>> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let weight: f64 = word.parse().with_context(|| format!("Not a weight: {}", word))?;
+            if !weight.is_finite() || weight <= 0.0 {
+                bail!("Weights must be positive, got {}", word);
+            }
+            Ok(weight)
+        })
+        .collect()
+}
+
+/// Why ensemble.py can't run on `config`, checked before it is spawned.
+pub fn validate(config: &EnsembleConfig) -> Result<()> {
+    if config.files.len() < 2 {
+        bail!("Add at least two files to combine");
+    }
+    if config.weights.len() != config.files.len() {
+        bail!("{} weights for {} files; give one weight per file", config.weights.len(), config.files.len());
+    }
+    if let Some(weight) = config.weights.iter().find(|weight| !weight.is_finite() || **weight <= 0.0) {
+        bail!("Weights must be positive, got {}", weight);
+    }
+    if let Some(file) = config.files.iter().find(|file| !Path::new(file).is_file()) {
+        bail!("Input not found: {}", file);
+    }
+    if config.output_path.trim().is_empty() {
+        bail!("Set the output path first");
+    }
+    if !ENSEMBLE_TYPES.contains(&config.ensemble_type.as_str()) {
+        bail!("Unknown ensemble type {}", config.ensemble_type);
+    }
+    Ok(())
+}
+
+/// Invocation of the ensemble `script` by `python`.
+pub fn ensemble_command(config: &EnsembleConfig, python: &str, script: &str) -> CommandSpec {
+    let mut cmd = CommandSpec::new(python);
+    cmd.arg(script).arg("--files");
+    for file in &config.files {
+        cmd.arg(file);
+    }
+    cmd.arg("--weights");
+    for weight in &config.weights {
+        cmd.arg(weight.to_string());
+    }
+    cmd.arg("--type").arg(&config.ensemble_type).arg("--output").arg(&config.output_path);
+    cmd
+}
+
+pub struct EnsembleManager {
+    process: Option<tokio::process::Child>,
+    state: ProcessState,
+    script: String,
+    runner: RunnerEnv,
+    /// Everything ensemble.py printed, stderr lines marked.
+    log: Arc<Mutex<LogBuffer>>,
+}
+
+impl Default for EnsembleManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EnsembleManager {
+    pub fn new() -> Self {
+        EnsembleManager {
+            process: None,
+            state: ProcessState::NotStarted,
+            script: ENSEMBLE_SCRIPT.to_string(),
+            runner: RunnerEnv::default(),
+            log: Arc::new(Mutex::new(LogBuffer::new())),
+        }
+    }
+
+    pub fn log(&self) -> Arc<Mutex<LogBuffer>> {
+        self.log.clone()
+    }
+
+    pub fn set_runner(&mut self, runner: RunnerEnv) {
+        self.runner = runner;
+    }
+
+    /// Runs ensemble.py to the end, its output going to the log as it
+    /// comes, and returns the combined file.
+    pub async fn run_ensemble(&mut self, config: &EnsembleConfig) -> Result<String> {
+        let config = &config.in_root(self.runner.root());
+        validate(config)?;
+        let python = self.runner.python()?;
+        let command = self.runner.in_project(ensemble_command(config, &python, &self.runner.script(&self.script)));
+        self.log.lock().unwrap().push(&command.display());
+        let spawned = command.command()
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn();
+        let mut child = match spawned {
+            Ok(child) => child,
+            Err(e) => {
+                self.state = ProcessState::Failed(e.to_string());
+                return Err(e).context("Failed to spawn ensemble process");
+            }
+        };
+        self.state = ProcessState::Running;
+        let (stdout, stderr) = piped_output(&mut child, "ensemble")?;
+        self.process = Some(child);
+
+        let stderr_log = self.log.clone();
+        let stderr_task = tokio::spawn(async move {
+            let mut lines = Segments::new(BufReader::new(stderr));
+            let mut tail = StderrTail::new();
+            while let Some(line) = lines.next_segment().await.context("Failed to read ensemble stderr")? {
+                if !line.trim().is_empty() {
+                    stderr_log.lock().unwrap().push_error(&line);
+                    tail.push(line);
+                }
+            }
+            Ok::<_, anyhow::Error>(tail)
+        });
+        let mut lines = Segments::new(BufReader::new(stdout));
+        while let Some(line) = lines.next_segment().await.context("Failed to read ensemble stdout")? {
+            if !line.trim().is_empty() {
+                self.log.lock().unwrap().push(&line);
+            }
+        }
+
+        let status = match self.process.as_mut() {
+            Some(child) => match child.wait().await {
+                Ok(status) => status,
+                Err(e) => {
+                    self.state = ProcessState::Failed(e.to_string());
+                    return Err(e).context("Failed to wait for ensemble process");
+                }
+            },
+            None => bail!("Ensemble was stopped"),
+        };
+        self.state = ProcessState::Exited(status);
+        self.process = None;
+        let tail = stderr_task.await.context("stderr task failed")??;
+        if !status.success() {
+            let reason = tail.exception().unwrap_or_else(|| tail.text().lines().last().unwrap_or_default().to_string());
+            bail!("{} exited with code {}: {}", self.script, status.code().unwrap_or(-1), reason);
+        }
+        Ok(config.output_path.clone())
+    }
+
+    pub async fn stop_ensemble(&mut self) -> Result<()> {
+        if let Some(mut child) = self.process.take() {
+            child.kill().await.context("Failed to stop ensemble process")?;
+            self.state.update(Some(&mut child));
+        }
+        Ok(())
+    }
+
+    pub fn status(&mut self) -> &ProcessState {
+        self.state.update(self.process.as_mut());
+        &self.state
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.process.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_command_pairs_each_file_with_its_weight() {
+        let config = EnsembleConfig {
+            files: vec!["scnet/song_vocals.wav".to_string(), "bs roformer/song_vocals.wav".to_string()],
+            weights: vec![1.0, 1.5],
+            output_path: "ensemble/song_vocals.wav".to_string(),
+            ensemble_type: "median_fft".to_string(),
+        };
+        assert_eq!(
+            ensemble_command(&config, "python", "ensemble.py").display(),
+            "python ensemble.py --files scnet/song_vocals.wav 'bs roformer/song_vocals.wav' --weights 1 1.5 --type median_fft --output ensemble/song_vocals.wav"
+        );
+        assert_eq!(parse_weights("1, 1.5 2").unwrap(), [1.0, 1.5, 2.0]);
+        assert!(parse_weights("1 -2").is_err());
+        assert_eq!(next_type("avg_wave"), "median_wave");
+        assert_eq!(next_type("max_fft"), "avg_wave");
+    }
+
+    #[test]
+    fn mismatched_weights_and_missing_inputs_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let files: Vec<String> = ["a.wav", "b.wav"].iter()
+            .map(|name| {
+                let path = dir.path().join(name);
+                std::fs::write(&path, b"RIFF").unwrap();
+                path.to_string_lossy().into_owned()
+            })
+            .collect();
+        let mut config = EnsembleConfig { files, weights: vec![1.0, 1.0], ..EnsembleConfig::default() };
+        assert!(validate(&config).is_ok());
+
+        config.weights.pop();
+        assert_eq!(validate(&config).unwrap_err().to_string(), "1 weights for 2 files; give one weight per file");
+        config.weights.push(1.0);
+        config.files[1] = dir.path().join("c.wav").to_string_lossy().into_owned();
+        assert!(validate(&config).unwrap_err().to_string().starts_with("Input not found: "));
+        config.files.truncate(1);
+        config.weights.truncate(1);
+        assert_eq!(validate(&config).unwrap_err().to_string(), "Add at least two files to combine");
+    }
+}
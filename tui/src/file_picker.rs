@@ -0,0 +1,64 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Extensions of the weights inference.py loads.
+pub const CHECKPOINT_EXTENSIONS: [&str; 3] = ["ckpt", "th", "pt"];
+
+/// How deep below each root checkpoints are looked for: results_path holds
+/// one folder per run, with the weights at most a couple of levels down.
+const CHECKPOINT_DEPTH: usize = 4;
+
+/// Checkpoints under `roots`, newest first. Roots that don't exist are
+/// skipped, as are folders that can't be read.
+pub fn find_checkpoints(roots: &[PathBuf]) -> Vec<PathBuf> {
+    let mut found = vec![];
+    for root in roots {
+        collect_checkpoints(root, CHECKPOINT_DEPTH, &mut found);
+    }
+    let mut found: Vec<(SystemTime, PathBuf)> = found.into_iter()
+        .map(|path| (fs::metadata(&path).and_then(|meta| meta.modified()).unwrap_or(SystemTime::UNIX_EPOCH), path))
+        .collect();
+    found.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    found.dedup_by(|a, b| a.1 == b.1);
+    found.into_iter().map(|(_, path)| path).collect()
+}
+
+fn collect_checkpoints(dir: &Path, depth: usize, found: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if depth > 0 {
+                collect_checkpoints(&path, depth - 1, found);
+            }
+        } else if path.extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| CHECKPOINT_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+        {
+            found.push(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_checkpoints_newest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let run = dir.path().join("results/2026-10-01_scnet");
+        fs::create_dir_all(run.join("logs")).unwrap();
+        fs::write(run.join("model_scnet_ep_3_sdr_8.1.ckpt"), "").unwrap();
+        fs::write(run.join("logs/stdout.log"), "").unwrap();
+        fs::write(dir.path().join("results/config.yaml"), "").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        fs::write(run.join("last_scnet.PT"), "").unwrap();
+
+        let found = find_checkpoints(&[dir.path().join("results"), dir.path().join("missing")]);
+        assert_eq!(found, [run.join("last_scnet.PT"), run.join("model_scnet_ep_3_sdr_8.1.ckpt")]);
+    }
+}
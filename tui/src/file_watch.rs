@@ -0,0 +1,166 @@
+use anyhow::{Context, Result};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// How long the watched folders have to stay quiet after a change before
+/// the lists are refreshed; a checkpoint being written fires many events.
+pub const QUIET_PERIOD: Duration = Duration::from_millis(750);
+
+/// Which watched folder a change was in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Watched {
+    Configs,
+    Results,
+}
+
+/// Collects changes until none have come for `quiet`, so a burst of them
+/// is handled once.
+#[derive(Debug)]
+pub struct Debounce {
+    quiet: Duration,
+    pending: Vec<Watched>,
+    last: Option<Instant>,
+}
+
+impl Debounce {
+    pub fn new(quiet: Duration) -> Self {
+        Debounce { quiet, pending: vec![], last: None }
+    }
+
+    pub fn note(&mut self, what: Watched, at: Instant) {
+        if !self.pending.contains(&what) {
+            self.pending.push(what);
+        }
+        self.last = Some(at);
+    }
+
+    /// The folders that changed, once they have been quiet long enough.
+    pub fn ready(&mut self, now: Instant) -> Vec<Watched> {
+        match self.last {
+            Some(last) if now.duration_since(last) >= self.quiet => {
+                self.last = None;
+                std::mem::take(&mut self.pending)
+            }
+            _ => vec![],
+        }
+    }
+}
+
+/// Watches the configs folder and the results folder for files being
+/// created, changed or removed, e.g. a checkpoint copied in from another
+/// terminal.
+pub struct FileWatcher {
+    watcher: RecommendedWatcher,
+    events: mpsc::Receiver<notify::Result<Event>>,
+    /// Each folder as asked for and as watched, absolute.
+    roots: Vec<(Watched, PathBuf, PathBuf)>,
+    debounce: Debounce,
+}
+
+impl FileWatcher {
+    pub fn new() -> Result<Self> {
+        let (tx, events) = mpsc::channel();
+        let watcher = notify::recommended_watcher(tx).context("Failed to start the file watcher")?;
+        Ok(FileWatcher { watcher, events, roots: vec![], debounce: Debounce::new(QUIET_PERIOD) })
+    }
+
+    /// Watches `path` and everything under it for `what`, in place of the
+    /// folder watched for it before. A folder that doesn't exist yet isn't
+    /// watched; asking again once it does starts watching it.
+    pub fn watch(&mut self, what: Watched, path: &Path) -> Result<()> {
+        if self.roots.iter().any(|(kind, asked, _)| *kind == what && asked == path) {
+            return Ok(());
+        }
+        let Ok(absolute) = path.canonicalize() else {
+            return Ok(());
+        };
+        if let Some(index) = self.roots.iter().position(|(kind, _, _)| *kind == what) {
+            let (_, _, old) = self.roots.remove(index);
+            let _ = self.watcher.unwatch(&old);
+        }
+        // Kept when watching fails too, so the failure is reported once.
+        let watched = self.watcher.watch(&absolute, RecursiveMode::Recursive)
+            .with_context(|| format!("Failed to watch {}", absolute.display()));
+        self.roots.push((what, path.to_path_buf(), absolute));
+        watched
+    }
+
+    /// The folders that changed and have since been quiet for
+    /// `QUIET_PERIOD`, and the watcher's errors since the last poll.
+    pub fn poll(&mut self, now: Instant) -> (Vec<Watched>, Vec<String>) {
+        let mut errors = vec![];
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                Ok(event) => {
+                    for what in changed(&self.roots, &event) {
+                        self.debounce.note(what, now);
+                    }
+                }
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+        (self.debounce.ready(now), errors)
+    }
+}
+
+/// The watched folders `event` changed something in; reads don't count.
+fn changed(roots: &[(Watched, PathBuf, PathBuf)], event: &Event) -> Vec<Watched> {
+    if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) | EventKind::Any) {
+        return vec![];
+    }
+    let mut changed = vec![];
+    for (what, _, root) in roots {
+        if !changed.contains(what) && event.paths.iter().any(|path| path.starts_with(root)) {
+            changed.push(*what);
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, CreateKind};
+
+    #[test]
+    fn a_burst_of_changes_is_handled_once_it_goes_quiet() {
+        let start = Instant::now();
+        let mut debounce = Debounce::new(QUIET_PERIOD);
+        debounce.note(Watched::Results, start);
+        debounce.note(Watched::Results, start + Duration::from_millis(500));
+        debounce.note(Watched::Configs, start + Duration::from_millis(600));
+        assert!(debounce.ready(start + Duration::from_millis(1000)).is_empty());
+        assert_eq!(debounce.ready(start + Duration::from_millis(1400)), [Watched::Results, Watched::Configs]);
+        assert!(debounce.ready(start + Duration::from_secs(5)).is_empty());
+
+        let roots = vec![
+            (Watched::Configs, PathBuf::from("configs"), PathBuf::from("/srv/msst/configs")),
+            (Watched::Results, PathBuf::from("results"), PathBuf::from("/srv/msst/results")),
+        ];
+        let created = Event::new(EventKind::Create(CreateKind::File)).add_path(PathBuf::from("/srv/msst/results/run-1/model_ep_3.ckpt"));
+        assert_eq!(changed(&roots, &created), [Watched::Results]);
+        let read = Event::new(EventKind::Access(AccessKind::Any)).add_path(PathBuf::from("/srv/msst/configs/config_scnet.yaml"));
+        assert!(changed(&roots, &read).is_empty());
+    }
+
+    #[test]
+    fn a_new_file_in_a_watched_folder_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut watcher = FileWatcher::new().unwrap();
+        watcher.watch(Watched::Configs, dir.path()).unwrap();
+        std::fs::write(dir.path().join("config_scnet.yaml"), "training: {}\n").unwrap();
+
+        let waited = Instant::now();
+        let mut noted = false;
+        while !noted && waited.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(50));
+            watcher.poll(Instant::now());
+            noted = watcher.debounce.last.is_some();
+        }
+        assert!(noted, "no event for the new file");
+        let (changed, errors) = watcher.poll(Instant::now() + QUIET_PERIOD);
+        assert_eq!((changed, errors), (vec![Watched::Configs], vec![]));
+    }
+}
@@ -0,0 +1,227 @@
+use std::time::Duration;
+use tokio::sync::watch;
+
+use crate::gpu_check::nvidia_smi;
+
+/// How often the monitor asks `nvidia-smi`.
+pub const GPU_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Free memory under which a picked GPU gets a warning, unless
+/// `gpu_min_free_mib` says otherwise.
+pub const DEFAULT_MIN_FREE_MIB: u64 = 2048;
+
+const QUERY: &[&str] = &["--query-gpu=index,name,memory.used,memory.total,utilization.gpu", "--format=csv,noheader,nounits"];
+
+/// One device as `nvidia-smi --query-gpu` reports it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpuStats {
+    pub index: usize,
+    pub name: String,
+    pub memory_used_mib: u64,
+    pub memory_total_mib: u64,
+    /// Percent; `None` where the driver says "[N/A]".
+    pub utilization: Option<f64>,
+}
+
+impl GpuStats {
+    pub fn memory_fraction(&self) -> f64 {
+        if self.memory_total_mib == 0 {
+            return 0.0;
+        }
+        (self.memory_used_mib as f64 / self.memory_total_mib as f64).clamp(0.0, 1.0)
+    }
+
+    pub fn memory_free_mib(&self) -> u64 {
+        self.memory_total_mib.saturating_sub(self.memory_used_mib)
+    }
+
+    /// E.g. "GPU 0 NVIDIA GeForce RTX 4090: 21.3/24.0 GB, util 97%".
+    pub fn describe(&self) -> String {
+        let utilization = self.utilization.map_or("n/a".to_string(), |value| format!("{:.0}%", value));
+        format!(
+            "GPU {} {}: {:.1}/{:.1} GB, util {}",
+            self.index,
+            self.name,
+            self.memory_used_mib as f64 / 1024.0,
+            self.memory_total_mib as f64 / 1024.0,
+            utilization,
+        )
+    }
+}
+
+/// The monitor's latest look at the GPUs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum GpuReading {
+    /// Nothing asked yet.
+    #[default]
+    Pending,
+    /// No working `nvidia-smi`.
+    Unavailable,
+    Devices(Vec<GpuStats>),
+}
+
+impl GpuReading {
+    /// Memory used on all devices in GB, and their mean utilization, in
+    /// the units of `TrainingProgress`.
+    pub fn totals(&self) -> (Option<f64>, Option<f64>) {
+        let GpuReading::Devices(devices) = self else {
+            return (None, None);
+        };
+        if devices.is_empty() {
+            return (None, None);
+        }
+        let memory = devices.iter().map(|gpu| gpu.memory_used_mib).sum::<u64>() as f64 / 1024.0;
+        let utilization: Vec<f64> = devices.iter().filter_map(|gpu| gpu.utilization).collect();
+        let mean = (!utilization.is_empty()).then(|| utilization.iter().sum::<f64>() / utilization.len() as f64);
+        (Some(memory), mean)
+    }
+}
+
+/// `index, name, memory.used, memory.total, utilization.gpu` lines.
+/// Malformed lines are skipped; names may contain commas.
+pub fn parse_gpu_stats(output: &str) -> Vec<GpuStats> {
+    output.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [index, name @ .., used, total, utilization] = fields.as_slice() else {
+                return None;
+            };
+            if name.is_empty() {
+                return None;
+            }
+            Some(GpuStats {
+                index: index.parse().ok()?,
+                name: name.join(", "),
+                memory_used_mib: used.parse().ok()?,
+                memory_total_mib: total.parse().ok()?,
+                utilization: utilization.parse().ok(),
+            })
+        })
+        .collect()
+}
+
+/// A GPU that can be picked for `device_ids`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpuDevice {
+    pub index: usize,
+    pub name: String,
+    /// `None` when only `CUDA_VISIBLE_DEVICES` names the device.
+    pub free_mib: Option<u64>,
+}
+
+impl GpuDevice {
+    /// E.g. "GPU 1 NVIDIA A100-SXM4-80GB, 80.0 GB free".
+    pub fn describe(&self) -> String {
+        match self.free_mib {
+            Some(free) => format!("GPU {} {}, {:.1} GB free", self.index, self.name, free as f64 / 1024.0),
+            None => format!("GPU {} {}", self.index, self.name),
+        }
+    }
+
+    pub fn low_memory(&self, min_free_mib: u64) -> bool {
+        self.free_mib.is_some_and(|free| free < min_free_mib)
+    }
+}
+
+/// The GPUs `nvidia-smi` lists, or without it the ones
+/// `CUDA_VISIBLE_DEVICES` names, numbered as torch numbers them. Empty when
+/// neither finds one.
+pub fn list_devices() -> Vec<GpuDevice> {
+    devices_from(nvidia_smi(QUERY).as_deref(), std::env::var("CUDA_VISIBLE_DEVICES").ok().as_deref())
+}
+
+fn devices_from(nvidia_smi: Option<&str>, cuda_visible_devices: Option<&str>) -> Vec<GpuDevice> {
+    if let Some(output) = nvidia_smi {
+        return parse_gpu_stats(output)
+            .into_iter()
+            .map(|gpu| GpuDevice { index: gpu.index, free_mib: Some(gpu.memory_free_mib()), name: gpu.name })
+            .collect();
+    }
+    // torch stops at the first invalid entry, e.g. "-1" to hide them all.
+    cuda_visible_devices.unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .take_while(|entry| !entry.is_empty() && !entry.starts_with('-'))
+        .enumerate()
+        .map(|(index, entry)| GpuDevice { index, name: format!("(CUDA_VISIBLE_DEVICES {})", entry), free_mib: None })
+        .collect()
+}
+
+fn read_gpus() -> GpuReading {
+    match nvidia_smi(QUERY) {
+        Some(output) => GpuReading::Devices(parse_gpu_stats(&output)),
+        None => GpuReading::Unavailable,
+    }
+}
+
+/// Polls `nvidia-smi` on a thread of its own and publishes each reading.
+/// The thread stops once the monitor is dropped.
+#[derive(Debug)]
+pub struct GpuMonitor {
+    readings: watch::Receiver<GpuReading>,
+}
+
+impl GpuMonitor {
+    pub fn start(interval: Duration) -> Self {
+        let (tx, readings) = watch::channel(GpuReading::Pending);
+        std::thread::spawn(move || {
+            while tx.send(read_gpus()).is_ok() {
+                std::thread::sleep(interval);
+            }
+        });
+        GpuMonitor { readings }
+    }
+
+    pub fn latest(&self) -> GpuReading {
+        self.readings.borrow().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_and_several_gpus_are_parsed() {
+        let single = "0, NVIDIA GeForce RTX 4090, 21843, 24564, 97\n";
+        assert_eq!(parse_gpu_stats(single), [GpuStats {
+            index: 0,
+            name: "NVIDIA GeForce RTX 4090".to_string(),
+            memory_used_mib: 21843,
+            memory_total_mib: 24564,
+            utilization: Some(97.0),
+        }]);
+        assert_eq!(parse_gpu_stats(single)[0].describe(), "GPU 0 NVIDIA GeForce RTX 4090: 21.3/24.0 GB, util 97%");
+
+        let multi = "0, NVIDIA A100-SXM4-80GB, 40960, 81920, 100\n\
+                     1, NVIDIA A100-SXM4-80GB, 0, 81920, 0\n\
+                     2, Tesla K80, 11, 11441, [N/A]\n\
+                     No devices were found\n";
+        let gpus = parse_gpu_stats(multi);
+        assert_eq!(gpus.iter().map(|gpu| gpu.index).collect::<Vec<_>>(), [0, 1, 2]);
+        assert_eq!(gpus[0].memory_fraction(), 0.5);
+        assert_eq!(gpus[2].utilization, None);
+        assert_eq!(parse_gpu_stats("0, Odd, Name, 512, 1024, 5")[0].name, "Odd, Name");
+
+        assert_eq!(GpuReading::Devices(gpus).totals(), (Some(40.0 + 11.0 / 1024.0), Some(50.0)));
+        assert_eq!(GpuReading::Unavailable.totals(), (None, None));
+    }
+
+    #[test]
+    fn devices_come_from_nvidia_smi_or_cuda_visible_devices() {
+        let devices = devices_from(Some("0, NVIDIA A100-SXM4-80GB, 80896, 81920, 100\n1, NVIDIA A100-SXM4-80GB, 0, 81920, 0\n"), Some("3"));
+        assert_eq!(devices.iter().map(GpuDevice::describe).collect::<Vec<_>>(), [
+            "GPU 0 NVIDIA A100-SXM4-80GB, 1.0 GB free",
+            "GPU 1 NVIDIA A100-SXM4-80GB, 80.0 GB free",
+        ]);
+        assert!(devices[0].low_memory(DEFAULT_MIN_FREE_MIB) && !devices[1].low_memory(DEFAULT_MIN_FREE_MIB));
+
+        let visible = devices_from(None, Some("2, GPU-5f3c,-1,4"));
+        assert_eq!(visible.iter().map(GpuDevice::describe).collect::<Vec<_>>(), [
+            "GPU 0 (CUDA_VISIBLE_DEVICES 2)",
+            "GPU 1 (CUDA_VISIBLE_DEVICES GPU-5f3c)",
+        ]);
+        assert!(!visible[0].low_memory(DEFAULT_MIN_FREE_MIB));
+        assert!(devices_from(None, Some("")).is_empty() && devices_from(None, None).is_empty());
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Stdio;
+
+use crate::project::{INFERENCE_SCRIPTS, TRAIN_SCRIPTS, VALIDATION_SCRIPTS};
+
+/// A compute process as `nvidia-smi --query-compute-apps` lists it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpuProcess {
+    pub device: usize,
+    pub pid: u32,
+    pub used_mib: u64,
+}
+
+/// An MSST process already on a GPU a new run wants.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpuConflict {
+    pub device: usize,
+    pub pid: u32,
+    pub used_mib: u64,
+    /// `train.py` and the like; `None` for a TUI job whose command line
+    /// couldn't be read.
+    pub script: Option<String>,
+}
+
+impl GpuConflict {
+    /// E.g. "GPU 0 is already running train.py (PID 12345, 21.3 GB used)".
+    pub fn message(&self) -> String {
+        format!(
+            "GPU {} is already running {} (PID {}, {:.1} GB used)",
+            self.device,
+            self.script.as_deref().unwrap_or("a TUI job"),
+            self.pid,
+            self.used_mib as f64 / 1024.0
+        )
+    }
+}
+
+/// The question to ask before launching anyway, or `None` when the devices
+/// are free of MSST processes.
+pub fn launch_prompt(conflicts: &[GpuConflict]) -> Option<String> {
+    if conflicts.is_empty() {
+        return None;
+    }
+    let messages: Vec<String> = conflicts.iter().map(GpuConflict::message).collect();
+    Some(format!("{} — start anyway / choose another device / cancel", messages.join("; ")))
+}
+
+/// The GPUs a run asks for: its `device_ids`, or GPU 0 like train.py.
+pub fn requested_devices(device_ids: Option<&[usize]>) -> Vec<usize> {
+    match device_ids {
+        Some(ids) if !ids.is_empty() => ids.to_vec(),
+        _ => vec![0],
+    }
+}
+
+/// `index, uuid` lines from `nvidia-smi --query-gpu=index,uuid`.
+pub fn parse_gpu_uuids(output: &str) -> HashMap<String, usize> {
+    output.lines()
+        .filter_map(|line| {
+            let (index, uuid) = line.split_once(',')?;
+            Some((uuid.trim().to_string(), index.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// `gpu_uuid, pid, used_memory` lines from `nvidia-smi
+/// --query-compute-apps=gpu_uuid,pid,used_memory --format=csv,noheader,nounits`.
+/// Processes on GPUs missing from `uuids` are dropped.
+pub fn parse_compute_apps(output: &str, uuids: &HashMap<String, usize>) -> Vec<GpuProcess> {
+    output.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [uuid, pid, used] = fields.as_slice() else {
+                return None;
+            };
+            Some(GpuProcess {
+                device: *uuids.get(*uuid)?,
+                pid: pid.parse().ok()?,
+                // "[N/A]" on some drivers and in containers.
+                used_mib: used.parse().unwrap_or(0),
+            })
+        })
+        .collect()
+}
+
+/// The arguments of a `/proc/<pid>/cmdline` file.
+pub fn parse_cmdline(bytes: &[u8]) -> Vec<String> {
+    bytes.split(|&byte| byte == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).to_string())
+        .collect()
+}
+
+/// The MSST script a command line runs, if it is python running one.
+fn msst_script(cmdline: &[String]) -> Option<String> {
+    let name = |arg: &String| Path::new(arg).file_name().unwrap_or_default().to_string_lossy().to_string();
+    let python = cmdline.iter().position(|arg| name(arg).starts_with("python"))?;
+    cmdline[python + 1..].iter()
+        .map(name)
+        .find(|arg| TRAIN_SCRIPTS.iter().chain(INFERENCE_SCRIPTS.iter()).chain(VALIDATION_SCRIPTS.iter()).any(|script| script == arg))
+}
+
+/// The processes on `devices` that look like MSST jobs: python running one
+/// of the MSST scripts according to `cmdlines`, or one of `own_pids` (the
+/// TUI's own jobs, whose command lines may not be readable).
+pub fn find_conflicts(
+    processes: &[GpuProcess],
+    cmdlines: &HashMap<u32, Vec<String>>,
+    own_pids: &[u32],
+    devices: &[usize],
+) -> Vec<GpuConflict> {
+    processes.iter()
+        .filter(|process| devices.contains(&process.device))
+        .filter_map(|process| {
+            let script = cmdlines.get(&process.pid).and_then(|cmdline| msst_script(cmdline));
+            (script.is_some() || own_pids.contains(&process.pid)).then_some(GpuConflict {
+                device: process.device,
+                pid: process.pid,
+                used_mib: process.used_mib,
+                script,
+            })
+        })
+        .collect()
+}
+
+/// Asks `nvidia-smi` and `/proc` what is running on `devices`. Empty when
+/// either can't be read: the check warns, it never blocks a launch.
+pub fn check(devices: &[usize], own_pids: &[u32]) -> Vec<GpuConflict> {
+    let Some(processes) = gpu_processes() else {
+        return vec![];
+    };
+    let cmdlines = processes.iter()
+        .filter_map(|process| {
+            let bytes = std::fs::read(format!("/proc/{}/cmdline", process.pid)).ok()?;
+            Some((process.pid, parse_cmdline(&bytes)))
+        })
+        .collect();
+    find_conflicts(&processes, &cmdlines, own_pids, devices)
+}
+
+/// Every compute process on the GPUs; `None` without a working `nvidia-smi`.
+pub fn gpu_processes() -> Option<Vec<GpuProcess>> {
+    let gpus = nvidia_smi(&["--query-gpu=index,uuid", "--format=csv,noheader"])?;
+    let apps = nvidia_smi(&["--query-compute-apps=gpu_uuid,pid,used_memory", "--format=csv,noheader,nounits"])?;
+    Some(parse_compute_apps(&apps, &parse_gpu_uuids(&gpus)))
+}
+
+pub fn nvidia_smi(args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new("nvidia-smi")
+        .args(args)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_msst_processes_on_the_requested_devices_conflict() {
+        let uuids = parse_gpu_uuids("0, GPU-aaa\n1, GPU-bbb\n");
+        let apps = "GPU-aaa, 12345, 21811\nGPU-aaa, 222, 900\nGPU-bbb, 333, 4096\nGPU-aaa, 444, [N/A]\nGPU-zzz, 555, 1\n";
+        let processes = parse_compute_apps(apps, &uuids);
+        assert_eq!(processes.len(), 4);
+
+        let cmdlines = HashMap::from([
+            (12345, parse_cmdline(b"/usr/bin/python3\0train.py\0--model_type\0scnet\0")),
+            (222, parse_cmdline(b"python\0-m\0jupyter\0notebook\0")),
+            (333, parse_cmdline(b"/opt/conda/bin/python\0/home/me/msst/inference.py\0")),
+        ]);
+        let conflicts = find_conflicts(&processes, &cmdlines, &[444], &[0]);
+        assert_eq!(
+            conflicts,
+            [
+                GpuConflict { device: 0, pid: 12345, used_mib: 21811, script: Some("train.py".into()) },
+                GpuConflict { device: 0, pid: 444, used_mib: 0, script: None },
+            ]
+        );
+        assert_eq!(conflicts[0].message(), "GPU 0 is already running train.py (PID 12345, 21.3 GB used)");
+        assert_eq!(find_conflicts(&processes, &cmdlines, &[], &[1])[0].script.as_deref(), Some("inference.py"));
+        assert!(find_conflicts(&processes, &cmdlines, &[], &[2]).is_empty());
+    }
+
+    #[test]
+    fn prompt_offers_the_three_choices() {
+        assert_eq!(launch_prompt(&[]), None);
+        let conflict = GpuConflict { device: 1, pid: 7, used_mib: 2048, script: None };
+        assert_eq!(
+            launch_prompt(&[conflict]).unwrap(),
+            "GPU 1 is already running a TUI job (PID 7, 2.0 GB used) — start anyway / choose another device / cancel"
+        );
+        assert_eq!(requested_devices(None), [0]);
+        assert_eq!(requested_devices(Some(&[2, 3])), [2, 3]);
+    }
+}
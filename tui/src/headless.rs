@@ -0,0 +1,240 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::process::ExitStatus;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::checkpoints::resume_checkpoint;
+use crate::cli::{HeadlessCommand, HeadlessRun};
+use crate::config::{AppConfig, ConfigManager, DEFAULT_CONFIG_FILE};
+use crate::config_check::{self, has_errors, ConfigIssue};
+use crate::gpu_check::GpuConflict;
+use crate::history::DEFAULT_HISTORY_FILE;
+use crate::inference::InferenceManager;
+use crate::model::{InferenceConfig, TrainingConfig, TrainingProgress, ValidationConfig};
+use crate::parser::ParserRules;
+use crate::process::ProcessState;
+use crate::project::Scripts;
+use crate::runner::RunnerEnv;
+use crate::scratch::Scratch;
+use crate::storage;
+use crate::training::TrainingManager;
+use crate::ui::inference_result_line;
+use crate::validation::{self, ValidationManager, ValidationProgress};
+
+/// Lines of the training log shown when a headless run fails.
+const FAILURE_LOG_LINES: usize = 20;
+
+/// Runs `run` to the end with plain progress lines on stdout, and gives the
+/// exit code to leave with: the python process's own, or 1 when it
+/// couldn't be run. The scripts run in `root`.
+pub fn run(run: &HeadlessRun, root: &Path, scripts: &Scripts) -> Result<i32> {
+    let configs = ConfigManager::new(&root.join(DEFAULT_CONFIG_FILE).to_string_lossy());
+    let app_config = configs.load_config()?;
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .context("Failed to start the runtime")?;
+    let path = root.join(&run.config);
+    let path = path.to_string_lossy();
+    runtime.block_on(async {
+        match run.command {
+            HeadlessCommand::Train => train(configs.load_training_config(&path)?, &app_config, root, scripts, run.force).await,
+            HeadlessCommand::Infer => infer(&configs.load_inference_config(&path)?, &app_config, root, scripts).await,
+            HeadlessCommand::Validate => validate(&configs.load_validation_config(&path)?, &app_config, root, scripts).await,
+        }
+    })
+}
+
+/// `force` starts it even with other MSST processes on its GPUs.
+async fn train(mut config: TrainingConfig, app_config: &AppConfig, root: &Path, scripts: &Scripts, force: bool) -> Result<i32> {
+    if app_config.auto_resume
+        && config.start_checkpoint.as_deref().is_none_or(|path| path.trim().is_empty())
+        && let Some(checkpoint) = resume_checkpoint(&root.join(&config.results_path), config.model_type.key())
+    {
+        println!("Resuming from {}", checkpoint.label());
+        config.start_checkpoint = Some(checkpoint.path.to_string_lossy().into_owned());
+    }
+    let rooted = config.in_root(root);
+    let mut issues = config_check::validate(&rooted);
+    issues.extend(storage::issues_now(&rooted)?);
+    if !checked(&issues) {
+        return Ok(1);
+    }
+    let mut manager = TrainingManager::new();
+    manager.set_scripts(scripts);
+    manager.set_runner(RunnerEnv::from_config(app_config).in_root(root));
+    manager.set_parser_rules(ParserRules::load_or_builtin(app_config.parser_rules_path.as_deref().map(Path::new), root)?);
+    manager.set_cost_settings(app_config.cost.clone());
+    if let Some(template) = &app_config.run_name_template {
+        manager.set_run_name_template(template);
+    }
+    manager.set_history_path(&root.join(app_config.history_path.as_deref().unwrap_or(DEFAULT_HISTORY_FILE)));
+    if let Some(secs) = app_config.training_stop_grace_secs {
+        manager.set_stop_grace(Duration::from_secs(secs));
+    }
+    let conflicts = manager.gpu_preflight(&config, &[]);
+    if !conflicts.is_empty() {
+        let messages: Vec<String> = conflicts.iter().map(GpuConflict::message).collect();
+        if !force {
+            eprintln!("{}; pass --force to start anyway", messages.join("; "));
+            return Ok(1);
+        }
+        eprintln!("Warning: {}; starting anyway (--force)", messages.join("; "));
+    }
+
+    let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+    let printer = tokio::spawn(async move {
+        while let Some(progress) = progress_rx.recv().await {
+            println!("{}", progress_line(&progress));
+        }
+    });
+    let result = manager.start_training(&config, progress_tx).await;
+    let _ = printer.await;
+    if let Some(run_dir) = manager.run_dir() {
+        println!("Run folder: {}", run_dir.path().display());
+    }
+    let state = manager.status().clone();
+    if let Err(e) = result {
+        return Ok(failed(&e, &state));
+    }
+    println!("Training {}", state.describe());
+    if exit_code(&state) != 0 {
+        let log = manager.log();
+        let log = log.lock().unwrap();
+        for line in log.lines().skip(log.len().saturating_sub(FAILURE_LOG_LINES)) {
+            eprintln!("{}", line.text);
+        }
+    }
+    Ok(exit_code(&state))
+}
+
+async fn infer(config: &InferenceConfig, app_config: &AppConfig, root: &Path, scripts: &Scripts) -> Result<i32> {
+    if !checked(&config_check::validate_inference(&config.in_root(root))) {
+        return Ok(1);
+    }
+    // Without an output channel the manager prints inference.py's output.
+    let mut manager = InferenceManager::new();
+    manager.set_scripts(scripts);
+    manager.set_runner(RunnerEnv::from_config(app_config).in_root(root));
+    manager.set_scratch(Scratch::from_config(app_config, root));
+    let result = manager.run_inference(config).await;
+    let state = manager.status().clone();
+    match result {
+        Ok(result) => {
+            println!("Inference {}", inference_result_line(&result));
+            // Every input already separated means no process ran.
+            Ok(if result.success { 0 } else { exit_code(&state) })
+        }
+        Err(e) => Ok(failed(&e, &state)),
+    }
+}
+
+async fn validate(config: &ValidationConfig, app_config: &AppConfig, root: &Path, scripts: &Scripts) -> Result<i32> {
+    if !checked(&config_check::validate_validation(&config.in_root(root))) {
+        return Ok(1);
+    }
+    let mut manager = ValidationManager::new();
+    manager.set_scripts(scripts);
+    manager.set_runner(RunnerEnv::from_config(app_config).in_root(root));
+    let (progress_tx, mut progress_rx) = mpsc::unbounded_channel::<ValidationProgress>();
+    let printer = tokio::spawn(async move {
+        let mut last = None;
+        while let Some(progress) = progress_rx.recv().await {
+            if last != Some(progress) {
+                println!("Progress: {} of {} files", progress.done, progress.total);
+                last = Some(progress);
+            }
+        }
+    });
+    let result = manager.run_validation(config, progress_tx).await;
+    let _ = printer.await;
+    let state = manager.status().clone();
+    let results = match result {
+        Ok(results) => results,
+        Err(e) => return Ok(failed(&e, &state)),
+    };
+    for result in &results {
+        match result.si_sdr {
+            Some(si_sdr) => println!("{}: SDR {:.4}, SI-SDR {:.4}", result.instrument, result.sdr, si_sdr),
+            None => println!("{}: SDR {:.4}", result.instrument, result.sdr),
+        }
+    }
+    if let (Some(sdr), _) = validation::mean(&results) {
+        println!("Mean SDR: {:.4}", sdr);
+    }
+    Ok(0)
+}
+
+/// E.g. "Epoch 3: train loss 0.1234, valid loss 0.1100, SDR 9.34".
+pub fn progress_line(progress: &TrainingProgress) -> String {
+    let mut line = format!("Epoch {}: train loss {:.4}", progress.epoch, progress.train_loss);
+    let optional = [
+        ("valid loss", progress.valid_loss, 4),
+        ("SDR", progress.sdr, 2),
+        ("GPU memory", progress.gpu_memory, 1),
+    ];
+    for (name, value, digits) in optional {
+        if let Some(value) = value {
+            line.push_str(&format!(", {} {:.*}", name, digits, value));
+        }
+    }
+    line
+}
+
+/// Prints the config's issues; false if any of them is an error, so the
+/// run can't start.
+fn checked(issues: &[ConfigIssue]) -> bool {
+    for issue in issues {
+        eprintln!("{}", issue.line());
+    }
+    !has_errors(issues)
+}
+
+/// A run that went wrong exits non-zero even if its process didn't.
+fn failed(error: &anyhow::Error, state: &ProcessState) -> i32 {
+    eprintln!("Error: {:#}", error);
+    match exit_code(state) {
+        0 => 1,
+        code => code,
+    }
+}
+
+/// The process's exit code; 128 + the signal for a killed one, as a shell
+/// reports it, and 1 when there is no exit status.
+pub fn exit_code(state: &ProcessState) -> i32 {
+    match state {
+        ProcessState::Exited(status) => status.code().unwrap_or_else(|| 128 + signal(status)),
+        _ => 1,
+    }
+}
+
+#[cfg(unix)]
+fn signal(status: &ExitStatus) -> i32 {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal().unwrap_or(0)
+}
+
+#[cfg(not(unix))]
+fn signal(_status: &ExitStatus) -> i32 {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn exit_codes_mirror_the_process() {
+        use std::os::unix::process::ExitStatusExt;
+        assert_eq!(exit_code(&ProcessState::Exited(ExitStatus::from_raw(0))), 0);
+        assert_eq!(exit_code(&ProcessState::Exited(ExitStatus::from_raw(3 << 8))), 3);
+        assert_eq!(exit_code(&ProcessState::Exited(ExitStatus::from_raw(9))), 137);
+        assert_eq!(exit_code(&ProcessState::Failed("No such file".to_string())), 1);
+        assert_eq!(exit_code(&ProcessState::NotStarted), 1);
+
+        let progress = TrainingProgress { epoch: 3, train_loss: 0.12345, sdr: Some(9.341), ..Default::default() };
+        assert_eq!(progress_line(&progress), "Epoch 3: train loss 0.1235, SDR 9.34");
+    }
+}
@@ -0,0 +1,175 @@
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Modifier, Style},
+    text::Text,
+    widgets::{Block, Borders, Cell, Clear, Row, Table},
+    Frame,
+};
+
+use crate::theme::Styles;
+
+/// Between the entries of a key line, e.g. "s: save    Esc: back".
+const ENTRY_SEPARATOR: &str = "    ";
+
+/// The keys of one screen or popup, as the help overlay lists them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HelpTopic {
+    pub title: String,
+    /// Named groups of keys and what they do; an empty key is a note.
+    pub sections: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl HelpTopic {
+    pub fn new(title: &str) -> Self {
+        HelpTopic { title: title.to_string(), sections: vec![] }
+    }
+
+    pub fn section(mut self, name: &str, keys: Vec<(String, String)>) -> Self {
+        if !keys.is_empty() {
+            self.sections.push((name.to_string(), keys));
+        }
+        self
+    }
+
+    /// "s - save" and so on, under each section's name, for plain mode.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![];
+        for (name, keys) in &self.sections {
+            lines.push(format!("{}:", name));
+            lines.extend(keys.iter().map(|(key, description)| match key.is_empty() {
+                true => description.clone(),
+                false => format!("{} - {}", key, description),
+            }));
+        }
+        lines
+    }
+
+    /// A popup in the middle of `area` with the keys in one column and what
+    /// they do, wrapped to fit, in the other. The screen under it is dimmed.
+    pub fn render(&self, f: &mut Frame, area: Rect, styles: &Styles) {
+        f.buffer_mut().set_style(area, Style::default().add_modifier(Modifier::DIM));
+        let width = (area.width * 3 / 4).max(area.width.min(40));
+        let inner = width.saturating_sub(2) as usize;
+        let key_width = self.sections.iter()
+            .flat_map(|(_, keys)| keys.iter().map(|(key, _)| key.chars().count()))
+            .max()
+            .unwrap_or(0)
+            .min(inner / 3);
+        let description_width = inner.saturating_sub(key_width + 1).max(1);
+
+        let mut rows = vec![];
+        let mut lines = 0;
+        for (name, keys) in &self.sections {
+            lines += 1;
+            rows.push(Row::new([Cell::from(name.as_str()).style(styles.header), Cell::from("")]));
+            for (key, description) in keys {
+                let key_lines = wrap(key, key_width.max(1));
+                let description_lines = wrap(description, description_width);
+                let height = key_lines.len().max(description_lines.len());
+                lines += height;
+                rows.push(Row::new([
+                    Cell::from(Text::from(key_lines.join("\n"))).style(styles.highlight),
+                    Cell::from(Text::from(description_lines.join("\n"))),
+                ]).height(height as u16));
+            }
+        }
+        let height = (lines as u16 + 2).min(area.height);
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+        f.render_widget(Clear, popup);
+        f.render_widget(
+            Table::new(rows, [Constraint::Length(key_width as u16), Constraint::Length(description_width as u16)])
+                .block(Block::default().borders(Borders::ALL).border_style(styles.border).title(format!("Help: {}", self.title)).title_bottom("any key closes"))
+                .style(styles.text),
+            popup,
+        );
+    }
+}
+
+/// The entries of a key line like "s: save    m: edit the model config",
+/// as key and description; an entry without a key is kept as a note.
+pub fn parse_keys(line: &str) -> Vec<(String, String)> {
+    line.split(ENTRY_SEPARATOR)
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| match entry.split_once(": ") {
+            Some((key, description)) => (key.to_string(), description.to_string()),
+            None => (String::new(), entry.to_string()),
+        })
+        .collect()
+}
+
+/// `text` in lines of at most `width` characters, broken between words
+/// where it can be.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = vec![];
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word = word.to_string();
+        while word.chars().count() > width {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            let rest = word.chars().skip(width).collect();
+            lines.push(word.chars().take(width).collect());
+            word = rest;
+        }
+        let needed = if line.is_empty() { word.chars().count() } else { line.chars().count() + 1 + word.chars().count() };
+        if needed > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    #[test]
+    fn key_lines_split_into_keys_and_descriptions() {
+        assert_eq!(parse_keys("Type or paste log lines    Ctrl+L: clear    Esc: back"), [
+            (String::new(), "Type or paste log lines".to_string()),
+            ("Ctrl+L".to_string(), "clear".to_string()),
+            ("Esc".to_string(), "back".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn long_descriptions_wrap_inside_the_popup() {
+        let description = "start training with the Config screen's config, or resume the one that was stopped last time";
+        let topic = HelpTopic::new("Training")
+            .section("This screen", vec![("t".to_string(), description.to_string()), ("PgUp/PgDn".to_string(), "scroll log".to_string())]);
+        let mut terminal = Terminal::new(TestBackend::new(40, 20)).unwrap();
+        terminal.draw(|f| topic.render(f, f.size(), &Styles::default())).unwrap();
+        let buffer = terminal.backend().buffer();
+        let rows: Vec<String> = (0..20).map(|y| (0..40).map(|x| buffer.get(x, y).symbol()).collect()).collect();
+
+        let inside: Vec<&str> = rows.iter()
+            .filter(|row| row.starts_with('│'))
+            .map(|row| {
+                assert!(row.ends_with('│'), "overflows the border: {:?}", row);
+                row.trim_matches('│').trim()
+            })
+            .collect();
+        let words: Vec<&str> = inside.iter().flat_map(|row| row.split_whitespace()).collect();
+        for word in description.split_whitespace().chain(["PgUp/PgDn", "scroll", "log"]) {
+            assert!(words.contains(&word), "{:?} missing from {:#?}", word, rows);
+        }
+        assert!(rows.iter().any(|row| row.contains("Help: Training")), "{:#?}", rows);
+        assert_eq!(wrap("a b", 0), ["a", "b"], "a zero width still makes progress");
+    }
+}
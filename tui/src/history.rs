@@ -0,0 +1,773 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::bakeoff::BakeOffReport;
+use crate::cost::RunCost;
+use crate::import::{self, Found, ImportedMetadata, LegacyRun};
+use crate::integrity;
+use crate::metrics::{self, TrainingHistory};
+use crate::model::ModelType;
+use crate::remote::RemoteLocation;
+use crate::resources::ResourceSummary;
+use crate::run_dir::{RunDir, METRICS_FILE};
+use crate::secret::redact;
+use crate::validset::{Fingerprints, ValidSetStamp};
+
+/// Looked up in the project root when no other history file is configured.
+pub const DEFAULT_HISTORY_FILE: &str = "tui_history.json";
+
+/// Format of the history file written by this version; older files are
+/// migrated by `migrate_history` on load.
+pub const HISTORY_SCHEMA_VERSION: u32 = 1;
+
+/// What produced a history entry, and so which metadata file it points at.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunKind {
+    /// A run directory with `run.json`.
+    #[default]
+    Training,
+    /// A bake-off folder with `bakeoff.json`, one entry for all its models.
+    BakeOff,
+    /// A results folder from before run directories, found by `import`.
+    /// It has no metadata file; the history entry is all there is.
+    Imported,
+}
+
+/// One run as the history screen lists it. The run's own metadata file is
+/// the source of truth; label and notes are copied here so the filter
+/// doesn't have to open every run directory.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HistoryEntry {
+    #[serde(default)]
+    pub kind: RunKind,
+    pub name: String,
+    pub label: Option<String>,
+    pub model_type: ModelType,
+    /// Every candidate of a bake-off; empty for training runs.
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// Run directory relative to the history file's folder when it lives
+    /// under it, so moving the whole project keeps the link; absolute
+    /// otherwise.
+    pub anchor: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub imported: Option<ImportedMetadata>,
+    /// The run's price, when costs were tracked as it started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<RunCost>,
+    /// Best mean SDR of a training run, or of a bake-off's candidates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub best_sdr: Option<f64>,
+    /// The training epoch `best_sdr` came at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub best_epoch: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epochs_completed: Option<usize>,
+    /// How heavy a training run was, once it ended.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resources: Option<ResourceSummary>,
+    /// The validation or reference folder the run was measured against.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_set: Option<ValidSetStamp>,
+    /// That folder has changed since, so its numbers don't compare with
+    /// newer ones. Worked out by `refresh`.
+    #[serde(skip)]
+    pub valid_set_stale: bool,
+    /// Why the run's folder or metadata file couldn't be read on the last
+    /// `refresh`; the entry is listed as it was last read.
+    #[serde(skip)]
+    pub problem: Option<String>,
+}
+
+impl HistoryEntry {
+    /// Whether every whitespace-separated term of `query` appears, ignoring
+    /// case, in the name, label, models or notes.
+    pub fn matches(&self, query: &str) -> bool {
+        let haystack = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            self.name,
+            self.label.as_deref().unwrap_or_default(),
+            self.model_type.key(),
+            self.models.join(" "),
+            self.notes,
+            if self.kind == RunKind::Imported { "imported" } else { "" }
+        )
+        .to_lowercase();
+        query.split_whitespace().all(|term| haystack.contains(&term.to_lowercase()))
+    }
+
+    pub fn status(&self) -> String {
+        if self.problem.is_some() {
+            return "unreadable".to_string();
+        }
+        if let Some(imported) = &self.imported {
+            return if imported.needs_review() { "imported ?" } else { "imported" }.to_string();
+        }
+        match (self.finished_at, self.exit_code) {
+            (None, _) => "running".to_string(),
+            (Some(_), Some(0)) => "ok".to_string(),
+            (Some(_), Some(_)) if self.kind == RunKind::BakeOff => "failed".to_string(),
+            (Some(_), Some(code)) => format!("exit {}", code),
+            (Some(_), None) => "killed".to_string(),
+        }
+    }
+
+    /// Best SDR known for the run, measured or imported.
+    pub fn sdr(&self) -> Option<f64> {
+        self.best_sdr.or(self.imported.as_ref().and_then(|imported| imported.best_sdr))
+    }
+
+    /// What the run detail screen says about the run, above its charts.
+    pub fn detail_lines(&self) -> Vec<String> {
+        let time = |at: DateTime<Utc>| at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string();
+        let model = match self.kind {
+            RunKind::BakeOff => format!("bake-off: {}", self.models.join(" vs ")),
+            _ => self.model_type.key().to_string(),
+        };
+        let mut lines = vec![
+            format!("Model: {}", model),
+            format!("Started: {}", time(self.started_at)),
+            format!("Finished: {}", self.finished_at.map_or("not yet".to_string(), time)),
+            format!("Status: {}", self.status()),
+        ];
+        if let Some(epochs) = self.epochs_completed {
+            lines.push(format!("Epochs: {}", epochs));
+        }
+        match (self.sdr(), self.best_epoch) {
+            (Some(sdr), Some(epoch)) => lines.push(format!("Best SDR: {:.2} at epoch {}", sdr, epoch)),
+            (Some(sdr), None) => lines.push(format!("Best SDR: {:.2}", sdr)),
+            (None, _) => {}
+        }
+        if let Some(problem) = &self.problem {
+            lines.push(format!("Problem: {}", problem));
+        }
+        lines
+    }
+
+    /// Estimated cost so far, for a run that is priced.
+    pub fn cost_amount(&self) -> Option<f64> {
+        self.cost.as_ref().map(|cost| cost.amount(self.started_at, self.finished_at))
+    }
+
+    fn is(&self, summary: &Summary) -> bool {
+        self.name == summary.name && self.started_at == summary.started_at
+    }
+
+    fn update_from(&mut self, summary: Summary) {
+        self.label = summary.label;
+        self.finished_at = summary.finished_at;
+        self.exit_code = summary.exit_code;
+        self.notes = summary.notes;
+        self.cost = summary.cost;
+        self.best_sdr = summary.best_sdr;
+        self.best_epoch = summary.best_epoch;
+        self.epochs_completed = summary.epochs_completed;
+        self.resources = summary.resources;
+        self.valid_set = summary.valid_set;
+    }
+}
+
+/// The part of a run's metadata file the history mirrors.
+struct Summary {
+    name: String,
+    label: Option<String>,
+    started_at: DateTime<Utc>,
+    finished_at: Option<DateTime<Utc>>,
+    exit_code: Option<i32>,
+    notes: String,
+    cost: Option<RunCost>,
+    best_sdr: Option<f64>,
+    best_epoch: Option<usize>,
+    epochs_completed: Option<usize>,
+    resources: Option<ResourceSummary>,
+    valid_set: Option<ValidSetStamp>,
+}
+
+impl Summary {
+    fn load(kind: RunKind, dir: &Path) -> Result<Self> {
+        Ok(match kind {
+            RunKind::Training => RunDir::load(dir)?.into(),
+            RunKind::BakeOff => BakeOffReport::load(dir)?.into(),
+            RunKind::Imported => anyhow::bail!("Imported runs have no metadata file"),
+        })
+    }
+}
+
+impl From<RunDir> for Summary {
+    fn from(run: RunDir) -> Self {
+        let metadata = run.metadata;
+        Summary {
+            name: metadata.name,
+            label: metadata.label,
+            started_at: metadata.started_at,
+            finished_at: metadata.finished_at,
+            exit_code: metadata.exit_code,
+            notes: metadata.notes,
+            cost: metadata.cost,
+            best_sdr: metadata.best_sdr,
+            best_epoch: metadata.best_epoch,
+            epochs_completed: metadata.epochs_completed,
+            resources: metadata.resources,
+            valid_set: metadata.valid_set,
+        }
+    }
+}
+
+impl From<BakeOffReport> for Summary {
+    /// A finished bake-off "exits" 0 when every candidate succeeded.
+    fn from(report: BakeOffReport) -> Self {
+        let exit_code = report.finished_at.map(|_| if report.all_succeeded() { 0 } else { 1 });
+        Summary {
+            name: report.name,
+            label: report.label,
+            started_at: report.started_at,
+            finished_at: report.finished_at,
+            exit_code,
+            best_sdr: report.outcomes.iter().filter_map(|outcome| outcome.sdr).reduce(f64::max),
+            best_epoch: None,
+            epochs_completed: None,
+            notes: report.notes,
+            cost: report.cost,
+            resources: None,
+            valid_set: report.reference_set,
+        }
+    }
+}
+
+/// A run opened from the history: what is known about it and the metrics
+/// its `metrics.csv` holds.
+#[derive(Debug, Clone)]
+pub struct RunDetail {
+    pub entry: HistoryEntry,
+    /// `HistoryEntry::detail_lines`, with the config and where the metrics
+    /// came from, or why there are none.
+    pub lines: Vec<String>,
+    pub metrics: TrainingHistory,
+    /// The run's folder here, when it could be found.
+    pub folder: Option<PathBuf>,
+    /// Where its results are, for a run on a remote host.
+    pub remote: Option<RemoteLocation>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ImportSummary {
+    pub added: usize,
+    /// Found but already in the history.
+    pub known: usize,
+    /// Added with no single model guess.
+    pub needs_review: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct HistoryFile {
+    #[serde(default)]
+    schema_version: u32,
+    entries: Vec<HistoryEntry>,
+}
+
+/// Every run the TUI started, newest last, persisted as JSON.
+#[derive(Debug, Clone)]
+pub struct RunHistory {
+    path: PathBuf,
+    entries: Vec<HistoryEntry>,
+}
+
+impl RunHistory {
+    /// Loads the history at `path`; a missing file is an empty history.
+    pub fn load(path: &Path) -> Result<Self> {
+        let entries = if path.is_file() {
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read run history {}", path.display()))?;
+            let value = serde_json::from_str::<serde_yaml::Value>(&content)
+                .with_context(|| format!("Failed to parse run history {}", path.display()))?;
+            let value = integrity::migrate(value, HISTORY_SCHEMA_VERSION, "Run history", migrate_history)?;
+            serde_yaml::from_value::<HistoryFile>(value)
+                .with_context(|| format!("Failed to parse run history {}", path.display()))?
+                .entries
+        } else {
+            vec![]
+        };
+        Ok(RunHistory { path: path.to_path_buf(), entries })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let file = HistoryFile { schema_version: HISTORY_SCHEMA_VERSION, entries: self.entries.clone() };
+        let content = serde_json::to_string_pretty(&file)
+            .context("Failed to serialize run history")?;
+        fs::write(&self.path, redact(&content))
+            .with_context(|| format!("Failed to write run history {}", self.path.display()))?;
+        Ok(())
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Indexes of the entries matching a filter query.
+    pub fn filter(&self, query: &str) -> Vec<usize> {
+        (0..self.entries.len()).filter(|&i| self.entries[i].matches(query)).collect()
+    }
+
+    pub fn position(&self, entry: &HistoryEntry) -> Option<usize> {
+        self.entries.iter().position(|e| e.name == entry.name && e.started_at == entry.started_at)
+    }
+
+    /// Adds a training run, or updates it if it is already recorded.
+    pub fn record(&mut self, run: &RunDir) {
+        let model_type = run.metadata.model_type.clone();
+        self.upsert(RunKind::Training, run.path(), model_type, vec![], run.clone().into());
+    }
+
+    /// Adds a bake-off as one entry, or updates it.
+    pub fn record_bake_off(&mut self, dir: &Path, report: &BakeOffReport) {
+        let models: Vec<String> = report.outcomes.iter().map(|outcome| outcome.candidate.key()).collect();
+        let model_type = report.outcomes.first()
+            .map(|outcome| outcome.candidate.model_type.clone())
+            .unwrap_or(ModelType::BsRoformer);
+        self.upsert(RunKind::BakeOff, dir, model_type, models, report.clone().into());
+    }
+
+    fn upsert(&mut self, kind: RunKind, dir: &Path, model_type: ModelType, models: Vec<String>, summary: Summary) {
+        let anchor = self.anchor(dir);
+        match self.entries.iter_mut().find(|entry| entry.is(&summary)) {
+            Some(entry) => {
+                entry.anchor = anchor;
+                if !models.is_empty() {
+                    entry.models = models;
+                }
+                entry.update_from(summary);
+            }
+            None => self.entries.push(HistoryEntry {
+                kind,
+                name: summary.name,
+                label: summary.label,
+                model_type,
+                models,
+                anchor,
+                started_at: summary.started_at,
+                finished_at: summary.finished_at,
+                exit_code: summary.exit_code,
+                notes: summary.notes,
+                imported: None,
+                cost: summary.cost,
+                best_sdr: summary.best_sdr,
+                best_epoch: summary.best_epoch,
+                epochs_completed: summary.epochs_completed,
+                resources: summary.resources,
+                valid_set: summary.valid_set,
+                valid_set_stale: false,
+                problem: None,
+            }),
+        }
+    }
+
+    /// Re-reads every run that can still be found, picking up exit codes and
+    /// notes written since, and following runs that were moved. Runs that
+    /// can't be read keep their entry, marked with the problem.
+    pub fn refresh(&mut self) {
+        for index in 0..self.entries.len() {
+            self.entries[index].problem = self.locate(index).err().map(|e| format!("{:#}", e));
+        }
+        self.check_valid_sets();
+    }
+
+    /// Marks the entries whose validation folder has changed since they
+    /// were measured; each folder is fingerprinted once.
+    pub fn check_valid_sets(&mut self) {
+        let mut fingerprints = Fingerprints::default();
+        for entry in &mut self.entries {
+            entry.valid_set_stale = entry.valid_set.as_ref()
+                .is_some_and(|stamp| stamp.is_stale(fingerprints.current(&stamp.path)));
+        }
+    }
+
+    /// Finds the folder of an entry. If it is no longer where the history
+    /// says, the known results folders are searched for it and the anchor is
+    /// updated.
+    pub fn locate(&mut self, index: usize) -> Result<PathBuf> {
+        let entry = self.entries.get(index).context("No such history entry")?;
+        let path = self.resolve(&entry.anchor);
+        if entry.kind == RunKind::Imported {
+            // Nothing to match a moved folder by, so it is only looked for
+            // where it was imported from.
+            if !path.is_dir() {
+                anyhow::bail!("Imported run {} is no longer at {}", entry.name, path.display());
+            }
+            return Ok(path);
+        }
+        let (path, summary) = match Summary::load(entry.kind, &path) {
+            Ok(summary) if entry.is(&summary) => (path, summary),
+            _ => self.search(entry)
+                .with_context(|| format!("Run {} is no longer at {}", entry.name, path.display()))?,
+        };
+
+        let anchor = self.anchor(&path);
+        let entry = &mut self.entries[index];
+        entry.anchor = anchor;
+        entry.update_from(summary);
+        Ok(path)
+    }
+
+    /// The entry at `index` with the metrics its run recorded. A run that
+    /// can't be found or has no metrics still opens, saying so.
+    pub fn detail(&mut self, index: usize) -> Result<RunDetail> {
+        let located = self.locate(index);
+        let entry = self.entries.get_mut(index).context("No such history entry")?;
+        entry.problem = located.as_ref().err().map(|e| format!("{:#}", e));
+        let mut lines = entry.detail_lines();
+        let mut history = TrainingHistory::new();
+        let mut remote = None;
+        let folder = located.ok();
+        if let Some(path) = &folder {
+            lines.push(format!("Folder: {}", path.display()));
+            if let Ok(run) = RunDir::load(path) {
+                lines.push(format!("Config: {}", run.metadata.config_source));
+                remote = run.metadata.remote;
+            }
+            if let Some(location) = &remote {
+                lines.push(format!("Remote: {} on {}", location.source(), location.host));
+            }
+            let csv = path.join(METRICS_FILE);
+            match metrics::read_csv(&csv) {
+                Ok(rows) => history = TrainingHistory::from_rows(rows),
+                Err(_) if !csv.exists() => lines.push(format!("No {} in the run folder", METRICS_FILE)),
+                Err(e) => lines.push(format!("Metrics not read: {:#}", e)),
+            }
+        }
+        Ok(RunDetail { entry: entry.clone(), lines, metrics: history, folder, remote })
+    }
+
+    /// Writes notes into the run's own metadata file and the history.
+    /// Imported runs keep them in the history only.
+    pub fn set_notes(&mut self, index: usize, notes: &str) -> Result<()> {
+        if self.entries.get(index).context("No such history entry")?.kind == RunKind::Imported {
+            self.entries[index].notes = notes.trim_end().to_string();
+            return self.save();
+        }
+        let path = self.locate(index)?;
+        let notes = match self.entries[index].kind {
+            RunKind::Training => {
+                let mut run = RunDir::load(&path)?;
+                run.set_notes(notes)?;
+                run.metadata.notes
+            }
+            RunKind::BakeOff => {
+                let mut report = BakeOffReport::load(&path)?;
+                report.set_notes(&path, notes)?;
+                report.notes
+            }
+            RunKind::Imported => unreachable!(),
+        };
+        self.entries[index].notes = notes;
+        self.save()
+    }
+
+    /// Adds every results folder under `root` that the history doesn't list
+    /// yet. Run and bake-off folders are recorded from their metadata; older
+    /// folders become `RunKind::Imported` entries with whatever could be
+    /// inferred. Folders already listed, by path, are left as they are, so
+    /// fix-ups made since survive importing the same root again.
+    pub fn import(&mut self, root: &Path) -> Result<ImportSummary> {
+        let mut summary = ImportSummary::default();
+        for found in import::scan(root)? {
+            let before = self.entries.len();
+            match found {
+                Found::Run(dir) => self.record(&RunDir::load(&dir)?),
+                Found::BakeOff(dir) => self.record_bake_off(&dir, &BakeOffReport::load(&dir)?),
+                Found::Legacy(run) => {
+                    let anchor = self.anchor(&run.path);
+                    if !self.entries.iter().any(|entry| entry.anchor == anchor) {
+                        summary.needs_review += usize::from(run.metadata.needs_review());
+                        self.entries.push(self.imported_entry(anchor, run));
+                    }
+                }
+            }
+            if self.entries.len() > before {
+                summary.added += 1;
+            } else {
+                summary.known += 1;
+            }
+        }
+        Ok(summary)
+    }
+
+    fn imported_entry(&self, anchor: String, run: LegacyRun) -> HistoryEntry {
+        HistoryEntry {
+            kind: RunKind::Imported,
+            name: run.name,
+            label: None,
+            // Only a placeholder when there isn't exactly one candidate; the
+            // status column asks for a fix-up.
+            model_type: run.metadata.model_candidates.first().cloned().unwrap_or(ModelType::BsRoformer),
+            models: vec![],
+            anchor,
+            started_at: run.started_at,
+            finished_at: Some(run.finished_at),
+            exit_code: None,
+            notes: String::new(),
+            imported: Some(run.metadata),
+            cost: None,
+            best_sdr: None,
+            best_epoch: None,
+            epochs_completed: None,
+            resources: None,
+            valid_set: None,
+            valid_set_stale: false,
+            problem: None,
+        }
+    }
+
+    /// Fixes up the model of an imported entry.
+    pub fn set_model(&mut self, index: usize, model_type: ModelType) -> Result<()> {
+        let imported = self.imported_mut(index)?;
+        imported.model_candidates = vec![model_type.clone()];
+        self.entries[index].model_type = model_type;
+        self.save()
+    }
+
+    /// Fixes up the label of an imported entry. Other runs keep their label
+    /// in their own metadata file.
+    pub fn set_label(&mut self, index: usize, label: &str) -> Result<()> {
+        self.imported_mut(index)?;
+        let label = label.trim();
+        self.entries[index].label = (!label.is_empty()).then(|| label.to_string());
+        self.save()
+    }
+
+    fn imported_mut(&mut self, index: usize) -> Result<&mut ImportedMetadata> {
+        self.entries.get_mut(index)
+            .context("No such history entry")?
+            .imported
+            .as_mut()
+            .context("Only imported runs can be fixed up; other runs take model and label from their metadata")
+    }
+
+    fn base(&self) -> PathBuf {
+        let parent = self.path.parent().unwrap_or(Path::new(""));
+        std::path::absolute(parent).unwrap_or_else(|_| parent.to_path_buf())
+    }
+
+    fn anchor(&self, run_path: &Path) -> String {
+        let absolute = std::path::absolute(run_path).unwrap_or_else(|_| run_path.to_path_buf());
+        match absolute.strip_prefix(self.base()) {
+            Ok(relative) => relative.to_string_lossy().to_string(),
+            Err(_) => absolute.to_string_lossy().to_string(),
+        }
+    }
+
+    fn resolve(&self, anchor: &str) -> PathBuf {
+        self.base().join(anchor)
+    }
+
+    /// Looks for the entry's folder in every folder that holds (or held) a
+    /// recorded run, in the history file's folder and in the folders
+    /// directly under it.
+    fn search(&self, entry: &HistoryEntry) -> Option<(PathBuf, Summary)> {
+        let base = self.base();
+        let mut roots = vec![base.clone()];
+        roots.extend(subdirs(&base));
+        for other in &self.entries {
+            let resolved = self.resolve(&other.anchor);
+            if let Some(parent) = resolved.parent()
+                && !roots.iter().any(|root| root == parent)
+            {
+                roots.push(parent.to_path_buf());
+            }
+        }
+        roots.iter()
+            .flat_map(|root| subdirs(root))
+            .find_map(|dir| {
+                let summary = Summary::load(entry.kind, &dir).ok()?;
+                entry.is(&summary).then_some((dir, summary))
+            })
+    }
+}
+
+/// Migrates a history file from format `from` to the next one.
+fn migrate_history(from: u32, value: serde_yaml::Value) -> Result<serde_yaml::Value> {
+    match from {
+        // Format 1 only added `schema_version`.
+        0 => Ok(value),
+        _ => bail!("No migration from history format {}", from),
+    }
+}
+
+fn subdirs(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::CommandSpec;
+
+    fn create_run(results: &Path, name: &str, label: Option<&str>) -> RunDir {
+        fs::create_dir_all(results).unwrap();
+        let config = results.join("model.yaml");
+        fs::write(&config, "audio: {}\n").unwrap();
+        RunDir::create(
+            &results.join(name),
+            config.to_str().unwrap(),
+            label.map(str::to_string),
+            ModelType::MelBandRoformer,
+            CommandSpec::new("python"),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn notes_are_stored_in_the_run_and_searchable() {
+        let project = tempfile::tempdir().unwrap();
+        let results = project.path().join("results");
+        let mut history = RunHistory::load(&project.path().join(DEFAULT_HISTORY_FILE)).unwrap();
+        history.record(&create_run(&results, "a", Some("vocals")));
+        history.record(&create_run(&results, "b", None));
+        history.save().unwrap();
+        assert_eq!(history.entries()[0].anchor, Path::new("results").join("a").to_string_lossy());
+
+        history.set_notes(1, "Lower LR\nlooks overfit after epoch 40\n").unwrap();
+        assert_eq!(RunDir::load(&results.join("b")).unwrap().metadata.notes, "Lower LR\nlooks overfit after epoch 40");
+
+        let history = RunHistory::load(&project.path().join(DEFAULT_HISTORY_FILE)).unwrap();
+        assert_eq!(history.filter("OVERFIT"), vec![1]);
+        assert_eq!(history.filter("vocals mel_band"), vec![0]);
+        assert_eq!(history.filter(""), vec![0, 1]);
+        assert!(history.filter("drums").is_empty());
+    }
+
+    #[test]
+    fn runs_open_with_their_metrics_and_unreadable_ones_stay_listed() {
+        let project = tempfile::tempdir().unwrap();
+        let results = project.path().join("results");
+        let mut history = RunHistory::load(&project.path().join(DEFAULT_HISTORY_FILE)).unwrap();
+        let mut run = create_run(&results, "a", None);
+        let mut metrics = TrainingHistory::new();
+        metrics.push(crate::model::TrainingProgress { epoch: 0, train_loss: 0.2, sdr: Some(7.25), ..Default::default() });
+        run.record_metrics(&metrics).unwrap();
+        run.finish(Some(0), None).unwrap();
+        history.record(&run);
+        history.record(&create_run(&results, "b", None));
+
+        let detail = history.detail(0).unwrap();
+        assert_eq!(detail.metrics.len(), 1);
+        assert!(detail.lines.contains(&"Epochs: 1".to_string()), "{:?}", detail.lines);
+        assert!(detail.lines.contains(&"Best SDR: 7.25 at epoch 0".to_string()), "{:?}", detail.lines);
+        assert!(history.detail(1).unwrap().lines.contains(&"No metrics.csv in the run folder".to_string()));
+
+        fs::write(results.join("b").join(crate::run_dir::RUN_FILE), "{ not json").unwrap();
+        history.refresh();
+        let statuses: Vec<String> = history.entries().iter().map(HistoryEntry::status).collect();
+        assert_eq!(statuses, ["ok", "unreadable"]);
+        assert!(history.detail(1).unwrap().lines.iter().any(|line| line.starts_with("Problem: Run b is no longer at")));
+    }
+
+    #[test]
+    fn moved_runs_are_found_again() {
+        let project = tempfile::tempdir().unwrap();
+        let results = project.path().join("results");
+        let history_path = project.path().join(DEFAULT_HISTORY_FILE);
+        let mut history = RunHistory::load(&history_path).unwrap();
+        history.record(&create_run(&results, "a", None));
+        history.record(&create_run(&results, "b", None));
+        history.save().unwrap();
+
+        // The whole project moves: relative anchors still resolve.
+        let moved_project = tempfile::tempdir().unwrap();
+        let moved_results = moved_project.path().join("results");
+        fs::rename(&results, &moved_results).unwrap();
+        fs::copy(&history_path, moved_project.path().join(DEFAULT_HISTORY_FILE)).unwrap();
+        let mut history = RunHistory::load(&moved_project.path().join(DEFAULT_HISTORY_FILE)).unwrap();
+        assert_eq!(history.locate(0).unwrap(), moved_results.join("a"));
+
+        // One run moves to another results folder next to the history file.
+        let archive = moved_project.path().join("archive");
+        fs::create_dir(&archive).unwrap();
+        fs::rename(moved_results.join("b"), archive.join("b")).unwrap();
+        history.set_notes(1, "archived").unwrap();
+        assert_eq!(history.entries()[1].anchor, Path::new("archive").join("b").to_string_lossy());
+        assert_eq!(RunDir::load(&archive.join("b")).unwrap().metadata.notes, "archived");
+    }
+
+    #[test]
+    fn runs_on_a_changed_valid_set_are_marked_stale() {
+        let project = tempfile::tempdir().unwrap();
+        let results = project.path().join("results");
+        let valid = project.path().join("valid");
+        fs::create_dir_all(valid.join("song")).unwrap();
+        fs::write(valid.join("song/vocals.wav"), "abcd").unwrap();
+        let valid_path = valid.to_str().unwrap();
+
+        let mut history = RunHistory::load(&project.path().join(DEFAULT_HISTORY_FILE)).unwrap();
+        let mut old = create_run(&results, "old", None);
+        old.metadata.valid_set = Some(ValidSetStamp::take(valid_path).unwrap());
+        old.save().unwrap();
+        history.record(&old);
+        history.record(&create_run(&results, "unstamped", None));
+        history.refresh();
+        assert!(!history.entries()[0].valid_set_stale);
+
+        fs::create_dir(valid.join("new_song")).unwrap();
+        fs::write(valid.join("new_song/vocals.wav"), "abcd").unwrap();
+        let mut new = create_run(&results, "new", None);
+        new.metadata.valid_set = Some(ValidSetStamp::take(valid_path).unwrap());
+        new.save().unwrap();
+        history.record(&new);
+        history.refresh();
+        let stale: Vec<bool> = history.entries().iter().map(|entry| entry.valid_set_stale).collect();
+        assert_eq!(stale, [true, false, false]);
+    }
+
+    #[test]
+    fn import_is_idempotent_and_read_only() {
+        let project = tempfile::tempdir().unwrap();
+        let old = project.path().join("old_results");
+        create_run(&old, "structured", Some("drums"));
+        let legacy = old.join("2023_guitar");
+        fs::create_dir_all(&legacy).unwrap();
+        fs::write(legacy.join("weights.ckpt"), "w").unwrap();
+        let listing = |dir: &Path| {
+            let mut files: Vec<(PathBuf, std::time::SystemTime)> = fs::read_dir(dir).unwrap()
+                .map(|entry| entry.unwrap().path())
+                .map(|path| (path.clone(), fs::metadata(&path).unwrap().modified().unwrap()))
+                .collect();
+            files.sort();
+            files
+        };
+        let before = listing(&legacy);
+
+        let history_path = project.path().join(DEFAULT_HISTORY_FILE);
+        let mut history = RunHistory::load(&history_path).unwrap();
+        let summary = history.import(&old).unwrap();
+        assert_eq!(summary, ImportSummary { added: 2, known: 0, needs_review: 1 });
+        let index = history.filter("imported").pop().unwrap();
+        assert_eq!(history.entries()[index].status(), "imported ?");
+        history.set_model(index, ModelType::HtDemucs).unwrap();
+        history.set_label(index, "guitar ").unwrap();
+        history.set_notes(index, "from the old box").unwrap();
+        assert!(history.set_label(history.filter("drums")[0], "x").is_err());
+
+        let mut history = RunHistory::load(&history_path).unwrap();
+        assert_eq!(history.import(&old).unwrap(), ImportSummary { added: 0, known: 2, needs_review: 0 });
+        assert_eq!(history.entries().len(), 2);
+        let entry = &history.entries()[index];
+        assert_eq!((entry.model_type.clone(), entry.label.as_deref(), entry.status().as_str()), (ModelType::HtDemucs, Some("guitar"), "imported"));
+        assert_eq!(history.locate(index).unwrap(), std::path::absolute(&legacy).unwrap());
+        assert_eq!(listing(&legacy), before);
+    }
+}
@@ -0,0 +1,235 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::bakeoff::BAKEOFF_FILE;
+use crate::model::ModelType;
+use crate::run_dir::RUN_FILE;
+
+/// Weight files train.py and the usual pretrained downloads use.
+const CHECKPOINT_EXTENSIONS: &[&str] = &["ckpt", "chpt", "th", "bin", "pt", "pth"];
+
+/// What could be recovered about a results folder from before run
+/// directories existed. Everything here is a guess from file names and
+/// modification times.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ImportedMetadata {
+    /// Model types the checkpoint and config names point at. Anything but
+    /// exactly one needs the user to pick; their pick replaces the guesses.
+    #[serde(default)]
+    pub model_candidates: Vec<ModelType>,
+    pub best_sdr: Option<f64>,
+    pub best_checkpoint: Option<String>,
+    pub config: Option<String>,
+}
+
+impl ImportedMetadata {
+    pub fn needs_review(&self) -> bool {
+        self.model_candidates.len() != 1
+    }
+}
+
+/// A results folder found by `scan`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Found {
+    /// Written by the TUI; imported from its `run.json`.
+    Run(PathBuf),
+    /// A bake-off folder with `bakeoff.json`.
+    BakeOff(PathBuf),
+    /// Only checkpoints and maybe a config.
+    Legacy(LegacyRun),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegacyRun {
+    pub path: PathBuf,
+    /// Folder path relative to the scanned root, or the root's own name.
+    pub name: String,
+    /// Oldest and newest modification time of the files in the folder.
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub metadata: ImportedMetadata,
+}
+
+/// Walks `root` for folders that hold checkpoints or TUI metadata. Only
+/// reads: nothing under `root` is created, changed or touched.
+pub fn scan(root: &Path) -> Result<Vec<Found>> {
+    if !root.is_dir() {
+        anyhow::bail!("{} is not a folder", root.display());
+    }
+    let mut found = vec![];
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        if dir.join(RUN_FILE).is_file() {
+            found.push(Found::Run(dir));
+            continue;
+        }
+        if dir.join(BAKEOFF_FILE).is_file() {
+            found.push(Found::BakeOff(dir));
+            continue;
+        }
+
+        let mut files = vec![];
+        let entries = fs::read_dir(&dir)
+            .with_context(|| format!("Failed to read {}", dir.display()))?;
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            // Symlinks are not followed, so a link back up can't loop.
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                pending.push(entry.path());
+            } else if file_type.is_file() {
+                files.push(entry.path());
+            }
+        }
+        if let Some(run) = legacy_run(root, &dir, files) {
+            found.push(Found::Legacy(run));
+        }
+    }
+    found.sort_by_key(|found| match found {
+        Found::Run(path) | Found::BakeOff(path) => path.clone(),
+        Found::Legacy(run) => run.path.clone(),
+    });
+    Ok(found)
+}
+
+fn legacy_run(root: &Path, dir: &Path, mut files: Vec<PathBuf>) -> Option<LegacyRun> {
+    files.sort();
+    let checkpoints: Vec<&PathBuf> = files.iter().filter(|path| has_extension(path, CHECKPOINT_EXTENSIONS)).collect();
+    if checkpoints.is_empty() {
+        return None;
+    }
+    let config = files.iter().find(|path| has_extension(path, &["yaml", "yml"]));
+
+    let checkpoint_pattern = Regex::new(r"^model_(.+?)_ep_\d+").unwrap();
+    let mut candidates: Vec<ModelType> = vec![];
+    for checkpoint in &checkpoints {
+        let name = file_name(checkpoint);
+        if let Some(captures) = checkpoint_pattern.captures(&name)
+            && let Some(model) = ModelType::all_models().into_iter().find(|model| model.key() == &captures[1])
+            && !candidates.contains(&model)
+        {
+            candidates.push(model);
+        }
+    }
+    if candidates.is_empty() {
+        // Files like `config_vocals_mel_band_roformer.yaml` or
+        // `scnet_masked_last.ckpt`: take the longest model key each name
+        // contains, so `scnet_masked` doesn't also count as `scnet`.
+        for path in config.into_iter().chain(checkpoints.iter().copied()) {
+            let name = file_name(path).to_lowercase();
+            let longest = ModelType::all_models().into_iter()
+                .filter(|model| contains_word(&name, model.key()))
+                .max_by_key(|model| model.key().len());
+            if let Some(model) = longest
+                && !candidates.contains(&model)
+            {
+                candidates.push(model);
+            }
+        }
+    }
+
+    let best = checkpoints.iter()
+        .filter_map(|checkpoint| Some((sdr_from_name(&file_name(checkpoint))?, *checkpoint)))
+        .max_by(|a, b| a.0.total_cmp(&b.0));
+
+    let times: Vec<DateTime<Utc>> = files.iter()
+        .filter_map(|path| fs::metadata(path).and_then(|metadata| metadata.modified()).ok())
+        .map(DateTime::<Utc>::from)
+        .collect();
+    let fallback = DateTime::<Utc>::from(SystemTime::UNIX_EPOCH);
+    let name = match dir.strip_prefix(root) {
+        Ok(relative) if !relative.as_os_str().is_empty() => relative.to_string_lossy().to_string(),
+        _ => file_name(dir),
+    };
+
+    Some(LegacyRun {
+        path: dir.to_path_buf(),
+        name,
+        started_at: times.iter().min().copied().unwrap_or(fallback),
+        finished_at: times.iter().max().copied().unwrap_or(fallback),
+        metadata: ImportedMetadata {
+            model_candidates: candidates,
+            best_sdr: best.map(|(sdr, _)| sdr),
+            best_checkpoint: best.map(|(_, path)| path.to_string_lossy().to_string()),
+            config: config.map(|path| path.to_string_lossy().to_string()),
+        },
+    })
+}
+
+/// SDR in a train.py checkpoint name: `..._sdr_9.1234.ckpt`, or the mean of
+/// the per-stem `vocals_sdr_9.1234_std_..__other_sdr_...` form.
+pub fn sdr_from_name(name: &str) -> Option<f64> {
+    let pattern = Regex::new(r"(?i)(?:^|_)sdr_(-?\d+(?:\.\d+)?)").unwrap();
+    let values: Vec<f64> = pattern.captures_iter(name)
+        .filter_map(|captures| captures[1].parse().ok())
+        .collect();
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+fn contains_word(name: &str, key: &str) -> bool {
+    name.match_indices(key).any(|(start, _)| {
+        let before = name[..start].chars().next_back();
+        let after = name[start + key.len()..].chars().next();
+        before.is_none_or(|c| !c.is_ascii_alphanumeric()) && after.is_none_or(|c| !c.is_ascii_alphanumeric())
+    })
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .is_some_and(|extension| extensions.iter().any(|e| extension.eq_ignore_ascii_case(e)))
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().unwrap_or_default().to_string_lossy().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sdr_is_read_from_train_py_names() {
+        assert_eq!(sdr_from_name("model_scnet_ep_12_sdr_9.5123.ckpt"), Some(9.5123));
+        let per_stem = "model_mdx23c_ep_3_vocals_sdr_10.0000_std_1.0000__other_sdr_8.0000_std_2.0000.ckpt";
+        assert_eq!(sdr_from_name(per_stem), Some(9.0));
+        assert_eq!(sdr_from_name("model_scnet_ep_12_l1_freq_0.0312.ckpt"), None);
+        assert_eq!(sdr_from_name("last_scnet.ckpt"), None);
+    }
+
+    #[test]
+    fn legacy_folders_are_inferred_from_file_names() {
+        let root = tempfile::tempdir().unwrap();
+        let clear = root.path().join("2023/vocals");
+        fs::create_dir_all(&clear).unwrap();
+        fs::write(clear.join("model_scnet_masked_ep_3_sdr_8.1000.ckpt"), "w").unwrap();
+        fs::write(clear.join("model_scnet_masked_ep_9_sdr_9.2500.ckpt"), "w").unwrap();
+        fs::write(clear.join("config.yaml"), "audio: {}\n").unwrap();
+
+        let unclear = root.path().join("old");
+        fs::create_dir_all(unclear.join("logs")).unwrap();
+        fs::write(unclear.join("weights.ckpt"), "w").unwrap();
+        fs::write(unclear.join("notes.txt"), "no checkpoints here").unwrap();
+
+        let found = scan(root.path()).unwrap();
+        let [Found::Legacy(clear_run), Found::Legacy(unclear_run)] = found.as_slice() else {
+            panic!("{:?}", found);
+        };
+        assert_eq!(clear_run.name, Path::new("2023").join("vocals").to_string_lossy());
+        assert_eq!(clear_run.metadata.model_candidates, [ModelType::ScNetMasked]);
+        assert_eq!(clear_run.metadata.best_sdr, Some(9.25));
+        assert!(clear_run.metadata.best_checkpoint.as_ref().unwrap().ends_with("ep_9_sdr_9.2500.ckpt"));
+        assert!(clear_run.metadata.config.as_ref().unwrap().ends_with("config.yaml"));
+        assert!(!clear_run.metadata.needs_review());
+
+        assert_eq!(unclear_run.name, "old");
+        assert!(unclear_run.metadata.model_candidates.is_empty());
+        assert!(unclear_run.metadata.needs_review());
+        assert!(unclear_run.started_at <= unclear_run.finished_at);
+    }
+}
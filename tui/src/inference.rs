@@ -1,64 +1,347 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use tokio::process::Command;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::io::BufReader;
+use tokio::sync::mpsc;
 
-use crate::model::{InferenceConfig, InferenceResult};
+use crate::audio;
+use crate::config::{model_audio, write_normalize_override};
+use crate::dedup::{self, DuplicateScan};
+use crate::log_buffer::Severity;
+use crate::loudness;
+use crate::manifest::{ManifestTrack, ResultsManifest};
+use crate::model::{
+    CommandSpec, InferenceConfig, InferenceResult, InputAdjustment, InputNote, LoudnessMatch, PostProcessing, SidecarModel,
+    SidecarStem, TrackSidecar, SIDECAR_SCHEMA_VERSION,
+};
+use crate::process::{ProcessState, StderrTail};
+use crate::project::Scripts;
+use crate::reconstruction;
+use crate::remote::{self, RemoteHost, SSH_FAILED};
+use crate::runner::RunnerEnv;
+use crate::scratch::{self, Scratch};
+use crate::selection;
+use crate::staging::Staging;
+use crate::step_progress::{record, Segments, StepProgress};
+use crate::stem_names;
+use crate::training::piped_output;
 
 pub struct InferenceManager {
     process: Option<tokio::process::Child>,
+    state: ProcessState,
+    scratch: Scratch,
+    /// Inference entry point of the checkout, e.g. `inference.py`.
+    script: String,
+    /// Where the process's output lines go, tagged with their stream;
+    /// printed when unset.
+    output: Option<mpsc::UnboundedSender<(Severity, String)>>,
+    /// The latest tqdm bar of the run.
+    step: Arc<Mutex<Option<StepProgress>>>,
+    runner: RunnerEnv,
+    /// Where runs go over ssh, with the tag of the current one.
+    remote: Option<(RemoteHost, String)>,
+}
+
+impl Default for InferenceManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl InferenceManager {
     pub fn new() -> Self {
         InferenceManager {
             process: None,
+            state: ProcessState::NotStarted,
+            scratch: Scratch::default(),
+            script: Scripts::default().inference,
+            output: None,
+            step: Arc::new(Mutex::new(None)),
+            runner: RunnerEnv::default(),
+            remote: None,
         }
     }
 
+    pub fn set_scripts(&mut self, scripts: &Scripts) {
+        self.script = scripts.inference.clone();
+    }
+
+    pub fn set_runner(&mut self, runner: RunnerEnv) {
+        self.runner = runner;
+    }
+
+    /// Runs from now on go to `host` over ssh; `None` runs them here.
+    pub fn set_remote(&mut self, host: Option<RemoteHost>) {
+        self.remote = host.map(|host| (host, String::new()));
+    }
+
+    pub fn set_scratch(&mut self, scratch: Scratch) {
+        self.scratch = scratch;
+    }
+
+    /// Sends the output of runs from now on to `output`, line by line,
+    /// instead of printing it.
+    pub fn set_output(&mut self, output: mpsc::UnboundedSender<(Severity, String)>) {
+        self.output = Some(output);
+    }
+
+    /// How far the run is through inference.py's tqdm loop.
+    pub fn step_progress(&self) -> Arc<Mutex<Option<StepProgress>>> {
+        self.step.clone()
+    }
+
     pub async fn run_inference(
         &mut self,
         config: &InferenceConfig,
     ) -> Result<InferenceResult> {
-        let mut cmd = Command::new("python");
-        cmd.arg("inference.py")
-            .arg("--model_type")
-            .arg(config.model_type.key())
-            .arg("--config_path")
-            .arg(&config.config_path)
-            .arg("--start_check_point")
-            .arg(&config.start_checkpoint)
-            .arg("--input_folder")
-            .arg(&config.input_folder)
-            .arg("--store_dir")
-            .arg(&config.store_dir);
-
-        let mut child = cmd.spawn()
-            .context("Failed to spawn inference process")?;
-
-        let stdout = child.stdout.take().context("Failed to capture stdout")?;
-        let stderr = child.stderr.take().context("Failed to capture stderr")?;
+        if let Some((host, tag)) = self.remote.as_mut() {
+            *tag = remote::run_tag("inference");
+            let command = host.command(&self.script, &build_inference_command(config), tag);
+            let host = host.ssh_destination.clone();
+            let started = Instant::now();
+            let mut result = self.run_command(config, command.command()).await?;
+            result.duration = Some(started.elapsed().as_secs_f64());
+            if matches!(&self.state, ProcessState::Exited(status) if status.code() == Some(SSH_FAILED)) {
+                bail!("The ssh connection to {} failed or was lost: {}", host, result.error_message.unwrap_or_default());
+            }
+            return Ok(result);
+        }
+        let config = &config.in_root(self.runner.root());
+        // Before staging, which can take a while on a big folder.
+        let python = self.runner.python()?;
+        let model = model_audio(&config.config_path).unwrap_or_default();
+        let input_folder = PathBuf::from(&config.input_folder);
+        let rate_policy = config.sample_rate_policy;
+        let channel_policy = config.channel_policy;
+        let job_id = scratch::job_id("inference");
+        let (scratch, staging_job) = (self.scratch.clone(), job_id.clone());
+        let store_dir = PathBuf::from(&config.store_dir);
+        let (model_type, checkpoint) = (config.model_type.clone(), config.start_checkpoint.clone());
+        let duplicate_policy = config.duplicate_policy;
+        let deselected = selection::deselected_notes(&input_folder, &config.excluded_inputs);
+        let (staging, duplicates) = tokio::task::spawn_blocking(move || -> Result<(Staging, DuplicateScan)> {
+            let previous = ResultsManifest::load(&store_dir)?
+                .filter(|manifest| manifest.same_model(&model_type, &checkpoint));
+            let duplicates = dedup::find_duplicates(&input_folder, &store_dir, previous.as_ref(), &deselected)?;
+            let mut held_back = deselected;
+            held_back.extend(duplicates.notes(duplicate_policy));
+            let staging = Staging::prepare(&scratch, &staging_job, &input_folder, model, rate_policy, channel_policy, held_back)?;
+            Ok((staging, duplicates))
+        })
+        .await
+        .context("Input staging task failed")??;
+
+        // inference.py only takes normalization from the model config, so an
+        // override means handing it a patched copy.
+        let mut run_config = config.clone();
+        let override_dir = match config.normalize {
+            Some(normalize) => {
+                let written = self.scratch.create(&job_id, "config").and_then(|dir| {
+                    let path = dir.path().join("config.yaml");
+                    let written = write_normalize_override(&config.config_path, normalize, &path);
+                    if written.is_err() {
+                        let _ = dir.remove();
+                    }
+                    written.map(|_| (dir, path))
+                });
+                match written {
+                    Ok((dir, path)) => {
+                        run_config.config_path = path.to_string_lossy().to_string();
+                        Some(dir)
+                    }
+                    Err(e) => {
+                        staging.cleanup()?;
+                        return Err(e);
+                    }
+                }
+            }
+            None => None,
+        };
+
+        let result = if staging.is_empty() {
+            // Every input was a duplicate; there is nothing to separate.
+            Ok(InferenceResult {
+                input_file: config.input_folder.clone(),
+                output_dir: config.store_dir.clone(),
+                duration: None,
+                success: true,
+                error_message: None,
+                error_summary: None,
+                input_notes: vec![],
+                loudness: vec![],
+                reconstruction: vec![],
+            })
+        } else {
+            let started = Instant::now();
+            self.run_staged(&run_config, &python, &staging).await.map(|mut result| {
+                result.duration = Some(started.elapsed().as_secs_f64());
+                result
+            })
+        };
+        // The outputs are there either way; without a manifest the preview,
+        // the bake-off and the checks below can't locate the original inputs,
+        // so a failure is reported rather than failing the run.
+        let manifest = match &result {
+            Ok(result) if result.success => write_manifest(config, &staging, &duplicates).err(),
+            _ => None,
+        };
+        let cleanup = staging.cleanup();
+        if let Some(dir) = &override_dir {
+            let _ = dir.remove();
+        }
+        let mut result = result?;
+        cleanup?;
+        if let Some(e) = manifest {
+            add_failure(&mut result, format!("Writing the results manifest failed: {:#}", e));
+        }
+
+        result.input_notes = staging.notes.clone();
+        if result.success
+            && let Some(naming) = config.stem_naming.clone()
+        {
+            let store_dir = PathBuf::from(&config.store_dir);
+            let renamed = tokio::task::spawn_blocking(move || {
+                let mut manifest = ResultsManifest::load(&store_dir)?
+                    .context("No results manifest to rename stems in")?;
+                let plan = stem_names::plan(&store_dir, &manifest, &naming);
+                stem_names::apply(&store_dir, &mut manifest, &plan)
+            })
+            .await
+            .context("Stem renaming task failed")?;
+            // The stems are there under their old names, so this is reported
+            // rather than failing the run.
+            if let Err(e) = renamed {
+                add_failure(&mut result, format!("Renaming stems failed: {:#}", e));
+            }
+        }
+        // Before loudness matching, which would leave the sum off by its gains.
+        if result.success
+            && let Some(threshold) = config.reconstruction_threshold_db
+        {
+            let store_dir = PathBuf::from(&config.store_dir);
+            let verified = tokio::task::spawn_blocking(move || {
+                let manifest = ResultsManifest::load(&store_dir)?
+                    .context("No results manifest to verify the stems against")?;
+                reconstruction::verify(&store_dir, &manifest, threshold)
+            })
+            .await
+            .context("Stem verification task failed")?;
+            match verified {
+                Ok(report) => result.reconstruction = report.tracks,
+                Err(e) => add_failure(&mut result, format!("Verifying stems failed: {:#}", e)),
+            }
+        }
+        if result.success && config.loudness_match != LoudnessMatch::Off {
+            let store_dir = PathBuf::from(&config.store_dir);
+            let mode = config.loudness_match;
+            let matched = tokio::task::spawn_blocking(move || {
+                let manifest = ResultsManifest::load(&store_dir)?
+                    .context("No results manifest to match loudness against")?;
+                loudness::match_input_loudness(&store_dir, &manifest, mode)
+            })
+            .await
+            .context("Loudness matching task failed")?;
+
+            // The stems are there either way, so report this rather than failing the run.
+            match matched {
+                Ok(report) => result.loudness = report.tracks,
+                Err(e) => add_failure(&mut result, format!("Loudness matching failed: {:#}", e)),
+            }
+        }
+        if result.success {
+            let (config, batch) = (config.clone(), result.clone());
+            let written = tokio::task::spawn_blocking(move || write_sidecars(&config, &batch))
+                .await
+                .context("Sidecar task failed")?;
+            if let Err(e) = written {
+                add_failure(&mut result, format!("Writing track sidecars failed: {:#}", e));
+            }
+        }
+        Ok(result)
+    }
+
+    async fn run_staged(
+        &mut self,
+        config: &InferenceConfig,
+        python: &str,
+        staging: &Staging,
+    ) -> Result<InferenceResult> {
+        let mut cmd = CommandSpec::new(python);
+        cmd.arg(self.runner.script(&self.script));
+        for arg in inference_args(config, staging.input_folder()) {
+            cmd.arg(arg);
+        }
+        self.run_command(config, self.runner.in_project(cmd).command()).await
+    }
+
+    /// Runs `cmd`, sending its output on as it comes.
+    async fn run_command(&mut self, config: &InferenceConfig, mut cmd: Command) -> Result<InferenceResult> {
+        // Dropping the run, e.g. to cancel it, takes the process with it.
+        let mut child = match cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).kill_on_drop(true).spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                self.state = ProcessState::Failed(e.to_string());
+                return Err(e).context("Failed to spawn inference process");
+            }
+        };
+        self.state = ProcessState::Running;
+        let (stdout, stderr) = piped_output(&mut child, "inference")?;
+        let child = self.process.insert(child);
 
         let stdout_reader = BufReader::new(stdout);
         let stderr_reader = BufReader::new(stderr);
 
+        let (output, step) = (self.output.clone(), self.step.clone());
         let stderr_task = tokio::spawn(async move {
-            let mut lines = stderr_reader.lines();
-            while let Ok(Some(line)) = lines.next_line().await {
-                eprintln!("Inference error: {}", line);
+            let mut lines = Segments::new(stderr_reader);
+            let mut tail = StderrTail::new();
+            while let Some(line) = lines.next_segment().await.context("Failed to read inference stderr")? {
+                if record(&step, &line) {
+                    continue;
+                }
+                tail.push(line.clone());
+                match &output {
+                    Some(output) => {
+                        let _ = output.send((Severity::Error, line));
+                    }
+                    None => eprintln!("Inference error: {}", line),
+                }
             }
+            Ok::<_, anyhow::Error>(tail)
         });
 
+        let (output, step) = (self.output.clone(), self.step.clone());
         let stdout_task = tokio::spawn(async move {
-            let mut lines = stdout_reader.lines();
-            while let Ok(Some(line)) = lines.next_line().await {
-                println!("{}", line);
+            let mut lines = Segments::new(stdout_reader);
+            while let Some(line) = lines.next_segment().await.context("Failed to read inference stdout")? {
+                if record(&step, &line) {
+                    continue;
+                }
+                match &output {
+                    Some(output) => {
+                        let _ = output.send((Severity::Info, line));
+                    }
+                    None => println!("{}", line),
+                }
             }
+            Ok::<_, anyhow::Error>(())
         });
 
-        let status = child.wait().await.context("Failed to wait for inference process")?;
+        let status = match child.wait().await {
+            Ok(status) => status,
+            Err(e) => {
+                self.state = ProcessState::Failed(e.to_string());
+                return Err(e).context("Failed to wait for inference process");
+            }
+        };
+        self.state = ProcessState::Exited(status);
+        self.process = None;
 
-        stdout_task.await.context("stdout task failed")?;
-        stderr_task.await.context("stderr task failed")?;
+        stdout_task.await.context("stdout task failed")??;
+        let tail = stderr_task.await.context("stderr task failed")??;
 
         if status.success() {
             Ok(InferenceResult {
@@ -67,6 +350,10 @@ impl InferenceManager {
                 duration: None,
                 success: true,
                 error_message: None,
+                error_summary: None,
+                input_notes: vec![],
+                loudness: vec![],
+                reconstruction: vec![],
             })
         } else {
             Ok(InferenceResult {
@@ -74,19 +361,204 @@ impl InferenceManager {
                 output_dir: config.store_dir.clone(),
                 duration: None,
                 success: false,
-                error_message: Some(format!("Process exited with code: {}", status.code().unwrap_or(-1))),
+                error_message: Some(if tail.is_empty() {
+                    format!("Process {}", self.state.describe())
+                } else {
+                    format!("Process {}\n{}", self.state.describe(), tail.text())
+                }),
+                error_summary: tail.exception(),
+                input_notes: vec![],
+                loudness: vec![],
+                reconstruction: vec![],
             })
         }
     }
 
     pub async fn stop_inference(&mut self) -> Result<()> {
+        if let Some((host, tag)) = &self.remote
+            && self.process.is_some()
+        {
+            host.signal_command(&self.script, tag, "INT").command().output().await
+                .with_context(|| format!("Failed to reach {} to stop the run", host.ssh_destination))?;
+        }
         if let Some(mut child) = self.process.take() {
             child.kill().await.context("Failed to stop inference process")?;
+            if let Ok(Some(status)) = child.try_wait() {
+                self.state = ProcessState::Exited(status);
+            }
         }
         Ok(())
     }
 
-    pub fn is_running(&self) -> bool {
-        self.process.is_some()
+    /// The inference process as of now, checked without waiting.
+    pub fn status(&mut self) -> &ProcessState {
+        self.state.update(self.process.as_mut());
+        &self.state
+    }
+
+    pub fn is_running(&mut self) -> bool {
+        *self.status() == ProcessState::Running
+    }
+}
+
+/// inference.py's arguments for `config`, for showing before a run. The
+/// run itself reads its inputs from a staging folder instead.
+pub fn build_inference_command(config: &InferenceConfig) -> Vec<String> {
+    inference_args(config, Path::new(&config.input_folder))
+}
+
+fn inference_args(config: &InferenceConfig, input_folder: &Path) -> Vec<String> {
+    let mut args = vec![
+        "--model_type".to_string(),
+        config.model_type.key().to_string(),
+        "--config_path".to_string(),
+        config.config_path.clone(),
+        "--start_check_point".to_string(),
+        config.start_checkpoint.clone(),
+        "--input_folder".to_string(),
+        input_folder.to_string_lossy().to_string(),
+        "--store_dir".to_string(),
+        config.store_dir.clone(),
+    ];
+    if config.use_tta {
+        args.push("--use_tta".to_string());
+    }
+    if config.flac_file {
+        args.push("--flac_file".to_string());
+    }
+    args.extend(config.extra_args.iter().cloned());
+    args
+}
+
+/// The command `run_inference` would run for `config`, as typed into a shell.
+pub fn preview_inference_command(config: &InferenceConfig, python: &str, script: &str) -> String {
+    let mut cmd = CommandSpec::new(python);
+    cmd.arg(script);
+    for arg in build_inference_command(config) {
+        cmd.arg(arg);
+    }
+    cmd.display()
+}
+
+/// Adds `failed` to what went wrong after the run itself succeeded; the
+/// run stays a success, since its stems are there.
+fn add_failure(result: &mut InferenceResult, failed: String) {
+    result.error_message = Some(match result.error_message.take() {
+        Some(earlier) => format!("{}; {}", earlier, failed),
+        None => failed,
+    });
+}
+
+/// Records which original input each output folder came from, looking
+/// through the staging folder when inputs were rewritten, and links or
+/// copies stems for duplicates held back from the run.
+fn write_manifest(config: &InferenceConfig, staging: &Staging, duplicates: &DuplicateScan) -> Result<()> {
+    let store_dir = Path::new(&config.store_dir);
+    std::fs::create_dir_all(store_dir).context("Failed to create store_dir")?;
+    let mut manifest = ResultsManifest::scan(store_dir, staging.input_folder())?;
+    manifest.model_type = Some(config.model_type.clone());
+    manifest.start_checkpoint = Some(config.start_checkpoint.clone());
+
+    for track in manifest.tracks.iter_mut() {
+        if let Some(original) = staging.original_input(&track.name) {
+            track.input_path = original.to_string_lossy().to_string();
+        }
+        track.adjustments = staging.notes.iter()
+            .filter(|note| note.input == track.input_path)
+            .map(|note| note.adjustment.clone())
+            .collect();
+        track.content_hash = duplicates.hash_of(Path::new(&track.input_path)).cloned();
+    }
+    manifest.excluded = staging.excluded().cloned().collect();
+
+    if let Some(previous) = ResultsManifest::load(store_dir)? {
+        manifest.keep_previous(previous, store_dir);
+    }
+    dedup::reuse_outputs(store_dir, &mut manifest, &staging.notes, duplicates, config.duplicate_policy)?;
+    manifest.save(store_dir)
+}
+
+/// Writes a `TrackSidecar` next to the outputs of every track separated
+/// from this run's input folder. Tracks whose stems were reused from a
+/// duplicate get no share of the run's time.
+fn write_sidecars(config: &InferenceConfig, batch: &InferenceResult) -> Result<()> {
+    let store_dir = Path::new(&config.store_dir);
+    let Some(manifest) = ResultsManifest::load(store_dir)? else {
+        return Ok(());
+    };
+    let input_folder = Path::new(&config.input_folder);
+    let tracks: Vec<&ManifestTrack> = manifest.tracks.iter()
+        .filter(|track| Path::new(&track.input_path).parent() == Some(input_folder))
+        .collect();
+    let reused = |track: &ManifestTrack| {
+        track.adjustments.iter().any(|adjustment| matches!(adjustment, InputAdjustment::Reused { .. }))
+    };
+    let separated = tracks.iter().filter(|track| !reused(track)).count();
+    let share = batch.duration.filter(|_| separated > 0).map(|secs| secs / separated as f64);
+
+    for track in tracks {
+        let sample_rate = track.stems.first()
+            .and_then(|stem| audio::probe(&store_dir.join(&stem.path)).ok())
+            .map(|info| info.sample_rate);
+        let sidecar = TrackSidecar {
+            schema_version: SIDECAR_SCHEMA_VERSION,
+            track: track.name.clone(),
+            result: InferenceResult {
+                input_file: track.input_path.clone(),
+                output_dir: store_dir.join(&track.name).to_string_lossy().to_string(),
+                duration: if reused(track) { None } else { share },
+                success: true,
+                error_message: None,
+                error_summary: None,
+                input_notes: track.adjustments.iter()
+                    .map(|adjustment| InputNote { input: track.input_path.clone(), adjustment: adjustment.clone() })
+                    .collect(),
+                loudness: batch.loudness.iter().filter(|loudness| loudness.track == track.name).cloned().collect(),
+                reconstruction: batch.reconstruction.iter().filter(|check| check.track == track.name).cloned().collect(),
+            },
+            model: SidecarModel {
+                model_type: config.model_type.clone(),
+                config_path: config.config_path.clone(),
+                checkpoint: config.start_checkpoint.clone(),
+            },
+            stems: track.stems.iter()
+                .map(|stem| SidecarStem { name: stem.name.clone(), path: stem.path.clone() })
+                .collect(),
+            sample_rate,
+            post_processing: PostProcessing {
+                normalize: config.normalize,
+                use_tta: config.use_tta,
+                loudness_match: config.loudness_match,
+                format: if config.flac_file { "flac" } else { "wav" }.to_string(),
+            },
+            mix: None,
+        };
+        sidecar.save(store_dir)
+            .with_context(|| format!("Failed to write the sidecar of {}", track.name))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ModelType;
+
+    #[test]
+    fn command_quotes_paths_with_spaces() {
+        let mut config = InferenceConfig::new(ModelType::MelBandRoformer);
+        config.config_path = "configs/vocals.yaml".to_string();
+        config.start_checkpoint = "results/run 1/model.ckpt".to_string();
+        config.input_folder = "/music/My Album".to_string();
+        config.store_dir = "separated".to_string();
+        config.flac_file = true;
+        assert_eq!(build_inference_command(&config).last().map(String::as_str), Some("--flac_file"));
+        config.extra_args = vec!["--lora_checkpoint".to_string(), "lora/my vocals.ckpt".to_string()];
+        assert_eq!(
+            preview_inference_command(&config, "python3", "inference.py"),
+            "python3 inference.py --model_type mel_band_roformer --config_path configs/vocals.yaml \
+             --start_check_point 'results/run 1/model.ckpt' --input_folder '/music/My Album' --store_dir separated --flac_file \
+             --lora_checkpoint 'lora/my vocals.ckpt'"
+        );
     }
 }
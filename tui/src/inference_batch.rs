@@ -0,0 +1,248 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::model::{InferenceConfig, InferenceResult};
+use crate::selection::TrackSelection;
+
+pub const DEFAULT_BATCH_FILE: &str = "tui_inference_batch.yaml";
+
+/// An input folder, or a single audio file, separated as part of a batch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchItem {
+    pub input: String,
+    /// Set once the item is done, however it went; items without one are
+    /// still to go.
+    #[serde(default)]
+    pub result: Option<InferenceResult>,
+}
+
+impl BatchItem {
+    /// The folder or file name, which is also its output subfolder: the
+    /// file's without the extension.
+    pub fn name(&self) -> String {
+        let path = Path::new(&self.input);
+        let name = if path.is_file() { path.file_stem() } else { path.file_name() };
+        name.map(|name| name.to_string_lossy().into_owned()).unwrap_or_else(|| self.input.clone())
+    }
+
+    pub fn failed(&self) -> bool {
+        self.result.as_ref().is_some_and(|result| !result.success)
+    }
+
+    /// The inference config for this item: the batch's settings, with the
+    /// item as input and its own subfolder of `base.store_dir` as output.
+    /// A single file runs as its folder with every other track left out.
+    pub fn config(&self, base: &InferenceConfig) -> Result<InferenceConfig> {
+        let path = Path::new(&self.input);
+        let mut config = base.clone();
+        config.store_dir = Path::new(&base.store_dir).join(self.name()).to_string_lossy().into_owned();
+        config.excluded_inputs = vec![];
+        if path.is_dir() {
+            config.input_folder = self.input.clone();
+        } else if path.is_file() {
+            let folder = path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let selection = TrackSelection::scan(folder, &[])?;
+            if !selection.tracks().iter().any(|track| track.name() == name) {
+                bail!("{} is not an audio file inference can read", self.input);
+            }
+            config.input_folder = folder.to_string_lossy().into_owned();
+            config.excluded_inputs = selection.tracks().iter().map(|track| track.name()).filter(|track| *track != name).collect();
+        } else {
+            bail!("{} doesn't exist", self.input);
+        }
+        Ok(config)
+    }
+
+    /// Name, duration, status and output folder, for the summary.
+    pub fn cells(&self) -> [String; 4] {
+        let (duration, status, output) = match &self.result {
+            Some(result) => (
+                result.duration.map_or("-".to_string(), |secs| format!("{}m {:02}s", secs as u64 / 60, secs as u64 % 60)),
+                match (result.error_line(), result.success) {
+                    (Some(error), false) => format!("failed: {}", error),
+                    (None, false) => "failed".to_string(),
+                    (_, true) => "done".to_string(),
+                },
+                result.output_dir.clone(),
+            ),
+            None => ("-".to_string(), "waiting".to_string(), String::new()),
+        };
+        [self.name(), duration, status, output]
+    }
+}
+
+/// Inputs separated one after another with the same settings, kept in a
+/// YAML file so a crashed session can go on with the items still to go.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InferenceBatch {
+    /// The Inference screen's settings when the batch last started, for
+    /// resuming it before a model is picked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<InferenceConfig>,
+    #[serde(default)]
+    items: Vec<BatchItem>,
+}
+
+impl InferenceBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the batch at `path`; a missing file is an empty batch.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            return Ok(Self::new());
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read inference batch {}", path.display()))?;
+        serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse inference batch {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_yaml::to_string(self).context("Failed to serialize inference batch")?;
+        fs::write(path, content)
+            .with_context(|| format!("Failed to write inference batch {}", path.display()))
+    }
+
+    pub fn items(&self) -> &[BatchItem] {
+        &self.items
+    }
+
+    /// Adds `input` unless it is already in the batch; false if it was.
+    pub fn push(&mut self, input: &str) -> bool {
+        if self.items.iter().any(|item| item.input == input) {
+            return false;
+        }
+        self.items.push(BatchItem { input: input.to_string(), result: None });
+        true
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<BatchItem> {
+        (index < self.items.len()).then(|| self.items.remove(index))
+    }
+
+    /// The first item still to go.
+    pub fn next_pending(&self) -> Option<usize> {
+        self.items.iter().position(|item| item.result.is_none())
+    }
+
+    pub fn record(&mut self, index: usize, result: InferenceResult) {
+        if let Some(item) = self.items.get_mut(index) {
+            item.result = Some(result);
+        }
+    }
+
+    /// Records an item that failed before inference.py could report on it.
+    pub fn record_failure(&mut self, index: usize, output_dir: &str, error: String) {
+        let Some(item) = self.items.get(index) else {
+            return;
+        };
+        let result = InferenceResult {
+            input_file: item.input.clone(),
+            output_dir: output_dir.to_string(),
+            duration: None,
+            success: false,
+            error_message: Some(error),
+            error_summary: None,
+            input_notes: vec![],
+            loudness: vec![],
+            reconstruction: vec![],
+        };
+        self.record(index, result);
+    }
+
+    /// Puts the failed items back to go again; how many there were.
+    pub fn retry_failed(&mut self) -> usize {
+        let failed: Vec<&mut BatchItem> = self.items.iter_mut().filter(|item| item.failed()).collect();
+        let count = failed.len();
+        for item in failed {
+            item.result = None;
+        }
+        count
+    }
+
+    /// E.g. "3 of 4 done, 1 failed".
+    pub fn summary(&self) -> String {
+        let done = self.items.iter().filter(|item| item.result.is_some()).count();
+        let failed = self.items.iter().filter(|item| item.failed()).count();
+        format!("{} of {} done, {} failed", done, self.items.len(), failed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::write_wav;
+    use crate::model::ModelType;
+
+    fn result(success: bool) -> InferenceResult {
+        InferenceResult {
+            input_file: "in".to_string(),
+            output_dir: "out".to_string(),
+            duration: Some(125.0),
+            success,
+            error_message: (!success).then(|| "Process exited with 1".to_string()),
+            error_summary: None,
+            input_notes: vec![],
+            loudness: vec![],
+            reconstruction: vec![],
+        }
+    }
+
+    #[test]
+    fn items_get_their_own_output_folder_and_files_run_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let album = dir.path().join("album");
+        fs::create_dir(&album).unwrap();
+        for name in ["a.wav", "b.wav"] {
+            write_wav(&album.join(name), 44_100, &[vec![0.0; 441], vec![0.0; 441]]).unwrap();
+        }
+        let mut base = InferenceConfig::new(ModelType::ScNet);
+        base.store_dir = dir.path().join("out").to_string_lossy().into_owned();
+        base.excluded_inputs = vec!["stale.wav".to_string()];
+
+        let mut batch = InferenceBatch::new();
+        assert!(batch.push(&album.to_string_lossy()));
+        assert!(!batch.push(&album.to_string_lossy()), "added once");
+        batch.push(&album.join("b.wav").to_string_lossy());
+        batch.push(&dir.path().join("gone").to_string_lossy());
+
+        let folder = batch.items()[0].config(&base).unwrap();
+        assert_eq!(Path::new(&folder.store_dir), dir.path().join("out").join("album"));
+        assert!(folder.excluded_inputs.is_empty());
+        let file = batch.items()[1].config(&base).unwrap();
+        assert_eq!((Path::new(&file.input_folder), file.excluded_inputs), (album.as_path(), vec!["a.wav".to_string()]));
+        assert_eq!(Path::new(&file.store_dir), dir.path().join("out").join("b"));
+        assert!(batch.items()[2].config(&base).is_err());
+    }
+
+    #[test]
+    fn failures_are_kept_for_a_retry_and_the_batch_resumes_from_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DEFAULT_BATCH_FILE);
+        let mut batch = InferenceBatch::new();
+        for input in ["one", "two", "three"] {
+            batch.push(input);
+        }
+        batch.record(0, result(false));
+        batch.record(1, result(true));
+        batch.record_failure(2, "out/three", "three doesn't exist".to_string());
+        assert_eq!(batch.items()[2].cells()[2], "failed: three doesn't exist");
+        batch.retry_failed();
+        batch.record(0, result(false));
+        assert_eq!(batch.items()[0].cells(), ["one", "2m 05s", "failed: Process exited with 1", "out"].map(String::from));
+        assert_eq!(batch.summary(), "2 of 3 done, 1 failed");
+        batch.save(&path).unwrap();
+
+        let mut resumed = InferenceBatch::load(&path).unwrap();
+        assert_eq!(resumed.next_pending(), Some(2));
+        assert_eq!(resumed.retry_failed(), 1);
+        assert_eq!(resumed.next_pending(), Some(0));
+        assert!(resumed.items()[1].result.as_ref().unwrap().success);
+        assert!(InferenceBatch::load(&dir.path().join("missing.yaml")).unwrap().items().is_empty());
+    }
+}
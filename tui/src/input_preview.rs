@@ -0,0 +1,144 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::audio::{self, AudioInfo};
+use crate::checkpoints::file_size;
+use crate::manifest::is_audio_file;
+
+/// An audio file in the input folder.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioEntry {
+    /// Relative to the scanned folder.
+    pub name: String,
+    pub size: u64,
+    /// Read from the header for wav and flac; `None` for mp3 and for files
+    /// that couldn't be read.
+    pub info: Option<AudioInfo>,
+}
+
+impl AudioEntry {
+    pub fn size_text(&self) -> String {
+        file_size(self.size)
+    }
+
+    pub fn duration_secs(&self) -> Option<f64> {
+        self.info.and_then(|info| info.duration_secs())
+    }
+
+    pub fn duration_text(&self) -> String {
+        self.duration_secs().map_or("-".to_string(), |secs| format!("{}:{:02}", secs as u64 / 60, secs as u64 % 60))
+    }
+
+    pub fn rate_text(&self) -> String {
+        self.info.map_or("-".to_string(), |info| format!("{} Hz", info.sample_rate))
+    }
+}
+
+/// What an input folder holds before inference runs on it: its wav, flac
+/// and mp3 files, with subfolders only when asked for since inference.py
+/// itself doesn't look into them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputPreview {
+    pub folder: PathBuf,
+    pub recursive: bool,
+    pub files: Vec<AudioEntry>,
+}
+
+impl InputPreview {
+    /// Scans `folder`, leaving out the top-level files named in `excluded`.
+    pub fn scan(folder: &Path, recursive: bool, excluded: &[String]) -> Result<Self> {
+        let mut files = vec![];
+        scan_into(folder, folder, recursive, excluded, &mut files)?;
+        files.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(InputPreview { folder: folder.to_path_buf(), recursive, files })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn total_secs(&self) -> f64 {
+        self.files.iter().filter_map(AudioEntry::duration_secs).sum()
+    }
+
+    /// E.g. "132 files, 9.4 hours" or "3 files, 12.5 minutes (1 without a duration)".
+    pub fn summary(&self) -> String {
+        if self.files.is_empty() {
+            let place = if self.recursive { "or its subfolders" } else { "(subfolders aren't read)" };
+            return format!("no .wav, .flac or .mp3 files in {} {}", self.folder.display(), place);
+        }
+        let secs = self.total_secs();
+        let total = if secs >= 3600.0 { format!("{:.1} hours", secs / 3600.0) } else { format!("{:.1} minutes", secs / 60.0) };
+        let unknown = self.files.iter().filter(|file| file.duration_secs().is_none()).count();
+        let unknown = if unknown > 0 { format!(" ({} without a duration)", unknown) } else { String::new() };
+        format!("{} files, {}{}", self.files.len(), total, unknown)
+    }
+}
+
+fn scan_into(root: &Path, folder: &Path, recursive: bool, excluded: &[String], files: &mut Vec<AudioEntry>) -> Result<()> {
+    for entry in fs::read_dir(folder).with_context(|| format!("Failed to read input folder {}", folder.display()))? {
+        let path = entry.context("Failed to read directory entry")?.path();
+        if path.is_dir() {
+            if recursive {
+                scan_into(root, &path, recursive, excluded, files)?;
+            }
+            continue;
+        }
+        if !is_audio_file(&path) {
+            continue;
+        }
+        let name = path.strip_prefix(root).unwrap_or(&path).to_string_lossy().into_owned();
+        if excluded.contains(&name) {
+            continue;
+        }
+        let lossless = path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ["wav", "flac"].contains(&ext.to_lowercase().as_str()));
+        files.push(AudioEntry {
+            name,
+            size: path.metadata().map(|meta| meta.len()).unwrap_or(0),
+            info: if lossless { audio::probe(&path).ok() } else { None },
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::write_wav;
+
+    #[test]
+    fn audio_files_are_listed_with_their_header_and_totals() {
+        let dir = tempfile::tempdir().unwrap();
+        write_wav(&dir.path().join("a.wav"), 44_100, &[vec![0.0; 44_100 * 90]]).unwrap();
+        write_wav(&dir.path().join("skip.wav"), 44_100, &[vec![0.0; 441]]).unwrap();
+        fs::write(dir.path().join("b.mp3"), b"not really").unwrap();
+        fs::write(dir.path().join("notes.txt"), b"").unwrap();
+        fs::create_dir(dir.path().join("disc 2")).unwrap();
+        write_wav(&dir.path().join("disc 2").join("c.wav"), 48_000, &[vec![0.0; 48_000 * 30]]).unwrap();
+
+        let preview = InputPreview::scan(dir.path(), false, &["skip.wav".to_string()]).unwrap();
+        let names: Vec<&str> = preview.files.iter().map(|file| file.name.as_str()).collect();
+        assert_eq!(names, ["a.wav", "b.mp3"]);
+        assert_eq!((preview.files[0].duration_text(), preview.files[0].rate_text()), ("1:30".to_string(), "44100 Hz".to_string()));
+        assert_eq!(preview.files[1].rate_text(), "-");
+        assert_eq!(preview.summary(), "2 files, 1.5 minutes (1 without a duration)");
+
+        let deep = InputPreview::scan(dir.path(), true, &[]).unwrap();
+        assert_eq!(deep.files.len(), 4);
+        assert_eq!(deep.total_secs().round(), 120.0);
+    }
+
+    #[test]
+    fn a_folder_without_audio_says_so() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("inside")).unwrap();
+        write_wav(&dir.path().join("inside").join("a.wav"), 44_100, &[vec![0.0; 441]]).unwrap();
+        let preview = InputPreview::scan(dir.path(), false, &[]).unwrap();
+        assert!(preview.is_empty());
+        assert!(preview.summary().starts_with("no .wav, .flac or .mp3 files in "), "{}", preview.summary());
+        assert!(!InputPreview::scan(dir.path(), true, &[]).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,174 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use serde_yaml::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Key holding the format version in every persisted state file.
+pub const VERSION_KEY: &str = "schema_version";
+
+/// Brings a state file's contents up to format `current`, one version at a
+/// time through `step`, which gets the version to migrate from. A file
+/// without `schema_version` is version 0, from before formats were
+/// versioned. A newer version than `current` is refused, as reading it
+/// could drop what the newer TUI wrote.
+pub fn migrate(mut value: Value, current: u32, what: &str, step: fn(u32, Value) -> Result<Value>) -> Result<Value> {
+    let Value::Mapping(mapping) = &value else {
+        bail!("{} is not a mapping", what);
+    };
+    let mut version = match mapping.get(VERSION_KEY) {
+        None => 0,
+        Some(version) => version.as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .with_context(|| format!("{} has an invalid {}", what, VERSION_KEY))?,
+    };
+    if version > current {
+        bail!(
+            "{} has format {}, written by a newer version of the TUI; this one reads up to format {}",
+            what,
+            version,
+            current
+        );
+    }
+    while version < current {
+        value = step(version, value).with_context(|| format!("Failed to migrate {} from format {}", what, version))?;
+        version += 1;
+    }
+    stamp(&mut value, current);
+    Ok(value)
+}
+
+/// Sets the format version of `value`, putting it first so it is the
+/// first thing seen when reading the file.
+pub fn stamp(value: &mut Value, version: u32) {
+    if let Value::Mapping(mapping) = value {
+        mapping.remove(VERSION_KEY);
+        let mut stamped = serde_yaml::Mapping::new();
+        stamped.insert(VERSION_KEY.into(), version.into());
+        stamped.extend(std::mem::take(mapping));
+        *mapping = stamped;
+    }
+}
+
+/// A state file moved aside at startup because it couldn't be loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quarantined {
+    /// E.g. "run history".
+    pub what: String,
+    pub path: PathBuf,
+    /// Where it went; `None` if it couldn't be moved either.
+    pub moved_to: Option<PathBuf>,
+    pub reason: String,
+    /// What doesn't work as before, e.g. "settings are back to defaults".
+    pub degraded: String,
+}
+
+impl Quarantined {
+    pub fn warning(&self) -> String {
+        let moved = match &self.moved_to {
+            Some(moved_to) => format!("moved to {}", moved_to.display()),
+            None => "could not be moved aside".to_string(),
+        };
+        format!("{} {} is unreadable and was {}: {}; {}", self.what, self.path.display(), moved, self.reason, self.degraded)
+    }
+
+    /// Why the app can't go on, when the file couldn't be moved aside: the
+    /// next save would overwrite it.
+    pub fn unrecoverable(&self) -> Option<anyhow::Error> {
+        if self.moved_to.is_some() {
+            return None;
+        }
+        Some(anyhow::anyhow!("{}", self.reason).context(format!(
+            "{} {} is unreadable and could not be moved aside; move or fix it, then start again",
+            self.what,
+            self.path.display()
+        )))
+    }
+}
+
+/// Tries to `load` the state file at `path`, renaming it to
+/// `<name>.corrupt-<timestamp>` if that fails so startup can go on with
+/// defaults. The file is kept for a look or a manual fix rather than being
+/// overwritten by the next save. A missing file is fine.
+pub fn check(
+    what: &str,
+    path: &Path,
+    degraded: &str,
+    now: DateTime<Local>,
+    load: impl FnOnce(&Path) -> Result<()>,
+) -> Option<Quarantined> {
+    if !path.is_file() {
+        return None;
+    }
+    let reason = format!("{:#}", load(path).err()?);
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".corrupt-{}", now.format("%Y%m%d-%H%M%S")));
+    let target = path.with_file_name(name);
+    let moved_to = fs::rename(path, &target).ok().map(|_| target);
+    Some(Quarantined {
+        what: what.to_string(),
+        path: path.to_path_buf(),
+        moved_to,
+        reason,
+        degraded: degraded.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AppConfig, ConfigManager, RecentConfig, RecentKind, CONFIG_SCHEMA_VERSION};
+    use crate::model::ModelType;
+    use crate::history::{RunHistory, HISTORY_SCHEMA_VERSION};
+    use chrono::TimeZone;
+
+    #[test]
+    fn truncated_and_newer_files_are_quarantined() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = Local.with_ymd_and_hms(2026, 10, 17, 9, 30, 0).unwrap();
+        let history = dir.path().join("tui_history.json");
+        fs::write(&history, r#"{"schema_version": 1, "entries": [{"kind": "training", "na"#).unwrap();
+        let config = dir.path().join("tui_config.yaml");
+        fs::write(&config, format!("schema_version: {}\ntheme: Dark\n", CONFIG_SCHEMA_VERSION + 1)).unwrap();
+
+        let found = check("run history", &history, "history starts empty", now, |path| RunHistory::load(path).map(drop)).unwrap();
+        assert_eq!(found.moved_to, Some(dir.path().join("tui_history.json.corrupt-20261017-093000")));
+        assert!(found.moved_to.as_ref().unwrap().is_file());
+        assert!(!history.exists());
+        assert!(RunHistory::load(&history).unwrap().entries().is_empty());
+
+        let manager = ConfigManager::new(&config.to_string_lossy());
+        let found = check("app config", &config, "settings are back to defaults", now, |_| manager.load_config().map(drop)).unwrap();
+        assert!(found.warning().contains("written by a newer version"), "{}", found.warning());
+        assert!(found.unrecoverable().is_none());
+        assert!(manager.load_config().is_ok());
+        assert!(check("app config", &config, "", now, |_| unreachable!()).is_none());
+
+        // Where it would go is taken: it stays, and would be saved over.
+        let later = now + chrono::TimeDelta::seconds(1);
+        fs::write(&history, "{").unwrap();
+        fs::create_dir_all(dir.path().join("tui_history.json.corrupt-20261017-093001/kept")).unwrap();
+        let found = check("run history", &history, "history starts empty", later, |path| RunHistory::load(path).map(drop)).unwrap();
+        assert_eq!(found.moved_to, None);
+        let error = found.unrecoverable().unwrap();
+        assert!(format!("{:#}", error).starts_with(&format!("run history {} is unreadable and could not be moved aside", history.display())));
+    }
+
+    #[test]
+    fn unversioned_files_are_migrated_and_saved_with_a_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = dir.path().join("tui_history.json");
+        fs::write(&history, r#"{"entries": []}"#).unwrap();
+        RunHistory::load(&history).unwrap().save().unwrap();
+        assert!(fs::read_to_string(&history).unwrap().contains(&format!("\"schema_version\": {}", HISTORY_SCHEMA_VERSION)));
+
+        let config = dir.path().join("tui_config.yaml");
+        fs::write(&config, "selected_model: null\nrecent_configs: []\ntheme: Light\n").unwrap();
+        let manager = ConfigManager::new(&config.to_string_lossy());
+        let loaded = manager.load_config().unwrap();
+        manager.save_config(&AppConfig { recent_configs: vec![RecentConfig::new("a.yaml", RecentKind::Training, ModelType::ScNet)], ..loaded }).unwrap();
+        let saved = fs::read_to_string(&config).unwrap();
+        assert!(saved.starts_with(&format!("schema_version: {}\n", CONFIG_SCHEMA_VERSION)), "{}", saved);
+        assert!(manager.load_config().unwrap().recent_configs[0].path == "a.yaml");
+    }
+}
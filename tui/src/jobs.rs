@@ -0,0 +1,896 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::log_buffer::LogBuffer;
+use crate::model::CommandSpec;
+use crate::parser::ParserRules;
+use crate::resources::{ResourceSampler, ResourceSummary};
+use crate::task::TaskMonitor;
+use crate::watchdog::{WatchState, Watchdog, WatchdogSettings};
+
+pub type JobId = u64;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JobKind {
+    Train,
+    Infer,
+    Validate,
+    /// Results pulled from a remote host.
+    Transfer,
+    Ensemble,
+    /// A command the supervisor runs itself.
+    Command,
+}
+
+impl JobKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobKind::Train => "Training",
+            JobKind::Infer => "Inference",
+            JobKind::Validate => "Validation",
+            JobKind::Transfer => "Transfer",
+            JobKind::Ensemble => "Ensemble",
+            JobKind::Command => "Command",
+        }
+    }
+}
+
+/// GPUs a job needs before it can start.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeviceRequirement {
+    /// CPU-only work; starts right away.
+    None,
+    /// Whichever GPU frees up first; handed to the job as
+    /// `CUDA_VISIBLE_DEVICES`.
+    AnyGpu,
+    /// Exactly these GPUs, as given in `device_ids`.
+    Devices(Vec<usize>),
+}
+
+impl DeviceRequirement {
+    pub fn from_device_ids(device_ids: Option<&[usize]>) -> Self {
+        match device_ids {
+            Some(ids) if !ids.is_empty() => DeviceRequirement::Devices(ids.to_vec()),
+            _ => DeviceRequirement::AnyGpu,
+        }
+    }
+}
+
+/// Which GPUs on this machine are held by which running job.
+#[derive(Debug, Clone, Default)]
+pub struct DeviceAllocator {
+    devices: Vec<usize>,
+    held: BTreeMap<usize, JobId>,
+}
+
+impl DeviceAllocator {
+    pub fn new(devices: Vec<usize>) -> Self {
+        DeviceAllocator { devices, held: BTreeMap::new() }
+    }
+
+    pub fn devices(&self) -> &[usize] {
+        &self.devices
+    }
+
+    pub fn holder(&self, device: usize) -> Option<JobId> {
+        self.held.get(&device).copied()
+    }
+
+    /// The devices that would satisfy `requirement` right now, if any.
+    pub fn available_for(&self, requirement: &DeviceRequirement) -> Option<Vec<usize>> {
+        let free = |device: &usize| self.devices.contains(device) && !self.held.contains_key(device);
+        match requirement {
+            DeviceRequirement::None => Some(vec![]),
+            DeviceRequirement::AnyGpu => self.devices.iter().find(|device| free(device)).map(|&device| vec![device]),
+            DeviceRequirement::Devices(ids) => ids.iter().all(free).then(|| ids.clone()),
+        }
+    }
+
+    fn acquire(&mut self, job: JobId, devices: &[usize]) {
+        for &device in devices {
+            self.held.insert(device, job);
+        }
+    }
+
+    fn release(&mut self, job: JobId) {
+        self.held.retain(|_, holder| *holder != job);
+    }
+}
+
+/// GPU indices reported by `nvidia-smi`; empty when there is no NVIDIA
+/// driver, in which case GPU jobs wait until started by hand.
+pub fn detect_gpus() -> Vec<usize> {
+    let output = std::process::Command::new("nvidia-smi")
+        .args(["--query-gpu=index", "--format=csv,noheader"])
+        .stderr(Stdio::null())
+        .output();
+    match output {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.trim().parse().ok())
+            .collect(),
+        _ => vec![],
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct JobSpec {
+    pub kind: JobKind,
+    /// E.g. "Training SCNet".
+    pub name: String,
+    /// `None` for a run the app starts itself once `take_started` hands it
+    /// its devices, and ends with `finish`.
+    pub command: Option<CommandSpec>,
+    pub requirement: DeviceRequirement,
+    /// Where stdout and stderr go; discarded when unset.
+    pub log_path: Option<PathBuf>,
+    /// Folder the job writes its results to, measured for the resource
+    /// summary.
+    pub results_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobState {
+    Queued,
+    /// `forced` jobs were started by hand without holding their devices.
+    Running { devices: Vec<usize>, forced: bool },
+    /// The main process exited but others in its process group (data loader
+    /// workers, say) are still alive, so the devices stay held.
+    Exiting { exit_code: Option<i32> },
+    Finished { exit_code: Option<i32> },
+    Failed(String),
+}
+
+/// A queued job taken off the queue by `cancel`, enough to put it back.
+#[derive(Debug, Clone)]
+pub struct RemovedJob {
+    pub id: JobId,
+    /// Its place in the queue.
+    pub position: usize,
+    pub spec: JobSpec,
+}
+
+/// A queued, running or finished job. App-run jobs are driven by their
+/// task; the job keeps what is needed to look at them and stop them.
+pub struct Job {
+    id: JobId,
+    spec: JobSpec,
+    state: JobState,
+    child: Option<Child>,
+    sampler: Option<ResourceSampler>,
+    /// How heavy the job was, once it is over.
+    resources: Option<ResourceSummary>,
+    watchdog: Option<Watchdog>,
+    /// How much of the log the watchdog has read.
+    log_read: u64,
+    started_at: Option<DateTime<Local>>,
+    /// An app-run job's task, cancelled to stop it, and its log, which
+    /// fills while it runs.
+    task: Option<(TaskMonitor, Arc<Mutex<LogBuffer>>)>,
+    /// The latest metric or file count, e.g. "epoch 3, loss 0.2500".
+    progress: Option<String>,
+    stopping: bool,
+    /// How an app-run job ended, as its screen said, e.g. "Training
+    /// finished (exit 0)".
+    summary: Option<String>,
+}
+
+impl Job {
+    fn new(id: JobId, spec: JobSpec) -> Self {
+        Job {
+            id,
+            spec,
+            state: JobState::Queued,
+            child: None,
+            sampler: None,
+            resources: None,
+            watchdog: None,
+            log_read: 0,
+            started_at: None,
+            task: None,
+            progress: None,
+            stopping: false,
+            summary: None,
+        }
+    }
+
+    pub fn id(&self) -> JobId {
+        self.id
+    }
+
+    pub fn kind(&self) -> JobKind {
+        self.spec.kind
+    }
+
+    pub fn name(&self) -> &str {
+        &self.spec.name
+    }
+
+    pub fn state(&self) -> &JobState {
+        &self.state
+    }
+
+    pub fn is_active(&self) -> bool {
+        matches!(self.state, JobState::Running { .. } | JobState::Exiting { .. })
+    }
+
+    pub fn is_stopping(&self) -> bool {
+        self.stopping
+    }
+
+    pub fn progress(&self) -> Option<&str> {
+        self.progress.as_deref()
+    }
+
+    pub fn started_at(&self) -> Option<DateTime<Local>> {
+        self.started_at
+    }
+
+    /// An app-run job's live log.
+    pub fn log(&self) -> Option<&Arc<Mutex<LogBuffer>>> {
+        self.task.as_ref().map(|(_, log)| log)
+    }
+}
+
+/// Runs queued jobs as soon as the GPUs they need are free. A job's devices
+/// are released only once its whole process group is gone.
+#[derive(Default)]
+pub struct JobSupervisor {
+    allocator: DeviceAllocator,
+    jobs: Vec<Job>,
+    next_id: JobId,
+    watchdog: WatchdogSettings,
+    /// For the epoch numbers that end the watchdog's first-epoch grace.
+    rules: ParserRules,
+    /// App-run jobs started since the last `take_started`, with their
+    /// devices.
+    started: Vec<(JobId, Vec<usize>)>,
+}
+
+impl JobSupervisor {
+    pub fn new(allocator: DeviceAllocator) -> Self {
+        JobSupervisor { allocator, jobs: vec![], next_id: 1, ..Default::default() }
+    }
+
+    /// Watchdog thresholds and parser rules for jobs started from now on.
+    pub fn set_watchdog(&mut self, settings: WatchdogSettings, rules: ParserRules) {
+        self.watchdog = settings;
+        self.rules = rules;
+    }
+
+    pub fn allocator(&self) -> &DeviceAllocator {
+        &self.allocator
+    }
+
+    /// Queues a job; it starts on the next `tick` its devices are free.
+    pub fn submit(&mut self, spec: JobSpec) -> JobId {
+        let id = self.next_id.max(1);
+        self.next_id = id + 1;
+        self.jobs.push(Job::new(id, spec));
+        id
+    }
+
+    /// Lists an app-run job that needs no GPU, running from now on.
+    pub fn begin(&mut self, spec: JobSpec, monitor: TaskMonitor, log: Arc<Mutex<LogBuffer>>) -> JobId {
+        let id = self.submit(spec);
+        let job = self.jobs.last_mut().unwrap();
+        job.state = JobState::Running { devices: vec![], forced: false };
+        job.started_at = Some(Local::now());
+        job.task = Some((monitor, log));
+        id
+    }
+
+    /// Gives a started app-run job the task that runs it and its log.
+    pub fn attach(&mut self, id: JobId, monitor: TaskMonitor, log: Arc<Mutex<LogBuffer>>) {
+        if let Some(job) = self.jobs.iter_mut().find(|job| job.id == id) {
+            job.task = Some((monitor, log));
+        }
+    }
+
+    pub fn set_progress(&mut self, id: JobId, progress: String) {
+        if let Some(job) = self.jobs.iter_mut().find(|job| job.id == id) {
+            job.progress = Some(progress);
+        }
+    }
+
+    pub fn state(&self, id: JobId) -> Option<&JobState> {
+        self.job(id).map(|job| &job.state)
+    }
+
+    /// Every job of the session, oldest first. Ids are never reused, so a
+    /// finished job can still be looked up by the one shown for it.
+    pub fn jobs(&self) -> &[Job] {
+        &self.jobs
+    }
+
+    pub fn get(&self, id: JobId) -> Option<&Job> {
+        self.job(id)
+    }
+
+    /// The jobs with a process or task going.
+    pub fn active(&self) -> impl Iterator<Item = &Job> {
+        self.jobs.iter().filter(|job| job.is_active())
+    }
+
+    /// PIDs of the jobs still running, to tell them apart from other
+    /// processes on the GPUs.
+    pub fn pids(&self) -> Vec<u32> {
+        self.jobs.iter().filter_map(|job| job.child.as_ref()).map(Child::id).collect()
+    }
+
+    /// Reaps exited jobs, releases their devices once their process group is
+    /// gone, then starts every queued job whose devices are free, oldest
+    /// first. Call it regularly, e.g. once per UI frame.
+    pub fn tick(&mut self) {
+        let now = Instant::now();
+        for job in self.jobs.iter_mut() {
+            if let Some(sampler) = job.sampler.as_mut() {
+                sampler.sample_if_due();
+            }
+            if matches!(job.state, JobState::Running { .. }) {
+                job.watch(&self.rules, now);
+            }
+            let (pid, exit_code) = match (&job.state, job.child.as_mut()) {
+                (JobState::Running { .. }, Some(child)) => match child.try_wait() {
+                    Ok(Some(status)) => (child.id(), status.code()),
+                    Ok(None) => continue,
+                    Err(e) => {
+                        job.state = JobState::Failed(format!("Failed to check process: {}", e));
+                        job.resources = job.sampler.take().map(|sampler| sampler.finish());
+                        self.allocator.release(job.id);
+                        continue;
+                    }
+                },
+                (JobState::Exiting { exit_code }, Some(child)) => (child.id(), *exit_code),
+                _ => continue,
+            };
+            if process_group_alive(pid) {
+                job.state = JobState::Exiting { exit_code };
+            } else {
+                job.state = JobState::Finished { exit_code };
+                job.child = None;
+                job.watchdog = None;
+                job.resources = job.sampler.take().map(|sampler| sampler.finish());
+                self.allocator.release(job.id);
+            }
+        }
+
+        for index in 0..self.jobs.len() {
+            if self.jobs[index].state != JobState::Queued {
+                continue;
+            }
+            if let Some(devices) = self.allocator.available_for(&self.jobs[index].spec.requirement) {
+                self.start(index, devices, false);
+            }
+        }
+    }
+
+    /// Starts a queued job now, even if its devices are in use. Nothing is
+    /// acquired for it, so it doesn't hold up anyone else either.
+    pub fn start_anyway(&mut self, id: JobId) -> Result<()> {
+        let Some(index) = self.jobs.iter().position(|job| job.id == id) else {
+            bail!("No job {}", id);
+        };
+        if self.jobs[index].state != JobState::Queued {
+            bail!("Job '{}' is not queued", self.jobs[index].spec.name);
+        }
+        let devices = match &self.jobs[index].spec.requirement {
+            DeviceRequirement::None => vec![],
+            DeviceRequirement::AnyGpu => {
+                self.allocator.available_for(&DeviceRequirement::AnyGpu)
+                    .or_else(|| self.allocator.devices().first().map(|&device| vec![device]))
+                    .unwrap_or_default()
+            }
+            DeviceRequirement::Devices(ids) => ids.clone(),
+        };
+        self.start(index, devices, true);
+        if let JobState::Failed(error) = &self.jobs[index].state {
+            bail!("{}", error);
+        }
+        Ok(())
+    }
+
+    /// The app-run jobs started since last time, with the devices each was
+    /// given; the app launches them now.
+    pub fn take_started(&mut self) -> Vec<(JobId, Vec<usize>)> {
+        std::mem::take(&mut self.started)
+    }
+
+    /// Ends an app-run job once its process has been waited for, releasing
+    /// its devices. `Err` is why it failed; `summary` is what its screen
+    /// said about it.
+    pub fn finish(&mut self, id: JobId, outcome: Result<Option<i32>, String>, summary: &str) {
+        let Some(job) = self.jobs.iter_mut().find(|job| job.id == id) else {
+            return;
+        };
+        job.state = match outcome {
+            Ok(exit_code) => JobState::Finished { exit_code },
+            Err(error) => JobState::Failed(error),
+        };
+        job.stopping = false;
+        job.summary = Some(summary.to_string());
+        self.allocator.release(id);
+    }
+
+    /// Whether the app runs this job itself rather than as a command.
+    pub fn runs_in_app(&self, id: JobId) -> bool {
+        self.job(id).is_some_and(|job| job.spec.command.is_none())
+    }
+
+    /// Removes a queued job, returning it so it can be put back with
+    /// `requeue`, or kills a running one's process group.
+    pub fn cancel(&mut self, id: JobId) -> Result<Option<RemovedJob>> {
+        let Some(index) = self.jobs.iter().position(|job| job.id == id) else {
+            bail!("No job {}", id);
+        };
+        let job = &mut self.jobs[index];
+        match (&job.state, job.child.as_mut()) {
+            (JobState::Queued, _) => {
+                let job = self.jobs.remove(index);
+                return Ok(Some(RemovedJob { id, position: index, spec: job.spec }));
+            }
+            (JobState::Running { .. } | JobState::Exiting { .. }, Some(child)) => {
+                signal_process_group(child, Signal::Kill)?;
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    /// The graceful stop at quit: drops the queue and asks every running
+    /// job's process group to terminate, so python can save what it can.
+    /// `tick` then reaps them as usual; `kill_all` deals with the rest.
+    pub fn stop_all(&mut self) {
+        self.jobs.retain(|job| job.state != JobState::Queued);
+        for job in self.jobs.iter_mut() {
+            if let (JobState::Running { .. } | JobState::Exiting { .. }, Some(child)) = (&job.state, job.child.as_mut()) {
+                let _ = signal_process_group(child, Signal::Terminate);
+            }
+        }
+    }
+
+    /// Asks a running job's process group to terminate, as at quit, or
+    /// cancels an app-run job's task, which stops it the way Ctrl+X does.
+    pub fn stop(&mut self, id: JobId) -> Result<()> {
+        let Some(job) = self.jobs.iter_mut().find(|job| job.id == id) else {
+            bail!("No job {}", id);
+        };
+        match (&job.state, job.child.as_mut(), &job.task) {
+            (JobState::Running { .. } | JobState::Exiting { .. }, Some(child), _) => signal_process_group(child, Signal::Terminate),
+            (JobState::Running { .. }, None, Some((monitor, _))) if !job.stopping => {
+                monitor.cancel();
+                job.stopping = true;
+                Ok(())
+            }
+            (JobState::Running { .. }, None, None) => bail!("'{}' is still starting", job.spec.name),
+            _ => bail!("Job '{}' is not running", job.spec.name),
+        }
+    }
+
+    /// Where the watchdog stands on a running job.
+    pub fn watch_state(&self, id: JobId) -> Option<WatchState> {
+        self.job(id)?.watchdog.as_ref().map(Watchdog::state)
+    }
+
+    pub fn is_hung(&self, id: JobId) -> bool {
+        self.job(id).and_then(|job| job.watchdog.as_ref()).is_some_and(Watchdog::is_hung)
+    }
+
+    /// Puts off a possibly-hung warning for another threshold.
+    pub fn snooze(&mut self, id: JobId) -> Result<()> {
+        let Some(watchdog) = self.jobs.iter_mut().find(|job| job.id == id).and_then(|job| job.watchdog.as_mut()) else {
+            bail!("Job {} is not being watched", id);
+        };
+        watchdog.snooze(Instant::now());
+        Ok(())
+    }
+
+    /// The main process of a running job, for py-spy.
+    pub fn pid(&self, id: JobId) -> Option<u32> {
+        self.job(id)?.child.as_ref().map(Child::id)
+    }
+
+    /// Whether no job has a process left. App-run jobs are the app's to
+    /// stop, so they don't count.
+    pub fn is_idle(&self) -> bool {
+        !self.jobs.iter().any(|job| job.child.is_some() && matches!(job.state, JobState::Running { .. } | JobState::Exiting { .. }))
+    }
+
+    /// Kills the process group of every job still running, returning their
+    /// names.
+    pub fn kill_all(&mut self) -> Vec<String> {
+        let mut killed = vec![];
+        for job in self.jobs.iter_mut() {
+            if let (JobState::Running { .. } | JobState::Exiting { .. }, Some(child)) = (&job.state, job.child.as_mut()) {
+                let _ = signal_process_group(child, Signal::Kill);
+                killed.push(job.spec.name.clone());
+            }
+        }
+        killed
+    }
+
+    /// Puts a removed job back in its old place, under its old id.
+    pub fn requeue(&mut self, removed: RemovedJob) {
+        let position = removed.position.min(self.jobs.len());
+        self.jobs.insert(position, Job::new(removed.id, removed.spec));
+    }
+
+    /// Peak memory, CPU time and the like of a job that is over, whether
+    /// it finished, failed or was killed.
+    pub fn resources(&self, id: JobId) -> Option<&ResourceSummary> {
+        self.job(id)?.resources.as_ref()
+    }
+
+    /// E.g. "#2 Validation SCNet: running - 12/50 files", or once it is
+    /// over, how it ended and how heavy it was.
+    pub fn line(&self, id: JobId) -> String {
+        let Some(job) = self.job(id) else {
+            return "unknown job".to_string();
+        };
+        let mut line = format!("#{} {}: {}", job.id, job.spec.name, self.status(id));
+        if let Some(progress) = job.progress.as_ref().filter(|_| job.is_active()) {
+            line.push_str(&format!(" - {}", progress));
+        }
+        if let Some(resources) = &job.resources {
+            line.push_str(&format!(" ({})", resources.one_line()));
+        }
+        line
+    }
+
+    /// What the job is doing or waiting for, or how it ended.
+    pub fn status(&self, id: JobId) -> String {
+        let Some(job) = self.job(id) else {
+            return "unknown job".to_string();
+        };
+        if let Some(summary) = &job.summary {
+            return summary.clone();
+        }
+        if job.stopping {
+            return "stopping".to_string();
+        }
+        match &job.state {
+            JobState::Queued => match &job.spec.requirement {
+                DeviceRequirement::Devices(ids) => {
+                    let busy: Vec<String> = ids.iter()
+                        .filter_map(|&device| {
+                            let holder = self.allocator.holder(device)?;
+                            let name = self.job(holder).map_or("", |job| job.spec.name.as_str());
+                            Some(format!("GPU {} (held by '{}')", device, name))
+                        })
+                        .collect();
+                    if busy.is_empty() {
+                        "waiting for GPU".to_string()
+                    } else {
+                        format!("waiting for {}", busy.join(", "))
+                    }
+                }
+                DeviceRequirement::AnyGpu if self.allocator.devices().is_empty() => {
+                    "waiting for GPU (none detected; start anyway to run)".to_string()
+                }
+                _ => "waiting for GPU".to_string(),
+            },
+            JobState::Running { devices, forced } => {
+                let on = if devices.is_empty() {
+                    "running".to_string()
+                } else {
+                    let list: Vec<String> = devices.iter().map(usize::to_string).collect();
+                    format!("running on GPU {}", list.join(","))
+                };
+                let on = if *forced { format!("{} (started anyway)", on) } else { on };
+                match job.watchdog.as_ref() {
+                    Some(watchdog) if watchdog.is_hung() => format!("{} - {}", on, watchdog.warning().unwrap_or_default()),
+                    Some(watchdog) if matches!(watchdog.state(), WatchState::Snoozed { .. }) => format!("{} - hang warning snoozed", on),
+                    _ => on,
+                }
+            }
+            JobState::Exiting { .. } => "exiting: waiting for child processes".to_string(),
+            JobState::Finished { exit_code: Some(0) } => "done".to_string(),
+            JobState::Finished { exit_code: Some(code) } => format!("exit {}", code),
+            JobState::Finished { exit_code: None } => "killed".to_string(),
+            JobState::Failed(error) => format!("failed: {}", error),
+        }
+    }
+
+    fn job(&self, id: JobId) -> Option<&Job> {
+        self.jobs.iter().find(|job| job.id == id)
+    }
+
+    fn start(&mut self, index: usize, devices: Vec<usize>, forced: bool) {
+        let job = &mut self.jobs[index];
+        let Some(mut command) = job.spec.command.clone() else {
+            if !forced {
+                self.allocator.acquire(job.id, &devices);
+            }
+            self.started.push((job.id, devices.clone()));
+            job.state = JobState::Running { devices, forced };
+            job.started_at = Some(Local::now());
+            return;
+        };
+        if job.spec.requirement == DeviceRequirement::AnyGpu && !devices.is_empty() {
+            let list: Vec<String> = devices.iter().map(usize::to_string).collect();
+            command.env("CUDA_VISIBLE_DEVICES", &list.join(","));
+        }
+        match spawn_in_own_group(&command, job.spec.log_path.as_ref()) {
+            Ok(child) => {
+                if !forced {
+                    self.allocator.acquire(job.id, &devices);
+                }
+                job.sampler = Some(ResourceSampler::new(child.id(), job.spec.results_dir.as_deref()));
+                job.watchdog = Some(Watchdog::new(self.watchdog, Instant::now()));
+                job.log_read = 0;
+                job.child = Some(child);
+                job.state = JobState::Running { devices, forced };
+                job.started_at = Some(Local::now());
+            }
+            Err(e) => job.state = JobState::Failed(format!("{:#}", e)),
+        }
+    }
+}
+
+impl Job {
+    /// Feeds the watchdog what the job wrote since last time and the GPU
+    /// utilization, then lets it decide.
+    fn watch(&mut self, rules: &ParserRules, now: Instant) {
+        let Some(watchdog) = self.watchdog.as_mut() else {
+            return;
+        };
+        if let Some(path) = &self.spec.log_path
+            && let Some((text, read)) = read_from(path, self.log_read)
+        {
+            self.log_read = read;
+            for line in text.split(['\n', '\r']).filter(|line| !line.trim().is_empty()) {
+                watchdog.line(rules, line, now);
+            }
+        }
+        if let Some(utilization) = self.sampler.as_ref().and_then(ResourceSampler::last_utilization) {
+            watchdog.utilization(utilization, now);
+        }
+        watchdog.check(now);
+    }
+}
+
+/// What was appended to `path` past `offset`, and the new offset; `None`
+/// when nothing was.
+fn read_from(path: &Path, offset: u64) -> Option<(String, u64)> {
+    let len = fs::metadata(path).ok()?.len();
+    if len <= offset {
+        return None;
+    }
+    let mut file = File::open(path).ok()?;
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    let mut bytes = vec![];
+    file.take(len - offset).read_to_end(&mut bytes).ok()?;
+    Some((String::from_utf8_lossy(&bytes).to_string(), len))
+}
+
+fn spawn_in_own_group(command: &CommandSpec, log_path: Option<&PathBuf>) -> Result<Child> {
+    let mut cmd = command.std_command();
+    cmd.stdin(Stdio::null());
+    match log_path {
+        Some(path) => {
+            let log = File::create(path).context("Failed to create job log")?;
+            cmd.stderr(log.try_clone().context("Failed to open job log")?).stdout(log);
+        }
+        None => {
+            cmd.stdout(Stdio::null()).stderr(Stdio::null());
+        }
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    cmd.spawn().with_context(|| format!("Failed to start {}", command.program))
+}
+
+/// Whether anything is left in the process group led by `pid`.
+#[cfg(unix)]
+fn process_group_alive(pid: u32) -> bool {
+    // SAFETY: signal 0 only checks that the group exists.
+    let result = unsafe { libc::kill(-(pid as libc::pid_t), 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Without process groups, the main process exiting is all we can see.
+#[cfg(not(unix))]
+fn process_group_alive(_pid: u32) -> bool {
+    false
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Signal {
+    Terminate,
+    Kill,
+}
+
+#[cfg(unix)]
+fn signal_process_group(child: &mut Child, signal: Signal) -> Result<()> {
+    let number = match signal {
+        Signal::Terminate => libc::SIGTERM,
+        Signal::Kill => libc::SIGKILL,
+    };
+    // SAFETY: the group was created for this child by `process_group(0)`.
+    if unsafe { libc::kill(-(child.id() as libc::pid_t), number) } != 0 {
+        child.kill().context("Failed to kill job")?;
+    }
+    Ok(())
+}
+
+/// Without signals, both stop the process outright.
+#[cfg(not(unix))]
+fn signal_process_group(child: &mut Child, _signal: Signal) -> Result<()> {
+    child.kill().context("Failed to kill job")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn allocator_matches_requirements_to_free_devices() {
+        let mut allocator = DeviceAllocator::new(vec![0, 1]);
+        assert_eq!(allocator.available_for(&DeviceRequirement::AnyGpu), Some(vec![0]));
+        allocator.acquire(7, &[0]);
+        assert_eq!(allocator.available_for(&DeviceRequirement::AnyGpu), Some(vec![1]));
+        assert_eq!(allocator.available_for(&DeviceRequirement::Devices(vec![0, 1])), None);
+        assert_eq!(allocator.available_for(&DeviceRequirement::Devices(vec![1])), Some(vec![1]));
+        assert_eq!(allocator.available_for(&DeviceRequirement::Devices(vec![2])), None, "no such GPU");
+        assert_eq!(allocator.available_for(&DeviceRequirement::None), Some(vec![]));
+        allocator.release(7);
+        assert_eq!(allocator.available_for(&DeviceRequirement::Devices(vec![0, 1])), Some(vec![0, 1]));
+        assert_eq!(DeviceRequirement::from_device_ids(Some(&[])), DeviceRequirement::AnyGpu);
+    }
+
+    #[test]
+    fn app_run_jobs_hold_their_devices_until_finished() {
+        let mut supervisor = JobSupervisor::new(DeviceAllocator::new(vec![0]));
+        let app_job = |name: &str| JobSpec { kind: JobKind::Train, name: name.into(), command: None, requirement: DeviceRequirement::AnyGpu, log_path: None, results_dir: None };
+        let training = supervisor.submit(app_job("train"));
+        let inference = supervisor.submit(app_job("infer"));
+        let validation = supervisor.submit(app_job("valid"));
+        supervisor.tick();
+        assert_eq!(supervisor.take_started(), vec![(training, vec![0])]);
+        assert_eq!(supervisor.state(inference), Some(&JobState::Queued));
+        assert!(supervisor.runs_in_app(training));
+        assert!(supervisor.is_idle(), "the app stops its own runs");
+
+        supervisor.start_anyway(validation).unwrap();
+        assert_eq!(supervisor.take_started(), vec![(validation, vec![0])]);
+        supervisor.finish(training, Ok(Some(0)), "Training finished (exit 0)");
+        supervisor.tick();
+        assert_eq!(supervisor.take_started(), vec![(inference, vec![0])]);
+        assert_eq!(supervisor.line(training), "#1 train: Training finished (exit 0)");
+        supervisor.finish(inference, Err("CUDA out of memory".to_string()), "Inference failed: CUDA out of memory");
+        assert_eq!(supervisor.state(inference), Some(&JobState::Failed("CUDA out of memory".to_string())));
+        assert_eq!(supervisor.allocator().holder(0), None);
+    }
+
+    #[test]
+    fn app_run_jobs_are_stopped_through_their_task_and_stay_listed_once_over() {
+        use crate::task::{OnCancel, Task};
+        let waiting_task = || Task::spawn("Run", OnCancel::Discard, |ctx| {
+            while !ctx.is_cancelled() {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Ok(())
+        });
+        let mut supervisor = JobSupervisor::new(DeviceAllocator::new(vec![]));
+        let (training, validation) = (waiting_task(), waiting_task());
+        let log = Arc::new(Mutex::new(LogBuffer::new()));
+        let spec = |kind: JobKind, name: &str| JobSpec { kind, name: name.into(), command: None, requirement: DeviceRequirement::None, log_path: None, results_dir: None };
+        let train = supervisor.begin(spec(JobKind::Train, "Training SCNet"), training.monitor(), log.clone());
+        let validate = supervisor.submit(spec(JobKind::Validate, "Validation SCNet"));
+        supervisor.tick();
+        assert_eq!(supervisor.take_started(), vec![(validate, vec![])]);
+        assert!(supervisor.stop(validate).unwrap_err().to_string().contains("still starting"));
+        supervisor.attach(validate, validation.monitor(), Arc::new(Mutex::new(LogBuffer::new())));
+
+        supervisor.set_progress(train, "epoch 3, loss 0.2500".to_string());
+        log.lock().unwrap().push("Train epoch: 3");
+        assert_eq!(supervisor.line(train), "#1 Training SCNet: running - epoch 3, loss 0.2500");
+        assert_eq!(supervisor.get(train).and_then(Job::log).unwrap().lock().unwrap().len(), 1);
+
+        supervisor.stop(validate).unwrap();
+        assert!(validation.monitor().is_cancelled());
+        assert_eq!(supervisor.status(validate), "stopping");
+        assert!(supervisor.stop(validate).unwrap_err().to_string().contains("not running"));
+        validation.join().unwrap();
+        supervisor.finish(validate, Ok(None), "Validation stopped");
+
+        let active: Vec<JobId> = supervisor.active().map(Job::id).collect();
+        assert_eq!(active, [train]);
+        assert_eq!(supervisor.line(validate), "#2 Validation SCNet: Validation stopped");
+        assert!(supervisor.stop(7).is_err());
+        training.cancel();
+    }
+
+    #[cfg(unix)]
+    fn shell_job(name: &str, script: &str) -> JobSpec {
+        let mut command = CommandSpec::new("sh");
+        command.arg("-c").arg(script);
+        JobSpec { kind: JobKind::Command, name: name.into(), command: Some(command), requirement: DeviceRequirement::Devices(vec![1]), log_path: None, results_dir: None }
+    }
+
+    #[cfg(unix)]
+    fn tick_until(supervisor: &mut JobSupervisor, id: JobId, done: impl Fn(&JobState) -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !done(supervisor.state(id).unwrap()) {
+            assert!(Instant::now() < deadline, "stuck in {:?}", supervisor.state(id));
+            std::thread::sleep(Duration::from_millis(20));
+            supervisor.tick();
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn queued_job_waits_until_the_whole_process_group_is_gone() {
+        let mut supervisor = JobSupervisor::new(DeviceAllocator::new(vec![0, 1]));
+        // The shell exits at once but leaves a child behind in its group,
+        // like train.py leaving data loader workers.
+        let training = supervisor.submit(shell_job("train", "sleep 0.5 & exit 0"));
+        let inference = supervisor.submit(shell_job("infer", "exit 3"));
+        supervisor.tick();
+        assert_eq!(supervisor.state(training), Some(&JobState::Running { devices: vec![1], forced: false }));
+        assert_eq!(supervisor.status(inference), "waiting for GPU 1 (held by 'train')");
+
+        tick_until(&mut supervisor, training, |state| !matches!(state, JobState::Running { .. }));
+        if supervisor.state(training) == Some(&JobState::Exiting { exit_code: Some(0) }) {
+            assert_eq!(supervisor.state(inference), Some(&JobState::Queued), "devices held while the group lives");
+        }
+        tick_until(&mut supervisor, inference, |state| matches!(state, JobState::Finished { .. }));
+        assert_eq!(supervisor.state(training), Some(&JobState::Finished { exit_code: Some(0) }));
+        assert_eq!(supervisor.status(inference), "exit 3");
+        assert_eq!(supervisor.allocator().holder(1), None);
+        // Sampled while the group lived, and summed up when it was gone.
+        assert!(supervisor.resources(training).is_some_and(|summary| summary.samples >= 1));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn start_anyway_skips_the_queue_without_holding_devices() {
+        let mut supervisor = JobSupervisor::new(DeviceAllocator::new(vec![1]));
+        let long = supervisor.submit(shell_job("long", "sleep 5"));
+        let urgent = supervisor.submit(shell_job("urgent", "exit 0"));
+        supervisor.tick();
+        assert_eq!(supervisor.state(urgent), Some(&JobState::Queued));
+
+        supervisor.start_anyway(urgent).unwrap();
+        assert_eq!(supervisor.status(urgent), "running on GPU 1 (started anyway)");
+        assert_eq!(supervisor.allocator().holder(1), Some(long));
+        tick_until(&mut supervisor, urgent, |state| matches!(state, JobState::Finished { .. }));
+        assert_eq!(supervisor.allocator().holder(1), Some(long));
+
+        supervisor.cancel(long).unwrap();
+        tick_until(&mut supervisor, long, |state| matches!(state, JobState::Finished { .. }));
+        assert_eq!(supervisor.status(long), "killed");
+        assert_eq!(supervisor.allocator().holder(1), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn quiet_job_is_flagged_snoozed_and_stopped() {
+        let mut supervisor = JobSupervisor::new(DeviceAllocator::new(vec![1]));
+        supervisor.set_watchdog(WatchdogSettings { enabled: true, quiet_minutes: 0, first_epoch_minutes: 0 }, ParserRules::builtin());
+        let quiet = supervisor.submit(shell_job("quiet", "exec sleep 30"));
+        // Started on the first tick, looked at on the next.
+        supervisor.tick();
+        supervisor.tick();
+        assert!(supervisor.is_hung(quiet));
+        assert_eq!(supervisor.status(quiet), "running on GPU 1 - possibly hung, quiet for 0 min");
+
+        supervisor.snooze(quiet).unwrap();
+        assert!(!supervisor.is_hung(quiet));
+        supervisor.stop(quiet).unwrap();
+        tick_until(&mut supervisor, quiet, |state| matches!(state, JobState::Finished { .. }));
+        assert_eq!(supervisor.watch_state(quiet), None);
+        assert!(supervisor.snooze(quiet).is_err());
+    }
+}
@@ -0,0 +1,364 @@
+use anyhow::{anyhow, bail, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::help;
+
+/// What a bound key does on every screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Quit,
+    Help,
+    Up,
+    Down,
+    Select,
+    Back,
+    PageUp,
+    PageDown,
+    Top,
+    Bottom,
+    Search,
+}
+
+impl Action {
+    pub const ALL: [Action; 11] = [
+        Action::Quit,
+        Action::Help,
+        Action::Up,
+        Action::Down,
+        Action::Select,
+        Action::Back,
+        Action::PageUp,
+        Action::PageDown,
+        Action::Top,
+        Action::Bottom,
+        Action::Search,
+    ];
+
+    /// As written in `tui_config.yaml`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Help => "help",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Select => "select",
+            Action::Back => "back",
+            Action::PageUp => "page_up",
+            Action::PageDown => "page_down",
+            Action::Top => "top",
+            Action::Bottom => "bottom",
+            Action::Search => "search",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "Quit",
+            Action::Help => "Show this help",
+            Action::Up => "Move up",
+            Action::Down => "Move down",
+            Action::Select => "Select",
+            Action::Back => "Go back",
+            Action::PageUp => "Page up",
+            Action::PageDown => "Page down",
+            Action::Top => "Go to the top",
+            Action::Bottom => "Go to the bottom",
+            Action::Search => "Search or filter, where the screen has one",
+        }
+    }
+
+    /// The key the screens handle the action as, which is also its default
+    /// binding.
+    pub fn key(self) -> KeyCode {
+        match self {
+            Action::Quit => KeyCode::Char('q'),
+            Action::Help => KeyCode::Char('h'),
+            Action::Up => KeyCode::Up,
+            Action::Down => KeyCode::Down,
+            Action::Select => KeyCode::Enter,
+            Action::Back => KeyCode::Esc,
+            Action::PageUp => KeyCode::PageUp,
+            Action::PageDown => KeyCode::PageDown,
+            Action::Top => KeyCode::Home,
+            Action::Bottom => KeyCode::End,
+            Action::Search => KeyCode::Char('/'),
+        }
+    }
+}
+
+/// A key with its modifiers, written like "ctrl+c", "j", "G" or "esc".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeySpec {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeySpec {
+    pub fn new(code: KeyCode) -> Self {
+        KeySpec { code, modifiers: KeyModifiers::NONE }
+    }
+
+    /// Shift is part of the character itself, so "G" is Shift+g.
+    pub fn matches(&self, key: KeyEvent) -> bool {
+        let modifiers = if matches!(key.code, KeyCode::Char(_)) { key.modifiers - KeyModifiers::SHIFT } else { key.modifiers };
+        self.code == key.code && self.modifiers == modifiers
+    }
+}
+
+const NAMED_KEYS: [(&str, KeyCode); 15] = [
+    ("esc", KeyCode::Esc),
+    ("enter", KeyCode::Enter),
+    ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("space", KeyCode::Char(' ')),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+];
+
+impl FromStr for KeySpec {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let (modifier_names, key) = match text.rsplit_once('+') {
+            // "+" on its own, or after a modifier as in "ctrl++".
+            Some((rest, "")) => (rest.strip_suffix('+').unwrap_or(rest), "+"),
+            Some((modifier_names, key)) => (modifier_names, key),
+            None => ("", text),
+        };
+        let mut modifiers = KeyModifiers::NONE;
+        for name in modifier_names.split('+').filter(|name| !name.is_empty()) {
+            modifiers |= match name.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => bail!("Unknown modifier \"{}\" in key \"{}\"", name, text),
+            };
+        }
+        let lower = key.to_lowercase();
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ if lower.starts_with('f') && lower[1..].parse::<u8>().is_ok_and(|n| (1..=12).contains(&n)) => {
+                KeyCode::F(lower[1..].parse().unwrap())
+            }
+            _ => NAMED_KEYS.iter()
+                .find(|(name, _)| *name == lower)
+                .map(|(_, code)| *code)
+                .ok_or_else(|| anyhow!("Unknown key \"{}\"", text))?,
+        };
+        // Shift with a character is the character itself, e.g. "shift+g" is "G".
+        let code = match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers -= KeyModifiers::SHIFT;
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            code => code,
+        };
+        Ok(KeySpec { code, modifiers })
+    }
+}
+
+impl fmt::Display for KeySpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (modifier, name) in [(KeyModifiers::CONTROL, "ctrl"), (KeyModifiers::ALT, "alt"), (KeyModifiers::SHIFT, "shift")] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            code => {
+                let name = NAMED_KEYS.iter().find(|(_, named)| *named == code).map_or("?", |(name, _)| name);
+                write!(f, "{}", name)
+            }
+        }
+    }
+}
+
+/// One key or several, as an action's keys may be written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Keys for the actions every screen shares, e.g.
+/// `keybindings: { up: [up, k], down: [down, j], quit: ctrl+q }`. Actions
+/// left out keep their default key; one that is listed has only the keys
+/// listed. A key bound to two actions is refused when the config loads.
+/// Bindings are applied before a screen sees the key, so `k` above takes
+/// over the screens' own `k`; see `shadowed`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "BTreeMap<Action, KeyList>", into = "BTreeMap<Action, KeyList>")]
+pub struct KeyBindings {
+    overrides: BTreeMap<Action, Vec<KeySpec>>,
+}
+
+impl KeyBindings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.overrides.is_empty()
+    }
+
+    /// The keys `action` is bound to.
+    pub fn keys(&self, action: Action) -> Vec<KeySpec> {
+        self.overrides.get(&action).cloned().unwrap_or_else(|| vec![KeySpec::new(action.key())])
+    }
+
+    pub fn action(&self, key: KeyEvent) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| self.keys(*action).iter().any(|spec| spec.matches(key)))
+    }
+
+    /// The key the screens should see for `key`: its action's key when it
+    /// is bound to one, nothing for an action's default key that was bound
+    /// elsewhere, and otherwise the key as pressed.
+    pub fn translate(&self, key: KeyEvent) -> KeyCode {
+        if let Some(action) = self.action(key) {
+            return action.key();
+        }
+        let rebound = Action::ALL.into_iter()
+            .any(|action| action.key() == key.code && !self.keys(action).contains(&KeySpec::new(key.code)));
+        if rebound { KeyCode::Null } else { key.code }
+    }
+
+    /// The keys of a screen's key line, e.g. "c/k/f/o: set …", that a
+    /// binding takes over before the screen sees them, with the action
+    /// that takes each. A screen key that is its action's own key, such
+    /// as "/: search", is not taken over.
+    pub fn shadowed(&self, key_help: &str) -> Vec<(KeySpec, Action)> {
+        let mut shadowed: Vec<(KeySpec, Action)> = vec![];
+        for (keys, _) in help::parse_keys(key_help) {
+            let names: Vec<&str> = if keys == "/" { vec!["/"] } else { keys.split([',', '/']).map(str::trim).collect() };
+            for spec in names.into_iter().filter_map(|name| name.parse::<KeySpec>().ok()) {
+                if let Some(action) = self.action(KeyEvent::new(spec.code, spec.modifiers))
+                    && action.key() != spec.code
+                    && !shadowed.contains(&(spec, action))
+                {
+                    shadowed.push((spec, action));
+                }
+            }
+        }
+        shadowed
+    }
+
+    /// ("q", "Quit") and so on, for the help overlay.
+    pub fn help_rows(&self) -> Vec<(String, String)> {
+        Action::ALL.into_iter()
+            .map(|action| {
+                let keys: Vec<String> = self.keys(action).iter().map(KeySpec::to_string).collect();
+                (keys.join(" / "), action.description().to_string())
+            })
+            .collect()
+    }
+}
+
+impl TryFrom<BTreeMap<Action, KeyList>> for KeyBindings {
+    type Error = anyhow::Error;
+
+    fn try_from(written: BTreeMap<Action, KeyList>) -> Result<Self> {
+        let mut overrides = BTreeMap::new();
+        for (action, keys) in written {
+            let keys = match keys {
+                KeyList::One(key) => vec![key],
+                KeyList::Many(keys) => keys,
+            };
+            let specs = keys.iter()
+                .map(|key| key.parse::<KeySpec>().map_err(|e| anyhow!("{} for {}", e, action.name())))
+                .collect::<Result<Vec<_>>>()?;
+            overrides.insert(action, specs);
+        }
+        let bindings = KeyBindings { overrides };
+        for (i, first) in Action::ALL.iter().enumerate() {
+            for second in &Action::ALL[i + 1..] {
+                let second_keys = bindings.keys(*second);
+                if let Some(spec) = bindings.keys(*first).into_iter().find(|spec| second_keys.contains(spec)) {
+                    bail!("Key \"{}\" is bound to both {} and {}", spec, first.name(), second.name());
+                }
+            }
+        }
+        Ok(bindings)
+    }
+}
+
+impl From<KeyBindings> for BTreeMap<Action, KeyList> {
+    fn from(bindings: KeyBindings) -> Self {
+        bindings.overrides
+            .into_iter()
+            .map(|(action, specs)| (action, KeyList::Many(specs.iter().map(KeySpec::to_string).collect())))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn key_specs_parse_and_print_back() {
+        for text in ["ctrl+c", "j", "G", "esc", "pageup", "end", "f5", "alt+enter", "space", "+", "ctrl++"] {
+            assert_eq!(text.parse::<KeySpec>().unwrap().to_string(), text);
+        }
+        assert_eq!("Shift+g".parse::<KeySpec>().unwrap(), KeySpec::new(KeyCode::Char('G')));
+        assert!("ctrl+nope".parse::<KeySpec>().is_err());
+        assert!("hyper+x".parse::<KeySpec>().is_err());
+        assert!("G".parse::<KeySpec>().unwrap().matches(key(KeyCode::Char('G'), KeyModifiers::SHIFT)));
+        assert!(!"c".parse::<KeySpec>().unwrap().matches(key(KeyCode::Char('c'), KeyModifiers::CONTROL)));
+    }
+
+    #[test]
+    fn vim_keys_are_translated_and_defaults_kept() {
+        let bindings: KeyBindings = serde_yaml::from_str("up: [up, k]\ndown: [down, j]\ntop: g\nbottom: G\nquit: ctrl+q\n").unwrap();
+        let none = KeyModifiers::NONE;
+        assert_eq!(bindings.translate(key(KeyCode::Char('k'), none)), KeyCode::Up);
+        assert_eq!(bindings.translate(key(KeyCode::Up, none)), KeyCode::Up);
+        assert_eq!(bindings.translate(key(KeyCode::Char('G'), KeyModifiers::SHIFT)), KeyCode::End);
+        assert_eq!(bindings.action(key(KeyCode::Char('q'), KeyModifiers::CONTROL)), Some(Action::Quit));
+        assert_eq!(bindings.translate(key(KeyCode::Char('q'), none)), KeyCode::Null, "q no longer quits");
+        assert_eq!(bindings.translate(key(KeyCode::Char('x'), none)), KeyCode::Char('x'));
+        assert_eq!(bindings.action(key(KeyCode::Esc, none)), Some(Action::Back));
+        assert_eq!(bindings.help_rows()[0], ("ctrl+q".to_string(), "Quit".to_string()));
+
+        let saved = serde_yaml::to_string(&bindings).unwrap();
+        assert_eq!(serde_yaml::from_str::<KeyBindings>(&saved).unwrap(), bindings);
+        assert_eq!(KeyBindings::new().action(key(KeyCode::Char('h'), none)), Some(Action::Help));
+    }
+
+    #[test]
+    fn screen_keys_taken_by_a_binding_are_found() {
+        let bindings: KeyBindings = serde_yaml::from_str("up: [up, k]\ndown: [down, j]\ntop: g\n").unwrap();
+        let taken = bindings.shadowed("Enter: select    /: search    c/k/f/o: set files    g: pick GPUs    Up/Down: choose");
+        assert_eq!(taken, [(KeySpec::new(KeyCode::Char('k')), Action::Up), (KeySpec::new(KeyCode::Char('g')), Action::Top)]);
+        assert!(KeyBindings::new().shadowed("k: stop a run    /: search").is_empty());
+    }
+
+    #[test]
+    fn a_key_bound_twice_is_refused() {
+        let error = serde_yaml::from_str::<KeyBindings>("back: q\n").unwrap_err().to_string();
+        assert!(error.contains("Key \"q\" is bound to both quit and back"), "{}", error);
+        let error = serde_yaml::from_str::<KeyBindings>("search: [ctrl+f, nope]\n").unwrap_err().to_string();
+        assert!(error.contains("Unknown key \"nope\" for search"), "{}", error);
+    }
+}
@@ -0,0 +1,90 @@
+pub mod attention;
+pub mod audio;
+pub mod bakeoff;
+pub mod bundle;
+pub mod checkpoints;
+pub mod checksum;
+pub mod cli;
+pub mod clipboard;
+pub mod compare;
+pub mod config;
+pub mod config_check;
+pub mod config_form;
+pub mod cost;
+pub mod dashboard;
+pub mod dataset;
+pub mod dedup;
+pub mod download;
+pub mod ensemble;
+pub mod file_picker;
+pub mod file_watch;
+pub mod gpu;
+pub mod gpu_check;
+pub mod headless;
+pub mod help;
+pub mod history;
+pub mod import;
+pub mod inference;
+pub mod inference_batch;
+pub mod input_preview;
+pub mod integrity;
+pub mod jobs;
+pub mod keymap;
+pub mod lint;
+pub mod log_buffer;
+pub mod log_pane;
+pub mod loudness;
+pub mod manifest;
+pub mod metrics;
+pub mod mixdown;
+pub mod model;
+pub mod model_config;
+pub mod mouse;
+pub mod notify;
+pub mod output_view;
+pub mod owner;
+pub mod parse_health;
+pub mod parser;
+pub mod plain;
+pub mod preflight;
+pub mod preset;
+pub mod pretrained;
+pub mod preview;
+pub mod priority;
+pub mod process;
+pub mod project;
+pub mod reconstruction;
+pub mod remote;
+pub mod resources;
+pub mod run_dir;
+pub mod runner;
+pub mod scratch;
+pub mod secret;
+pub mod selection;
+pub mod shell_words;
+pub mod shutdown;
+pub mod staging;
+pub mod status_bar;
+pub mod stem_names;
+pub mod step_progress;
+pub mod storage;
+pub mod sweep;
+pub mod table;
+pub mod tail;
+pub mod task;
+pub mod template;
+pub mod terminal;
+pub mod text_input;
+pub mod theme;
+pub mod training;
+pub mod training_chart;
+pub mod training_queue;
+pub mod transfer;
+pub mod trash;
+pub mod ui;
+pub mod undo;
+pub mod validation;
+pub mod validset;
+pub mod watchdog;
+pub mod widgets;
+pub mod yaml_diff;
@@ -0,0 +1,254 @@
+use anyhow::{Context, Result};
+use serde_yaml::Value;
+use std::fs;
+
+use crate::model::ModelType;
+
+/// Audio per optimizer step above which a batch most likely won't fit on
+/// any GPU, in seconds. The shipped configs use two minutes or less.
+const BATCH_AUDIO_WARN_SECS: f64 = 600.0;
+const BATCH_AUDIO_ERROR_SECS: f64 = 3600.0;
+
+const LR_RANGE: (f64, f64) = (1e-6, 1e-2);
+
+/// Models that cut the spectrogram to `audio.dim_f` bins and
+/// `audio.dim_t` frames of `audio.hop_length`.
+const FIXED_STFT_MODELS: &[ModelType] = &[ModelType::MDX23C, ModelType::VitLarge23, ModelType::TorchSeg];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintIssue {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    /// Dotted YAML path of the value at fault, e.g. `audio.hop_length`.
+    pub path: String,
+}
+
+impl LintIssue {
+    fn new(rule: &'static str, severity: Severity, path: &str, message: String) -> Self {
+        LintIssue { rule, severity, message, path: path.to_string() }
+    }
+}
+
+pub struct LintRule {
+    pub id: &'static str,
+    /// Models the rule applies to; `None` for all of them.
+    pub models: Option<&'static [ModelType]>,
+    pub check: fn(&'static str, &Value) -> Vec<LintIssue>,
+}
+
+pub static RULES: &[LintRule] = &[
+    LintRule { id: "hop_divides_chunk", models: Some(FIXED_STFT_MODELS), check: hop_divides_chunk },
+    LintRule { id: "dim_f_matches_n_fft", models: Some(FIXED_STFT_MODELS), check: dim_f_matches_n_fft },
+    LintRule { id: "batch_memory", models: None, check: batch_memory },
+    LintRule { id: "learning_rate", models: None, check: learning_rate },
+    LintRule { id: "smoke_test_epochs", models: None, check: smoke_test_epochs },
+];
+
+/// Runs every rule that applies to `model_type` over a parsed model config.
+pub fn lint(model_type: &ModelType, config: &Value) -> Vec<LintIssue> {
+    let mut issues: Vec<LintIssue> = RULES.iter()
+        .filter(|rule| rule.models.is_none_or(|models| models.contains(model_type)))
+        .flat_map(|rule| (rule.check)(rule.id, config))
+        .collect();
+    issues.sort_by_key(|issue| std::cmp::Reverse(issue.severity));
+    issues
+}
+
+pub fn lint_file(model_type: &ModelType, path: &str) -> Result<LintReport> {
+    let content = fs::read_to_string(path)
+        .context("Failed to read model config")?;
+    let config: Value = serde_yaml::from_str(&content)
+        .context("Failed to parse model config")?;
+    Ok(LintReport { issues: lint(model_type, &config) })
+}
+
+/// Lint results as a pre-flight checklist. Errors always block; warnings
+/// block until their rule id is listed in the run's `lint_overrides`.
+#[derive(Debug, Clone, Default)]
+pub struct LintReport {
+    pub issues: Vec<LintIssue>,
+}
+
+impl LintReport {
+    pub fn blocking(&self, overrides: &[String]) -> Vec<&LintIssue> {
+        self.issues.iter()
+            .filter(|issue| issue.severity == Severity::Error || !overrides.iter().any(|id| id == issue.rule))
+            .collect()
+    }
+
+    pub fn checklist(&self, overrides: &[String]) -> Vec<String> {
+        self.issues.iter()
+            .map(|issue| {
+                let mark = match issue.severity {
+                    Severity::Error => "✗ error",
+                    Severity::Warning if overrides.iter().any(|id| id == issue.rule) => "~ overridden",
+                    Severity::Warning => "! warning",
+                };
+                format!("{} [{}] {}: {}", mark, issue.rule, issue.path, issue.message)
+            })
+            .collect()
+    }
+}
+
+fn number(config: &Value, path: &str) -> Option<f64> {
+    path.split('.')
+        .try_fold(config, |value, key| value.get(key))
+        .and_then(Value::as_f64)
+}
+
+fn hop_divides_chunk(rule: &'static str, config: &Value) -> Vec<LintIssue> {
+    let (Some(hop), Some(chunk)) = (number(config, "audio.hop_length"), number(config, "audio.chunk_size")) else {
+        return vec![];
+    };
+    if hop > 0.0 && chunk % hop == 0.0 {
+        return vec![];
+    }
+    vec![LintIssue::new(rule, Severity::Error, "audio.hop_length", format!(
+        "hop_length {} does not divide chunk_size {}; the spectrogram won't have a whole number of frames",
+        hop, chunk
+    ))]
+}
+
+fn dim_f_matches_n_fft(rule: &'static str, config: &Value) -> Vec<LintIssue> {
+    let (Some(dim_f), Some(n_fft)) = (number(config, "audio.dim_f"), number(config, "audio.n_fft")) else {
+        return vec![];
+    };
+    let bins = n_fft / 2.0 + 1.0;
+    if dim_f > bins {
+        vec![LintIssue::new(rule, Severity::Error, "audio.dim_f", format!(
+            "dim_f {} is more than the {} frequency bins n_fft {} gives",
+            dim_f, bins, n_fft
+        ))]
+    } else if dim_f < n_fft / 2.0 {
+        vec![LintIssue::new(rule, Severity::Warning, "audio.dim_f", format!(
+            "dim_f {} drops everything above bin {} of n_fft/2 = {}",
+            dim_f, dim_f, n_fft / 2.0
+        ))]
+    } else {
+        vec![]
+    }
+}
+
+fn batch_memory(rule: &'static str, config: &Value) -> Vec<LintIssue> {
+    let (Some(batch), Some(chunk)) = (number(config, "training.batch_size"), number(config, "audio.chunk_size")) else {
+        return vec![];
+    };
+    let rate = number(config, "audio.sample_rate").unwrap_or(44_100.0);
+    let secs = batch * chunk / rate;
+    let severity = if secs > BATCH_AUDIO_ERROR_SECS {
+        Severity::Error
+    } else if secs > BATCH_AUDIO_WARN_SECS {
+        Severity::Warning
+    } else {
+        return vec![];
+    };
+    vec![LintIssue::new(rule, severity, "training.batch_size", format!(
+        "batch_size {} x chunk_size {} is {:.0} s of audio per step; expect out-of-memory",
+        batch, chunk, secs
+    ))]
+}
+
+fn learning_rate(rule: &'static str, config: &Value) -> Vec<LintIssue> {
+    let Some(lr) = number(config, "training.lr") else {
+        return vec![];
+    };
+    // Prodigy estimates the step size itself and expects lr around 1.0.
+    let optimizer = config.get("training").and_then(|training| training.get("optimizer")).and_then(Value::as_str);
+    if optimizer.is_some_and(|optimizer| optimizer.eq_ignore_ascii_case("prodigy")) {
+        return vec![];
+    }
+    let (low, high) = LR_RANGE;
+    if lr <= 0.0 {
+        return vec![LintIssue::new(rule, Severity::Error, "training.lr", format!("lr {} must be positive", lr))];
+    }
+    if (low..=high).contains(&lr) {
+        return vec![];
+    }
+    vec![LintIssue::new(rule, Severity::Warning, "training.lr", format!(
+        "lr {:e} is outside the usual [{:e}, {:e}]", lr, low, high
+    ))]
+}
+
+fn smoke_test_epochs(rule: &'static str, config: &Value) -> Vec<LintIssue> {
+    match number(config, "training.num_epochs") {
+        Some(epochs) if epochs <= 1.0 => vec![LintIssue::new(rule, Severity::Warning, "training.num_epochs", format!(
+            "num_epochs is {}; left over from a smoke test?", epochs
+        ))],
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(rule: &str, model_type: ModelType, yaml: &str) -> Vec<(Severity, String)> {
+        let config: Value = serde_yaml::from_str(yaml).unwrap();
+        lint(&model_type, &config).into_iter()
+            .filter(|issue| issue.rule == rule)
+            .map(|issue| (issue.severity, issue.path))
+            .collect()
+    }
+
+    #[test]
+    fn hop_must_divide_chunk() {
+        let bad = "audio: {chunk_size: 261121, hop_length: 1024}";
+        assert_eq!(check("hop_divides_chunk", ModelType::MDX23C, bad), [(Severity::Error, "audio.hop_length".into())]);
+        assert!(check("hop_divides_chunk", ModelType::MDX23C, "audio: {chunk_size: 261120, hop_length: 1024}").is_empty());
+        // HTDemucs doesn't cut chunks into hop-sized frames.
+        assert!(check("hop_divides_chunk", ModelType::HtDemucs, bad).is_empty());
+    }
+
+    #[test]
+    fn dim_f_fits_n_fft() {
+        let rule = "dim_f_matches_n_fft";
+        assert!(check(rule, ModelType::MDX23C, "audio: {dim_f: 4096, n_fft: 8192}").is_empty());
+        assert_eq!(check(rule, ModelType::MDX23C, "audio: {dim_f: 8192, n_fft: 8192}"), [(Severity::Error, "audio.dim_f".into())]);
+        assert_eq!(check(rule, ModelType::TorchSeg, "audio: {dim_f: 1024, n_fft: 8192}"), [(Severity::Warning, "audio.dim_f".into())]);
+    }
+
+    #[test]
+    fn batch_audio_per_step_is_bounded() {
+        let rule = "batch_memory";
+        let yaml = |batch: u32| format!("audio: {{chunk_size: 485100, sample_rate: 44100}}\ntraining: {{batch_size: {}}}", batch);
+        assert!(check(rule, ModelType::ScNet, &yaml(10)).is_empty());
+        assert_eq!(check(rule, ModelType::ScNet, &yaml(64)), [(Severity::Warning, "training.batch_size".into())]);
+        assert_eq!(check(rule, ModelType::ScNet, &yaml(400)), [(Severity::Error, "training.batch_size".into())]);
+    }
+
+    #[test]
+    fn learning_rate_in_usual_range() {
+        let rule = "learning_rate";
+        assert!(check(rule, ModelType::BsRoformer, "training: {lr: 5.0e-05}").is_empty());
+        assert_eq!(check(rule, ModelType::BsRoformer, "training: {lr: 0.1}"), [(Severity::Warning, "training.lr".into())]);
+        assert_eq!(check(rule, ModelType::BsRoformer, "training: {lr: 1.0e-08}"), [(Severity::Warning, "training.lr".into())]);
+        assert_eq!(check(rule, ModelType::BsRoformer, "training: {lr: 0}"), [(Severity::Error, "training.lr".into())]);
+        assert!(check(rule, ModelType::Apollo, "training: {lr: 1.0, optimizer: prodigy}").is_empty());
+    }
+
+    #[test]
+    fn single_epoch_is_flagged() {
+        let rule = "smoke_test_epochs";
+        assert_eq!(check(rule, ModelType::ScNet, "training: {num_epochs: 1}"), [(Severity::Warning, "training.num_epochs".into())]);
+        assert!(check(rule, ModelType::ScNet, "training: {num_epochs: 1000}").is_empty());
+    }
+
+    #[test]
+    fn warnings_can_be_overridden_but_errors_block() {
+        let config: Value = serde_yaml::from_str("audio: {chunk_size: 100, hop_length: 3}\ntraining: {num_epochs: 1}").unwrap();
+        let report = LintReport { issues: lint(&ModelType::MDX23C, &config) };
+        assert_eq!(report.blocking(&[]).len(), 2);
+        let overrides = vec!["smoke_test_epochs".to_string(), "hop_divides_chunk".to_string()];
+        let blocking = report.blocking(&overrides);
+        assert_eq!(blocking.len(), 1);
+        assert_eq!(blocking[0].rule, "hop_divides_chunk");
+        assert!(report.checklist(&overrides)[1].starts_with("~ overridden [smoke_test_epochs] training.num_epochs"));
+    }
+}
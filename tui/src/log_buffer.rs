@@ -0,0 +1,226 @@
+use chrono::{DateTime, Local, NaiveDateTime, SubsecRound, TimeDelta, Utc};
+use std::collections::VecDeque;
+
+/// Lines kept for scrolling back; older ones fall off.
+pub const LOG_CAPACITY: usize = 10_000;
+
+/// One received line. The receive time is stored as seconds since the
+/// buffer's start rather than a full timestamp, so a line costs 24 bytes
+/// plus its text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogLine {
+    offset_secs: u32,
+    pub severity: Severity,
+    pub text: Box<str>,
+}
+
+/// Which stream a line came from: what a process writes to stderr is
+/// shown as an error.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Severity {
+    #[default]
+    Info,
+    Error,
+}
+
+/// How receive times are shown next to lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TimeDisplay {
+    /// "14:03:12".
+    #[default]
+    Absolute,
+    /// "5m ago".
+    Relative,
+}
+
+impl TimeDisplay {
+    pub fn toggled(self) -> Self {
+        match self {
+            TimeDisplay::Absolute => TimeDisplay::Relative,
+            TimeDisplay::Relative => TimeDisplay::Absolute,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MarkerKind {
+    /// The first CUDA out-of-memory message.
+    OutOfMemory,
+    /// train.py's "Store weights:".
+    CheckpointSaved,
+}
+
+/// A notable line, for marking on a timeline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogMarker {
+    pub at: DateTime<Utc>,
+    pub kind: MarkerKind,
+    pub text: String,
+}
+
+/// The output of a run as received, each line with the time it arrived:
+/// neither train.py nor inference.py timestamps its own output.
+#[derive(Debug, Clone)]
+pub struct LogBuffer {
+    start: DateTime<Utc>,
+    lines: VecDeque<LogLine>,
+    capacity: usize,
+}
+
+impl Default for LogBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LogBuffer {
+    pub fn new() -> Self {
+        LogBuffer::with_capacity(LOG_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        LogBuffer { start: Utc::now().trunc_subsecs(0), lines: VecDeque::new(), capacity }
+    }
+
+    /// A buffer for lines received since `start`, e.g. a run's output read
+    /// back from its log files. Offsets are whole seconds, so the start is
+    /// too; otherwise a line would show up to a second early.
+    pub fn since(start: DateTime<Utc>) -> Self {
+        LogBuffer { start: start.trunc_subsecs(0), ..LogBuffer::new() }
+    }
+
+    pub fn push(&mut self, text: &str) -> &LogLine {
+        self.push_at(text, Utc::now())
+    }
+
+    pub fn push_at(&mut self, text: &str, at: DateTime<Utc>) -> &LogLine {
+        self.push_line(text, Severity::Info, at)
+    }
+
+    /// Adds a line of stderr.
+    pub fn push_error(&mut self, text: &str) -> &LogLine {
+        self.push_line(text, Severity::Error, Utc::now())
+    }
+
+    fn push_line(&mut self, text: &str, severity: Severity, at: DateTime<Utc>) -> &LogLine {
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+        let offset_secs = (at - self.start).num_seconds().clamp(0, u32::MAX as i64) as u32;
+        self.lines.push_back(LogLine { offset_secs, severity, text: text.into() });
+        self.lines.back().expect("just pushed")
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &LogLine> {
+        self.lines.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn received_at(&self, line: &LogLine) -> DateTime<Utc> {
+        self.start + TimeDelta::seconds(line.offset_secs.into())
+    }
+
+    /// The time column for `line` as of `now`.
+    pub fn time_label(&self, line: &LogLine, display: TimeDisplay, now: DateTime<Utc>) -> String {
+        let at = self.received_at(line);
+        match display {
+            TimeDisplay::Absolute => at.with_timezone(&Local).format("%H:%M:%S").to_string(),
+            TimeDisplay::Relative => {
+                let secs = (now - at).num_seconds().max(0);
+                match secs {
+                    0..=9 => "just now".to_string(),
+                    10..=59 => format!("{}s ago", secs),
+                    60..=3599 => format!("{}m ago", secs / 60),
+                    _ => format!("{}h {}m ago", secs / 3600, secs % 3600 / 60),
+                }
+            }
+        }
+    }
+
+    /// `line` as written to exported logs, e.g.
+    /// "[2026-10-17 14:03:12] Store weights: ...".
+    pub fn export_line(&self, line: &LogLine) -> String {
+        format!("[{}] {}", self.received_at(line).with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"), line.text)
+    }
+
+    /// Splits a line of an exported log into its receive time and text.
+    /// Lines without a timestamp come back whole.
+    pub fn parse_exported(line: &str) -> (Option<DateTime<Utc>>, &str) {
+        let stamped = line.strip_prefix('[')
+            .and_then(|rest| rest.split_once("] "))
+            .and_then(|(time, text)| Some((NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S").ok()?, text)));
+        match stamped {
+            Some((time, text)) => (time.and_local_timezone(Local).earliest().map(|time| time.to_utc()), text),
+            None => (None, line),
+        }
+    }
+
+    /// The first out-of-memory message and every checkpoint save still in
+    /// the buffer, oldest first.
+    pub fn markers(&self) -> Vec<LogMarker> {
+        let mut markers = vec![];
+        let mut seen_oom = false;
+        for line in &self.lines {
+            let kind = if line.text.contains("Store weights:") {
+                MarkerKind::CheckpointSaved
+            } else if !seen_oom && line.text.to_lowercase().contains("out of memory") {
+                seen_oom = true;
+                MarkerKind::OutOfMemory
+            } else {
+                continue;
+            };
+            markers.push(LogMarker { at: self.received_at(line), kind, text: line.text.to_string() });
+        }
+        markers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_stay_small_and_old_ones_fall_off() {
+        assert!(std::mem::size_of::<LogLine>() <= 24);
+        let mut buffer = LogBuffer::with_capacity(3);
+        let start = buffer.start;
+        for (minute, text) in ["epoch 1", "torch.OutOfMemoryError: CUDA out of memory", "Store weights: ep_1.ckpt", "CUDA out of memory again"]
+            .into_iter()
+            .enumerate()
+        {
+            buffer.push_at(text, start + TimeDelta::minutes(minute as i64));
+        }
+        assert_eq!(buffer.len(), 3);
+        let markers = buffer.markers();
+        assert_eq!(markers.iter().map(|marker| marker.kind).collect::<Vec<_>>(), [MarkerKind::OutOfMemory, MarkerKind::CheckpointSaved]);
+        assert_eq!(markers[1].at, start + TimeDelta::minutes(2));
+        assert_eq!(buffer.push_error("Traceback").severity, Severity::Error);
+        assert_eq!(buffer.lines().next().unwrap().severity, Severity::Info);
+    }
+
+    #[test]
+    fn times_show_absolute_or_relative() {
+        let mut buffer = LogBuffer::new();
+        let at = buffer.start + TimeDelta::seconds(30);
+        let line = buffer.push_at("warning", at).clone();
+        let label = |display, later| buffer.time_label(&line, display, at + later);
+
+        assert_eq!(label(TimeDisplay::Relative, TimeDelta::seconds(3)), "just now");
+        assert_eq!(label(TimeDisplay::Relative, TimeDelta::minutes(5)), "5m ago");
+        assert_eq!(label(TimeDisplay::Relative, TimeDelta::minutes(310)), "5h 10m ago");
+        let local = at.with_timezone(&Local);
+        assert_eq!(label(TimeDisplay::Absolute, TimeDelta::zero()), local.format("%H:%M:%S").to_string());
+        let exported = buffer.export_line(&line);
+        assert_eq!(exported, format!("[{}] warning", local.format("%Y-%m-%d %H:%M:%S")));
+        assert_eq!(LogBuffer::parse_exported(&exported), (Some(at), "warning"));
+        assert_eq!(LogBuffer::parse_exported("[not a time] text"), (None, "[not a time] text"));
+        assert_eq!(TimeDisplay::Absolute.toggled(), TimeDisplay::Relative);
+    }
+}
@@ -0,0 +1,129 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::Rect,
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use std::cell::Cell;
+use std::ops::Range;
+
+use crate::log_buffer::{LogBuffer, Severity};
+use crate::theme::Styles;
+
+/// A scrolled view over a `LogBuffer`. It follows new lines until scrolled
+/// up, and again once scrolled back down to the end.
+///
+/// Keys: PageUp/PageDown scroll a page, Up/Down a line, Home/End jump to
+/// the first and the latest line.
+#[derive(Debug)]
+pub struct LogPane {
+    /// First line shown; `None` follows the end.
+    top: Option<usize>,
+    page_size: Cell<usize>,
+    /// Where the last render drew the pane.
+    area: Cell<Rect>,
+}
+
+impl Default for LogPane {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LogPane {
+    pub fn new() -> Self {
+        LogPane { top: None, page_size: Cell::new(10), area: Cell::new(Rect::default()) }
+    }
+
+    pub fn is_following(&self) -> bool {
+        self.top.is_none()
+    }
+
+    /// Scrolls for `code` over a log of `len` lines; false if the key isn't
+    /// one of the pane's.
+    pub fn handle_key(&mut self, code: KeyCode, len: usize) -> bool {
+        let page = self.page_size.get().max(1);
+        let top = self.visible(len, page).start;
+        match code {
+            KeyCode::PageUp => self.scroll_to(top.saturating_sub(page), len, page),
+            KeyCode::PageDown => self.scroll_to(top + page, len, page),
+            KeyCode::Up => self.scroll_to(top.saturating_sub(1), len, page),
+            KeyCode::Down => self.scroll_to(top + 1, len, page),
+            KeyCode::Home => self.scroll_to(0, len, page),
+            KeyCode::End => self.top = None,
+            _ => return false,
+        }
+        true
+    }
+
+    fn scroll_to(&mut self, top: usize, len: usize, page: usize) {
+        self.top = (top < len.saturating_sub(page)).then_some(top);
+    }
+
+    /// Whether the last render drew the pane over `column`, `row`.
+    pub fn contains(&self, column: u16, row: u16) -> bool {
+        crate::mouse::contains(self.area.get(), column, row)
+    }
+
+    /// Indexes of the lines shown in `height` rows.
+    pub fn visible(&self, len: usize, height: usize) -> Range<usize> {
+        let last_page = len.saturating_sub(height);
+        let top = self.top.map_or(last_page, |top| top.min(last_page));
+        top..(top + height).min(len)
+    }
+
+    /// The log in a frame titled `title`, stderr lines in the error style.
+    pub fn render(&self, f: &mut Frame, area: Rect, title: &str, log: &LogBuffer, styles: &Styles) {
+        let block = Block::default().borders(Borders::ALL).border_style(styles.border);
+        let height = block.inner(area).height as usize;
+        self.page_size.set(height);
+        self.area.set(area);
+        let range = self.visible(log.len(), height);
+        let title = if self.is_following() {
+            title.to_string()
+        } else {
+            format!("{} (lines {}-{} of {}, End follows)", title, range.start + 1, range.end, log.len())
+        };
+        let lines: Vec<Line> = log.lines()
+            .skip(range.start)
+            .take(range.len())
+            .map(|line| match line.severity {
+                Severity::Error => Line::from(Span::styled(line.text.to_string(), styles.error)),
+                Severity::Info => Line::from(line.text.to_string()),
+            })
+            .collect();
+        f.render_widget(Paragraph::new(lines).block(block.title(title)), area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_until_scrolled_up() {
+        let mut pane = LogPane::new();
+        pane.page_size.set(5);
+        assert_eq!(pane.visible(3, 5), 0..3);
+        assert_eq!(pane.visible(20, 5), 15..20);
+
+        assert!(pane.handle_key(KeyCode::PageUp, 20));
+        assert_eq!(pane.visible(20, 5), 10..15);
+        assert_eq!(pane.visible(30, 5), 10..15, "new lines don't move the view");
+        assert!(pane.handle_key(KeyCode::Home, 30));
+        assert_eq!(pane.visible(30, 5), 0..5);
+        pane.handle_key(KeyCode::Down, 30);
+        assert_eq!(pane.visible(30, 5), 1..6);
+
+        for _ in 0..5 {
+            pane.handle_key(KeyCode::PageDown, 30);
+        }
+        assert!(pane.is_following(), "back at the end");
+        pane.handle_key(KeyCode::Up, 30);
+        assert!(!pane.is_following());
+        pane.handle_key(KeyCode::End, 30);
+        assert!(pane.is_following());
+        assert!(!pane.handle_key(KeyCode::Enter, 30));
+    }
+}
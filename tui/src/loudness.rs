@@ -0,0 +1,301 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::audio;
+use crate::manifest::{ManifestTrack, ResultsManifest};
+use crate::model::LoudnessMatch;
+
+pub const LOUDNESS_FILE: &str = "msst_loudness.json";
+
+const BLOCK_SECS: f64 = 0.4;
+const STEP_SECS: f64 = 0.1;
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+/// Gains smaller than this are not worth rewriting a stem for.
+const MIN_GAIN_DB: f64 = 0.01;
+
+/// Sidecar written next to the outputs when loudness matching ran.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoudnessReport {
+    pub mode: LoudnessMatch,
+    pub tracks: Vec<TrackLoudness>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrackLoudness {
+    pub track: String,
+    pub input_lufs: Option<f64>,
+    pub stems: Vec<StemGain>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StemGain {
+    pub name: String,
+    /// Loudness before the gain, of the stem or of the stem sum in `Sum` mode.
+    pub lufs: Option<f64>,
+    pub gain_db: f64,
+    /// False when the stem is not a wav we can rewrite in place.
+    pub applied: bool,
+}
+
+/// Integrated loudness per ITU-R BS.1770-4, with every channel weighted 1.0
+/// (exact for mono and stereo). Returns `None` for silence or audio shorter
+/// than one 400 ms block.
+pub fn integrated_lufs(planar: &[Vec<f32>], sample_rate: u32) -> Option<f64> {
+    let block = (BLOCK_SECS * sample_rate as f64).round() as usize;
+    let step = (STEP_SECS * sample_rate as f64).round() as usize;
+    let frames = planar.first().map_or(0, |channel| channel.len());
+    if planar.is_empty() || block == 0 || frames < block {
+        return None;
+    }
+
+    let filters = k_weighting(sample_rate as f64);
+    let squared: Vec<Vec<f64>> = planar.iter()
+        .map(|channel| {
+            let mut stages = filters;
+            channel.iter()
+                .map(|sample| {
+                    let value = stages.iter_mut().fold(*sample as f64, |value, stage| stage.process(value));
+                    value * value
+                })
+                .collect()
+        })
+        .collect();
+
+    // Mean square per block, summed over channels.
+    let mut powers = vec![];
+    let mut start = 0;
+    while start + block <= frames {
+        let power: f64 = squared.iter()
+            .map(|channel| channel[start..start + block].iter().sum::<f64>() / block as f64)
+            .sum();
+        powers.push(power);
+        start += step;
+    }
+
+    let loudness = |power: f64| -0.691 + 10.0 * power.log10();
+    let gated_mean = |threshold: f64| {
+        let kept: Vec<f64> = powers.iter().copied().filter(|power| loudness(*power) > threshold).collect();
+        (!kept.is_empty()).then(|| kept.iter().sum::<f64>() / kept.len() as f64)
+    };
+
+    let relative_gate = loudness(gated_mean(ABSOLUTE_GATE_LUFS)?) + RELATIVE_GATE_LU;
+    Some(loudness(gated_mean(relative_gate)?))
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 3]) -> Self {
+        Biquad {
+            b: [b[0] / a[0], b[1] / a[0], b[2] / a[0]],
+            a: [a[1] / a[0], a[2] / a[0]],
+            x: [0.0; 2],
+            y: [0.0; 2],
+        }
+    }
+
+    fn process(&mut self, input: f64) -> f64 {
+        let output = self.b[0] * input + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0] - self.a[1] * self.y[1];
+        self.x = [input, self.x[0]];
+        self.y = [output, self.y[0]];
+        output
+    }
+}
+
+/// The two BS.1770 pre-filters (head-related high shelf, then RLB high
+/// pass), designed for the given rate rather than hard-coded for 48 kHz.
+fn k_weighting(rate: f64) -> [Biquad; 2] {
+    use std::f64::consts::{FRAC_1_SQRT_2, PI};
+
+    let gain_db = 4.0;
+    let w0 = 2.0 * PI * 1500.0 / rate;
+    let alpha = w0.sin() / (2.0 * FRAC_1_SQRT_2);
+    let a = 10f64.powf(gain_db / 40.0);
+    let cos = w0.cos();
+    let shelf = Biquad::new(
+        [
+            a * ((a + 1.0) + (a - 1.0) * cos + 2.0 * a.sqrt() * alpha),
+            -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+            a * ((a + 1.0) + (a - 1.0) * cos - 2.0 * a.sqrt() * alpha),
+        ],
+        [
+            (a + 1.0) - (a - 1.0) * cos + 2.0 * a.sqrt() * alpha,
+            2.0 * ((a - 1.0) - (a + 1.0) * cos),
+            (a + 1.0) - (a - 1.0) * cos - 2.0 * a.sqrt() * alpha,
+        ],
+    );
+
+    let w0 = 2.0 * PI * 38.0 / rate;
+    let alpha = w0.sin() / (2.0 * 0.5);
+    let cos = w0.cos();
+    let high_pass = Biquad::new(
+        [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0],
+        [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+    );
+
+    [shelf, high_pass]
+}
+
+fn db_to_gain(db: f64) -> f32 {
+    10f64.powf(db / 20.0) as f32
+}
+
+/// Measures every track's input and applies a static gain to its stems so
+/// they match it, then records the gains in `LOUDNESS_FILE`.
+pub fn match_input_loudness(store_dir: &Path, manifest: &ResultsManifest, mode: LoudnessMatch) -> Result<LoudnessReport> {
+    let mut report = LoudnessReport { mode, tracks: vec![] };
+    if mode == LoudnessMatch::Off {
+        return Ok(report);
+    }
+
+    for track in &manifest.tracks {
+        report.tracks.push(match_track(store_dir, track, mode)
+            .with_context(|| format!("Failed to match loudness of {}", track.name))?);
+    }
+
+    let content = serde_json::to_string_pretty(&report)
+        .context("Failed to serialize loudness report")?;
+    fs::write(store_dir.join(LOUDNESS_FILE), content)
+        .context("Failed to write loudness report")?;
+    Ok(report)
+}
+
+fn match_track(store_dir: &Path, track: &ManifestTrack, mode: LoudnessMatch) -> Result<TrackLoudness> {
+    let (input_info, input) = audio::decode_planar(Path::new(&track.input_path))?;
+    let input_lufs = integrated_lufs(&input, input_info.sample_rate);
+
+    let mut stems = vec![];
+    for stem in &track.stems {
+        let (info, planar) = audio::decode_planar(&store_dir.join(&stem.path))?;
+        stems.push((stem, info, planar));
+    }
+
+    let sum_lufs = if mode == LoudnessMatch::Sum {
+        stems.first().and_then(|(_, info, _)| {
+            let mut sum: Vec<Vec<f32>> = vec![];
+            for (_, _, planar) in &stems {
+                for (channel, samples) in planar.iter().enumerate() {
+                    if sum.len() <= channel {
+                        sum.push(vec![]);
+                    }
+                    if sum[channel].len() < samples.len() {
+                        sum[channel].resize(samples.len(), 0.0);
+                    }
+                    for (total, sample) in sum[channel].iter_mut().zip(samples) {
+                        *total += sample;
+                    }
+                }
+            }
+            integrated_lufs(&sum, info.sample_rate)
+        })
+    } else {
+        None
+    };
+
+    let mut gains = vec![];
+    for (stem, info, mut planar) in stems {
+        let lufs = match mode {
+            LoudnessMatch::Sum => sum_lufs,
+            _ => integrated_lufs(&planar, info.sample_rate),
+        };
+        let gain_db = match (input_lufs, lufs) {
+            (Some(input), Some(lufs)) => input - lufs,
+            _ => 0.0,
+        };
+
+        let path = store_dir.join(&stem.path);
+        let is_wav = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("wav"));
+        let applied = is_wav && gain_db.abs() >= MIN_GAIN_DB;
+        if applied {
+            let gain = db_to_gain(gain_db);
+            for sample in planar.iter_mut().flatten() {
+                *sample *= gain;
+            }
+            audio::write_wav(&path, info.sample_rate, &planar)?;
+        }
+
+        gains.push(StemGain { name: stem.name.clone(), lufs, gain_db, applied });
+    }
+
+    Ok(TrackLoudness { track: track.name.clone(), input_lufs, stems: gains })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::ManifestStem;
+
+    fn sine(rate: u32, secs: f64, amplitude: f32) -> Vec<f32> {
+        (0..(rate as f64 * secs) as usize)
+            .map(|i| (i as f32 * 997.0 * std::f32::consts::TAU / rate as f32).sin() * amplitude)
+            .collect()
+    }
+
+    #[test]
+    fn full_scale_sine_reads_minus_three_lufs() {
+        // BS.1770: a 0 dBFS 1 kHz sine in one channel measures -3.01 LKFS.
+        for rate in [44_100, 48_000] {
+            let lufs = integrated_lufs(&[sine(rate, 3.0, 1.0)], rate).unwrap();
+            assert!((lufs + 3.01).abs() < 0.05, "{} Hz: {}", rate, lufs);
+        }
+
+        let quiet = integrated_lufs(&[sine(48_000, 3.0, 0.1), sine(48_000, 3.0, 0.1)], 48_000).unwrap();
+        assert!((quiet + 20.0).abs() < 0.05, "{}", quiet);
+
+        assert_eq!(integrated_lufs(&[vec![0.0; 48_000]], 48_000), None);
+        assert_eq!(integrated_lufs(&[sine(48_000, 0.2, 1.0)], 48_000), None);
+    }
+
+    #[test]
+    fn stems_are_matched_to_the_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let rate = 44_100;
+        let input = dir.path().join("song.wav");
+        audio::write_wav(&input, rate, &[sine(rate, 2.0, 0.5)]).unwrap();
+        fs::create_dir(dir.path().join("song")).unwrap();
+        audio::write_wav(&dir.path().join("song/vocals.wav"), rate, &[sine(rate, 2.0, 0.25)]).unwrap();
+        audio::write_wav(&dir.path().join("song/other.wav"), rate, &[sine(rate, 2.0, 0.25)]).unwrap();
+
+        let manifest = ResultsManifest {
+            model_type: None,
+            tracks: vec![ManifestTrack {
+                name: "song".into(),
+                input_path: input.to_string_lossy().to_string(),
+                stems: ["other", "vocals"].iter()
+                    .map(|name| ManifestStem { name: name.to_string(), path: format!("song/{}.wav", name), renamed_from: None })
+                    .collect(),
+                adjustments: vec![],
+                content_hash: None,
+            }],
+            excluded: vec![],
+            start_checkpoint: None,
+        };
+
+        // The two stems already sum to the input.
+        let report = match_input_loudness(dir.path(), &manifest, LoudnessMatch::Sum).unwrap();
+        for stem in &report.tracks[0].stems {
+            assert!(stem.gain_db.abs() < 0.01, "{:?}", stem);
+            assert!(!stem.applied);
+        }
+
+        let report = match_input_loudness(dir.path(), &manifest, LoudnessMatch::PerStem).unwrap();
+        for stem in &report.tracks[0].stems {
+            assert!((stem.gain_db - 6.02).abs() < 0.05, "{:?}", stem);
+            assert!(stem.applied);
+        }
+        let (_, vocals) = audio::decode_planar(&dir.path().join("song/vocals.wav")).unwrap();
+        let peak = vocals[0].iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+        assert!((peak - 0.5).abs() < 0.01, "{}", peak);
+        assert!(dir.path().join(LOUDNESS_FILE).exists());
+    }
+}
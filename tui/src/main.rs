@@ -1,45 +1,58 @@
-mod config;
-mod model;
-mod training;
-mod inference;
-mod ui;
-
-use ui::App;
+use mss_tui::cli::{write_completions, CliOptions};
+use mss_tui::headless;
+use mss_tui::project::{settings_path, ProjectSettings, ROOT_ENV};
+use mss_tui::ui::App;
 use std::env;
-use std::path::Path;
+use std::io;
+use std::path::PathBuf;
+use std::process;
 
 fn main() -> anyhow::Result<()> {
+    let options = CliOptions::parse();
+    if let Some(shell) = options.completions {
+        write_completions(shell, &mut io::stdout());
+        return Ok(());
+    }
+
     let current_exe = env::current_exe()?;
     let exe_dir = current_exe.parent()
         .ok_or_else(|| anyhow::anyhow!("Cannot get executable directory"))?;
-    
-    let project_root = find_project_root(exe_dir)?;
-    env::set_current_dir(&project_root)?;
+    let cwd = env::current_dir()?;
+
+    let settings_path = settings_path();
+    let mut settings = ProjectSettings::load(&settings_path)?;
+    // A root given by hand comes first, then the saved one, then the folders
+    // above the executable and above the working folder.
+    let found = match (&options.project_root, env::var_os(ROOT_ENV).filter(|root| !root.is_empty())) {
+        (Some(root), _) => Ok(settings.given_root(root, "--project-root")?),
+        (None, Some(root)) => Ok(settings.given_root(&PathBuf::from(root), ROOT_ENV)?),
+        (None, None) => settings.find_root(&[exe_dir, &cwd]),
+    };
+    let (project_root, scripts) = match found {
+        Ok(found) => found,
+        Err(examined) => {
+            let found = settings.recover(&examined, &mut io::stdin().lock(), &mut io::stdout())?;
+            settings.save(&settings_path)?;
+            println!("Saved to {}", settings_path.display());
+            found
+        }
+    };
+    // Relative paths in tui_config.yaml and the configs are resolved against
+    // the root explicitly, and every script is spawned in it.
+
+    if let Some(run) = &options.headless {
+        println!("Running {} from: {}", run.command.name(), project_root.display());
+        process::exit(headless::run(run, &project_root, &scripts)?);
+    }
     
     println!("TUI running from: {}", project_root.display());
     
     let mut app = App::new();
+    app.plain = options.plain;
+    app.read_only = options.dashboard;
+    app.selected_model = options.model;
+    app.scripts = scripts;
+    app.project_root = project_root;
     app.run()?;
     Ok(())
 }
-
-fn find_project_root(start_dir: &Path) -> anyhow::Result<&Path> {
-    let mut current = start_dir;
-    
-    loop {
-        if is_project_root(current) {
-            return Ok(current);
-        }
-        
-        match current.parent() {
-            Some(parent) => current = parent,
-            None => return Err(anyhow::anyhow!("Cannot find project root")),
-        }
-    }
-}
-
-fn is_project_root(dir: &Path) -> bool {
-    dir.join("train.py").exists() 
-        && dir.join("inference.py").exists()
-        && dir.join("README.md").exists()
-}
@@ -0,0 +1,192 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::model::{InputAdjustment, InputNote, ModelType};
+
+pub const MANIFEST_FILE: &str = "msst_results.json";
+
+const AUDIO_EXTENSIONS: [&str; 3] = ["wav", "flac", "mp3"];
+
+/// Index of what inference runs wrote into a store_dir, written next to the
+/// outputs. Stem paths are relative to the store_dir, input paths are kept as
+/// they were given to inference.py.
+///
+/// Tracks carry the content hash of their input, which makes the manifest
+/// the store_dir's duplicate index as well: both are one file, replaced
+/// atomically, so they can't disagree after a crash.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResultsManifest {
+    pub model_type: Option<ModelType>,
+    /// Checkpoint the outputs came from; stems are only reused for the same
+    /// model and checkpoint.
+    #[serde(default)]
+    pub start_checkpoint: Option<String>,
+    pub tracks: Vec<ManifestTrack>,
+    /// Inputs staging kept away from inference.py, with the reason.
+    #[serde(default)]
+    pub excluded: Vec<InputNote>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestTrack {
+    pub name: String,
+    pub input_path: String,
+    pub stems: Vec<ManifestStem>,
+    #[serde(default)]
+    pub adjustments: Vec<InputAdjustment>,
+    #[serde(default)]
+    pub content_hash: Option<String>,
+}
+
+impl ManifestTrack {
+    /// Whether every stem listed is still on disk.
+    pub fn outputs_exist(&self, store_dir: &Path) -> bool {
+        !self.stems.is_empty() && self.stems.iter().all(|stem| store_dir.join(&stem.path).is_file())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestStem {
+    /// As the model named it, whatever the file is called.
+    pub name: String,
+    pub path: String,
+    /// The path inference wrote, once the stem was renamed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub renamed_from: Option<String>,
+}
+
+impl ResultsManifest {
+    pub fn load(store_dir: &Path) -> Result<Option<Self>> {
+        let path = store_dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&path)
+            .context("Failed to read results manifest")?;
+        let manifest = serde_json::from_str(&content)
+            .context("Failed to parse results manifest")?;
+        Ok(Some(manifest))
+    }
+
+    /// Writes to a temporary file and renames it over the old manifest.
+    pub fn save(&self, store_dir: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)
+            .context("Failed to serialize results manifest")?;
+        let path = store_dir.join(MANIFEST_FILE);
+        let partial = store_dir.join(format!("{}.partial", MANIFEST_FILE));
+        fs::write(&partial, content)
+            .context("Failed to write results manifest")?;
+        fs::rename(&partial, &path)
+            .context("Failed to replace results manifest")?;
+        Ok(())
+    }
+
+    pub fn same_model(&self, model_type: &ModelType, start_checkpoint: &str) -> bool {
+        self.model_type.as_ref() == Some(model_type)
+            && self.start_checkpoint.as_deref() == Some(start_checkpoint)
+    }
+
+    /// Carries over tracks from an earlier run into the same store_dir that
+    /// this run didn't overwrite, as long as their stems are still there and
+    /// both runs used the same model.
+    pub fn keep_previous(&mut self, previous: ResultsManifest, store_dir: &Path) {
+        let same_model = self.model_type.as_ref()
+            .zip(self.start_checkpoint.as_deref())
+            .is_some_and(|(model_type, checkpoint)| previous.same_model(model_type, checkpoint));
+        if !same_model {
+            return;
+        }
+        let kept: Vec<ManifestTrack> = previous.tracks.into_iter()
+            .filter(|old| !self.tracks.iter().any(|track| track.name == old.name))
+            .filter(|old| old.outputs_exist(store_dir))
+            .collect();
+        self.tracks.extend(kept);
+        self.tracks.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    /// The first track with outputs on disk separated from content `hash`.
+    pub fn find_hash(&self, store_dir: &Path, hash: &str) -> Option<&ManifestTrack> {
+        self.tracks.iter()
+            .find(|track| track.content_hash.as_deref() == Some(hash) && track.outputs_exist(store_dir))
+    }
+
+    /// Builds a manifest from inference.py's default `{file_name}/{instr}`
+    /// output layout, pairing each output folder with the input file of the
+    /// same name.
+    pub fn scan(store_dir: &Path, input_folder: &Path) -> Result<Self> {
+        let mut inputs = vec![];
+        for entry in fs::read_dir(input_folder)
+            .context("Failed to read input folder")?
+        {
+            let path = entry.context("Failed to read directory entry")?.path();
+            if path.is_file() {
+                inputs.push(path);
+            }
+        }
+        inputs.sort();
+
+        let mut tracks = vec![];
+        for input in inputs {
+            let Some(name) = input.file_stem().map(|stem| stem.to_string_lossy().to_string()) else {
+                continue;
+            };
+            let track_dir = store_dir.join(&name);
+            if !track_dir.is_dir() {
+                continue;
+            }
+
+            let mut stems = vec![];
+            for entry in fs::read_dir(&track_dir)
+                .context("Failed to read track output directory")?
+            {
+                let path = entry.context("Failed to read directory entry")?.path();
+                if !is_audio_file(&path) {
+                    continue;
+                }
+                if let Some(stem) = path.file_stem() {
+                    stems.push(ManifestStem {
+                        name: stem.to_string_lossy().to_string(),
+                        path: format!("{}/{}", name, path.file_name().unwrap_or_default().to_string_lossy()),
+                        renamed_from: None,
+                    });
+                }
+            }
+            stems.sort_by(|a, b| a.name.cmp(&b.name));
+
+            tracks.push(ManifestTrack {
+                name,
+                input_path: input.to_string_lossy().to_string(),
+                stems,
+                adjustments: vec![],
+                content_hash: None,
+            });
+        }
+
+        Ok(ResultsManifest { tracks, ..Default::default() })
+    }
+
+    /// Finds the track and stem entry an output file belongs to.
+    pub fn find_stem(&self, store_dir: &Path, stem_path: &Path) -> Option<(&ManifestTrack, &ManifestStem)> {
+        let wanted = normalize(stem_path);
+        self.tracks.iter().find_map(|track| {
+            track.stems
+                .iter()
+                .find(|stem| normalize(&store_dir.join(&stem.path)) == wanted)
+                .map(|stem| (track, stem))
+        })
+    }
+}
+
+pub fn is_audio_file(path: &Path) -> bool {
+    path.is_file()
+        && path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| AUDIO_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
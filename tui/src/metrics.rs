@@ -0,0 +1,363 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::model::TrainingProgress;
+
+const CSV_HEADER: &str = "received_at,epoch,train_loss,valid_loss,sdr,sir,sar,isr,gpu_memory,epoch_secs,instrument_sdr";
+/// Exports from before SDR per instrument was written; still read.
+const CSV_HEADER_V1: &str = "received_at,epoch,train_loss,valid_loss,sdr,sir,sar,isr,gpu_memory,epoch_secs";
+
+/// Epochs of each kind the average duration is taken over.
+pub const EPOCH_WINDOW: usize = 10;
+
+/// Every progress update of a training run, with the time it came in.
+#[derive(Debug, Clone, Default)]
+pub struct TrainingHistory {
+    progress: Vec<TrainingProgress>,
+    received: Vec<DateTime<Utc>>,
+}
+
+/// One exported row. Missing values are empty cells in CSV and nulls in
+/// JSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricsRow {
+    pub received_at: DateTime<Utc>,
+    pub epoch: usize,
+    pub train_loss: f64,
+    pub valid_loss: Option<f64>,
+    pub sdr: Option<f64>,
+    pub sir: Option<f64>,
+    pub sar: Option<f64>,
+    pub isr: Option<f64>,
+    pub gpu_memory: Option<f64>,
+    /// Seconds since the update before; none for the first.
+    #[serde(default)]
+    pub epoch_secs: Option<f64>,
+    /// SDR of each instrument, in CSV as e.g. `vocals=9.12;other=5.3`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub instrument_sdr: Vec<(String, f64)>,
+}
+
+/// How long epochs take, averaged over the last `EPOCH_WINDOW` of each
+/// kind: validating makes an epoch much longer, so those are kept apart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EpochTiming {
+    pub train_secs: Option<f64>,
+    pub valid_secs: Option<f64>,
+    /// The part of the epochs so far that validated.
+    pub valid_share: f64,
+}
+
+impl EpochTiming {
+    /// Seconds an epoch takes on average, validation included as often as
+    /// it came up.
+    pub fn avg_secs(&self) -> f64 {
+        match (self.train_secs, self.valid_secs) {
+            (Some(train), Some(valid)) => train * (1.0 - self.valid_share) + valid * self.valid_share,
+            (Some(secs), None) | (None, Some(secs)) => secs,
+            (None, None) => 0.0,
+        }
+    }
+}
+
+impl TrainingHistory {
+    pub fn new() -> Self {
+        TrainingHistory::default()
+    }
+
+    /// The history a metrics export was written from, as far as the
+    /// export holds it: GPU utilization isn't exported.
+    pub fn from_rows(rows: Vec<MetricsRow>) -> Self {
+        let mut history = TrainingHistory::new();
+        for row in rows {
+            let progress = TrainingProgress {
+                epoch: row.epoch,
+                train_loss: row.train_loss,
+                valid_loss: row.valid_loss,
+                sdr: row.sdr,
+                sir: row.sir,
+                sar: row.sar,
+                isr: row.isr,
+                gpu_memory: row.gpu_memory,
+                instrument_sdr: row.instrument_sdr,
+                ..Default::default()
+            };
+            history.push_at(progress, row.received_at);
+        }
+        history
+    }
+
+    pub fn push(&mut self, progress: TrainingProgress) {
+        self.push_at(progress, Utc::now());
+    }
+
+    pub fn push_at(&mut self, progress: TrainingProgress, at: DateTime<Utc>) {
+        self.progress.push(progress);
+        self.received.push(at);
+    }
+
+    pub fn clear(&mut self) {
+        self.progress.clear();
+        self.received.clear();
+    }
+
+    /// The updates, oldest first.
+    pub fn progress(&self) -> &[TrainingProgress] {
+        &self.progress
+    }
+
+    pub fn len(&self) -> usize {
+        self.progress.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.progress.is_empty()
+    }
+
+    pub fn rows(&self) -> Vec<MetricsRow> {
+        self.progress.iter()
+            .zip(&self.received)
+            .zip(self.epoch_secs())
+            .map(|((progress, at), epoch_secs)| MetricsRow {
+                received_at: *at,
+                epoch: progress.epoch,
+                train_loss: progress.train_loss,
+                valid_loss: progress.valid_loss,
+                sdr: progress.sdr,
+                sir: progress.sir,
+                sar: progress.sar,
+                isr: progress.isr,
+                gpu_memory: progress.gpu_memory,
+                epoch_secs,
+                instrument_sdr: progress.instrument_sdr.clone(),
+            })
+            .collect()
+    }
+
+    /// Seconds between each update and the one before; none for the first.
+    pub fn epoch_secs(&self) -> Vec<Option<f64>> {
+        let mut secs = vec![None];
+        secs.extend(self.received.windows(2).map(|pair| Some((pair[1] - pair[0]).as_seconds_f64())));
+        secs.truncate(self.received.len());
+        secs
+    }
+
+    /// None until two updates are in.
+    pub fn timing(&self) -> Option<EpochTiming> {
+        let timed: Vec<(bool, f64)> = self.progress.iter()
+            .zip(self.epoch_secs())
+            .filter_map(|(progress, secs)| secs.map(|secs| (validated(progress), secs)))
+            .collect();
+        if timed.is_empty() {
+            return None;
+        }
+        let average = |valid: bool| {
+            let recent: Vec<f64> = timed.iter().rev().filter(|(v, _)| *v == valid).take(EPOCH_WINDOW).map(|(_, secs)| *secs).collect();
+            (!recent.is_empty()).then(|| recent.iter().sum::<f64>() / recent.len() as f64)
+        };
+        let valid_share = timed.iter().filter(|(valid, _)| *valid).count() as f64 / timed.len() as f64;
+        Some(EpochTiming { train_secs: average(false), valid_secs: average(true), valid_share })
+    }
+
+    /// E.g. "epoch 37/300, avg 14m 20s/epoch, est. finish Thu 06:45", from
+    /// the epochs train.py counts from 0.
+    pub fn timing_line(&self, planned_epochs: Option<usize>, now: DateTime<Local>) -> Option<String> {
+        let done = self.progress.last()?.epoch + 1;
+        let mut line = match planned_epochs {
+            Some(planned) => format!("epoch {}/{}", done, planned),
+            None => format!("epoch {}", done),
+        };
+        if let Some(timing) = self.timing() {
+            let avg = timing.avg_secs();
+            line.push_str(&format!(", avg {}/epoch", duration_text(avg)));
+            if let Some(planned) = planned_epochs {
+                let remaining = planned.saturating_sub(done) as f64 * avg;
+                let finish = now + chrono::TimeDelta::milliseconds((remaining * 1000.0) as i64);
+                line.push_str(&format!(", est. finish {}", finish.format("%a %H:%M")));
+            }
+        }
+        Some(line)
+    }
+
+    pub fn write_csv(&self, path: &Path) -> Result<()> {
+        let cell = |value: Option<f64>| value.map_or(String::new(), |value| value.to_string());
+        let mut content = format!("{}\n", CSV_HEADER);
+        for row in self.rows() {
+            let instruments = row.instrument_sdr.iter()
+                .map(|(name, sdr)| format!("{}={}", name.replace([',', ';', '='], "_"), sdr))
+                .collect::<Vec<_>>()
+                .join(";");
+            content.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{},{}\n",
+                row.received_at.to_rfc3339(),
+                row.epoch,
+                row.train_loss,
+                cell(row.valid_loss),
+                cell(row.sdr),
+                cell(row.sir),
+                cell(row.sar),
+                cell(row.isr),
+                cell(row.gpu_memory),
+                cell(row.epoch_secs),
+                instruments,
+            ));
+        }
+        fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn write_json(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(&self.rows())
+            .context("Failed to serialize training metrics")?;
+        fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Rows of a file `write_csv` wrote.
+pub fn read_csv(path: &Path) -> Result<Vec<MetricsRow>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut lines = content.lines();
+    if !matches!(lines.next(), Some(CSV_HEADER | CSV_HEADER_V1)) {
+        bail!("{} is not a metrics export", path.display());
+    }
+    lines.enumerate()
+        .map(|(i, line)| parse_csv_row(line).with_context(|| format!("Failed to parse line {} of {}", i + 2, path.display())))
+        .collect()
+}
+
+fn parse_csv_row(line: &str) -> Result<MetricsRow> {
+    let mut cells: Vec<&str> = line.split(',').collect();
+    if cells.len() == 10 {
+        cells.push("");
+    }
+    let [received_at, epoch, train_loss, valid_loss, sdr, sir, sar, isr, gpu_memory, epoch_secs, instruments] = cells.as_slice() else {
+        bail!("Expected 11 cells, found {}", cells.len());
+    };
+    let optional = |cell: &str| -> Result<Option<f64>> {
+        if cell.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(cell.parse().with_context(|| format!("Invalid number '{}'", cell))?))
+    };
+    Ok(MetricsRow {
+        received_at: DateTime::parse_from_rfc3339(received_at).context("Invalid time")?.to_utc(),
+        epoch: epoch.parse().context("Invalid epoch")?,
+        train_loss: train_loss.parse().context("Invalid train loss")?,
+        valid_loss: optional(valid_loss)?,
+        sdr: optional(sdr)?,
+        sir: optional(sir)?,
+        sar: optional(sar)?,
+        isr: optional(isr)?,
+        gpu_memory: optional(gpu_memory)?,
+        epoch_secs: optional(epoch_secs)?,
+        instrument_sdr: instruments.split(';')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (name, sdr) = pair.split_once('=').with_context(|| format!("Invalid instrument SDR '{}'", pair))?;
+                Ok((name.to_string(), sdr.parse().with_context(|| format!("Invalid number '{}'", sdr))?))
+            })
+            .collect::<Result<_>>()?,
+    })
+}
+
+/// Whether the update carries a validation summary.
+fn validated(progress: &TrainingProgress) -> bool {
+    progress.valid_loss.is_some() || progress.sdr.is_some() || !progress.instrument_sdr.is_empty()
+}
+
+/// E.g. "14m 20s", or "2h 03m" from an hour up.
+pub fn duration_text(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    match secs / 3600 {
+        0 => format!("{}m {:02}s", secs / 60, secs % 60),
+        hours => format!("{}h {:02}m", hours, secs / 60 % 60),
+    }
+}
+
+/// Rows of a file `write_json` wrote.
+pub fn read_json(path: &Path) -> Result<Vec<MetricsRow>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).context("Failed to parse training metrics")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history() -> TrainingHistory {
+        let start = DateTime::parse_from_rfc3339("2026-10-18T09:30:00Z").unwrap().to_utc();
+        let mut history = TrainingHistory::new();
+        history.push_at(TrainingProgress { epoch: 0, train_loss: 0.1234567, ..Default::default() }, start);
+        history.push_at(
+            TrainingProgress {
+                epoch: 1,
+                train_loss: 0.09,
+                valid_loss: Some(0.11),
+                sdr: Some(9.3411),
+                gpu_memory: Some(21.3),
+                instrument_sdr: vec![("vocals".to_string(), 9.5), ("other".to_string(), 8.0)],
+                ..Default::default()
+            },
+            start + chrono::TimeDelta::minutes(12),
+        );
+        history
+    }
+
+    #[test]
+    fn csv_round_trips_with_empty_cells() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metrics.csv");
+        let history = history();
+        history.write_csv(&path).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().nth(1), Some("2026-10-18T09:30:00+00:00,0,0.1234567,,,,,,,,"));
+        assert!(content.lines().nth(2).unwrap().ends_with(",21.3,720,vocals=9.5;other=8"));
+        assert_eq!(read_csv(&path).unwrap(), history.rows());
+        assert_eq!(TrainingHistory::from_rows(read_csv(&path).unwrap()).rows(), history.rows());
+        fs::write(&path, format!("{}\n2026-10-18T09:30:00Z,0,0.5,,4.5,,,,,\n", CSV_HEADER_V1)).unwrap();
+        assert_eq!(read_csv(&path).unwrap()[0].sdr, Some(4.5), "exports without SDR per instrument still read");
+        fs::write(&path, "epoch\n1\n").unwrap();
+        assert!(read_csv(&path).is_err());
+    }
+
+    #[test]
+    fn json_round_trips_with_nulls() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metrics.json");
+        let history = history();
+        history.write_json(&path).unwrap();
+
+        let value: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(value[0]["sdr"], serde_json::Value::Null);
+        assert_eq!(value[1]["sdr"], 9.3411);
+        assert_eq!(read_json(&path).unwrap(), history.rows());
+    }
+
+    #[test]
+    fn validation_epochs_are_averaged_apart() {
+        let start = DateTime::parse_from_rfc3339("2026-10-15T09:00:00Z").unwrap().to_utc();
+        let mut history = TrainingHistory::new();
+        let mut at = start;
+        for epoch in 0..8 {
+            // Ten-minute epochs, validating every other one for ten more.
+            let validates = epoch % 2 == 1;
+            at += chrono::TimeDelta::minutes(if validates { 20 } else { 10 });
+            let sdr = validates.then_some(5.0);
+            history.push_at(TrainingProgress { epoch, train_loss: 0.1, sdr, ..Default::default() }, at);
+        }
+        let timing = history.timing().unwrap();
+        assert_eq!((timing.train_secs, timing.valid_secs), (Some(600.0), Some(1200.0)));
+        assert!((timing.avg_secs() - 6600.0 / 7.0).abs() < 1e-6, "{:?}", timing);
+
+        let now = DateTime::parse_from_rfc3339("2026-10-15T12:00:00+00:00").unwrap().with_timezone(&Local);
+        let line = history.timing_line(Some(300), now).unwrap();
+        assert!(line.starts_with("epoch 8/300, avg 15m 43s/epoch, est. finish "), "{}", line);
+        assert_eq!(history.timing_line(None, now).unwrap(), "epoch 8, avg 15m 43s/epoch");
+        assert_eq!(TrainingHistory::new().timing_line(Some(300), now), None);
+    }
+}
@@ -0,0 +1,542 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::audio::{write_wav, AudioDecoder};
+use crate::manifest::ResultsManifest;
+use crate::model::TrackSidecar;
+use crate::preview::{db_to_linear, start_playback};
+use crate::task::TaskContext;
+
+const NO_SEEK: u64 = u64::MAX;
+
+/// Appended to the track name for its mixdown, next to its sidecar.
+pub const MIXDOWN_SUFFIX: &str = ".mixdown.wav";
+
+/// One stem's part in a blend.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StemMix {
+    pub name: String,
+    pub gain_db: f32,
+    #[serde(default)]
+    pub muted: bool,
+    #[serde(default)]
+    pub solo: bool,
+}
+
+/// How a track's stems are blended into its mixdown; kept in the track's
+/// sidecar once written, so the mixdown can be made again.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MixSettings {
+    pub stems: Vec<StemMix>,
+}
+
+impl MixSettings {
+    /// Every stem at unity gain.
+    pub fn new(names: &[String]) -> Self {
+        MixSettings {
+            stems: names.iter().map(|name| StemMix { name: name.clone(), gain_db: 0.0, muted: false, solo: false }).collect(),
+        }
+    }
+
+    /// `saved` where it covers exactly the stems in `names`, otherwise
+    /// unity gain: a blend of other stems says nothing about these.
+    pub fn for_stems(names: &[String], saved: Option<&MixSettings>) -> Self {
+        match saved {
+            Some(saved) if saved.stems.iter().map(|stem| &stem.name).eq(names.iter()) => saved.clone(),
+            _ => MixSettings::new(names),
+        }
+    }
+}
+
+struct StemControl {
+    /// dB, as f32 bits; kept in dB so a commit stores what was set.
+    gain_db: AtomicU32,
+    muted: AtomicBool,
+    solo: AtomicBool,
+}
+
+/// The blend as it is being adjusted, shared with the playback thread.
+/// Everything is atomic so the audio callback never waits on the UI.
+pub struct MixControls {
+    stems: Vec<StemControl>,
+    paused: AtomicBool,
+    stopped: AtomicBool,
+    position: AtomicU64,
+    seek_to: AtomicU64,
+}
+
+impl MixControls {
+    pub fn new(settings: &MixSettings) -> Self {
+        MixControls {
+            stems: settings.stems.iter()
+                .map(|stem| StemControl {
+                    gain_db: AtomicU32::new(stem.gain_db.to_bits()),
+                    muted: AtomicBool::new(stem.muted),
+                    solo: AtomicBool::new(stem.solo),
+                })
+                .collect(),
+            paused: AtomicBool::new(false),
+            stopped: AtomicBool::new(false),
+            position: AtomicU64::new(0),
+            seek_to: AtomicU64::new(NO_SEEK),
+        }
+    }
+
+    pub fn gain_db(&self, stem: usize) -> f32 {
+        self.stems.get(stem).map_or(0.0, |control| f32::from_bits(control.gain_db.load(Ordering::Relaxed)))
+    }
+
+    pub fn set_gain_db(&self, stem: usize, gain_db: f32) {
+        if let Some(control) = self.stems.get(stem) {
+            control.gain_db.store(gain_db.to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    pub fn is_muted(&self, stem: usize) -> bool {
+        self.stems.get(stem).is_some_and(|control| control.muted.load(Ordering::Relaxed))
+    }
+
+    pub fn is_solo(&self, stem: usize) -> bool {
+        self.stems.get(stem).is_some_and(|control| control.solo.load(Ordering::Relaxed))
+    }
+
+    pub fn toggle_mute(&self, stem: usize) {
+        if let Some(control) = self.stems.get(stem) {
+            control.muted.fetch_xor(true, Ordering::Relaxed);
+        }
+    }
+
+    pub fn toggle_solo(&self, stem: usize) {
+        if let Some(control) = self.stems.get(stem) {
+            control.solo.fetch_xor(true, Ordering::Relaxed);
+        }
+    }
+
+    /// The factor stem `stem` is played at: nothing when muted, or when
+    /// other stems are soloed and it isn't.
+    pub fn linear_gain(&self, stem: usize) -> f32 {
+        let any_solo = (0..self.stems.len()).any(|other| self.is_solo(other));
+        if self.is_muted(stem) || (any_solo && !self.is_solo(stem)) {
+            0.0
+        } else {
+            db_to_linear(self.gain_db(stem))
+        }
+    }
+
+    /// The blend as set right now, to commit.
+    pub fn settings(&self, names: &[String]) -> MixSettings {
+        MixSettings {
+            stems: names.iter()
+                .enumerate()
+                .map(|(index, name)| StemMix {
+                    name: name.clone(),
+                    gain_db: self.gain_db(index),
+                    muted: self.is_muted(index),
+                    solo: self.is_solo(index),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    pub fn position(&self) -> u64 {
+        self.position.load(Ordering::Relaxed)
+    }
+
+    pub fn seek(&self, frame: u64) {
+        self.seek_to.store(frame, Ordering::Relaxed);
+    }
+}
+
+/// One stem being read, a chunk at a time.
+struct StemStream {
+    decoder: AudioDecoder,
+    channels: usize,
+    chunk: Vec<f32>,
+    pos: usize,
+    finished: bool,
+}
+
+impl StemStream {
+    /// Adds the stem's next frame, times `gain`, to `frame`. Returns false,
+    /// adding nothing, once the stem has ended: shorter stems are padded
+    /// with silence.
+    fn mix_into(&mut self, frame: &mut [f32], gain: f32) -> bool {
+        if self.pos >= self.chunk.len() && !self.refill() {
+            return false;
+        }
+        let values = &self.chunk[self.pos..self.pos + self.channels];
+        for (channel, out) in frame.iter_mut().enumerate() {
+            *out += values[if self.channels == 1 { 0 } else { channel }] * gain;
+        }
+        self.pos += self.channels;
+        true
+    }
+
+    fn refill(&mut self) -> bool {
+        if self.finished {
+            return false;
+        }
+        match self.decoder.next_chunk() {
+            Ok(Some(chunk)) if !chunk.is_empty() => {
+                self.chunk.clear();
+                self.chunk.extend_from_slice(chunk);
+                self.pos = 0;
+                true
+            }
+            _ => {
+                self.finished = true;
+                false
+            }
+        }
+    }
+
+    fn seek(&mut self, frame: u64) {
+        self.chunk.clear();
+        self.pos = 0;
+        self.finished = self.decoder.seek(frame).is_err();
+    }
+}
+
+/// Interleaved samples of all stems of a track mixed together, with the
+/// gains, mutes and solos of `MixControls` applied frame by frame. Both the
+/// live preview and the written mixdown come from here, so what was heard
+/// is what gets written.
+pub struct MixSource {
+    stems: Vec<StemStream>,
+    controls: Arc<MixControls>,
+    channels: u16,
+    sample_rate: u32,
+    frame: Vec<f32>,
+    frame_pos: usize,
+    position: u64,
+}
+
+impl MixSource {
+    pub fn new(decoders: Vec<AudioDecoder>, controls: Arc<MixControls>) -> Result<Self> {
+        let first = decoders.first().ok_or_else(|| anyhow!("No stems to mix"))?.info();
+        let channels = decoders.iter().map(|decoder| decoder.info().channels).max().unwrap_or(1);
+        for decoder in &decoders {
+            let info = decoder.info();
+            if info.sample_rate != first.sample_rate {
+                bail!("Sample rates differ ({} Hz and {} Hz); mixing needs matching rates", first.sample_rate, info.sample_rate);
+            }
+            if info.channels != 1 && info.channels != channels {
+                bail!("Channel layouts differ ({} and {} channels)", info.channels, channels);
+            }
+        }
+        let stems = decoders.into_iter()
+            .map(|decoder| StemStream { channels: decoder.info().channels as usize, decoder, chunk: vec![], pos: 0, finished: false })
+            .collect();
+        Ok(MixSource {
+            stems,
+            controls,
+            channels,
+            sample_rate: first.sample_rate,
+            frame: vec![0.0; channels as usize],
+            frame_pos: channels as usize,
+            position: 0,
+        })
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Mixes the next frame into `frame`. False once every stem has ended.
+    fn next_frame(&mut self) -> bool {
+        self.frame.fill(0.0);
+        let mut any = false;
+        for (index, stem) in self.stems.iter_mut().enumerate() {
+            // Every stem is read, muted or not, so they stay in step.
+            any |= stem.mix_into(&mut self.frame, self.controls.linear_gain(index));
+        }
+        if any {
+            self.position += 1;
+            self.controls.position.store(self.position, Ordering::Relaxed);
+        }
+        any
+    }
+
+    fn reposition(&mut self, frame: u64) {
+        for stem in &mut self.stems {
+            stem.seek(frame);
+        }
+        self.position = frame;
+        self.controls.position.store(frame, Ordering::Relaxed);
+    }
+}
+
+impl Iterator for MixSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.controls.stopped.load(Ordering::Relaxed) {
+            return None;
+        }
+        if self.frame_pos >= self.frame.len() {
+            let seek = self.controls.seek_to.swap(NO_SEEK, Ordering::Relaxed);
+            if seek != NO_SEEK {
+                self.reposition(seek);
+            }
+            if self.controls.is_paused() {
+                return Some(0.0);
+            }
+            // Past the end the stream stays alive, silent, so the user can
+            // still seek back.
+            if !self.next_frame() {
+                return Some(0.0);
+            }
+            self.frame_pos = 0;
+        }
+        let value = self.frame[self.frame_pos];
+        self.frame_pos += 1;
+        Some(value)
+    }
+}
+
+#[cfg(feature = "playback")]
+impl rodio::Source for MixSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<std::time::Duration> {
+        None
+    }
+}
+
+/// The stems of `track` in `store_dir`, by name and path, from its results
+/// manifest.
+pub fn track_stems(store_dir: &Path, track: &str) -> Result<Vec<(String, PathBuf)>> {
+    let manifest = ResultsManifest::load(store_dir)?
+        .ok_or_else(|| anyhow!("No results manifest in {}", store_dir.display()))?;
+    let track = manifest.tracks.iter()
+        .find(|candidate| candidate.name == track)
+        .ok_or_else(|| anyhow!("{} is not listed in the results manifest", track))?;
+    Ok(track.stems.iter().map(|stem| (stem.name.clone(), store_dir.join(&stem.path))).collect())
+}
+
+pub fn mixdown_path(store_dir: &Path, track: &str) -> PathBuf {
+    store_dir.join(format!("{}{}", track, MIXDOWN_SUFFIX))
+}
+
+/// Writes the mixdown of `track` with `settings` as a float wav as long as
+/// the longest stem, then stores `settings` in the track's sidecar.
+pub fn render(store_dir: &Path, track: &str, settings: &MixSettings, ctx: &TaskContext) -> Result<PathBuf> {
+    let sidecar_path = TrackSidecar::path(store_dir, track);
+    let mut sidecar = TrackSidecar::load(&sidecar_path)
+        .with_context(|| format!("No usable sidecar for {}; the blend would not be kept", track))?;
+    let stems = track_stems(store_dir, track)?;
+    let names: Vec<String> = stems.iter().map(|(name, _)| name.clone()).collect();
+    if MixSettings::for_stems(&names, Some(settings)) != *settings {
+        bail!("The blend doesn't match the stems of {}", track);
+    }
+    let decoders = stems.iter().map(|(_, path)| AudioDecoder::open(path)).collect::<Result<Vec<_>>>()?;
+    if let Some(frames) = decoders.iter().filter_map(|decoder| decoder.info().total_frames).max() {
+        ctx.set_total(frames);
+    }
+    let mut source = MixSource::new(decoders, Arc::new(MixControls::new(settings)))?;
+
+    let mut planar = vec![vec![]; source.channels() as usize];
+    while source.next_frame() {
+        for (channel, value) in planar.iter_mut().zip(&source.frame) {
+            channel.push(*value);
+        }
+        if source.position.is_multiple_of(source.sample_rate as u64) {
+            ctx.check()?;
+            ctx.set_done(source.position);
+        }
+    }
+    let path = mixdown_path(store_dir, track);
+    write_wav(&path, source.sample_rate(), &planar)?;
+    sidecar.mix = Some(settings.clone());
+    sidecar.save(store_dir)?;
+    Ok(path)
+}
+
+/// Live preview of a track's stems mixed together, adjusted from the UI.
+/// Without an audio device the blend can still be set and committed; the
+/// reason is in `playback_error`.
+pub struct MixPreview {
+    pub store_dir: PathBuf,
+    pub track: String,
+    pub names: Vec<String>,
+    pub sample_rate: u32,
+    pub total_frames: Option<u64>,
+    pub playback_error: Option<String>,
+    controls: Arc<MixControls>,
+}
+
+impl MixPreview {
+    /// Starts from the blend last committed for the track, if any.
+    pub fn open(store_dir: &Path, track: &str) -> Result<Self> {
+        let stems = track_stems(store_dir, track)?;
+        let names: Vec<String> = stems.iter().map(|(name, _)| name.clone()).collect();
+        let saved = TrackSidecar::load(&TrackSidecar::path(store_dir, track)).ok().and_then(|sidecar| sidecar.mix);
+        let controls = Arc::new(MixControls::new(&MixSettings::for_stems(&names, saved.as_ref())));
+        let decoders = stems.iter().map(|(_, path)| AudioDecoder::open(path)).collect::<Result<Vec<_>>>()?;
+        let total_frames = decoders.iter().filter_map(|decoder| decoder.info().total_frames).max();
+        let source = MixSource::new(decoders, controls.clone())?;
+        let sample_rate = source.sample_rate();
+        let stop = controls.clone();
+        let playback_error = start_playback(source, move || stop.stopped.load(Ordering::Relaxed))
+            .err()
+            .map(|e| format!("{:#}", e));
+
+        Ok(MixPreview {
+            store_dir: store_dir.to_path_buf(),
+            track: track.to_string(),
+            names,
+            sample_rate,
+            total_frames,
+            playback_error,
+            controls,
+        })
+    }
+
+    pub fn controls(&self) -> &MixControls {
+        &self.controls
+    }
+
+    /// The blend as set right now.
+    pub fn settings(&self) -> MixSettings {
+        self.controls.settings(&self.names)
+    }
+
+    pub fn position_secs(&self) -> f64 {
+        self.controls.position() as f64 / self.sample_rate as f64
+    }
+
+    pub fn duration_secs(&self) -> Option<f64> {
+        self.total_frames.map(|frames| frames as f64 / self.sample_rate as f64)
+    }
+
+    pub fn seek_by(&self, delta_secs: f64) {
+        let target = (self.position_secs() + delta_secs).max(0.0);
+        self.controls.seek((target * self.sample_rate as f64) as u64);
+    }
+
+    /// E.g. "vocals  +1.5 dB  muted".
+    pub fn stem_line(&self, stem: usize) -> String {
+        let mut line = format!("{}  {:+.1} dB", self.names[stem], self.controls.gain_db(stem));
+        if self.controls.is_solo(stem) {
+            line.push_str("  solo");
+        }
+        if self.controls.is_muted(stem) {
+            line.push_str("  muted");
+        }
+        line
+    }
+}
+
+impl Drop for MixPreview {
+    fn drop(&mut self) {
+        self.controls.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::decode_planar;
+    use crate::manifest::{ManifestStem, ManifestTrack};
+
+    /// A store_dir with one track whose stems hold constant values, the
+    /// bass shorter than the rest.
+    fn store_dir() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("song")).unwrap();
+        let mut stems = vec![];
+        for (name, value, frames) in [("bass", 0.25, 50), ("drums", 0.125, 100), ("vocals", 0.5, 100)] {
+            write_wav(&dir.path().join(format!("song/{}.wav", name)), 8000, &[vec![value; frames], vec![value; frames]]).unwrap();
+            stems.push(ManifestStem { name: name.into(), path: format!("song/{}.wav", name), renamed_from: None });
+        }
+        let manifest = ResultsManifest {
+            tracks: vec![ManifestTrack { name: "song".into(), input_path: "in/song.wav".into(), stems, adjustments: vec![], content_hash: None }],
+            ..Default::default()
+        };
+        manifest.save(dir.path()).unwrap();
+        dir
+    }
+
+    fn source(dir: &Path, controls: &Arc<MixControls>) -> MixSource {
+        let decoders = track_stems(dir, "song").unwrap().iter().map(|(_, path)| AudioDecoder::open(path).unwrap()).collect();
+        MixSource::new(decoders, controls.clone()).unwrap()
+    }
+
+    #[test]
+    fn gains_mutes_and_solos_apply_live() {
+        let dir = store_dir();
+        let names = ["bass", "drums", "vocals"].map(String::from);
+        let controls = Arc::new(MixControls::new(&MixSettings::new(&names)));
+        let mut mix = source(dir.path(), &controls);
+        assert_eq!(mix.next(), Some(0.875));
+
+        controls.toggle_mute(2);
+        mix.next();
+        assert_eq!(mix.next(), Some(0.375));
+        controls.set_gain_db(1, 6.0206);
+        controls.toggle_solo(1);
+        mix.next();
+        assert!((mix.next().unwrap() - 0.25).abs() < 1e-4);
+        assert_eq!(controls.settings(&names).stems[1], StemMix { name: "drums".into(), gain_db: 6.0206, muted: false, solo: true });
+
+        // Past the bass' end only the others are left.
+        controls.toggle_solo(1);
+        controls.toggle_mute(2);
+        controls.seek(60);
+        // The right channel of the frame before the seek is still due.
+        mix.next();
+        let value = mix.next().unwrap();
+        assert!((value - 0.75).abs() < 1e-4, "{}", value);
+        assert_eq!(controls.position(), 61);
+    }
+
+    #[test]
+    fn committed_blend_is_rendered_and_kept_in_the_sidecar() {
+        let dir = store_dir();
+        let sidecar = r#"{"schema_version":1,"track":"song","result":{"input_file":"in/song.wav","output_dir":"song","duration":null,"success":true,"error_message":null},"model":{"model_type":"ScNet","config_path":"c.yaml","checkpoint":"m.ckpt"},"stems":[],"sample_rate":8000,"post_processing":{"normalize":null,"use_tta":false,"loudness_match":"Off","format":"wav"}}"#;
+        std::fs::write(TrackSidecar::path(dir.path(), "song"), sidecar).unwrap();
+        let names = ["bass", "drums", "vocals"].map(String::from);
+        let mut settings = MixSettings::new(&names);
+        settings.stems[2].muted = true;
+
+        let path = render(dir.path(), "song", &settings, &TaskContext::default()).unwrap();
+        let (info, planar) = decode_planar(&path).unwrap();
+        assert_eq!((info.channels, planar[0].len()), (2, 100));
+        assert_eq!((planar[0][0], planar[1][99]), (0.375, 0.125));
+
+        let preview = MixPreview::open(dir.path(), "song").unwrap();
+        assert_eq!(preview.settings(), settings);
+        assert_eq!(preview.stem_line(2), "vocals  +0.0 dB  muted");
+        let other = MixSettings::new(&names[..2]);
+        assert!(render(dir.path(), "song", &other, &TaskContext::default()).is_err());
+    }
+}
@@ -1,4 +1,15 @@
+use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::loudness::TrackLoudness;
+use crate::mixdown::MixSettings;
+use crate::priority::ProcessPriority;
+use crate::reconstruction::TrackReconstruction;
+use crate::secret::{redact, SecretRef};
+use crate::shell_words;
+use crate::stem_names::StemNaming;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ModelType {
@@ -84,6 +95,41 @@ impl ModelType {
         }
     }
 
+    /// Python packages the model's code imports beyond what every model
+    /// needs, as (import name, pip package). Checked by `preflight`.
+    pub fn required_packages(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            ModelType::BsRoformer | ModelType::MelBandRoformer => &[
+                ("rotary_embedding_torch", "rotary-embedding-torch"),
+                ("beartype", "beartype"),
+                ("einops", "einops"),
+            ],
+            ModelType::TsBsMamba2 => &[("mamba_ssm", "mamba-ssm"), ("einops", "einops")],
+            ModelType::HtDemucs => &[("demucs", "demucs")],
+            ModelType::VitLarge23 => &[("segmentation_models_pytorch", "segmentation-models-pytorch")],
+            ModelType::TorchSeg => &[("torchseg", "torchseg")],
+            ModelType::SwinUpernet => &[("transformers", "transformers")],
+            _ => &[],
+        }
+    }
+
+    /// Models that restore audio rather than separate it; their output is
+    /// not meant to sum back to the input.
+    pub fn is_restoration(&self) -> bool {
+        matches!(self, ModelType::Apollo)
+    }
+
+    /// File names the repo gives this model's configs in `configs/`, best
+    /// first, e.g. `config_musdb18_scnet.yaml`.
+    pub fn default_config_candidates(&self) -> Vec<String> {
+        let key = self.key();
+        vec![
+            format!("config_musdb18_{}.yaml", key),
+            format!("config_{}.yaml", key),
+            format!("config_vocals_{}.yaml", key),
+        ]
+    }
+
     pub fn all_models() -> Vec<ModelType> {
         vec![
             ModelType::MDX23C,
@@ -104,6 +150,20 @@ impl ModelType {
             ModelType::ScNetMasked,
         ]
     }
+
+    /// Models whose name, key or description contain `query`, ignoring
+    /// case, in `all_models` order. An empty query matches every model.
+    pub fn filter(query: &str) -> Vec<ModelType> {
+        let query = query.trim().to_lowercase();
+        Self::all_models()
+            .into_iter()
+            .filter(|model| {
+                [model.name(), model.key(), model.description()]
+                    .iter()
+                    .any(|text| text.to_lowercase().contains(&query))
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -116,6 +176,73 @@ pub struct TrainingConfig {
     pub valid_path: Option<String>,
     pub num_workers: Option<usize>,
     pub device_ids: Option<Vec<usize>>,
+    /// train.py `--force_cpu`, for machines without a GPU; `device_ids` is
+    /// then left out.
+    #[serde(default)]
+    pub force_cpu: bool,
+    /// Appended to the run directory name.
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Passed to train.py as `--wandb_key`; resolved only when the run starts.
+    #[serde(default)]
+    pub wandb_key: Option<SecretRef>,
+    /// Lint rules whose warnings are accepted for this run; see `lint::RULES`.
+    #[serde(default)]
+    pub lint_overrides: Vec<String>,
+    /// Niceness, I/O class and CPU cores for the train.py process.
+    #[serde(default, skip_serializing_if = "ProcessPriority::is_default")]
+    pub priority: ProcessPriority,
+    /// Name of the run this config was duplicated from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derived_from: Option<String>,
+    /// Appended verbatim to the train.py command line, after everything
+    /// the form sets.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_args: Vec<String>,
+}
+
+impl TrainingConfig {
+    /// Defaults for `model_type`, with paths still to be filled in.
+    pub fn new(model_type: ModelType) -> Self {
+        TrainingConfig {
+            model_type,
+            config_path: String::new(),
+            start_checkpoint: None,
+            results_path: String::new(),
+            data_paths: vec![],
+            valid_path: None,
+            num_workers: None,
+            device_ids: None,
+            force_cpu: false,
+            label: None,
+            wandb_key: None,
+            lint_overrides: vec![],
+            priority: ProcessPriority::default(),
+            derived_from: None,
+            extra_args: vec![],
+        }
+    }
+
+    /// With its relative paths made absolute under `root`, for a local run.
+    pub fn in_root(&self, root: &Path) -> Self {
+        TrainingConfig {
+            config_path: rooted(root, &self.config_path),
+            start_checkpoint: self.start_checkpoint.as_deref().map(|path| rooted(root, path)),
+            results_path: rooted(root, &self.results_path),
+            data_paths: self.data_paths.iter().map(|path| rooted(root, path)).collect(),
+            valid_path: self.valid_path.as_deref().map(|path| rooted(root, path)),
+            ..self.clone()
+        }
+    }
+}
+
+/// `path` under `root` unless it is absolute or empty: relative paths in the
+/// configs are relative to the project root, wherever the TUI was started.
+pub fn rooted(root: &Path, path: &str) -> String {
+    if path.trim().is_empty() {
+        return path.to_string();
+    }
+    root.join(path).to_string_lossy().into_owned()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -125,6 +252,185 @@ pub struct InferenceConfig {
     pub start_checkpoint: String,
     pub input_folder: String,
     pub store_dir: String,
+    #[serde(default)]
+    pub sample_rate_policy: SampleRatePolicy,
+    #[serde(default)]
+    pub channel_policy: ChannelPolicy,
+    /// Overrides `inference.normalize` in the model config; `None` keeps it.
+    #[serde(default)]
+    pub normalize: Option<bool>,
+    #[serde(default)]
+    pub loudness_match: LoudnessMatch,
+    #[serde(default)]
+    pub duplicate_policy: DuplicatePolicy,
+    /// inference.py `--use_tta`: also separate flipped and inverted copies.
+    #[serde(default)]
+    pub use_tta: bool,
+    /// inference.py `--flac_file`: write stems as FLAC instead of WAV.
+    #[serde(default)]
+    pub flac_file: bool,
+    /// File names in `input_folder` deselected before the run; staging
+    /// leaves them out.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded_inputs: Vec<String>,
+    /// Stem files renamed this way once the outputs are indexed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stem_naming: Option<StemNaming>,
+    /// Sums the stems back after separation and flags tracks whose residual
+    /// against the input is above this many dB; off when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reconstruction_threshold_db: Option<f64>,
+    /// Appended verbatim to the inference.py command line, after everything
+    /// the form sets.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_args: Vec<String>,
+}
+
+impl InferenceConfig {
+    /// With its relative paths made absolute under `root`, for a local run.
+    pub fn in_root(&self, root: &Path) -> Self {
+        InferenceConfig {
+            config_path: rooted(root, &self.config_path),
+            start_checkpoint: rooted(root, &self.start_checkpoint),
+            input_folder: rooted(root, &self.input_folder),
+            store_dir: rooted(root, &self.store_dir),
+            ..self.clone()
+        }
+    }
+
+    /// Defaults for `model_type`, with paths still to be filled in.
+    pub fn new(model_type: ModelType) -> Self {
+        InferenceConfig {
+            model_type,
+            config_path: String::new(),
+            start_checkpoint: String::new(),
+            input_folder: String::new(),
+            store_dir: String::new(),
+            sample_rate_policy: Default::default(),
+            channel_policy: Default::default(),
+            normalize: None,
+            loudness_match: Default::default(),
+            duplicate_policy: Default::default(),
+            use_tta: false,
+            flac_file: false,
+            excluded_inputs: vec![],
+            stem_naming: None,
+            reconstruction_threshold_db: None,
+            extra_args: vec![],
+        }
+    }
+
+    /// One line per option that changes what inference.py sees or what
+    /// happens to its outputs afterwards.
+    pub fn options_summary(&self) -> Vec<String> {
+        let normalize = match self.normalize {
+            Some(true) => "on",
+            Some(false) => "off",
+            None => "model config",
+        };
+        let mut lines = vec![
+            format!("Sample rate mismatch: {}", self.sample_rate_policy.name()),
+            format!("Channel mismatch: {}", self.channel_policy.name()),
+            format!("Normalize: {}", normalize),
+            format!("Match input loudness: {}", self.loudness_match.name()),
+            format!("Duplicate inputs: {}", self.duplicate_policy.name()),
+            format!("Test-time augmentation: {}", if self.use_tta { "on" } else { "off" }),
+            format!("Output format: {}", if self.flac_file { "FLAC" } else { "WAV" }),
+            match self.reconstruction_threshold_db {
+                Some(threshold) => format!("Verify stem sum: flag above {:.0} dB", threshold),
+                None => "Verify stem sum: off".to_string(),
+            },
+        ];
+        if !self.extra_args.is_empty() {
+            lines.push(format!("Extra args: {}", shell_words::join(&self.extra_args)));
+        }
+        lines
+    }
+}
+
+/// What to do with inputs whose sample rate differs from the model's
+/// `audio.sample_rate`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum SampleRatePolicy {
+    Block,
+    #[default]
+    Warn,
+    Resample,
+}
+
+impl SampleRatePolicy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SampleRatePolicy::Block => "block",
+            SampleRatePolicy::Warn => "warn and continue",
+            SampleRatePolicy::Resample => "resample",
+        }
+    }
+}
+
+/// Post-processing gain applied to output stems so they match the input's
+/// integrated loudness.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum LoudnessMatch {
+    #[default]
+    Off,
+    /// Every stem on its own is brought to the input's loudness.
+    PerStem,
+    /// One gain for all stems, so that their sum matches the input.
+    Sum,
+}
+
+impl LoudnessMatch {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LoudnessMatch::Off => "off",
+            LoudnessMatch::PerStem => "per stem",
+            LoudnessMatch::Sum => "stem sum",
+        }
+    }
+}
+
+/// What to do with inputs whose channel count differs from the model's
+/// `audio.num_channels`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum ChannelPolicy {
+    /// Duplicate mono up, downmix anything wider with a standard matrix.
+    #[default]
+    Convert,
+    Exclude,
+}
+
+impl ChannelPolicy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChannelPolicy::Convert => "upmix / downmix",
+            ChannelPolicy::Exclude => "exclude",
+        }
+    }
+}
+
+/// What to do with an input whose content was already separated into the
+/// same store_dir by the same model, or that appears twice in one batch.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum DuplicatePolicy {
+    #[default]
+    Process,
+    Skip,
+    /// Hard-link the existing stems under the new input's name, copying
+    /// where links aren't possible.
+    Link,
+    Copy,
+}
+
+impl DuplicatePolicy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DuplicatePolicy::Process => "process anyway",
+            DuplicatePolicy::Skip => "skip",
+            DuplicatePolicy::Link => "link existing outputs",
+            DuplicatePolicy::Copy => "copy existing outputs",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -135,7 +441,127 @@ pub struct ValidationConfig {
     pub valid_path: String,
 }
 
-#[derive(Debug, Clone)]
+impl ValidationConfig {
+    /// With its relative paths made absolute under `root`.
+    pub fn in_root(&self, root: &Path) -> Self {
+        ValidationConfig {
+            config_path: rooted(root, &self.config_path),
+            start_checkpoint: rooted(root, &self.start_checkpoint),
+            valid_path: rooted(root, &self.valid_path),
+            ..self.clone()
+        }
+    }
+}
+
+/// One instrument's metrics from a valid.py run, in dB.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ValidationResult {
+    pub instrument: String,
+    pub sdr: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub si_sdr: Option<f64>,
+}
+
+/// Stems of the same track from several models, combined into one file by
+/// ensemble.py.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EnsembleConfig {
+    pub files: Vec<String>,
+    /// One per file, in the same order.
+    pub weights: Vec<f64>,
+    pub output_path: String,
+    /// One of `ensemble::ENSEMBLE_TYPES`, e.g. "avg_wave".
+    pub ensemble_type: String,
+}
+
+impl EnsembleConfig {
+    /// With its relative paths made absolute under `root`.
+    pub fn in_root(&self, root: &Path) -> Self {
+        EnsembleConfig {
+            files: self.files.iter().map(|path| rooted(root, path)).collect(),
+            output_path: rooted(root, &self.output_path),
+            ..self.clone()
+        }
+    }
+}
+
+impl Default for EnsembleConfig {
+    fn default() -> Self {
+        EnsembleConfig {
+            files: vec![],
+            weights: vec![],
+            output_path: "ensemble.wav".to_string(),
+            ensemble_type: "avg_wave".to_string(),
+        }
+    }
+}
+
+/// A process invocation kept as data, so it can be shown, recorded in a run
+/// directory and replayed later.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CommandSpec {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Extra environment variables for the process.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env: Vec<(String, String)>,
+    /// Folder the process runs in; the TUI's own when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_dir: Option<PathBuf>,
+}
+
+impl CommandSpec {
+    pub fn new(program: &str) -> Self {
+        CommandSpec {
+            program: program.to_string(),
+            args: vec![],
+            env: vec![],
+            current_dir: None,
+        }
+    }
+
+    pub fn arg(&mut self, arg: impl AsRef<std::ffi::OsStr>) -> &mut Self {
+        self.args.push(arg.as_ref().to_string_lossy().to_string());
+        self
+    }
+
+    pub fn env(&mut self, key: &str, value: &str) -> &mut Self {
+        self.env.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn command(&self) -> tokio::process::Command {
+        let mut cmd = tokio::process::Command::new(&self.program);
+        cmd.args(&self.args).envs(self.env.iter().cloned());
+        if let Some(dir) = &self.current_dir {
+            cmd.current_dir(dir);
+        }
+        cmd
+    }
+
+    /// Like `command`, for callers outside the async runtime.
+    pub fn std_command(&self) -> std::process::Command {
+        let mut cmd = std::process::Command::new(&self.program);
+        cmd.args(&self.args).envs(self.env.iter().cloned());
+        if let Some(dir) = &self.current_dir {
+            cmd.current_dir(dir);
+        }
+        cmd
+    }
+
+    /// The command line as it would be typed into a POSIX shell, with
+    /// secrets masked.
+    pub fn display(&self) -> String {
+        let line = self.env.iter()
+            .map(|(key, value)| format!("{}={}", key, shell_words::quote(value)))
+            .chain(std::iter::once(&self.program).chain(&self.args).map(|word| shell_words::quote(word)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        redact(&line)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct TrainingProgress {
     pub epoch: usize,
     pub train_loss: f64,
@@ -146,13 +572,293 @@ pub struct TrainingProgress {
     pub isr: Option<f64>,
     pub gpu_memory: Option<f64>,
     pub gpu_utilization: Option<f64>,
+    /// SDR of each instrument from the validation summary.
+    pub instrument_sdr: Vec<(String, f64)>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct InferenceResult {
     pub input_file: String,
     pub output_dir: String,
+    /// Seconds of processing.
     pub duration: Option<f64>,
     pub success: bool,
     pub error_message: Option<String>,
+    /// The exception a failed run's traceback ended with, e.g.
+    /// "RuntimeError: CUDA out of memory".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_summary: Option<String>,
+    #[serde(default)]
+    pub input_notes: Vec<InputNote>,
+    #[serde(default)]
+    pub loudness: Vec<TrackLoudness>,
+    /// How well the stems sum back to the input, when that was checked.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reconstruction: Vec<TrackReconstruction>,
+}
+
+impl InferenceResult {
+    /// What went wrong, in one line: the exception when there was a
+    /// traceback, otherwise the first line of the error message.
+    pub fn error_line(&self) -> Option<&str> {
+        self.error_summary.as_deref().or_else(|| self.error_message.as_deref()?.lines().next())
+    }
+}
+
+/// Version of the `TrackSidecar` format; bumped on changes that older
+/// readers would misread.
+pub const SIDECAR_SCHEMA_VERSION: u32 = 1;
+
+/// Appended to the track name for its sidecar, next to its output folder.
+pub const SIDECAR_SUFFIX: &str = ".msst.json";
+
+/// Machine-readable facts about one separated track, written as
+/// `<track>.msst.json` in the store_dir for downstream tools.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrackSidecar {
+    pub schema_version: u32,
+    pub track: String,
+    /// This track's share of the run: its input, its output folder, the
+    /// adjustments made to its input and the loudness gains on its stems.
+    /// `duration` is an even share of the run's time, as inference.py
+    /// doesn't time tracks separately.
+    pub result: InferenceResult,
+    pub model: SidecarModel,
+    /// Relative to the store_dir.
+    pub stems: Vec<SidecarStem>,
+    /// Of the stems as written.
+    pub sample_rate: Option<u32>,
+    pub post_processing: PostProcessing,
+    /// The blend of the last mixdown written from the stems.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mix: Option<MixSettings>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SidecarModel {
+    pub model_type: ModelType,
+    pub config_path: String,
+    pub checkpoint: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SidecarStem {
+    pub name: String,
+    pub path: String,
+}
+
+/// The options of the run that changed the stems.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PostProcessing {
+    pub normalize: Option<bool>,
+    pub use_tta: bool,
+    pub loudness_match: LoudnessMatch,
+    /// "wav" or "flac".
+    pub format: String,
+}
+
+impl TrackSidecar {
+    pub fn path(store_dir: &Path, track: &str) -> PathBuf {
+        store_dir.join(format!("{}{}", track, SIDECAR_SUFFIX))
+    }
+
+    pub fn save(&self, store_dir: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)
+            .context("Failed to serialize track sidecar")?;
+        fs::write(Self::path(store_dir, &self.track), content)
+            .context("Failed to write track sidecar")
+    }
+
+    /// Refuses sidecars from a newer schema rather than misreading them.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .context("Failed to read track sidecar")?;
+        let sidecar: TrackSidecar = serde_json::from_str(&content)
+            .context("Failed to parse track sidecar")?;
+        if sidecar.schema_version > SIDECAR_SCHEMA_VERSION {
+            bail!(
+                "{} has schema version {}, this build reads up to {}",
+                path.display(),
+                sidecar.schema_version,
+                SIDECAR_SCHEMA_VERSION
+            );
+        }
+        Ok(sidecar)
+    }
+}
+
+/// Per-input record of anything the TUI did to, or noticed about, a file
+/// before inference.py saw it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InputNote {
+    pub input: String,
+    pub adjustment: InputAdjustment,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum InputAdjustment {
+    Resampled { from: u32, to: u32 },
+    SampleRateMismatch { rate: u32, expected: u32 },
+    Upmixed { from: u16, to: u16 },
+    Downmixed { from: u16, to: u16 },
+    Excluded { reason: String },
+    /// Not separated; the stems of the identical input `of` were reused.
+    Reused { of: String },
+}
+
+impl InputAdjustment {
+    pub fn describe(&self) -> String {
+        match self {
+            InputAdjustment::Resampled { from, to } => format!("resampled {} -> {} Hz", from, to),
+            InputAdjustment::SampleRateMismatch { rate, expected } => {
+                format!("{} Hz, model expects {} Hz", rate, expected)
+            }
+            InputAdjustment::Upmixed { from, to } => format!("upmixed {} -> {} channels", from, to),
+            InputAdjustment::Downmixed { from, to } => format!("downmixed {} -> {} channels", from, to),
+            InputAdjustment::Excluded { reason } => format!("excluded: {}", reason),
+            InputAdjustment::Reused { of } => format!("same content as {}, outputs reused", of),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loudness::StemGain;
+
+    const EXAMPLE: &str = r#"{
+  "schema_version": 1,
+  "track": "song",
+  "result": {
+    "input_file": "in/song.wav",
+    "output_dir": "out/song",
+    "duration": 12.5,
+    "success": true,
+    "error_message": null,
+    "input_notes": [
+      {
+        "input": "in/song.wav",
+        "adjustment": {
+          "Resampled": {
+            "from": 48000,
+            "to": 44100
+          }
+        }
+      }
+    ],
+    "loudness": [
+      {
+        "track": "song",
+        "input_lufs": -14.0,
+        "stems": [
+          {
+            "name": "vocals",
+            "lufs": -18.0,
+            "gain_db": 4.0,
+            "applied": true
+          }
+        ]
+      }
+    ]
+  },
+  "model": {
+    "model_type": "BsRoformer",
+    "config_path": "configs/viperx/model_bs_roformer_ep_317_sdr_12.9755.yaml",
+    "checkpoint": "pretrained/model_bs_roformer_ep_317_sdr_12.9755.ckpt"
+  },
+  "stems": [
+    {
+      "name": "vocals",
+      "path": "song/vocals.wav"
+    }
+  ],
+  "sample_rate": 44100,
+  "post_processing": {
+    "normalize": null,
+    "use_tta": false,
+    "loudness_match": "PerStem",
+    "format": "wav"
+  }
+}"#;
+
+    fn example() -> TrackSidecar {
+        TrackSidecar {
+            schema_version: SIDECAR_SCHEMA_VERSION,
+            track: "song".into(),
+            result: InferenceResult {
+                input_file: "in/song.wav".into(),
+                output_dir: "out/song".into(),
+                duration: Some(12.5),
+                success: true,
+                error_message: None,
+                error_summary: None,
+                input_notes: vec![InputNote {
+                    input: "in/song.wav".into(),
+                    adjustment: InputAdjustment::Resampled { from: 48_000, to: 44_100 },
+                }],
+                loudness: vec![TrackLoudness {
+                    track: "song".into(),
+                    input_lufs: Some(-14.0),
+                    stems: vec![StemGain { name: "vocals".into(), lufs: Some(-18.0), gain_db: 4.0, applied: true }],
+                }],
+                reconstruction: vec![],
+            },
+            model: SidecarModel {
+                model_type: ModelType::BsRoformer,
+                config_path: "configs/viperx/model_bs_roformer_ep_317_sdr_12.9755.yaml".into(),
+                checkpoint: "pretrained/model_bs_roformer_ep_317_sdr_12.9755.ckpt".into(),
+            },
+            stems: vec![SidecarStem { name: "vocals".into(), path: "song/vocals.wav".into() }],
+            sample_rate: Some(44_100),
+            post_processing: PostProcessing {
+                normalize: None,
+                use_tta: false,
+                loudness_match: LoudnessMatch::PerStem,
+                format: "wav".into(),
+            },
+            mix: None,
+        }
+    }
+
+    #[test]
+    fn models_are_filtered_by_name_key_or_description() {
+        assert_eq!(ModelType::filter(""), ModelType::all_models());
+        assert_eq!(ModelType::filter("  "), ModelType::all_models());
+        assert_eq!(ModelType::filter("APOLLO"), [ModelType::Apollo]);
+        let scnets = ModelType::filter("scnet");
+        assert_eq!(scnets, [ModelType::ScNet, ModelType::ScNetTran, ModelType::ScNetMasked]);
+        assert_eq!(ModelType::filter("attention"), [ModelType::BsRoformer, ModelType::BandIt, ModelType::BandItV2]);
+        assert!(ModelType::filter("no such model").is_empty());
+    }
+
+    #[test]
+    fn sidecar_matches_the_documented_example() {
+        assert_eq!(serde_json::to_string_pretty(&example()).unwrap(), EXAMPLE);
+        assert_eq!(serde_json::from_str::<TrackSidecar>(EXAMPLE).unwrap(), example());
+    }
+
+    #[test]
+    fn sidecar_round_trips_and_refuses_newer_schemas() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut sidecar = example();
+        sidecar.save(dir.path()).unwrap();
+        let path = dir.path().join("song.msst.json");
+        assert_eq!(TrackSidecar::load(&path).unwrap(), sidecar);
+
+        sidecar.schema_version = SIDECAR_SCHEMA_VERSION + 1;
+        sidecar.save(dir.path()).unwrap();
+        assert!(TrackSidecar::load(&path).unwrap_err().to_string().contains("schema version 2"));
+    }
+
+    #[test]
+    fn relative_paths_resolve_under_the_root_and_absolute_ones_stay() {
+        let mut config = InferenceConfig::new(ModelType::ScNet);
+        config.config_path = "configs/config_scnet.yaml".to_string();
+        config.input_folder = "/data/songs".to_string();
+        let rooted = config.in_root(Path::new("/srv/msst"));
+        assert_eq!(rooted.config_path, "/srv/msst/configs/config_scnet.yaml");
+        assert_eq!(rooted.input_folder, "/data/songs");
+        assert_eq!(rooted.store_dir, "", "unset paths stay unset");
+        assert_eq!(rooted.in_root(Path::new("/elsewhere")).config_path, rooted.config_path);
+    }
 }
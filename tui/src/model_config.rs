@@ -0,0 +1,322 @@
+use anyhow::{bail, Context, Result};
+use serde_yaml::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The top-level sections of a model config shown for editing, in order.
+pub const SECTIONS: [&str; 3] = ["training", "audio", "inference"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NodeKind {
+    /// A mapping; Enter folds and unfolds it.
+    Section,
+    /// A single value or a list of them, edited as text.
+    Leaf,
+    /// Anything else, e.g. a list of mappings; shown but not edited here.
+    Fixed,
+}
+
+/// A line of the tree, with the keys leading to it from the root.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeRow {
+    pub keys: Vec<Value>,
+    pub kind: NodeKind,
+}
+
+impl TreeRow {
+    /// E.g. "training.lr".
+    pub fn dotted(&self) -> String {
+        self.keys.iter().map(key_text).collect::<Vec<_>>().join(".")
+    }
+}
+
+/// A model config (the YAML passed as `--config_path`) being edited. The
+/// whole file is kept, sections not shown included, and written back as
+/// it was apart from the edited values; comments are not kept.
+#[derive(Debug, Clone)]
+pub struct ModelConfigEditor {
+    path: PathBuf,
+    root: Value,
+    folded: HashSet<String>,
+    changed: bool,
+}
+
+impl ModelConfigEditor {
+    pub fn open(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read model config {}", path.display()))?;
+        let root: Value = serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse model config {}", path.display()))?;
+        if !root.is_mapping() {
+            bail!("{} is not a mapping of sections", path.display());
+        }
+        Ok(ModelConfigEditor { path: path.to_path_buf(), root, folded: HashSet::new(), changed: false })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn root(&self) -> &Value {
+        &self.root
+    }
+
+    /// Edited since it was opened or saved.
+    pub fn is_changed(&self) -> bool {
+        self.changed
+    }
+
+    /// The visible lines of the tree, folded sections without their
+    /// children.
+    pub fn rows(&self) -> Vec<TreeRow> {
+        let mut rows = vec![];
+        for section in SECTIONS {
+            let key = Value::from(section);
+            if let Some(value) = self.root.get(&key) {
+                self.push_rows(&mut rows, vec![key], value);
+            }
+        }
+        rows
+    }
+
+    fn push_rows(&self, rows: &mut Vec<TreeRow>, keys: Vec<Value>, value: &Value) {
+        let kind = kind(value);
+        let row = TreeRow { keys, kind };
+        let dotted = row.dotted();
+        rows.push(row.clone());
+        if let (NodeKind::Section, Value::Mapping(mapping)) = (kind, value)
+            && !self.folded.contains(&dotted)
+        {
+            for (key, child) in mapping {
+                let mut keys = row.keys.clone();
+                keys.push(key.clone());
+                self.push_rows(rows, keys, child);
+            }
+        }
+    }
+
+    /// The row as shown, indented by depth, e.g. "  lr: 5.0e-05".
+    pub fn line(&self, row: &TreeRow) -> String {
+        let indent = "  ".repeat(row.keys.len() - 1);
+        let key = row.keys.last().map(key_text).unwrap_or_default();
+        match row.kind {
+            NodeKind::Section if self.folded.contains(&row.dotted()) => format!("{}+ {}: ...", indent, key),
+            NodeKind::Section => format!("{}- {}:", indent, key),
+            NodeKind::Leaf => format!("{}{}: {}", indent, key, self.edit_text(row)),
+            NodeKind::Fixed => format!("{}{}: (not editable here)", indent, key),
+        }
+    }
+
+    pub fn value(&self, row: &TreeRow) -> Option<&Value> {
+        row.keys.iter().try_fold(&self.root, |node, key| node.get(key))
+    }
+
+    /// The leaf's value as text; a list as comma-separated values.
+    pub fn edit_text(&self, row: &TreeRow) -> String {
+        match self.value(row) {
+            Some(Value::Sequence(items)) => items.iter().map(scalar_text).collect::<Vec<_>>().join(", "),
+            Some(value) => scalar_text(value),
+            None => String::new(),
+        }
+    }
+
+    pub fn toggle_folded(&mut self, row: &TreeRow) {
+        let dotted = row.dotted();
+        if !self.folded.remove(&dotted) {
+            self.folded.insert(dotted);
+        }
+    }
+
+    /// Sets the leaf from `text`, keeping its type: integers stay integers,
+    /// floats floats, and list items take the type of the items there.
+    pub fn set(&mut self, row: &TreeRow, text: &str) -> Result<()> {
+        let current = self.value(row).with_context(|| format!("{} is gone", row.dotted()))?;
+        let value = typed(current, text).with_context(|| format!("Not set: {}", row.dotted()))?;
+        let node = row.keys.iter()
+            .try_fold(&mut self.root, |node, key| node.get_mut(key))
+            .with_context(|| format!("{} is gone", row.dotted()))?;
+        if *node != value {
+            *node = value;
+            self.changed = true;
+        }
+        Ok(())
+    }
+
+    /// Flips a boolean leaf; false if the leaf isn't one.
+    pub fn toggle(&mut self, row: &TreeRow) -> bool {
+        let node = row.keys.iter().try_fold(&mut self.root, |node, key| node.get_mut(key));
+        match node {
+            Some(Value::Bool(flag)) => {
+                *flag = !*flag;
+                self.changed = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Writes the config back, first copying the file as it was to
+    /// `<name>.<date>-<time>.bak` next to it. The backup's path.
+    pub fn save(&mut self) -> Result<PathBuf> {
+        let name = self.path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let backup = self.path.with_file_name(format!("{}.{}.bak", name, chrono::Local::now().format("%Y%m%d-%H%M%S")));
+        fs::copy(&self.path, &backup)
+            .with_context(|| format!("Failed to back up {} to {}", self.path.display(), backup.display()))?;
+        let content = serde_yaml::to_string(&self.root).context("Failed to serialize model config")?;
+        fs::write(&self.path, content)
+            .with_context(|| format!("Failed to write model config {}", self.path.display()))?;
+        self.changed = false;
+        Ok(backup)
+    }
+}
+
+fn kind(value: &Value) -> NodeKind {
+    match value {
+        Value::Mapping(_) => NodeKind::Section,
+        Value::Sequence(items) if items.iter().all(is_scalar) => NodeKind::Leaf,
+        Value::Sequence(_) | Value::Tagged(_) => NodeKind::Fixed,
+        _ => NodeKind::Leaf,
+    }
+}
+
+fn is_scalar(value: &Value) -> bool {
+    matches!(value, Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_))
+}
+
+fn key_text(key: &Value) -> String {
+    match key {
+        Value::String(text) => text.clone(),
+        key => scalar_text(key),
+    }
+}
+
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(flag) => flag.to_string(),
+        Value::Number(number) => number.to_string(),
+        Value::String(text) => text.clone(),
+        _ => "...".to_string(),
+    }
+}
+
+/// `text` as a value of the same type as `current`.
+fn typed(current: &Value, text: &str) -> Result<Value> {
+    let text = text.trim();
+    Ok(match current {
+        Value::Number(number) if number.is_i64() || number.is_u64() => {
+            Value::from(text.parse::<i64>().with_context(|| format!("{} is not a whole number", text))?)
+        }
+        Value::Number(_) => {
+            let float = text.parse::<f64>().with_context(|| format!("{} is not a number", text))?;
+            if !float.is_finite() {
+                bail!("{} is not a finite number", text);
+            }
+            Value::from(float)
+        }
+        Value::Bool(_) => match text.to_lowercase().as_str() {
+            "true" | "yes" | "on" => Value::Bool(true),
+            "false" | "no" | "off" => Value::Bool(false),
+            _ => bail!("{} is not true or false", text),
+        },
+        Value::String(_) => Value::String(text.to_string()),
+        Value::Null => serde_yaml::from_str(text).with_context(|| format!("{} is not a YAML value", text))?,
+        Value::Sequence(items) if items.iter().all(is_scalar) => {
+            let template = items.first().cloned().unwrap_or(Value::Null);
+            let items = text.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| typed(&template, item))
+                .collect::<Result<Vec<_>>>()?;
+            Value::Sequence(items)
+        }
+        _ => bail!("Only single values and lists of them can be edited"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "\
+audio:
+  chunk_size: 485100
+  sample_rate: 44100
+model:
+  dim: 384
+  freqs_per_bands: [2, 2, 4]
+training:
+  batch_size: 2
+  lr: 5.0e-05
+  instruments:
+  - vocals
+  - other
+  use_amp: true
+  augmentations:
+    enable: false
+  loss_multistft:
+  - fft_size: 1024
+inference:
+  num_overlap: 4
+";
+
+    fn row(editor: &ModelConfigEditor, dotted: &str) -> TreeRow {
+        editor.rows().into_iter().find(|row| row.dotted() == dotted).unwrap()
+    }
+
+    #[test]
+    fn edits_keep_their_types_and_the_rest_of_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config_scnet.yaml");
+        fs::write(&path, CONFIG).unwrap();
+        let original: Value = serde_yaml::from_str(CONFIG).unwrap();
+
+        let mut editor = ModelConfigEditor::open(&path).unwrap();
+        let batch_size = row(&editor, "training.batch_size");
+        assert!(editor.set(&batch_size, "2.5").is_err(), "an integer stays an integer");
+        editor.set(&batch_size, "8").unwrap();
+        editor.set(&row(&editor, "training.lr"), "3e-4").unwrap();
+        let instruments = row(&editor, "training.instruments");
+        assert_eq!(editor.edit_text(&instruments), "vocals, other");
+        editor.set(&instruments, "vocals, drums, bass").unwrap();
+        assert!(editor.toggle(&row(&editor, "training.use_amp")));
+        assert_eq!(row(&editor, "training.loss_multistft").kind, NodeKind::Fixed);
+        assert!(editor.is_changed());
+
+        let backup = editor.save().unwrap();
+        assert_eq!(fs::read_to_string(&backup).unwrap(), CONFIG);
+        let saved: Value = serde_yaml::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved["training"]["batch_size"], Value::from(8));
+        assert_eq!(saved["training"]["lr"], Value::from(3e-4));
+        assert_eq!(saved["training"]["instruments"], serde_yaml::from_str::<Value>("[vocals, drums, bass]").unwrap());
+        assert_eq!(saved["training"]["use_amp"], Value::Bool(false));
+
+        let mut untouched = saved.clone();
+        for (key, value) in [("batch_size", 2.into()), ("lr", 5.0e-05.into()), ("use_amp", true.into())] {
+            untouched["training"][key] = value;
+        }
+        untouched["training"]["instruments"] = original["training"]["instruments"].clone();
+        assert_eq!(untouched, original, "nothing else changed, the model section included");
+    }
+
+    #[test]
+    fn sections_fold_and_unedited_configs_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+        fs::write(&path, CONFIG).unwrap();
+        let mut editor = ModelConfigEditor::open(&path).unwrap();
+        let sections: Vec<String> = editor.rows().iter().filter(|row| row.keys.len() == 1).map(TreeRow::dotted).collect();
+        assert_eq!(sections, ["training", "audio", "inference"]);
+
+        let training = row(&editor, "training");
+        editor.toggle_folded(&training);
+        assert_eq!(editor.line(&training), "+ training: ...");
+        assert!(editor.rows().iter().all(|row| row.keys.len() == 1 || row.keys[0] != "training"));
+        assert_eq!(editor.line(&row(&editor, "audio.chunk_size")), "  chunk_size: 485100");
+
+        editor.save().unwrap();
+        let saved: Value = serde_yaml::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved, serde_yaml::from_str::<Value>(CONFIG).unwrap());
+    }
+}
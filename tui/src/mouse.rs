@@ -0,0 +1,111 @@
+use ratatui::layout::Rect;
+use std::time::{Duration, Instant};
+
+/// Two clicks on the same cell at most this far apart are a double-click.
+pub const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ListArea {
+    /// The rows inside the list's border.
+    rows: Rect,
+    /// Index of the item on the first row.
+    first: usize,
+    len: usize,
+}
+
+/// Where the current frame drew the lists that follow `selected_index`,
+/// for mapping a click to an item. Cleared before every frame; tables and
+/// log panes keep their own areas.
+#[derive(Debug, Clone, Default)]
+pub struct LayoutMap {
+    lists: Vec<ListArea>,
+}
+
+impl LayoutMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.lists.clear();
+    }
+
+    /// A bordered list drawn in `area`, its first row showing item `first`.
+    pub fn list(&mut self, area: Rect, first: usize, len: usize) {
+        let rows = Rect {
+            x: area.x + 1,
+            y: area.y + 1,
+            width: area.width.saturating_sub(2),
+            height: area.height.saturating_sub(2),
+        };
+        self.lists.push(ListArea { rows, first, len });
+    }
+
+    /// The item drawn at `column`, `row`.
+    pub fn item_at(&self, column: u16, row: u16) -> Option<usize> {
+        let list = self.lists.iter().find(|list| contains(list.rows, column, row))?;
+        let line = (row - list.rows.y) as usize;
+        (line < list.len).then_some(list.first + line)
+    }
+
+    pub fn over_list(&self, column: u16, row: u16) -> bool {
+        self.lists.iter().any(|list| contains(list.rows, column, row))
+    }
+}
+
+pub fn contains(area: Rect, column: u16, row: u16) -> bool {
+    column >= area.x && column < area.x + area.width && row >= area.y && row < area.y + area.height
+}
+
+/// Tells a double-click from two single ones.
+#[derive(Debug, Clone, Default)]
+pub struct ClickTracker {
+    last: Option<(Instant, u16, u16)>,
+}
+
+impl ClickTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A click at `column`, `row`; true if it completes a double-click, after
+    /// which the next click starts over.
+    pub fn click(&mut self, column: u16, row: u16, at: Instant) -> bool {
+        let double = self.last.is_some_and(|(last, last_column, last_row)| {
+            (last_column, last_row) == (column, row) && at.duration_since(last) <= DOUBLE_CLICK
+        });
+        self.last = if double { None } else { Some((at, column, row)) };
+        double
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clicks_map_to_the_items_of_the_list_under_them() {
+        let mut layout = LayoutMap::new();
+        layout.list(Rect { x: 0, y: 2, width: 20, height: 6 }, 0, 3);
+        layout.list(Rect { x: 0, y: 8, width: 20, height: 5 }, 3, 10);
+        assert_eq!(layout.item_at(4, 3), Some(0));
+        assert_eq!(layout.item_at(4, 5), Some(2));
+        assert_eq!(layout.item_at(4, 6), None, "below the last item");
+        assert_eq!(layout.item_at(0, 3), None, "on the border");
+        assert_eq!(layout.item_at(4, 10), Some(4));
+        assert!(layout.over_list(4, 6));
+        layout.clear();
+        assert!(!layout.over_list(4, 3));
+    }
+
+    #[test]
+    fn a_second_click_on_the_same_cell_soon_after_is_a_double_click() {
+        let start = Instant::now();
+        let mut clicks = ClickTracker::new();
+        assert!(!clicks.click(3, 4, start));
+        assert!(clicks.click(3, 4, start + Duration::from_millis(200)));
+        assert!(!clicks.click(3, 4, start + Duration::from_millis(300)), "a third click starts over");
+        assert!(!clicks.click(3, 5, start + Duration::from_millis(400)));
+        assert!(!clicks.click(3, 5, start + Duration::from_secs(2)));
+    }
+}
@@ -0,0 +1,93 @@
+use anyhow::{bail, Context, Result};
+use std::process::Stdio;
+use std::time::Duration;
+
+use crate::metrics::duration_text;
+use crate::model::CommandSpec;
+use crate::shell_words;
+
+/// How a long job ended, for the banner and `notify_command`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    /// "finished", "failed" or "stopped".
+    pub status: String,
+    /// What ran, e.g. "Training" or "Inference batch".
+    pub job: String,
+    pub model: String,
+    /// The line its screen shows, e.g. "Training finished (exit 0)".
+    pub summary: String,
+    pub duration: Option<Duration>,
+}
+
+impl Notification {
+    pub fn failed(&self) -> bool {
+        self.status == "failed"
+    }
+
+    /// E.g. "Training failed after 2h 03m: Training crashed (exit 1)".
+    pub fn banner(&self) -> String {
+        let after = self.duration.map(|duration| format!(" after {}", duration_text(duration.as_secs_f64()))).unwrap_or_default();
+        format!("{} {}{}: {}", self.job, self.status, after, self.summary)
+    }
+
+    /// `template` split into words as a shell would, then `{status}`,
+    /// `{model}`, `{summary}` and `{duration}` filled in word by word. No
+    /// shell runs it, so quotes or `;` in a summary stay text.
+    pub fn command(&self, template: &str) -> Result<CommandSpec> {
+        let duration = self.duration.map(|duration| duration_text(duration.as_secs_f64())).unwrap_or_default();
+        let mut words = shell_words::split(template)?.into_iter().map(|word| {
+            word.replace("{status}", &self.status)
+                .replace("{model}", &self.model)
+                .replace("{summary}", &self.summary)
+                .replace("{duration}", &duration)
+        });
+        let Some(program) = words.next() else {
+            bail!("notify_command is empty");
+        };
+        let mut command = CommandSpec::new(&program);
+        for word in words {
+            command.arg(word);
+        }
+        Ok(command)
+    }
+}
+
+/// Runs `template` for `notification` in the background and returns the
+/// command line; only a command that can't be started is an error. Its
+/// output is discarded.
+pub fn send(template: &str, notification: &Notification) -> Result<String> {
+    let spec = notification.command(template)?;
+    let mut child = spec.std_command()
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run notify_command {}", spec.display()))?;
+    std::thread::spawn(move || child.wait());
+    Ok(spec.display())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_fill_whole_arguments_without_a_shell() {
+        let notification = Notification {
+            status: "failed".to_string(),
+            job: "Training".to_string(),
+            model: "scnet".to_string(),
+            summary: "crashed\"; rm -rf ~; echo \"$(id)".to_string(),
+            duration: Some(Duration::from_secs(7380)),
+        };
+        let command = notification.command(r#"notify-send "MSST {model}" '{status}: {summary}' --hint=int:t:{duration}"#).unwrap();
+        assert_eq!(command.program, "notify-send");
+        assert_eq!(command.args, [
+            "MSST scnet",
+            "failed: crashed\"; rm -rf ~; echo \"$(id)",
+            "--hint=int:t:2h 03m",
+        ]);
+        assert_eq!(notification.banner(), "Training failed after 2h 03m: crashed\"; rm -rf ~; echo \"$(id)");
+        assert!(notification.command("  ").is_err());
+    }
+}
@@ -0,0 +1,218 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::checkpoints::file_size;
+use crate::manifest::{is_audio_file, ResultsManifest};
+
+/// What opens a stem when `player_command` isn't set.
+pub const DEFAULT_PLAYER: &str = "xdg-open";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputStem {
+    /// As the model named it, e.g. "vocals".
+    pub name: String,
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputTrack {
+    pub name: String,
+    pub stems: Vec<OutputStem>,
+    /// Instruments of the model config without a stem here.
+    pub missing: Vec<String>,
+}
+
+/// A line of the outputs view: a track, or one of its stems.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputLine {
+    pub text: String,
+    /// The file Enter opens; None on track lines.
+    pub stem: Option<PathBuf>,
+    pub flagged: bool,
+}
+
+/// What an inference run wrote into a store_dir, by source track. Taken
+/// from the results manifest when there is one, which knows about renamed
+/// stems; otherwise from inference.py's `{track}/{stem}` folders, or
+/// `{track}_{stem}` files directly in the store_dir.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputListing {
+    pub store_dir: PathBuf,
+    pub tracks: Vec<OutputTrack>,
+}
+
+impl OutputListing {
+    /// Lists `store_dir`, flagging tracks without a stem for each of
+    /// `expected`, the model's instruments.
+    pub fn scan(store_dir: &Path, expected: &[String]) -> Result<Self> {
+        let mut tracks = match ResultsManifest::load(store_dir)? {
+            Some(manifest) => manifest.tracks
+                .into_iter()
+                .map(|track| OutputTrack {
+                    name: track.name,
+                    stems: track.stems
+                        .into_iter()
+                        .map(|stem| stem_at(stem.name, store_dir.join(&stem.path)))
+                        .filter(|stem| stem.path.is_file())
+                        .collect(),
+                    missing: vec![],
+                })
+                .collect(),
+            None => scan_folders(store_dir)?,
+        };
+        for track in &mut tracks {
+            track.stems.sort_by(|a, b| a.name.cmp(&b.name));
+            track.missing = expected.iter()
+                .filter(|instrument| !track.stems.iter().any(|stem| stem.name.eq_ignore_ascii_case(instrument)))
+                .cloned()
+                .collect();
+        }
+        tracks.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(OutputListing { store_dir: store_dir.to_path_buf(), tracks })
+    }
+
+    /// E.g. "12 tracks, 48 stems, 2 with stems missing".
+    pub fn summary(&self) -> String {
+        let stems: usize = self.tracks.iter().map(|track| track.stems.len()).sum();
+        let flagged = self.tracks.iter().filter(|track| !track.missing.is_empty()).count();
+        let mut summary = format!("{} tracks, {} stems", self.tracks.len(), stems);
+        if flagged > 0 {
+            summary.push_str(&format!(", {} with stems missing", flagged));
+        }
+        summary
+    }
+
+    /// Each track, then its stems indented under it.
+    pub fn lines(&self) -> Vec<OutputLine> {
+        let mut lines = vec![];
+        for track in &self.tracks {
+            let text = if track.missing.is_empty() {
+                format!("{} ({} stems)", track.name, track.stems.len())
+            } else {
+                format!("{} ({} stems, missing {})", track.name, track.stems.len(), track.missing.join(", "))
+            };
+            lines.push(OutputLine { text, stem: None, flagged: !track.missing.is_empty() });
+            for stem in &track.stems {
+                let file = stem.path.file_name().unwrap_or_default().to_string_lossy();
+                lines.push(OutputLine {
+                    text: format!("    {:<14} {:>8}  {}", stem.name, file_size(stem.size), file),
+                    stem: Some(stem.path.clone()),
+                    flagged: false,
+                });
+            }
+        }
+        lines
+    }
+}
+
+fn stem_at(name: String, path: PathBuf) -> OutputStem {
+    let size = path.metadata().map(|meta| meta.len()).unwrap_or(0);
+    OutputStem { name, path, size }
+}
+
+fn scan_folders(store_dir: &Path) -> Result<Vec<OutputTrack>> {
+    let mut tracks: Vec<OutputTrack> = vec![];
+    for entry in fs::read_dir(store_dir).with_context(|| format!("Failed to read output folder {}", store_dir.display()))? {
+        let path = entry.context("Failed to read directory entry")?.path();
+        if path.is_dir() {
+            let mut stems = vec![];
+            for entry in fs::read_dir(&path).context("Failed to read track output directory")? {
+                let file = entry.context("Failed to read directory entry")?.path();
+                if is_audio_file(&file) {
+                    let name = file.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+                    stems.push(stem_at(name, file));
+                }
+            }
+            if !stems.is_empty() {
+                let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+                tracks.push(OutputTrack { name, stems, missing: vec![] });
+            }
+        } else if is_audio_file(&path) {
+            let file_stem = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+            let (track, stem) = file_stem.rsplit_once('_').unwrap_or((&file_stem, &file_stem));
+            let stem = stem_at(stem.to_string(), path.clone());
+            match tracks.iter_mut().find(|existing| existing.name == track) {
+                Some(existing) => existing.stems.push(stem),
+                None => tracks.push(OutputTrack { name: track.to_string(), stems: vec![stem], missing: vec![] }),
+            }
+        }
+    }
+    Ok(tracks)
+}
+
+/// `command` with `path` as its last argument; the command is split on
+/// whitespace, e.g. "mpv --no-video". None for a blank command.
+pub fn player_command(command: &str, path: &Path) -> Option<Command> {
+    let mut words = command.split_whitespace();
+    let mut player = Command::new(words.next()?);
+    player.args(words).arg(path);
+    Some(player)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::{ManifestStem, ManifestTrack};
+
+    fn expected() -> Vec<String> {
+        ["vocals", "bass", "drums", "other"].map(String::from).to_vec()
+    }
+
+    #[test]
+    fn stems_are_grouped_by_track_and_missing_ones_flagged() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("song")).unwrap();
+        for stem in ["vocals", "bass", "drums", "other"] {
+            fs::write(dir.path().join("song").join(format!("{}.wav", stem)), [0u8; 2000]).unwrap();
+        }
+        fs::create_dir(dir.path().join("empty")).unwrap();
+        fs::write(dir.path().join("demo_vocals.flac"), b"").unwrap();
+        fs::write(dir.path().join("demo_drums.flac"), b"").unwrap();
+        fs::write(dir.path().join("notes.txt"), b"").unwrap();
+
+        let listing = OutputListing::scan(dir.path(), &expected()).unwrap();
+        let names: Vec<&str> = listing.tracks.iter().map(|track| track.name.as_str()).collect();
+        assert_eq!(names, ["demo", "song"]);
+        assert_eq!(listing.tracks[0].missing, ["bass", "other"]);
+        assert!(listing.tracks[1].missing.is_empty());
+        assert_eq!(listing.summary(), "2 tracks, 6 stems, 1 with stems missing");
+
+        let lines = listing.lines();
+        assert_eq!((lines[0].text.as_str(), lines[0].flagged), ("demo (2 stems, missing bass, other)", true));
+        assert_eq!(lines[4].text, "    bass               2 KB  bass.wav");
+        assert_eq!(lines[4].stem, Some(dir.path().join("song").join("bass.wav")));
+    }
+
+    #[test]
+    fn the_manifest_names_renamed_stems() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("song")).unwrap();
+        fs::write(dir.path().join("song").join("Song - Vocals.wav"), b"").unwrap();
+        let manifest = ResultsManifest {
+            tracks: vec![ManifestTrack {
+                name: "song".to_string(),
+                input_path: "in/song.wav".to_string(),
+                stems: vec![
+                    ManifestStem { name: "vocals".to_string(), path: "song/Song - Vocals.wav".to_string(), renamed_from: Some("song/vocals.wav".to_string()) },
+                    ManifestStem { name: "other".to_string(), path: "song/other.wav".to_string(), renamed_from: None },
+                ],
+                adjustments: vec![],
+                content_hash: None,
+            }],
+            ..Default::default()
+        };
+        manifest.save(dir.path()).unwrap();
+
+        let listing = OutputListing::scan(dir.path(), &["vocals".to_string(), "other".to_string()]).unwrap();
+        assert_eq!(listing.tracks[0].stems.len(), 1, "other.wav is gone");
+        assert_eq!(listing.tracks[0].missing, ["other"]);
+
+        let player = player_command("mpv  --no-video", Path::new("a b.wav")).unwrap();
+        let args: Vec<_> = player.get_args().collect();
+        assert_eq!((player.get_program(), args), ("mpv".as_ref(), vec!["--no-video".as_ref(), "a b.wav".as_ref()]));
+        assert!(player_command(" ", Path::new("a.wav")).is_none());
+    }
+}
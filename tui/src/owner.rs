@@ -0,0 +1,151 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use crate::run_dir::hostname;
+
+/// Names the TUI instance supervising a run, in the run directory.
+pub const OWNER_FILE: &str = "owner.json";
+
+/// The TUI instance that owns a run: the one that started it and will
+/// record how it ended. Everyone else, a dashboard included, only watches.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Owner {
+    pub pid: u32,
+    pub hostname: Option<String>,
+    pub since: DateTime<Utc>,
+}
+
+impl Owner {
+    pub fn current() -> Self {
+        Owner { pid: std::process::id(), hostname: hostname(), since: Utc::now() }
+    }
+
+    /// Whether the owning process is still running. An owner on another
+    /// host can't be checked and is taken to be alive.
+    pub fn is_alive(&self) -> bool {
+        if self.hostname != hostname() {
+            return true;
+        }
+        process_alive(self.pid)
+    }
+
+    /// E.g. "PID 4242 on gpu-box since 14:03".
+    pub fn describe(&self) -> String {
+        format!(
+            "PID {} on {} since {}",
+            self.pid,
+            self.hostname.as_deref().unwrap_or("this host"),
+            self.since.with_timezone(&Local).format("%H:%M")
+        )
+    }
+}
+
+/// The owner recorded for the run in `dir`, alive or not. `None` once the
+/// owner has let go.
+pub fn owner(dir: &Path) -> Option<Owner> {
+    serde_json::from_str(&fs::read_to_string(dir.join(OWNER_FILE)).ok()?).ok()
+}
+
+/// Ownership of a run directory, held until dropped. The owner file is
+/// locked with `flock` while held, so of two instances racing for a run
+/// exactly one gets it, and the kernel lets go of it if the owner dies.
+/// The file is emptied rather than removed on release: removing it would
+/// let a racing instance lock the old file while a third creates a new one.
+#[derive(Debug)]
+pub struct RunLock {
+    file: File,
+    owner: Owner,
+}
+
+impl RunLock {
+    pub fn acquire(dir: &Path) -> Result<Self> {
+        let path = dir.join(OWNER_FILE);
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        if !try_lock(&file).with_context(|| format!("Failed to lock {}", path.display()))? {
+            match owner(dir) {
+                Some(other) => bail!("{} is owned by {}", dir.display(), other.describe()),
+                None => bail!("{} is owned by another instance", dir.display()),
+            }
+        }
+        let owner = Owner::current();
+        let content = serde_json::to_string_pretty(&owner).context("Failed to serialize run owner")?;
+        file.set_len(0)
+            .and_then(|_| file.write_all(content.as_bytes()))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(RunLock { file, owner })
+    }
+
+    pub fn owner(&self) -> &Owner {
+        &self.owner
+    }
+}
+
+impl Drop for RunLock {
+    /// Gives the run up; closing the file releases the lock.
+    fn drop(&mut self) {
+        let _ = self.file.set_len(0);
+    }
+}
+
+#[cfg(unix)]
+fn try_lock(file: &File) -> std::io::Result<bool> {
+    use std::os::unix::io::AsRawFd;
+    // SAFETY: the descriptor is open for as long as `file` lives.
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        return Ok(true);
+    }
+    let error = std::io::Error::last_os_error();
+    if error.raw_os_error() == Some(libc::EWOULDBLOCK) { Ok(false) } else { Err(error) }
+}
+
+/// Without `flock`, whoever opens the file owns the run.
+#[cfg(not(unix))]
+fn try_lock(_file: &File) -> std::io::Result<bool> {
+    Ok(true)
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    // SAFETY: signal 0 only checks that the process exists.
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Where we can't tell, assume it is alive.
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn one_owner_at_a_time_and_dead_owners_are_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock = RunLock::acquire(dir.path()).unwrap();
+        assert_eq!(owner(dir.path()).as_ref(), Some(lock.owner()));
+        let error = RunLock::acquire(dir.path()).unwrap_err().to_string();
+        assert!(error.contains(&format!("is owned by PID {}", std::process::id())), "{}", error);
+        drop(lock);
+        assert_eq!(owner(dir.path()), None);
+
+        // An owner that died without letting go: its lock went with it.
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        let dead = Owner { pid, ..Owner::current() };
+        assert!(!dead.is_alive());
+        fs::write(dir.path().join(OWNER_FILE), serde_json::to_string(&dead).unwrap()).unwrap();
+        let lock = RunLock::acquire(dir.path()).unwrap();
+        assert_eq!(owner(dir.path()).map(|owner| owner.pid), Some(std::process::id()));
+        assert!(lock.owner().is_alive());
+    }
+}
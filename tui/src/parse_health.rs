@@ -0,0 +1,161 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Unmatched lines kept for the notice.
+const SAMPLE_LINES: usize = 5;
+
+/// When a run's output counts as unparseable: this long and this many
+/// lines without a single progress event.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ParseHealthSettings {
+    pub after_minutes: u64,
+    pub after_lines: usize,
+}
+
+impl Default for ParseHealthSettings {
+    fn default() -> Self {
+        ParseHealthSettings { after_minutes: 5, after_lines: 100 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParseHealth {
+    /// Not enough output yet to tell.
+    Waiting,
+    /// At least one line gave progress; stays so for the run.
+    Parsing,
+    /// Plenty of output, none of it progress: the log format has likely
+    /// changed under the parser rules.
+    Unrecognized,
+}
+
+/// Parser hit rate of one run, deciding when to stop trusting an empty
+/// progress display.
+#[derive(Debug, Clone)]
+pub struct ParseMonitor {
+    settings: ParseHealthSettings,
+    started_at: DateTime<Utc>,
+    lines: usize,
+    events: usize,
+    unmatched: VecDeque<String>,
+    state: ParseHealth,
+}
+
+impl ParseMonitor {
+    pub fn new(settings: ParseHealthSettings, started_at: DateTime<Utc>) -> Self {
+        ParseMonitor {
+            settings,
+            started_at,
+            lines: 0,
+            events: 0,
+            unmatched: VecDeque::new(),
+            state: ParseHealth::Waiting,
+        }
+    }
+
+    /// One line of output, received at `at`, and whether it gave progress.
+    pub fn observe(&mut self, line: &str, parsed: bool, at: DateTime<Utc>) {
+        self.lines += 1;
+        if parsed {
+            self.events += 1;
+            self.state = ParseHealth::Parsing;
+            return;
+        }
+        if !line.trim().is_empty() {
+            if self.unmatched.len() == SAMPLE_LINES {
+                self.unmatched.pop_front();
+            }
+            self.unmatched.push_back(line.to_string());
+        }
+        self.check(at);
+    }
+
+    /// Re-evaluates with the time now, for runs that went quiet after
+    /// printing enough lines.
+    pub fn check(&mut self, now: DateTime<Utc>) {
+        let waited = now - self.started_at >= TimeDelta::minutes(self.settings.after_minutes as i64);
+        if self.state == ParseHealth::Waiting && self.events == 0 && self.lines >= self.settings.after_lines && waited {
+            self.state = ParseHealth::Unrecognized;
+        }
+    }
+
+    pub fn state(&self) -> ParseHealth {
+        self.state
+    }
+
+    /// The latest lines no rule matched.
+    pub fn unmatched_sample(&self) -> impl Iterator<Item = &str> {
+        self.unmatched.iter().map(String::as_str)
+    }
+
+    /// What to show while the output is unrecognized: the notice, then the
+    /// sample, ready to paste into a bug report.
+    pub fn notice(&self) -> Option<Vec<String>> {
+        if self.state != ParseHealth::Unrecognized {
+            return None;
+        }
+        let mut lines = vec![
+            format!(
+                "Receiving output but can't parse progress ({} lines, no progress) - showing raw log; \
+                 consider updating the TUI or adding a parser rule to tui_parsers.yaml",
+                self.lines
+            ),
+            "Latest unmatched lines:".to_string(),
+        ];
+        lines.extend(self.unmatched_sample().map(|line| format!("  {}", line)));
+        Some(lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor() -> (ParseMonitor, DateTime<Utc>) {
+        let start = Utc::now();
+        (ParseMonitor::new(ParseHealthSettings { after_minutes: 2, after_lines: 3 }, start), start)
+    }
+
+    #[test]
+    fn unrecognized_only_after_both_thresholds() {
+        let (mut monitor, start) = monitor();
+        for i in 0..7 {
+            monitor.observe(&format!("step {} ok", i), false, start + TimeDelta::seconds(i));
+        }
+        // Enough lines, not enough time.
+        assert_eq!(monitor.state(), ParseHealth::Waiting);
+        monitor.check(start + TimeDelta::minutes(2));
+        assert_eq!(monitor.state(), ParseHealth::Unrecognized);
+        let notice = monitor.notice().unwrap();
+        assert!(notice[0].starts_with("Receiving output but can't parse progress (7 lines"));
+        assert_eq!(&notice[2..], ["  step 2 ok", "  step 3 ok", "  step 4 ok", "  step 5 ok", "  step 6 ok"]);
+
+        // Time alone isn't enough either.
+        let (mut quiet, start) = self::monitor();
+        quiet.observe("loading", false, start);
+        quiet.check(start + TimeDelta::minutes(10));
+        assert_eq!(quiet.state(), ParseHealth::Waiting);
+        assert_eq!(quiet.notice(), None);
+    }
+
+    #[test]
+    fn one_progress_event_settles_it() {
+        let (mut monitor, start) = monitor();
+        monitor.observe("Epoch 1", true, start);
+        for i in 0..10 {
+            monitor.observe("noise", false, start + TimeDelta::minutes(i));
+        }
+        assert_eq!(monitor.state(), ParseHealth::Parsing);
+
+        // Progress late still clears the notice.
+        let (mut late, start) = self::monitor();
+        for i in 0..5 {
+            late.observe("noise", false, start + TimeDelta::minutes(i));
+        }
+        assert_eq!(late.state(), ParseHealth::Unrecognized);
+        late.observe("Epoch 1", true, start + TimeDelta::minutes(6));
+        assert_eq!(late.state(), ParseHealth::Parsing);
+    }
+}
@@ -0,0 +1,330 @@
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::model::TrainingProgress;
+
+/// Looked up in the project root when no other rules file is configured.
+pub const DEFAULT_RULES_FILE: &str = "tui_parsers.yaml";
+
+const NUMBER: &str = r"[-+]?(?:\d+\.?\d*|\.\d+)(?:[eE][-+]?\d+)?";
+
+/// `TrainingProgress` fields a rule can fill in.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressField {
+    Epoch,
+    TrainLoss,
+    ValidLoss,
+    Sdr,
+    Sir,
+    Sar,
+    Isr,
+    GpuMemory,
+    GpuUtilization,
+}
+
+impl ProgressField {
+    pub fn key(&self) -> &'static str {
+        match self {
+            ProgressField::Epoch => "epoch",
+            ProgressField::TrainLoss => "train_loss",
+            ProgressField::ValidLoss => "valid_loss",
+            ProgressField::Sdr => "sdr",
+            ProgressField::Sir => "sir",
+            ProgressField::Sar => "sar",
+            ProgressField::Isr => "isr",
+            ProgressField::GpuMemory => "gpu_memory",
+            ProgressField::GpuUtilization => "gpu_utilization",
+        }
+    }
+
+    pub fn all() -> [ProgressField; 9] {
+        [
+            ProgressField::Epoch,
+            ProgressField::TrainLoss,
+            ProgressField::ValidLoss,
+            ProgressField::Sdr,
+            ProgressField::Sir,
+            ProgressField::Sar,
+            ProgressField::Isr,
+            ProgressField::GpuMemory,
+            ProgressField::GpuUtilization,
+        ]
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        Self::all().into_iter().find(|field| field.key() == key)
+    }
+
+    pub fn apply(&self, progress: &mut TrainingProgress, value: f64) {
+        match self {
+            ProgressField::Epoch => progress.epoch = value as usize,
+            ProgressField::TrainLoss => progress.train_loss = value,
+            ProgressField::ValidLoss => progress.valid_loss = Some(value),
+            ProgressField::Sdr => progress.sdr = Some(value),
+            ProgressField::Sir => progress.sir = Some(value),
+            ProgressField::Sar => progress.sar = Some(value),
+            ProgressField::Isr => progress.isr = Some(value),
+            ProgressField::GpuMemory => progress.gpu_memory = Some(value),
+            ProgressField::GpuUtilization => progress.gpu_utilization = Some(value),
+        }
+    }
+}
+
+/// A rule as written in the rules file. Capture groups named after a
+/// progress field fill that field; `fields` maps other group names.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RuleSpec {
+    pub name: String,
+    #[serde(default)]
+    pub pattern: String,
+    #[serde(default)]
+    pub fields: HashMap<String, ProgressField>,
+    /// Drops the built-in rule of the same name.
+    #[serde(default)]
+    pub disabled: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RulesFile {
+    pub rules: Vec<RuleSpec>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ParseRule {
+    pub name: String,
+    regex: Regex,
+    groups: Vec<(String, ProgressField)>,
+}
+
+impl ParseRule {
+    pub fn compile(spec: &RuleSpec) -> Result<Self> {
+        let regex = Regex::new(&spec.pattern)
+            .with_context(|| format!("rule '{}': invalid regex", spec.name))?;
+
+        let mut groups = vec![];
+        for name in regex.capture_names().flatten() {
+            let field = spec.fields.get(name).copied().or_else(|| ProgressField::from_key(name));
+            match field {
+                Some(field) => groups.push((name.to_string(), field)),
+                None => bail!("rule '{}': capture group '{}' is not a progress field", spec.name, name),
+            }
+        }
+        if groups.is_empty() {
+            bail!("rule '{}': no named capture groups", spec.name);
+        }
+
+        Ok(ParseRule { name: spec.name.clone(), regex, groups })
+    }
+
+    pub fn captures(&self, line: &str) -> Vec<(ProgressField, f64)> {
+        let Some(captures) = self.regex.captures(line) else {
+            return vec![];
+        };
+        self.groups.iter()
+            .filter_map(|(group, field)| {
+                let value = captures.name(group)?.as_str().parse().ok()?;
+                Some((*field, value))
+            })
+            .collect()
+    }
+}
+
+/// What one rule pulled out of one line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleMatch {
+    pub rule: String,
+    pub values: Vec<(ProgressField, f64)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineReport {
+    pub line_number: usize,
+    pub line: String,
+    pub matches: Vec<RuleMatch>,
+}
+
+/// Ordered rules turning python output lines into progress fields. Every
+/// rule is tried on every line; when two fill the same field the earlier
+/// one wins.
+#[derive(Debug, Clone)]
+pub struct ParserRules {
+    rules: Vec<ParseRule>,
+}
+
+impl Default for ParserRules {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl ParserRules {
+    pub fn builtin_specs() -> Vec<RuleSpec> {
+        let rule = |name: &str, pattern: String| RuleSpec {
+            name: name.to_string(),
+            pattern,
+            ..Default::default()
+        };
+        let metric = |name: &str| rule(name, format!(r"(?i)\b{}\s*:\s*(?P<{}>{})", name, name, NUMBER));
+        vec![
+            rule("epoch", r"epoch:\s*(?P<epoch>\d+)".to_string()),
+            rule("train_loss", format!(r"Training loss:\s*(?P<train_loss>{})", NUMBER)),
+            metric("sdr"),
+            metric("sir"),
+            metric("sar"),
+            metric("isr"),
+        ]
+    }
+
+    pub fn builtin() -> Self {
+        let rules = Self::builtin_specs()
+            .iter()
+            .map(|spec| ParseRule::compile(spec).expect("built-in parser rule"))
+            .collect();
+        ParserRules { rules }
+    }
+
+    /// Built-in rules with `specs` merged over them: a spec replaces (or,
+    /// with `disabled`, removes) the built-in rule of the same name, and
+    /// new rules go first so they win over the built-ins. Every invalid rule
+    /// is reported, not just the first.
+    pub fn with_overrides(specs: &[RuleSpec]) -> Result<Self> {
+        let mut merged = Self::builtin_specs();
+        let mut added = vec![];
+        for spec in specs {
+            match merged.iter().position(|builtin| builtin.name == spec.name) {
+                Some(index) => merged[index] = spec.clone(),
+                None => added.push(spec.clone()),
+            }
+        }
+        added.extend(merged);
+
+        let mut rules = vec![];
+        let mut errors = vec![];
+        for spec in added.iter().filter(|spec| !spec.disabled) {
+            match ParseRule::compile(spec) {
+                Ok(rule) => rules.push(rule),
+                Err(e) => errors.push(format!("  {:#}", e)),
+            }
+        }
+        if !errors.is_empty() {
+            bail!("Invalid parser rules:\n{}", errors.join("\n"));
+        }
+        Ok(ParserRules { rules })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read parser rules {}", path.display()))?;
+        let file: RulesFile = serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse parser rules {}", path.display()))?;
+        Self::with_overrides(&file.rules)
+            .with_context(|| format!("In {}", path.display()))
+    }
+
+    /// Loads `path`, or the default rules file if there is one, falling back
+    /// to the built-in rules. Relative paths are under `root`.
+    pub fn load_or_builtin(path: Option<&Path>, root: &Path) -> Result<Self> {
+        let default = root.join(DEFAULT_RULES_FILE);
+        match path {
+            Some(path) => Self::load(&root.join(path)),
+            None if default.is_file() => Self::load(&default),
+            None => Ok(Self::builtin()),
+        }
+    }
+
+    pub fn rules(&self) -> &[ParseRule] {
+        &self.rules
+    }
+
+    pub fn match_line(&self, line: &str) -> Vec<RuleMatch> {
+        self.rules.iter()
+            .filter_map(|rule| {
+                let values = rule.captures(line);
+                (!values.is_empty()).then(|| RuleMatch { rule: rule.name.clone(), values })
+            })
+            .collect()
+    }
+
+    /// Fields found in a line, earlier rules taking precedence.
+    pub fn parse_line(&self, line: &str) -> Vec<(ProgressField, f64)> {
+        let mut fields: Vec<(ProgressField, f64)> = vec![];
+        for (field, value) in self.match_line(line).into_iter().flat_map(|m| m.values) {
+            if !fields.iter().any(|(seen, _)| *seen == field) {
+                fields.push((field, value));
+            }
+        }
+        fields
+    }
+
+    /// Which rules matched what in a pasted log excerpt.
+    pub fn explain(&self, text: &str) -> Vec<LineReport> {
+        text.lines()
+            .enumerate()
+            .map(|(i, line)| LineReport {
+                line_number: i + 1,
+                line: line.to_string(),
+                matches: self.match_line(line),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(name: &str, pattern: &str) -> RuleSpec {
+        RuleSpec { name: name.into(), pattern: pattern.into(), ..Default::default() }
+    }
+
+    #[test]
+    fn builtin_rules_read_train_py_output() {
+        let rules = ParserRules::builtin();
+        assert_eq!(rules.parse_line("Train epoch: 12 Learning rate: 5e-05"), vec![(ProgressField::Epoch, 12.0)]);
+        assert_eq!(rules.parse_line("Training loss: 1.5e-02"), vec![(ProgressField::TrainLoss, 0.015)]);
+        assert_eq!(rules.parse_line("Instr vocals sdr: -3.2100 (Std: 1.0)"), vec![(ProgressField::Sdr, -3.21)]);
+        assert_eq!(rules.parse_line("Metric avg si_sdr     : 4.0"), vec![]);
+        assert_eq!(rules.parse_line("Store weights: results/model.ckpt"), vec![]);
+    }
+
+    #[test]
+    fn overrides_replace_disable_and_extend() {
+        let mut loss = spec("train_loss", r"loss=(?P<l>[\d.]+)");
+        loss.fields.insert("l".into(), ProgressField::TrainLoss);
+        let mut sdr = spec("sdr", "");
+        sdr.disabled = true;
+        let custom = spec("fork_epoch", r"\[ep (?P<epoch>\d+)\]");
+
+        let rules = ParserRules::with_overrides(&[loss, sdr, custom]).unwrap();
+        let names: Vec<&str> = rules.rules().iter().map(|rule| rule.name.as_str()).collect();
+        assert_eq!(names, ["fork_epoch", "epoch", "train_loss", "sir", "sar", "isr"]);
+
+        assert_eq!(
+            rules.parse_line("[ep 3] epoch: 4 loss=0.25 SDR: 9"),
+            vec![(ProgressField::Epoch, 3.0), (ProgressField::TrainLoss, 0.25)]
+        );
+
+        let report = rules.explain("[ep 3]\nnothing here");
+        assert_eq!(report[0].matches, vec![RuleMatch { rule: "fork_epoch".into(), values: vec![(ProgressField::Epoch, 3.0)] }]);
+        assert!(report[1].matches.is_empty());
+    }
+
+    #[test]
+    fn invalid_rules_are_reported_by_name() {
+        let error = ParserRules::with_overrides(&[
+            spec("broken", r"epoch (?P<epoch>\d+"),
+            spec("unknown_field", r"lr=(?P<lr>[\d.]+)"),
+            spec("fine", r"ep (?P<epoch>\d+)"),
+        ])
+        .unwrap_err();
+        let message = format!("{:#}", error);
+        assert!(message.contains("rule 'broken': invalid regex"), "{}", message);
+        assert!(message.contains("rule 'unknown_field': capture group 'lr'"), "{}", message);
+        assert!(!message.contains("'fine'"), "{}", message);
+    }
+}
@@ -0,0 +1,616 @@
+use crate::dashboard;
+use crate::gpu::GpuReading;
+use crate::training_chart;
+use crate::ui::{checkpoint_line, ensemble_file_lines, ensemble_lines, format_clock, inference_lines, inference_result_line, rename_summary, task_progress, trash_item_text, validation_lines, validation_summary, App, Screen, HOME_MENU};
+
+/// The current screen as labeled lines of text, for plain mode: no borders
+/// and no positions, lists as numbered lines and tables as "row 3 of 20: ...",
+/// each followed by a `Selected:` line for the one the arrow keys are on.
+/// Built from the same state as the drawn screens.
+pub fn render(app: &App) -> Vec<String> {
+    let mut lines = if app.help_visible {
+        let topic = app.help_topic();
+        let mut lines = vec![format!("Screen: Help for {}", topic.title)];
+        lines.extend(topic.lines());
+        lines
+    } else {
+        screen_lines(app)
+    };
+    if let Some(title) = app.prompt_title() {
+        lines.push(format!("Input: {}", title));
+        if let Some(input) = app.focused_input() {
+            lines.extend(input.text().lines().map(|line| format!("Text: {}", line)));
+        }
+    }
+    if let Some(browser) = &app.file_browser {
+        lines.extend(browser.plain_lines());
+    }
+    if let Some(question) = app.pending_confirmation() {
+        lines.push(format!("Confirm: {}", question));
+    }
+    if let Some((question, keys)) = app.leave_question() {
+        lines.push(format!("Confirm: {}", question));
+        lines.push(format!("Keys: {}", keys));
+    }
+    if let Some((question, keys)) = app.gpu_question() {
+        lines.push(format!("Confirm: {}", question));
+        lines.push(format!("Keys: {}", keys));
+    }
+    if let Some(command) = app.pending_launch() {
+        lines.push(format!("Command: {}", command));
+        lines.push("Keys: Enter run, c copy, Esc cancel".to_string());
+    }
+    if let Some(task) = app.tasks.last() {
+        let state = if task.is_cancelled() { ", cancelling" } else { "" };
+        lines.push(format!("Task: {} {}{} ({} running)", task.name, task_progress(task), state, app.tasks.len()));
+    }
+    if let Some(notification) = &app.banner {
+        lines.push(format!("Banner: {}", notification.banner()));
+    }
+    if let Some(notice) = &app.notice {
+        lines.push(format!("Notice: {}", notice));
+    }
+    if let Some((warning, _)) = &app.warning {
+        lines.push(format!("Warning: {}", warning));
+    }
+    if let Some(error) = &app.error {
+        lines.push(format!("Error: {}", error.title));
+        lines.extend(error.lines().into_iter().map(|line| format!("Detail: {}", line)));
+        lines.push(format!("Keys: {}", error.hint()));
+    }
+    lines
+}
+
+fn screen_lines(app: &App) -> Vec<String> {
+    let mut lines = vec![format!("Screen: {}", screen_name(app))];
+    if app.read_only {
+        lines.push("Mode: read-only dashboard".to_string());
+    }
+    match app.screen {
+        Screen::Home => {
+            lines.extend(app.quarantined.iter().map(|file| format!("Warning: {}", file.warning())));
+            lines.push(format!("Model: {}", app.model_summary()));
+            lines.extend(app.supervisor.active().map(|job| format!("Running: {}", app.supervisor.line(job.id()))));
+            list(&mut lines, HOME_MENU.iter().map(|item| item.to_string()).collect(), app.selected_index, false);
+        }
+        Screen::ModelSelection => {
+            lines.push(format!("Model: {}", app.model_summary()));
+            let models = app.listed_models()
+                .iter()
+                .map(|model| format!("{} - {}", model.name(), model.description()))
+                .collect();
+            list(&mut lines, models, app.selected_index, true);
+            status(&mut lines, app.model_status.as_deref());
+        }
+        Screen::Config => {
+            if let Some(form) = &app.config_form {
+                lines.push(format!("File: {}", form.path));
+                list(&mut lines, form.lines().iter().map(|line| squeeze(line)).collect(), app.selected_index, false);
+            }
+            status(&mut lines, app.config_form_status.as_deref());
+        }
+        Screen::Training => {
+            lines.extend(training_chart::latest_rows(app.training_history.progress())
+                .into_iter()
+                .map(|(name, value)| format!("{}: {}", name, value)));
+            lines.extend(training_chart::instrument_rows(app.training_history.progress())
+                .iter()
+                .map(|row| format!("Instrument {}", row.line())));
+            if let Some(timing) = app.training_timing() {
+                lines.push(format!("Timing: {}", timing));
+            }
+            if let Some(step) = *app.training_step.lock().unwrap() {
+                lines.push(format!("Epoch: {}", step.label()));
+            }
+            match &app.gpu_reading {
+                GpuReading::Devices(devices) => lines.extend(devices.iter().map(|gpu| gpu.describe())),
+                GpuReading::Unavailable => lines.push("GPU monitoring unavailable".to_string()),
+                GpuReading::Pending => {}
+            }
+            if let Some(warning) = app.training_hang_warning() {
+                lines.push(format!("Watchdog: training {}", warning));
+            }
+            if let Some(notice) = app.training_parse_notice() {
+                lines.extend(notice.iter().map(|line| format!("Notice: {}", line.trim_start())));
+                lines.extend(app.training_log_tail(dashboard::LOG_FORWARD_LINES).iter().map(|line| format!("Log: {}", line)));
+            }
+            if app.training_stopping() {
+                lines.push("Stopping (saving checkpoint)…".to_string());
+            }
+            lines.extend(app.storage_lines());
+            lines.extend(app.training_issues.iter().map(|issue| format!("Config issue: {}", issue.line())));
+            status(&mut lines, app.training_status.as_deref());
+        }
+        Screen::Validation => match &app.validation_form {
+            None => lines.push("Select a model first (1. Model Selection)".to_string()),
+            Some(form) => {
+                lines.extend(validation_lines(form).iter().map(|line| squeeze(line)));
+                if let Some(progress) = app.validation_progress {
+                    lines.push(format!("Progress: {} of {} files", progress.done, progress.total));
+                }
+                if !app.validation_results.is_empty() {
+                    lines.push(validation_summary(&app.validation_results));
+                    lines.extend(app.validation_table.plain_lines());
+                }
+                status(&mut lines, app.validation_status.as_deref());
+            }
+        },
+        Screen::Inference => match &app.inference_form {
+            None => lines.push("Select a model first (1. Model Selection)".to_string()),
+            Some(form) => {
+                lines.extend(inference_lines(form).iter().filter(|line| !line.is_empty()).map(|line| squeeze(line)));
+                if let Some(preview) = &app.input_preview {
+                    lines.push(format!("Audio: {}", preview.summary()));
+                }
+                let presets = app.config.presets(&form.model_type);
+                lines.push(format!("Presets for {}: {}", form.model_type.key(), presets.len()));
+                let presets = presets.iter().map(|preset| format!("{} ({})", preset.name, preset.summary())).collect();
+                list(&mut lines, presets, app.selected_index, true);
+                if let Some(line) = app.inference_log.lock().unwrap().lines().last() {
+                    lines.push(format!("Output: {}", line.text));
+                }
+                if let Some(step) = *app.inference_step.lock().unwrap() {
+                    lines.push(format!("Tracks: {}", step.label()));
+                }
+                lines.extend(app.inference_results.iter().map(|result| format!("Run: {}", inference_result_line(result))));
+                status(&mut lines, app.inference_status.as_deref());
+            }
+        },
+        Screen::RenameStems => {
+            if let Some((_, plan)) = &app.rename_plan {
+                lines.push(rename_summary(plan));
+                list(&mut lines, plan.lines(), app.selected_index, true);
+            }
+            status(&mut lines, app.inference_status.as_deref());
+        }
+        Screen::Checkpoints => {
+            lines.push(format!("Checkpoints: {}, best SDR first", app.checkpoints.len()));
+            list(&mut lines, app.checkpoints.iter().map(|checkpoint| squeeze(&checkpoint_line(checkpoint))).collect(), app.selected_index, true);
+            status(&mut lines, app.checkpoint_status.as_deref());
+        }
+        // Announced as new lines come in, like any other change.
+        Screen::Log => lines.extend(app.log_tail(20)),
+        Screen::Tracks => {
+            if let Some(selection) = &app.track_selection {
+                lines.push(selection.confirmation());
+                let tracks = selection.tracks()
+                    .iter()
+                    .map(|track| format!(
+                        "{}, {}, {}",
+                        track.name(),
+                        track.duration_secs.map_or("length unknown".to_string(), format_clock),
+                        if track.selected { "selected" } else { "left out" }
+                    ))
+                    .collect();
+                list(&mut lines, tracks, app.selected_index, true);
+                match selection.patterns() {
+                    [] => lines.push("Excluded by pattern: none".to_string()),
+                    patterns => lines.push(format!("Excluded by pattern: {}", patterns.join(", "))),
+                }
+            }
+            status(&mut lines, app.inference_status.as_deref());
+        }
+        Screen::InferenceBatch => {
+            lines.push(app.inference_batch.summary());
+            list(&mut lines, app.batch_lines(), app.selected_index, true);
+            status(&mut lines, app.batch_status.as_deref());
+        }
+        Screen::TrainingQueue => {
+            lines.push(format!("On failure: {}", app.training_queue.on_failure.name()));
+            let runs = app.queue_lines();
+            if runs.is_empty() {
+                lines.push("Nothing queued".to_string());
+            }
+            list(&mut lines, runs.iter().map(|line| squeeze(line)).collect(), app.selected_index, true);
+            status(&mut lines, app.queue_status.as_deref());
+        }
+        Screen::ModelConfig => {
+            list(&mut lines, app.model_config_lines().iter().map(|line| squeeze(line)).collect(), app.selected_index, false);
+            status(&mut lines, app.model_config_status.as_deref());
+        }
+        Screen::Sweep => {
+            lines.extend(app.sweep_table.plain_lines());
+            status(&mut lines, app.queue_status.as_deref());
+        }
+        Screen::Recent => {
+            list(&mut lines, app.config.recent_configs.iter().map(|entry| squeeze(&entry.line(&app.project_root))).collect(), app.selected_index, true);
+            status(&mut lines, app.recent_status.as_deref());
+        }
+        Screen::Downloads => {
+            lines.push(format!("Into: {}", app.config.weights_dir().display()));
+            let entries = app.download_entries().iter().map(|entry| entry.line(app.config.weights_dir())).collect();
+            list(&mut lines, entries, app.selected_index, true);
+            status(&mut lines, app.download_status.as_deref());
+        }
+        Screen::Environment => {
+            lines.extend(app.environment_lines());
+            status(&mut lines, app.preflight_status.as_deref());
+        }
+        Screen::Hosts => {
+            list(&mut lines, app.host_lines(), app.selected_index, true);
+            status(&mut lines, app.hosts_status.as_deref());
+        }
+        Screen::ConfigDiff => list(&mut lines, app.config_diff_lines(), app.selected_index, false),
+        Screen::Ensemble => {
+            lines.extend(ensemble_lines(&app.ensemble_form).iter().map(|line| squeeze(line)));
+            list(&mut lines, ensemble_file_lines(&app.ensemble_form), app.selected_index, true);
+            lines.extend(app.log_tail(10).into_iter().map(|line| format!("Output: {}", line)));
+            status(&mut lines, app.ensemble_status.as_deref());
+        }
+        Screen::Devices => {
+            list(&mut lines, app.device_lines(), app.selected_index, true);
+            status(&mut lines, app.devices_status.as_deref());
+        }
+        Screen::Trash => {
+            lines.push(format!("Trash: {} items", app.trash_items.len()));
+            list(&mut lines, app.trash_items.iter().map(|item| squeeze(&trash_item_text(item))).collect(), app.selected_index, true);
+            match app.undo.recent().next() {
+                Some(entry) => lines.push(format!("u undoes: {}", entry.description)),
+                None => lines.push("Nothing to undo this session".to_string()),
+            }
+            status(&mut lines, app.trash_status.as_deref());
+        }
+        Screen::Preview => lines.extend(app.preview_text().lines().filter(|line| !line.is_empty()).map(squeeze)),
+        Screen::DuplicateRun => {
+            let template = app.template_lines();
+            if let Some((heading, fields)) = template.split_first() {
+                lines.push(heading.clone());
+                list(&mut lines, fields.iter().map(|line| squeeze(line)).collect(), app.selected_index, false);
+            }
+            status(&mut lines, app.template_status.as_deref());
+        }
+        Screen::Mix => {
+            lines.extend(app.mix_lines());
+            if let Some(mix) = &app.mix {
+                list(&mut lines, (0..mix.names.len()).map(|stem| mix.stem_line(stem)).collect(), app.selected_index, true);
+            }
+        }
+        Screen::Outputs => {
+            if let Some(listing) = &app.output_listing {
+                lines.push(format!("Summary: {}", listing.summary()));
+                let stems = listing.lines().into_iter().map(|line| squeeze(&line.text)).collect();
+                list(&mut lines, stems, app.selected_index, false);
+            }
+            status(&mut lines, app.output_status.as_deref());
+        }
+        Screen::InputFiles => {
+            if let Some(preview) = &app.input_preview {
+                lines.push(format!("Summary: {}", preview.summary()));
+            }
+            lines.extend(app.input_files_table.plain_lines());
+        }
+        Screen::Reconstruction => {
+            if let Some((_, report)) = &app.reconstruction {
+                lines.push(format!("Summary: {}", report.summary()));
+            }
+            lines.extend(app.reconstruction_table.plain_lines());
+        }
+        Screen::Jobs => {
+            let jobs = app.job_lines();
+            if jobs.is_empty() {
+                lines.push("No jobs this session".to_string());
+            }
+            list(&mut lines, jobs, app.selected_index, true);
+            if let Some((name, dump)) = &app.last_dump {
+                lines.push(format!("py-spy dump of {}:", name));
+                lines.extend(dump.lines().map(|line| format!("Dump: {}", line)));
+            }
+            status(&mut lines, app.jobs_status.as_deref());
+        }
+        Screen::Dashboard => match &app.dashboard {
+            Some(dashboard) if !dashboard.runs.is_empty() => {
+                list(&mut lines, dashboard.runs.iter().map(|run| run.summary()).collect(), app.selected_index, true);
+                if let Some(notice) = dashboard.parse_notice(app.selected_index) {
+                    lines.extend(notice.iter().map(|line| format!("Notice: {}", line.trim_start())));
+                }
+                lines.extend(dashboard.log_lines(app.selected_index, app.dashboard_times).iter().map(|line| format!("Log: {}", line)));
+            }
+            _ => lines.push("No running training owned by another instance".to_string()),
+        },
+        Screen::ImportBundle => lines.extend(app.import_lines().iter().filter(|line| !line.is_empty()).map(|line| squeeze(line))),
+        Screen::Settings => lines.extend(app.settings_lines().iter().filter(|line| !line.is_empty()).map(|line| squeeze(line))),
+        Screen::ParserTest => lines.extend(app.parser_matches().iter().map(|line| format!("Line {}", line.trim_start()))),
+        Screen::History => {
+            let total = app.history.as_ref().map_or(0, |history| history.entries().len());
+            lines.push(format!("Filter: {}", app.history_filter.text()));
+            lines.push(format!("Runs: {} of {}", app.history_table.len(), total));
+            if let Some(month) = app.month_cost() {
+                lines.push(format!("This month: {}", month));
+            }
+            if let Some(stale) = app.stale_summary() {
+                lines.push(format!("Stale: {}", stale));
+            }
+            lines.extend(app.history_table.plain_lines());
+            status(&mut lines, app.history_status.as_deref());
+        }
+        Screen::RunDetail => {
+            if let Some(detail) = &app.run_detail {
+                lines.extend(detail.lines.iter().cloned());
+                lines.extend(training_chart::latest_rows(detail.metrics.progress())
+                    .into_iter()
+                    .map(|(name, value)| format!("{}: {}", name, value)));
+            }
+            if let Some(progress) = app.pull_progress() {
+                lines.push(format!("Pulling: {}", progress.label()));
+            }
+            status(&mut lines, app.pull_status.as_deref());
+        }
+        Screen::Compare => {
+            if let Some(comparison) = &app.comparison {
+                lines.push(format!("A: {}, B: {}", comparison.a.entry.name, comparison.b.entry.name));
+                if !comparison.per_instrument() {
+                    lines.push("One run has no SDR per instrument; only the mean is compared".to_string());
+                }
+            }
+            lines.extend(app.comparison_table.plain_lines());
+        }
+        Screen::BakeOff => {
+            if let Some((_, report)) = &app.bake_off {
+                lines.push(format!("Side A: model {}, side B: model {}", app.bake_off_sides.0 + 1, app.bake_off_sides.1 + 1));
+                lines.extend(app.bake_off_models(report).iter().map(|line| squeeze(line)));
+            }
+            lines.push("Stems:".to_string());
+            lines.extend(app.bake_off_table.plain_lines());
+        }
+    }
+    // Preview, Settings and the parser test show their keys already.
+    if !matches!(app.screen, Screen::Preview | Screen::Settings | Screen::ParserTest) {
+        lines.push(format!("Keys: {}", squeeze(app.key_help())));
+    }
+    lines
+}
+
+/// E.g. "Run History" or "Log of job #2".
+pub fn screen_name(app: &App) -> String {
+    match app.screen {
+        Screen::RunDetail => match &app.run_detail {
+            Some(detail) => format!("Run {}", detail.entry.name),
+            None => "Run".to_string(),
+        },
+        Screen::BakeOff => match &app.bake_off {
+            Some((_, report)) => format!("Bake-off {}", report.name),
+            None => "Bake-off".to_string(),
+        },
+        Screen::RenameStems => match &app.rename_plan {
+            Some((store_dir, _)) => format!("Rename stems in {}", store_dir.display()),
+            None => "Rename stems".to_string(),
+        },
+        Screen::Log => match app.previous_screen() {
+            Some(Screen::Training) => "Training log".to_string(),
+            Some(Screen::Jobs) => match app.attached_job() {
+                Some(job) => format!("Log of job #{}", job.id()),
+                None => "Job log".to_string(),
+            },
+            _ => "Inference output".to_string(),
+        },
+        Screen::Tracks => match &app.track_selection {
+            Some(selection) => format!("Input files in {}", selection.folder().display()),
+            None => "Input files".to_string(),
+        },
+        Screen::ConfigDiff => match &app.config_diff {
+            Some((first, second, _)) => format!("Config diff {} → {}", first.display(), second.display()),
+            None => "Config diff".to_string(),
+        },
+        Screen::ModelConfig => match &app.model_config {
+            Some(editor) if editor.is_changed() => format!("Model config {} (unsaved)", editor.path().display()),
+            Some(editor) => format!("Model config {}", editor.path().display()),
+            None => "Model config".to_string(),
+        },
+        Screen::Sweep => match &app.sweep {
+            Some(sweep) => format!("Sweep {}", sweep),
+            None => "Sweep".to_string(),
+        },
+        Screen::Outputs => match &app.output_listing {
+            Some(listing) => format!("Outputs in {}", listing.store_dir.display()),
+            None => "Outputs".to_string(),
+        },
+        Screen::InputFiles => match &app.input_preview {
+            Some(preview) if preview.recursive => format!("Input files in {} and its subfolders", preview.folder.display()),
+            Some(preview) => format!("Input files in {}", preview.folder.display()),
+            None => "Input files".to_string(),
+        },
+        Screen::Reconstruction => match &app.reconstruction {
+            Some((store_dir, _)) => format!("Stem sums in {}", store_dir.display()),
+            None => "Stem sums".to_string(),
+        },
+        ref screen => screen_title(screen).to_string(),
+    }
+}
+
+/// The name of `screen` without what it shows, e.g. "Run" for any run.
+pub fn screen_title(screen: &Screen) -> &'static str {
+    match screen {
+        Screen::Home => "Home",
+        Screen::ModelSelection => "Model Selection",
+        Screen::Config => "Configuration",
+        Screen::Training => "Training",
+        Screen::Inference => "Inference",
+        Screen::Validation => "Validation",
+        Screen::Preview => "Stem Preview",
+        Screen::Settings => "Settings",
+        Screen::ParserTest => "Test Log Parser",
+        Screen::History => "Run History",
+        Screen::Compare => "Compare runs",
+        Screen::RunDetail => "Run",
+        Screen::BakeOff => "Bake-off",
+        Screen::RenameStems => "Rename stems",
+        Screen::Checkpoints => "Checkpoints",
+        Screen::Log => "Log",
+        Screen::Tracks => "Input files",
+        Screen::Trash => "Recently Deleted",
+        Screen::Recent => "Recent Configs",
+        Screen::Downloads => "Download Pretrained",
+        Screen::Devices => "Training Devices",
+        Screen::Environment => "Environment Check",
+        Screen::Hosts => "Run On",
+        Screen::Ensemble => "Ensemble",
+        Screen::ConfigDiff => "Config diff",
+        Screen::TrainingQueue => "Training Queue",
+        Screen::InferenceBatch => "Inference Batch",
+        Screen::ModelConfig => "Model config",
+        Screen::Sweep => "Sweep",
+        Screen::ImportBundle => "Import Settings",
+        Screen::DuplicateRun => "Duplicate Run",
+        Screen::Dashboard => "Dashboard",
+        Screen::Mix => "Stem Mix",
+        Screen::Jobs => "Jobs",
+        Screen::Outputs => "Outputs",
+        Screen::InputFiles => "Input files",
+        Screen::Reconstruction => "Stem sums",
+    }
+}
+
+/// Numbered lines, then the selected one again. `number` is false for
+/// items that carry their own key, like the Home menu.
+fn list(lines: &mut Vec<String>, items: Vec<String>, selected: usize, number: bool) {
+    let items: Vec<String> = items.into_iter()
+        .enumerate()
+        .map(|(i, item)| if number { format!("{}. {}", i + 1, item) } else { item })
+        .collect();
+    if let Some(item) = items.get(selected) {
+        let item = format!("Selected: {}", item);
+        lines.extend(items);
+        lines.push(item);
+    } else {
+        lines.extend(items);
+    }
+}
+
+fn status(lines: &mut Vec<String>, status: Option<&str>) {
+    if let Some(status) = status {
+        lines.extend(status.lines().map(|line| format!("Status: {}", line)));
+    }
+}
+
+/// Collapses the runs of spaces used for alignment, which screen readers
+/// read out as pauses or not at all.
+fn squeeze(line: &str) -> String {
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Decides what plain mode prints: the whole screen when another one
+/// opens, otherwise only the lines that weren't there before, so a moved
+/// selection or a new status is one new line rather than a redraw.
+#[derive(Debug, Default)]
+pub struct Announcer {
+    shown: Vec<String>,
+}
+
+impl Announcer {
+    pub fn new() -> Self {
+        Announcer::default()
+    }
+
+    pub fn update(&mut self, lines: Vec<String>) -> Vec<String> {
+        let announced = if self.shown.first() != lines.first() {
+            lines.clone()
+        } else {
+            lines.iter().filter(|line| !self.shown.contains(line)).cloned().collect()
+        };
+        self.shown = lines;
+        announced
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::write_wav;
+    use crate::selection::TrackSelection;
+    use crossterm::event::{KeyCode, KeyEvent};
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_key(KeyEvent::from(code));
+    }
+
+    #[test]
+    fn home_and_model_selection_read_as_lines() {
+        let mut app = App::new();
+        let mut announcer = Announcer::new();
+        assert_eq!(
+            announcer.update(render(&app)),
+            [
+                "Screen: Home",
+                "Model: none selected",
+                "1. Model Selection",
+                "2. Configuration",
+                "3. Training",
+                "4. Inference",
+                "5. Validation",
+                "6. Run History",
+                "7. Settings",
+                "8. Ensemble",
+                "e. Check environment",
+                "q. Quit",
+                "h. Help",
+                "Selected: 1. Model Selection",
+                "Keys: Up/Down: choose Enter or 1-8: open e: check the python environment r: recent configs d: watch runs j: jobs",
+            ]
+        );
+
+        press(&mut app, KeyCode::Down);
+        assert_eq!(announcer.update(render(&app)), ["Selected: 2. Configuration"]);
+        assert!(announcer.update(render(&app)).is_empty());
+
+        press(&mut app, KeyCode::Up);
+        press(&mut app, KeyCode::Enter);
+        let screen = announcer.update(render(&app));
+        assert_eq!(screen[..3], ["Screen: Model Selection", "Model: none selected", "1. MDX23C - KUIELab TFC TDF v3 architecture"]);
+        assert_eq!(screen[screen.len() - 2], "Selected: 1. MDX23C - KUIELab TFC TDF v3 architecture");
+
+        press(&mut app, KeyCode::Char('k'));
+        assert_eq!(announcer.update(render(&app)), ["Input: Checkpoint path (Enter bind, Ctrl+O browse, Esc cancel)"]);
+        press(&mut app, KeyCode::Char('a'));
+        assert_eq!(announcer.update(render(&app)), ["Text: a"]);
+    }
+
+    #[test]
+    fn track_selection_reads_as_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let silence = vec![0.0f32; 44_100 * 90];
+        for name in ["a.wav", "b_reference.wav"] {
+            write_wav(&dir.path().join(name), 44_100, &[silence.clone(), silence.clone()]).unwrap();
+        }
+        let mut app = App::new();
+        app.track_selection = Some(TrackSelection::scan(dir.path(), &["*_reference.wav".to_string()]).unwrap());
+        app.screen = Screen::Tracks;
+        app.selected_index = 1;
+
+        let lines = render(&app);
+        assert_eq!(lines[0], format!("Screen: Input files in {}", dir.path().display()));
+        assert_eq!(
+            lines[1..],
+            [
+                "1 of 2 tracks selected, 0:01:30 total",
+                "1. a.wav, 1:30, selected",
+                "2. b_reference.wav, 1:30, left out",
+                "Selected: 2. b_reference.wav, 1:30, left out",
+                "Excluded by pattern: *_reference.wav",
+                "Keys: Space: toggle x: exclude pattern a: select all w: remember patterns for this folder Enter: use selection Esc: back",
+            ]
+        );
+    }
+
+    #[test]
+    fn read_only_mode_watches_and_refuses_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = App::new();
+        app.read_only = true;
+        app.config.history_path = Some(dir.path().join("tui_history.json").to_string_lossy().into_owned());
+
+        press(&mut app, KeyCode::Char('d'));
+        assert_eq!(
+            render(&app),
+            [
+                "Screen: Dashboard",
+                "Mode: read-only dashboard",
+                "No running training owned by another instance",
+                "Keys: Up/Down: choose run t: absolute/relative times r: look for runs again Esc: back",
+            ]
+        );
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('u'));
+        assert_eq!(render(&app).last().unwrap(), "Notice: Read-only dashboard: key disabled");
+        assert!(!dir.path().join("tui_history.json").exists());
+    }
+}
@@ -0,0 +1,212 @@
+use anyhow::{bail, Context, Result};
+
+use crate::model::{CommandSpec, ModelType};
+use crate::runner::RunnerEnv;
+
+/// Packages every model needs, as (import name, pip package).
+pub const BASE_PACKAGES: [(&str, &str); 3] = [("torch", "torch"), ("yaml", "pyyaml"), ("ml_collections", "ml_collections")];
+
+/// One line of the checklist.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckItem {
+    pub name: String,
+    pub passed: bool,
+    /// A failure stops launches; CUDA missing only warns, as a run can
+    /// still go on the CPU.
+    pub required: bool,
+    /// The version found, or why the import failed.
+    pub detail: String,
+    /// What to `pip install` when it failed.
+    pub pip: Option<String>,
+}
+
+impl CheckItem {
+    /// E.g. "✓ torch 2.3.1+cu121" or "✗ mamba_ssm: ModuleNotFoundError".
+    pub fn line(&self) -> String {
+        match (self.passed, self.required) {
+            (true, _) => format!("✓ {} {}", self.name, self.detail),
+            (false, true) => format!("✗ {}: {}", self.name, self.detail),
+            (false, false) => format!("! {}: {}", self.name, self.detail),
+        }
+    }
+}
+
+/// What the configured interpreter has for a model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreflightReport {
+    pub python: String,
+    /// The model whose extra packages were checked.
+    pub model_type: Option<ModelType>,
+    pub items: Vec<CheckItem>,
+}
+
+impl PreflightReport {
+    /// False when a required package is missing.
+    pub fn passed(&self) -> bool {
+        self.items.iter().all(|item| item.passed || !item.required)
+    }
+
+    /// E.g. "pip install rotary-embedding-torch beartype".
+    pub fn install_hint(&self) -> Option<String> {
+        let packages: Vec<&str> = self.items.iter()
+            .filter(|item| !item.passed)
+            .filter_map(|item| item.pip.as_deref())
+            .collect();
+        (!packages.is_empty()).then(|| format!("{} -m pip install {}", self.python, packages.join(" ")))
+    }
+
+    pub fn summary(&self) -> String {
+        let failed = self.items.iter().filter(|item| !item.passed && item.required).count();
+        match (failed, self.items.iter().any(|item| !item.passed)) {
+            (0, false) => "The python environment has everything".to_string(),
+            (0, true) => "The python environment can run, with warnings".to_string(),
+            (failed, _) => format!("{} required package(s) missing", failed),
+        }
+    }
+}
+
+/// The packages to check for `model_type`, base ones first.
+pub fn packages(model_type: Option<&ModelType>) -> Vec<(&'static str, &'static str)> {
+    let mut packages = BASE_PACKAGES.to_vec();
+    for package in model_type.map_or(&[][..], ModelType::required_packages) {
+        if !packages.contains(package) {
+            packages.push(*package);
+        }
+    }
+    packages
+}
+
+/// Python that imports each of `imports` and prints a `package` or
+/// `missing` line for it, then a `cuda` line with what torch sees.
+pub fn snippet(imports: &[&str]) -> String {
+    let names: Vec<String> = imports.iter().map(|name| format!("{:?}", name)).collect();
+    format!(
+        "import importlib\n\
+         for name in [{}]:\n\
+         \x20   try:\n\
+         \x20       module = importlib.import_module(name)\n\
+         \x20       print('package', name, getattr(module, '__version__', 'unknown'))\n\
+         \x20   except Exception as e:\n\
+         \x20       print('missing', name, type(e).__name__, str(e).splitlines()[0] if str(e) else '')\n\
+         try:\n\
+         \x20   import torch\n\
+         \x20   print('cuda', torch.cuda.is_available(), torch.version.cuda)\n\
+         except Exception:\n\
+         \x20   pass\n",
+        names.join(", ")
+    )
+}
+
+/// Reads the snippet's output into a checklist for `packages`. A package
+/// the output doesn't mention counts as missing.
+pub fn parse(output: &str, packages: &[(&str, &str)]) -> Vec<CheckItem> {
+    let mut items: Vec<CheckItem> = packages.iter()
+        .map(|(name, pip)| CheckItem {
+            name: name.to_string(),
+            passed: false,
+            required: true,
+            detail: "not reported".to_string(),
+            pip: Some(pip.to_string()),
+        })
+        .collect();
+    let mut cuda = None;
+    for line in output.lines() {
+        let mut words = line.trim().splitn(3, ' ');
+        let (kind, name, rest) = (words.next().unwrap_or_default(), words.next().unwrap_or_default(), words.next().unwrap_or_default());
+        match kind {
+            "package" | "missing" => {
+                if let Some(item) = items.iter_mut().find(|item| item.name == name) {
+                    item.passed = kind == "package";
+                    item.detail = rest.trim().to_string();
+                }
+            }
+            "cuda" => cuda = Some((name == "True", rest.trim().to_string())),
+            _ => {}
+        }
+    }
+    let torch = items.iter().find(|item| item.name == "torch").filter(|item| item.passed).map(|item| item.detail.clone());
+    if let Some(torch) = torch {
+        let (passed, detail) = match cuda {
+            Some((true, version)) => (true, format!("available (CUDA {})", version)),
+            Some((false, _)) if torch.contains("+cpu") => (false, format!("not available; torch {} is a CPU-only build", torch)),
+            _ => (false, "not available; training and inference run on the CPU".to_string()),
+        };
+        items.push(CheckItem { name: "CUDA".to_string(), passed, required: false, detail, pip: None });
+    }
+    items
+}
+
+/// Runs the snippet with `python` for `model_type`, in the project like the
+/// scripts. Takes a few seconds, mostly importing torch.
+pub fn run(python: &str, model_type: Option<&ModelType>, runner: &RunnerEnv) -> Result<PreflightReport> {
+    let packages = packages(model_type);
+    let imports: Vec<&str> = packages.iter().map(|(name, _)| *name).collect();
+    let mut command = CommandSpec::new(python);
+    command.arg("-c").arg(snippet(&imports));
+    let output = runner.in_project(command).std_command()
+        .output()
+        .with_context(|| format!("Failed to run {}", python))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("{} exited with {}: {}", python, output.status, stderr.lines().last().unwrap_or_default());
+    }
+    Ok(PreflightReport {
+        python: python.to_string(),
+        model_type: model_type.cloned(),
+        items: parse(&String::from_utf8_lossy(&output.stdout), &packages),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_packages_fail_and_a_cpu_torch_warns() {
+        let packages = packages(Some(&ModelType::BsRoformer));
+        assert_eq!(packages.len(), 6);
+        assert!(snippet(&["torch", "yaml"]).contains("for name in [\"torch\", \"yaml\"]:\n    try:"));
+
+        let output = "package torch 2.3.1+cpu\n\
+                      package yaml 6.0.1\n\
+                      package ml_collections unknown\n\
+                      missing rotary_embedding_torch ModuleNotFoundError No module named 'rotary_embedding_torch'\n\
+                      package beartype 0.18.5\n\
+                      cuda False None\n";
+        let report = PreflightReport { python: "python3".to_string(), model_type: Some(ModelType::BsRoformer), items: parse(output, &packages) };
+        let lines: Vec<String> = report.items.iter().map(CheckItem::line).collect();
+        assert_eq!(lines, [
+            "✓ torch 2.3.1+cpu",
+            "✓ yaml 6.0.1",
+            "✓ ml_collections unknown",
+            "✗ rotary_embedding_torch: ModuleNotFoundError No module named 'rotary_embedding_torch'",
+            "✓ beartype 0.18.5",
+            "✗ einops: not reported",
+            "! CUDA: not available; torch 2.3.1+cpu is a CPU-only build",
+        ]);
+        assert!(!report.passed());
+        assert_eq!(report.summary(), "2 required package(s) missing");
+        assert_eq!(report.install_hint().as_deref(), Some("python3 -m pip install rotary-embedding-torch einops"));
+
+        let good = parse("package torch 2.3.1\npackage yaml 6.0\npackage ml_collections 0.1\ncuda True 12.1\n", &BASE_PACKAGES);
+        let report = PreflightReport { python: "python3".to_string(), model_type: None, items: good };
+        assert!(report.passed() && report.install_hint().is_none());
+        assert_eq!(report.items.last().unwrap().line(), "✓ CUDA available (CUDA 12.1)");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn the_snippet_runs_through_the_interpreter() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let python = dir.path().join("python");
+        std::fs::write(&python, "#!/bin/sh\n[ \"$1\" = -c ] || exit 2\necho 'package torch 2.3.1'\necho 'missing yaml ModuleNotFoundError'\n").unwrap();
+        std::fs::set_permissions(&python, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let runner = RunnerEnv::default().in_root(dir.path());
+        let report = run(&python.to_string_lossy(), None, &runner).unwrap();
+        assert!(!report.passed());
+        assert_eq!(report.items[1].line(), "✗ yaml: ModuleNotFoundError");
+        assert!(run(&dir.path().join("nothing").to_string_lossy(), None, &runner).is_err());
+    }
+}
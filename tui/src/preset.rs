@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::model::{ChannelPolicy, DuplicatePolicy, InferenceConfig, LoudnessMatch, SampleRatePolicy};
+use crate::stem_names::StemNaming;
+
+/// Named inference settings for one model type. Every field is optional:
+/// applying a preset only changes the fields it sets, so presets can be
+/// layered (e.g. "8 GB card" over "archival FLAC").
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InferencePreset {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_checkpoint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_rate_policy: Option<SampleRatePolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel_policy: Option<ChannelPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalize: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loudness_match: Option<LoudnessMatch>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicate_policy: Option<DuplicatePolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub use_tta: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flac_file: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stem_naming: Option<StemNaming>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reconstruction_threshold_db: Option<f64>,
+}
+
+impl InferencePreset {
+    /// Captures every setting of `config` except the input and output
+    /// folders, which belong to the job rather than the model.
+    pub fn from_config(name: &str, config: &InferenceConfig) -> Self {
+        let path = |path: &str| (!path.is_empty()).then(|| path.to_string());
+        InferencePreset {
+            name: name.to_string(),
+            config_path: path(&config.config_path),
+            start_checkpoint: path(&config.start_checkpoint),
+            sample_rate_policy: Some(config.sample_rate_policy),
+            channel_policy: Some(config.channel_policy),
+            normalize: config.normalize,
+            loudness_match: Some(config.loudness_match),
+            duplicate_policy: Some(config.duplicate_policy),
+            use_tta: Some(config.use_tta),
+            flac_file: Some(config.flac_file),
+            stem_naming: config.stem_naming.clone(),
+            reconstruction_threshold_db: config.reconstruction_threshold_db,
+        }
+    }
+
+    /// Sets the fields this preset defines and leaves the rest alone.
+    /// Returns warnings for paths that no longer exist; they are applied
+    /// anyway so the user can see what the preset expected.
+    pub fn apply(&self, config: &mut InferenceConfig) -> Vec<String> {
+        let mut warnings = vec![];
+        if let Some(path) = &self.config_path {
+            if !Path::new(path).is_file() {
+                warnings.push(format!("Preset '{}': config {} no longer exists", self.name, path));
+            }
+            config.config_path = path.clone();
+        }
+        if let Some(path) = &self.start_checkpoint {
+            if !Path::new(path).is_file() {
+                warnings.push(format!("Preset '{}': checkpoint {} no longer exists", self.name, path));
+            }
+            config.start_checkpoint = path.clone();
+        }
+        if let Some(policy) = self.sample_rate_policy {
+            config.sample_rate_policy = policy;
+        }
+        if let Some(policy) = self.channel_policy {
+            config.channel_policy = policy;
+        }
+        if let Some(normalize) = self.normalize {
+            config.normalize = Some(normalize);
+        }
+        if let Some(mode) = self.loudness_match {
+            config.loudness_match = mode;
+        }
+        if let Some(policy) = self.duplicate_policy {
+            config.duplicate_policy = policy;
+        }
+        if let Some(use_tta) = self.use_tta {
+            config.use_tta = use_tta;
+        }
+        if let Some(flac_file) = self.flac_file {
+            config.flac_file = flac_file;
+        }
+        if let Some(naming) = &self.stem_naming {
+            config.stem_naming = Some(naming.clone());
+        }
+        if let Some(threshold) = self.reconstruction_threshold_db {
+            config.reconstruction_threshold_db = Some(threshold);
+        }
+        warnings
+    }
+
+    /// The fields the preset sets, for the picker.
+    pub fn summary(&self) -> String {
+        let on_off = |on: bool| if on { "on" } else { "off" };
+        let mut parts = vec![];
+        if let Some(path) = &self.start_checkpoint {
+            parts.push(format!("checkpoint {}", path));
+        }
+        if let Some(path) = &self.config_path {
+            parts.push(format!("config {}", path));
+        }
+        if let Some(policy) = self.sample_rate_policy {
+            parts.push(format!("sample rate: {}", policy.name()));
+        }
+        if let Some(policy) = self.channel_policy {
+            parts.push(format!("channels: {}", policy.name()));
+        }
+        if let Some(normalize) = self.normalize {
+            parts.push(format!("normalize {}", on_off(normalize)));
+        }
+        if let Some(mode) = self.loudness_match {
+            parts.push(format!("loudness match: {}", mode.name()));
+        }
+        if let Some(policy) = self.duplicate_policy {
+            parts.push(format!("duplicates: {}", policy.name()));
+        }
+        if let Some(use_tta) = self.use_tta {
+            parts.push(format!("TTA {}", on_off(use_tta)));
+        }
+        if let Some(flac_file) = self.flac_file {
+            parts.push(if flac_file { "FLAC" } else { "WAV" }.to_string());
+        }
+        if self.stem_naming.is_some() {
+            parts.push("renames stems".to_string());
+        }
+        if let Some(threshold) = self.reconstruction_threshold_db {
+            parts.push(format!("verifies stem sum ({:.0} dB)", threshold));
+        }
+        parts.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::model::ModelType;
+    use std::fs;
+
+    #[test]
+    fn applying_only_touches_fields_the_preset_defines() {
+        let preset: InferencePreset = serde_yaml::from_str("name: archival\nflac_file: true\nuse_tta: true\n").unwrap();
+        let mut config = InferenceConfig::new(ModelType::BsRoformer);
+        config.start_checkpoint = "current.ckpt".into();
+        config.normalize = Some(false);
+        config.loudness_match = LoudnessMatch::Sum;
+
+        assert!(preset.apply(&mut config).is_empty());
+        assert!(config.flac_file && config.use_tta);
+        assert_eq!(config.start_checkpoint, "current.ckpt");
+        assert_eq!(config.normalize, Some(false));
+        assert_eq!(config.loudness_match, LoudnessMatch::Sum);
+        assert_eq!(preset.summary(), "TTA on, FLAC");
+    }
+
+    #[test]
+    fn saved_presets_round_trip_and_warn_about_missing_checkpoints() {
+        let dir = tempfile::tempdir().unwrap();
+        let checkpoint = dir.path().join("model.ckpt");
+        fs::write(&checkpoint, "weights").unwrap();
+
+        let mut current = InferenceConfig::new(ModelType::MDX23C);
+        current.start_checkpoint = checkpoint.to_string_lossy().to_string();
+        current.input_folder = "songs".into();
+        current.use_tta = true;
+
+        let mut app_config = AppConfig::default();
+        app_config.save_preset(&ModelType::MDX23C, InferencePreset::from_config("8gb", &current));
+        current.flac_file = true;
+        app_config.save_preset(&ModelType::MDX23C, InferencePreset::from_config("8gb", &current));
+        let reloaded: AppConfig = serde_yaml::from_str(&serde_yaml::to_string(&app_config).unwrap()).unwrap();
+        assert!(reloaded.presets(&ModelType::ScNet).is_empty());
+        let presets = reloaded.presets(&ModelType::MDX23C);
+        assert_eq!(presets.len(), 1, "saving under the same name replaces");
+        assert_eq!(presets[0].flac_file, Some(true));
+
+        let mut fresh = InferenceConfig::new(ModelType::MDX23C);
+        fresh.input_folder = "other songs".into();
+        assert!(presets[0].apply(&mut fresh).is_empty());
+        assert_eq!(fresh.input_folder, "other songs");
+        assert!(fresh.use_tta);
+
+        fs::remove_file(&checkpoint).unwrap();
+        let warnings = presets[0].apply(&mut fresh);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("no longer exists"), "{}", warnings[0]);
+    }
+}
@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::model::{InferenceConfig, ModelType};
+
+/// Where downloaded pretrained weights are looked for by default.
+pub const DEFAULT_WEIGHTS_DIR: &str = "pretrained";
+
+/// A published checkpoint from `docs/pretrained_models.md` whose config
+/// ships with the repo.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PretrainedVariant {
+    pub label: &'static str,
+    pub model_type: ModelType,
+    /// Relative to the project root.
+    pub config: &'static str,
+    /// File name of the downloaded weights.
+    pub checkpoint: &'static str,
+    /// Where the weights are published.
+    pub weights_url: &'static str,
+    /// Lowercase hex of the weights, checked before they are installed;
+    /// `None` while no hash is published with them, and the download is
+    /// then shown and asked about as unverified.
+    pub sha256: Option<&'static str>,
+}
+
+pub fn registry() -> Vec<PretrainedVariant> {
+    vec![
+        PretrainedVariant {
+            label: "viperx 1297",
+            model_type: ModelType::BsRoformer,
+            config: "configs/viperx/model_bs_roformer_ep_317_sdr_12.9755.yaml",
+            checkpoint: "model_bs_roformer_ep_317_sdr_12.9755.ckpt",
+            weights_url: "https://github.com/TRvlvr/model_repo/releases/download/all_public_uvr_models/model_bs_roformer_ep_317_sdr_12.9755.ckpt",
+            sha256: None,
+        },
+        PretrainedVariant {
+            label: "viperx other",
+            model_type: ModelType::BsRoformer,
+            config: "configs/viperx/model_bs_roformer_ep_937_sdr_10.5309.yaml",
+            checkpoint: "model_bs_roformer_ep_937_sdr_10.5309.ckpt",
+            weights_url: "https://github.com/TRvlvr/model_repo/releases/download/all_public_uvr_models/model_bs_roformer_ep_937_sdr_10.5309.ckpt",
+            sha256: None,
+        },
+        PretrainedVariant {
+            label: "viperx vocals",
+            model_type: ModelType::MelBandRoformer,
+            config: "configs/viperx/model_mel_band_roformer_ep_3005_sdr_11.4360.yaml",
+            checkpoint: "model_mel_band_roformer_ep_3005_sdr_11.4360.ckpt",
+            weights_url: "https://github.com/TRvlvr/model_repo/releases/download/all_public_uvr_models/model_mel_band_roformer_ep_3005_sdr_11.4360.ckpt",
+            sha256: None,
+        },
+        PretrainedVariant {
+            label: "KimberleyJensen vocals",
+            model_type: ModelType::MelBandRoformer,
+            config: "configs/KimberleyJensen/config_vocals_mel_band_roformer_kj.yaml",
+            checkpoint: "MelBandRoformer.ckpt",
+            weights_url: "https://huggingface.co/KimberleyJSN/melbandroformer/resolve/main/MelBandRoformer.ckpt",
+            sha256: None,
+        },
+    ]
+}
+
+/// Registry entries for one architecture.
+pub fn variants_for(model_type: &ModelType) -> Vec<PretrainedVariant> {
+    registry().into_iter().filter(|variant| variant.model_type == *model_type).collect()
+}
+
+/// The checkpoint and config that go with the selected model, so it means
+/// "viperx's BS RoFormer" and not just "some BS RoFormer".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelBinding {
+    pub model_type: ModelType,
+    pub checkpoint: String,
+    pub config: String,
+    /// Registry label; dropped once the checkpoint no longer is that one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+impl ModelBinding {
+    /// Binds `variant` with its weights expected in `weights_dir`.
+    pub fn from_variant(variant: &PretrainedVariant, weights_dir: &Path) -> Self {
+        ModelBinding {
+            model_type: variant.model_type.clone(),
+            checkpoint: weights_dir.join(variant.checkpoint).to_string_lossy().to_string(),
+            config: variant.config.to_string(),
+            label: Some(variant.label.to_string()),
+        }
+    }
+
+    /// A binding typed in by hand, with the config still to come.
+    pub fn manual(model_type: ModelType, checkpoint: &str) -> Self {
+        ModelBinding { model_type, checkpoint: checkpoint.to_string(), config: String::new(), label: None }
+    }
+
+    /// Points the binding at another checkpoint of the same architecture,
+    /// keeping the config.
+    pub fn set_checkpoint(&mut self, checkpoint: &str) {
+        if self.checkpoint != checkpoint {
+            self.checkpoint = checkpoint.to_string();
+            self.label = None;
+        }
+    }
+
+    /// E.g. "Band Split RoFormer — viperx 1297", or the checkpoint's file
+    /// name when it didn't come from the registry.
+    pub fn display(&self) -> String {
+        let variant = self.label.clone().unwrap_or_else(|| {
+            Path::new(&self.checkpoint).file_stem().unwrap_or_default().to_string_lossy().to_string()
+        });
+        format!("{} — {}", self.model_type.name(), variant)
+    }
+
+    /// What is wrong with the bound files, e.g. after they were moved.
+    /// Empty when both are where the binding says.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = vec![];
+        for (what, path) in [("checkpoint", &self.checkpoint), ("config", &self.config)] {
+            if path.is_empty() {
+                problems.push(format!("no {} set", what));
+            } else if !Path::new(path).is_file() {
+                problems.push(format!("{} {} not found", what, path));
+            }
+        }
+        problems
+    }
+
+    /// Fills in the paths `config` doesn't have yet.
+    pub fn fill_inference(&self, config: &mut InferenceConfig) {
+        if config.model_type != self.model_type {
+            return;
+        }
+        if config.config_path.is_empty() {
+            config.config_path = self.config.clone();
+        }
+        if config.start_checkpoint.is_empty() {
+            config.start_checkpoint = self.checkpoint.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn registry_configs_ship_with_the_repo() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+        for variant in registry() {
+            assert!(root.join(variant.config).is_file(), "{}", variant.config);
+        }
+        assert_eq!(variants_for(&ModelType::BsRoformer).len(), 2);
+        assert!(variants_for(&ModelType::ScNet).is_empty());
+    }
+
+    #[test]
+    fn binding_reports_moved_files_and_keeps_config_on_new_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let variant = &variants_for(&ModelType::BsRoformer)[0];
+        let mut binding = ModelBinding::from_variant(variant, dir.path());
+        assert_eq!(binding.display(), "Band Split RoFormer — viperx 1297");
+
+        binding.config = dir.path().join("viperx.yaml").to_string_lossy().to_string();
+        fs::write(&binding.config, "audio: {}\n").unwrap();
+        assert_eq!(binding.problems().len(), 1);
+        assert!(binding.problems()[0].starts_with("checkpoint "));
+
+        let finetuned = dir.path().join("finetuned.ckpt");
+        fs::write(&finetuned, "w").unwrap();
+        binding.set_checkpoint(&finetuned.to_string_lossy());
+        assert!(binding.problems().is_empty());
+        assert_eq!(binding.display(), "Band Split RoFormer — finetuned");
+        assert!(binding.config.ends_with("viperx.yaml"));
+
+        let mut form = InferenceConfig::new(ModelType::BsRoformer);
+        form.config_path = "mine.yaml".into();
+        binding.fill_inference(&mut form);
+        assert_eq!(form.config_path, "mine.yaml");
+        assert_eq!(form.start_checkpoint, binding.checkpoint);
+    }
+}
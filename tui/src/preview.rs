@@ -0,0 +1,494 @@
+use anyhow::{anyhow, bail, Result};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicU8, Ordering};
+use std::sync::Arc;
+
+use crate::audio::AudioDecoder;
+use crate::manifest::ResultsManifest;
+
+const NO_SEEK: u64 = u64::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Side {
+    Mixture,
+    Stem,
+}
+
+impl Side {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Side::Mixture => "A: mixture",
+            Side::Stem => "B: stem",
+        }
+    }
+}
+
+/// Parameters shared between the UI and the playback thread. Everything is
+/// atomic so the audio callback never blocks on the UI.
+pub struct PreviewControls {
+    side: AtomicU8,
+    stem_gain: AtomicU32,
+    paused: AtomicBool,
+    stopped: AtomicBool,
+    position: AtomicU64,
+    seek_to: AtomicU64,
+}
+
+impl PreviewControls {
+    fn new(stem_gain_db: f32) -> Self {
+        PreviewControls {
+            side: AtomicU8::new(1),
+            stem_gain: AtomicU32::new(db_to_linear(stem_gain_db).to_bits()),
+            paused: AtomicBool::new(false),
+            stopped: AtomicBool::new(false),
+            position: AtomicU64::new(0),
+            seek_to: AtomicU64::new(NO_SEEK),
+        }
+    }
+
+    pub fn side(&self) -> Side {
+        if self.side.load(Ordering::Relaxed) == 0 { Side::Mixture } else { Side::Stem }
+    }
+
+    pub fn set_side(&self, side: Side) {
+        self.side.store(if side == Side::Mixture { 0 } else { 1 }, Ordering::Relaxed);
+    }
+
+    pub fn set_stem_gain_db(&self, gain_db: f32) {
+        self.stem_gain.store(db_to_linear(gain_db).to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    pub fn position(&self) -> u64 {
+        self.position.load(Ordering::Relaxed)
+    }
+
+    pub fn seek(&self, frame: u64) {
+        self.seek_to.store(frame, Ordering::Relaxed);
+    }
+
+    fn stem_gain(&self) -> f32 {
+        f32::from_bits(self.stem_gain.load(Ordering::Relaxed))
+    }
+}
+
+pub fn db_to_linear(gain_db: f32) -> f32 {
+    10f32.powf(gain_db / 20.0)
+}
+
+/// Interleaved sample stream that plays either the mixture or the stem.
+///
+/// Both decoders live here, on whichever thread pulls samples. Switching
+/// sides or seeking only happens on frame boundaries, and a side switch seeks
+/// the newly active decoder to the current frame so the position carries
+/// over. That relies on `AudioDecoder::seek` being sample-accurate.
+pub struct AbSource {
+    mixture: AudioDecoder,
+    stem: AudioDecoder,
+    controls: Arc<PreviewControls>,
+    active: Side,
+    channels: u16,
+    sample_rate: u32,
+    chunk: Vec<f32>,
+    chunk_pos: usize,
+    frame: u64,
+    finished: bool,
+}
+
+impl AbSource {
+    pub fn new(mixture: AudioDecoder, stem: AudioDecoder, controls: Arc<PreviewControls>) -> Result<Self> {
+        let stem_info = stem.info();
+        let mixture_info = mixture.info();
+
+        if stem_info.sample_rate != mixture_info.sample_rate {
+            bail!(
+                "Sample rates differ (mixture {} Hz, stem {} Hz); A/B needs matching rates",
+                mixture_info.sample_rate,
+                stem_info.sample_rate
+            );
+        }
+        for info in [stem_info, mixture_info] {
+            if info.channels != 1 && info.channels != stem_info.channels {
+                bail!(
+                    "Channel layouts differ (mixture {}, stem {})",
+                    mixture_info.channels,
+                    stem_info.channels
+                );
+            }
+        }
+
+        let active = controls.side();
+        Ok(AbSource {
+            mixture,
+            stem,
+            active,
+            channels: stem_info.channels,
+            sample_rate: stem_info.sample_rate,
+            controls,
+            chunk: Vec::new(),
+            chunk_pos: 0,
+            frame: 0,
+            finished: false,
+        })
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn decoder(&mut self) -> &mut AudioDecoder {
+        match self.active {
+            Side::Mixture => &mut self.mixture,
+            Side::Stem => &mut self.stem,
+        }
+    }
+
+    fn reposition(&mut self, frame: u64) {
+        self.chunk.clear();
+        self.chunk_pos = 0;
+        self.frame = frame;
+        self.finished = self.decoder().seek(frame).is_err();
+        self.controls.position.store(frame, Ordering::Relaxed);
+    }
+
+    fn refill(&mut self) -> bool {
+        let out_channels = self.channels as usize;
+        let decoder = self.decoder();
+        let in_channels = decoder.info().channels as usize;
+
+        let chunk = match decoder.next_chunk() {
+            Ok(Some(chunk)) if !chunk.is_empty() => chunk,
+            _ => return false,
+        };
+
+        let mut samples = Vec::with_capacity(chunk.len() / in_channels * out_channels);
+        if in_channels == out_channels {
+            samples.extend_from_slice(chunk);
+        } else {
+            for value in chunk {
+                samples.extend(std::iter::repeat_n(*value, out_channels));
+            }
+        }
+
+        self.chunk = samples;
+        self.chunk_pos = 0;
+        true
+    }
+}
+
+impl Iterator for AbSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.controls.stopped.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        let at_frame_start = self.chunk_pos.is_multiple_of(self.channels as usize);
+        if at_frame_start {
+            let seek = self.controls.seek_to.swap(NO_SEEK, Ordering::Relaxed);
+            if seek != NO_SEEK {
+                self.reposition(seek);
+            }
+
+            let side = self.controls.side();
+            if side != self.active {
+                self.active = side;
+                self.reposition(self.frame);
+            }
+
+            if self.controls.is_paused() {
+                return Some(0.0);
+            }
+        }
+
+        if self.chunk_pos >= self.chunk.len() && (self.finished || !self.refill()) {
+            self.finished = true;
+            // Keep the stream alive after the end so the user can still seek
+            // back or switch sides.
+            return Some(0.0);
+        }
+
+        let gain = match self.active {
+            Side::Mixture => 1.0,
+            Side::Stem => self.controls.stem_gain(),
+        };
+        let value = self.chunk[self.chunk_pos] * gain;
+        self.chunk_pos += 1;
+        if self.chunk_pos.is_multiple_of(self.channels as usize) {
+            self.frame += 1;
+            self.controls.position.store(self.frame, Ordering::Relaxed);
+        }
+        Some(value)
+    }
+}
+
+#[cfg(feature = "playback")]
+impl rodio::Source for AbSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<std::time::Duration> {
+        None
+    }
+}
+
+/// A/B preview of one separated stem against the mixture it came from.
+pub struct PreviewPlayer {
+    pub stem_name: String,
+    pub stem_path: PathBuf,
+    pub mixture_path: PathBuf,
+    /// What the A and B sides are called on screen.
+    pub labels: [String; 2],
+    pub sample_rate: u32,
+    pub total_frames: Option<u64>,
+    controls: Arc<PreviewControls>,
+}
+
+impl PreviewPlayer {
+    /// Looks up the stem's original input in the store_dir's results
+    /// manifest and starts playback of the stem.
+    pub fn open(store_dir: &Path, stem_path: &Path, stem_gain_db: f32) -> Result<Self> {
+        let manifest = ResultsManifest::load(store_dir)?
+            .ok_or_else(|| anyhow!("No results manifest in {}", store_dir.display()))?;
+        let (track, stem) = manifest.find_stem(store_dir, stem_path)
+            .ok_or_else(|| anyhow!("{} is not listed in the results manifest", stem_path.display()))?;
+
+        let mixture_path = PathBuf::from(&track.input_path);
+        let mixture = AudioDecoder::open(&mixture_path)?;
+        let stem_decoder = AudioDecoder::open(stem_path)?;
+        let total_frames = stem_decoder.info().total_frames;
+
+        let controls = Arc::new(PreviewControls::new(stem_gain_db));
+        let source = AbSource::new(mixture, stem_decoder, controls.clone())?;
+        let sample_rate = source.sample_rate();
+        start_playback(source, stop_flag(&controls))?;
+
+        Ok(PreviewPlayer {
+            stem_name: stem.name.clone(),
+            stem_path: stem_path.to_path_buf(),
+            mixture_path,
+            labels: [Side::Mixture.label().to_string(), Side::Stem.label().to_string()],
+            sample_rate,
+            total_frames,
+            controls,
+        })
+    }
+
+    /// A/B between two renderings of the same stem, e.g. from two models in
+    /// a bake-off. `a` plays on the mixture side, `b` on the stem side.
+    pub fn compare(stem_name: &str, a: (&Path, &str), b: (&Path, &str), stem_gain_db: f32) -> Result<Self> {
+        let a_decoder = AudioDecoder::open(a.0)?;
+        let b_decoder = AudioDecoder::open(b.0)?;
+        let total_frames = b_decoder.info().total_frames;
+
+        let controls = Arc::new(PreviewControls::new(stem_gain_db));
+        let source = AbSource::new(a_decoder, b_decoder, controls.clone())?;
+        let sample_rate = source.sample_rate();
+        start_playback(source, stop_flag(&controls))?;
+
+        Ok(PreviewPlayer {
+            stem_name: stem_name.to_string(),
+            stem_path: b.0.to_path_buf(),
+            mixture_path: a.0.to_path_buf(),
+            labels: [format!("A: {}", a.1), format!("B: {}", b.1)],
+            sample_rate,
+            total_frames,
+            controls,
+        })
+    }
+
+    pub fn side_label(&self, side: Side) -> &str {
+        match side {
+            Side::Mixture => &self.labels[0],
+            Side::Stem => &self.labels[1],
+        }
+    }
+
+    pub fn controls(&self) -> &PreviewControls {
+        &self.controls
+    }
+
+    pub fn toggle_side(&self) {
+        let next = match self.controls.side() {
+            Side::Mixture => Side::Stem,
+            Side::Stem => Side::Mixture,
+        };
+        self.controls.set_side(next);
+    }
+
+    pub fn toggle_pause(&self) {
+        self.controls.set_paused(!self.controls.is_paused());
+    }
+
+    pub fn position_secs(&self) -> f64 {
+        self.controls.position() as f64 / self.sample_rate as f64
+    }
+
+    pub fn duration_secs(&self) -> Option<f64> {
+        self.total_frames.map(|frames| frames as f64 / self.sample_rate as f64)
+    }
+
+    pub fn seek_by(&self, delta_secs: f64) {
+        let target = (self.position_secs() + delta_secs).max(0.0);
+        self.controls.seek((target * self.sample_rate as f64) as u64);
+    }
+}
+
+fn stop_flag(controls: &Arc<PreviewControls>) -> impl Fn() -> bool + Send + 'static {
+    let controls = controls.clone();
+    move || controls.stopped.load(Ordering::Relaxed)
+}
+
+impl Drop for PreviewPlayer {
+    fn drop(&mut self) {
+        self.controls.stop();
+    }
+}
+
+/// Plays `source` on its own thread until it runs dry or `stopped` says so.
+#[cfg(feature = "playback")]
+pub fn start_playback<S>(source: S, stopped: impl Fn() -> bool + Send + 'static) -> Result<()>
+where
+    S: rodio::Source<Item = f32> + Send + 'static,
+{
+    use anyhow::Context;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let (ready_tx, ready_rx) = mpsc::channel();
+
+    // The output stream is not Send, so it lives and dies on its own thread.
+    std::thread::spawn(move || {
+        let (_stream, handle) = match rodio::OutputStream::try_default() {
+            Ok(output) => output,
+            Err(e) => {
+                let _ = ready_tx.send(Err(anyhow!("No audio output device: {}", e)));
+                return;
+            }
+        };
+        let sink = match rodio::Sink::try_new(&handle) {
+            Ok(sink) => sink,
+            Err(e) => {
+                let _ = ready_tx.send(Err(anyhow!("Failed to open audio sink: {}", e)));
+                return;
+            }
+        };
+        sink.append(source);
+        let _ = ready_tx.send(Ok(()));
+
+        while !stopped() && !sink.empty() {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        sink.stop();
+    });
+
+    ready_rx.recv()
+        .context("Audio thread exited before starting playback")?
+}
+
+#[cfg(not(feature = "playback"))]
+pub fn start_playback<S>(_source: S, _stopped: impl Fn() -> bool + Send + 'static) -> Result<()> {
+    bail!("Audio preview is not available: rebuild with `--features playback`")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_wav(path: &Path, channels: u16, value: i16, frames: u32) {
+        let spec = hound::WavSpec {
+            channels,
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for frame in 0..frames {
+            for _ in 0..channels {
+                writer.write_sample(value.saturating_add((frame % 1000) as i16)).unwrap();
+            }
+        }
+        writer.finalize().unwrap();
+    }
+
+    fn frame_value(source: &mut AbSource) -> i32 {
+        let left = source.next().unwrap();
+        let _right = source.next().unwrap();
+        (left * 32768.0).round() as i32
+    }
+
+    #[test]
+    fn switching_sides_keeps_position() {
+        let dir = tempfile::tempdir().unwrap();
+        let mixture = dir.path().join("mix.wav");
+        let stem = dir.path().join("vocals.wav");
+        write_wav(&mixture, 2, 10_000, 5000);
+        write_wav(&stem, 2, 20_000, 5000);
+
+        let controls = Arc::new(PreviewControls::new(0.0));
+        let mut source = AbSource::new(
+            AudioDecoder::open(&mixture).unwrap(),
+            AudioDecoder::open(&stem).unwrap(),
+            controls.clone(),
+        ).unwrap();
+
+        for _ in 0..1234 {
+            frame_value(&mut source);
+        }
+        assert_eq!(controls.position(), 1234);
+
+        controls.set_side(Side::Mixture);
+        assert_eq!(frame_value(&mut source), 10_000 + 234);
+        controls.set_side(Side::Stem);
+        assert_eq!(frame_value(&mut source), 20_000 + 235);
+
+        controls.seek(4321);
+        assert_eq!(frame_value(&mut source), 20_000 + 321);
+        assert_eq!(controls.position(), 4322);
+    }
+
+    #[test]
+    fn stem_gain_applies_only_to_stem() {
+        let dir = tempfile::tempdir().unwrap();
+        let mixture = dir.path().join("mix.wav");
+        let stem = dir.path().join("vocals.wav");
+        write_wav(&mixture, 1, 8000, 100);
+        write_wav(&stem, 2, 8000, 100);
+
+        let controls = Arc::new(PreviewControls::new(-6.0206));
+        let mut source = AbSource::new(
+            AudioDecoder::open(&mixture).unwrap(),
+            AudioDecoder::open(&stem).unwrap(),
+            controls.clone(),
+        ).unwrap();
+
+        assert_eq!(frame_value(&mut source), 4000);
+        controls.set_side(Side::Mixture);
+        assert_eq!(frame_value(&mut source), 8001);
+    }
+}
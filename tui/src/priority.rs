@@ -0,0 +1,239 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::model::CommandSpec;
+
+/// I/O scheduling class for `ionice`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IoClass {
+    /// Disk time only when nothing else wants it.
+    Idle,
+    /// The normal class, with a level from 0 (first) to 7 (last).
+    BestEffort(u8),
+}
+
+/// How hard the python process may lean on the machine, so the TUI and an
+/// SSH session stay usable while it saturates every core. Applied by
+/// prefixing the command with `nice`, `ionice` and `taskset`, so it shows up
+/// in the command preview and in `run.json`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProcessPriority {
+    /// Niceness, -20 (greediest) to 19 (most polite). Below 0 needs root.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nice: Option<i32>,
+    /// Linux only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub io_class: Option<IoClass>,
+    /// CPU cores the process may run on; empty for all of them. Linux only.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cpu_cores: Vec<usize>,
+}
+
+/// Which of the prefix tools this machine has.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PriorityTools {
+    pub nice: bool,
+    pub ionice: bool,
+    pub taskset: bool,
+}
+
+impl PriorityTools {
+    /// Looks the tools up on PATH. `ionice` and `taskset` are Linux tools
+    /// (util-linux); other systems are assumed not to have them.
+    pub fn detect() -> Self {
+        let linux = cfg!(target_os = "linux");
+        PriorityTools {
+            nice: cfg!(unix) && on_path("nice"),
+            ionice: linux && on_path("ionice"),
+            taskset: linux && on_path("taskset"),
+        }
+    }
+}
+
+impl ProcessPriority {
+    pub fn is_default(&self) -> bool {
+        *self == ProcessPriority::default()
+    }
+
+    /// `command` prefixed with the tools for every setting `tools` can
+    /// apply. Settings whose tool is missing are dropped without a word:
+    /// the run goes ahead at normal priority rather than not at all.
+    pub fn apply(&self, command: CommandSpec, tools: PriorityTools) -> CommandSpec {
+        let mut prefix: Vec<String> = vec![];
+        if let Some(nice) = self.nice
+            && tools.nice
+        {
+            prefix.extend(["nice".into(), "-n".into(), nice.clamp(-20, 19).to_string()]);
+        }
+        if let Some(class) = self.io_class
+            && tools.ionice
+        {
+            prefix.extend(["ionice".into(), "-c".into()]);
+            match class {
+                IoClass::Idle => prefix.push("3".into()),
+                IoClass::BestEffort(level) => prefix.extend(["2".into(), "-n".into(), level.min(7).to_string()]),
+            }
+        }
+        if !self.cpu_cores.is_empty() && tools.taskset {
+            prefix.extend(["taskset".into(), "-c".into(), core_list(&self.cpu_cores)]);
+        }
+        if prefix.is_empty() {
+            return command;
+        }
+
+        let mut wrapped = CommandSpec::new(&prefix[0]);
+        for arg in prefix[1..].iter().chain([&command.program]).chain(&command.args) {
+            wrapped.arg(arg);
+        }
+        wrapped.env = command.env;
+        wrapped.current_dir = command.current_dir;
+        wrapped
+    }
+
+    /// Undoes `apply`: the priority a command's `nice`, `ionice` and
+    /// `taskset` prefix sets, and the command without it.
+    pub fn strip(command: &CommandSpec) -> (Self, CommandSpec) {
+        let words: Vec<&str> = [command.program.as_str()].into_iter()
+            .chain(command.args.iter().map(String::as_str))
+            .collect();
+        let mut priority = ProcessPriority::default();
+        let mut rest = words.as_slice();
+        loop {
+            match rest {
+                ["nice", "-n", level, tail @ ..] if level.parse::<i32>().is_ok() => {
+                    priority.nice = level.parse().ok();
+                    rest = tail;
+                }
+                ["ionice", "-c", "3", tail @ ..] => {
+                    priority.io_class = Some(IoClass::Idle);
+                    rest = tail;
+                }
+                ["ionice", "-c", "2", "-n", level, tail @ ..] if level.parse::<u8>().is_ok() => {
+                    priority.io_class = level.parse().ok().map(IoClass::BestEffort);
+                    rest = tail;
+                }
+                ["taskset", "-c", list, tail @ ..] if parse_core_list(list).is_some() => {
+                    priority.cpu_cores = parse_core_list(list).unwrap_or_default();
+                    rest = tail;
+                }
+                _ => break,
+            }
+        }
+        let Some((program, args)) = rest.split_first() else {
+            return (ProcessPriority::default(), command.clone());
+        };
+        let mut stripped = CommandSpec::new(program);
+        for arg in args {
+            stripped.arg(arg);
+        }
+        stripped.env = command.env.clone();
+        (priority, stripped)
+    }
+
+    /// E.g. "nice 10, I/O idle, cores 0-3".
+    pub fn summary(&self) -> String {
+        let mut parts = vec![];
+        if let Some(nice) = self.nice {
+            parts.push(format!("nice {}", nice.clamp(-20, 19)));
+        }
+        match self.io_class {
+            Some(IoClass::Idle) => parts.push("I/O idle".to_string()),
+            Some(IoClass::BestEffort(level)) => parts.push(format!("I/O best-effort {}", level.min(7))),
+            None => {}
+        }
+        if !self.cpu_cores.is_empty() {
+            parts.push(format!("cores {}", core_list(&self.cpu_cores)));
+        }
+        if parts.is_empty() {
+            "normal priority".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+/// taskset's list syntax, with runs collapsed: `[0, 1, 2, 3, 6]` is "0-3,6".
+fn core_list(cores: &[usize]) -> String {
+    let mut cores = cores.to_vec();
+    cores.sort_unstable();
+    cores.dedup();
+    let mut ranges: Vec<(usize, usize)> = vec![];
+    for core in cores {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == core => *end = core,
+            _ => ranges.push((core, core)),
+        }
+    }
+    ranges.iter()
+        .map(|&(start, end)| if start == end { start.to_string() } else { format!("{}-{}", start, end) })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Reads `core_list`'s syntax back; `None` if it isn't one.
+fn parse_core_list(list: &str) -> Option<Vec<usize>> {
+    let mut cores = vec![];
+    for part in list.split(',') {
+        match part.split_once('-') {
+            Some((start, end)) => cores.extend(start.parse::<usize>().ok()?..=end.parse().ok()?),
+            None => cores.push(part.parse().ok()?),
+        }
+    }
+    Some(cores)
+}
+
+fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| Path::new(&dir).join(program).is_file()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn train_py() -> CommandSpec {
+        let mut command = CommandSpec::new("python");
+        command.arg("train.py").arg("--model_type").arg("scnet").env("CUDA_VISIBLE_DEVICES", "0");
+        command
+    }
+
+    #[test]
+    fn prefixes_only_what_the_platform_has() {
+        let priority = ProcessPriority {
+            nice: Some(10),
+            io_class: Some(IoClass::Idle),
+            cpu_cores: vec![3, 0, 1, 2, 6],
+        };
+        let linux = PriorityTools { nice: true, ionice: true, taskset: true };
+        let wrapped = priority.apply(train_py(), linux);
+        assert_eq!(
+            wrapped.display(),
+            "CUDA_VISIBLE_DEVICES=0 nice -n 10 ionice -c 3 taskset -c 0-3,6 python train.py --model_type scnet"
+        );
+
+        let (stripped, command) = ProcessPriority::strip(&wrapped);
+        assert_eq!(stripped, ProcessPriority { cpu_cores: vec![0, 1, 2, 3, 6], ..priority.clone() });
+        assert_eq!(command, train_py());
+        assert_eq!(ProcessPriority::strip(&train_py()), (ProcessPriority::default(), train_py()));
+
+        let macos = PriorityTools { nice: true, ..Default::default() };
+        assert_eq!(priority.apply(train_py(), macos).display(), "CUDA_VISIBLE_DEVICES=0 nice -n 10 python train.py --model_type scnet");
+        assert_eq!(priority.apply(train_py(), PriorityTools::default()), train_py());
+        assert_eq!(ProcessPriority::default().apply(train_py(), linux), train_py());
+    }
+
+    #[test]
+    fn out_of_range_levels_are_clamped() {
+        let priority = ProcessPriority {
+            nice: Some(40),
+            io_class: Some(IoClass::BestEffort(9)),
+            cpu_cores: vec![],
+        };
+        let tools = PriorityTools { nice: true, ionice: true, taskset: true };
+        assert_eq!(priority.apply(train_py(), tools).args[..6], ["-n", "19", "ionice", "-c", "2", "-n"]);
+        assert_eq!(priority.apply(train_py(), tools).args[6], "7");
+        assert_eq!(priority.summary(), "nice 19, I/O best-effort 7");
+        assert_eq!(ProcessPriority::default().summary(), "normal priority");
+    }
+}
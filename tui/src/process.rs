@@ -0,0 +1,158 @@
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::process::ExitStatus;
+use tokio::process::Child;
+
+/// A manager's subprocess as last seen: the exit status is kept once
+/// `try_wait` reports it, so a run that ended doesn't look like it still
+/// goes on.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProcessState {
+    NotStarted,
+    Running,
+    Exited(ExitStatus),
+    /// It couldn't be started or waited for.
+    Failed(String),
+    /// Left running when the TUI quit; its stdout goes on into `log`, next
+    /// to the stderr log.
+    Detached { pid: Option<u32>, log: PathBuf },
+}
+
+impl ProcessState {
+    /// Checks on `child` without blocking. Only a running state changes.
+    pub fn update(&mut self, child: Option<&mut Child>) {
+        let (ProcessState::Running, Some(child)) = (&*self, child) else {
+            return;
+        };
+        match child.try_wait() {
+            Ok(Some(status)) => *self = ProcessState::Exited(status),
+            Ok(None) => {}
+            Err(e) => *self = ProcessState::Failed(format!("Failed to check on the process: {}", e)),
+        }
+    }
+
+    /// E.g. "running", "finished (exit 0)", "crashed (exit 1)".
+    pub fn describe(&self) -> String {
+        match self {
+            ProcessState::NotStarted => "not started".to_string(),
+            ProcessState::Running => "running".to_string(),
+            ProcessState::Exited(status) if status.success() => "finished (exit 0)".to_string(),
+            ProcessState::Exited(status) => match status.code() {
+                Some(code) => format!("crashed (exit {})", code),
+                None => killed(status),
+            },
+            ProcessState::Failed(error) => format!("failed: {}", error),
+            ProcessState::Detached { pid, log } => match pid {
+                Some(pid) => format!("running in the background (pid {}), logging to {}", pid, log.display()),
+                None => format!("running in the background, logging to {}", log.display()),
+            },
+        }
+    }
+}
+
+/// Lines of stderr a failed run reports.
+pub const STDERR_TAIL_LINES: usize = 200;
+
+/// The last `STDERR_TAIL_LINES` lines a process wrote to stderr, kept for
+/// reporting why it failed.
+#[derive(Debug, Clone, Default)]
+pub struct StderrTail {
+    lines: VecDeque<String>,
+}
+
+impl StderrTail {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, line: String) {
+        if self.lines.len() == STDERR_TAIL_LINES {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn text(&self) -> String {
+        self.lines.iter().map(String::as_str).collect::<Vec<_>>().join("\n")
+    }
+
+    /// The line a Python traceback ends with, e.g. "ValueError: bad
+    /// sample rate": the first unindented line after the last
+    /// "Traceback" header. None without a traceback.
+    pub fn exception(&self) -> Option<String> {
+        let header = self.lines.iter().rposition(|line| line.starts_with("Traceback (most recent call last)"))?;
+        self.lines.iter()
+            .skip(header + 1)
+            .find(|line| !line.trim().is_empty() && !line.starts_with(char::is_whitespace))
+            .map(|line| line.trim_end().to_string())
+    }
+}
+
+#[cfg(unix)]
+fn killed(status: &ExitStatus) -> String {
+    use std::os::unix::process::ExitStatusExt;
+    match status.signal() {
+        Some(signal) => format!("killed (signal {})", signal),
+        None => "killed".to_string(),
+    }
+}
+
+#[cfg(not(unix))]
+fn killed(_status: &ExitStatus) -> String {
+    "killed".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn exit_is_seen_and_kept() {
+        let mut state = ProcessState::NotStarted;
+        let mut child = tokio::process::Command::new("sh").args(["-c", "sleep 0.2; exit 3"]).spawn().unwrap();
+        state.update(Some(&mut child));
+        assert_eq!(state, ProcessState::NotStarted, "only a running state is checked");
+
+        state = ProcessState::Running;
+        state.update(Some(&mut child));
+        assert_eq!(state.describe(), "running");
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        state.update(Some(&mut child));
+        assert_eq!(state.describe(), "crashed (exit 3)");
+        state.update(None);
+        assert_eq!(state.describe(), "crashed (exit 3)");
+
+        let mut state = ProcessState::Running;
+        let mut child = tokio::process::Command::new("true").spawn().unwrap();
+        child.wait().await.unwrap();
+        state.update(Some(&mut child));
+        assert_eq!(state.describe(), "finished (exit 0)");
+
+        let mut state = ProcessState::Detached { pid: Some(42), log: PathBuf::from("results/run/logs/stdout.log") };
+        state.update(Some(&mut child));
+        assert_eq!(state.describe(), "running in the background (pid 42), logging to results/run/logs/stdout.log");
+    }
+
+    #[test]
+    fn a_python_traceback_ends_in_its_exception() {
+        let script = "def load():\n    raise ValueError('bad sample rate: 22050')\ntry:\n    load()\nexcept ValueError as e:\n    raise RuntimeError('model failed\\nsee above') from e\n";
+        let output = std::process::Command::new("python3").args(["-c", script]).output().unwrap();
+        assert!(!output.status.success());
+        let mut tail = StderrTail::new();
+        for _ in 0..STDERR_TAIL_LINES {
+            tail.push("  0%|          | 0/10 [00:00<?, ?it/s]".to_string());
+        }
+        assert_eq!(tail.exception(), None);
+        for line in String::from_utf8_lossy(&output.stderr).lines() {
+            tail.push(line.to_string());
+        }
+        assert_eq!(tail.exception().as_deref(), Some("RuntimeError: model failed"));
+        assert_eq!(tail.text().lines().count(), STDERR_TAIL_LINES);
+        assert!(tail.text().contains("ValueError: bad sample rate: 22050"));
+    }
+}
@@ -0,0 +1,328 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// Training entry points looked for in a checkout, in order: upstream MSST,
+/// then the accelerate variant some forks ship instead.
+pub const TRAIN_SCRIPTS: [&str; 2] = ["train.py", "train_accelerate.py"];
+pub const INFERENCE_SCRIPTS: [&str; 1] = ["inference.py"];
+/// Not needed to recognize a checkout: without one, validation fails when
+/// it is run rather than the whole app at startup.
+pub const VALIDATION_SCRIPTS: [&str; 1] = ["valid.py"];
+
+/// Environment variable naming the project root, checked after
+/// `--project-root`.
+pub const ROOT_ENV: &str = "MSST_PROJECT_ROOT";
+
+/// Where the project settings are kept. They are needed before the project
+/// root is known, so they live in the user's config folder rather than in
+/// the project like `tui_config.yaml`.
+pub fn settings_path() -> PathBuf {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_default();
+    config_dir.join("msst-tui").join("project.yaml")
+}
+
+/// The scripts of the MSST checkout, relative to its root.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scripts {
+    pub train: String,
+    pub inference: String,
+    pub validation: String,
+}
+
+impl Default for Scripts {
+    fn default() -> Self {
+        Scripts {
+            train: TRAIN_SCRIPTS[0].to_string(),
+            inference: INFERENCE_SCRIPTS[0].to_string(),
+            validation: VALIDATION_SCRIPTS[0].to_string(),
+        }
+    }
+}
+
+/// How to find the MSST checkout: a root given by hand, and the entry-point
+/// names to accept, each checked in order. Names may be paths into a
+/// package, e.g. `msst/train.py`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectSettings {
+    #[serde(default)]
+    pub root: Option<PathBuf>,
+    #[serde(default = "default_train_scripts")]
+    pub train_scripts: Vec<String>,
+    #[serde(default = "default_inference_scripts")]
+    pub inference_scripts: Vec<String>,
+    #[serde(default = "default_validation_scripts")]
+    pub validation_scripts: Vec<String>,
+}
+
+fn default_train_scripts() -> Vec<String> {
+    TRAIN_SCRIPTS.iter().map(|name| name.to_string()).collect()
+}
+
+fn default_inference_scripts() -> Vec<String> {
+    INFERENCE_SCRIPTS.iter().map(|name| name.to_string()).collect()
+}
+
+fn default_validation_scripts() -> Vec<String> {
+    VALIDATION_SCRIPTS.iter().map(|name| name.to_string()).collect()
+}
+
+impl Default for ProjectSettings {
+    fn default() -> Self {
+        ProjectSettings {
+            root: None,
+            train_scripts: default_train_scripts(),
+            inference_scripts: default_inference_scripts(),
+            validation_scripts: default_validation_scripts(),
+        }
+    }
+}
+
+/// The first of `candidates` that is a file in `dir`.
+pub fn resolve(dir: &Path, candidates: &[String]) -> Option<String> {
+    candidates.iter().find(|name| dir.join(name).is_file()).cloned()
+}
+
+impl ProjectSettings {
+    /// Missing settings are the defaults.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            return Ok(ProjectSettings::default());
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read project settings {}", path.display()))?;
+        serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse project settings {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create project settings directory")?;
+        }
+        let content = serde_yaml::to_string(self).context("Failed to serialize project settings")?;
+        fs::write(path, content).with_context(|| format!("Failed to write project settings {}", path.display()))
+    }
+
+    /// The scripts `dir` has, if it has both a training and an inference one.
+    pub fn scripts_in(&self, dir: &Path) -> Option<Scripts> {
+        Some(Scripts {
+            train: resolve(dir, &self.train_scripts)?,
+            inference: resolve(dir, &self.inference_scripts)?,
+            validation: self.validation_script(dir),
+        })
+    }
+
+    /// The validation script `dir` has, or the first candidate if none.
+    fn validation_script(&self, dir: &Path) -> String {
+        resolve(dir, &self.validation_scripts)
+            .or_else(|| self.validation_scripts.first().cloned())
+            .unwrap_or_else(|| VALIDATION_SCRIPTS[0].to_string())
+    }
+
+    /// A root given by `--project-root` or `source`, which has to be a
+    /// checkout: a wrong one is an error rather than a reason to search.
+    pub fn given_root(&self, root: &Path, source: &str) -> Result<(PathBuf, Scripts)> {
+        if !root.is_dir() {
+            bail!("{} {} is not a folder", source, root.display());
+        }
+        let root = std::path::absolute(root).unwrap_or_else(|_| root.to_path_buf());
+        match self.scripts_in(&root) {
+            Some(scripts) => Ok((root, scripts)),
+            None => bail!(
+                "{} {} has no {} or no {}",
+                source,
+                root.display(),
+                self.train_scripts.join(" or "),
+                self.inference_scripts.join(" or ")
+            ),
+        }
+    }
+
+    /// The configured root if it still has the scripts, otherwise the
+    /// nearest folder at or above one of `starts`, tried in order, that
+    /// does. Without one, the folders that were looked in.
+    pub fn find_root(&self, starts: &[&Path]) -> Result<(PathBuf, Scripts), Vec<PathBuf>> {
+        let mut examined: Vec<PathBuf> = vec![];
+        if let Some(root) = &self.root {
+            match self.scripts_in(root) {
+                Some(scripts) => return Ok((root.clone(), scripts)),
+                None => examined.push(root.clone()),
+            }
+        }
+        for dir in starts.iter().flat_map(|start| start.ancestors()) {
+            if examined.iter().any(|seen| seen == dir) {
+                continue;
+            }
+            if let Some(scripts) = self.scripts_in(dir) {
+                return Ok((dir.to_path_buf(), scripts));
+            }
+            examined.push(dir.to_path_buf());
+        }
+        Err(examined)
+    }
+
+    /// Keeps `root` and puts `scripts` first in line for next time.
+    pub fn remember(&mut self, root: &Path, scripts: &Scripts) {
+        self.root = Some(root.to_path_buf());
+        for (candidates, name) in [
+            (&mut self.train_scripts, &scripts.train),
+            (&mut self.inference_scripts, &scripts.inference),
+            (&mut self.validation_scripts, &scripts.validation),
+        ] {
+            candidates.retain(|candidate| candidate != name);
+            candidates.insert(0, name.clone());
+        }
+    }
+
+    /// Asks on the terminal for the project root and script names when
+    /// none was found in `examined`, before the TUI takes over the screen.
+    /// Each answer is checked before moving on, and an empty answer takes
+    /// the offered default. The answers are remembered in `self`.
+    pub fn recover(&mut self, examined: &[PathBuf], input: &mut impl BufRead, output: &mut impl Write) -> Result<(PathBuf, Scripts)> {
+        writeln!(
+            output,
+            "No MSST checkout found (looked for {} and {}) in:",
+            self.train_scripts.join(" or "),
+            self.inference_scripts.join(" or ")
+        )?;
+        for dir in examined {
+            writeln!(output, "  {}", dir.display())?;
+        }
+        writeln!(output, "Pass --project-root <path> or set {} to skip this question.", ROOT_ENV)?;
+        let root = loop {
+            let answer = ask(input, output, "Path to the MSST checkout", None)?;
+            let root = PathBuf::from(&answer);
+            if root.is_dir() {
+                break std::path::absolute(&root).unwrap_or(root);
+            }
+            writeln!(output, "{} is not a folder.", answer)?;
+        };
+        let mut script = |what: &str, candidates: &[String]| -> Result<String> {
+            let found = resolve(&root, candidates);
+            loop {
+                let name = ask(input, output, &format!("{} script", what), found.as_deref())?;
+                if root.join(&name).is_file() {
+                    return Ok(name);
+                }
+                writeln!(output, "{} is not in {}.", name, root.display())?;
+            }
+        };
+        let scripts = Scripts {
+            train: script("Training", &self.train_scripts)?,
+            inference: script("Inference", &self.inference_scripts)?,
+            validation: self.validation_script(&root),
+        };
+        self.remember(&root, &scripts);
+        Ok((root, scripts))
+    }
+}
+
+fn ask(input: &mut impl BufRead, output: &mut impl Write, question: &str, default: Option<&str>) -> Result<String> {
+    loop {
+        match default {
+            Some(default) => write!(output, "{} [{}]: ", question, default)?,
+            None => write!(output, "{}: ", question)?,
+        }
+        output.flush()?;
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            bail!("No answer given for \"{}\"", question);
+        }
+        match (answer.trim(), default) {
+            ("", Some(default)) => return Ok(default.to_string()),
+            ("", None) => continue,
+            (answer, _) => return Ok(answer.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkout(files: &[&str]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for file in files {
+            let path = dir.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn scripts_are_resolved_in_order_and_the_root_found_above() {
+        let settings = ProjectSettings::default();
+        let upstream = checkout(&["train.py", "train_accelerate.py", "inference.py", "tui/target/debug/.keep"]);
+        let start = upstream.path().join("tui/target/debug");
+        assert_eq!(settings.find_root(&[&start]), Ok((upstream.path().to_path_buf(), Scripts::default())));
+
+        let fork = checkout(&["train_accelerate.py", "inference.py"]);
+        let (_, scripts) = settings.find_root(&[fork.path()]).unwrap();
+        assert_eq!(scripts.train, "train_accelerate.py");
+
+        let package = checkout(&["msst/train.py", "msst/infer.py", "msst/valid.py"]);
+        let examined = settings.find_root(&[package.path()]).unwrap_err();
+        assert_eq!(examined.first().map(PathBuf::as_path), Some(package.path()));
+        let mut remembered = ProjectSettings::default();
+        let package_scripts = Scripts { train: "msst/train.py".into(), inference: "msst/infer.py".into(), validation: "msst/valid.py".into() };
+        remembered.remember(package.path(), &package_scripts);
+        assert_eq!(remembered.train_scripts, ["msst/train.py", "train.py", "train_accelerate.py"]);
+        assert_eq!(remembered.validation_scripts, ["msst/valid.py", "valid.py"]);
+        // The configured root wins over the folders above the executable.
+        let (root, scripts) = remembered.find_root(&[&start]).unwrap();
+        assert_eq!((root.as_path(), scripts), (package.path(), package_scripts));
+    }
+
+    #[test]
+    fn a_given_root_must_be_a_checkout_and_the_search_falls_back_to_the_working_folder() {
+        let settings = ProjectSettings::default();
+        let checkout_dir = checkout(&["train.py", "inference.py", "data/songs/.keep"]);
+        let (root, _) = settings.given_root(checkout_dir.path(), "--project-root").unwrap();
+        assert_eq!(root, checkout_dir.path());
+        let error = settings.given_root(&checkout_dir.path().join("data"), ROOT_ENV).unwrap_err().to_string();
+        assert!(error.starts_with("MSST_PROJECT_ROOT ") && error.ends_with("has no train.py or train_accelerate.py or no inference.py"), "{}", error);
+        assert!(settings.given_root(Path::new("/no/such/folder"), "--project-root").is_err());
+
+        // Installed into ~/.cargo/bin, run from inside the checkout.
+        let cargo_bin = tempfile::tempdir().unwrap();
+        let cwd = checkout_dir.path().join("data/songs");
+        assert_eq!(settings.find_root(&[cargo_bin.path(), &cwd]).unwrap().0, checkout_dir.path());
+
+        // Folders both searches pass through are listed once.
+        let elsewhere = tempfile::tempdir().unwrap();
+        let examined = settings.find_root(&[cargo_bin.path(), elsewhere.path()]).unwrap_err();
+        assert_eq!(examined.iter().filter(|dir| dir.as_path() == Path::new("/")).count(), 1);
+        assert!(examined.iter().any(|dir| dir == elsewhere.path()));
+    }
+
+    #[test]
+    fn recovery_asks_until_the_answers_check_out() {
+        let package = checkout(&["msst/train.py", "msst/infer.py", "train_accelerate.py"]);
+        let answers = format!("/no/such/folder\n{}\n\nmissing.py\nmsst/infer.py\n", package.path().display());
+        let mut output = vec![];
+        let mut settings = ProjectSettings::default();
+        let (root, scripts) = settings.recover(&[PathBuf::from("/opt/bin")], &mut answers.as_bytes(), &mut output).unwrap();
+
+        assert_eq!(root, package.path());
+        // No valid.py in the checkout: the default, for an error when it is run.
+        assert_eq!(scripts, Scripts { train: "train_accelerate.py".into(), inference: "msst/infer.py".into(), validation: "valid.py".into() });
+        assert_eq!(settings.root.as_deref(), Some(package.path()));
+        assert_eq!(settings.inference_scripts[0], "msst/infer.py");
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("\n  /opt/bin\n"));
+        assert!(output.contains("--project-root <path>"));
+        assert!(output.contains("/no/such/folder is not a folder."));
+        assert!(output.contains("Training script [train_accelerate.py]: "));
+        assert!(output.contains("missing.py is not in"));
+
+        let path = package.path().join("settings/project.yaml");
+        settings.save(&path).unwrap();
+        assert_eq!(ProjectSettings::load(&path).unwrap(), settings);
+    }
+}
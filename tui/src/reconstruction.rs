@@ -0,0 +1,259 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::audio;
+use crate::loudness::{LoudnessReport, LOUDNESS_FILE};
+use crate::manifest::{ManifestTrack, ResultsManifest};
+
+pub const RECONSTRUCTION_FILE: &str = "msst_reconstruction.json";
+
+/// Residual above this, relative to the mixture, flags a track.
+pub const DEFAULT_THRESHOLD_DB: f64 = -20.0;
+
+/// A perfect sum would be minus infinity; reported as this instead.
+const FLOOR_DB: f64 = -120.0;
+
+/// Stems this much longer or shorter than the input are called truncated;
+/// inference.py pads and trims by a few samples on its own.
+const LENGTH_TOLERANCE_SECS: f64 = 0.05;
+
+/// Written next to the outputs by a verification pass.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReconstructionReport {
+    pub threshold_db: f64,
+    pub tracks: Vec<TrackReconstruction>,
+}
+
+/// How far the sum of one track's stems is from its input.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrackReconstruction {
+    pub track: String,
+    /// Energy of input minus stem sum, relative to the input's, in dB.
+    /// Lower is better; unset when the track was not compared.
+    pub error_db: Option<f64>,
+    pub flagged: bool,
+    /// Why it was skipped or flagged beyond the error itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl TrackReconstruction {
+    fn skipped(track: &str, note: String) -> Self {
+        TrackReconstruction { track: track.to_string(), error_db: None, flagged: false, note: Some(note) }
+    }
+
+    /// E.g. "-38.2 dB", or "skipped".
+    pub fn error_text(&self) -> String {
+        match self.error_db {
+            Some(error) => format!("{:.1} dB", error),
+            None => "skipped".to_string(),
+        }
+    }
+}
+
+impl ReconstructionReport {
+    pub fn flagged(&self) -> usize {
+        self.tracks.iter().filter(|track| track.flagged).count()
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.tracks.iter().filter(|track| track.error_db.is_none()).count()
+    }
+
+    /// E.g. "12 tracks, 1 above -20 dB, 2 skipped".
+    pub fn summary(&self) -> String {
+        format!(
+            "{} tracks, {} above {:.0} dB, {} skipped",
+            self.tracks.len(),
+            self.flagged(),
+            self.threshold_db,
+            self.skipped()
+        )
+    }
+}
+
+/// Residual energy of `mixture` minus the sum of `stems`, relative to the
+/// mixture's energy, in dB. The sum is taken over the mixture's length: a
+/// stem that ends early adds nothing after its end, one that runs long is
+/// cut. Mono stems count towards every channel of the mixture. `None` for
+/// a silent mixture.
+pub fn residual_db(mixture: &[Vec<f32>], stems: &[Vec<Vec<f32>>]) -> Option<f64> {
+    let mut signal = 0.0;
+    let mut residual = 0.0;
+    for (channel, samples) in mixture.iter().enumerate() {
+        for (frame, sample) in samples.iter().enumerate() {
+            let sum: f64 = stems.iter()
+                .filter(|stem| !stem.is_empty())
+                .filter_map(|stem| stem[channel % stem.len()].get(frame))
+                .map(|sample| *sample as f64)
+                .sum();
+            let sample = *sample as f64;
+            signal += sample * sample;
+            residual += (sample - sum) * (sample - sum);
+        }
+    }
+    if signal == 0.0 {
+        return None;
+    }
+    Some((10.0 * (residual / signal).log10()).max(FLOOR_DB))
+}
+
+/// Sums every track's stems back and compares them with its input, then
+/// records the result in `RECONSTRUCTION_FILE`. Restoration models are
+/// skipped: their output isn't meant to add up to the input. Gains applied
+/// by loudness matching are taken off the stems first.
+pub fn verify(store_dir: &Path, manifest: &ResultsManifest, threshold_db: f64) -> Result<ReconstructionReport> {
+    let loudness = read_loudness(store_dir)?;
+    let mut report = ReconstructionReport { threshold_db, tracks: vec![] };
+    for track in &manifest.tracks {
+        let result = match &manifest.model_type {
+            Some(model_type) if model_type.is_restoration() => TrackReconstruction::skipped(
+                &track.name,
+                format!("{} restores rather than separates; its output doesn't sum to the input", model_type.name()),
+            ),
+            _ => verify_track(store_dir, track, loudness.as_ref(), threshold_db)
+                .with_context(|| format!("Failed to verify {}", track.name))?,
+        };
+        report.tracks.push(result);
+    }
+
+    let content = serde_json::to_string_pretty(&report)
+        .context("Failed to serialize reconstruction report")?;
+    fs::write(store_dir.join(RECONSTRUCTION_FILE), content)
+        .context("Failed to write reconstruction report")?;
+    Ok(report)
+}
+
+fn read_loudness(store_dir: &Path) -> Result<Option<LoudnessReport>> {
+    let path = store_dir.join(LOUDNESS_FILE);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path).context("Failed to read loudness report")?;
+    serde_json::from_str(&content).context("Failed to parse loudness report").map(Some)
+}
+
+fn verify_track(
+    store_dir: &Path,
+    track: &ManifestTrack,
+    loudness: Option<&LoudnessReport>,
+    threshold_db: f64,
+) -> Result<TrackReconstruction> {
+    if track.stems.is_empty() {
+        return Ok(TrackReconstruction::skipped(&track.name, "no stems".to_string()));
+    }
+    let (input_info, input) = audio::decode_planar(Path::new(&track.input_path))?;
+    let gains = loudness
+        .and_then(|report| report.tracks.iter().find(|loudness| loudness.track == track.name));
+
+    let mut stems = vec![];
+    let mut notes = vec![];
+    let input_frames = input.first().map_or(0, Vec::len);
+    for stem in &track.stems {
+        let (info, mut planar) = audio::decode_planar(&store_dir.join(&stem.path))?;
+        if info.sample_rate != input_info.sample_rate {
+            return Ok(TrackReconstruction::skipped(&track.name, format!(
+                "{} is at {} Hz, the input at {} Hz; not compared",
+                stem.name, info.sample_rate, input_info.sample_rate
+            )));
+        }
+        let frames = planar.first().map_or(0, Vec::len);
+        let difference = (frames as f64 - input_frames as f64) / input_info.sample_rate as f64;
+        if difference.abs() > LENGTH_TOLERANCE_SECS {
+            let (amount, which) = if difference < 0.0 { (-difference, "shorter") } else { (difference, "longer") };
+            notes.push(format!("{} is {:.2} s {} than the input", stem.name, amount, which));
+        }
+        if let Some(gain) = gains
+            .and_then(|gains| gains.stems.iter().find(|gain| gain.name == stem.name && gain.applied))
+        {
+            let undo = 10f64.powf(-gain.gain_db / 20.0) as f32;
+            planar.iter_mut().flatten().for_each(|sample| *sample *= undo);
+        }
+        stems.push(planar);
+    }
+
+    let truncated = !notes.is_empty();
+    let error_db = residual_db(&input, &stems);
+    if error_db.is_none() {
+        notes.push("silent input".to_string());
+    }
+    let flagged = truncated || error_db.is_some_and(|error| error > threshold_db);
+    Ok(TrackReconstruction {
+        track: track.name.clone(),
+        error_db,
+        flagged,
+        note: (!notes.is_empty()).then(|| notes.join("; ")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::ManifestStem;
+    use crate::model::ModelType;
+
+    fn sine(frames: usize, frequency: f32, amplitude: f32) -> Vec<f32> {
+        (0..frames)
+            .map(|i| (i as f32 * frequency * std::f32::consts::TAU / 44100.0).sin() * amplitude)
+            .collect()
+    }
+
+    #[test]
+    fn residual_matches_the_known_error() {
+        let vocals = sine(44100, 440.0, 0.5);
+        let drums = sine(44100, 97.0, 0.25);
+        let mixture = vec![vocals.iter().zip(&drums).map(|(a, b)| a + b).collect::<Vec<f32>>()];
+
+        // Exact stems: as good as it gets.
+        assert_eq!(residual_db(&mixture, &[vec![vocals.clone()], vec![drums.clone()]]), Some(FLOOR_DB));
+
+        // Drums at 90%: the residual is 10% of the drums.
+        let quiet: Vec<f32> = drums.iter().map(|sample| sample * 0.9).collect();
+        let error = residual_db(&mixture, &[vec![vocals.clone()], vec![quiet]]).unwrap();
+        let expected = 10.0 * ((0.1f64 * 0.25).powi(2) / (0.5f64.powi(2) + 0.25f64.powi(2))).log10();
+        assert!((error - expected).abs() < 0.05, "{} vs {}", error, expected);
+
+        // Missing the drums entirely: their share of the energy.
+        let error = residual_db(&mixture, &[vec![vocals.clone()]]).unwrap();
+        assert!((error - 10.0 * (0.0625f64 / 0.3125).log10()).abs() < 0.05);
+
+        // A mono stem of a stereo mixture counts on both channels.
+        assert_eq!(residual_db(&[vocals.clone(), vocals.clone()], &[vec![vocals]]), Some(FLOOR_DB));
+        assert_eq!(residual_db(&[vec![0.0; 10]], &[]), None);
+    }
+
+    #[test]
+    fn truncated_stems_are_flagged_and_restoration_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("song.wav");
+        let full = vec![sine(44100, 440.0, 0.5)];
+        audio::write_wav(&input, 44100, &full).unwrap();
+        fs::create_dir(dir.path().join("song")).unwrap();
+        audio::write_wav(&dir.path().join("song/vocals.wav"), 44100, &[full[0][..22050].to_vec()]).unwrap();
+        let mut manifest = ResultsManifest {
+            model_type: Some(ModelType::MelBandRoformer),
+            tracks: vec![ManifestTrack {
+                name: "song".into(),
+                input_path: input.to_string_lossy().to_string(),
+                stems: vec![ManifestStem { name: "vocals".into(), path: "song/vocals.wav".into(), renamed_from: None }],
+                adjustments: vec![],
+                content_hash: None,
+            }],
+            ..Default::default()
+        };
+
+        let report = verify(dir.path(), &manifest, DEFAULT_THRESHOLD_DB).unwrap();
+        let track = &report.tracks[0];
+        assert!(track.flagged);
+        assert!((track.error_db.unwrap() + 3.0).abs() < 0.1, "half the energy is missing");
+        assert_eq!(track.note.as_deref(), Some("vocals is 0.50 s shorter than the input"));
+        assert!(dir.path().join(RECONSTRUCTION_FILE).exists());
+
+        manifest.model_type = Some(ModelType::Apollo);
+        let report = verify(dir.path(), &manifest, DEFAULT_THRESHOLD_DB).unwrap();
+        assert_eq!((report.flagged(), report.skipped()), (0, 1));
+        assert_eq!(report.summary(), "1 tracks, 0 above -20 dB, 1 skipped");
+    }
+}
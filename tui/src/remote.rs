@@ -0,0 +1,232 @@
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::model::CommandSpec;
+use crate::scratch;
+use crate::shell_words;
+
+/// Set on a remote run so stopping it interrupts that run and no other
+/// process running the same script on the host.
+pub const RUN_TAG_VAR: &str = "MSST_TUI_RUN_TAG";
+
+/// Seconds ssh waits for a host before giving up, so a box that is down
+/// fails the run or the test instead of hanging it.
+pub const CONNECT_TIMEOUT_SECS: u64 = 10;
+
+/// What ssh exits with when it couldn't connect or lost the connection.
+pub const SSH_FAILED: i32 = 255;
+
+/// What the connection test's remote command exits with when the script
+/// isn't in `project_root`.
+const SCRIPT_MISSING: i32 = 3;
+
+/// A machine the scripts can run on over ssh, e.g. a rented GPU box.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteHost {
+    pub name: String,
+    /// What `ssh` connects to: `user@host`, or a Host from `~/.ssh/config`.
+    pub ssh_destination: String,
+    /// The MSST checkout on the host; scripts and relative paths are
+    /// taken from there.
+    pub project_root: String,
+    #[serde(default = "default_python")]
+    pub python: String,
+}
+
+fn default_python() -> String {
+    "python3".to_string()
+}
+
+/// Where a remote run left its results on the host, kept in its run
+/// metadata so they can be pulled later.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteLocation {
+    /// The host's name in `remote_hosts` when the run started.
+    pub host: String,
+    pub ssh_destination: String,
+    /// Absolute, or relative to the login folder.
+    pub path: String,
+}
+
+impl RemoteLocation {
+    /// E.g. "ubuntu@10.0.0.5:/srv/msst/results/run", as rsync and scp take it.
+    pub fn source(&self) -> String {
+        format!("{}:{}", self.ssh_destination, self.path)
+    }
+
+    /// The folder's name, which a pull copies it under.
+    pub fn name(&self) -> String {
+        Path::new(&self.path).file_name().map_or(self.path.clone(), |name| name.to_string_lossy().into_owned())
+    }
+}
+
+/// The ssh options every connection is made with: no password prompt can
+/// hold it up, as keys or an agent have to do, and a host that is down
+/// fails within `CONNECT_TIMEOUT_SECS`.
+pub fn ssh_options() -> Vec<String> {
+    vec![
+        "-o".to_string(),
+        "BatchMode=yes".to_string(),
+        "-o".to_string(),
+        format!("ConnectTimeout={}", CONNECT_TIMEOUT_SECS),
+    ]
+}
+
+impl RemoteHost {
+    /// E.g. "gpu-box (ubuntu@10.0.0.5:~/MSST)".
+    pub fn describe(&self) -> String {
+        format!("{} ({}:{})", self.name, self.ssh_destination, self.project_root)
+    }
+
+    /// `path` on the host, which is relative to `project_root` unless it
+    /// is absolute.
+    pub fn location(&self, path: &str) -> RemoteLocation {
+        let path = if path.starts_with('/') || path.starts_with('~') {
+            path.to_string()
+        } else {
+            format!("{}/{}", self.project_root.trim_end_matches('/'), path.trim_start_matches("./"))
+        };
+        RemoteLocation { host: self.name.clone(), ssh_destination: self.ssh_destination.clone(), path }
+    }
+
+    /// `ssh` running `line` in `project_root` on the host.
+    fn ssh(&self, line: &str) -> CommandSpec {
+        let mut cmd = CommandSpec::new("ssh");
+        for option in ssh_options() {
+            cmd.arg(option);
+        }
+        cmd.arg(&self.ssh_destination);
+        cmd.arg(format!("cd {} && {}", home_quote(&self.project_root), line));
+        cmd
+    }
+
+    /// `script` with `args` run by the host's python, tagged with `tag`.
+    /// Its output comes back over ssh like a local run's.
+    pub fn command(&self, script: &str, args: &[String], tag: &str) -> CommandSpec {
+        let mut words = vec![script.to_string()];
+        words.extend(args.iter().cloned());
+        self.ssh(&format!("{}={} exec {} {}", RUN_TAG_VAR, shell_words::quote(tag), home_quote(&self.python), shell_words::join(&words)))
+    }
+
+    /// Sends `signal` (e.g. `INT`) to the processes running `script` that
+    /// carry `tag`: `pkill -f` on the script, narrowed by the tag in
+    /// their environment.
+    pub fn signal_command(&self, script: &str, tag: &str, signal: &str) -> CommandSpec {
+        let name = Path::new(script).file_name().map_or(script.to_string(), |name| name.to_string_lossy().into_owned());
+        let marker = format!("{}={}", RUN_TAG_VAR, tag);
+        self.ssh(&format!(
+            "for pid in $(pgrep -f {}); do tr '\\0' '\\n' < /proc/$pid/environ 2>/dev/null | grep -qxF {} && kill -{} $pid; done; true",
+            shell_words::quote(&name),
+            shell_words::quote(&marker),
+            signal
+        ))
+    }
+
+    /// Checks `script` is in `project_root` and prints the python version.
+    pub fn test_command(&self, script: &str) -> CommandSpec {
+        self.ssh(&format!(
+            "{{ test -f {} || exit {}; }} && {} --version 2>&1",
+            shell_words::quote(script),
+            SCRIPT_MISSING,
+            home_quote(&self.python)
+        ))
+    }
+}
+
+/// A tag for a new run, unique to this host and session.
+pub fn run_tag(kind: &str) -> String {
+    format!("{}-{}", Local::now().format("%Y%m%d%H%M%S"), scratch::job_id(kind))
+}
+
+/// Connects to `host` and checks `script` is there, e.g. before the first
+/// run on it. Blocks for up to `CONNECT_TIMEOUT_SECS` on a host that
+/// doesn't answer.
+pub fn test_connection(host: &RemoteHost, script: &str) -> Result<String> {
+    let output = host.test_command(script).std_command()
+        .output()
+        .context("Failed to run ssh")?;
+    connection_outcome(host, script, output.status.code(), &String::from_utf8_lossy(&output.stdout), &String::from_utf8_lossy(&output.stderr))
+}
+
+/// What the test's exit `code` and output say about `host`.
+fn connection_outcome(host: &RemoteHost, script: &str, code: Option<i32>, stdout: &str, stderr: &str) -> Result<String> {
+    let last = |text: &str| text.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or_default().trim().to_string();
+    match code {
+        Some(0) => Ok(format!("{}: connected, {} found, {}", host.name, script, last(stdout))),
+        Some(SSH_FAILED) => bail!("Failed to connect to {}: {}", host.ssh_destination, last(stderr)),
+        Some(SCRIPT_MISSING) => bail!("{} not found in {} on {}", script, host.project_root, host.name),
+        Some(code) => bail!("{} on {} failed (exit {}): {}", host.python, host.name, code, last(&format!("{}\n{}", stdout, stderr))),
+        None => bail!("ssh to {} was killed", host.ssh_destination),
+    }
+}
+
+/// `path` quoted for the remote shell, leaving a leading `~/` outside the
+/// quotes so the shell still expands it to the home folder.
+fn home_quote(path: &str) -> String {
+    match path.strip_prefix("~/") {
+        Some("") => "~/".to_string(),
+        Some(rest) => format!("~/{}", shell_words::quote(rest)),
+        None if path == "~" => path.to_string(),
+        None => shell_words::quote(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host() -> RemoteHost {
+        RemoteHost {
+            name: "gpu-box".to_string(),
+            ssh_destination: "ubuntu@10.0.0.5".to_string(),
+            project_root: "/home/ubuntu/Music Source Separation".to_string(),
+            python: "/opt/conda/bin/python".to_string(),
+        }
+    }
+
+    #[test]
+    fn runs_and_stops_go_through_ssh_in_the_project_root() {
+        let host = host();
+        let command = host.command("train.py", &["--model_type".to_string(), "scnet".to_string(), "--data_path".to_string(), "my data".to_string()], "run-1");
+        assert_eq!(command.program, "ssh");
+        assert_eq!(&command.args[..5], ["-o", "BatchMode=yes", "-o", "ConnectTimeout=10", "ubuntu@10.0.0.5"]);
+        assert_eq!(
+            command.args[5],
+            "cd '/home/ubuntu/Music Source Separation' && MSST_TUI_RUN_TAG=run-1 exec /opt/conda/bin/python train.py --model_type scnet --data_path 'my data'"
+        );
+
+        assert_eq!(host.location("./results/run-1").source(), "ubuntu@10.0.0.5:/home/ubuntu/Music Source Separation/results/run-1");
+        assert_eq!(host.location("/scratch/results").path, "/scratch/results");
+
+        let stop = host.signal_command("scripts/train.py", "run-1", "INT");
+        assert!(stop.args[5].contains("pgrep -f train.py"), "{}", stop.args[5]);
+        assert!(stop.args[5].contains("grep -qxF MSST_TUI_RUN_TAG=run-1 && kill -INT $pid"), "{}", stop.args[5]);
+    }
+
+    #[test]
+    fn a_root_in_the_home_folder_is_left_for_the_shell_to_expand() {
+        let mut host = host();
+        host.project_root = "~/MSST".to_string();
+        assert!(host.test_command("train.py").args[5].starts_with("cd ~/MSST && "));
+        host.python = "~/miniconda3/bin/python".to_string();
+        assert!(host.command("train.py", &[], "run-1").args[5].ends_with("exec ~/miniconda3/bin/python train.py"));
+        host.project_root = "~/Music Source Separation".to_string();
+        assert!(host.test_command("train.py").args[5].starts_with("cd ~/'Music Source Separation' && "));
+    }
+
+    #[test]
+    fn the_connection_test_tells_a_dead_host_from_a_missing_script() {
+        let host = host();
+        assert!(host.test_command("train.py").args[5].ends_with("{ test -f train.py || exit 3; } && /opt/conda/bin/python --version 2>&1"));
+        assert_eq!(
+            connection_outcome(&host, "train.py", Some(0), "Python 3.10.12\n", "").unwrap(),
+            "gpu-box: connected, train.py found, Python 3.10.12"
+        );
+        let error = connection_outcome(&host, "train.py", Some(255), "", "ssh: connect to host 10.0.0.5 port 22: Connection timed out\n").unwrap_err();
+        assert_eq!(error.to_string(), "Failed to connect to ubuntu@10.0.0.5: ssh: connect to host 10.0.0.5 port 22: Connection timed out");
+        let error = connection_outcome(&host, "train.py", Some(3), "", "").unwrap_err();
+        assert_eq!(error.to_string(), "train.py not found in /home/ubuntu/Music Source Separation on gpu-box");
+    }
+}
@@ -0,0 +1,294 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::gpu_check::{gpu_processes, nvidia_smi};
+use crate::scratch::dir_size;
+
+/// How often a running job is sampled. Each sample walks `/proc` and runs
+/// `nvidia-smi` a few times, so samples are kept well apart.
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How heavy a finished job was, recorded in its metadata.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceSummary {
+    /// GPU memory of the whole process tree, at its highest sample.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peak_gpu_mib: Option<u64>,
+    /// Utilization of the GPUs the job used, averaged over the samples.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avg_gpu_utilization: Option<f64>,
+    /// Resident memory of the whole process tree, at its highest sample.
+    pub peak_rss_kib: u64,
+    /// User and system time of every process seen in the tree. Processes
+    /// that come and go between two samples are missed.
+    pub cpu_secs: f64,
+    /// Growth of the results folder from start to finish.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub written_bytes: Option<u64>,
+    pub samples: u32,
+}
+
+impl ResourceSummary {
+    /// E.g. "peak GPU 21.3 GB, GPU 87% avg, peak RSS 6.2 GB, CPU 4h 12m, wrote 1.8 GB".
+    pub fn one_line(&self) -> String {
+        let mut parts = vec![];
+        if let Some(mib) = self.peak_gpu_mib {
+            parts.push(format!("peak GPU {}", gigabytes(mib * 1024 * 1024)));
+        }
+        if let Some(utilization) = self.avg_gpu_utilization {
+            parts.push(format!("GPU {:.0}% avg", utilization));
+        }
+        parts.push(format!("peak RSS {}", gigabytes(self.peak_rss_kib * 1024)));
+        parts.push(format!("CPU {}", cpu_time(self.cpu_secs)));
+        if let Some(bytes) = self.written_bytes {
+            parts.push(format!("wrote {}", gigabytes(bytes)));
+        }
+        parts.join(", ")
+    }
+
+    /// For a table column, e.g. "21.3G 87% / 6.2G".
+    pub fn short(&self) -> String {
+        let gpu = match (self.peak_gpu_mib, self.avg_gpu_utilization) {
+            (Some(mib), Some(utilization)) => format!("{:.1}G {:.0}% / ", mib as f64 / 1024.0, utilization),
+            (Some(mib), None) => format!("{:.1}G / ", mib as f64 / 1024.0),
+            _ => String::new(),
+        };
+        format!("{}{:.1}G", gpu, self.peak_rss_kib as f64 / (1024.0 * 1024.0))
+    }
+}
+
+fn gigabytes(bytes: u64) -> String {
+    format!("{:.1} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+}
+
+fn cpu_time(secs: f64) -> String {
+    let minutes = (secs / 60.0).round() as u64;
+    if secs < 60.0 {
+        format!("{:.0}s", secs)
+    } else if minutes < 60 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}h {}m", minutes / 60, minutes % 60)
+    }
+}
+
+/// A process as `/proc/<pid>/stat` describes it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProcStat {
+    pub pid: u32,
+    pub ppid: u32,
+    pub pgrp: u32,
+    /// utime + stime, in clock ticks.
+    pub cpu_ticks: u64,
+}
+
+/// Parses `/proc/<pid>/stat`. The command name is in parentheses and may
+/// contain spaces and parentheses itself, so fields are counted from the
+/// last `)`.
+pub fn parse_stat(text: &str) -> Option<ProcStat> {
+    let (pid, rest) = text.split_once(" (")?;
+    let fields: Vec<&str> = rest.rsplit_once(')')?.1.split_whitespace().collect();
+    let field = |index: usize| fields.get(index)?.parse::<u64>().ok();
+    Some(ProcStat {
+        pid: pid.trim().parse().ok()?,
+        ppid: field(1)? as u32,
+        pgrp: field(2)? as u32,
+        cpu_ticks: field(11)? + field(12)?,
+    })
+}
+
+/// `VmRSS` from `/proc/<pid>/status`, in KiB; 0 for kernel threads and
+/// zombies, which have none.
+pub fn parse_rss_kib(status: &str) -> u64 {
+    status.lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .and_then(|value| value.split_whitespace().next()?.parse().ok())
+        .unwrap_or(0)
+}
+
+/// `index, utilization.gpu` lines from `nvidia-smi --query-gpu=index,utilization.gpu`.
+pub fn parse_utilization(output: &str) -> HashMap<usize, f64> {
+    output.lines()
+        .filter_map(|line| {
+            let (index, utilization) = line.split_once(',')?;
+            Some((index.trim().parse().ok()?, utilization.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// `root` and everything descended from it, plus whatever is left in its
+/// process group: workers whose parent died are reparented but keep the
+/// group.
+pub fn process_tree(root: u32, processes: &[ProcStat]) -> Vec<ProcStat> {
+    let mut tree: HashSet<u32> = HashSet::from([root]);
+    loop {
+        let children: Vec<u32> = processes.iter()
+            .filter(|process| tree.contains(&process.ppid) && !tree.contains(&process.pid))
+            .map(|process| process.pid)
+            .collect();
+        if children.is_empty() {
+            break;
+        }
+        tree.extend(children);
+    }
+    processes.iter().filter(|process| tree.contains(&process.pid) || process.pgrp == root).copied().collect()
+}
+
+/// Samples the process tree of a running job now and then and adds it up.
+/// `finish` still gives a summary for a job that failed or was killed,
+/// covering the samples taken before.
+#[derive(Debug)]
+pub struct ResourceSampler {
+    root: u32,
+    results_dir: Option<PathBuf>,
+    /// Size of the results folder when the job started.
+    results_before: u64,
+    last_sample: Option<Instant>,
+    /// Highest CPU ticks seen per process.
+    cpu_ticks: HashMap<u32, u64>,
+    utilization_total: f64,
+    utilization_samples: u32,
+    last_utilization: Option<f64>,
+    summary: ResourceSummary,
+}
+
+impl ResourceSampler {
+    pub fn new(root: u32, results_dir: Option<&Path>) -> Self {
+        ResourceSampler {
+            root,
+            results_dir: results_dir.map(Path::to_path_buf),
+            results_before: results_dir.map_or(0, dir_size),
+            last_sample: None,
+            cpu_ticks: HashMap::new(),
+            utilization_total: 0.0,
+            utilization_samples: 0,
+            last_utilization: None,
+            summary: ResourceSummary::default(),
+        }
+    }
+
+    /// Samples if `SAMPLE_INTERVAL` has passed since the last time; cheap
+    /// to call on every tick.
+    pub fn sample_if_due(&mut self) {
+        if self.last_sample.is_some_and(|last| last.elapsed() < SAMPLE_INTERVAL) {
+            return;
+        }
+        self.last_sample = Some(Instant::now());
+        self.sample();
+    }
+
+    fn sample(&mut self) {
+        let tree = process_tree(self.root, &read_processes());
+        if tree.is_empty() {
+            return;
+        }
+        let rss: u64 = tree.iter()
+            .map(|process| fs::read_to_string(format!("/proc/{}/status", process.pid)).map_or(0, |status| parse_rss_kib(&status)))
+            .sum();
+        for process in &tree {
+            let ticks = self.cpu_ticks.entry(process.pid).or_default();
+            *ticks = (*ticks).max(process.cpu_ticks);
+        }
+        let summary = &mut self.summary;
+        summary.samples += 1;
+        summary.peak_rss_kib = summary.peak_rss_kib.max(rss);
+        summary.cpu_secs = self.cpu_ticks.values().sum::<u64>() as f64 / clock_ticks_per_sec();
+
+        let pids: HashSet<u32> = tree.iter().map(|process| process.pid).collect();
+        let Some(gpu) = gpu_processes() else {
+            return;
+        };
+        let ours: Vec<_> = gpu.iter().filter(|process| pids.contains(&process.pid)).collect();
+        let used: u64 = ours.iter().map(|process| process.used_mib).sum();
+        summary.peak_gpu_mib = Some(summary.peak_gpu_mib.unwrap_or(0).max(used));
+        let devices: HashSet<usize> = ours.iter().map(|process| process.device).collect();
+        if devices.is_empty() {
+            return;
+        }
+        let utilization = nvidia_smi(&["--query-gpu=index,utilization.gpu", "--format=csv,noheader,nounits"])
+            .map(|output| parse_utilization(&output))
+            .unwrap_or_default();
+        let values: Vec<f64> = devices.iter().filter_map(|device| utilization.get(device).copied()).collect();
+        if !values.is_empty() {
+            self.utilization_total += values.iter().sum::<f64>() / values.len() as f64;
+            self.utilization_samples += 1;
+            self.last_utilization = Some(values.iter().sum::<f64>() / values.len() as f64);
+            summary.avg_gpu_utilization = Some(self.utilization_total / self.utilization_samples as f64);
+        }
+    }
+
+    /// Utilization of the job's GPUs at the latest sample.
+    pub fn last_utilization(&self) -> Option<f64> {
+        self.last_utilization
+    }
+
+    /// The summary so far, with what the job wrote to its results folder.
+    pub fn finish(&self) -> ResourceSummary {
+        let mut summary = self.summary.clone();
+        summary.written_bytes = self.results_dir.as_deref().map(|dir| dir_size(dir).saturating_sub(self.results_before));
+        summary
+    }
+}
+
+fn read_processes() -> Vec<ProcStat> {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return vec![];
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().bytes().all(|byte| byte.is_ascii_digit()))
+        .filter_map(|entry| parse_stat(&fs::read_to_string(entry.path().join("stat")).ok()?))
+        .collect()
+}
+
+#[cfg(unix)]
+fn clock_ticks_per_sec() -> f64 {
+    // SAFETY: sysconf only reads a configuration value.
+    match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        ticks if ticks > 0 => ticks as f64,
+        _ => 100.0,
+    }
+}
+
+#[cfg(not(unix))]
+fn clock_ticks_per_sec() -> f64 {
+    100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proc_files_are_parsed_and_the_tree_collected() {
+        let stat = parse_stat("4242 (python (worker) 1) S 4200 4200 4200 0 -1 4194304 100 0 0 0 1500 250 0 0 20 0 9 0").unwrap();
+        assert_eq!(stat, ProcStat { pid: 4242, ppid: 4200, pgrp: 4200, cpu_ticks: 1750 });
+        assert_eq!(parse_rss_kib("Name:\tpython\nVmRSS:\t  6291456 kB\n"), 6291456);
+        assert_eq!(parse_rss_kib("Name:\tkworker\n"), 0);
+        assert_eq!(parse_utilization("0, 87\n1, [N/A]\n"), HashMap::from([(0, 87.0)]));
+
+        let process = |pid, ppid, pgrp| ProcStat { pid, ppid, pgrp, cpu_ticks: 0 };
+        let processes = [process(1, 0, 1), process(100, 1, 100), process(101, 100, 100), process(102, 101, 100), process(103, 1, 100), process(200, 1, 200)];
+        let pids: Vec<u32> = process_tree(100, &processes).iter().map(|process| process.pid).collect();
+        assert_eq!(pids, [100, 101, 102, 103]);
+    }
+
+    #[test]
+    fn summary_reads_as_one_line() {
+        let summary = ResourceSummary {
+            peak_gpu_mib: Some(21_811),
+            avg_gpu_utilization: Some(86.6),
+            peak_rss_kib: 6_500_000,
+            cpu_secs: 15_120.0,
+            written_bytes: Some(1_932_735_283),
+            samples: 1512,
+        };
+        assert_eq!(summary.one_line(), "peak GPU 21.3 GB, GPU 87% avg, peak RSS 6.2 GB, CPU 4h 12m, wrote 1.8 GB");
+        assert_eq!(summary.short(), "21.3G 87% / 6.2G");
+        let without_gpu = ResourceSummary { peak_rss_kib: 1024, cpu_secs: 42.0, ..Default::default() };
+        assert_eq!(without_gpu.one_line(), "peak RSS 0.0 GB, CPU 42s");
+    }
+}
@@ -0,0 +1,326 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::cost::{CostSettings, RunCost};
+use crate::metrics::TrainingHistory;
+use crate::model::{CommandSpec, ModelType};
+use crate::remote::RemoteLocation;
+use crate::resources::ResourceSummary;
+use crate::secret::redact;
+use crate::training_chart::best_mean_sdr;
+use crate::validset::ValidSetStamp;
+
+pub const RUN_FILE: &str = "run.json";
+pub const CONFIG_SNAPSHOT: &str = "config.yaml";
+pub const METRICS_FILE: &str = "metrics.csv";
+pub const LOGS_DIR: &str = "logs";
+
+pub const DEFAULT_RUN_NAME_TEMPLATE: &str = "{date}_{model}_{label}";
+
+/// Everything needed to tell what a run was and to start it again, written
+/// to `run.json` in the run directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunMetadata {
+    pub name: String,
+    pub label: Option<String>,
+    pub model_type: ModelType,
+    /// The config the run was started from; the copy it actually used is
+    /// `config.yaml` next to this file.
+    pub config_source: String,
+    pub command: CommandSpec,
+    pub environment: EnvironmentSummary,
+    pub python_git_hash: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub exit_code: Option<i32>,
+    /// Free text added from the history screen after the fact.
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<RunCost>,
+    /// The run this one was duplicated from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derived_from: Option<String>,
+    /// Peak memory, CPU time and output size, written when the run ends.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resources: Option<ResourceSummary>,
+    /// The folder train.py validates against, as it was when the run started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_set: Option<ValidSetStamp>,
+    /// Epochs train.py reported, counted from its log.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epochs_completed: Option<usize>,
+    /// Highest mean validation SDR and the epoch it came at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub best_sdr: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub best_epoch: Option<usize>,
+    /// Where the checkpoints are when the run went to a remote host.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<RemoteLocation>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EnvironmentSummary {
+    pub tui_version: String,
+    pub os: String,
+    pub arch: String,
+    pub hostname: Option<String>,
+    pub working_dir: Option<String>,
+    pub python: Option<String>,
+    pub cuda_visible_devices: Option<String>,
+}
+
+impl EnvironmentSummary {
+    /// About `command`, run in its `current_dir` (this process's when unset).
+    pub fn collect(command: &CommandSpec) -> Self {
+        let working_dir = command.current_dir.clone().or_else(|| std::env::current_dir().ok());
+        EnvironmentSummary {
+            tui_version: env!("CARGO_PKG_VERSION").to_string(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            hostname: hostname(),
+            // Python 2 printed its version to stderr, so take whichever has it.
+            python: command_output(Command::new(&command.program).arg("--version").current_dir(working_dir.as_deref().unwrap_or(Path::new("."))), true),
+            working_dir: working_dir.map(|dir| dir.to_string_lossy().to_string()),
+            cuda_visible_devices: std::env::var("CUDA_VISIBLE_DEVICES").ok(),
+        }
+    }
+}
+
+pub fn hostname() -> Option<String> {
+    std::env::var("HOSTNAME").ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// Commit of the python checkout in `dir`, if it is a git repo.
+pub fn python_git_hash(dir: &Path) -> Option<String> {
+    command_output(Command::new("git").args(["rev-parse", "HEAD"]).current_dir(dir), false)
+}
+
+fn command_output(cmd: &mut Command, include_stderr: bool) -> Option<String> {
+    let output = cmd.output().ok().filter(|output| output.status.success())?;
+    let mut text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if text.is_empty() && include_stderr {
+        text = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    }
+    (!text.is_empty()).then_some(text)
+}
+
+/// Expands `{date}`, `{model}` and `{label}` in a run name template. Unsafe
+/// path characters become `_`, and separators left dangling by an empty
+/// placeholder are dropped.
+pub fn run_name(template: &str, now: DateTime<Local>, model: &ModelType, label: Option<&str>) -> String {
+    let expanded = template
+        .replace("{date}", &now.format("%Y%m%d-%H%M%S").to_string())
+        .replace("{model}", model.key())
+        .replace("{label}", label.unwrap_or_default().trim());
+
+    let mut name = String::new();
+    for c in expanded.chars() {
+        let c = if c.is_alphanumeric() || "-_.".contains(c) { c } else { '_' };
+        if c == '_' && (name.is_empty() || name.ends_with('_')) {
+            continue;
+        }
+        name.push(c);
+    }
+    let name = name.trim_matches(['_', '-', '.']).to_string();
+    if name.is_empty() { "run".to_string() } else { name }
+}
+
+/// `results_path/<name>`, with a numeric suffix if that is already taken.
+pub fn unique_run_path(results_path: &Path, name: &str) -> PathBuf {
+    let mut path = results_path.join(name);
+    let mut suffix = 2;
+    while path.exists() {
+        path = results_path.join(format!("{}-{}", name, suffix));
+        suffix += 1;
+    }
+    path
+}
+
+/// Self-describing folder holding everything one training run produced:
+/// checkpoints from train.py plus the TUI's config snapshot, metadata,
+/// metrics and logs.
+#[derive(Debug, Clone)]
+pub struct RunDir {
+    path: PathBuf,
+    pub metadata: RunMetadata,
+}
+
+impl RunDir {
+    /// Creates `path`, snapshots the config and writes the initial `run.json`.
+    pub fn create(path: &Path, config_path: &str, label: Option<String>, model_type: ModelType, command: CommandSpec) -> Result<Self> {
+        fs::create_dir_all(path.join(LOGS_DIR))
+            .with_context(|| format!("Failed to create run directory {}", path.display()))?;
+        fs::copy(config_path, path.join(CONFIG_SNAPSHOT))
+            .context("Failed to snapshot model config")?;
+
+        let run = RunDir {
+            path: path.to_path_buf(),
+            metadata: RunMetadata {
+                name: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+                label,
+                model_type,
+                config_source: config_path.to_string(),
+                environment: EnvironmentSummary::collect(&command),
+                python_git_hash: python_git_hash(command.current_dir.as_deref().unwrap_or(Path::new("."))),
+                command,
+                started_at: Utc::now(),
+                finished_at: None,
+                exit_code: None,
+                notes: String::new(),
+                cost: None,
+                derived_from: None,
+                resources: None,
+                valid_set: None,
+                epochs_completed: None,
+                best_sdr: None,
+                best_epoch: None,
+                remote: None,
+            },
+        };
+        run.save()?;
+        Ok(run)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path.join(RUN_FILE))
+            .with_context(|| format!("Failed to read {}", path.join(RUN_FILE).display()))?;
+        let metadata = serde_json::from_str(&content)
+            .context("Failed to parse run metadata")?;
+        Ok(RunDir { path: path.to_path_buf(), metadata })
+    }
+
+    /// Run directories directly under `results_path`, oldest first.
+    pub fn list(results_path: &Path) -> Result<Vec<Self>> {
+        let mut runs = vec![];
+        if !results_path.is_dir() {
+            return Ok(runs);
+        }
+        for entry in fs::read_dir(results_path)
+            .context("Failed to read results folder")?
+        {
+            let path = entry.context("Failed to read directory entry")?.path();
+            if path.join(RUN_FILE).is_file()
+                && let Ok(run) = RunDir::load(&path)
+            {
+                runs.push(run);
+            }
+        }
+        runs.sort_by_key(|run| run.metadata.started_at);
+        Ok(runs)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(&self.metadata)
+            .context("Failed to serialize run metadata")?;
+        fs::write(self.path.join(RUN_FILE), redact(&content))
+            .context("Failed to write run metadata")?;
+        Ok(())
+    }
+
+    pub fn finish(&mut self, exit_code: Option<i32>, resources: Option<ResourceSummary>) -> Result<()> {
+        self.metadata.finished_at = Some(Utc::now());
+        self.metadata.exit_code = exit_code;
+        self.metadata.resources = resources;
+        self.save()
+    }
+
+    /// Prices the run at `settings`.
+    pub fn set_cost(&mut self, settings: &CostSettings) -> Result<()> {
+        self.metadata.cost = Some(RunCost::new(settings));
+        self.save()
+    }
+
+    /// Writes the metrics so far to `metrics.csv` and their summary to
+    /// `run.json`, for the history to show after the TUI is closed.
+    pub fn record_metrics(&mut self, history: &TrainingHistory) -> Result<()> {
+        if history.is_empty() {
+            return Ok(());
+        }
+        history.write_csv(&self.metrics_path())?;
+        let best = best_mean_sdr(history.progress());
+        self.metadata.epochs_completed = history.progress().last().map(|progress| progress.epoch + 1);
+        self.metadata.best_sdr = best.map(|(sdr, _)| sdr);
+        self.metadata.best_epoch = best.map(|(_, epoch)| epoch);
+        self.save()
+    }
+
+    pub fn set_notes(&mut self, notes: &str) -> Result<()> {
+        self.metadata.notes = notes.trim_end().to_string();
+        self.save()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn metrics_path(&self) -> PathBuf {
+        self.path.join(METRICS_FILE)
+    }
+
+    pub fn log_path(&self, name: &str) -> PathBuf {
+        self.path.join(LOGS_DIR).join(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::TrainingProgress;
+    use chrono::TimeZone;
+
+    #[test]
+    fn run_names_follow_the_template() {
+        let now = Local.with_ymd_and_hms(2024, 3, 9, 14, 5, 7).unwrap();
+        let model = ModelType::BsRoformer;
+        assert_eq!(
+            run_name(DEFAULT_RUN_NAME_TEMPLATE, now, &model, Some("vocals v2")),
+            "20240309-140507_bs_roformer_vocals_v2"
+        );
+        assert_eq!(run_name(DEFAULT_RUN_NAME_TEMPLATE, now, &model, None), "20240309-140507_bs_roformer");
+        assert_eq!(run_name("{label}_{model}", now, &model, Some("../x")), "x_bs_roformer");
+        assert_eq!(run_name("{label}", now, &model, None), "run");
+    }
+
+    #[test]
+    fn run_directory_describes_itself() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("model.yaml");
+        fs::write(&config, "audio:\n  sample_rate: 44100\n").unwrap();
+
+        let path = unique_run_path(dir.path(), "run");
+        let mut command = CommandSpec::new("python");
+        command.arg("train.py").arg("--results_path").arg(&path);
+        let mut run = RunDir::create(&path, config.to_str().unwrap(), Some("first".into()), ModelType::ScNet, command.clone()).unwrap();
+
+        assert_eq!(fs::read_to_string(path.join(CONFIG_SNAPSHOT)).unwrap(), "audio:\n  sample_rate: 44100\n");
+        assert!(path.join(LOGS_DIR).is_dir());
+        assert_eq!(unique_run_path(dir.path(), "run"), dir.path().join("run-2"));
+
+        run.finish(Some(0), None).unwrap();
+        let runs = RunDir::list(dir.path()).unwrap();
+        assert_eq!(runs.len(), 1);
+        let metadata = &runs[0].metadata;
+        assert_eq!(metadata.name, "run");
+        assert_eq!(metadata.label.as_deref(), Some("first"));
+        assert_eq!(metadata.command, command);
+        assert_eq!(metadata.exit_code, Some(0));
+        assert!(metadata.finished_at.is_some());
+
+        let mut metrics = TrainingHistory::new();
+        metrics.push(TrainingProgress { epoch: 0, train_loss: 0.2, sdr: Some(4.5), ..Default::default() });
+        metrics.push(TrainingProgress { epoch: 1, train_loss: 0.1, sdr: Some(4.0), ..Default::default() });
+        run.record_metrics(&metrics).unwrap();
+        let metadata = RunDir::load(&path).unwrap().metadata;
+        assert_eq!((metadata.epochs_completed, metadata.best_sdr, metadata.best_epoch), (Some(2), Some(4.5), Some(0)));
+        assert_eq!(crate::metrics::read_csv(&run.metrics_path()).unwrap().len(), 2);
+    }
+}
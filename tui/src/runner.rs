@@ -0,0 +1,138 @@
+use anyhow::{bail, Result};
+use std::path::{Path, PathBuf};
+
+use crate::config::AppConfig;
+use crate::model::CommandSpec;
+
+/// Interpreters tried, in order, when `python_executable` isn't set.
+pub const PYTHON_CANDIDATES: [&str; 2] = ["python3", "python"];
+
+/// How the python scripts are launched: with which interpreter and from
+/// which folder. Shared by the training, inference and validation managers.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunnerEnv {
+    /// A path or a name looked up on PATH; `PYTHON_CANDIDATES` when unset.
+    pub python_executable: Option<String>,
+    /// Folder the script names are relative to; the project root when unset.
+    pub scripts_dir: Option<PathBuf>,
+    /// The MSST checkout, which the scripts run in; the TUI's working
+    /// folder when unset.
+    pub project_root: Option<PathBuf>,
+    /// GPUs the scripts may see, passed as `CUDA_VISIBLE_DEVICES`; all of
+    /// them when unset.
+    pub visible_devices: Option<Vec<usize>>,
+}
+
+impl RunnerEnv {
+    pub fn from_config(config: &AppConfig) -> Self {
+        RunnerEnv {
+            python_executable: config.python_executable.clone().filter(|python| !python.trim().is_empty()),
+            scripts_dir: config.scripts_dir.as_deref().filter(|dir| !dir.trim().is_empty()).map(PathBuf::from),
+            project_root: None,
+            visible_devices: None,
+        }
+    }
+
+    /// Runs the scripts in `root`.
+    pub fn in_root(mut self, root: &Path) -> Self {
+        self.project_root = Some(root.to_path_buf());
+        self
+    }
+
+    /// The interpreter to launch, checked to exist so a bad setting fails
+    /// before anything is started rather than as a spawn error.
+    pub fn python(&self) -> Result<String> {
+        match &self.python_executable {
+            Some(python) => match find_program(python) {
+                Some(path) => Ok(path.to_string_lossy().to_string()),
+                None => bail!("Python interpreter '{}' not found (python_executable in Settings)", python),
+            },
+            None => match PYTHON_CANDIDATES.iter().find_map(|name| find_program(name)) {
+                Some(path) => Ok(path.to_string_lossy().to_string()),
+                None => bail!(
+                    "No python interpreter found: tried {} on PATH. Set python_executable in Settings",
+                    PYTHON_CANDIDATES.join(" and ")
+                ),
+            },
+        }
+    }
+
+    /// `name` in `scripts_dir`, a relative one taken from the project root.
+    pub fn script(&self, name: &str) -> String {
+        let dir = match (&self.project_root, &self.scripts_dir) {
+            (Some(root), Some(dir)) => root.join(dir),
+            (Some(dir), None) | (None, Some(dir)) => dir.clone(),
+            (None, None) => return name.to_string(),
+        };
+        dir.join(name).to_string_lossy().to_string()
+    }
+
+    /// The project root, or the working folder when unset (an empty path,
+    /// which leaves relative paths as they are).
+    pub fn root(&self) -> &Path {
+        self.project_root.as_deref().unwrap_or(Path::new(""))
+    }
+
+    /// Limits the scripts to `devices`.
+    pub fn on_devices(mut self, devices: &[usize]) -> Self {
+        self.visible_devices = Some(devices.to_vec());
+        self
+    }
+
+    /// `command` set to run in the project root, on the visible devices.
+    pub fn in_project(&self, mut command: CommandSpec) -> CommandSpec {
+        command.current_dir = self.project_root.clone();
+        if let Some(devices) = &self.visible_devices {
+            let list: Vec<String> = devices.iter().map(usize::to_string).collect();
+            command.env("CUDA_VISIBLE_DEVICES", &list.join(","));
+        }
+        command
+    }
+}
+
+/// `program` itself if it is a path to a file, otherwise the first match on
+/// PATH (with `.exe` on Windows).
+pub fn find_program(program: &str) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    let names = [program.to_string(), format!("{}{}", program, std::env::consts::EXE_SUFFIX)];
+    let dirs = std::env::var_os("PATH")?;
+    std::env::split_paths(&dirs)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configured_interpreter_must_exist() {
+        let dir = tempfile::tempdir().unwrap();
+        let python = dir.path().join("envs/msst/bin/python");
+        std::fs::create_dir_all(python.parent().unwrap()).unwrap();
+        std::fs::write(&python, "").unwrap();
+
+        let runner = RunnerEnv {
+            python_executable: Some(python.to_string_lossy().to_string()),
+            scripts_dir: Some(dir.path().join("msst")),
+            project_root: None,
+            visible_devices: None,
+        };
+        assert_eq!(runner.python().unwrap(), python.to_string_lossy());
+        assert_eq!(runner.script("train.py"), dir.path().join("msst/train.py").to_string_lossy());
+
+        let missing = RunnerEnv { python_executable: Some("/opt/conda/envs/none/bin/python".to_string()), ..RunnerEnv::default() };
+        let error = missing.python().unwrap_err().to_string();
+        assert!(error.contains("/opt/conda/envs/none/bin/python"), "{}", error);
+        assert_eq!(missing.script("train.py"), "train.py");
+
+        let rooted = RunnerEnv { scripts_dir: Some(PathBuf::from("msst")), ..RunnerEnv::default() }.in_root(dir.path());
+        assert_eq!(rooted.script("train.py"), dir.path().join("msst/train.py").to_string_lossy());
+        assert_eq!(rooted.in_project(CommandSpec::new("python")).current_dir.as_deref(), Some(dir.path()));
+        let pinned = rooted.on_devices(&[2]).in_project(CommandSpec::new("python"));
+        assert_eq!(pinned.env, vec![("CUDA_VISIBLE_DEVICES".to_string(), "2".to_string())]);
+    }
+}
@@ -0,0 +1,268 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::config::AppConfig;
+
+pub const MARKER_FILE: &str = ".msst_scratch";
+pub const DEFAULT_MAX_AGE_HOURS: u64 = 24;
+
+static JOB_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Scratch dirs this process created and has not removed yet.
+static LIVE_DIRS: Mutex<Option<HashSet<PathBuf>>> = Mutex::new(None);
+
+/// New id for a job, unique within this machine while the process lives.
+pub fn job_id(kind: &str) -> String {
+    format!("{}-{}-{}", kind, std::process::id(), JOB_COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+/// Written into every scratch dir so a later sweep knows who made it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScratchMarker {
+    pub job_id: String,
+    pub purpose: String,
+    pub pid: u32,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ScratchUsage {
+    pub dirs: usize,
+    pub orphaned: usize,
+    pub bytes: u64,
+}
+
+/// The one place temporary files go: `<root>/<job-id>_<purpose>/`, each
+/// with a marker file. Whoever creates a dir removes it when the job ends;
+/// `sweep` catches the ones a crash left behind.
+#[derive(Debug, Clone)]
+pub struct Scratch {
+    root: PathBuf,
+    max_age: Duration,
+}
+
+impl Default for Scratch {
+    fn default() -> Self {
+        Scratch::new(std::env::temp_dir().join("msst_tui_scratch"), DEFAULT_MAX_AGE_HOURS)
+    }
+}
+
+impl Scratch {
+    pub fn new(root: PathBuf, max_age_hours: u64) -> Self {
+        Scratch {
+            root,
+            max_age: Duration::hours(max_age_hours as i64),
+        }
+    }
+
+    /// A relative `scratch_dir` is under the project `root`.
+    pub fn from_config(config: &AppConfig, root: &Path) -> Self {
+        let default = Scratch::default();
+        Scratch::new(
+            config.scratch_dir.as_ref().map(|dir| root.join(dir)).unwrap_or(default.root),
+            config.scratch_max_age_hours.unwrap_or(DEFAULT_MAX_AGE_HOURS),
+        )
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn create(&self, job_id: &str, purpose: &str) -> Result<ScratchDir> {
+        let path = self.root.join(format!("{}_{}", job_id, purpose));
+        fs::create_dir_all(&path)
+            .with_context(|| format!("Failed to create scratch folder {}", path.display()))?;
+
+        let marker = ScratchMarker {
+            job_id: job_id.to_string(),
+            purpose: purpose.to_string(),
+            pid: std::process::id(),
+            created_at: Utc::now(),
+        };
+        let content = serde_json::to_string_pretty(&marker)
+            .context("Failed to serialize scratch marker")?;
+        fs::write(path.join(MARKER_FILE), content)
+            .context("Failed to write scratch marker")?;
+
+        live_dirs(|dirs| dirs.insert(path.clone()));
+        Ok(ScratchDir { path, job_id: job_id.to_string() })
+    }
+
+    /// Removes orphaned scratch dirs older than the configured age. Returns
+    /// how many were removed.
+    pub fn sweep(&self) -> Result<usize> {
+        self.remove_orphans(self.max_age)
+    }
+
+    /// Removes every orphaned scratch dir regardless of age.
+    pub fn clean(&self) -> Result<usize> {
+        self.remove_orphans(Duration::zero())
+    }
+
+    fn remove_orphans(&self, min_age: Duration) -> Result<usize> {
+        let now = Utc::now();
+        let mut removed = 0;
+        for (path, marker) in self.entries()? {
+            if !is_orphaned(&path, &marker) || now - marker.created_at < min_age {
+                continue;
+            }
+            fs::remove_dir_all(&path)
+                .with_context(|| format!("Failed to remove scratch folder {}", path.display()))?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    pub fn usage(&self) -> Result<ScratchUsage> {
+        let mut usage = ScratchUsage::default();
+        for (path, marker) in self.entries()? {
+            usage.dirs += 1;
+            if is_orphaned(&path, &marker) {
+                usage.orphaned += 1;
+            }
+            usage.bytes += dir_size(&path);
+        }
+        Ok(usage)
+    }
+
+    /// Dirs under the root that carry a marker; anything else is not ours.
+    fn entries(&self) -> Result<Vec<(PathBuf, ScratchMarker)>> {
+        let mut entries = vec![];
+        if !self.root.is_dir() {
+            return Ok(entries);
+        }
+        for entry in fs::read_dir(&self.root)
+            .context("Failed to read scratch folder")?
+        {
+            let path = entry.context("Failed to read directory entry")?.path();
+            let marker = fs::read_to_string(path.join(MARKER_FILE))
+                .ok()
+                .and_then(|content| serde_json::from_str::<ScratchMarker>(&content).ok());
+            if let Some(marker) = marker {
+                entries.push((path, marker));
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// A scratch dir owned by a running job. Call `remove` when the job is done.
+#[derive(Debug)]
+pub struct ScratchDir {
+    path: PathBuf,
+    job_id: String,
+}
+
+impl ScratchDir {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn job_id(&self) -> &str {
+        &self.job_id
+    }
+
+    pub fn remove(&self) -> Result<()> {
+        live_dirs(|dirs| dirs.remove(&self.path));
+        if self.path.exists() {
+            fs::remove_dir_all(&self.path)
+                .with_context(|| format!("Failed to remove scratch folder {}", self.path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+fn live_dirs<T>(f: impl FnOnce(&mut HashSet<PathBuf>) -> T) -> T {
+    let mut guard = LIVE_DIRS.lock().unwrap_or_else(|e| e.into_inner());
+    f(guard.get_or_insert_with(HashSet::new))
+}
+
+fn is_orphaned(path: &Path, marker: &ScratchMarker) -> bool {
+    if marker.pid == std::process::id() {
+        return !live_dirs(|dirs| dirs.contains(path));
+    }
+    !process_alive(marker.pid)
+}
+
+/// Whether another process with this pid is running. Where we can't tell,
+/// assume it is dead and let the age limit protect it.
+fn process_alive(pid: u32) -> bool {
+    if cfg!(target_os = "linux") {
+        Path::new("/proc").join(pid.to_string()).exists()
+    } else {
+        false
+    }
+}
+
+/// Total size of the files under `path`, 0 if it can't be read.
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backdate(dir: &Path, pid: u32, hours: i64) {
+        let marker_path = dir.join(MARKER_FILE);
+        let mut marker: ScratchMarker = serde_json::from_str(&fs::read_to_string(&marker_path).unwrap()).unwrap();
+        marker.pid = pid;
+        marker.created_at -= Duration::hours(hours);
+        fs::write(marker_path, serde_json::to_string(&marker).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn sweep_only_removes_old_orphans() {
+        let root = tempfile::tempdir().unwrap();
+        let scratch = Scratch::new(root.path().to_path_buf(), 24);
+
+        let live = scratch.create(&job_id("test"), "staging").unwrap();
+        fs::write(live.path().join("data"), vec![0u8; 100]).unwrap();
+        backdate(live.path(), std::process::id(), 48);
+
+        let fresh = scratch.create(&job_id("test"), "staging").unwrap();
+        live_dirs(|dirs| dirs.remove(fresh.path()));
+
+        let crashed = scratch.create(&job_id("test"), "config").unwrap();
+        live_dirs(|dirs| dirs.remove(crashed.path()));
+        backdate(crashed.path(), u32::MAX, 48);
+
+        let foreign = root.path().join("not_ours");
+        fs::create_dir(&foreign).unwrap();
+
+        let usage = scratch.usage().unwrap();
+        assert_eq!(usage.dirs, 3);
+        assert_eq!(usage.orphaned, 2);
+        assert!(usage.bytes >= 100);
+
+        assert_eq!(scratch.sweep().unwrap(), 1);
+        assert!(!crashed.path().exists());
+        assert!(fresh.path().exists());
+        assert!(live.path().exists());
+
+        assert_eq!(scratch.clean().unwrap(), 1);
+        assert!(!fresh.path().exists());
+        assert!(live.path().exists());
+        assert!(foreign.exists());
+
+        live.remove().unwrap();
+        assert!(!live.path().exists());
+        assert_eq!(scratch.usage().unwrap(), ScratchUsage::default());
+    }
+}
@@ -0,0 +1,207 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+use crate::model::{InputAdjustment, InputNote};
+use crate::staging::scan_inputs;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackChoice {
+    pub path: PathBuf,
+    /// `None` for files that couldn't be probed.
+    pub duration_secs: Option<f64>,
+    pub selected: bool,
+}
+
+impl TrackChoice {
+    pub fn name(&self) -> String {
+        self.path.file_name().unwrap_or_default().to_string_lossy().to_string()
+    }
+}
+
+/// The inputs of one folder with a checkbox each, picked before a batch
+/// starts. Everything starts selected; exclude patterns and toggles take
+/// files out.
+#[derive(Debug, Clone)]
+pub struct TrackSelection {
+    folder: PathBuf,
+    tracks: Vec<TrackChoice>,
+    patterns: Vec<String>,
+}
+
+impl TrackSelection {
+    /// Scans `folder` like staging does, then applies `patterns` (usually
+    /// the ones saved for the folder).
+    pub fn scan(folder: &Path, patterns: &[String]) -> Result<Self> {
+        let tracks = scan_inputs(folder)?
+            .into_iter()
+            .map(|input| TrackChoice {
+                duration_secs: input.info.and_then(|info| info.duration_secs()),
+                path: input.path,
+                selected: true,
+            })
+            .collect();
+        let mut selection = TrackSelection { folder: folder.to_path_buf(), tracks, patterns: vec![] };
+        for pattern in patterns {
+            selection.exclude(pattern);
+        }
+        Ok(selection)
+    }
+
+    pub fn folder(&self) -> &Path {
+        &self.folder
+    }
+
+    pub fn tracks(&self) -> &[TrackChoice] {
+        &self.tracks
+    }
+
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    pub fn toggle(&mut self, index: usize) {
+        if let Some(track) = self.tracks.get_mut(index) {
+            track.selected = !track.selected;
+        }
+    }
+
+    /// Deselects every file whose name matches `pattern` (see `glob_match`)
+    /// and remembers the pattern. Returns how many files it matched.
+    pub fn exclude(&mut self, pattern: &str) -> usize {
+        let pattern = pattern.trim();
+        if pattern.is_empty() {
+            return 0;
+        }
+        let mut matched = 0;
+        for track in &mut self.tracks {
+            if glob_match(pattern, &track.name()) {
+                track.selected = false;
+                matched += 1;
+            }
+        }
+        if !self.patterns.iter().any(|existing| existing == pattern) {
+            self.patterns.push(pattern.to_string());
+        }
+        matched
+    }
+
+    /// Selects everything again and forgets the patterns.
+    pub fn select_all(&mut self) {
+        self.patterns.clear();
+        for track in &mut self.tracks {
+            track.selected = true;
+        }
+    }
+
+    pub fn selected(&self) -> impl Iterator<Item = &TrackChoice> {
+        self.tracks.iter().filter(|track| track.selected)
+    }
+
+    /// File names left out, for `InferenceConfig::excluded_inputs`.
+    pub fn excluded_names(&self) -> Vec<String> {
+        self.tracks.iter().filter(|track| !track.selected).map(TrackChoice::name).collect()
+    }
+
+    /// E.g. "12 of 14 tracks selected, 0:48:12 total (1 of unknown length)".
+    pub fn confirmation(&self) -> String {
+        let count = self.selected().count();
+        let total: f64 = self.selected().filter_map(|track| track.duration_secs).sum();
+        let unknown = self.selected().filter(|track| track.duration_secs.is_none()).count();
+        let total = total.round() as u64;
+        let mut text = format!(
+            "{} of {} tracks selected, {}:{:02}:{:02} total",
+            count,
+            self.tracks.len(),
+            total / 3600,
+            total / 60 % 60,
+            total % 60
+        );
+        if unknown > 0 {
+            text.push_str(&format!(" ({} of unknown length)", unknown));
+        }
+        text
+    }
+}
+
+/// Notes holding back the inputs of `input_folder` named in `excluded`, for
+/// `Staging::prepare`.
+pub fn deselected_notes(input_folder: &Path, excluded: &[String]) -> Vec<InputNote> {
+    excluded.iter()
+        .map(|name| InputNote {
+            input: input_folder.join(name).to_string_lossy().to_string(),
+            adjustment: InputAdjustment::Excluded { reason: "deselected before the run".to_string() },
+        })
+        .collect()
+}
+
+/// Shell-style match of a file name: `*` is any run of characters, `?` any
+/// one character. Case is ignored, since libraries mix `.wav` and `.WAV`.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    // Backtracks only to the last `*`, which is enough for this syntax.
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::write_wav;
+    use std::fs;
+
+    #[test]
+    fn globs_match_whole_names() {
+        assert!(glob_match("*_reference.wav", "song_reference.WAV"));
+        assert!(!glob_match("*_reference.wav", "song_reference.wav.bak"));
+        assert!(glob_match("track??.*", "track01.flac"));
+        assert!(!glob_match("track??.*", "track1.flac"));
+        assert!(glob_match("*mix*", "final mix v2.wav"));
+        assert!(glob_match("*", ""));
+    }
+
+    #[test]
+    fn selection_counts_only_what_is_left() {
+        let dir = tempfile::tempdir().unwrap();
+        let silence = vec![0.0f32; 44_100 * 90];
+        for name in ["a.wav", "b.wav", "b_reference.wav"] {
+            write_wav(&dir.path().join(name), 44_100, &[silence.clone(), silence.clone()]).unwrap();
+        }
+        fs::write(dir.path().join("notes.txt"), "not audio").unwrap();
+
+        let mut selection = TrackSelection::scan(dir.path(), &["*_reference.wav".to_string()]).unwrap();
+        assert_eq!(selection.excluded_names(), ["b_reference.wav"]);
+        assert_eq!(selection.confirmation(), "3 of 4 tracks selected, 0:03:00 total (1 of unknown length)");
+
+        selection.toggle(3);
+        assert_eq!(selection.exclude("A.WAV"), 1);
+        assert_eq!(selection.confirmation(), "1 of 4 tracks selected, 0:01:30 total");
+        assert_eq!(selection.patterns(), ["*_reference.wav", "A.WAV"]);
+        assert_eq!(deselected_notes(dir.path(), &selection.excluded_names()).len(), 3);
+
+        selection.select_all();
+        assert!(selection.patterns().is_empty());
+        assert!(selection.excluded_names().is_empty());
+    }
+}
@@ -0,0 +1,83 @@
+use anyhow::{bail, Result};
+
+/// The words of a command line: whitespace separates them, single quotes
+/// keep everything, double quotes keep all but `\"` and `\\`, and a
+/// backslash outside quotes escapes the next character. No variables or
+/// globs are expanded.
+pub fn split(line: &str) -> Result<Vec<String>> {
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => bail!("Unclosed ' in {}", line),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => bail!("Unclosed \" in {}", line),
+                        },
+                        Some(c) => word.push(c),
+                        None => bail!("Unclosed \" in {}", line),
+                    }
+                }
+            }
+            '\\' => word.get_or_insert_with(String::new).extend(chars.next()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// `word` as a POSIX shell needs it, single-quoted unless it is plain.
+pub fn quote(word: &str) -> String {
+    let safe = !word.is_empty() && word.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+    if safe {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', "'\\''"))
+    }
+}
+
+/// `words` as one line that `split` gives back.
+pub fn join(words: &[String]) -> String {
+    words.iter().map(|word| quote(word)).collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_and_escapes_keep_words_together() {
+        assert_eq!(
+            split(r#"--metrics sdr si_sdr  --extract_instrumental a\ b "c \"d\" \n" '' 'it'\''s'"#).unwrap(),
+            ["--metrics", "sdr", "si_sdr", "--extract_instrumental", "a b", "c \"d\" \\n", "", "it's"]
+        );
+        assert!(split("").unwrap().is_empty());
+        assert!(split("--lora 'unclosed").is_err());
+        assert!(split("\"unclosed \\\"").is_err());
+
+        let words: Vec<String> = ["--force_cpu", "my file.wav", "", "it's", "$HOME"].map(String::from).into();
+        assert_eq!(join(&words), "--force_cpu 'my file.wav' '' 'it'\\''s' '$HOME'");
+        assert_eq!(split(&join(&words)).unwrap(), words);
+    }
+}
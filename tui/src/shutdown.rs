@@ -0,0 +1,141 @@
+use std::time::{Duration, Instant};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long each part of the app gets to stop on its own when quitting,
+/// before whatever is left is forced.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShutdownBudget {
+    /// Running jobs, between SIGTERM and SIGKILL. Enough for train.py to
+    /// get through a checkpoint save it is in the middle of.
+    pub jobs: Duration,
+    /// Background tasks, after they were cancelled. Their threads can't be
+    /// stopped from outside, so any still running are left behind to end
+    /// with the process.
+    pub tasks: Duration,
+}
+
+impl Default for ShutdownBudget {
+    fn default() -> Self {
+        ShutdownBudget { jobs: Duration::from_secs(10), tasks: Duration::from_secs(2) }
+    }
+}
+
+impl ShutdownBudget {
+    /// The longest quitting can take, give or take a poll.
+    pub fn total(&self) -> Duration {
+        self.jobs + self.tasks
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepOutcome {
+    Stopped,
+    /// Ran out of time; names what had to be forced.
+    Forced(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShutdownStep {
+    pub subsystem: &'static str,
+    pub elapsed: Duration,
+    pub outcome: StepOutcome,
+}
+
+/// What happened to each part of the app on the way out.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShutdownReport {
+    pub steps: Vec<ShutdownStep>,
+}
+
+impl ShutdownReport {
+    /// One subsystem's stop, already asked for: polls `done` until it
+    /// holds or `grace` runs out, then hands what's left to `force`, which
+    /// returns the names of what it forced.
+    pub fn step<S>(
+        &mut self,
+        subsystem: &'static str,
+        grace: Duration,
+        target: &mut S,
+        mut done: impl FnMut(&mut S) -> bool,
+        force: impl FnOnce(&mut S) -> Vec<String>,
+    ) {
+        let start = Instant::now();
+        let outcome = loop {
+            if done(target) {
+                break StepOutcome::Stopped;
+            }
+            if start.elapsed() >= grace {
+                break StepOutcome::Forced(force(target));
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        };
+        self.steps.push(ShutdownStep { subsystem, elapsed: start.elapsed(), outcome });
+    }
+
+    /// Lines for the final log: only the steps that had to be forced,
+    /// e.g. "Jobs: forced after 10.0s: train_a".
+    pub fn forced_lines(&self) -> Vec<String> {
+        self.steps.iter()
+            .filter_map(|step| match &step.outcome {
+                StepOutcome::Forced(names) => {
+                    Some(format!("{}: forced after {:.1}s: {}", step.subsystem, step.elapsed.as_secs_f64(), names.join(", ")))
+                }
+                StepOutcome::Stopped => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::{DeviceAllocator, DeviceRequirement, JobKind, JobSpec, JobSupervisor};
+    use crate::model::CommandSpec;
+    use crate::task::OnCancel;
+    use crate::ui::App;
+
+    fn shell_job(name: &str, script: &str) -> JobSpec {
+        let mut command = CommandSpec::new("sh");
+        command.arg("-c").arg(script);
+        JobSpec { kind: JobKind::Command, name: name.into(), command: Some(command), requirement: DeviceRequirement::None, log_path: None, results_dir: None }
+    }
+
+    /// A job that stops on SIGTERM, one that ignores it, a task that
+    /// notices the cancel and a "download" stuck in a call that never looks:
+    /// quitting still ends within the budget, naming the two stragglers.
+    #[cfg(unix)]
+    #[test]
+    fn quitting_stays_within_the_budget() {
+        let mut app = App::new();
+        app.supervisor = JobSupervisor::new(DeviceAllocator::new(vec![]));
+        app.supervisor.submit(shell_job("polite", "exec sleep 30"));
+        app.supervisor.submit(shell_job("stubborn", "trap '' TERM; sleep 30"));
+        app.supervisor.tick();
+        let cooperative = app.spawn_task("scan", OnCancel::Discard, |ctx| {
+            while !ctx.is_cancelled() {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Ok(())
+        });
+        app.spawn_task("download", OnCancel::Discard, |_| {
+            std::thread::sleep(Duration::from_secs(30));
+            Ok(())
+        });
+        // Lets the shell install its trap before the TERM arrives.
+        std::thread::sleep(Duration::from_millis(200));
+
+        let budget = ShutdownBudget { jobs: Duration::from_millis(800), tasks: Duration::from_millis(300) };
+        let start = Instant::now();
+        let report = app.shut_down(&budget);
+        assert!(start.elapsed() < budget.total() + Duration::from_millis(500), "took {:?}", start.elapsed());
+        let outcomes: Vec<_> = report.steps.iter().map(|step| (step.subsystem, step.outcome.clone())).collect();
+        assert_eq!(outcomes, [
+            ("Jobs", StepOutcome::Forced(vec!["stubborn".to_string()])),
+            ("Background tasks", StepOutcome::Forced(vec!["download".to_string()])),
+        ]);
+        assert!(report.forced_lines()[0].starts_with("Jobs: forced after 0.8s: stubborn"), "{:?}", report.forced_lines());
+        assert!(app.shutdown.is_cancelled());
+        assert!(cooperative.join().is_ok());
+    }
+}
@@ -0,0 +1,591 @@
+use anyhow::{bail, Context, Result};
+use rubato::{FftFixedIn, Resampler};
+use std::fs;
+use std::path::{Path, PathBuf};
+use symphonia::core::audio::Channels;
+
+use crate::audio::{self, AudioInfo};
+use crate::config::ModelAudio;
+use crate::model::{ChannelPolicy, InputAdjustment, InputNote, SampleRatePolicy};
+use crate::scratch::{Scratch, ScratchDir};
+
+const RESAMPLE_CHUNK: usize = 4096;
+
+/// inference.py assumes stereo when the model config doesn't say otherwise.
+const DEFAULT_MODEL_CHANNELS: u16 = 2;
+
+#[derive(Debug, Clone)]
+pub struct InputFile {
+    pub path: PathBuf,
+    pub info: Option<AudioInfo>,
+}
+
+/// Lists the files inference.py would pick up (`input_folder/*.*`) and probes
+/// the ones we can decode. Files we can't read are passed through untouched;
+/// inference.py reports those itself.
+pub fn scan_inputs(input_folder: &Path) -> Result<Vec<InputFile>> {
+    let mut files = vec![];
+    for entry in fs::read_dir(input_folder)
+        .with_context(|| format!("Failed to read input folder {}", input_folder.display()))?
+    {
+        let path = entry.context("Failed to read directory entry")?.path();
+        if !path.is_file() || path.extension().is_none() {
+            continue;
+        }
+        let info = audio::probe(&path).ok();
+        files.push(InputFile { path, info });
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+pub fn sample_rate_mismatches(inputs: &[InputFile], expected: u32) -> Vec<InputNote> {
+    inputs.iter()
+        .filter_map(|input| {
+            let rate = input.info?.sample_rate;
+            (rate != expected).then(|| InputNote {
+                input: input.path.to_string_lossy().to_string(),
+                adjustment: InputAdjustment::SampleRateMismatch { rate, expected },
+            })
+        })
+        .collect()
+}
+
+/// What staging does with a single input file.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Conversion {
+    rate: Option<u32>,
+    channels: Option<u16>,
+    exclude: bool,
+}
+
+impl Conversion {
+    fn rewrites(&self) -> bool {
+        self.rate.is_some() || self.channels.is_some()
+    }
+}
+
+/// Input folder actually handed to inference.py. When inputs had to be
+/// rewritten or excluded they live in a scratch folder next to links to the
+/// untouched ones; `cleanup` removes it once the run is over.
+pub struct Staging {
+    input_folder: PathBuf,
+    staging_dir: Option<ScratchDir>,
+    pub notes: Vec<InputNote>,
+    originals: Vec<(String, PathBuf)>,
+}
+
+impl Staging {
+    pub fn prepare(
+        scratch: &Scratch,
+        job_id: &str,
+        input_folder: &Path,
+        model: ModelAudio,
+        rate_policy: SampleRatePolicy,
+        channel_policy: ChannelPolicy,
+        held_back: Vec<InputNote>,
+    ) -> Result<Self> {
+        let inputs = scan_inputs(input_folder)?;
+        let is_held_back = |input: &InputFile| {
+            let name = input.path.to_string_lossy();
+            held_back.iter().any(|note| note.input == name)
+        };
+
+        if let Some(expected) = model.sample_rate
+            && rate_policy == SampleRatePolicy::Block
+        {
+            let considered: Vec<InputFile> = inputs.iter()
+                .filter(|input| !is_held_back(input))
+                .cloned()
+                .collect();
+            let mismatches = sample_rate_mismatches(&considered, expected);
+            if !mismatches.is_empty() {
+                let listed: Vec<String> = mismatches.iter()
+                    .map(|note| format!("  {}: {}", note.input, note.adjustment.describe()))
+                    .collect();
+                bail!(
+                    "{} input(s) do not match the model sample rate of {} Hz:\n{}",
+                    mismatches.len(),
+                    expected,
+                    listed.join("\n")
+                );
+            }
+        }
+
+        let expected_channels = model.num_channels.unwrap_or(DEFAULT_MODEL_CHANNELS);
+        let mut notes = vec![];
+        let mut conversions = vec![];
+        for input in &inputs {
+            let mut conversion = Conversion::default();
+            if is_held_back(input) {
+                conversion.exclude = true;
+                conversions.push(conversion);
+                continue;
+            }
+            let note = |adjustment| InputNote {
+                input: input.path.to_string_lossy().to_string(),
+                adjustment,
+            };
+
+            if let Some(info) = input.info {
+                if info.channels != expected_channels {
+                    match channel_policy {
+                        ChannelPolicy::Convert => {
+                            conversion.channels = Some(expected_channels);
+                            notes.push(note(if info.channels < expected_channels {
+                                InputAdjustment::Upmixed { from: info.channels, to: expected_channels }
+                            } else {
+                                InputAdjustment::Downmixed { from: info.channels, to: expected_channels }
+                            }));
+                        }
+                        ChannelPolicy::Exclude => {
+                            conversion.exclude = true;
+                            notes.push(note(InputAdjustment::Excluded {
+                                reason: format!("{} channel(s), model expects {}", info.channels, expected_channels),
+                            }));
+                        }
+                    }
+                }
+
+                if let Some(expected) = model.sample_rate
+                    && info.sample_rate != expected
+                    && !conversion.exclude
+                {
+                    if rate_policy == SampleRatePolicy::Resample {
+                        conversion.rate = Some(expected);
+                        notes.push(note(InputAdjustment::Resampled { from: info.sample_rate, to: expected }));
+                    } else {
+                        notes.push(note(InputAdjustment::SampleRateMismatch { rate: info.sample_rate, expected }));
+                    }
+                }
+            }
+            conversions.push(conversion);
+        }
+        notes.extend(held_back);
+
+        if conversions.iter().all(|conversion| *conversion == Conversion::default()) {
+            return Ok(Staging {
+                input_folder: input_folder.to_path_buf(),
+                staging_dir: None,
+                notes,
+                originals: vec![],
+            });
+        }
+
+        let mut staging = Self::stage(scratch.create(job_id, "staging")?, &inputs, &conversions)?;
+        staging.notes = notes;
+        Ok(staging)
+    }
+
+    fn stage(staging_dir: ScratchDir, inputs: &[InputFile], conversions: &[Conversion]) -> Result<Self> {
+        let dir = staging_dir.path().to_path_buf();
+        let mut staging = Staging {
+            input_folder: dir.clone(),
+            staging_dir: Some(staging_dir),
+            notes: vec![],
+            originals: vec![],
+        };
+
+        // Names the untouched inputs keep, so a converted one (`b.flac` as
+        // `b.wav`) never takes one of them.
+        let mut taken: Vec<String> = inputs.iter()
+            .zip(conversions)
+            .filter(|(_, conversion)| !conversion.exclude && !conversion.rewrites())
+            .map(|(input, _)| input.path.file_name().unwrap_or_default().to_string_lossy().to_string())
+            .collect();
+        for (input, conversion) in inputs.iter().zip(conversions) {
+            if conversion.exclude {
+                continue;
+            }
+
+            let staged = if conversion.rewrites() {
+                let name = converted_name(&input.path, &taken);
+                let staged = dir.join(&name);
+                taken.push(name);
+                convert_to_wav(&input.path, &staged, conversion.rate, conversion.channels)
+                    .with_context(|| format!("Failed to convert {}", input.path.display()))?;
+                staged
+            } else {
+                let staged = dir.join(input.path.file_name().unwrap_or_default());
+                link_or_copy(&input.path, &staged)?;
+                staged
+            };
+            let staged_stem = staged.file_stem().unwrap_or_default().to_string_lossy().to_string();
+            staging.originals.push((staged_stem, input.path.clone()));
+        }
+
+        Ok(staging)
+    }
+
+    pub fn input_folder(&self) -> &Path {
+        &self.input_folder
+    }
+
+    pub fn is_staged(&self) -> bool {
+        self.staging_dir.is_some()
+    }
+
+    /// True when every input was left out, so there is nothing to run.
+    pub fn is_empty(&self) -> bool {
+        self.is_staged() && self.originals.is_empty()
+    }
+
+    /// Maps a track name (staged file stem) back to the user's original file.
+    pub fn original_input(&self, track_name: &str) -> Option<&Path> {
+        self.originals.iter()
+            .find(|(name, _)| name == track_name)
+            .map(|(_, path)| path.as_path())
+    }
+
+    /// Notes for inputs that were left out of the run entirely.
+    pub fn excluded(&self) -> impl Iterator<Item = &InputNote> {
+        self.notes.iter()
+            .filter(|note| matches!(note.adjustment, InputAdjustment::Excluded { .. }))
+    }
+
+    pub fn cleanup(&self) -> Result<()> {
+        match &self.staging_dir {
+            Some(dir) => dir.remove(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// `<stem>.wav` for a converted `path`, or `<stem>_<ext>.wav` (and then a
+/// counter) when that name is `taken`.
+fn converted_name(path: &Path, taken: &[String]) -> String {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().unwrap_or_default().to_string_lossy();
+    let mut name = format!("{}.wav", stem);
+    let mut count = 1;
+    while taken.contains(&name) {
+        name = match count {
+            1 => format!("{}_{}.wav", stem, extension),
+            count => format!("{}_{}_{}.wav", stem, extension, count),
+        };
+        count += 1;
+    }
+    name
+}
+
+fn link_or_copy(from: &Path, to: &Path) -> Result<()> {
+    #[cfg(unix)]
+    if std::os::unix::fs::symlink(from.canonicalize()?, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)
+        .with_context(|| format!("Failed to stage {}", from.display()))?;
+    Ok(())
+}
+
+/// Decodes `from`, optionally remixes to `channels` and resamples to `rate`,
+/// and writes the result as a 32-bit float wav.
+pub fn convert_to_wav(from: &Path, to: &Path, rate: Option<u32>, channels: Option<u16>) -> Result<()> {
+    let (info, mut planar) = audio::decode_planar(from)?;
+    if let Some(channels) = channels {
+        planar = remix(&planar, info.layout, channels as usize);
+    }
+    let rate = rate.unwrap_or(info.sample_rate);
+    let converted = resample(&planar, info.sample_rate, rate)?;
+    audio::write_wav(to, rate, &converted)
+}
+
+/// Applies `mix_matrix` to planar audio.
+pub fn remix(planar: &[Vec<f32>], layout: Channels, channels: usize) -> Vec<Vec<f32>> {
+    let matrix = mix_matrix(layout, planar.len(), channels);
+    let frames = planar.first().map_or(0, |channel| channel.len());
+    matrix.iter()
+        .map(|row| {
+            (0..frames)
+                .map(|frame| row.iter().zip(planar).map(|(gain, input)| gain * input[frame]).sum())
+                .collect()
+        })
+        .collect()
+}
+
+/// Gains from each source channel (columns) to each output channel (rows).
+///
+/// Mono is duplicated into every output. Stereo and wider sources going to
+/// mono are averaged, LFE excluded. Surround going to stereo uses the usual
+/// ITU-style fold-down: left/right channels at unity, centre and surround
+/// channels at -3 dB, LFE dropped, then scaled so no output can clip. Other
+/// combinations keep the channels they share and leave the rest silent.
+pub fn mix_matrix(layout: Channels, from: usize, to: usize) -> Vec<Vec<f32>> {
+    use std::f32::consts::FRAC_1_SQRT_2;
+
+    let positions: Vec<Channels> = if layout.count() == from {
+        layout.iter().collect()
+    } else {
+        vec![Channels::empty(); from]
+    };
+    let is_lfe = |position: &Channels| *position == Channels::LFE1 || *position == Channels::LFE2;
+
+    if from == 1 {
+        return vec![vec![1.0]; to];
+    }
+
+    if to == 1 {
+        let used = positions.iter().filter(|position| !is_lfe(position)).count().max(1);
+        let row = positions.iter()
+            .map(|position| if is_lfe(position) { 0.0 } else { 1.0 / used as f32 })
+            .collect();
+        return vec![row];
+    }
+
+    if to == 2 && from > 2 {
+        let left = Channels::FRONT_LEFT | Channels::REAR_LEFT | Channels::FRONT_LEFT_CENTRE
+            | Channels::SIDE_LEFT | Channels::TOP_FRONT_LEFT | Channels::TOP_REAR_LEFT
+            | Channels::REAR_LEFT_CENTRE | Channels::FRONT_LEFT_WIDE | Channels::FRONT_LEFT_HIGH;
+        let right = Channels::FRONT_RIGHT | Channels::REAR_RIGHT | Channels::FRONT_RIGHT_CENTRE
+            | Channels::SIDE_RIGHT | Channels::TOP_FRONT_RIGHT | Channels::TOP_REAR_RIGHT
+            | Channels::REAR_RIGHT_CENTRE | Channels::FRONT_RIGHT_WIDE | Channels::FRONT_RIGHT_HIGH;
+
+        let mut matrix = vec![vec![0.0; from]; 2];
+        for (i, position) in positions.iter().enumerate() {
+            let (l, r) = if is_lfe(position) {
+                (0.0, 0.0)
+            } else if *position == Channels::FRONT_LEFT {
+                (1.0, 0.0)
+            } else if *position == Channels::FRONT_RIGHT {
+                (0.0, 1.0)
+            } else if left.contains(*position) && !position.is_empty() {
+                (FRAC_1_SQRT_2, 0.0)
+            } else if right.contains(*position) && !position.is_empty() {
+                (0.0, FRAC_1_SQRT_2)
+            } else {
+                (FRAC_1_SQRT_2, FRAC_1_SQRT_2)
+            };
+            matrix[0][i] = l;
+            matrix[1][i] = r;
+        }
+
+        let peak = matrix.iter().map(|row| row.iter().sum::<f32>()).fold(0.0, f32::max);
+        if peak > 1.0 {
+            for gain in matrix.iter_mut().flatten() {
+                *gain /= peak;
+            }
+        }
+        return matrix;
+    }
+
+    (0..to)
+        .map(|out| (0..from).map(|i| if i == out { 1.0 } else { 0.0 }).collect())
+        .collect()
+}
+
+
+/// Resamples planar audio, compensating for the resampler's delay so the
+/// output is aligned with the input and has the expected length.
+pub fn resample(planar: &[Vec<f32>], from: u32, to: u32) -> Result<Vec<Vec<f32>>> {
+    let channels = planar.len();
+    let frames = planar.first().map_or(0, |channel| channel.len());
+    if from == to || frames == 0 {
+        return Ok(planar.to_vec());
+    }
+
+    let mut resampler = FftFixedIn::<f32>::new(from as usize, to as usize, RESAMPLE_CHUNK, 2, channels)
+        .context("Failed to create resampler")?;
+    let delay = resampler.output_delay();
+    let expected = (frames as u64 * to as u64).div_ceil(from as u64) as usize;
+
+    let mut output: Vec<Vec<f32>> = vec![Vec::with_capacity(expected + delay); channels];
+    let mut position = 0;
+    while frames - position >= resampler.input_frames_next() {
+        let needed = resampler.input_frames_next();
+        let block: Vec<&[f32]> = planar.iter()
+            .map(|channel| &channel[position..position + needed])
+            .collect();
+        let out = resampler.process(&block, None)?;
+        for (channel, samples) in out.into_iter().enumerate() {
+            output[channel].extend(samples);
+        }
+        position += needed;
+    }
+
+    if position < frames {
+        let block: Vec<&[f32]> = planar.iter().map(|channel| &channel[position..]).collect();
+        let out = resampler.process_partial(Some(&block), None)?;
+        for (channel, samples) in out.into_iter().enumerate() {
+            output[channel].extend(samples);
+        }
+    }
+
+    while output[0].len() < expected + delay {
+        let out = resampler.process_partial::<&[f32]>(None, None)?;
+        for (channel, samples) in out.into_iter().enumerate() {
+            output[channel].extend(samples);
+        }
+    }
+
+    for channel in output.iter_mut() {
+        channel.drain(..delay);
+        channel.truncate(expected);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_wav(path: &Path, rate: u32, frames: u32) {
+        write_wav_channels(path, rate, frames, 2);
+    }
+
+    fn write_wav_channels(path: &Path, rate: u32, frames: u32, channels: u16) {
+        let spec = hound::WavSpec {
+            channels,
+            sample_rate: rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for frame in 0..frames {
+            let value = ((frame as f32 * 440.0 * std::f32::consts::TAU / rate as f32).sin() * 8000.0) as i16;
+            for _ in 0..channels {
+                writer.write_sample(value).unwrap();
+            }
+        }
+        writer.finalize().unwrap();
+    }
+
+    fn prepare(input_folder: &Path, rate_policy: SampleRatePolicy, channel_policy: ChannelPolicy) -> Result<Staging> {
+        let scratch = Scratch::new(input_folder.join("scratch"), 24);
+        let model = ModelAudio { sample_rate: Some(44_100), num_channels: Some(2) };
+        Staging::prepare(&scratch, "test", input_folder, model, rate_policy, channel_policy, vec![])
+    }
+
+    #[test]
+    fn resample_keeps_duration_and_alignment() {
+        let frames = 48_000;
+        let sine: Vec<f32> = (0..frames)
+            .map(|i| (i as f32 * 440.0 * std::f32::consts::TAU / 48_000.0).sin())
+            .collect();
+        let out = resample(&[sine.clone(), sine], 48_000, 44_100).unwrap();
+        assert_eq!(out.len(), 2);
+        assert_eq!(out[0].len(), 44_100);
+
+        // Compare against the ideal 440 Hz sine at the new rate, away from
+        // the edges where the filter ramps in and out. The delay compensation
+        // is whole-frame, so allow for up to half a frame of phase error.
+        for i in (1000..43_000).step_by(997) {
+            let ideal = (i as f32 * 440.0 * std::f32::consts::TAU / 44_100.0).sin();
+            assert!((out[0][i] - ideal).abs() < 0.05, "sample {}: {} vs {}", i, out[0][i], ideal);
+        }
+    }
+
+    #[test]
+    fn policies_decide_what_happens_to_mismatched_inputs() {
+        let dir = tempfile::tempdir().unwrap();
+        write_wav(&dir.path().join("a.wav"), 44_100, 4410);
+        write_wav(&dir.path().join("b.wav"), 48_000, 4800);
+        fs::write(dir.path().join("notes.txt"), "not audio").unwrap();
+
+        let blocked = prepare(dir.path(), SampleRatePolicy::Block, ChannelPolicy::Convert);
+        assert!(blocked.is_err());
+
+        let warned = prepare(dir.path(), SampleRatePolicy::Warn, ChannelPolicy::Convert).unwrap();
+        assert!(!warned.is_staged());
+        assert_eq!(warned.input_folder(), dir.path());
+        assert_eq!(warned.notes.len(), 1);
+
+        let staged = prepare(dir.path(), SampleRatePolicy::Resample, ChannelPolicy::Convert).unwrap();
+        assert!(staged.is_staged());
+        let staged_b = staged.input_folder().join("b.wav");
+        let info = audio::probe(&staged_b).unwrap();
+        assert_eq!(info.sample_rate, 44_100);
+        assert_eq!(info.total_frames, Some(4410));
+        assert!(staged.input_folder().join("a.wav").exists());
+        assert!(staged.input_folder().join("notes.txt").exists());
+        assert_eq!(staged.original_input("b"), Some(dir.path().join("b.wav").as_path()));
+        assert_eq!(
+            staged.notes,
+            vec![InputNote {
+                input: dir.path().join("b.wav").to_string_lossy().to_string(),
+                adjustment: InputAdjustment::Resampled { from: 48_000, to: 44_100 },
+            }]
+        );
+
+        let staging_dir = staged.input_folder().to_path_buf();
+        staged.cleanup().unwrap();
+        assert!(!staging_dir.exists());
+        assert!(dir.path().join("a.wav").exists());
+    }
+
+    #[test]
+    fn a_converted_input_gets_a_name_of_its_own() {
+        let dir = tempfile::tempdir().unwrap();
+        write_wav(&dir.path().join("b.wav"), 44_100, 441);
+        // Wav data under another extension; it is decoded by its content.
+        write_wav(&dir.path().join("b.flac"), 48_000, 480);
+
+        let staged = prepare(dir.path(), SampleRatePolicy::Resample, ChannelPolicy::Convert).unwrap();
+        assert_eq!(staged.original_input("b"), Some(dir.path().join("b.wav").as_path()));
+        assert_eq!(staged.original_input("b_flac"), Some(dir.path().join("b.flac").as_path()));
+        assert_eq!(audio::probe(&staged.input_folder().join("b_flac.wav")).unwrap().sample_rate, 44_100);
+        assert_eq!(audio::probe(&staged.input_folder().join("b.wav")).unwrap().total_frames, Some(441));
+        assert_eq!(converted_name(Path::new("b.flac"), &["b.wav".into(), "b_flac.wav".into()]), "b_flac_2.wav");
+    }
+
+    #[test]
+    fn matching_inputs_are_not_staged() {
+        let dir = tempfile::tempdir().unwrap();
+        write_wav(&dir.path().join("a.wav"), 44_100, 441);
+
+        let staging = prepare(dir.path(), SampleRatePolicy::Resample, ChannelPolicy::Convert).unwrap();
+        assert!(!staging.is_staged());
+        assert!(staging.notes.is_empty());
+    }
+
+    #[test]
+    fn surround_folds_down_without_clipping() {
+        let layout = Channels::FRONT_LEFT | Channels::FRONT_RIGHT | Channels::FRONT_CENTRE
+            | Channels::LFE1 | Channels::REAR_LEFT | Channels::REAR_RIGHT;
+        let matrix = mix_matrix(layout, 6, 2);
+        let scale = 1.0 / (1.0 + 2.0 * std::f32::consts::FRAC_1_SQRT_2);
+        let c = std::f32::consts::FRAC_1_SQRT_2 * scale;
+        let expected = [[scale, 0.0, c, 0.0, c, 0.0], [0.0, scale, c, 0.0, 0.0, c]];
+        for (row, want) in matrix.iter().zip(expected) {
+            for (gain, want) in row.iter().zip(want) {
+                assert!((gain - want).abs() < 1e-6, "{:?}", matrix);
+            }
+        }
+
+        assert_eq!(mix_matrix(Channels::FRONT_LEFT, 1, 2), vec![vec![1.0], vec![1.0]]);
+        assert_eq!(mix_matrix(layout, 6, 1), vec![vec![0.2, 0.2, 0.2, 0.0, 0.2, 0.2]]);
+    }
+
+    #[test]
+    fn channel_policy_converts_or_excludes() {
+        let dir = tempfile::tempdir().unwrap();
+        write_wav_channels(&dir.path().join("mono.wav"), 44_100, 441, 1);
+        write_wav_channels(&dir.path().join("surround.wav"), 44_100, 441, 6);
+        write_wav(&dir.path().join("stereo.wav"), 44_100, 441);
+
+        let converted = prepare(dir.path(), SampleRatePolicy::Warn, ChannelPolicy::Convert).unwrap();
+        assert!(converted.is_staged());
+        for name in ["mono.wav", "surround.wav", "stereo.wav"] {
+            let info = audio::probe(&converted.input_folder().join(name)).unwrap();
+            assert_eq!(info.channels, 2, "{}", name);
+            assert_eq!(info.total_frames, Some(441), "{}", name);
+        }
+        let adjustments: Vec<_> = converted.notes.iter().map(|note| note.adjustment.clone()).collect();
+        assert_eq!(
+            adjustments,
+            vec![
+                InputAdjustment::Upmixed { from: 1, to: 2 },
+                InputAdjustment::Downmixed { from: 6, to: 2 },
+            ]
+        );
+        converted.cleanup().unwrap();
+
+        let excluded = prepare(dir.path(), SampleRatePolicy::Warn, ChannelPolicy::Exclude).unwrap();
+        assert!(excluded.is_staged());
+        assert!(!excluded.input_folder().join("mono.wav").exists());
+        assert!(!excluded.input_folder().join("surround.wav").exists());
+        assert!(excluded.input_folder().join("stereo.wav").exists());
+        assert_eq!(excluded.excluded().count(), 2);
+        assert_eq!(excluded.original_input("mono"), None);
+        excluded.cleanup().unwrap();
+    }
+}
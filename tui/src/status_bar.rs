@@ -0,0 +1,86 @@
+use ratatui::{layout::Rect, widgets::Paragraph, Frame};
+
+use crate::theme::Styles;
+
+const SEPARATOR: &str = " | ";
+
+/// What the status bar at the bottom of every screen shows, assembled once
+/// per frame by `App::draw`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatusInfo {
+    /// `ModelType::name()` of the selected model.
+    pub model: Option<String>,
+    /// One per running job, e.g. "training: epoch 12, loss 0.0420".
+    pub jobs: Vec<String>,
+    pub theme: String,
+    /// Keys for where the user is, e.g. "h: help  Esc: back".
+    pub hints: String,
+}
+
+impl StatusInfo {
+    /// The bar in `width` columns. What doesn't fit goes in this order:
+    /// the hints, the theme, the model; job state is cut short last.
+    pub fn line(&self, width: usize) -> String {
+        let model = self.model.as_deref().unwrap_or("no model selected");
+        let jobs = if self.jobs.is_empty() { "idle".to_string() } else { self.jobs.join(", ") };
+        let theme = format!("theme: {}", self.theme);
+        let candidates = [
+            vec![model, &jobs, &theme, &self.hints],
+            vec![model, &jobs, &theme],
+            vec![model, &jobs],
+        ];
+        for parts in candidates {
+            let line = parts.into_iter().filter(|part| !part.is_empty()).collect::<Vec<_>>().join(SEPARATOR);
+            if line.chars().count() <= width {
+                return line;
+            }
+        }
+        truncate(&jobs, width)
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect, styles: &Styles) {
+        f.render_widget(Paragraph::new(self.line(area.width as usize)).style(styles.status_bar()), area);
+    }
+}
+
+/// The screen above the bar, and the bar on the bottom row.
+pub fn split(area: Rect) -> (Rect, Rect) {
+    let bar = area.height.min(1);
+    (
+        Rect { height: area.height - bar, ..area },
+        Rect { y: area.y + area.height - bar, height: bar, ..area },
+    )
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut short: String = text.chars().take(width.saturating_sub(1)).collect();
+    if width > 0 {
+        short.push('…');
+    }
+    short
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn narrow_bars_drop_hints_before_job_state() {
+        let info = StatusInfo {
+            model: Some("SCNet".to_string()),
+            jobs: vec!["training: epoch 12, loss 0.0420".to_string()],
+            theme: "Dark".to_string(),
+            hints: "h: help  Esc: back".to_string(),
+        };
+        let full = "SCNet | training: epoch 12, loss 0.0420 | theme: Dark | h: help  Esc: back";
+        assert_eq!(info.line(100), full);
+        assert_eq!(info.line(full.len() - 1), "SCNet | training: epoch 12, loss 0.0420 | theme: Dark");
+        assert_eq!(info.line(45), "SCNet | training: epoch 12, loss 0.0420");
+        assert_eq!(info.line(20), "training: epoch 12,…");
+        let idle = StatusInfo { theme: "Light".to_string(), ..StatusInfo::default() };
+        assert_eq!(idle.line(80), "no model selected | idle | theme: Light");
+    }
+}
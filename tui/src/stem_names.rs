@@ -0,0 +1,269 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use crate::manifest::ResultsManifest;
+use crate::model::TrackSidecar;
+
+/// Case of the canonical stem name, after the mapping.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum NameCase {
+    /// As mapped, or as the model wrote it.
+    Keep,
+    Lower,
+    Upper,
+    /// `Vocals`, `Backing_vocals`.
+    #[default]
+    Title,
+}
+
+impl NameCase {
+    pub fn apply(&self, name: &str) -> String {
+        match self {
+            NameCase::Keep => name.to_string(),
+            NameCase::Lower => name.to_lowercase(),
+            NameCase::Upper => name.to_uppercase(),
+            NameCase::Title => {
+                let mut chars = name.chars();
+                match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect(),
+                    None => String::new(),
+                }
+            }
+        }
+    }
+}
+
+/// File naming for stems, e.g. `song/vox.wav` → `song/song_Vocals.wav` for
+/// DAW templates. Only file names change: the manifest keeps the model's
+/// stem names, which reference folders and bake-off pairing go by.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct StemNaming {
+    /// Model stem name → canonical name, matched ignoring case.
+    pub map: BTreeMap<String, String>,
+    pub case: NameCase,
+    /// Between the track name and the stem name.
+    pub separator: String,
+    /// `TRACK_Vocals.wav` rather than `Vocals.wav`.
+    pub prefix_track: bool,
+}
+
+impl Default for StemNaming {
+    fn default() -> Self {
+        StemNaming { map: BTreeMap::new(), case: NameCase::Title, separator: "_".to_string(), prefix_track: true }
+    }
+}
+
+impl StemNaming {
+    pub fn canonical(&self, stem: &str) -> String {
+        let mapped = self.map.iter()
+            .find(|(from, _)| from.eq_ignore_ascii_case(stem))
+            .map_or(stem, |(_, to)| to.as_str());
+        self.case.apply(mapped)
+    }
+
+    /// The new file name for `stem` of `track`, keeping the extension.
+    pub fn file_name(&self, track: &str, stem: &str, extension: Option<&str>) -> String {
+        let base = if self.prefix_track {
+            format!("{}{}{}", track, self.separator, self.canonical(stem))
+        } else {
+            self.canonical(stem)
+        };
+        match extension {
+            Some(extension) => format!("{}.{}", base, extension),
+            None => base,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StemRename {
+    pub track: String,
+    pub stem: String,
+    /// Both relative to the store_dir.
+    pub from: String,
+    pub to: String,
+}
+
+/// What renaming a store_dir would do, shown before anything moves.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenamePlan {
+    pub renames: Vec<StemRename>,
+    /// Targets two stems would share, or that a file not being renamed
+    /// already holds. Any of these blocks `apply`.
+    pub collisions: Vec<String>,
+    /// Stems already named as they should be.
+    pub unchanged: usize,
+}
+
+impl RenamePlan {
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self.collisions.iter().map(|collision| format!("Collision: {}", collision)).collect();
+        lines.extend(self.renames.iter().map(|rename| format!("{} → {}", rename.from, rename.to)));
+        lines
+    }
+}
+
+/// Plans renaming every stem in the manifest. New names stay in the stem's
+/// own folder.
+pub fn plan(store_dir: &Path, manifest: &ResultsManifest, naming: &StemNaming) -> RenamePlan {
+    let mut plan = RenamePlan::default();
+    for track in &manifest.tracks {
+        for stem in &track.stems {
+            let from = Path::new(&stem.path);
+            let extension = from.extension().map(|extension| extension.to_string_lossy().to_string());
+            let to = from.with_file_name(naming.file_name(&track.name, &stem.name, extension.as_deref()));
+            let to = to.to_string_lossy().to_string();
+            if to == stem.path {
+                plan.unchanged += 1;
+            } else {
+                plan.renames.push(StemRename { track: track.name.clone(), stem: stem.name.clone(), from: stem.path.clone(), to });
+            }
+        }
+    }
+
+    let mut targets: HashMap<&str, &str> = HashMap::new();
+    for rename in &plan.renames {
+        if let Some(other) = targets.insert(&rename.to, &rename.from) {
+            plan.collisions.push(format!("{} and {} would both become {}", other, rename.from, rename.to));
+        }
+    }
+    let kept: Vec<&str> = manifest.tracks.iter()
+        .flat_map(|track| track.stems.iter())
+        .map(|stem| stem.path.as_str())
+        .filter(|path| !plan.renames.iter().any(|rename| rename.from == *path))
+        .collect();
+    for rename in &plan.renames {
+        let moving_away = plan.renames.iter().any(|other| other.from == rename.to);
+        if kept.contains(&rename.to.as_str()) || (!moving_away && store_dir.join(&rename.to).exists()) {
+            plan.collisions.push(format!("{} already exists", rename.to));
+        }
+    }
+    plan
+}
+
+/// Renames the files, then records the old paths in the manifest and
+/// points the manifest and the track sidecars at the new ones. Files move
+/// through a temporary name first, so swaps and chains can't clobber.
+pub fn apply(store_dir: &Path, manifest: &mut ResultsManifest, plan: &RenamePlan) -> Result<usize> {
+    if !plan.collisions.is_empty() {
+        bail!("{} collision(s); change the naming rules first", plan.collisions.len());
+    }
+    let temporary = |rename: &StemRename| store_dir.join(format!("{}.renaming", rename.from));
+    for rename in &plan.renames {
+        fs::rename(store_dir.join(&rename.from), temporary(rename))
+            .with_context(|| format!("Failed to rename {}", rename.from))?;
+    }
+    for rename in &plan.renames {
+        fs::rename(temporary(rename), store_dir.join(&rename.to))
+            .with_context(|| format!("Failed to rename {} to {}", rename.from, rename.to))?;
+    }
+
+    for rename in &plan.renames {
+        let stem = manifest.tracks.iter_mut()
+            .filter(|track| track.name == rename.track)
+            .flat_map(|track| track.stems.iter_mut())
+            .find(|stem| stem.name == rename.stem);
+        if let Some(stem) = stem {
+            stem.renamed_from.get_or_insert_with(|| stem.path.clone());
+            stem.path = rename.to.clone();
+        }
+    }
+    manifest.save(store_dir)?;
+
+    let mut tracks: Vec<&str> = plan.renames.iter().map(|rename| rename.track.as_str()).collect();
+    tracks.dedup();
+    for track in tracks {
+        let path = TrackSidecar::path(store_dir, track);
+        if !path.is_file() {
+            continue;
+        }
+        let mut sidecar = TrackSidecar::load(&path)?;
+        for stem in &mut sidecar.stems {
+            if let Some(rename) = plan.renames.iter().find(|rename| rename.track == track && rename.stem == stem.name) {
+                stem.path = rename.to.clone();
+            }
+        }
+        sidecar.save(store_dir)?;
+    }
+    Ok(plan.renames.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::{ManifestStem, ManifestTrack};
+
+    fn manifest(stems: &[(&str, &str)]) -> ResultsManifest {
+        ResultsManifest {
+            tracks: vec![ManifestTrack {
+                name: "Song".into(),
+                input_path: "in/Song.wav".into(),
+                stems: stems.iter()
+                    .map(|(name, path)| ManifestStem { name: name.to_string(), path: path.to_string(), renamed_from: None })
+                    .collect(),
+                adjustments: vec![],
+                content_hash: None,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn renames_stems_and_keeps_manifest_and_sidecar_in_step() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("Song")).unwrap();
+        for file in ["vox.wav", "drums.wav"] {
+            fs::write(dir.path().join("Song").join(file), file).unwrap();
+        }
+        let mut manifest = manifest(&[("vox", "Song/vox.wav"), ("drums", "Song/drums.wav")]);
+        fs::write(
+            TrackSidecar::path(dir.path(), "Song"),
+            r#"{"schema_version": 1, "track": "Song",
+                "result": {"input_file": "in/Song.wav", "output_dir": "Song", "duration": null, "success": true, "error_message": null},
+                "model": {"model_type": "ScNet", "config_path": "c.yaml", "checkpoint": "m.ckpt"},
+                "stems": [{"name": "vox", "path": "Song/vox.wav"}, {"name": "drums", "path": "Song/drums.wav"}],
+                "sample_rate": 44100,
+                "post_processing": {"normalize": null, "use_tta": false, "loudness_match": "Off", "format": "wav"}}"#,
+        )
+        .unwrap();
+
+        let mut naming = StemNaming::default();
+        naming.map.insert("VOX".into(), "vocals".into());
+        let plan = plan(dir.path(), &manifest, &naming);
+        assert_eq!(plan.lines(), ["Song/vox.wav → Song/Song_Vocals.wav", "Song/drums.wav → Song/Song_Drums.wav"]);
+        assert_eq!(apply(dir.path(), &mut manifest, &plan).unwrap(), 2);
+
+        assert_eq!(fs::read_to_string(dir.path().join("Song/Song_Vocals.wav")).unwrap(), "vox.wav");
+        let saved = ResultsManifest::load(dir.path()).unwrap().unwrap();
+        assert_eq!(saved.tracks[0].stems[0].name, "vox");
+        assert_eq!(saved.tracks[0].stems[0].path, "Song/Song_Vocals.wav");
+        assert_eq!(saved.tracks[0].stems[0].renamed_from.as_deref(), Some("Song/vox.wav"));
+        let sidecar = TrackSidecar::load(&TrackSidecar::path(dir.path(), "Song")).unwrap();
+        assert_eq!(sidecar.stems[1].path, "Song/Song_Drums.wav");
+
+        // Applied again, nothing is left to do.
+        let again = self::plan(dir.path(), &saved, &naming);
+        assert_eq!((again.renames.len(), again.unchanged), (0, 2));
+    }
+
+    #[test]
+    fn collisions_are_found_before_anything_moves() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("Song")).unwrap();
+        fs::write(dir.path().join("Song/Song_Other.wav"), "someone else's").unwrap();
+        let mut manifest = manifest(&[("vocals", "Song/vocals.wav"), ("Vocals", "Song/Vocals_1.wav"), ("other", "Song/other.wav")]);
+
+        let plan = plan(dir.path(), &manifest, &StemNaming::default());
+        assert_eq!(plan.collisions, [
+            "Song/vocals.wav and Song/Vocals_1.wav would both become Song/Song_Vocals.wav",
+            "Song/Song_Other.wav already exists",
+        ]);
+        assert!(apply(dir.path(), &mut manifest, &plan).is_err());
+        assert_eq!(fs::read_to_string(dir.path().join("Song/Song_Other.wav")).unwrap(), "someone else's");
+    }
+}
@@ -0,0 +1,172 @@
+use regex::Regex;
+use std::sync::{Mutex, OnceLock};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+/// A tqdm bar with a known total, e.g.
+/// " 35%|███▌      | 420/1200 [03:12<05:55,  2.19it/s, loss=0.05]".
+const BAR: &str = r"(?P<percent>\d{1,3})%\|[^|]*\|\s*(?P<done>\d+)/(?P<total>\d+)\s*\[(?P<elapsed>[\d:]+)<(?P<eta>[\d:]+|\?)";
+
+fn bar() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(BAR).expect("built-in tqdm bar pattern"))
+}
+
+/// How far a run is through its current tqdm loop: an epoch's batches, or
+/// the tracks of an inference run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StepProgress {
+    pub percent: u8,
+    pub done: u64,
+    pub total: u64,
+    pub elapsed_secs: u64,
+    /// None while tqdm has no rate to estimate from yet.
+    pub eta_secs: Option<u64>,
+}
+
+impl StepProgress {
+    /// The bar in `segment`, if it is one.
+    pub fn parse(segment: &str) -> Option<StepProgress> {
+        let captures = bar().captures(segment)?;
+        Some(StepProgress {
+            percent: captures["percent"].parse::<u8>().ok()?.min(100),
+            done: captures["done"].parse().ok()?,
+            total: captures["total"].parse().ok()?,
+            elapsed_secs: clock_secs(&captures["elapsed"])?,
+            eta_secs: clock_secs(&captures["eta"]),
+        })
+    }
+
+    pub fn ratio(&self) -> f64 {
+        if self.total == 0 { 0.0 } else { (self.done as f64 / self.total as f64).min(1.0) }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.total > 0 && self.done >= self.total
+    }
+
+    /// E.g. "420/1200 (35%), 03:12 elapsed, ETA 05:55".
+    pub fn label(&self) -> String {
+        let eta = self.eta_secs.map_or("?".to_string(), clock);
+        format!("{}/{} ({}%), {} elapsed, ETA {}", self.done, self.total, self.percent, clock(self.elapsed_secs), eta)
+    }
+}
+
+/// "05:55" or "1:02:03" as seconds.
+fn clock_secs(text: &str) -> Option<u64> {
+    let parts: Vec<u64> = text.split(':').map(|part| part.parse().ok()).collect::<Option<_>>()?;
+    match parts.as_slice() {
+        [minutes, seconds] => Some(minutes * 60 + seconds),
+        [hours, minutes, seconds] => Some(hours * 3600 + minutes * 60 + seconds),
+        _ => None,
+    }
+}
+
+/// Seconds the way tqdm prints them.
+fn clock(secs: u64) -> String {
+    match secs / 3600 {
+        0 => format!("{:02}:{:02}", secs / 60, secs % 60),
+        hours => format!("{}:{:02}:{:02}", hours, secs / 60 % 60, secs % 60),
+    }
+}
+
+/// Keeps a tqdm redraw in `step`; true if it was one still going. Only the
+/// full bar is worth logging, or every redraw would be a line of its own.
+pub fn record(step: &Mutex<Option<StepProgress>>, segment: &str) -> bool {
+    let Some(bar) = StepProgress::parse(segment) else {
+        return false;
+    };
+    *step.lock().unwrap() = Some(bar);
+    !bar.is_done()
+}
+
+/// Splits a process's output on both `\n` and `\r` as it arrives, so each
+/// tqdm redraw comes out on its own instead of as one long line once the
+/// bar is full. "\r\n" ends a single line.
+pub struct Segments<R> {
+    reader: R,
+    pending: Vec<u8>,
+    after_cr: bool,
+}
+
+impl<R: AsyncBufRead + Unpin> Segments<R> {
+    pub fn new(reader: R) -> Self {
+        Segments { reader, pending: vec![], after_cr: false }
+    }
+
+    /// The next line or redraw, without its ending; None at the end.
+    pub async fn next_segment(&mut self) -> std::io::Result<Option<String>> {
+        loop {
+            let available = self.reader.fill_buf().await?;
+            if available.is_empty() {
+                if self.pending.is_empty() {
+                    return Ok(None);
+                }
+                return Ok(Some(self.take()));
+            }
+            let Some(end) = available.iter().position(|byte| matches!(byte, b'\n' | b'\r')) else {
+                self.pending.extend_from_slice(available);
+                let len = available.len();
+                self.reader.consume(len);
+                self.after_cr = false;
+                continue;
+            };
+            let delimiter = available[end];
+            self.pending.extend_from_slice(&available[..end]);
+            self.reader.consume(end + 1);
+            let after_cr = std::mem::replace(&mut self.after_cr, delimiter == b'\r');
+            if delimiter == b'\n' && after_cr && end == 0 && self.pending.is_empty() {
+                continue;
+            }
+            return Ok(Some(self.take()));
+        }
+    }
+
+    fn take(&mut self) -> String {
+        let segment = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending.clear();
+        segment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// tqdm's own redraws of an epoch, as train.py writes them.
+    const TQDM: &str = "\
+  0%|          | 0/1200 [00:00<?, ?it/s]\r 35%|███▌      | 420/1200 [03:12<05:55,  2.19it/s, loss=0.0631]\r\
+100%|██████████| 1200/1200 [1:09:07<00:00,  2.19it/s, loss=0.05]\r\nTraining loss: 0.05\n\nProcessing audio chunks: 100%|##########| 10/10 [00:04<00:00]";
+
+    #[test]
+    fn bars_parse_with_unicode_blocks_and_the_final_line() {
+        assert_eq!(StepProgress::parse(" 35%|███▌      | 420/1200 [03:12<05:55,  2.19it/s, loss=0.0631]"), Some(StepProgress {
+            percent: 35,
+            done: 420,
+            total: 1200,
+            elapsed_secs: 192,
+            eta_secs: Some(355),
+        }));
+        let first = StepProgress::parse("  0%|          | 0/1200 [00:00<?, ?it/s]").unwrap();
+        assert_eq!((first.eta_secs, first.ratio()), (None, 0.0));
+        assert_eq!(first.label(), "0/1200 (0%), 00:00 elapsed, ETA ?");
+
+        let last = StepProgress::parse("100%|██████████| 1200/1200 [1:09:07<00:00,  2.19it/s]").unwrap();
+        assert!(last.is_done());
+        assert_eq!(last.label(), "1200/1200 (100%), 1:09:07 elapsed, ETA 00:00");
+        assert_eq!(StepProgress::parse("420it [03:12, 2.19it/s]"), None, "no total, no bar");
+        assert_eq!(StepProgress::parse("Training loss: 0.05"), None);
+    }
+
+    #[tokio::test]
+    async fn redraws_and_lines_come_out_one_by_one() {
+        let mut segments = Segments::new(TQDM.as_bytes());
+        let mut read = vec![];
+        while let Some(segment) = segments.next_segment().await.unwrap() {
+            read.push(segment);
+        }
+        assert_eq!(read.len(), 6, "{:?}", read);
+        assert_eq!(read[3..], ["Training loss: 0.05", "", "Processing audio chunks: 100%|##########| 10/10 [00:04<00:00]"]);
+        let bars: Vec<u64> = read.iter().filter_map(|segment| StepProgress::parse(segment)).map(|bar| bar.done).collect();
+        assert_eq!(bars, [0, 420, 1200, 10]);
+    }
+}
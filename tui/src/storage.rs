@@ -0,0 +1,324 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::checkpoints::{file_size, resume_checkpoint};
+use crate::config::model_num_epochs;
+use crate::config_check::ConfigIssue;
+use crate::dataset::{scan_dataset, DatasetScan};
+use crate::model::{ModelType, TrainingConfig};
+use crate::task::{CancelToken, TaskContext};
+
+/// How long a check of a path is reused before it is made again.
+pub const CHECK_TTL: Duration = Duration::from_secs(300);
+
+/// Bytes free to this user on the filesystem holding `path`, or its
+/// nearest existing parent, as `results_path` may not be created yet.
+#[cfg(unix)]
+pub fn free_space(path: &Path) -> Result<u64> {
+    use anyhow::Context;
+    use std::os::unix::ffi::OsStrExt;
+
+    let existing = path.ancestors()
+        .find(|dir| !dir.as_os_str().is_empty() && dir.exists())
+        .unwrap_or(Path::new("."));
+    let c_path = std::ffi::CString::new(existing.as_os_str().as_bytes()).context("Path contains a NUL byte")?;
+    // SAFETY: statvfs only writes the struct it is given.
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stats) } != 0 {
+        return Err(std::io::Error::last_os_error()).with_context(|| format!("Failed to read the free space of {}", existing.display()));
+    }
+    // The field types differ between platforms.
+    #[allow(clippy::unnecessary_cast)]
+    let free = stats.f_bavail as u64 * stats.f_frsize as u64;
+    Ok(free)
+}
+
+#[cfg(not(unix))]
+pub fn free_space(path: &Path) -> Result<u64> {
+    anyhow::bail!("The free space of {} is only read on Unix", path.display())
+}
+
+/// Rough size of one train.py checkpoint of a typical `model_type`
+/// config, for when `results_path` holds none to go by.
+pub fn checkpoint_estimate(model_type: &ModelType) -> u64 {
+    const MB: u64 = 1_000_000;
+    match model_type {
+        ModelType::BsRoformer | ModelType::MelBandRoformer => 700 * MB,
+        ModelType::VitLarge23 | ModelType::TorchSeg | ModelType::SwinUpernet => 500 * MB,
+        ModelType::MDX23C | ModelType::BandIt | ModelType::BandItV2 => 400 * MB,
+        ModelType::Conformer | ModelType::BsConformer | ModelType::TsBsMamba2 => 300 * MB,
+        ModelType::HtDemucs => 170 * MB,
+        ModelType::ScNet | ModelType::ScNetTran | ModelType::ScNetMasked => 100 * MB,
+        ModelType::Apollo => 70 * MB,
+    }
+}
+
+/// Free space where a run's checkpoints go, and how big one is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskSpace {
+    pub free: u64,
+    /// The newest checkpoint in `results_path`, or `checkpoint_estimate`.
+    pub checkpoint: u64,
+    pub estimated: bool,
+}
+
+impl DiskSpace {
+    pub fn read(results_path: &Path, model_type: &ModelType) -> Result<Self> {
+        let free = free_space(results_path)?;
+        Ok(match resume_checkpoint(results_path, model_type.key()).filter(|checkpoint| checkpoint.size > 0) {
+            Some(checkpoint) => DiskSpace { free, checkpoint: checkpoint.size, estimated: false },
+            None => DiskSpace { free, checkpoint: checkpoint_estimate(model_type), estimated: true },
+        })
+    }
+
+    /// What train.py may write over `epochs`: `last_<model>.ckpt`, and at
+    /// worst a new best checkpoint every epoch.
+    pub fn projected(&self, epochs: Option<usize>) -> u64 {
+        self.checkpoint * (epochs.unwrap_or(0) as u64 + 1)
+    }
+
+    /// A warning when the projection doesn't fit.
+    pub fn issue(&self, epochs: Option<usize>) -> Option<ConfigIssue> {
+        let estimated = if self.estimated { ", estimated" } else { "" };
+        let message = match epochs {
+            _ if self.projected(epochs) <= self.free => return None,
+            Some(epochs) => format!(
+                "up to {} of checkpoints over {} epochs ({} each{}), but only {} free",
+                file_size(self.projected(Some(epochs))),
+                epochs,
+                file_size(self.checkpoint),
+                estimated,
+                file_size(self.free),
+            ),
+            None => format!("a checkpoint ({}{}) doesn't fit in the {} free", file_size(self.checkpoint), estimated, file_size(self.free)),
+        };
+        Some(ConfigIssue::warning("results_path", message))
+    }
+}
+
+/// What a background check still has to look at for a config; see
+/// `StorageCache::request`.
+#[derive(Debug, Clone, Default)]
+pub struct StorageRequest {
+    disk: Option<(String, ModelType)>,
+    epochs: Option<String>,
+    datasets: Vec<String>,
+}
+
+impl StorageRequest {
+    pub fn is_empty(&self) -> bool {
+        self.disk.is_none() && self.epochs.is_none() && self.datasets.is_empty()
+    }
+}
+
+/// What `check` found; errors are kept per path as text.
+#[derive(Debug, Clone, Default)]
+pub struct StorageReport {
+    disk: Option<(String, ModelType, Result<DiskSpace, String>)>,
+    epochs: Option<(String, Result<Option<usize>, String>)>,
+    datasets: Vec<(String, Result<DatasetScan, String>)>,
+}
+
+/// Reads free space and walks the datasets `request` names. Slow on big
+/// datasets and network filesystems, so it runs as a task; cancelling it
+/// is an error rather than a partial count.
+pub fn check(request: &StorageRequest, ctx: &TaskContext) -> Result<StorageReport> {
+    let text = |e: anyhow::Error| format!("{:#}", e);
+    let mut report = StorageReport {
+        disk: request.disk.clone().map(|(path, model_type)| {
+            let disk = DiskSpace::read(Path::new(&path), &model_type).map_err(text);
+            (path, model_type, disk)
+        }),
+        epochs: request.epochs.clone().map(|path| {
+            let epochs = model_num_epochs(&path).map_err(text);
+            (path, epochs)
+        }),
+        datasets: vec![],
+    };
+    for path in &request.datasets {
+        let scan = scan_dataset(Path::new(path), ctx).map_err(text);
+        ctx.check()?;
+        report.datasets.push((path.clone(), scan));
+    }
+    Ok(report)
+}
+
+#[derive(Debug, Clone)]
+struct Checked<T> {
+    at: Instant,
+    result: Result<T, String>,
+}
+
+impl<T> Checked<T> {
+    fn new(result: Result<T, String>) -> Self {
+        Checked { at: Instant::now(), result }
+    }
+
+    fn fresh(&self) -> bool {
+        self.at.elapsed() < CHECK_TTL
+    }
+}
+
+/// Storage checks by path, so reopening the Training screen or starting
+/// again doesn't walk the dataset again.
+#[derive(Debug, Clone, Default)]
+pub struct StorageCache {
+    disks: HashMap<(String, String), Checked<DiskSpace>>,
+    epochs: HashMap<String, Checked<Option<usize>>>,
+    datasets: HashMap<String, Checked<DatasetScan>>,
+}
+
+impl StorageCache {
+    /// The parts of `config` not checked in the last `CHECK_TTL`.
+    pub fn request(&self, config: &TrainingConfig) -> StorageRequest {
+        let disk_key = (config.results_path.clone(), config.model_type.key().to_string());
+        StorageRequest {
+            disk: (!config.results_path.trim().is_empty() && !self.disks.get(&disk_key).is_some_and(Checked::fresh))
+                .then(|| (config.results_path.clone(), config.model_type.clone())),
+            epochs: (!config.config_path.trim().is_empty() && !self.epochs.get(&config.config_path).is_some_and(Checked::fresh))
+                .then(|| config.config_path.clone()),
+            datasets: config.data_paths.iter()
+                .filter(|path| !self.datasets.get(*path).is_some_and(Checked::fresh))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub fn insert(&mut self, report: StorageReport) {
+        if let Some((path, model_type, disk)) = report.disk {
+            self.disks.insert((path, model_type.key().to_string()), Checked::new(disk));
+        }
+        if let Some((path, epochs)) = report.epochs {
+            self.epochs.insert(path, Checked::new(epochs));
+        }
+        for (path, scan) in report.datasets {
+            self.datasets.insert(path, Checked::new(scan));
+        }
+    }
+
+    fn disk(&self, config: &TrainingConfig) -> Option<&Result<DiskSpace, String>> {
+        let key = (config.results_path.clone(), config.model_type.key().to_string());
+        self.disks.get(&key).map(|checked| &checked.result)
+    }
+
+    /// The model config's `training.num_epochs`, else `planned`.
+    fn epochs(&self, config: &TrainingConfig, planned: Option<usize>) -> Option<usize> {
+        let from_config = self.epochs.get(&config.config_path).and_then(|checked| checked.result.clone().ok().flatten());
+        from_config.or(planned)
+    }
+
+    /// A warning when checkpoints may fill the disk, and an error for each
+    /// dataset folder without audio. `None` until every part of `config`
+    /// has been checked lately. Paths that couldn't be read are left to
+    /// `config_check`.
+    pub fn issues(&self, config: &TrainingConfig, planned_epochs: Option<usize>) -> Option<Vec<ConfigIssue>> {
+        if !self.request(config).is_empty() {
+            return None;
+        }
+        let mut issues = vec![];
+        if let Some(Ok(disk)) = self.disk(config) {
+            issues.extend(disk.issue(self.epochs(config, planned_epochs)));
+        }
+        for path in &config.data_paths {
+            if let Some(Checked { result: Ok(scan), .. }) = self.datasets.get(path)
+                && scan.audio_files == 0
+            {
+                issues.push(ConfigIssue::error("data_paths", format!("{} has no audio files", path)));
+            }
+        }
+        Some(issues)
+    }
+
+    /// What has been found for `config` so far, e.g. "Dataset /data/musdb:
+    /// 100 tracks, 400 audio files, 12.3 GB".
+    pub fn lines(&self, config: &TrainingConfig, planned_epochs: Option<usize>) -> Vec<String> {
+        let mut lines = vec![];
+        match self.disk(config) {
+            Some(Ok(disk)) => {
+                let each = if disk.estimated {
+                    format!("~{} per checkpoint (typical for {})", file_size(disk.checkpoint), config.model_type.name())
+                } else {
+                    format!("{} per checkpoint", file_size(disk.checkpoint))
+                };
+                let projection = match self.epochs(config, planned_epochs) {
+                    Some(epochs) => format!(", up to {} over {} epochs", file_size(disk.projected(Some(epochs))), epochs),
+                    None => String::new(),
+                };
+                lines.push(format!("Disk: {} free for results; {}{}", file_size(disk.free), each, projection));
+            }
+            Some(Err(e)) => lines.push(format!("Disk: {}", e)),
+            None => {}
+        }
+        for path in &config.data_paths {
+            match self.datasets.get(path).map(|checked| &checked.result) {
+                Some(Ok(scan)) => lines.push(format!(
+                    "Dataset {}: {} tracks, {} audio files, {}",
+                    path,
+                    scan.track_dirs,
+                    scan.audio_files,
+                    file_size(scan.bytes),
+                )),
+                Some(Err(e)) => lines.push(format!("Dataset {}: {}", path, e)),
+                None => {}
+            }
+        }
+        lines
+    }
+}
+
+/// Checks `config` in place and returns the issues, for the headless
+/// commands.
+pub fn issues_now(config: &TrainingConfig) -> Result<Vec<ConfigIssue>> {
+    let mut cache = StorageCache::default();
+    cache.insert(check(&cache.request(config), &TaskContext::new(CancelToken::new()))?);
+    Ok(cache.issues(config, None).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn checkpoints_are_projected_and_empty_datasets_caught() {
+        let dir = tempfile::tempdir().unwrap();
+        let results = dir.path().join("results");
+        fs::create_dir_all(results.join("run")).unwrap();
+        fs::write(results.join("run").join("last_scnet.ckpt"), [0u8; 2000]).unwrap();
+        let songs = dir.path().join("songs");
+        fs::create_dir_all(songs.join("track")).unwrap();
+        fs::write(songs.join("track").join("vocals.wav"), [0u8; 3000]).unwrap();
+        let empty = dir.path().join("empty");
+        fs::create_dir_all(empty.join("nested")).unwrap();
+
+        let mut config = TrainingConfig::new(ModelType::ScNet);
+        config.results_path = results.join("new").to_string_lossy().into_owned();
+        config.data_paths = vec![songs.to_string_lossy().into_owned(), empty.to_string_lossy().into_owned()];
+        let mut cache = StorageCache::default();
+        assert!(cache.issues(&config, None).is_none());
+        let request = cache.request(&config);
+        assert_eq!(request.datasets.len(), 2);
+        cache.insert(check(&request, &TaskContext::new(CancelToken::new())).unwrap());
+        assert!(cache.request(&config).is_empty(), "checked paths are reused");
+
+        let issues = cache.issues(&config, Some(10)).unwrap();
+        assert_eq!(issues, [ConfigIssue::error("data_paths", format!("{} has no audio files", empty.display()))]);
+        let lines = cache.lines(&config, Some(10));
+        assert!(lines[0].starts_with("Disk: ") && lines[0].ends_with("; ~100 MB per checkpoint (typical for SCNet), up to 1.1 GB over 10 epochs"), "{:?}", lines);
+        assert_eq!(lines[1], format!("Dataset {}: 1 tracks, 1 audio files, 3 KB", songs.display()));
+
+        config.results_path = results.to_string_lossy().into_owned();
+        let disk = DiskSpace::read(&results, &ModelType::ScNet).unwrap();
+        assert_eq!((disk.checkpoint, disk.estimated), (2000, false));
+        assert!(disk.free > 0);
+
+        let tight = DiskSpace { free: 5_000_000_000, checkpoint: 700_000_000, estimated: true };
+        assert!(tight.issue(Some(6)).is_none());
+        assert_eq!(
+            tight.issue(Some(200)).unwrap().line(),
+            "! results_path: up to 140.7 GB of checkpoints over 200 epochs (700 MB each, estimated), but only 5.0 GB free"
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use anyhow::{bail, Context, Result};
+use serde_yaml::{Mapping, Value};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::training_queue::TrainingQueue;
+
+/// Largest sweep generated unless `max_sweep_runs` says otherwise.
+pub const DEFAULT_MAX_SWEEP_RUNS: usize = 32;
+
+/// Values to try per model config key, e.g. `training.lr: [1e-4, 3e-4]`,
+/// in the order of the spec file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepSpec {
+    pub params: Vec<(String, Vec<Value>)>,
+}
+
+impl SweepSpec {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read sweep spec {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("In sweep spec {}", path.display()))
+    }
+
+    /// A mapping of dotted keys to lists of values; a single value is a
+    /// list of one.
+    pub fn parse(content: &str) -> Result<Self> {
+        let mapping: Mapping = serde_yaml::from_str(content).context("Failed to parse sweep spec")?;
+        let mut params = vec![];
+        for (key, values) in mapping {
+            let Value::String(key) = key else {
+                bail!("Sweep keys must be dotted paths like training.lr, not {:?}", key);
+            };
+            let values = match values {
+                Value::Sequence(values) => values,
+                value => vec![value],
+            };
+            if values.is_empty() {
+                bail!("{} has no values to sweep", key);
+            }
+            params.push((key, values));
+        }
+        if params.is_empty() {
+            bail!("The sweep spec is empty");
+        }
+        Ok(SweepSpec { params })
+    }
+
+    /// How many runs the sweep makes.
+    pub fn size(&self) -> usize {
+        self.params.iter().map(|(_, values)| values.len()).product()
+    }
+
+    /// Every combination of one value per key, the last key changing fastest.
+    pub fn combinations(&self) -> Vec<Vec<(String, Value)>> {
+        let mut combinations = vec![vec![]];
+        for (key, values) in &self.params {
+            combinations = combinations
+                .into_iter()
+                .flat_map(|combination: Vec<(String, Value)>| {
+                    values.iter().map(move |value| {
+                        let mut combination = combination.clone();
+                        combination.push((key.clone(), value.clone()));
+                        combination
+                    })
+                })
+                .collect();
+        }
+        combinations
+    }
+}
+
+/// One combination, written out as a model config.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepRun {
+    /// E.g. "lr1e-4_bs8".
+    pub name: String,
+    pub config_path: PathBuf,
+}
+
+/// Replaces the value at a dotted path. The key must already be in the
+/// config, so a typo fails instead of adding a setting nothing reads.
+pub fn set_path(root: &mut Value, path: &str, value: Value) -> Result<()> {
+    let mut node = root;
+    for key in path.split('.') {
+        let Value::Mapping(mapping) = node else {
+            bail!("{} doesn't lead through mappings in the base config", path);
+        };
+        let Some(child) = mapping.get_mut(key) else {
+            bail!("{} is not in the base config", path);
+        };
+        node = child;
+    }
+    *node = value;
+    Ok(())
+}
+
+/// The run's name from its overrides: the key's last part, shortened to
+/// its initials when it has several words, then the value.
+pub fn run_name(overrides: &[(String, Value)]) -> String {
+    overrides
+        .iter()
+        .map(|(key, value)| format!("{}{}", short_key(key), short_value(value)))
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+fn short_key(key: &str) -> String {
+    let last = key.rsplit('.').next().unwrap_or(key);
+    let words: Vec<&str> = last.split('_').filter(|word| !word.is_empty()).collect();
+    if words.len() > 1 {
+        words.iter().filter_map(|word| word.chars().next()).collect()
+    } else {
+        last.to_string()
+    }
+}
+
+fn short_value(value: &Value) -> String {
+    let text = match value {
+        Value::Number(number) => match number.as_f64() {
+            Some(float) if !number.is_i64() && !number.is_u64() && float != 0.0 && (float.abs() < 0.01 || float.abs() >= 1e4) => {
+                format!("{:e}", float)
+            }
+            _ => number.to_string(),
+        },
+        Value::String(text) => text.clone(),
+        Value::Bool(flag) => flag.to_string(),
+        Value::Null => "null".to_string(),
+        Value::Sequence(values) => values.iter().map(short_value).collect::<Vec<_>>().join("-"),
+        Value::Mapping(_) | Value::Tagged(_) => "x".to_string(),
+    };
+    text.chars().map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+') { c } else { '-' }).collect()
+}
+
+/// Writes one model config per combination of `spec` over `base` into
+/// `dir`, as `<run name>.yaml`. Refuses sweeps of more than `max_runs`.
+pub fn generate(base: &Path, spec: &SweepSpec, dir: &Path, max_runs: usize) -> Result<Vec<SweepRun>> {
+    if spec.size() > max_runs {
+        bail!("The sweep would make {} runs, more than the cap of {} (max_sweep_runs in tui_config.yaml)", spec.size(), max_runs);
+    }
+    let content = fs::read_to_string(base)
+        .with_context(|| format!("Failed to read base config {}", base.display()))?;
+    let base_config: Value = serde_yaml::from_str(&content)
+        .with_context(|| format!("Failed to parse base config {}", base.display()))?;
+    let mut configs = vec![];
+    let mut names = HashSet::new();
+    for overrides in spec.combinations() {
+        let mut config = base_config.clone();
+        for (key, value) in &overrides {
+            set_path(&mut config, key, value.clone()).with_context(|| format!("In {}", base.display()))?;
+        }
+        let mut name = run_name(&overrides);
+        // Keys that shorten alike can still collide.
+        let mut n = 2;
+        while !names.insert(name.clone()) {
+            name = format!("{}_{}", run_name(&overrides), n);
+            n += 1;
+        }
+        configs.push((name, config));
+    }
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create sweep folder {}", dir.display()))?;
+    let mut runs = vec![];
+    for (name, config) in configs {
+        let config_path = dir.join(format!("{}.yaml", name));
+        let content = serde_yaml::to_string(&config).context("Failed to serialize sweep config")?;
+        fs::write(&config_path, content)
+            .with_context(|| format!("Failed to write {}", config_path.display()))?;
+        runs.push(SweepRun { name, config_path });
+    }
+    Ok(runs)
+}
+
+/// A row of the sweep summary.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepResult {
+    pub name: String,
+    /// "waiting", "training", or how the run ended.
+    pub status: String,
+    pub final_sdr: Option<f64>,
+    pub duration_secs: Option<u64>,
+}
+
+/// The queued runs of `sweep`, in queue order. `running` is the queue
+/// index of the run training now.
+pub fn results(queue: &TrainingQueue, sweep: &str, running: Option<usize>) -> Vec<SweepResult> {
+    queue.runs()
+        .iter()
+        .enumerate()
+        .filter(|(_, run)| run.sweep.as_deref() == Some(sweep))
+        .map(|(i, run)| SweepResult {
+            name: run.config.label.clone().unwrap_or_default(),
+            status: match &run.summary {
+                Some(summary) => summary.status.clone(),
+                None if running == Some(i) => "training".to_string(),
+                None => "waiting".to_string(),
+            },
+            final_sdr: run.summary.as_ref().and_then(|summary| summary.final_sdr),
+            duration_secs: run.summary.as_ref().map(|summary| summary.duration_secs),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "audio:\n  chunk_size: 485100\ntraining:\n  lr: 5.0e-05\n  batch_size: 2\n  instruments: [vocals, other]\n";
+
+    #[test]
+    fn every_combination_is_written_with_its_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("config_scnet.yaml");
+        fs::write(&base, BASE).unwrap();
+        let spec = SweepSpec::parse("training.lr: [1e-4, 3e-4, 1e-3]\ntraining.batch_size: [4, 8]\n").unwrap();
+        assert_eq!(spec.size(), 6);
+
+        let runs = generate(&base, &spec, &dir.path().join("sweep"), DEFAULT_MAX_SWEEP_RUNS).unwrap();
+        let names: Vec<&str> = runs.iter().map(|run| run.name.as_str()).collect();
+        assert_eq!(names, ["lr1e-4_bs4", "lr1e-4_bs8", "lr3e-4_bs4", "lr3e-4_bs8", "lr1e-3_bs4", "lr1e-3_bs8"]);
+        let written: Value = serde_yaml::from_str(&fs::read_to_string(&runs[3].config_path).unwrap()).unwrap();
+        assert_eq!(written["training"]["lr"].as_f64(), Some(3e-4));
+        assert_eq!(written["training"]["batch_size"].as_u64(), Some(8));
+        assert_eq!(written["audio"]["chunk_size"].as_u64(), Some(485100), "the rest is kept");
+    }
+
+    #[test]
+    fn typos_and_oversized_sweeps_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("config.yaml");
+        fs::write(&base, BASE).unwrap();
+        let typo = SweepSpec::parse("training.lrr: [1e-4]").unwrap();
+        let error = generate(&base, &typo, &dir.path().join("sweep"), 32).unwrap_err();
+        assert!(format!("{:#}", error).contains("training.lrr is not in the base config"));
+
+        let big = SweepSpec::parse("training.lr: [1, 2, 3]\ntraining.batch_size: [1, 2, 3]").unwrap();
+        let error = generate(&base, &big, &dir.path().join("sweep"), 8).unwrap_err();
+        assert!(error.to_string().contains("9 runs, more than the cap of 8"));
+        assert!(!dir.path().join("sweep").exists());
+        assert!(SweepSpec::parse("training.lr: []").is_err());
+    }
+}
@@ -0,0 +1,622 @@
+use crossterm::event::{KeyCode, MouseButton, MouseEventKind};
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, Wrap},
+    Frame,
+};
+use std::cell::Cell as StdCell;
+use std::cmp::Ordering;
+use std::ops::Range;
+
+use crate::mouse::contains;
+use crate::theme::Styles;
+
+const COLUMN_SPACING: u16 = 1;
+
+/// Value a column sorts by. Missing values sort after everything else in
+/// either direction, so a "best first" sort never leads with blanks.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SortKey {
+    Number(f64),
+    Text(String),
+    Missing,
+}
+
+impl SortKey {
+    fn compare(&self, other: &Self, order: SortOrder) -> Ordering {
+        let ordering = match (self, other) {
+            (SortKey::Missing, SortKey::Missing) => return Ordering::Equal,
+            (SortKey::Missing, _) => return Ordering::Greater,
+            (_, SortKey::Missing) => return Ordering::Less,
+            (SortKey::Number(a), SortKey::Number(b)) => a.total_cmp(b),
+            (SortKey::Text(a), SortKey::Text(b)) => a.to_lowercase().cmp(&b.to_lowercase()),
+            (SortKey::Number(_), SortKey::Text(_)) => Ordering::Less,
+            (SortKey::Text(_), SortKey::Number(_)) => Ordering::Greater,
+        };
+        match order {
+            SortOrder::Ascending => ordering,
+            SortOrder::Descending => ordering.reverse(),
+        }
+    }
+}
+
+impl From<f64> for SortKey {
+    fn from(value: f64) -> Self {
+        if value.is_nan() { SortKey::Missing } else { SortKey::Number(value) }
+    }
+}
+
+impl From<Option<f64>> for SortKey {
+    fn from(value: Option<f64>) -> Self {
+        value.map_or(SortKey::Missing, SortKey::from)
+    }
+}
+
+impl From<&str> for SortKey {
+    fn from(value: &str) -> Self {
+        SortKey::Text(value.to_string())
+    }
+}
+
+impl From<String> for SortKey {
+    fn from(value: String) -> Self {
+        SortKey::Text(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+type Accessor<T, V> = Box<dyn Fn(&T) -> V>;
+
+type RowStyle<T> = Box<dyn Fn(&T, &Styles) -> Style>;
+
+pub struct Column<T> {
+    pub title: String,
+    /// Preferred width in cells; the last visible column takes what is left.
+    pub width: u16,
+    value: Accessor<T, String>,
+    sort_key: Option<Accessor<T, SortKey>>,
+}
+
+impl<T> Column<T> {
+    pub fn new(title: &str, width: u16, value: impl Fn(&T) -> String + 'static) -> Self {
+        Column {
+            title: title.to_string(),
+            width,
+            value: Box::new(value),
+            sort_key: None,
+        }
+    }
+
+    pub fn sort_by(mut self, key: impl Fn(&T) -> SortKey + 'static) -> Self {
+        self.sort_key = Some(Box::new(key));
+        self
+    }
+
+    pub fn value(&self, row: &T) -> String {
+        (self.value)(row)
+    }
+
+    pub fn is_sortable(&self) -> bool {
+        self.sort_key.is_some()
+    }
+}
+
+/// Keyboard-driven table shared by the results, leaderboard and jobs
+/// screens: row selection, horizontal scrolling over columns that don't fit,
+/// per-column sorting and a details popup for the selected row.
+///
+/// Keys: Up/Down/PageUp/PageDown/Home/End move the selection, Left/Right
+/// scroll columns, `<`/`>` pick the sort column, `s` cycles its order
+/// (ascending, descending, unsorted) and Enter toggles the details popup.
+/// With the mouse, a click selects a row, a double-click acts as Enter and
+/// the wheel moves the selection.
+pub struct DataTable<T> {
+    columns: Vec<Column<T>>,
+    rows: Vec<T>,
+    /// Display order as indexes into `rows`.
+    order: Vec<usize>,
+    selected: usize,
+    sort: Option<(usize, SortOrder)>,
+    sort_column: usize,
+    first_column: usize,
+    offset: StdCell<usize>,
+    page_size: StdCell<usize>,
+    /// Where the last render drew the rows.
+    body: StdCell<Rect>,
+    details_visible: bool,
+    row_style: Option<RowStyle<T>>,
+}
+
+impl<T> DataTable<T> {
+    pub fn new(columns: Vec<Column<T>>) -> Self {
+        let sort_column = columns.iter().position(|column| column.is_sortable()).unwrap_or(0);
+        DataTable {
+            columns,
+            rows: vec![],
+            order: vec![],
+            selected: 0,
+            sort: None,
+            sort_column,
+            first_column: 0,
+            offset: StdCell::new(0),
+            page_size: StdCell::new(10),
+            body: StdCell::new(Rect::default()),
+            details_visible: false,
+            row_style: None,
+        }
+    }
+
+    /// Styles rows by their content; the selected row keeps its highlight.
+    pub fn with_row_style(mut self, style: impl Fn(&T, &Styles) -> Style + 'static) -> Self {
+        self.row_style = Some(Box::new(style));
+        self
+    }
+
+    /// Replaces the rows, keeping the sort and clamping the selection.
+    pub fn set_rows(&mut self, rows: Vec<T>) {
+        self.rows = rows;
+        self.order = (0..self.rows.len()).collect();
+        self.apply_sort();
+        self.selected = self.selected.min(self.rows.len().saturating_sub(1));
+    }
+
+    pub fn rows(&self) -> &[T] {
+        &self.rows
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Rows in display order.
+    pub fn sorted(&self) -> impl Iterator<Item = &T> {
+        self.order.iter().map(|&index| &self.rows[index])
+    }
+
+    pub fn selected(&self) -> Option<&T> {
+        self.order.get(self.selected).map(|&index| &self.rows[index])
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.rows.len().saturating_sub(1));
+    }
+
+    pub fn sort(&self) -> Option<(usize, SortOrder)> {
+        self.sort
+    }
+
+    pub fn details_visible(&self) -> bool {
+        self.details_visible
+    }
+
+    pub fn set_sort(&mut self, sort: Option<(usize, SortOrder)>) {
+        let selected = self.order.get(self.selected).copied();
+        self.sort = sort.filter(|(column, _)| self.columns.get(*column).is_some_and(Column::is_sortable));
+        if let Some((column, _)) = self.sort {
+            self.sort_column = column;
+        }
+        self.order = (0..self.rows.len()).collect();
+        self.apply_sort();
+        // Keep the same row selected after re-ordering.
+        if let Some(selected) = selected {
+            self.selected = self.order.iter().position(|&index| index == selected).unwrap_or(0);
+        }
+    }
+
+    /// Unsorted, then ascending, then descending, then back to unsorted.
+    pub fn cycle_sort(&mut self) {
+        let column = self.sort_column;
+        let next = match self.sort {
+            Some((current, SortOrder::Ascending)) if current == column => Some((column, SortOrder::Descending)),
+            Some((current, SortOrder::Descending)) if current == column => None,
+            _ => Some((column, SortOrder::Ascending)),
+        };
+        self.set_sort(next);
+    }
+
+    fn apply_sort(&mut self) {
+        let Some((column, order)) = self.sort else {
+            return;
+        };
+        let Some(key) = self.columns[column].sort_key.as_ref() else {
+            return;
+        };
+        let keys: Vec<SortKey> = self.rows.iter().map(key).collect();
+        self.order.sort_by(|&a, &b| keys[a].compare(&keys[b], order));
+    }
+
+    fn move_sort_column(&mut self, forward: bool) {
+        let count = self.columns.len();
+        let mut column = self.sort_column;
+        for _ in 0..count {
+            column = if forward { (column + 1) % count } else { (column + count - 1) % count };
+            if self.columns[column].is_sortable() {
+                self.sort_column = column;
+                return;
+            }
+        }
+    }
+
+    /// Handles a key, returning whether the table used it.
+    pub fn handle_key(&mut self, code: KeyCode) -> bool {
+        let last = self.rows.len().saturating_sub(1);
+        let page = self.page_size.get().max(1);
+        match code {
+            KeyCode::Enter => self.details_visible = !self.details_visible && !self.rows.is_empty(),
+            KeyCode::Esc if self.details_visible => self.details_visible = false,
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = (self.selected + 1).min(last),
+            KeyCode::PageUp => self.selected = self.selected.saturating_sub(page),
+            KeyCode::PageDown => self.selected = (self.selected + page).min(last),
+            KeyCode::Home => self.selected = 0,
+            KeyCode::End => self.selected = last,
+            KeyCode::Left => self.first_column = self.first_column.saturating_sub(1),
+            KeyCode::Right => self.first_column = (self.first_column + 1).min(self.columns.len().saturating_sub(1)),
+            KeyCode::Char('<') => self.move_sort_column(false),
+            KeyCode::Char('>') => self.move_sort_column(true),
+            KeyCode::Char('s') => self.cycle_sort(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Handles a mouse event at `column`, `row`, returning whether the table
+    /// used it.
+    pub fn handle_mouse(&mut self, kind: MouseEventKind, column: u16, row: u16, double: bool) -> bool {
+        match kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                let body = self.body.get();
+                if !contains(body, column, row) {
+                    return false;
+                }
+                let index = self.offset.get() + (row - body.y) as usize;
+                if index >= self.rows.len() {
+                    return false;
+                }
+                self.selected = index;
+                if double {
+                    self.handle_key(KeyCode::Enter);
+                }
+                true
+            }
+            MouseEventKind::ScrollUp => self.handle_key(KeyCode::Up),
+            MouseEventKind::ScrollDown => self.handle_key(KeyCode::Down),
+            _ => false,
+        }
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect, title: &str, styles: &Styles) {
+        let block = Block::default().borders(Borders::ALL).border_style(styles.border).title(title.to_string());
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let columns = visible_columns(
+            &self.columns.iter().map(|column| column.width).collect::<Vec<_>>(),
+            self.first_column,
+            inner.width,
+        );
+        let height = inner.height.saturating_sub(1) as usize;
+        let offset = scroll_offset(self.selected, self.offset.get(), height);
+        self.offset.set(offset);
+        self.page_size.set(height);
+        self.body.set(Rect { y: inner.y + 1, height: height as u16, ..inner });
+
+        let header = Row::new(columns.clone().map(|index| {
+            let column = &self.columns[index];
+            let arrow = match self.sort {
+                Some((sorted, SortOrder::Ascending)) if sorted == index => " ▲",
+                Some((sorted, SortOrder::Descending)) if sorted == index => " ▼",
+                _ => "",
+            };
+            let mut style = styles.header;
+            if index == self.sort_column && column.is_sortable() {
+                style = style.add_modifier(Modifier::UNDERLINED);
+            }
+            Cell::from(format!("{}{}", column.title, arrow)).style(style)
+        }));
+
+        let rows: Vec<Row> = self.order.iter()
+            .enumerate()
+            .skip(offset)
+            .take(height)
+            .map(|(position, &index)| {
+                let row = &self.rows[index];
+                let cells: Vec<Cell> = columns.clone()
+                    .map(|column| Cell::from(self.columns[column].value(row)))
+                    .collect();
+                let style = if position == self.selected {
+                    styles.selected_row()
+                } else {
+                    self.row_style.as_ref().map_or(Style::default(), |style| style(row, styles))
+                };
+                Row::new(cells).style(style)
+            })
+            .collect();
+
+        let last = columns.end.saturating_sub(1);
+        let widths: Vec<Constraint> = columns.clone()
+            .map(|index| {
+                if index == last {
+                    Constraint::Min(self.columns[index].width)
+                } else {
+                    Constraint::Length(self.columns[index].width)
+                }
+            })
+            .collect();
+
+        let table = Table::new(rows, widths)
+            .header(header)
+            .column_spacing(COLUMN_SPACING);
+        f.render_widget(table, inner);
+
+        if self.details_visible
+            && let Some(row) = self.selected()
+        {
+            self.render_details(f, area, row);
+        }
+    }
+
+    /// The table as lines of text for plain mode: what it is sorted by, one
+    /// line per row in display order ("row 3 of 20: vocals, 8.1"), the
+    /// selected row again, and its details when they are open.
+    pub fn plain_lines(&self) -> Vec<String> {
+        let titles: Vec<&str> = self.columns.iter().map(|column| column.title.as_str()).collect();
+        let sorted = match self.sort {
+            Some((column, SortOrder::Ascending)) => format!(", sorted by {} ascending", self.columns[column].title),
+            Some((column, SortOrder::Descending)) => format!(", sorted by {} descending", self.columns[column].title),
+            None => String::new(),
+        };
+        let mut lines = vec![format!("Columns: {}{}", titles.join(", "), sorted)];
+        if self.rows.is_empty() {
+            lines.push("No rows".to_string());
+            return lines;
+        }
+        lines.extend((0..self.order.len()).filter_map(|position| self.plain_row(position)));
+        if let Some(selected) = self.plain_row(self.selected) {
+            lines.push(format!("Selected: {}", selected));
+        }
+        if self.details_visible
+            && let Some(row) = self.selected()
+        {
+            lines.push("Details:".to_string());
+            lines.extend(self.details(row));
+        }
+        lines
+    }
+
+    fn plain_row(&self, position: usize) -> Option<String> {
+        let row = &self.rows[*self.order.get(position)?];
+        let values: Vec<String> = self.columns.iter().map(|column| column.value(row)).collect();
+        Some(format!("row {} of {}: {}", position + 1, self.order.len(), values.join(", ")))
+    }
+
+    fn details(&self, row: &T) -> Vec<String> {
+        self.columns.iter()
+            .map(|column| format!("{}: {}", column.title, column.value(row)))
+            .collect()
+    }
+
+    fn render_details(&self, f: &mut Frame, area: Rect, row: &T) {
+        let lines = self.details(row);
+        let width = area.width.saturating_sub(4).min(
+            lines.iter().map(|line| line.chars().count() as u16 + 4).max().unwrap_or(0).max(30),
+        );
+        let height = (lines.len() as u16 + 2).min(area.height.saturating_sub(2));
+        let popup = Rect {
+            x: area.x + (area.width.saturating_sub(width)) / 2,
+            y: area.y + (area.height.saturating_sub(height)) / 2,
+            width,
+            height,
+        };
+
+        let details = Paragraph::new(lines.join("\n"))
+            .block(Block::default().borders(Borders::ALL).title("Details (Enter/Esc to close)"))
+            .wrap(Wrap { trim: false });
+        f.render_widget(Clear, popup);
+        f.render_widget(details, popup);
+    }
+}
+
+/// First row to draw so that `selected` stays within `height` rows,
+/// moving the previous offset as little as possible.
+pub fn scroll_offset(selected: usize, offset: usize, height: usize) -> usize {
+    if height == 0 {
+        return selected;
+    }
+    if selected < offset {
+        selected
+    } else if selected >= offset + height {
+        selected + 1 - height
+    } else {
+        offset
+    }
+}
+
+/// Columns from `first` onwards that fit in `width`. At least one column is
+/// always shown, cut off if it has to be.
+pub fn visible_columns(widths: &[u16], first: usize, width: u16) -> Range<usize> {
+    let first = first.min(widths.len());
+    let mut used = 0u16;
+    let mut end = first;
+    for (index, column) in widths.iter().enumerate().skip(first) {
+        let needed = if index == first { *column } else { column + COLUMN_SPACING };
+        if index > first && used + needed > width {
+            break;
+        }
+        used = used.saturating_add(needed);
+        end = index + 1;
+    }
+    first..end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Run {
+        name: &'static str,
+        sdr: Option<f64>,
+    }
+
+    fn table() -> DataTable<Run> {
+        let mut table = DataTable::new(vec![
+            Column::new("Run", 8, |run: &Run| run.name.to_string())
+                .sort_by(|run| run.name.into()),
+            Column::new("SDR", 6, |run: &Run| run.sdr.map_or("-".to_string(), |sdr| format!("{:.1}", sdr)))
+                .sort_by(|run| run.sdr.into()),
+        ]);
+        table.set_rows(vec![
+            Run { name: "beta", sdr: Some(7.5) },
+            Run { name: "alpha", sdr: None },
+            Run { name: "gamma", sdr: Some(9.25) },
+            Run { name: "Delta", sdr: Some(-1.0) },
+        ]);
+        table
+    }
+
+    fn names(table: &DataTable<Run>) -> Vec<&'static str> {
+        table.sorted().map(|run| run.name).collect()
+    }
+
+    #[test]
+    fn sort_cycles_and_keeps_the_selection() {
+        let mut table = table();
+        assert_eq!(names(&table), ["beta", "alpha", "gamma", "Delta"]);
+
+        table.handle_key(KeyCode::Char('s'));
+        assert_eq!(names(&table), ["alpha", "beta", "Delta", "gamma"]);
+        assert_eq!(table.selected().unwrap().name, "beta");
+
+        table.handle_key(KeyCode::Char('>'));
+        table.handle_key(KeyCode::Char('s'));
+        assert_eq!(table.sort(), Some((1, SortOrder::Ascending)));
+        assert_eq!(names(&table), ["Delta", "beta", "gamma", "alpha"]);
+
+        table.handle_key(KeyCode::Char('s'));
+        assert_eq!(names(&table), ["gamma", "beta", "Delta", "alpha"]);
+        assert_eq!(table.selected().unwrap().name, "beta");
+
+        table.handle_key(KeyCode::Char('s'));
+        assert_eq!(table.sort(), None);
+        assert_eq!(names(&table), ["beta", "alpha", "gamma", "Delta"]);
+
+        table.handle_key(KeyCode::End);
+        table.handle_key(KeyCode::Down);
+        assert_eq!(table.selected().unwrap().name, "Delta");
+        table.set_rows(vec![Run { name: "only", sdr: None }]);
+        assert_eq!(table.selected().unwrap().name, "only");
+    }
+
+    #[test]
+    fn viewport_follows_the_selection() {
+        assert_eq!(scroll_offset(0, 0, 5), 0);
+        assert_eq!(scroll_offset(4, 0, 5), 0);
+        assert_eq!(scroll_offset(5, 0, 5), 1);
+        assert_eq!(scroll_offset(12, 3, 5), 8);
+        assert_eq!(scroll_offset(6, 8, 5), 6);
+        assert_eq!(scroll_offset(3, 0, 0), 3);
+
+        let widths = [8, 6, 10, 4];
+        assert_eq!(visible_columns(&widths, 0, 40), 0..4);
+        assert_eq!(visible_columns(&widths, 0, 15), 0..2);
+        assert_eq!(visible_columns(&widths, 0, 14), 0..1);
+        assert_eq!(visible_columns(&widths, 2, 15), 2..4);
+        assert_eq!(visible_columns(&widths, 0, 3), 0..1);
+        assert_eq!(visible_columns(&widths, 9, 40), 4..4);
+    }
+
+    fn render(table: &DataTable<Run>, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|f| table.render(f, f.size(), "Runs", &Styles::default())).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| (0..width).map(|x| buffer.get(x, y).symbol()).collect::<String>())
+            .collect()
+    }
+
+    #[test]
+    fn clicks_select_the_row_under_them() {
+        let mut table = table();
+        render(&table, 20, 5);
+        let click = MouseEventKind::Down(MouseButton::Left);
+        assert!(table.handle_mouse(click, 3, 3, false));
+        assert_eq!(table.selected_index(), 1);
+        assert!(!table.handle_mouse(click, 3, 1, false), "the header");
+        assert!(table.handle_mouse(click, 3, 2, true));
+        assert_eq!((table.selected_index(), table.details_visible()), (0, true));
+        assert!(table.handle_mouse(MouseEventKind::ScrollDown, 0, 0, false));
+        assert_eq!(table.selected_index(), 1);
+    }
+
+    #[test]
+    fn renders_header_rows_and_details() {
+        let mut table = table();
+        table.set_sort(Some((1, SortOrder::Descending)));
+        table.select(3);
+        assert_eq!(
+            render(&table, 20, 5),
+            [
+                "┌Runs──────────────┐",
+                "│Run      SDR ▼    │",
+                "│Delta    -1.0     │",
+                "│alpha    -        │",
+                "└──────────────────┘",
+            ]
+        );
+
+        table.handle_key(KeyCode::Right);
+        table.handle_key(KeyCode::Enter);
+        assert_eq!(
+            render(&table, 34, 8),
+            [
+                "┌Runs────────────────────────────┐",
+                "│SDR ▼                           │",
+                "│-┌Details (Enter/Esc to close)┐ │",
+                "│-│Run: alpha                  │ │",
+                "│ │SDR: -                      │ │",
+                "│ └────────────────────────────┘ │",
+                "│                                │",
+                "└────────────────────────────────┘",
+            ]
+        );
+    }
+
+    #[test]
+    fn plain_lines_label_every_row() {
+        let mut table = table();
+        table.set_sort(Some((1, SortOrder::Descending)));
+        table.select(1);
+        table.handle_key(KeyCode::Enter);
+        assert_eq!(
+            table.plain_lines(),
+            [
+                "Columns: Run, SDR, sorted by SDR descending",
+                "row 1 of 4: gamma, 9.2",
+                "row 2 of 4: beta, 7.5",
+                "row 3 of 4: Delta, -1.0",
+                "row 4 of 4: alpha, -",
+                "Selected: row 2 of 4: beta, 7.5",
+                "Details:",
+                "Run: beta",
+                "SDR: 7.5",
+            ]
+        );
+        assert_eq!(DataTable::<Run>::new(vec![]).plain_lines(), ["Columns: ", "No rows"]);
+    }
+}
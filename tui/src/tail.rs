@@ -0,0 +1,179 @@
+use anyhow::{Context, Result};
+use std::fs::{self, File, Metadata};
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// What changed in a tailed file since the last poll.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TailEvent {
+    Line(String),
+    /// The file got shorter; reading restarts from its beginning.
+    Truncated,
+    /// A new file took the old one's name (log rotation).
+    Rotated,
+    /// The file is gone. The writer may still be running, so this is not
+    /// the end of the run.
+    Missing,
+    Reappeared,
+}
+
+/// Follows a log file another process appends to, like `tail -F`: reads
+/// only what was added since the last poll, and copes with truncation,
+/// rotation and the file going away for a while.
+pub struct LogTail {
+    path: PathBuf,
+    file: Option<File>,
+    position: u64,
+    identity: Option<(u64, u64)>,
+    /// Text after the last newline, held back until the line is complete.
+    partial: Vec<u8>,
+    missing: bool,
+}
+
+impl LogTail {
+    /// Opens `path` positioned at its end, or at its start with
+    /// `from_start` to replay what is already there.
+    pub fn open(path: &Path, from_start: bool) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open log {}", path.display()))?;
+        let metadata = file.metadata().context("Failed to read log metadata")?;
+        Ok(LogTail {
+            path: path.to_path_buf(),
+            position: if from_start { 0 } else { metadata.len() },
+            identity: identity(&metadata),
+            file: Some(file),
+            partial: vec![],
+            missing: false,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn poll(&mut self) -> Result<Vec<TailEvent>> {
+        let mut events = vec![];
+        let metadata = match fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                if !self.missing {
+                    // Whatever the writer added before the file went away.
+                    self.read_new(&mut events)?;
+                    self.missing = true;
+                    self.file = None;
+                    events.push(TailEvent::Missing);
+                }
+                return Ok(events);
+            }
+            Err(e) => return Err(e).context("Failed to read log metadata"),
+        };
+
+        if self.missing {
+            self.missing = false;
+            events.push(TailEvent::Reappeared);
+            self.reopen(&metadata)?;
+        } else if identity(&metadata) != self.identity {
+            self.read_new(&mut events)?;
+            events.push(TailEvent::Rotated);
+            self.reopen(&metadata)?;
+        } else if metadata.len() < self.position {
+            events.push(TailEvent::Truncated);
+            self.position = 0;
+            self.partial.clear();
+        }
+
+        self.read_new(&mut events)?;
+        Ok(events)
+    }
+
+    fn reopen(&mut self, metadata: &Metadata) -> Result<()> {
+        self.file = Some(File::open(&self.path)
+            .with_context(|| format!("Failed to open log {}", self.path.display()))?);
+        self.identity = identity(metadata);
+        self.position = 0;
+        self.partial.clear();
+        Ok(())
+    }
+
+    fn read_new(&mut self, events: &mut Vec<TailEvent>) -> Result<()> {
+        let Some(file) = self.file.as_mut() else {
+            return Ok(());
+        };
+        file.seek(SeekFrom::Start(self.position)).context("Failed to seek in log")?;
+        let mut added = vec![];
+        file.read_to_end(&mut added).context("Failed to read log")?;
+        self.position += added.len() as u64;
+
+        self.partial.extend(added);
+        let Some(end) = self.partial.iter().rposition(|&byte| byte == b'\n') else {
+            return Ok(());
+        };
+        let complete: Vec<u8> = self.partial.drain(..=end).collect();
+        for line in String::from_utf8_lossy(&complete).lines() {
+            events.push(TailEvent::Line(line.trim_end_matches('\r').to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn identity(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+/// Without inode numbers only truncation is detected.
+#[cfg(not(unix))]
+fn identity(_metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn append(path: &Path, text: &str) {
+        fs::OpenOptions::new().create(true).append(true).open(path).unwrap().write_all(text.as_bytes()).unwrap();
+    }
+
+    fn lines(events: &[TailEvent]) -> Vec<&str> {
+        events.iter()
+            .filter_map(|event| match event {
+                TailEvent::Line(line) => Some(line.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn follows_appends_truncation_rotation_and_removal() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("train.log");
+        append(&log, "old line\n");
+
+        let mut tail = LogTail::open(&log, false).unwrap();
+        assert!(tail.poll().unwrap().is_empty(), "starts at the end");
+
+        append(&log, "Epoch 1\nhalf a li");
+        assert_eq!(lines(&tail.poll().unwrap()), ["Epoch 1"]);
+        append(&log, "ne\r\n");
+        assert_eq!(lines(&tail.poll().unwrap()), ["half a line"]);
+
+        fs::write(&log, "fresh\n").unwrap();
+        assert_eq!(tail.poll().unwrap(), [TailEvent::Truncated, TailEvent::Line("fresh".into())]);
+
+        append(&log, "last before rotation\n");
+        fs::rename(&log, dir.path().join("train.log.1")).unwrap();
+        append(&log, "after rotation\n");
+        let events = tail.poll().unwrap();
+        assert_eq!(lines(&events), ["last before rotation", "after rotation"]);
+        assert!(events.contains(&TailEvent::Rotated));
+
+        fs::remove_file(&log).unwrap();
+        assert_eq!(tail.poll().unwrap(), [TailEvent::Missing]);
+        assert!(tail.poll().unwrap().is_empty(), "reported once");
+        append(&log, "back\n");
+        assert_eq!(tail.poll().unwrap(), [TailEvent::Reappeared, TailEvent::Line("back".into())]);
+    }
+}
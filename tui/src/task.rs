@@ -0,0 +1,315 @@
+use anyhow::{anyhow, Result};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+/// Returned (inside an `anyhow::Error`) by `TaskContext::check` once the
+/// task has been cancelled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Cancelled by hand, or along with the token it was made a `child` of.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    flag: Arc<AtomicBool>,
+    parent: Option<Arc<CancelToken>>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A token cancelled with this one, that can also be cancelled alone.
+    pub fn child(&self) -> Self {
+        CancelToken { flag: Arc::default(), parent: Some(Arc::new(self.clone())) }
+    }
+
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::Relaxed) || self.parent.as_ref().is_some_and(|parent| parent.is_cancelled())
+    }
+}
+
+/// How far a task got. `total` is `None` when only items-done is known.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Progress {
+    pub done: u64,
+    pub total: Option<u64>,
+    pub finished: bool,
+}
+
+impl Progress {
+    pub fn fraction(&self) -> Option<f64> {
+        match self.total {
+            Some(0) => Some(1.0),
+            Some(total) => Some((self.done as f64 / total as f64).min(1.0)),
+            None => None,
+        }
+    }
+}
+
+/// What a cancelled task hands back.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OnCancel {
+    /// Partial results are meaningless (a hash of half a file): drop them.
+    Discard,
+    /// Partial results are still useful (the files scanned so far): keep
+    /// whatever the work returned after it noticed the cancel.
+    KeepPartial,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TaskOutcome<T> {
+    Completed(T),
+    /// Cancelled; carries the partial result for `OnCancel::KeepPartial`
+    /// tasks that returned one.
+    Cancelled(Option<T>),
+}
+
+/// Handed to the work closure to report progress and poll for cancellation.
+#[derive(Debug, Clone, Default)]
+pub struct TaskContext {
+    token: CancelToken,
+    progress: Arc<Mutex<Progress>>,
+    /// Set once the work has seen the cancel, so a cancel that arrives after
+    /// the work already finished doesn't throw away a complete result.
+    observed: Arc<AtomicBool>,
+}
+
+impl TaskContext {
+    /// A context for running work inline, cancelled through `token`.
+    pub fn new(token: CancelToken) -> Self {
+        TaskContext { token, ..Default::default() }
+    }
+
+    pub fn set_total(&self, total: u64) {
+        self.update(|progress| progress.total = Some(total));
+    }
+
+    pub fn advance(&self, items: u64) {
+        self.update(|progress| progress.done += items);
+    }
+
+    pub fn set_done(&self, done: u64) {
+        self.update(|progress| progress.done = done);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        let cancelled = self.token.is_cancelled();
+        if cancelled {
+            self.observed.store(true, Ordering::Relaxed);
+        }
+        cancelled
+    }
+
+    /// `Err(Cancelled)` once cancelled, for work that just stops.
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(Cancelled.into());
+        }
+        Ok(())
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Progress)) {
+        f(&mut self.progress.lock().unwrap_or_else(|e| e.into_inner()));
+    }
+}
+
+/// Cheap view of a running task for the status row: progress and a way to
+/// cancel, without the result type.
+#[derive(Debug, Clone)]
+pub struct TaskMonitor {
+    pub name: String,
+    pub on_cancel: OnCancel,
+    context: TaskContext,
+}
+
+impl TaskMonitor {
+    pub fn progress(&self) -> Progress {
+        *self.context.progress.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn cancel(&self) {
+        self.context.token.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.context.token.is_cancelled()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.progress().finished
+    }
+}
+
+/// Marks the task finished however the work ends, panics included, so the
+/// status row never waits on a dead thread.
+struct FinishOnDrop(TaskContext);
+
+impl Drop for FinishOnDrop {
+    fn drop(&mut self) {
+        self.0.update(|progress| progress.finished = true);
+    }
+}
+
+/// CPU/IO-heavy work (hashing, scanning, zipping) on its own thread, so the
+/// UI keeps drawing. Progress and cancellation go through `TaskContext`.
+pub struct Task<T> {
+    monitor: TaskMonitor,
+    thread: Option<JoinHandle<Result<T>>>,
+}
+
+impl<T: Send + 'static> Task<T> {
+    pub fn spawn(name: &str, on_cancel: OnCancel, work: impl FnOnce(&TaskContext) -> Result<T> + Send + 'static) -> Self {
+        Task::spawn_under(&CancelToken::new(), name, on_cancel, work)
+    }
+
+    /// Like `spawn`, also cancelled when `parent` is, e.g. at shutdown.
+    pub fn spawn_under(parent: &CancelToken, name: &str, on_cancel: OnCancel, work: impl FnOnce(&TaskContext) -> Result<T> + Send + 'static) -> Self {
+        let context = TaskContext::new(parent.child());
+        let worker = context.clone();
+        let thread = std::thread::spawn(move || {
+            let _finished = FinishOnDrop(worker.clone());
+            work(&worker)
+        });
+        Task {
+            monitor: TaskMonitor { name: name.to_string(), on_cancel, context },
+            thread: Some(thread),
+        }
+    }
+
+    pub fn monitor(&self) -> TaskMonitor {
+        self.monitor.clone()
+    }
+
+    pub fn cancel(&self) {
+        self.monitor.cancel();
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.thread.as_ref().is_none_or(|thread| thread.is_finished())
+    }
+
+    /// Waits for the task to end.
+    pub fn join(mut self) -> Result<TaskOutcome<T>> {
+        self.finish()
+    }
+
+    /// The outcome if the task has ended, without blocking. Returns `None`
+    /// while it runs, and after the outcome has been taken once.
+    pub fn try_join(&mut self) -> Option<Result<TaskOutcome<T>>> {
+        if self.thread.as_ref()?.is_finished() {
+            Some(self.finish())
+        } else {
+            None
+        }
+    }
+
+    fn finish(&mut self) -> Result<TaskOutcome<T>> {
+        let thread = self.thread.take().ok_or_else(|| anyhow!("Task '{}' already joined", self.monitor.name))?;
+        let result = thread.join()
+            .map_err(|_| anyhow!("Task '{}' panicked", self.monitor.name))?;
+        let observed = self.monitor.context.observed.load(Ordering::Relaxed);
+        match result {
+            Ok(value) if !observed => Ok(TaskOutcome::Completed(value)),
+            Ok(value) => Ok(TaskOutcome::Cancelled(match self.monitor.on_cancel {
+                OnCancel::Discard => None,
+                OnCancel::KeepPartial => Some(value),
+            })),
+            Err(e) if e.is::<Cancelled>() => Ok(TaskOutcome::Cancelled(None)),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    /// Counts to 100, pausing at 10 until the test says go, so the cancel
+    /// lands mid-way at a known point.
+    fn counting_task(on_cancel: OnCancel, stop_with_error: bool) -> (Task<Vec<u32>>, mpsc::Receiver<()>, mpsc::Sender<()>) {
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let (go_tx, go_rx) = mpsc::channel::<()>();
+        let task = Task::spawn("count", on_cancel, move |ctx| {
+            ctx.set_total(100);
+            let mut seen = vec![];
+            for i in 0..100 {
+                if i == 10 {
+                    ready_tx.send(()).unwrap();
+                    go_rx.recv().unwrap();
+                }
+                if stop_with_error {
+                    ctx.check()?;
+                } else if ctx.is_cancelled() {
+                    break;
+                }
+                seen.push(i);
+                ctx.advance(1);
+            }
+            Ok(seen)
+        });
+        (task, ready_rx, go_tx)
+    }
+
+    #[test]
+    fn cancel_mid_way_discards_or_keeps_partial_results() {
+        let (task, ready, go) = counting_task(OnCancel::Discard, true);
+        let monitor = task.monitor();
+        ready.recv().unwrap();
+        assert_eq!(monitor.progress().fraction(), Some(0.1));
+        task.cancel();
+        go.send(()).unwrap();
+        assert_eq!(task.join().unwrap(), TaskOutcome::Cancelled(None));
+        assert!(monitor.is_finished());
+        assert_eq!(monitor.progress().done, 10);
+
+        let (task, ready, go) = counting_task(OnCancel::KeepPartial, false);
+        ready.recv().unwrap();
+        task.cancel();
+        go.send(()).unwrap();
+        assert_eq!(task.join().unwrap(), TaskOutcome::Cancelled(Some((0..10).collect())));
+
+        // Discard drops a partial result even when the work returns one.
+        let (task, ready, go) = counting_task(OnCancel::Discard, false);
+        ready.recv().unwrap();
+        task.cancel();
+        go.send(()).unwrap();
+        assert_eq!(task.join().unwrap(), TaskOutcome::Cancelled(None));
+
+        // Children go with their parent, not the other way round.
+        let parent = CancelToken::new();
+        let (first, second) = (parent.child(), parent.child().child());
+        first.cancel();
+        assert!(!parent.is_cancelled() && !second.is_cancelled());
+        parent.cancel();
+        assert!(second.is_cancelled());
+    }
+
+    #[test]
+    fn uncancelled_tasks_complete() {
+        let (mut task, ready, go) = counting_task(OnCancel::KeepPartial, true);
+        ready.recv().unwrap();
+        assert!(task.try_join().is_none());
+        go.send(()).unwrap();
+        let outcome = task.join().unwrap();
+        assert_eq!(outcome, TaskOutcome::Completed((0..100).collect()));
+
+        let failing = Task::<()>::spawn("fail", OnCancel::Discard, |_| Err(anyhow!("disk on fire")));
+        assert_eq!(format!("{:#}", failing.join().unwrap_err()), "disk on fire");
+    }
+}
@@ -0,0 +1,255 @@
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+
+use crate::model::{ModelType, TrainingConfig};
+use crate::priority::ProcessPriority;
+use crate::run_dir::{RunDir, CONFIG_SNAPSHOT};
+use crate::shell_words;
+
+/// The fields of a duplicated run that can be edited, in display order.
+pub const FIELDS: [&str; 9] = [
+    "config_path",
+    "start_checkpoint",
+    "results_path",
+    "data_paths",
+    "valid_path",
+    "num_workers",
+    "device_ids",
+    "label",
+    "extra_args",
+];
+
+/// Rebuilds the config a training run was started with from its
+/// `run.json`. The model config is the run's snapshot, so the copy runs
+/// what the run ran even if the original file changed since. The wandb key
+/// is redacted in `run.json` and has to be set again. Extra arguments come
+/// last, so the first word that isn't a known flag starts them.
+pub fn training_config(run: &RunDir) -> Result<TrainingConfig> {
+    let (priority, command) = ProcessPriority::strip(&run.metadata.command);
+    // The training script is the first python file, whatever the checkout
+    // calls it (see `project::TRAIN_SCRIPTS`).
+    let script = command.args.iter()
+        .position(|arg| arg.ends_with(".py"))
+        .context("run.json doesn't record a training script command")?;
+
+    let mut config = TrainingConfig {
+        model_type: run.metadata.model_type.clone(),
+        config_path: run.path().join(CONFIG_SNAPSHOT).to_string_lossy().to_string(),
+        start_checkpoint: None,
+        results_path: run.path().parent().unwrap_or(Path::new(".")).to_string_lossy().to_string(),
+        data_paths: vec![],
+        valid_path: None,
+        num_workers: None,
+        device_ids: None,
+        force_cpu: false,
+        label: run.metadata.label.clone(),
+        wandb_key: None,
+        lint_overrides: vec![],
+        priority,
+        derived_from: Some(run.metadata.name.clone()),
+        extra_args: vec![],
+    };
+    let mut args = command.args[script + 1..].iter();
+    while let Some(flag) = args.next() {
+        if flag == "--force_cpu" {
+            config.force_cpu = true;
+            continue;
+        }
+        const KNOWN: [&str; 9] = [
+            "--model_type", "--config_path", "--start_check_point", "--results_path", "--data_path",
+            "--valid_path", "--num_workers", "--device_ids", "--wandb_key",
+        ];
+        if !KNOWN.contains(&flag.as_str()) {
+            config.extra_args = std::iter::once(flag).chain(args).cloned().collect();
+            break;
+        }
+        let value = args.next().with_context(|| format!("{} has no value in run.json", flag))?;
+        match flag.as_str() {
+            "--model_type" => {
+                config.model_type = ModelType::all_models().into_iter()
+                    .find(|model| model.key() == value)
+                    .with_context(|| format!("Unknown model type {}", value))?;
+            }
+            "--start_check_point" => config.start_checkpoint = Some(value.clone()),
+            "--data_path" => config.data_paths.push(value.clone()),
+            "--valid_path" => config.valid_path = Some(value.clone()),
+            "--num_workers" => config.num_workers = Some(value.parse().context("Invalid num_workers in run.json")?),
+            "--device_ids" => config.device_ids = Some(parse_ids(value)?),
+            // --config_path is replaced by the snapshot, --results_path by
+            // a new run directory, and --wandb_key is redacted.
+            _ => {}
+        }
+    }
+    Ok(config)
+}
+
+fn parse_ids(text: &str) -> Result<Vec<usize>> {
+    text.split(',')
+        .map(|id| id.trim().parse().with_context(|| format!("Invalid device id {:?}", id.trim())))
+        .collect()
+}
+
+/// A training config copied from an earlier run, being edited for a new
+/// one. Keeps the original to show what changed.
+#[derive(Debug, Clone)]
+pub struct RunTemplate {
+    original: TrainingConfig,
+    pub config: TrainingConfig,
+}
+
+impl RunTemplate {
+    pub fn from_run(run: &RunDir) -> Result<Self> {
+        let config = training_config(run)?;
+        Ok(RunTemplate { original: config.clone(), config })
+    }
+
+    /// The run this template was copied from.
+    pub fn source(&self) -> &str {
+        self.original.derived_from.as_deref().unwrap_or_default()
+    }
+
+    pub fn value(&self, field: usize) -> String {
+        field_value(&self.config, FIELDS[field])
+    }
+
+    pub fn changed(&self, field: usize) -> bool {
+        field_value(&self.config, FIELDS[field]) != field_value(&self.original, FIELDS[field])
+    }
+
+    pub fn set(&mut self, field: usize, text: &str) -> Result<()> {
+        set_field(&mut self.config, FIELDS.get(field).copied().unwrap_or_default(), text)
+    }
+
+    /// One line per field, "*" marking the ones that differ from the
+    /// original run along with what it had.
+    pub fn lines(&self) -> Vec<String> {
+        (0..FIELDS.len())
+            .map(|field| {
+                let value = self.value(field);
+                if self.changed(field) {
+                    format!("* {:<17}{}  (was: {})", FIELDS[field], value, field_value(&self.original, FIELDS[field]))
+                } else {
+                    format!("  {:<17}{}", FIELDS[field], value)
+                }
+            })
+            .collect()
+    }
+
+    /// Files the config points at that aren't there, e.g. a dataset moved
+    /// since the original run or a deleted checkpoint. Relative paths are
+    /// under `root`.
+    pub fn missing_files(&self, root: &Path) -> Vec<String> {
+        let config = &self.config;
+        let mut paths = vec![("config_path", config.config_path.as_str())];
+        paths.extend(config.start_checkpoint.as_deref().map(|path| ("start_checkpoint", path)));
+        paths.extend(config.data_paths.iter().map(|path| ("data_paths", path.as_str())));
+        paths.extend(config.valid_path.as_deref().map(|path| ("valid_path", path)));
+        paths.into_iter()
+            .filter(|(_, path)| !root.join(path).exists())
+            .map(|(field, path)| format!("{}: {} not found", field, path))
+            .collect()
+    }
+}
+
+/// The text form of a `FIELDS` field of `config`; unset ones are empty.
+pub fn field_value(config: &TrainingConfig, field: &str) -> String {
+    let ids = |ids: &[usize]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(",");
+    match field {
+        "config_path" => config.config_path.clone(),
+        "start_checkpoint" => config.start_checkpoint.clone().unwrap_or_default(),
+        "results_path" => config.results_path.clone(),
+        "data_paths" => config.data_paths.join(", "),
+        "valid_path" => config.valid_path.clone().unwrap_or_default(),
+        "num_workers" => config.num_workers.map(|n| n.to_string()).unwrap_or_default(),
+        "device_ids" => config.device_ids.as_deref().map(ids).unwrap_or_default(),
+        "label" => config.label.clone().unwrap_or_default(),
+        "extra_args" => shell_words::join(&config.extra_args),
+        _ => String::new(),
+    }
+}
+
+/// Sets a `FIELDS` field from its text form; lists are comma-separated,
+/// extra_args is split as a shell would, and an empty text clears an
+/// optional field rather than setting "".
+pub fn set_field(config: &mut TrainingConfig, field: &str, text: &str) -> Result<()> {
+    let text = text.trim();
+    let optional = |text: &str| (!text.is_empty()).then(|| text.to_string());
+    match field {
+        "config_path" if text.is_empty() => bail!("config_path is required"),
+        "config_path" => config.config_path = text.to_string(),
+        "start_checkpoint" => config.start_checkpoint = optional(text),
+        "results_path" if text.is_empty() => bail!("results_path is required"),
+        "results_path" => config.results_path = text.to_string(),
+        "data_paths" => {
+            config.data_paths = text.split(',').map(str::trim).filter(|path| !path.is_empty()).map(String::from).collect();
+        }
+        "valid_path" => config.valid_path = optional(text),
+        "num_workers" if text.is_empty() => config.num_workers = None,
+        "num_workers" => config.num_workers = Some(text.parse().context("num_workers must be a number")?),
+        "device_ids" if text.is_empty() => config.device_ids = None,
+        "device_ids" => config.device_ids = Some(parse_ids(text)?),
+        "label" => config.label = optional(text),
+        "extra_args" => config.extra_args = shell_words::split(text)?,
+        _ => bail!("No such field"),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::priority::{IoClass, PriorityTools};
+    use crate::training::training_command;
+    use std::fs;
+
+    #[test]
+    fn duplicate_reconstructs_the_run_and_tracks_edits() {
+        let dir = tempfile::tempdir().unwrap();
+        let model_config = dir.path().join("model.yaml");
+        fs::write(&model_config, "audio:\n  sample_rate: 44100\n").unwrap();
+        let dataset = dir.path().join("dataset");
+        fs::create_dir(&dataset).unwrap();
+        let original = TrainingConfig {
+            model_type: ModelType::ScNet,
+            config_path: model_config.to_string_lossy().to_string(),
+            start_checkpoint: Some(dir.path().join("gone.ckpt").to_string_lossy().to_string()),
+            results_path: dir.path().to_string_lossy().to_string(),
+            data_paths: vec![dataset.to_string_lossy().to_string()],
+            valid_path: None,
+            num_workers: Some(4),
+            device_ids: Some(vec![0, 1]),
+            force_cpu: false,
+            label: Some("vocals".into()),
+            wandb_key: None,
+            lint_overrides: vec![],
+            priority: ProcessPriority { nice: Some(10), io_class: Some(IoClass::Idle), cpu_cores: vec![] },
+            derived_from: None,
+            extra_args: vec!["--metrics".into(), "sdr".into(), "--pre_valid".into()],
+        };
+        let path = dir.path().join("run_a");
+        let tools = PriorityTools { nice: true, ionice: true, taskset: true };
+        let command = original.priority.apply(training_command(&original, &path, "python", "train.py").unwrap(), tools);
+        let run = RunDir::create(&path, &original.config_path, original.label.clone(), ModelType::ScNet, command).unwrap();
+
+        let mut template = RunTemplate::from_run(&run).unwrap();
+        assert_eq!(template.source(), "run_a");
+        assert_eq!(template.config.config_path, path.join(CONFIG_SNAPSHOT).to_string_lossy());
+        assert_eq!(template.config.data_paths, original.data_paths);
+        assert_eq!(template.config.device_ids, Some(vec![0, 1]));
+        assert_eq!(template.config.priority, original.priority);
+        assert_eq!(template.config.extra_args, original.extra_args);
+        assert_eq!(template.missing_files(Path::new("")), [format!("start_checkpoint: {} not found", dir.path().join("gone.ckpt").display())]);
+        assert!(template.lines().iter().all(|line| line.starts_with("  ")));
+
+        template.set(3, "/new/dataset").unwrap();
+        assert!(template.changed(3));
+        assert!(template.lines()[3].starts_with("* data_paths"));
+        assert!(template.lines()[3].ends_with(&format!("(was: {})", dataset.display())));
+        assert!(template.missing_files(Path::new("")).contains(&"data_paths: /new/dataset not found".to_string()));
+        assert!(template.set(5, "many").is_err());
+        template.set(8, "--metrics sdr 'l1 freq'").unwrap();
+        assert_eq!(template.config.extra_args, ["--metrics", "sdr", "l1 freq"]);
+        assert_eq!(template.value(8), "--metrics sdr 'l1 freq'");
+        assert!(template.set(8, "--metrics 'sdr").is_err());
+    }
+}
@@ -0,0 +1,153 @@
+use crossterm::{
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::io;
+use std::panic::{self, PanicHookInfo};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Once;
+
+const NOT_SET_UP: u8 = 0;
+const RAW: u8 = 1;
+const FULL_SCREEN: u8 = 2;
+
+/// What the terminal was put into, so that whichever of the panic hook and
+/// the guard's drop comes first puts it back, and only once.
+static STATE: AtomicU8 = AtomicU8::new(NOT_SET_UP);
+
+/// Raw mode, with the alternate screen unless in plain mode, for as long as
+/// it lives. Dropping it puts the terminal back, on every way out of
+/// `App::run`: quitting, an error returned with `?`, or a panic.
+pub struct TerminalGuard {
+    full_screen: bool,
+    mouse: bool,
+}
+
+impl TerminalGuard {
+    /// Sets the terminal up and installs the panic hook.
+    pub fn enter(full_screen: bool) -> io::Result<Self> {
+        install_panic_hook();
+        set_up(full_screen)?;
+        Ok(TerminalGuard { full_screen, mouse: false })
+    }
+
+    /// Turns mouse capture on or off, if it isn't already.
+    pub fn set_mouse(&mut self, on: bool) {
+        if self.mouse != on && self.full_screen {
+            self.mouse = on;
+            let _ = if on { execute!(io::stdout(), EnableMouseCapture) } else { execute!(io::stdout(), DisableMouseCapture) };
+        }
+    }
+
+    /// Hands the terminal to `run` as the shell left it, e.g. for an
+    /// external player, and takes it back afterwards.
+    pub fn suspend<T>(&mut self, run: impl FnOnce() -> T) -> io::Result<T> {
+        restore();
+        self.mouse = false;
+        let result = run();
+        set_up(self.full_screen)?;
+        Ok(result)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+fn set_up(full_screen: bool) -> io::Result<()> {
+    if let Err(e) = enable_raw_mode() {
+        eprintln!("Failed to enable raw mode: {}", e);
+        return Err(e);
+    }
+    STATE.store(RAW, Ordering::SeqCst);
+    if full_screen {
+        if let Err(e) = execute!(io::stdout(), EnterAlternateScreen) {
+            eprintln!("Failed to enter alternate screen: {}", e);
+            restore();
+            return Err(e);
+        }
+        STATE.store(FULL_SCREEN, Ordering::SeqCst);
+    }
+    let _ = execute!(io::stdout(), EnableBracketedPaste);
+    Ok(())
+}
+
+/// Undoes `set_up`; does nothing if the terminal wasn't set up.
+fn restore() {
+    match STATE.swap(NOT_SET_UP, Ordering::SeqCst) {
+        FULL_SCREEN => {
+            let _ = disable_raw_mode();
+            let _ = execute!(io::stdout(), DisableMouseCapture, DisableBracketedPaste, LeaveAlternateScreen);
+        }
+        RAW => {
+            let _ = disable_raw_mode();
+            let _ = execute!(io::stdout(), DisableBracketedPaste);
+        }
+        _ => {}
+    }
+}
+
+/// Puts the terminal back before a panic is reported, then writes the
+/// report with a backtrace to stderr, where the shell shows it. Installed
+/// once; other hooks are replaced.
+pub fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| panic::set_hook(report_panics(|report| eprintln!("{}", report))));
+}
+
+/// A panic hook that restores the terminal and passes the report to `out`.
+pub fn report_panics(out: impl Fn(&str) + Send + Sync + 'static) -> Box<dyn Fn(&PanicHookInfo) + Send + Sync + 'static> {
+    Box::new(move |info| {
+        restore();
+        out(&panic_report(info, &Backtrace::force_capture()));
+    })
+}
+
+/// E.g. "The TUI panicked at src/ui.rs:120:5: index out of bounds", then
+/// the backtrace.
+pub fn panic_report(info: &PanicHookInfo, backtrace: &Backtrace) -> String {
+    let place = info.location().map(|location| format!(" at {}", location)).unwrap_or_default();
+    format!("The TUI panicked{}: {}\n\nBacktrace:\n{}", place, panic_message(info.payload()), backtrace)
+}
+
+/// The message a panic was raised with.
+pub fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload.downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("(no message)")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{backend::TestBackend, Terminal};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn a_panic_while_drawing_is_reported_with_its_message() {
+        let reports = Arc::new(Mutex::new(vec![]));
+        let sink = reports.clone();
+        let previous = panic::take_hook();
+        panic::set_hook(report_panics(move |report| sink.lock().unwrap().push(report.to_string())));
+
+        let mut terminal = Terminal::new(TestBackend::new(20, 5)).unwrap();
+        let outcome = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            terminal.draw(|_| panic!("drawing row {} failed", 3)).unwrap();
+        }));
+        panic::set_hook(previous);
+
+        let payload = outcome.unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "drawing row 3 failed");
+        let reports = reports.lock().unwrap();
+        let report = reports.iter().find(|report| report.contains("drawing row 3 failed")).unwrap();
+        assert!(report.starts_with("The TUI panicked at src/terminal.rs:"), "{}", report);
+        assert!(report.contains("Backtrace:"), "{}", report);
+        assert_eq!(STATE.load(Ordering::SeqCst), NOT_SET_UP);
+    }
+}
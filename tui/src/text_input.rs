@@ -0,0 +1,186 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::Rect,
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+const CURSOR: char = '▏';
+
+/// Editable text with a cursor, single-line (filters, names) or multi-line
+/// (notes, pasted logs). Rendering wraps long lines.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextInput {
+    text: String,
+    /// Byte offset into `text`, always on a char boundary.
+    cursor: usize,
+    multiline: bool,
+}
+
+impl TextInput {
+    pub fn single_line() -> Self {
+        TextInput::default()
+    }
+
+    pub fn multi_line() -> Self {
+        TextInput { multiline: true, ..Default::default() }
+    }
+
+    /// Replaces the text and puts the cursor at the end.
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.set_text(text);
+        self
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn set_text(&mut self, text: &str) {
+        self.text = self.clean(text);
+        self.cursor = self.text.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Inserts at the cursor, e.g. a bracketed paste.
+    pub fn insert_str(&mut self, text: &str) {
+        let text = self.clean(text);
+        self.text.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+    }
+
+    /// Normalises line endings, and flattens newlines in single-line mode.
+    fn clean(&self, text: &str) -> String {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        if self.multiline { text } else { text.replace('\n', " ") }
+    }
+
+    /// Handles an editing key, returning whether it was used. Enter only
+    /// inserts a newline in multi-line mode; callers decide what else it means.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+            return false;
+        }
+        match key.code {
+            KeyCode::Char(c) => self.insert_str(c.encode_utf8(&mut [0; 4])),
+            KeyCode::Enter if self.multiline => self.insert_str("\n"),
+            KeyCode::Tab if self.multiline => self.insert_str("\t"),
+            KeyCode::Backspace => {
+                if let Some(previous) = self.previous_boundary() {
+                    self.text.drain(previous..self.cursor);
+                    self.cursor = previous;
+                }
+            }
+            KeyCode::Delete => {
+                if let Some(next) = self.next_boundary() {
+                    self.text.drain(self.cursor..next);
+                }
+            }
+            KeyCode::Left => self.cursor = self.previous_boundary().unwrap_or(self.cursor),
+            KeyCode::Right => self.cursor = self.next_boundary().unwrap_or(self.cursor),
+            KeyCode::Home => self.cursor = self.line_start(),
+            KeyCode::End => self.cursor = self.line_end(),
+            KeyCode::Up if self.multiline => self.move_line(false),
+            KeyCode::Down if self.multiline => self.move_line(true),
+            _ => return false,
+        }
+        true
+    }
+
+    fn previous_boundary(&self) -> Option<usize> {
+        self.text[..self.cursor].char_indices().next_back().map(|(i, _)| i)
+    }
+
+    fn next_boundary(&self) -> Option<usize> {
+        self.text[self.cursor..].chars().next().map(|c| self.cursor + c.len_utf8())
+    }
+
+    fn line_start(&self) -> usize {
+        self.text[..self.cursor].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    fn line_end(&self) -> usize {
+        self.text[self.cursor..].find('\n').map_or(self.text.len(), |i| self.cursor + i)
+    }
+
+    /// Moves to the same column on the next or previous line, or to the end
+    /// of that line if it is shorter.
+    fn move_line(&mut self, down: bool) {
+        let start = self.line_start();
+        let column = self.text[start..self.cursor].chars().count();
+        let target_start = if down {
+            let end = self.line_end();
+            if end == self.text.len() {
+                return;
+            }
+            end + 1
+        } else {
+            if start == 0 {
+                return;
+            }
+            self.text[..start - 1].rfind('\n').map_or(0, |i| i + 1)
+        };
+        let target_end = self.text[target_start..].find('\n').map_or(self.text.len(), |i| target_start + i);
+        self.cursor = self.text[target_start..target_end]
+            .char_indices()
+            .nth(column)
+            .map_or(target_end, |(i, _)| target_start + i);
+    }
+
+    /// The text with a cursor mark inserted, as drawn.
+    pub fn display(&self) -> String {
+        let mut shown = self.text.clone();
+        shown.insert(self.cursor, CURSOR);
+        shown
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect, title: &str) {
+        let input = Paragraph::new(self.display())
+            .block(Block::default().borders(Borders::ALL).title(title.to_string()))
+            .wrap(Wrap { trim: false });
+        f.render_widget(input, area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(input: &mut TextInput, codes: &[KeyCode]) {
+        for code in codes {
+            input.handle_key(KeyEvent::from(*code));
+        }
+    }
+
+    #[test]
+    fn edits_at_the_cursor() {
+        let mut input = TextInput::multi_line().with_text("first\nsecond line");
+        press(&mut input, &[KeyCode::Up, KeyCode::Backspace, KeyCode::Char('X')]);
+        assert_eq!(input.display(), "firsX▏\nsecond line");
+
+        press(&mut input, &[KeyCode::Home, KeyCode::Delete, KeyCode::Down]);
+        press(&mut input, &[KeyCode::Right; 4]);
+        press(&mut input, &[KeyCode::Char('é'), KeyCode::Left, KeyCode::Left]);
+        assert_eq!(input.text(), "irsX\nsecoénd line");
+        assert_eq!(input.display(), "irsX\nsec▏oénd line");
+
+        press(&mut input, &[KeyCode::End, KeyCode::Enter, KeyCode::Char('3')]);
+        assert_eq!(input.text(), "irsX\nsecoénd line\n3");
+    }
+
+    #[test]
+    fn single_line_input_flattens_newlines() {
+        let mut input = TextInput::single_line();
+        input.insert_str("a\r\nb");
+        assert!(!input.handle_key(KeyEvent::from(KeyCode::Enter)));
+        assert_eq!(input.text(), "a b");
+    }
+}
@@ -0,0 +1,111 @@
+use ratatui::style::{Color, Modifier, Style};
+
+use crate::config::Theme;
+
+/// What the screens draw with, picked by `Theme`. Draw code asks for a
+/// role (a title, the selected item, an error) rather than a color.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Styles {
+    /// Screen titles and their frames.
+    pub title: Style,
+    /// The selected list item or table row.
+    pub highlight: Style,
+    pub text: Style,
+    /// Frames of lists and panels.
+    pub border: Style,
+    pub error: Style,
+    pub warning: Style,
+    /// Something that got better, e.g. a higher SDR.
+    pub success: Style,
+    /// Gauges and the training loss line.
+    pub progress: Style,
+    pub header: Style,
+}
+
+impl Default for Styles {
+    fn default() -> Self {
+        Self::new(&Theme::default())
+    }
+}
+
+impl Styles {
+    pub fn new(theme: &Theme) -> Self {
+        let plain = Style::default();
+        match theme {
+            Theme::Dark => Styles {
+                title: plain.fg(Color::Cyan),
+                highlight: plain.fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                text: plain.fg(Color::White),
+                border: plain,
+                error: plain.fg(Color::Red),
+                warning: plain.fg(Color::Yellow),
+                success: plain.fg(Color::Green),
+                progress: plain.fg(Color::Cyan),
+                header: plain.fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            },
+            // Yellow and cyan wash out on a white background.
+            Theme::Light => Styles {
+                title: plain.fg(Color::Blue),
+                highlight: plain.fg(Color::Blue).add_modifier(Modifier::BOLD | Modifier::REVERSED),
+                text: plain.fg(Color::Black),
+                border: plain.fg(Color::DarkGray),
+                error: plain.fg(Color::Red),
+                warning: plain.fg(Color::Magenta),
+                success: plain.fg(Color::Green),
+                progress: plain.fg(Color::Blue),
+                header: plain.fg(Color::Blue).add_modifier(Modifier::BOLD),
+            },
+            // The terminal's own colors, told apart by bold, underline and
+            // reverse video only.
+            Theme::HighContrast => Styles {
+                title: plain.add_modifier(Modifier::BOLD),
+                highlight: plain.add_modifier(Modifier::BOLD | Modifier::REVERSED),
+                text: plain,
+                border: plain,
+                error: plain.add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                warning: plain.add_modifier(Modifier::BOLD),
+                success: plain.add_modifier(Modifier::ITALIC),
+                progress: plain.fg(Color::White).bg(Color::Black),
+                header: plain.add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            },
+        }
+    }
+
+    /// The selected table row.
+    pub fn selected_row(&self) -> Style {
+        self.highlight.add_modifier(Modifier::REVERSED)
+    }
+
+    /// An entry that is listed but can't be used, e.g. a missing file.
+    pub fn dimmed(&self) -> Style {
+        self.text.add_modifier(Modifier::DIM)
+    }
+
+    /// The status bar along the bottom of every screen.
+    pub fn status_bar(&self) -> Style {
+        self.text.add_modifier(Modifier::REVERSED)
+    }
+
+    /// A label that has to stand out on any screen, e.g. READ-ONLY.
+    pub fn badge(&self) -> Style {
+        self.error.add_modifier(Modifier::BOLD | Modifier::REVERSED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn high_contrast_uses_modifiers_not_colors() {
+        let styles = Styles::new(&Theme::HighContrast);
+        for style in [styles.title, styles.highlight, styles.text, styles.border, styles.error, styles.warning, styles.success, styles.header] {
+            assert_eq!((style.fg, style.bg), (None, None), "{:?}", style);
+        }
+        assert!(styles.selected_row().add_modifier.contains(Modifier::REVERSED));
+        assert_ne!(styles.error, styles.text);
+
+        assert_eq!(Styles::default(), Styles::new(&Theme::Dark));
+        assert_eq!(Theme::Dark.next().next().next(), Theme::Dark);
+    }
+}
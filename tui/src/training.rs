@@ -1,142 +1,1145 @@
-use anyhow::{Context, Result};
-use tokio::process::Command;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use anyhow::{bail, Context, Result};
+use chrono::{Local, Utc};
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::fs::File;
+use tokio::io::{AsyncBufRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStderr, ChildStdout};
 use tokio::sync::mpsc;
 
-use crate::model::{TrainingConfig, TrainingProgress};
+use crate::cost::CostSettings;
+use crate::gpu_check::{self, requested_devices, GpuConflict};
+use crate::history::{RunHistory, DEFAULT_HISTORY_FILE};
+use crate::lint::{lint_file, LintReport};
+use crate::log_buffer::{LogBuffer, Severity};
+use crate::metrics::TrainingHistory;
+use crate::model::{rooted, CommandSpec, TrainingConfig, TrainingProgress};
+use crate::owner::RunLock;
+use crate::priority::PriorityTools;
+use crate::process::ProcessState;
+use crate::parser::{ParserRules, ProgressField};
+use crate::project::Scripts;
+use crate::remote::{self, RemoteHost, SSH_FAILED};
+use crate::resources::{ResourceSampler, ResourceSummary};
+use crate::run_dir::{run_name, unique_run_path, RunDir, DEFAULT_RUN_NAME_TEMPLATE, LOGS_DIR, RUN_FILE};
+use crate::runner::RunnerEnv;
+use crate::secret::{redact, SecretRef};
+use crate::step_progress::{record, Segments, StepProgress};
+use crate::tail::{LogTail, TailEvent};
+use crate::task::CancelToken;
+use crate::validset::ValidSetStamp;
+use crate::watchdog::{Watchdog, WatchdogSettings};
+use crate::parse_health::{ParseHealthSettings, ParseMonitor};
+
+const STDOUT_LOG: &str = "stdout.log";
+const STDERR_LOG: &str = "stderr.log";
+const ATTACH_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// What a log to attach to may end in; `.out` for SLURM's.
+const LOG_EXTENSIONS: [&str; 3] = ["log", "out", "txt"];
+
+/// How long a stopped run gets to save its checkpoint and free the GPU
+/// before it is killed.
+pub const DEFAULT_STOP_GRACE: Duration = Duration::from_secs(30);
+
+/// How `stop_training` ended the run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopOutcome {
+    /// Nothing was running.
+    NotRunning,
+    /// It exited on the interrupt within the grace period.
+    Graceful,
+    /// It was killed, after the grace period or without one (Windows).
+    Forced,
+}
 
 pub struct TrainingManager {
     process: Option<tokio::process::Child>,
+    state: ProcessState,
+    run_name_template: String,
+    run_dir: Option<RunDir>,
+    parser_rules: ParserRules,
+    history_path: PathBuf,
+    cost: Option<CostSettings>,
+    /// Training entry point of the checkout, e.g. `train.py`.
+    train_script: String,
+    /// Output of the current or last run, timestamped as received.
+    log: Arc<Mutex<LogBuffer>>,
+    /// The latest tqdm bar of the run.
+    step: Arc<Mutex<Option<StepProgress>>>,
+    /// Every progress update of the current or last run, written to its
+    /// run directory when it ends.
+    metrics: Arc<Mutex<TrainingHistory>>,
+    /// Log being followed for a run started outside the TUI.
+    attached: Option<PathBuf>,
+    stop_grace: Duration,
+    runner: RunnerEnv,
+    /// Where runs go over ssh; here when unset.
+    remote: Option<RemoteHost>,
+    /// Tag of the current remote run, for stopping it.
+    run_tag: Option<String>,
+    /// Cancelled to leave the run going without the TUI; see `detach_handle`.
+    detach: CancelToken,
+    /// Samples the local run's process tree until its stop token is
+    /// cancelled, then yields the summary.
+    sampler: Option<(CancelToken, tokio::task::JoinHandle<ResourceSummary>)>,
+    /// How heavy the last run was, once it is over.
+    resources: Option<ResourceSummary>,
+    watchdog_settings: WatchdogSettings,
+    /// Fed the current run's output and GPU utilization; see `watchdog`.
+    watchdog: Arc<Mutex<Watchdog>>,
+    parse_health_settings: ParseHealthSettings,
+    /// Parser hit rate of the current or followed run; see `parse_health`.
+    parse_health: Arc<Mutex<ParseMonitor>>,
+}
+
+/// What watches a run's stdout besides the progress parser.
+#[derive(Clone)]
+pub struct OutputWatch {
+    pub watchdog: Arc<Mutex<Watchdog>>,
+    pub parse_health: Arc<Mutex<ParseMonitor>>,
+}
+
+impl Default for TrainingManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TrainingManager {
     pub fn new() -> Self {
         TrainingManager {
             process: None,
+            state: ProcessState::NotStarted,
+            run_name_template: DEFAULT_RUN_NAME_TEMPLATE.to_string(),
+            run_dir: None,
+            parser_rules: ParserRules::builtin(),
+            history_path: PathBuf::from(DEFAULT_HISTORY_FILE),
+            cost: None,
+            train_script: Scripts::default().train,
+            log: Arc::new(Mutex::new(LogBuffer::new())),
+            step: Arc::new(Mutex::new(None)),
+            metrics: Arc::new(Mutex::new(TrainingHistory::new())),
+            attached: None,
+            stop_grace: DEFAULT_STOP_GRACE,
+            runner: RunnerEnv::default(),
+            remote: None,
+            run_tag: None,
+            detach: CancelToken::new(),
+            sampler: None,
+            resources: None,
+            watchdog_settings: WatchdogSettings::default(),
+            watchdog: Arc::new(Mutex::new(Watchdog::new(WatchdogSettings::default(), Instant::now()))),
+            parse_health_settings: ParseHealthSettings::default(),
+            parse_health: Arc::new(Mutex::new(ParseMonitor::new(ParseHealthSettings::default(), Utc::now()))),
         }
     }
 
+    pub fn set_run_name_template(&mut self, template: &str) {
+        self.run_name_template = template.to_string();
+    }
+
+    pub fn set_parser_rules(&mut self, rules: ParserRules) {
+        self.parser_rules = rules;
+    }
+
+    pub fn set_history_path(&mut self, path: &Path) {
+        self.history_path = path.to_path_buf();
+    }
+
+    pub fn set_scripts(&mut self, scripts: &Scripts) {
+        self.train_script = scripts.train.clone();
+    }
+
+    pub fn set_runner(&mut self, runner: RunnerEnv) {
+        self.runner = runner;
+    }
+
+    /// Runs from now on go to `host` over ssh, with its python and from
+    /// its `project_root`; `None` runs them here.
+    pub fn set_remote(&mut self, host: Option<RemoteHost>) {
+        self.remote = host;
+    }
+
+    pub fn set_stop_grace(&mut self, grace: Duration) {
+        self.stop_grace = grace;
+    }
+
+    /// Prices runs started from now on; `None` stops pricing them.
+    pub fn set_cost_settings(&mut self, cost: Option<CostSettings>) {
+        self.cost = cost;
+    }
+
+    /// Directory of the current or last started run.
+    pub fn run_dir(&self) -> Option<&RunDir> {
+        self.run_dir.as_ref()
+    }
+
+    /// Peak memory, CPU time and the like of the last local run, once it
+    /// is over, whether it finished or was stopped.
+    pub fn resources(&self) -> Option<&ResourceSummary> {
+        self.resources.as_ref()
+    }
+
+    /// Cancelling it makes `start_training` stop reading the run's output
+    /// and hand its pipes to a relay that keeps appending to the run's log
+    /// files, then return without waiting: the run outlives the TUI.
+    pub fn detach_handle(&self) -> CancelToken {
+        self.detach.clone()
+    }
+
+    /// Output of the current or last run, started or attached to. The
+    /// buffer is shared with the reader tasks, so it fills while the run
+    /// goes on.
+    pub fn log(&self) -> Arc<Mutex<LogBuffer>> {
+        self.log.clone()
+    }
+
+    /// How far the run is through its current epoch, from tqdm.
+    pub fn step_progress(&self) -> Arc<Mutex<Option<StepProgress>>> {
+        self.step.clone()
+    }
+
+    /// Thresholds for the hang watchdog of runs started from now on.
+    pub fn set_watchdog(&mut self, settings: WatchdogSettings) {
+        self.watchdog_settings = settings;
+    }
+
+    /// The hang watchdog of the current run, fed every line it prints and
+    /// its GPU utilization. Whoever shows it calls `Watchdog::check`.
+    pub fn watchdog(&self) -> Arc<Mutex<Watchdog>> {
+        self.watchdog.clone()
+    }
+
+    /// When runs started or followed from now on count as unparseable.
+    pub fn set_parse_health(&mut self, settings: ParseHealthSettings) {
+        self.parse_health_settings = settings;
+    }
+
+    /// Whether the parser recognizes the current run's output, fed every
+    /// line. Whoever shows it calls `ParseMonitor::check`.
+    pub fn parse_health(&self) -> Arc<Mutex<ParseMonitor>> {
+        self.parse_health.clone()
+    }
+
+    /// Lints the model config. Fails if anything blocks the run: an error,
+    /// or a warning not listed in `lint_overrides`.
+    pub fn preflight(config: &TrainingConfig) -> Result<LintReport> {
+        let report = lint_file(&config.model_type, &config.config_path)?;
+        if !report.blocking(&config.lint_overrides).is_empty() {
+            bail!(
+                "Pre-flight checks failed (add warning rule ids to lint_overrides to accept them):\n{}",
+                report.checklist(&config.lint_overrides).join("\n")
+            );
+        }
+        Ok(report)
+    }
+
+    /// MSST processes already on the GPUs `config` asks for, to put before
+    /// starting (see `gpu_check::launch_prompt`). `own_pids` are the TUI's
+    /// jobs (see `JobSupervisor::pids`); this manager's own run is added.
+    /// Empty when the GPUs look free.
+    pub fn gpu_preflight(&self, config: &TrainingConfig, own_pids: &[u32]) -> Vec<GpuConflict> {
+        if config.force_cpu || self.remote.is_some() {
+            return vec![];
+        }
+        let mut own_pids = own_pids.to_vec();
+        own_pids.extend(self.process.as_ref().and_then(|child| child.id()));
+        gpu_check::check(&requested_devices(config.device_ids.as_deref()), &own_pids)
+    }
+
     pub async fn start_training(
         &mut self,
         config: &TrainingConfig,
         progress_tx: mpsc::UnboundedSender<TrainingProgress>,
     ) -> Result<()> {
-        let mut cmd = Command::new("python");
-        cmd.arg("train.py")
-            .arg("--model_type")
-            .arg(config.model_type.key())
-            .arg("--config_path")
-            .arg(&config.config_path)
-            .arg("--results_path")
-            .arg(&config.results_path);
-
-        if let Some(checkpoint) = &config.start_checkpoint {
-            cmd.arg("--start_check_point").arg(checkpoint);
-        }
-
-        for data_path in &config.data_paths {
-            cmd.arg("--data_path").arg(data_path);
+        let root = self.runner.root().to_path_buf();
+        Self::preflight(&config.in_root(&root))?;
+        // A remote run's paths are the host's, relative to its own root.
+        let config = &if self.remote.is_some() { config.clone() } else { config.in_root(&root) };
+        let name = run_name(&self.run_name_template, Local::now(), &config.model_type, config.label.as_deref());
+        let path = unique_run_path(Path::new(&config.results_path), &name);
+        let command = match &self.remote {
+            Some(host) => {
+                let tag = remote::run_tag("train");
+                let command = host.command(&self.train_script, &resolved_args(config, &path)?, &tag);
+                self.run_tag = Some(tag);
+                command
+            }
+            None => {
+                let python = self.runner.python()?;
+                self.runner.in_project(training_command(config, &path, &python, &self.runner.script(&self.train_script))?)
+            }
+        };
+        let mut run_dir = RunDir::create(&root.join(&path), &rooted(&root, &config.config_path), config.label.clone(), config.model_type.clone(), command.clone())?;
+        // Held until the run is recorded as finished, so a dashboard or a
+        // second instance never takes the run for its own.
+        let _lock = RunLock::acquire(run_dir.path())?;
+        if let Some(cost) = &self.cost {
+            run_dir.set_cost(cost)?;
         }
-
-        if let Some(valid_path) = &config.valid_path {
-            cmd.arg("--valid_path").arg(valid_path);
+        if let Some(host) = &self.remote {
+            run_dir.metadata.remote = Some(host.location(&path.to_string_lossy()));
+            run_dir.save()?;
         }
-
-        if let Some(num_workers) = config.num_workers {
-            cmd.arg("--num_workers").arg(num_workers.to_string());
+        if config.derived_from.is_some() {
+            run_dir.metadata.derived_from = config.derived_from.clone();
+            run_dir.save()?;
         }
-
-        if let Some(device_ids) = &config.device_ids {
-            let devices: Vec<String> = device_ids.iter().map(|id| id.to_string()).collect();
-            cmd.arg("--device_ids").arg(devices.join(","));
+        // An unreadable folder is train.py's to report; the run just isn't
+        // stamped. A remote run's folder is on the host.
+        if let Some(valid_path) = config.valid_path.as_ref().filter(|_| self.remote.is_none())
+            && let Ok(stamp) = ValidSetStamp::take(valid_path)
+        {
+            run_dir.metadata.valid_set = Some(stamp);
+            run_dir.save()?;
         }
+        self.record_history(&run_dir)?;
 
-        let mut child = cmd.spawn()
-            .context("Failed to spawn training process")?;
-
-        let stdout = child.stdout.take().context("Failed to capture stdout")?;
-        let stderr = child.stderr.take().context("Failed to capture stderr")?;
+        let (stdout_path, stderr_path) = (run_dir.log_path(STDOUT_LOG), run_dir.log_path(STDERR_LOG));
+        let mut stdout_log = File::create(&stdout_path).await
+            .context("Failed to create stdout log")?;
+        let mut stderr_log = File::create(&stderr_path).await
+            .context("Failed to create stderr log")?;
+        let child = match command.command().stdout(Stdio::piped()).stderr(Stdio::piped()).spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                self.state = ProcessState::Failed(e.to_string());
+                return Err(e).context("Failed to spawn training process");
+            }
+        };
+        // Kept before anything else is awaited: the app cancels a run by
+        // dropping this future and then stopping whatever child is here.
+        // Not killed on drop, so a detached run outlives the manager.
+        self.state = ProcessState::Running;
+        self.run_dir = Some(run_dir.clone());
+        let child = self.process.insert(child);
+        let pid = child.id();
+        let (stdout, stderr) = piped_output(child, "training")?;
 
-        let stdout_reader = BufReader::new(stdout);
+        let mut stdout_reader = BufReader::new(stdout);
         let mut stderr_reader = BufReader::new(stderr);
 
-        let progress_tx_clone = progress_tx.clone();
+        // The log files get the receive time of each line, like the buffer.
+        *self.log.lock().unwrap() = LogBuffer::new();
+        *self.step.lock().unwrap() = None;
+        *self.watchdog.lock().unwrap() = Watchdog::new(self.watchdog_settings, Instant::now());
+        *self.parse_health.lock().unwrap() = ParseMonitor::new(self.parse_health_settings, Utc::now());
+        let (progress_tx, metrics_task) = self.keep_metrics(progress_tx);
+        let (log, step, rules, detach) = (self.log.clone(), self.step.clone(), self.parser_rules.clone(), self.detach.clone());
+        let watch = OutputWatch { watchdog: self.watchdog.clone(), parse_health: self.parse_health.clone() };
         let stdout_task = tokio::spawn(async move {
-            let mut lines = stdout_reader.lines();
-            while let Ok(Some(line)) = lines.next_line().await {
-                if let Some(parsed) = parse_training_output(&line) {
-                    let _ = progress_tx_clone.send(parsed);
+            let detached = tokio::select! {
+                read = read_stdout(&mut stdout_reader, log, step, &mut stdout_log, rules, progress_tx, watch) => {
+                    read?;
+                    false
                 }
+                _ = detach_requested(&detach) => true,
+            };
+            if detached {
+                stdout_log.flush().await.context("Failed to write stdout log")?;
+                let buffered = stdout_reader.buffer().to_vec();
+                relay(&buffered, stdout_reader.into_inner(), &stdout_path)?;
             }
+            Ok::<_, anyhow::Error>(())
         });
 
+        let (log, step, detach, watchdog) = (self.log.clone(), self.step.clone(), self.detach.clone(), self.watchdog.clone());
         let stderr_task = tokio::spawn(async move {
-            let mut lines = stderr_reader.lines();
-            while let Ok(Some(line)) = lines.next_line().await {
-                eprintln!("Training error: {}", line);
+            let read = async {
+                let mut lines = Segments::new(&mut stderr_reader);
+                while let Some(line) = lines.next_segment().await.context("Failed to read training stderr")? {
+                    watchdog.lock().unwrap().output(None, Instant::now());
+                    if record(&step, &line) {
+                        continue;
+                    }
+                    let exported = push_line(&log, &redact(&line), Severity::Error);
+                    let _ = stderr_log.write_all(format!("{}\n", exported).as_bytes()).await;
+                }
+                Ok::<_, anyhow::Error>(())
+            };
+            let detached = tokio::select! {
+                read = read => {
+                    read?;
+                    false
+                }
+                _ = detach_requested(&detach) => true,
+            };
+            if detached {
+                stderr_log.flush().await.context("Failed to write stderr log")?;
+                let buffered = stderr_reader.buffer().to_vec();
+                relay(&buffered, stderr_reader.into_inner(), &stderr_path)?;
             }
+            Ok::<_, anyhow::Error>(())
+        });
+
+        // Sampled on its own thread, as reading /proc and running
+        // nvidia-smi block. Stopped once the run is over, however it ended.
+        self.resources = None;
+        self.sampler = pid.filter(|_| self.remote.is_none()).map(|pid| {
+            let stop = CancelToken::new();
+            let sampling = stop.clone();
+            let mut sampler = ResourceSampler::new(pid, Some(run_dir.path()));
+            let watchdog = self.watchdog.clone();
+            let handle = tokio::task::spawn_blocking(move || {
+                while !sampling.is_cancelled() {
+                    sampler.sample_if_due();
+                    if let Some(utilization) = sampler.last_utilization() {
+                        watchdog.lock().unwrap().utilization(utilization, Instant::now());
+                    }
+                    std::thread::sleep(Duration::from_secs(1));
+                }
+                sampler.finish()
+            });
+            (stop, handle)
         });
 
-        self.process = Some(child);
 
-        stdout_task.await.context("stdout task failed")?;
-        stderr_task.await.context("stderr task failed")?;
+        let output = stdout_task.await.context("stdout task failed").and_then(|read| read)
+            .and(stderr_task.await.context("stderr task failed").and_then(|read| read));
+        let _ = metrics_task.await;
+
+        if self.detach.is_cancelled() {
+            // Not waited for or finished: the run dir stays as a running
+            // run's, and train.py goes on without us.
+            if let Some((stop, _)) = self.sampler.take() {
+                stop.cancel();
+            }
+            output?;
+            let pid = self.process.take().and_then(|child| child.id());
+            self.state = ProcessState::Detached { pid, log: run_dir.log_path(STDOUT_LOG) };
+            return Ok(());
+        }
+
+        let exit_code = match self.process.as_mut().map(Child::wait) {
+            Some(wait) => match wait.await {
+                Ok(status) => {
+                    self.state = ProcessState::Exited(status);
+                    status.code()
+                }
+                Err(e) => {
+                    self.state = ProcessState::Failed(format!("Failed to wait for training process: {}", e));
+                    None
+                }
+            },
+            None => None,
+        };
+        let resources = self.finish_sampling().await;
+        output?;
+        run_dir.record_metrics(&self.metrics.lock().unwrap())?;
+        run_dir.finish(exit_code, resources)?;
+        self.record_history(&run_dir)?;
+        self.run_dir = Some(run_dir);
+        if let Some(host) = &self.remote
+            && exit_code == Some(SSH_FAILED)
+        {
+            bail!(
+                "The ssh connection to {} failed or was lost (exit {}); the log has ssh's message",
+                host.ssh_destination,
+                SSH_FAILED
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Progress for `progress_tx` that is also kept in `metrics`, for the
+    /// run directory; the task ends when the returned sender is dropped.
+    fn keep_metrics(
+        &self,
+        progress_tx: mpsc::UnboundedSender<TrainingProgress>,
+    ) -> (mpsc::UnboundedSender<TrainingProgress>, tokio::task::JoinHandle<()>) {
+        *self.metrics.lock().unwrap() = TrainingHistory::new();
+        let metrics = self.metrics.clone();
+        let (tx, mut rx) = mpsc::unbounded_channel::<TrainingProgress>();
+        let task = tokio::spawn(async move {
+            while let Some(progress) = rx.recv().await {
+                metrics.lock().unwrap().push(progress.clone());
+                let _ = progress_tx.send(progress);
+            }
+        });
+        (tx, task)
+    }
+
+    /// Records a run that was stopped from the TUI as over, with the exit
+    /// code it stopped with, if any.
+    /// Stops sampling the run and keeps what was measured.
+    async fn finish_sampling(&mut self) -> Option<ResourceSummary> {
+        let (stop, handle) = self.sampler.take()?;
+        stop.cancel();
+        self.resources = handle.await.ok();
+        self.resources.clone()
+    }
 
+    async fn finish_stopped_run(&mut self) -> Result<()> {
+        let resources = self.finish_sampling().await;
+        let Some(mut run_dir) = self.run_dir.take().filter(|run| run.metadata.finished_at.is_none()) else {
+            return Ok(());
+        };
+        let exit_code = match &self.state {
+            ProcessState::Exited(status) => status.code(),
+            _ => None,
+        };
+        run_dir.record_metrics(&self.metrics.lock().unwrap())?;
+        run_dir.finish(exit_code, resources)?;
+        self.record_history(&run_dir)?;
+        self.run_dir = Some(run_dir);
         Ok(())
     }
 
-    pub async fn stop_training(&mut self) -> Result<()> {
-        if let Some(mut child) = self.process.take() {
-            child.kill().await.context("Failed to stop training process")?;
+    /// Puts a followed run in the history: its metrics go to its run
+    /// directory if it has one, otherwise its folder is imported.
+    fn record_followed(&self, log: &Path) -> Result<()> {
+        let mut folder = log.parent().unwrap_or(Path::new("."));
+        if folder.file_name().is_some_and(|name| name == LOGS_DIR)
+            && let Some(parent) = folder.parent()
+        {
+            folder = parent;
+        }
+        if folder.join(RUN_FILE).is_file() {
+            let mut run_dir = RunDir::load(folder)?;
+            run_dir.record_metrics(&self.metrics.lock().unwrap())?;
+            return self.record_history(&run_dir);
+        }
+        let mut history = RunHistory::load(&self.history_path)?;
+        history.import(folder)?;
+        history.save()
+    }
+
+    fn record_history(&self, run_dir: &RunDir) -> Result<()> {
+        let mut history = RunHistory::load(&self.history_path)?;
+        history.record(run_dir);
+        history.save()
+    }
+
+    /// Follows the log of a training run the TUI didn't start, feeding its
+    /// lines through the progress parser like a run of our own, until `cancel`.
+    /// Events other than lines (the file vanishing, rotation) go to `status_tx`;
+    /// a vanished log is waited for, not taken as the end of the run.
+    pub async fn attach_log(
+        &mut self,
+        path: &Path,
+        from_start: bool,
+        progress_tx: mpsc::UnboundedSender<TrainingProgress>,
+        status_tx: mpsc::UnboundedSender<TailEvent>,
+        cancel: CancelToken,
+    ) -> Result<()> {
+        let mut tail = LogTail::open(path, from_start)?;
+        self.attached = Some(path.to_path_buf());
+        *self.log.lock().unwrap() = LogBuffer::new();
+        *self.parse_health.lock().unwrap() = ParseMonitor::new(self.parse_health_settings, Utc::now());
+        let (progress_tx, metrics_task) = self.keep_metrics(progress_tx);
+        let mut parser = ProgressParser::new();
+        let result = loop {
+            if cancel.is_cancelled() {
+                break Ok(());
+            }
+            let events = match tail.poll() {
+                Ok(events) => events,
+                Err(e) => break Err(e),
+            };
+            for event in events {
+                match event {
+                    TailEvent::Line(line) => {
+                        push_line(&self.log, &redact(&line), Severity::Info);
+                        let progress = parser.push(&self.parser_rules, &line);
+                        self.parse_health.lock().unwrap().observe(&line, parser.last_matched(), Utc::now());
+                        if let Some(progress) = progress {
+                            let _ = progress_tx.send(progress);
+                        }
+                    }
+                    other => {
+                        let _ = status_tx.send(other);
+                    }
+                }
+            }
+            tokio::time::sleep(ATTACH_POLL_INTERVAL).await;
+        };
+        if let Some(progress) = parser.finish() {
+            let _ = progress_tx.send(progress);
+        }
+        drop(progress_tx);
+        let _ = metrics_task.await;
+        self.attached = None;
+        result?;
+        if !self.metrics.lock().unwrap().is_empty()
+            && let Err(e) = self.record_followed(path)
+        {
+            push_line(&self.log, &format!("Not recorded in the run history: {:#}", e), Severity::Error);
         }
         Ok(())
     }
 
-    pub fn is_running(&self) -> bool {
-        self.process.is_some()
+    pub fn attached_log(&self) -> Option<&Path> {
+        self.attached.as_deref()
+    }
+
+    /// Interrupts the run as Ctrl+C would, so train.py can save its
+    /// checkpoint, and kills it if it is still there after the grace period.
+    pub async fn stop_training(&mut self) -> Result<StopOutcome> {
+        if let Some(path) = &self.attached {
+            bail!(
+                "Attached to {}: that run was started outside the TUI, so it can only be stopped where it was started",
+                path.display()
+            );
+        }
+        let Some(mut child) = self.process.take() else {
+            return Ok(StopOutcome::NotRunning);
+        };
+        if let (Some(host), Some(tag)) = (&self.remote, &self.run_tag) {
+            return self.stop_remote(child, &host.clone(), &tag.clone()).await;
+        }
+        if interrupt(&child)
+            && let Ok(exited) = tokio::time::timeout(self.stop_grace, child.wait()).await
+        {
+            self.state = ProcessState::Exited(exited.context("Failed to wait for training process")?);
+            self.finish_stopped_run().await?;
+            return Ok(StopOutcome::Graceful);
+        }
+        child.kill().await.context("Failed to stop training process")?;
+        if let Ok(Some(status)) = child.try_wait() {
+            self.state = ProcessState::Exited(status);
+        }
+        self.finish_stopped_run().await?;
+        Ok(StopOutcome::Forced)
+    }
+
+    /// Interrupts the tagged run on `host` with a second ssh, waits for it
+    /// to end like a local one, and kills it there too after the grace
+    /// period.
+    async fn stop_remote(&mut self, mut child: Child, host: &RemoteHost, tag: &str) -> Result<StopOutcome> {
+        let interrupted = host.signal_command(&self.train_script, tag, "INT").command().output().await
+            .with_context(|| format!("Failed to reach {} to stop the run", host.ssh_destination))?;
+        if interrupted.status.code() == Some(SSH_FAILED) {
+            push_line(&self.log, &format!("Stopping over ssh failed: {}", String::from_utf8_lossy(&interrupted.stderr).trim()), Severity::Error);
+        } else if let Ok(exited) = tokio::time::timeout(self.stop_grace, child.wait()).await {
+            self.state = ProcessState::Exited(exited.context("Failed to wait for training process")?);
+            self.finish_stopped_run().await?;
+            return Ok(StopOutcome::Graceful);
+        }
+        let _ = host.signal_command(&self.train_script, tag, "KILL").command().output().await;
+        child.kill().await.context("Failed to stop training process")?;
+        if let Ok(Some(status)) = child.try_wait() {
+            self.state = ProcessState::Exited(status);
+        }
+        self.finish_stopped_run().await?;
+        Ok(StopOutcome::Forced)
+    }
+
+    /// The run's process as of now, checked without waiting.
+    pub fn status(&mut self) -> &ProcessState {
+        self.state.update(self.process.as_mut());
+        &self.state
+    }
+
+    pub fn is_running(&mut self) -> bool {
+        *self.status() == ProcessState::Running
     }
 }
 
-fn parse_training_output(line: &str) -> Option<TrainingProgress> {
-    if line.contains("epoch:") {
-        let epoch_str = line.split("epoch:").nth(1)?
-            .trim()
-            .split_whitespace()
-            .next()?;
-        let epoch: usize = epoch_str.parse().ok()?;
+/// Sends SIGINT to `child`; false if it couldn't be sent.
+#[cfg(unix)]
+fn interrupt(child: &Child) -> bool {
+    let Some(pid) = child.id() else {
+        return false;
+    };
+    // SAFETY: a signal to our own child, which hasn't been waited for.
+    unsafe { libc::kill(pid as libc::pid_t, libc::SIGINT) == 0 }
+}
 
-        return Some(TrainingProgress {
-            epoch,
-            train_loss: 0.0,
-            valid_loss: None,
-            sdr: None,
-            sir: None,
-            sar: None,
-            isr: None,
-            gpu_memory: None,
-            gpu_utilization: None,
-        });
+/// No interrupt to send; the run is killed straight away.
+#[cfg(not(unix))]
+fn interrupt(_child: &Child) -> bool {
+    false
+}
+
+/// Invocation of the training `script` (train.py upstream) by `python` for
+/// a run whose checkpoints go to `results_path`.
+pub fn training_command(config: &TrainingConfig, results_path: &Path, python: &str, script: &str) -> Result<CommandSpec> {
+    let mut cmd = CommandSpec::new(python);
+    cmd.arg(script);
+    for arg in resolved_args(config, results_path)? {
+        cmd.arg(arg);
+    }
+    Ok(config.priority.apply(cmd, PriorityTools::detect()))
+}
+
+/// train.py's arguments for a run, with the wandb key resolved.
+fn resolved_args(config: &TrainingConfig, results_path: &Path) -> Result<Vec<String>> {
+    let wandb_key = match &config.wandb_key {
+        Some(key) => Some(key.resolve().context("Failed to resolve wandb key")?.expose().to_string()),
+        None => None,
+    };
+    Ok(train_args(config, results_path, wandb_key.as_deref()))
+}
+
+/// train.py's arguments for `config`, for showing before a run: the
+/// results path is the configured one rather than the run's own folder
+/// under it, and a wandb key stays its reference, e.g. `${WANDB_API_KEY}`.
+pub fn build_train_command(config: &TrainingConfig) -> Vec<String> {
+    train_args(config, Path::new(&config.results_path), config.wandb_key.as_ref().map(SecretRef::as_str))
+}
+
+fn train_args(config: &TrainingConfig, results_path: &Path, wandb_key: Option<&str>) -> Vec<String> {
+    let mut args = vec![
+        "--model_type".to_string(),
+        config.model_type.key().to_string(),
+        "--config_path".to_string(),
+        config.config_path.clone(),
+        "--results_path".to_string(),
+        results_path.to_string_lossy().to_string(),
+    ];
+    if let Some(checkpoint) = &config.start_checkpoint {
+        args.extend(["--start_check_point".to_string(), checkpoint.clone()]);
+    }
+    for data_path in &config.data_paths {
+        args.extend(["--data_path".to_string(), data_path.clone()]);
+    }
+    if let Some(valid_path) = &config.valid_path {
+        args.extend(["--valid_path".to_string(), valid_path.clone()]);
+    }
+    if let Some(num_workers) = config.num_workers {
+        args.extend(["--num_workers".to_string(), num_workers.to_string()]);
+    }
+    if config.force_cpu {
+        args.push("--force_cpu".to_string());
+    } else if let Some(device_ids) = &config.device_ids {
+        let devices: Vec<String> = device_ids.iter().map(|id| id.to_string()).collect();
+        args.extend(["--device_ids".to_string(), devices.join(",")]);
+    }
+    if let Some(key) = wandb_key {
+        args.extend(["--wandb_key".to_string(), key.to_string()]);
+    }
+    args.extend(config.extra_args.iter().cloned());
+    args
+}
+
+/// The command `start_training` would run for `config`, as typed into a
+/// shell, e.g. `python train.py --model_type scnet ...`.
+pub fn preview_training_command(config: &TrainingConfig, python: &str, script: &str) -> String {
+    let mut cmd = CommandSpec::new(python);
+    cmd.arg(script);
+    for arg in build_train_command(config) {
+        cmd.arg(arg);
+    }
+    config.priority.apply(cmd, PriorityTools::detect()).display()
+}
+
+/// Takes the output pipes of a `what` process, e.g. "training". Failing here
+/// means the command wasn't set up with `Stdio::piped()`, not a read error.
+pub fn piped_output(child: &mut Child, what: &str) -> Result<(ChildStdout, ChildStderr)> {
+    let stdout = child.stdout.take()
+        .with_context(|| format!("The {} process was not spawned with piped stdout", what))?;
+    let stderr = child.stderr.take()
+        .with_context(|| format!("The {} process was not spawned with piped stderr", what))?;
+    Ok((stdout, stderr))
+}
+
+/// The log to attach to for `path`: the file itself, or in a results
+/// folder the `logs/stdout.log` of a run the TUI started, or else the
+/// newest `.log`, `.out` or `.txt` file in the folder or its `logs/`.
+pub fn find_training_log(path: &Path) -> Result<PathBuf> {
+    if path.is_file() {
+        return Ok(path.to_path_buf());
     }
+    if !path.is_dir() {
+        bail!("{} is neither a log file nor a results folder", path.display());
+    }
+    let ours = path.join(LOGS_DIR).join(STDOUT_LOG);
+    if ours.is_file() {
+        return Ok(ours);
+    }
+    let mut newest: Option<(std::time::SystemTime, PathBuf)> = None;
+    for dir in [path.to_path_buf(), path.join(LOGS_DIR)] {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file = entry.path();
+            let is_log = file.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| LOG_EXTENSIONS.contains(&ext));
+            let Ok(modified) = entry.metadata().and_then(|metadata| metadata.modified()) else {
+                continue;
+            };
+            if is_log && file.is_file() && newest.as_ref().is_none_or(|(at, _)| modified > *at) {
+                newest = Some((modified, file));
+            }
+        }
+    }
+    newest.map(|(_, file)| file).with_context(|| format!("No .log, .out or .txt file in {}", path.display()))
+}
+
+/// Reads training stdout to the end: every line goes to the buffer and
+/// `out` with its receive time, and what the rules find in it to
+/// `progress_tx`. tqdm redraws go to `step` instead, see `step_progress::record`;
+/// they count as output for the watchdog all the same. Other lines feed
+/// the parse health too.
+pub async fn read_stdout(
+    reader: impl AsyncBufRead + Unpin,
+    log: Arc<Mutex<LogBuffer>>,
+    step: Arc<Mutex<Option<StepProgress>>>,
+    mut out: impl AsyncWrite + Unpin,
+    rules: ParserRules,
+    progress_tx: mpsc::UnboundedSender<TrainingProgress>,
+    watch: OutputWatch,
+) -> Result<()> {
+    let mut lines = Segments::new(reader);
+    let mut parser = ProgressParser::new();
+    while let Some(line) = lines.next_segment().await.context("Failed to read training stdout")? {
+        watch.watchdog.lock().unwrap().line(&rules, &line, Instant::now());
+        if record(&step, &line) {
+            continue;
+        }
+        let exported = push_line(&log, &redact(&line), Severity::Info);
+        let _ = out.write_all(format!("{}\n", exported).as_bytes()).await;
+        let progress = parser.push(&rules, &line);
+        watch.parse_health.lock().unwrap().observe(&line, parser.last_matched(), Utc::now());
+        if let Some(progress) = progress {
+            let _ = progress_tx.send(progress);
+        }
+    }
+    if let Some(progress) = parser.finish() {
+        let _ = progress_tx.send(progress);
+    }
+    Ok(())
+}
+
+async fn detach_requested(detach: &CancelToken) {
+    while !detach.is_cancelled() {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+}
+
+/// Appends what was read but not yet handled to `path`, then starts `cat`
+/// (in a process group of its own) to copy the rest of `pipe` there, so the
+/// run doesn't die on a broken pipe once the TUI is gone.
+fn relay(buffered: &[u8], pipe: impl TryInto<Stdio, Error = std::io::Error>, path: &Path) -> Result<()> {
+    use std::io::Write;
+    let pipe = pipe.try_into().context("Failed to take over the training output")?;
+    let mut log = std::fs::OpenOptions::new().append(true).open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    log.write_all(buffered).with_context(|| format!("Failed to write {}", path.display()))?;
+    let mut cat = std::process::Command::new("cat");
+    cat.stdin(pipe).stdout(log).stderr(Stdio::null());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cat.process_group(0);
+    }
+    cat.spawn().context("Failed to start cat to keep the training output going")?;
+    Ok(())
+}
+
+/// Adds `line` to the shared buffer, returning it as exported.
+fn push_line(log: &Mutex<LogBuffer>, line: &str, severity: Severity) -> String {
+    let mut log = log.lock().unwrap();
+    let line = match severity {
+        Severity::Info => log.push(line).clone(),
+        Severity::Error => log.push_error(line).clone(),
+    };
+    log.export_line(&line)
+}
+
+/// Per-instrument SDR from the validation summary, in any of the forms
+/// train.py has printed: "Instr SDR vocals: 8.23", "Instr vocals sdr: 8.23
+/// (Std: 1.2)" or "Instrument vocals sdr: 8.23".
+const INSTRUMENT_SDR: &str = r"(?i)^\s*instr(?:ument)?\s+(?:sdr\s+)?(?P<name>[\w.-]+?)(?:\s+sdr)?\s*:\s*(?P<sdr>[-+]?(?:\d+\.?\d*|\.\d+)(?:[eE][-+]?\d+)?)";
+
+/// Gathers the lines of one epoch into a single update: the epoch line,
+/// the training loss, then the validation summary. An epoch's block is over
+/// once the summary is followed by anything else, or the next epoch starts;
+/// `finish` hands over the block still open when the output ends. tqdm bars
+/// are skipped, also when glued to a line with a carriage return.
+#[derive(Debug, Clone)]
+pub struct ProgressParser {
+    current: Option<TrainingProgress>,
+    in_summary: bool,
+    last_matched: bool,
+    instrument_sdr: Regex,
+}
+
+impl Default for ProgressParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-    if line.contains("SDR:") {
-        let sdr_str = line.split("SDR:").nth(1)?
-            .trim()
-            .split_whitespace()
-            .next()?;
-        let sdr: f64 = sdr_str.parse().ok()?;
-
-        return Some(TrainingProgress {
-            epoch: 0,
-            train_loss: 0.0,
-            valid_loss: None,
-            sdr: Some(sdr),
-            sir: None,
-            sar: None,
-            isr: None,
-            gpu_memory: None,
-            gpu_utilization: None,
+impl ProgressParser {
+    pub fn new() -> Self {
+        ProgressParser {
+            current: None,
+            in_summary: false,
+            last_matched: false,
+            instrument_sdr: Regex::new(INSTRUMENT_SDR).expect("built-in instrument SDR pattern"),
+        }
+    }
+
+    /// Feeds one line of output; returns an epoch's progress once its block
+    /// is over.
+    pub fn push(&mut self, rules: &ParserRules, line: &str) -> Option<TrainingProgress> {
+        self.last_matched = false;
+        let mut finished = None;
+        for segment in line.split('\r').filter(|segment| !is_progress_bar(segment)) {
+            if let Some(done) = self.push_segment(rules, segment) {
+                finished = Some(done);
+            }
+        }
+        finished
+    }
+
+    /// The block still open, for the end of the output.
+    pub fn finish(&mut self) -> Option<TrainingProgress> {
+        self.in_summary = false;
+        self.current.take()
+    }
+
+    /// The epoch still being gathered, for a live view.
+    pub fn pending(&self) -> Option<&TrainingProgress> {
+        self.current.as_ref()
+    }
+
+    /// Whether anything was found in the last line pushed.
+    pub fn last_matched(&self) -> bool {
+        self.last_matched
+    }
+
+    fn push_segment(&mut self, rules: &ParserRules, segment: &str) -> Option<TrainingProgress> {
+        if let Some(captures) = self.instrument_sdr.captures(segment)
+            && let Ok(sdr) = captures["sdr"].parse()
+        {
+            self.last_matched = true;
+            self.in_summary = true;
+            self.current.get_or_insert_default().instrument_sdr.push((captures["name"].to_string(), sdr));
+            return None;
+        }
+
+        let fields = rules.parse_line(segment);
+        if fields.is_empty() {
+            if self.in_summary && !segment.trim().is_empty() {
+                return self.finish();
+            }
+            return None;
+        }
+        self.last_matched = true;
+
+        let mut finished = None;
+        if let Some((_, epoch)) = fields.iter().find(|(field, _)| *field == ProgressField::Epoch)
+            && self.current.as_ref().is_some_and(|current| current.epoch != *epoch as usize)
+        {
+            finished = self.finish();
+        }
+        let current = self.current.get_or_insert_default();
+        for (field, value) in fields {
+            field.apply(current, value);
+            if matches!(field, ProgressField::ValidLoss | ProgressField::Sdr | ProgressField::Sir | ProgressField::Sar | ProgressField::Isr) {
+                self.in_summary = true;
+            }
+        }
+        finished
+    }
+}
+
+/// A tqdm bar, e.g. " 45%|████▌     | 450/1000 [03:41<04:31,  2.03it/s, loss=0.05]".
+fn is_progress_bar(segment: &str) -> bool {
+    segment.contains("%|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_health::ParseHealth;
+
+    /// train.py output with tqdm on the same stream, as in a `2>&1` log.
+    const LOG: &str = "\
+Train epoch: 0 Learning rate: 9e-05
+  0%|          | 0/1000 [00:00<?, ?it/s]
+100%|##########| 1000/1000 [08:21<00:00,  1.99it/s, loss=0.0631, avg_loss=0.0712]
+Training loss: 7.123e-02
+Processing audio chunks:  50%|#####     | 5/10 [00:01<00:01,  4.20it/s]
+Instr SDR vocals: -1.2345 Debug: 100
+Instrument other sdr: 9.87
+Metric avg sdr        : -0.5000
+Metric avg sir: 2.5
+Store weights: results/model_epoch_0_sdr_-0.5000.ckpt
+Train epoch: 1 Learning rate: 9e-05
+ 99%|#########9| 990/1000 [08:11<00:04,  2.01it/s, loss=0.05]\rTraining loss: 5.5E-2
+Train epoch: x
+Training loss: nan
+Instr vocals sdr: n/a
+Instr vocals sdr: 3.5 (Std: 1.2)
+Metric avg sdr: 3.5
+";
+
+    fn parse(log: &str) -> Vec<TrainingProgress> {
+        let rules = ParserRules::builtin();
+        let mut parser = ProgressParser::new();
+        let mut updates: Vec<TrainingProgress> = log.lines().filter_map(|line| parser.push(&rules, line)).collect();
+        updates.extend(parser.finish());
+        updates
+    }
+
+    #[test]
+    fn one_update_per_epoch_block() {
+        let updates = parse(LOG);
+        assert_eq!(updates.len(), 2);
+
+        let first = &updates[0];
+        assert_eq!((first.epoch, first.train_loss), (0, 0.07123));
+        assert_eq!((first.sdr, first.sir), (Some(-0.5), Some(2.5)));
+        assert_eq!(first.instrument_sdr, [("vocals".to_string(), -1.2345), ("other".to_string(), 9.87)]);
+
+        // The loss after a bar on the same line; malformed lines change nothing.
+        let second = &updates[1];
+        assert_eq!((second.epoch, second.train_loss, second.sdr), (1, 0.055, Some(3.5)));
+        assert_eq!(second.instrument_sdr, [("vocals".to_string(), 3.5)]);
+    }
+
+    #[test]
+    fn instrument_names_may_have_underscores() {
+        let updates = parse("\
+Train epoch: 0
+Training loss: 0.1
+Instr SDR bass_guitar: 3.25
+Instr SDR drums: 8.0
+Instrument lead_vocals sdr: 9.5
+Instr other_stuff sdr: 4.0 (Std: 0.5)
+Metric avg sdr: 6.1875
+");
+        let names: Vec<&str> = updates[0].instrument_sdr.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["bass_guitar", "drums", "lead_vocals", "other_stuff"]);
+        assert_eq!(updates[0].instrument_sdr[3].1, 4.0);
+    }
+
+    #[test]
+    fn next_epoch_ends_a_block_without_validation() {
+        let updates = parse("Train epoch: 4\nTraining loss: 0.2\nTrain epoch: 5\nsome warning\nTraining loss: 0.1\n");
+        assert_eq!(updates.iter().map(|update| (update.epoch, update.train_loss)).collect::<Vec<_>>(), [(4, 0.2), (5, 0.1)]);
+        assert!(parse("garbage\n\r\r%|\nInstr : 1\n").is_empty());
+    }
+
+    #[cfg(unix)]
+    use tokio::process::Command;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn piped_lines_reach_the_parser() {
+        let mut child = Command::new("sh")
+            .args(["-c", "echo 'Train epoch: 3 Learning rate: 0.0001'; printf ' 50%%|#    | 1/2 [00:01<00:01]\\r100%%|##| 2/2 [00:02<00:00]\\n'; echo 'Training loss: 0.25'; echo tqdm"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let (stdout, _) = piped_output(&mut child, "training").unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel::<TrainingProgress>();
+        let log = Arc::new(Mutex::new(LogBuffer::new()));
+        let step = Arc::new(Mutex::new(None));
+        let mut out = vec![];
+        let watchdog = Arc::new(Mutex::new(Watchdog::new(WatchdogSettings::default(), Instant::now())));
+        let settings = ParseHealthSettings { after_minutes: 0, after_lines: 1 };
+        let parse_health = Arc::new(Mutex::new(ParseMonitor::new(settings, Utc::now())));
+        let watch = OutputWatch { watchdog: watchdog.clone(), parse_health: parse_health.clone() };
+        read_stdout(BufReader::new(stdout), log.clone(), step.clone(), &mut out, ParserRules::builtin(), tx, watch).await.unwrap();
+        child.wait().await.unwrap();
+
+        let progress = rx.recv().await.unwrap();
+        assert_eq!((progress.epoch, progress.train_loss), (3, 0.25));
+        assert!(rx.recv().await.is_none(), "one epoch, one update");
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 4, "the full bar, not the redraw before it");
+        assert_eq!(log.lock().unwrap().len(), 4);
+        assert_eq!(step.lock().unwrap().map(|bar| bar.done), Some(2));
+        assert!(!watchdog.lock().unwrap().first_epoch_done(), "one epoch number so far");
+        assert_eq!(parse_health.lock().unwrap().state(), ParseHealth::Parsing);
+
+        let mut unpiped = Command::new("sh").args(["-c", "exec true"]).spawn().unwrap();
+        let error = piped_output(&mut unpiped, "training").unwrap_err();
+        assert_eq!(error.to_string(), "The training process was not spawned with piped stdout");
+        unpiped.wait().await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn stop_interrupts_before_killing() {
+        let mut manager = TrainingManager::new();
+        assert_eq!(manager.stop_training().await.unwrap(), StopOutcome::NotRunning);
+
+        manager.process = Some(Command::new("sh").args(["-c", "trap 'exit 0' INT; sleep 10 & wait"]).spawn().unwrap());
+        manager.state = ProcessState::Running;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(manager.stop_training().await.unwrap(), StopOutcome::Graceful);
+        assert_eq!(manager.status().describe(), "finished (exit 0)");
+
+        manager.set_stop_grace(Duration::from_millis(200));
+        manager.process = Some(Command::new("sh").args(["-c", "trap '' INT; sleep 10"]).spawn().unwrap());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(manager.stop_training().await.unwrap(), StopOutcome::Forced);
+        assert_eq!(manager.status().describe(), "killed (signal 9)");
+        assert!(!manager.is_running());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn relayed_output_outlives_the_reader() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(STDOUT_LOG);
+        std::fs::write(&path, "epoch 1\n").unwrap();
+        let mut child = Command::new("sh")
+            .args(["-c", "sleep 0.3; echo 'epoch 2'; echo 'epoch 3'"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdout = child.stdout.take().unwrap();
+        relay(b"partial ", stdout, &path).unwrap();
+        assert!(child.wait().await.unwrap().success(), "no broken pipe once the reader let go");
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "epoch 1\npartial epoch 2\nepoch 3\n");
+    }
+
+    #[tokio::test]
+    async fn an_attached_log_backfills_its_past_epochs() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(find_training_log(dir.path()).is_err());
+        std::fs::write(dir.path().join("notes.md"), "").unwrap();
+        std::fs::write(dir.path().join("slurm-42.out"), LOG).unwrap();
+        let log = find_training_log(dir.path()).unwrap();
+        assert_eq!(log, dir.path().join("slurm-42.out"));
+        assert_eq!(find_training_log(&log).unwrap(), log);
+
+        let mut manager = TrainingManager::new();
+        manager.set_history_path(&dir.path().join("history.json"));
+        let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+        let (status_tx, _status_rx) = mpsc::unbounded_channel();
+        let cancel = CancelToken::new();
+        let stop = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            stop.cancel();
         });
+        manager.attach_log(&log, true, progress_tx, status_tx, cancel).await.unwrap();
+        let progress = progress_rx.recv().await.unwrap();
+        assert_eq!(progress.epoch, 0);
+        assert!(manager.log().lock().unwrap().len() > 3);
+        assert!(manager.attached_log().is_none());
     }
 
-    None
+    #[test]
+    fn commands_are_built_and_quoted() {
+        let mut config = TrainingConfig::new(crate::model::ModelType::ScNet);
+        config.config_path = "configs/my scnet.yaml".to_string();
+        config.results_path = "results".to_string();
+        config.data_paths = vec!["/data/it's here".to_string()];
+        config.device_ids = Some(vec![0, 1]);
+        config.wandb_key = Some(SecretRef::env("WANDB_API_KEY"));
+        config.extra_args = vec!["--metrics".to_string(), "sdr".to_string(), "--seed=7".to_string()];
+        assert_eq!(build_train_command(&config), [
+            "--model_type", "scnet", "--config_path", "configs/my scnet.yaml", "--results_path", "results",
+            "--data_path", "/data/it's here", "--device_ids", "0,1", "--wandb_key", "${WANDB_API_KEY}",
+            "--metrics", "sdr", "--seed=7",
+        ]);
+        assert_eq!(
+            preview_training_command(&config, "/opt/conda/bin/python", "train.py"),
+            "/opt/conda/bin/python train.py --model_type scnet --config_path 'configs/my scnet.yaml' --results_path results \
+             --data_path '/data/it'\\''s here' --device_ids 0,1 --wandb_key *** --metrics sdr --seed=7"
+        );
+    }
 }
@@ -0,0 +1,252 @@
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+
+use crate::model::TrainingProgress;
+
+/// Rows the loss chart needs; with less room it is left out.
+pub const CHART_MIN_HEIGHT: u16 = 10;
+
+/// Rows of the latest-metrics table next to the chart.
+pub const LATEST_HEIGHT: u16 = 8;
+
+/// Where the Training screen draws its parts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrainingLayout {
+    pub title: Rect,
+    pub gpu: Rect,
+    pub step: Rect,
+    /// The chart and metric tables, or only the latest metrics without
+    /// `chart`.
+    pub body: Rect,
+    pub log: Rect,
+    pub footer: Rect,
+    pub chart: bool,
+}
+
+/// Splits `area` for the Training screen. When the chart and a `log_height`
+/// log pane don't both fit, the chart goes, the GPU panel shrinks to three
+/// rows and the rest is shared by the latest metrics and the log pane.
+pub fn training_layout(area: Rect, gpu_height: u16, step_height: u16, log_height: u16) -> TrainingLayout {
+    let inner = area.inner(&ratatui::layout::Margin { horizontal: 1, vertical: 1 });
+    let full = inner.height.saturating_sub(6 + gpu_height + step_height);
+    let chart = full >= CHART_MIN_HEIGHT + log_height;
+    let (gpu_height, body, log) = if chart {
+        (gpu_height, full - log_height, log_height)
+    } else {
+        let gpu_height = gpu_height.min(3);
+        let rest = inner.height.saturating_sub(6 + gpu_height + step_height);
+        let body = LATEST_HEIGHT.min(rest / 2);
+        (gpu_height, body, rest - body)
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(gpu_height),
+            Constraint::Length(step_height),
+            Constraint::Length(body),
+            Constraint::Length(log),
+            Constraint::Length(3),
+        ])
+        .split(inner);
+    TrainingLayout { title: chunks[0], gpu: chunks[1], step: chunks[2], body: chunks[3], log: chunks[4], footer: chunks[5], chart }
+}
+
+/// (epoch, train loss) of every update, for the loss chart.
+pub fn train_loss_points(history: &[TrainingProgress]) -> Vec<(f64, f64)> {
+    history.iter().map(|progress| (progress.epoch as f64, progress.train_loss)).collect()
+}
+
+/// (epoch, validation loss) of the updates that have one.
+pub fn valid_loss_points(history: &[TrainingProgress]) -> Vec<(f64, f64)> {
+    history.iter()
+        .filter_map(|progress| progress.valid_loss.map(|loss| (progress.epoch as f64, loss)))
+        .collect()
+}
+
+/// (epoch, mean SDR) of the updates that validated.
+pub fn sdr_points(history: &[TrainingProgress]) -> Vec<(f64, f64)> {
+    history.iter()
+        .filter_map(|progress| mean_sdr(progress).map(|sdr| (progress.epoch as f64, sdr)))
+        .collect()
+}
+
+/// X and Y axis bounds covering every point, the Y range padded by a tenth
+/// so the curve doesn't run along the frame. A single point or a flat line
+/// still gets a range to draw in.
+pub fn bounds(points: &[(f64, f64)]) -> ([f64; 2], [f64; 2]) {
+    let finite = points.iter().filter(|(x, y)| x.is_finite() && y.is_finite());
+    let (mut x, mut y) = ([f64::MAX, f64::MIN], [f64::MAX, f64::MIN]);
+    for (px, py) in finite {
+        x = [x[0].min(*px), x[1].max(*px)];
+        y = [y[0].min(*py), y[1].max(*py)];
+    }
+    if x[0] > x[1] {
+        return ([0.0, 1.0], [0.0, 1.0]);
+    }
+    if x[1] - x[0] < 1.0 {
+        x[1] = x[0] + 1.0;
+    }
+    let pad = match (y[1] - y[0]) * 0.1 {
+        pad if pad > 0.0 => pad,
+        _ => y[0].abs().max(1.0) * 0.1,
+    };
+    (x, [y[0] - pad, y[1] + pad])
+}
+
+/// The latest epoch, SDR and GPU memory, for the table next to the chart.
+pub fn latest_rows(history: &[TrainingProgress]) -> Vec<(&'static str, String)> {
+    let Some(latest) = history.last() else {
+        return vec![];
+    };
+    // Validation comes at the end of an epoch, so the last SDR may be on
+    // an earlier update than the last loss.
+    let sdr = history.iter().rev().find_map(|progress| progress.sdr);
+    let gpu_memory = history.iter().rev().find_map(|progress| progress.gpu_memory);
+    let or_dash = |value: Option<f64>, digits: usize| value.map_or("-".to_string(), |value| format!("{:.*}", digits, value));
+    vec![
+        ("Epoch", latest.epoch.to_string()),
+        ("Train loss", format!("{:.4}", latest.train_loss)),
+        ("Valid loss", or_dash(history.iter().rev().find_map(|progress| progress.valid_loss), 4)),
+        ("SDR", or_dash(sdr, 2)),
+        ("GPU memory", or_dash(gpu_memory, 1)),
+        ("Best SDR", best_mean_sdr(history).map_or("-".to_string(), |(sdr, epoch)| format!("{:.2} @ ep {}", sdr, epoch))),
+    ]
+}
+
+/// The SDR an update reports for the whole model: the average train.py
+/// prints, or else the mean over its instruments.
+fn mean_sdr(progress: &TrainingProgress) -> Option<f64> {
+    progress.sdr.or_else(|| {
+        let sdrs = &progress.instrument_sdr;
+        (!sdrs.is_empty()).then(|| sdrs.iter().map(|(_, sdr)| sdr).sum::<f64>() / sdrs.len() as f64)
+    })
+}
+
+/// The highest mean SDR and its epoch, i.e. the checkpoint to keep; the
+/// earlier epoch on a tie.
+pub fn best_mean_sdr(history: &[TrainingProgress]) -> Option<(f64, usize)> {
+    history.iter()
+        .filter_map(|progress| mean_sdr(progress).filter(|sdr| sdr.is_finite()).map(|sdr| (sdr, progress.epoch)))
+        .fold(None, |best, (sdr, epoch)| match best {
+            Some((best_sdr, _)) if best_sdr >= sdr => best,
+            _ => Some((sdr, epoch)),
+        })
+}
+
+/// One instrument's row of the SDR table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstrumentSdr {
+    pub name: String,
+    /// In the latest validation; none if it wasn't in that one.
+    pub latest: Option<f64>,
+    pub best: f64,
+    pub best_epoch: usize,
+}
+
+impl InstrumentSdr {
+    /// E.g. "vocals: SDR 9.12, best 9.50 at epoch 3".
+    pub fn line(&self) -> String {
+        let latest = self.latest.map_or("-".to_string(), |sdr| format!("{:.2}", sdr));
+        format!("{}: SDR {}, best {:.2} at epoch {}", self.name, latest, self.best, self.best_epoch)
+    }
+}
+
+/// Every instrument validated so far, in the order they first came up.
+pub fn instrument_rows(history: &[TrainingProgress]) -> Vec<InstrumentSdr> {
+    let latest = history.iter().rev().find(|progress| !progress.instrument_sdr.is_empty());
+    let mut rows: Vec<InstrumentSdr> = vec![];
+    for progress in history {
+        for (name, sdr) in progress.instrument_sdr.iter().filter(|(_, sdr)| sdr.is_finite()) {
+            match rows.iter_mut().find(|row| row.name == *name) {
+                Some(row) if row.best >= *sdr => {}
+                Some(row) => (row.best, row.best_epoch) = (*sdr, progress.epoch),
+                None => rows.push(InstrumentSdr { name: name.clone(), latest: None, best: *sdr, best_epoch: progress.epoch }),
+            }
+        }
+    }
+    for row in &mut rows {
+        row.latest = latest.and_then(|progress| {
+            progress.instrument_sdr.iter().find(|(name, _)| *name == row.name).map(|(_, sdr)| *sdr)
+        });
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(epoch: usize, train_loss: f64, sdr: Option<f64>) -> TrainingProgress {
+        TrainingProgress { epoch, train_loss, sdr, ..Default::default() }
+    }
+
+    #[test]
+    fn short_terminals_lose_the_chart_before_the_log() {
+        let area = |height| Rect { x: 0, y: 0, width: 100, height };
+        let tall = training_layout(area(40), 3, 3, 10);
+        assert!(tall.chart);
+        assert_eq!((tall.body.height, tall.log.height, tall.footer.bottom()), (16, 10, 39));
+
+        let short = training_layout(area(28), 6, 3, 10);
+        assert!(!short.chart);
+        assert_eq!((short.gpu.height, short.body.height, short.log.height), (3, 7, 7));
+        assert_eq!(short.footer.bottom(), 27);
+
+        let tiny = training_layout(area(12), 3, 0, 10);
+        assert_eq!((tiny.body.height, tiny.log.height), (0, 1));
+        assert!(tiny.footer.bottom() <= 11);
+    }
+
+    #[test]
+    fn bounds_grow_with_the_points() {
+        assert_eq!(bounds(&[]), ([0.0, 1.0], [0.0, 1.0]));
+        assert_eq!(bounds(&[(0.0, 2.0)]), ([0.0, 1.0], [1.8, 2.2]));
+
+        let mut history = vec![progress(0, 0.5, None), progress(1, 0.3, Some(4.5))];
+        let (x, y) = bounds(&train_loss_points(&history));
+        assert_eq!(x, [0.0, 1.0]);
+        assert!((y[0] - 0.28).abs() < 1e-9 && (y[1] - 0.52).abs() < 1e-9);
+
+        history.push(progress(4, 0.1, None));
+        let (x, y) = bounds(&train_loss_points(&history));
+        assert_eq!(x, [0.0, 4.0]);
+        assert!((y[0] - 0.06).abs() < 1e-9);
+
+        let rows = latest_rows(&history);
+        assert_eq!(rows[0], ("Epoch", "4".to_string()));
+        assert_eq!(rows[3], ("SDR", "4.50".to_string()), "the last SDR, from an earlier update");
+        assert_eq!(rows[4], ("GPU memory", "-".to_string()));
+        assert_eq!(rows[5], ("Best SDR", "4.50 @ ep 1".to_string()));
+    }
+
+    fn validated(epoch: usize, sdrs: &[(&str, f64)]) -> TrainingProgress {
+        let instrument_sdr = sdrs.iter().map(|(name, sdr)| (name.to_string(), *sdr)).collect();
+        TrainingProgress { epoch, instrument_sdr, ..Default::default() }
+    }
+
+    #[test]
+    fn each_instrument_keeps_its_best_epoch() {
+        let history = vec![
+            validated(0, &[("vocals", 4.0), ("other", 6.0)]),
+            progress(1, 0.2, None),
+            validated(2, &[("vocals", 7.5), ("other", 5.0)]),
+            validated(3, &[("vocals", 7.0), ("other", 5.5)]),
+        ];
+        let rows = instrument_rows(&history);
+        assert_eq!(rows.iter().map(InstrumentSdr::line).collect::<Vec<_>>(), [
+            "vocals: SDR 7.00, best 7.50 at epoch 2",
+            "other: SDR 5.50, best 6.00 at epoch 0",
+        ]);
+        assert_eq!(best_mean_sdr(&history), Some((6.25, 2)), "the mean of the instruments without an average");
+
+        let mut four = validated(4, &[("bass_guitar", 3.0), ("drums", 8.0), ("lead_vocals", 9.0), ("other", 4.0)]);
+        four.sdr = Some(6.0);
+        let mut history = history;
+        history.push(four);
+        let rows = instrument_rows(&history);
+        assert_eq!(rows.len(), 5);
+        assert_eq!(rows[0].latest, None, "vocals wasn't in the latest validation");
+        assert_eq!(rows[4], InstrumentSdr { name: "lead_vocals".to_string(), latest: Some(9.0), best: 9.0, best_epoch: 4 });
+        assert_eq!(best_mean_sdr(&history), Some((6.25, 2)));
+    }
+}
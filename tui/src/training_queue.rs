@@ -0,0 +1,214 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::model::TrainingConfig;
+
+pub const DEFAULT_QUEUE_FILE: &str = "tui_training_queue.yaml";
+
+/// What the queue does when a run fails or is killed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum FailurePolicy {
+    #[default]
+    StopQueue,
+    Continue,
+}
+
+impl FailurePolicy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FailurePolicy::StopQueue => "stop the queue",
+            FailurePolicy::Continue => "go on with the next run",
+        }
+    }
+
+    pub fn toggled(&self) -> Self {
+        match self {
+            FailurePolicy::StopQueue => FailurePolicy::Continue,
+            FailurePolicy::Continue => FailurePolicy::StopQueue,
+        }
+    }
+}
+
+/// How a queued run ended.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunSummary {
+    /// E.g. "Training finished (exit 0)".
+    pub status: String,
+    pub success: bool,
+    /// The last SDR the run reported.
+    #[serde(default)]
+    pub final_sdr: Option<f64>,
+    pub duration_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedRun {
+    pub config: TrainingConfig,
+    /// Set once the run is over; runs without one are still to go.
+    #[serde(default)]
+    pub summary: Option<RunSummary>,
+    /// The sweep the run was generated for, see `sweep::generate`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sweep: Option<String>,
+}
+
+impl QueuedRun {
+    /// E.g. "SCNet (lr-3e-4): Training finished (exit 0), SDR 9.12, 2h 03m".
+    pub fn line(&self, running: bool) -> String {
+        let mut line = self.config.model_type.name().to_string();
+        if let Some(label) = &self.config.label {
+            line.push_str(&format!(" ({})", label));
+        }
+        match &self.summary {
+            Some(summary) => {
+                line.push_str(&format!(": {}", summary.status));
+                if let Some(sdr) = summary.final_sdr {
+                    line.push_str(&format!(", SDR {:.2}", sdr));
+                }
+                let minutes = summary.duration_secs / 60;
+                line.push_str(&format!(", {}h {:02}m", minutes / 60, minutes % 60));
+            }
+            None if running => line.push_str(": training"),
+            None => line.push_str(&format!(": waiting - {}", self.config.config_path)),
+        }
+        line
+    }
+}
+
+/// Training configs run one after another, kept in a YAML file so an
+/// interrupted session can pick up where it stopped.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrainingQueue {
+    #[serde(default)]
+    pub on_failure: FailurePolicy,
+    #[serde(default)]
+    runs: Vec<QueuedRun>,
+}
+
+impl TrainingQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the queue at `path`; a missing file is an empty queue.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            return Ok(Self::new());
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read training queue {}", path.display()))?;
+        serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse training queue {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_yaml::to_string(self).context("Failed to serialize training queue")?;
+        fs::write(path, content)
+            .with_context(|| format!("Failed to write training queue {}", path.display()))
+    }
+
+    pub fn runs(&self) -> &[QueuedRun] {
+        &self.runs
+    }
+
+    pub fn push(&mut self, config: TrainingConfig) {
+        self.runs.push(QueuedRun { config, summary: None, sweep: None });
+    }
+
+    pub fn push_sweep_run(&mut self, config: TrainingConfig, sweep: &str) {
+        self.runs.push(QueuedRun { config, summary: None, sweep: Some(sweep.to_string()) });
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<QueuedRun> {
+        (index < self.runs.len()).then(|| self.runs.remove(index))
+    }
+
+    /// Swaps the run at `index` with the one before it; false at the top.
+    pub fn move_up(&mut self, index: usize) -> bool {
+        if index == 0 || index >= self.runs.len() {
+            return false;
+        }
+        self.runs.swap(index, index - 1);
+        true
+    }
+
+    pub fn move_down(&mut self, index: usize) -> bool {
+        if index + 1 >= self.runs.len() {
+            return false;
+        }
+        self.runs.swap(index, index + 1);
+        true
+    }
+
+    /// The first run still to go.
+    pub fn next_pending(&self) -> Option<usize> {
+        self.runs.iter().position(|run| run.summary.is_none())
+    }
+
+    pub fn pending(&self) -> usize {
+        self.runs.iter().filter(|run| run.summary.is_none()).count()
+    }
+
+    /// Records how the run at `index` ended; true if the queue goes on.
+    pub fn record(&mut self, index: usize, summary: RunSummary) -> bool {
+        let go_on = summary.success || self.on_failure == FailurePolicy::Continue;
+        if let Some(run) = self.runs.get_mut(index) {
+            run.summary = Some(summary);
+        }
+        go_on
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ModelType;
+
+    fn summary(success: bool) -> RunSummary {
+        RunSummary {
+            status: if success { "Training finished (exit 0)" } else { "Training crashed (exit 1)" }.to_string(),
+            success,
+            final_sdr: success.then_some(9.123),
+            duration_secs: 7380,
+        }
+    }
+
+    #[test]
+    fn runs_are_reordered_and_resumed_from_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DEFAULT_QUEUE_FILE);
+        let mut queue = TrainingQueue::new();
+        for label in ["a", "b", "c"] {
+            let mut config = TrainingConfig::new(ModelType::ScNet);
+            config.label = Some(label.to_string());
+            queue.push(config);
+        }
+        assert!(queue.move_down(0));
+        assert!(queue.move_up(2));
+        assert!(!queue.move_up(0) && !queue.move_down(2));
+        let labels: Vec<_> = queue.runs().iter().map(|run| run.config.label.clone().unwrap()).collect();
+        assert_eq!(labels, ["b", "c", "a"]);
+
+        assert!(queue.record(0, summary(true)));
+        assert_eq!(queue.runs()[0].line(false), "SCNet (b): Training finished (exit 0), SDR 9.12, 2h 03m");
+        queue.save(&path).unwrap();
+
+        let mut resumed = TrainingQueue::load(&path).unwrap();
+        assert_eq!((resumed.next_pending(), resumed.pending()), (Some(1), 2));
+        assert_eq!(resumed.remove(2).unwrap().config.label.as_deref(), Some("a"));
+        assert!(resumed.remove(2).is_none());
+    }
+
+    #[test]
+    fn a_failed_run_stops_the_queue_unless_told_to_go_on() {
+        let mut queue = TrainingQueue::new();
+        queue.push(TrainingConfig::new(ModelType::ScNet));
+        queue.push(TrainingConfig::new(ModelType::ScNet));
+        assert!(!queue.record(0, summary(false)));
+        queue.on_failure = queue.on_failure.toggled();
+        assert!(queue.record(1, summary(false)));
+        assert_eq!(queue.next_pending(), None);
+    }
+}
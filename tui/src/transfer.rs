@@ -0,0 +1,179 @@
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::io::BufReader;
+
+use crate::checkpoints::file_size;
+use crate::log_buffer::LogBuffer;
+use crate::model::CommandSpec;
+use crate::remote::{ssh_options, RemoteLocation};
+use crate::runner::find_program;
+use crate::scratch::dir_size;
+use crate::step_progress::Segments;
+use crate::training::piped_output;
+
+/// A line of rsync's `--info=progress2`, e.g.
+/// "  1,234,567,890  45%   12.34MB/s    0:01:10 (xfr#3, to-chk=5/10)".
+const PROGRESS: &str = r"^\s*(?P<bytes>[\d,]+)\s+(?P<percent>\d{1,3})%\s+(?P<rate>\S+/s)";
+
+fn progress_line() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(PROGRESS).expect("built-in rsync progress pattern"))
+}
+
+/// What copies the files: rsync when it is there, scp otherwise.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tool {
+    Rsync,
+    Scp,
+}
+
+impl Tool {
+    /// rsync, or scp when there is no rsync on PATH.
+    pub fn find() -> Result<Tool> {
+        if find_program("rsync").is_some() {
+            Ok(Tool::Rsync)
+        } else if find_program("scp").is_some() {
+            Ok(Tool::Scp)
+        } else {
+            bail!("rsync not found on PATH, nor scp to fall back on; install rsync to pull results")
+        }
+    }
+
+    /// Copies `source` into `destination`, as a folder of the same name.
+    /// rsync keeps partly copied files and skips whole ones, so pulling
+    /// again resumes an interrupted pull.
+    pub fn command(&self, source: &RemoteLocation, destination: &Path) -> CommandSpec {
+        let ssh = format!("ssh {}", ssh_options().join(" "));
+        match self {
+            Tool::Rsync => {
+                let mut cmd = CommandSpec::new("rsync");
+                cmd.arg("-a").arg("--partial").arg("--info=progress2").arg("-e").arg(ssh);
+                cmd.arg(source.source()).arg(destination);
+                cmd
+            }
+            Tool::Scp => {
+                let mut cmd = CommandSpec::new("scp");
+                cmd.arg("-r");
+                for option in ssh_options() {
+                    cmd.arg(option);
+                }
+                cmd.arg(source.source()).arg(destination);
+                cmd
+            }
+        }
+    }
+}
+
+/// How far a pull is, from rsync's progress.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferProgress {
+    pub bytes: u64,
+    pub percent: u8,
+    pub rate: String,
+}
+
+impl TransferProgress {
+    pub fn parse(segment: &str) -> Option<TransferProgress> {
+        let captures = progress_line().captures(segment)?;
+        Some(TransferProgress {
+            bytes: captures["bytes"].replace(',', "").parse().ok()?,
+            percent: captures["percent"].parse::<u8>().ok()?.min(100),
+            rate: captures["rate"].to_string(),
+        })
+    }
+
+    pub fn ratio(&self) -> f64 {
+        f64::from(self.percent) / 100.0
+    }
+
+    /// E.g. "1.2 GB (45%), 12.34MB/s".
+    pub fn label(&self) -> String {
+        format!("{} ({}%), {}", file_size(self.bytes), self.percent, self.rate)
+    }
+}
+
+/// Copies `source` into `destination` with `tool`, keeping its progress in
+/// `progress` and its other output in `log`. Dropping it stops the copy.
+/// Returns the bytes copied: rsync's count, or with scp the size of the
+/// copied folder.
+pub async fn pull(
+    tool: Tool,
+    source: &RemoteLocation,
+    destination: &Path,
+    progress: Arc<Mutex<Option<TransferProgress>>>,
+    log: Arc<Mutex<LogBuffer>>,
+) -> Result<u64> {
+    std::fs::create_dir_all(destination)
+        .with_context(|| format!("Failed to create {}", destination.display()))?;
+    let command = tool.command(source, destination);
+    log.lock().unwrap().push(&command.display());
+    let mut child = command.command()
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Failed to start {}", command.program))?;
+    let (stdout, stderr) = piped_output(&mut child, "transfer")?;
+
+    let stderr_log = log.clone();
+    let stderr_task = tokio::spawn(async move {
+        let mut lines = Segments::new(BufReader::new(stderr));
+        let mut last = String::new();
+        while let Some(line) = lines.next_segment().await.context("Failed to read the transfer's stderr")? {
+            if !line.trim().is_empty() {
+                stderr_log.lock().unwrap().push_error(&line);
+                last = line;
+            }
+        }
+        Ok::<_, anyhow::Error>(last)
+    });
+    let mut lines = Segments::new(BufReader::new(stdout));
+    let mut copied = None;
+    while let Some(line) = lines.next_segment().await.context("Failed to read the transfer's output")? {
+        match TransferProgress::parse(&line) {
+            Some(update) => {
+                copied = Some(update.bytes);
+                *progress.lock().unwrap() = Some(update);
+            }
+            None if !line.trim().is_empty() => {
+                log.lock().unwrap().push(&line);
+            }
+            None => {}
+        }
+    }
+    let status = child.wait().await.context("Failed to wait for the transfer")?;
+    let error = stderr_task.await.context("stderr task failed")??;
+    if !status.success() {
+        bail!("{} from {} failed ({}): {}", command.program, source.source(), status, error);
+    }
+    Ok(copied.unwrap_or_else(|| dir_size(&destination.join(source.name()))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rsync_progress_is_read_and_pulls_resume() {
+        let progress = TransferProgress::parse("  1,234,567,890  45%   12.34MB/s    0:01:10 (xfr#3, to-chk=5/10)").unwrap();
+        assert_eq!(progress, TransferProgress { bytes: 1_234_567_890, percent: 45, rate: "12.34MB/s".to_string() });
+        assert_eq!(progress.label(), "1.2 GB (45%), 12.34MB/s");
+        assert_eq!(TransferProgress::parse("results/run-1/model_scnet_ep_3.ckpt"), None);
+
+        let source = RemoteLocation {
+            host: "gpu-box".to_string(),
+            ssh_destination: "ubuntu@10.0.0.5".to_string(),
+            path: "/srv/msst/results/run-1".to_string(),
+        };
+        let rsync = Tool::Rsync.command(&source, Path::new("results"));
+        assert_eq!(
+            rsync.args,
+            ["-a", "--partial", "--info=progress2", "-e", "ssh -o BatchMode=yes -o ConnectTimeout=10", "ubuntu@10.0.0.5:/srv/msst/results/run-1", "results"]
+        );
+        let scp = Tool::Scp.command(&source, Path::new("results"));
+        assert_eq!(scp.display(), "scp -r -o BatchMode=yes -o ConnectTimeout=10 ubuntu@10.0.0.5:/srv/msst/results/run-1 results");
+    }
+}
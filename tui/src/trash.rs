@@ -0,0 +1,320 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::config::AppConfig;
+
+pub const DEFAULT_TRASH_DIR: &str = ".msst_trash";
+pub const DEFAULT_TRASH_MAX_MB: u64 = 2048;
+pub const DEFAULT_TRASH_MAX_AGE_DAYS: u64 = 14;
+pub const ITEM_FILE: &str = "trashed.json";
+
+static ITEM_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// What went into the trash, written next to it as `trashed.json`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrashedItem {
+    pub id: String,
+    pub original: PathBuf,
+    /// The action that put it here, e.g. "config overwrite".
+    pub operation: String,
+    pub trashed_at: DateTime<Utc>,
+    pub bytes: u64,
+    /// The original was overwritten rather than removed, and this is its
+    /// old content. Restoring writes it back over whatever is there now.
+    #[serde(default)]
+    pub kept_copy: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PurgeReport {
+    pub removed: usize,
+    pub freed_bytes: u64,
+}
+
+/// Where destructive actions put things instead of deleting them:
+/// `<root>/<id>/<name>` plus `<root>/<id>/trashed.json`. Items older than
+/// the age limit go first when purging, then the oldest until the whole
+/// trash fits the size cap.
+#[derive(Debug, Clone)]
+pub struct Trash {
+    root: PathBuf,
+    max_bytes: u64,
+    max_age: Duration,
+}
+
+impl Default for Trash {
+    fn default() -> Self {
+        Trash::new(PathBuf::from(DEFAULT_TRASH_DIR), DEFAULT_TRASH_MAX_MB, DEFAULT_TRASH_MAX_AGE_DAYS)
+    }
+}
+
+impl Trash {
+    pub fn new(root: PathBuf, max_mb: u64, max_age_days: u64) -> Self {
+        Trash {
+            root,
+            max_bytes: max_mb * 1024 * 1024,
+            max_age: Duration::days(max_age_days as i64),
+        }
+    }
+
+    /// A relative `trash_dir`, like the default, is under the project `root`.
+    pub fn from_config(config: &AppConfig, root: &Path) -> Self {
+        Trash::new(
+            root.join(config.trash_dir.as_deref().unwrap_or(DEFAULT_TRASH_DIR)),
+            config.trash_max_mb.unwrap_or(DEFAULT_TRASH_MAX_MB),
+            config.trash_max_age_days.unwrap_or(DEFAULT_TRASH_MAX_AGE_DAYS),
+        )
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Moves a file or folder into the trash.
+    pub fn discard(&self, path: &Path, operation: &str) -> Result<TrashedItem> {
+        if !path.exists() {
+            bail!("{} does not exist", path.display());
+        }
+        let (dir, item) = self.new_item(path, operation, false)?;
+        move_path(path, &dir.join(item_name(path)))?;
+        Ok(item)
+    }
+
+    /// Copies a file that is about to be overwritten into the trash. A
+    /// missing file has nothing to keep.
+    pub fn keep_copy(&self, path: &Path, operation: &str) -> Result<Option<TrashedItem>> {
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let (dir, item) = self.new_item(path, operation, true)?;
+        fs::copy(path, dir.join(item_name(path)))
+            .with_context(|| format!("Failed to copy {} to the trash", path.display()))?;
+        Ok(Some(item))
+    }
+
+    fn new_item(&self, path: &Path, operation: &str, kept_copy: bool) -> Result<(PathBuf, TrashedItem)> {
+        let trashed_at = Utc::now();
+        let id = format!(
+            "{}-{}-{}",
+            trashed_at.format("%Y%m%dT%H%M%S%.3f"),
+            std::process::id(),
+            ITEM_COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        let dir = self.root.join(&id);
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create trash folder {}", dir.display()))?;
+        let item = TrashedItem {
+            id,
+            original: std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()),
+            operation: operation.to_string(),
+            trashed_at,
+            bytes: path_size(path),
+            kept_copy,
+        };
+        let content = serde_json::to_string_pretty(&item)
+            .context("Failed to serialize trash item")?;
+        fs::write(dir.join(ITEM_FILE), content)
+            .context("Failed to write trash item")?;
+        Ok((dir, item))
+    }
+
+    /// Puts an item back where it came from and removes it from the trash.
+    /// A removed file is not restored over something new in its place.
+    pub fn restore(&self, id: &str) -> Result<TrashedItem> {
+        let item = self.item(id)?;
+        let dir = self.root.join(id);
+        let content = dir.join(item_name(&item.original));
+        if item.original.exists() {
+            if !item.kept_copy {
+                bail!("Something new is at {}; move it away to restore", item.original.display());
+            }
+            fs::remove_file(&item.original)
+                .with_context(|| format!("Failed to replace {}", item.original.display()))?;
+        }
+        if let Some(parent) = item.original.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to recreate {}", parent.display()))?;
+        }
+        move_path(&content, &item.original)?;
+        fs::remove_dir_all(&dir)
+            .with_context(|| format!("Failed to remove trash folder {}", dir.display()))?;
+        Ok(item)
+    }
+
+    /// Deletes an item for good.
+    pub fn delete(&self, id: &str) -> Result<()> {
+        let item = self.item(id)?;
+        let dir = self.root.join(&item.id);
+        fs::remove_dir_all(&dir)
+            .with_context(|| format!("Failed to remove trash folder {}", dir.display()))
+    }
+
+    /// Everything in the trash, newest first.
+    pub fn items(&self) -> Result<Vec<TrashedItem>> {
+        let mut items = vec![];
+        if !self.root.is_dir() {
+            return Ok(items);
+        }
+        for entry in fs::read_dir(&self.root)
+            .context("Failed to read trash folder")?
+        {
+            let path = entry.context("Failed to read directory entry")?.path();
+            let item = fs::read_to_string(path.join(ITEM_FILE))
+                .ok()
+                .and_then(|content| serde_json::from_str::<TrashedItem>(&content).ok());
+            if let Some(item) = item {
+                items.push(item);
+            }
+        }
+        items.sort_by(|a, b| b.trashed_at.cmp(&a.trashed_at).then_with(|| b.id.cmp(&a.id)));
+        Ok(items)
+    }
+
+    fn item(&self, id: &str) -> Result<TrashedItem> {
+        let content = fs::read_to_string(self.root.join(id).join(ITEM_FILE))
+            .with_context(|| format!("No item {} in the trash (purged?)", id))?;
+        serde_json::from_str(&content).context("Failed to parse trash item")
+    }
+
+    /// Deletes items past the age limit, then the oldest until the rest fit
+    /// the size cap.
+    pub fn purge(&self, now: DateTime<Utc>) -> Result<PurgeReport> {
+        let mut report = PurgeReport::default();
+        let mut kept = 0;
+        for item in self.items()? {
+            if now - item.trashed_at <= self.max_age && kept + item.bytes <= self.max_bytes {
+                kept += item.bytes;
+                continue;
+            }
+            self.delete(&item.id)?;
+            report.removed += 1;
+            report.freed_bytes += item.bytes;
+        }
+        Ok(report)
+    }
+
+    /// Deletes everything.
+    pub fn empty(&self) -> Result<PurgeReport> {
+        let mut report = PurgeReport::default();
+        for item in self.items()? {
+            self.delete(&item.id)?;
+            report.removed += 1;
+            report.freed_bytes += item.bytes;
+        }
+        Ok(report)
+    }
+}
+
+fn item_name(path: &Path) -> PathBuf {
+    PathBuf::from(path.file_name().unwrap_or(path.as_os_str()))
+}
+
+/// Renames, or copies and removes when `from` and `to` are on different
+/// filesystems.
+fn move_path(from: &Path, to: &Path) -> Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    copy_recursive(from, to)?;
+    if from.is_dir() {
+        fs::remove_dir_all(from)
+    } else {
+        fs::remove_file(from)
+    }
+    .with_context(|| format!("Failed to remove {} after copying it", from.display()))
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> Result<()> {
+    if from.is_dir() {
+        fs::create_dir_all(to)
+            .with_context(|| format!("Failed to create {}", to.display()))?;
+        for entry in fs::read_dir(from).with_context(|| format!("Failed to read {}", from.display()))? {
+            let entry = entry.context("Failed to read directory entry")?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(from, to)
+            .with_context(|| format!("Failed to copy {}", from.display()))?;
+    }
+    Ok(())
+}
+
+fn path_size(path: &Path) -> u64 {
+    if path.is_dir() {
+        fs::read_dir(path)
+            .map(|entries| entries.filter_map(|entry| entry.ok()).map(|entry| path_size(&entry.path())).sum())
+            .unwrap_or(0)
+    } else {
+        fs::metadata(path).map_or(0, |metadata| metadata.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backdate(trash: &Trash, id: &str, days: i64) {
+        let path = trash.root().join(id).join(ITEM_FILE);
+        let mut item: TrashedItem = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        item.trashed_at -= Duration::days(days);
+        fs::write(path, serde_json::to_string(&item).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn discarded_files_and_folders_come_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash = Trash::new(dir.path().join("trash"), 10, 14);
+        let checkpoints = dir.path().join("results/run/checkpoints");
+        fs::create_dir_all(&checkpoints).unwrap();
+        fs::write(checkpoints.join("ep_1.ckpt"), "weights").unwrap();
+        let config = dir.path().join("tui_config.yaml");
+        fs::write(&config, "theme: Dark\n").unwrap();
+
+        let folder = trash.discard(&checkpoints, "checkpoint cleanup").unwrap();
+        assert!(!checkpoints.exists());
+        assert_eq!(folder.bytes, 7);
+        let copy = trash.keep_copy(&config, "config overwrite").unwrap().unwrap();
+        fs::write(&config, "theme: Light\n").unwrap();
+        assert!(trash.keep_copy(&dir.path().join("missing.yaml"), "config overwrite").unwrap().is_none());
+        assert_eq!(trash.items().unwrap().len(), 2);
+
+        trash.restore(&copy.id).unwrap();
+        assert_eq!(fs::read_to_string(&config).unwrap(), "theme: Dark\n");
+
+        fs::create_dir_all(&checkpoints).unwrap();
+        assert!(trash.restore(&folder.id).is_err(), "doesn't clobber a new folder");
+        fs::remove_dir(&checkpoints).unwrap();
+        trash.restore(&folder.id).unwrap();
+        assert_eq!(fs::read_to_string(checkpoints.join("ep_1.ckpt")).unwrap(), "weights");
+        assert!(trash.items().unwrap().is_empty());
+    }
+
+    #[test]
+    fn purge_drops_old_items_then_oldest_over_the_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        // A 1 MB cap with items of 400 KB each.
+        let trash = Trash::new(dir.path().join("trash"), 1, 14);
+        let mut ids = vec![];
+        for (i, age) in [20, 5, 3, 2, 0].iter().enumerate() {
+            let path = dir.path().join(format!("stem_{}.wav", i));
+            fs::write(&path, vec![0u8; 400 * 1024]).unwrap();
+            let item = trash.discard(&path, "cleanup").unwrap();
+            backdate(&trash, &item.id, *age);
+            ids.push(item.id);
+        }
+
+        let report = trash.purge(Utc::now()).unwrap();
+        // The 20-day-old one is past the age limit; of the rest only the
+        // two newest fit in 1 MB.
+        assert_eq!(report, PurgeReport { removed: 3, freed_bytes: 3 * 400 * 1024 });
+        let left: Vec<String> = trash.items().unwrap().into_iter().map(|item| item.id).collect();
+        assert_eq!(left, [ids[4].clone(), ids[3].clone()]);
+
+        assert_eq!(trash.purge(Utc::now()).unwrap(), PurgeReport::default(), "nothing more to do");
+        assert_eq!(trash.purge(Utc::now() + Duration::days(15)).unwrap().removed, 2);
+    }
+}
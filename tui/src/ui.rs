@@ -1,17 +1,130 @@
 use ratatui::{
     backend::CrosstermBackend,
-    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+    symbols::Marker,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, List, ListItem, Paragraph, Row, Table, Wrap},
     Frame, Terminal,
 };
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    event::{
+        self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    },
 };
-use std::io;
-use std::time::Duration;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
 
-use crate::model::ModelType;
+use crate::attention::{Attention, AttentionEvent, AttentionSettings};
+use crate::bakeoff::{self, pair_stems, BakeOffReport, StemComparison};
+use crate::bundle::{ConfigBundle, ImportMode, ImportPlan, DEFAULT_BUNDLE_FILE};
+use crate::checkpoints::{file_size, list_checkpoints, resume_checkpoint, CheckpointInfo};
+use crate::checksum;
+use crate::clipboard;
+use crate::config::{
+    model_instruments, model_num_epochs, AppConfig, ConfigManager, RecentConfig, RecentKind, DEFAULT_CONFIGS_DIR, DEFAULT_CONFIG_FILE, DEFAULT_TRAINING_CONFIG_FILE,
+};
+use crate::compare::{RunComparison, SdrDelta};
+use crate::config_check::{self, has_errors, ConfigIssue};
+use crate::config_form::{ConfigForm, FORM_FIELDS};
+use crate::cost::{format_amount, monthly_totals, sdr_per_cost};
+use crate::dashboard::Dashboard;
+use crate::download::{self, DownloadEntry};
+use crate::ensemble::{self, EnsembleManager};
+use crate::help::{self, HelpTopic};
+use crate::history::{HistoryEntry, RunDetail, RunHistory, RunKind, DEFAULT_HISTORY_FILE};
+use crate::file_picker::CHECKPOINT_EXTENSIONS;
+use crate::file_watch::{FileWatcher, Watched};
+use crate::gpu::{self, GpuDevice, GpuMonitor, GpuReading, GPU_POLL_INTERVAL};
+use crate::gpu_check;
+use crate::inference::{build_inference_command, preview_inference_command, InferenceManager};
+use crate::inference_batch::{InferenceBatch, DEFAULT_BATCH_FILE};
+use crate::input_preview::{AudioEntry, InputPreview};
+use crate::integrity::{self, Quarantined};
+use crate::jobs::{detect_gpus, DeviceAllocator, DeviceRequirement, Job, JobId, JobKind, JobSpec, JobState, JobSupervisor};
+use crate::keymap::{Action, KeySpec};
+use crate::lint;
+use crate::log_buffer::{LogBuffer, Severity, TimeDisplay};
+use crate::log_pane::LogPane;
+use crate::manifest::ResultsManifest;
+use crate::metrics::TrainingHistory;
+use crate::mixdown::{self, MixPreview};
+use crate::model_config::{ModelConfigEditor, NodeKind};
+use crate::mouse::{ClickTracker, LayoutMap};
+use crate::model::{EnsembleConfig, InferenceConfig, InferenceResult, ModelType, TrainingConfig, TrainingProgress, ValidationConfig, ValidationResult};
+use crate::notify::{self, Notification};
+use crate::output_view::{player_command, OutputListing};
+use crate::parse_health::ParseMonitor;
+use crate::parser::ParserRules;
+use crate::plain::{self, Announcer};
+use crate::preflight::{self, CheckItem, PreflightReport};
+use crate::preset::InferencePreset;
+use crate::pretrained::{variants_for, ModelBinding};
+use crate::process::ProcessState;
+use crate::project::{settings_path, Scripts};
+use crate::reconstruction::{self, ReconstructionReport, TrackReconstruction};
+use crate::remote::{self, RemoteHost, RemoteLocation};
+use crate::resources::ResourceSummary;
+use crate::preview::PreviewPlayer;
+use crate::run_dir::RunDir;
+use crate::runner::RunnerEnv;
+use crate::scratch::{Scratch, ScratchUsage};
+use crate::selection::TrackSelection;
+use crate::shell_words;
+use crate::shutdown::{ShutdownBudget, ShutdownReport};
+use crate::status_bar::{self, StatusInfo};
+use crate::storage::{self, StorageCache, StorageReport};
+use crate::stem_names::{self, RenamePlan};
+use crate::step_progress::StepProgress;
+use crate::sweep::{self, SweepResult, SweepSpec, DEFAULT_MAX_SWEEP_RUNS};
+use crate::table::{Column, DataTable, SortKey};
+use crate::tail::TailEvent;
+use crate::task::{CancelToken, OnCancel, Task, TaskContext, TaskMonitor, TaskOutcome};
+use crate::template::{self, RunTemplate};
+use crate::terminal::TerminalGuard;
+use crate::text_input::TextInput;
+use crate::theme::Styles;
+use crate::training::{build_train_command, find_training_log, preview_training_command, StopOutcome, TrainingManager};
+use crate::training_chart;
+use crate::training_queue::{RunSummary, TrainingQueue, DEFAULT_QUEUE_FILE};
+use crate::transfer::{self, Tool, TransferProgress};
+use crate::trash::{Trash, TrashedItem};
+use crate::undo::{confirmation, UndoAction, UndoJournal};
+use crate::validation::{self, ValidationManager, ValidationProgress};
+use crate::watchdog::{self, Watchdog};
+use crate::widgets::{self, BrowserAction, ErrorState, FileBrowser, PickMode};
+use crate::yaml_diff::{self, DiffEntry};
+
+const PREVIEW_GAIN_STEP_DB: f32 = 0.5;
+const PREVIEW_SEEK_SECS: f64 = 5.0;
+
+/// Lines the log pane scrolls per mouse wheel step.
+const MOUSE_SCROLL_LINES: usize = 3;
+
+/// How long a warning stays on the bottom row.
+const WARNING_DURATION: Duration = Duration::from_secs(5);
+
+/// Rows of the log pane on the Training and Inference screens.
+const LOG_PANE_HEIGHT: u16 = 10;
+
+pub const HOME_MENU: [&str; 11] = [
+    "1. Model Selection",
+    "2. Configuration",
+    "3. Training",
+    "4. Inference",
+    "5. Validation",
+    "6. Run History",
+    "7. Settings",
+    "8. Ensemble",
+    "e. Check environment",
+    "q. Quit",
+    "h. Help",
+];
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Screen {
@@ -21,15 +134,486 @@ pub enum Screen {
     Training,
     Inference,
     Validation,
+    Preview,
+    Settings,
+    ParserTest,
+    History,
+    /// A run from the history with the metrics it recorded, charted again.
+    RunDetail,
+    /// Two runs from the history: their SDR curves and best SDR per instrument.
+    Compare,
+    BakeOff,
+    /// Picking the input files of a batch.
+    Tracks,
+    /// The trash: recently deleted and overwritten files.
+    Trash,
+    /// What importing a settings bundle would change, before it does.
+    ImportBundle,
+    /// A history run's training config, copied to be edited for a new run.
+    DuplicateRun,
+    /// Training runs owned by other instances, followed through their logs.
+    Dashboard,
+    /// A track's stems played mixed, before a mixdown is written.
+    Mix,
+    /// Stem renames for an existing output folder, before any file moves.
+    RenameStems,
+    /// Supervised python jobs, with the watchdog's hang warnings.
+    Jobs,
+    /// How well each track's stems in an output folder sum to its input.
+    Reconstruction,
+    /// Checkpoints under the results and weights folders, to run inference with.
+    Checkpoints,
+    /// The log of the Training or Inference screen, whichever opened it,
+    /// or of the run attached to on the Jobs screen.
+    Log,
+    /// Configs runs were launched with, to load one again.
+    Recent,
+    /// Training configs waiting to run one after another.
+    TrainingQueue,
+    /// The runs of a hyperparameter sweep and how each did.
+    Sweep,
+    /// The model YAML the training config points at, as a tree of values.
+    ModelConfig,
+    /// Input folders and files separated one after another, with how each went.
+    InferenceBatch,
+    /// The audio files in the inference input folder, before a run.
+    InputFiles,
+    /// The stems an inference run wrote, by track.
+    Outputs,
+    /// Published checkpoints to download and bind.
+    Downloads,
+    /// The GPUs found, to pick the training run's `device_ids` from.
+    Devices,
+    /// Whether the python environment has what the scripts import.
+    Environment,
+    /// Where the Training or Inference screen's runs go: here, or one of
+    /// `remote_hosts` over ssh.
+    Hosts,
+    /// Stems from several models combined into one file by ensemble.py.
+    Ensemble,
+    /// What differs between two YAML configs, by top-level section.
+    ConfigDiff,
+}
+
+impl Screen {
+    pub const ALL: [Screen; 37] = [
+        Screen::Home,
+        Screen::ModelSelection,
+        Screen::Config,
+        Screen::Training,
+        Screen::Inference,
+        Screen::Validation,
+        Screen::Preview,
+        Screen::Settings,
+        Screen::ParserTest,
+        Screen::History,
+        Screen::RunDetail,
+        Screen::Compare,
+        Screen::BakeOff,
+        Screen::Tracks,
+        Screen::Trash,
+        Screen::ImportBundle,
+        Screen::DuplicateRun,
+        Screen::Dashboard,
+        Screen::Mix,
+        Screen::RenameStems,
+        Screen::Jobs,
+        Screen::Reconstruction,
+        Screen::Checkpoints,
+        Screen::Log,
+        Screen::Recent,
+        Screen::TrainingQueue,
+        Screen::Sweep,
+        Screen::ModelConfig,
+        Screen::InferenceBatch,
+        Screen::InputFiles,
+        Screen::Outputs,
+        Screen::Downloads,
+        Screen::Devices,
+        Screen::Environment,
+        Screen::Hosts,
+        Screen::Ensemble,
+        Screen::ConfigDiff,
+    ];
+}
+
+/// A destructive action waiting for y/n.
+#[derive(Debug, Clone, PartialEq)]
+enum ConfirmAction {
+    DeleteTrashed(String),
+    EmptyTrash,
+    DiscardModelConfig,
+    /// Run inference on an input folder without any audio files in it.
+    RunWithoutAudio,
+    /// Set the training config's start_checkpoint to this checkpoint.
+    Resume(PathBuf),
+    /// Download weights there is no checksum for.
+    DownloadUnverified(DownloadEntry),
+}
+
+/// Where the user was going when a running job made the app ask first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Leaving {
+    Quit,
+    /// Esc off the Training or Inference screen.
+    Screen,
+}
+
+/// A run whose command is shown before it starts.
+#[derive(Debug, Clone)]
+enum Launch {
+    Training(TrainingConfig),
+    Inference(InferenceConfig),
+}
+
+impl Launch {
+    fn model_type(&self) -> &ModelType {
+        match self {
+            Launch::Training(config) => &config.model_type,
+            Launch::Inference(config) => &config.model_type,
+        }
+    }
+}
+
+/// How a training run's process ended, how it was stopped if it was, and
+/// how heavy it was.
+type TrainingEnd = (ProcessState, Option<StopOutcome>, Option<ResourceSummary>);
+
+/// A run submitted to `supervisor`, waiting there for its GPUs.
+#[derive(Debug, Clone)]
+enum GatedRun {
+    Training(TrainingConfig),
+    Inference(InferenceConfig),
+    Validation(ValidationConfig),
+}
+
+impl GatedRun {
+    fn kind(&self) -> JobKind {
+        match self {
+            GatedRun::Training(_) => JobKind::Train,
+            GatedRun::Inference(_) => JobKind::Infer,
+            GatedRun::Validation(_) => JobKind::Validate,
+        }
+    }
 }
 
 pub struct App {
     pub screen: Screen,
     pub selected_index: usize,
-    pub previous_screen: Option<Screen>,
+    /// The screens Esc goes back to, innermost last, each with the
+    /// selection it had.
+    pub nav_stack: Vec<(Screen, usize)>,
     pub help_visible: bool,
     pub selected_model: Option<ModelType>,
     pub should_quit: bool,
+    /// Linear text instead of boxes, for screen readers; also `plain` in
+    /// the app config. See `plain::render`.
+    pub plain: bool,
+    pub config: AppConfig,
+    /// Entry points of the checkout, found at startup; see `project`.
+    pub scripts: Scripts,
+    /// The checkout the scripts run in, found at startup.
+    pub project_root: PathBuf,
+    /// Why the app config could not be loaded or saved, if it couldn't.
+    pub config_status: Option<String>,
+    /// State files found unreadable at startup and moved aside.
+    pub quarantined: Vec<Quarantined>,
+    pub preview: Option<PreviewPlayer>,
+    pub preview_error: Option<String>,
+    pub scratch_status: Option<String>,
+    /// Measured when Settings opens rather than on every frame.
+    pub scratch_usage: Option<Result<ScratchUsage, String>>,
+    pub parser_rules: ParserRules,
+    /// Why custom parser rules could not be loaded, if they couldn't.
+    pub parser_error: Option<String>,
+    pub parser_input: TextInput,
+    pub history: Option<RunHistory>,
+    pub history_table: DataTable<HistoryEntry>,
+    pub history_filter: TextInput,
+    pub history_filter_active: bool,
+    /// Notes being edited for the selected history entry.
+    pub notes_editor: Option<TextInput>,
+    /// Folder being typed for "import results folder".
+    pub import_root: Option<TextInput>,
+    /// Label being typed for the selected imported entry.
+    pub label_editor: Option<TextInput>,
+    pub history_status: Option<String>,
+    pub run_detail: Option<RunDetail>,
+    /// Local folder to pull a remote run's results into, being typed.
+    pub pull_prompt: Option<TextInput>,
+    pull_source: Option<RemoteLocation>,
+    /// A pull going on, with the folder it goes into.
+    pull: Option<(PathBuf, Task<u64>)>,
+    pull_progress: Arc<Mutex<Option<TransferProgress>>>,
+    pull_job: Option<u64>,
+    pub pull_status: Option<String>,
+    /// The run marked with `c`, to compare with the next one.
+    pub compare_first: Option<HistoryEntry>,
+    pub comparison: Option<RunComparison>,
+    pub comparison_table: DataTable<SdrDelta>,
+    /// Folder and report of the bake-off being compared.
+    pub bake_off: Option<(PathBuf, BakeOffReport)>,
+    pub bake_off_table: DataTable<StemComparison>,
+    /// Candidates playing on the A and B sides.
+    pub bake_off_sides: (usize, usize),
+    /// Background tasks shown in the status row; Ctrl+X cancels the newest.
+    pub tasks: Vec<TaskMonitor>,
+    /// Cancelled when the app quits; `spawn_task` starts work under it.
+    pub shutdown: CancelToken,
+    /// Every job of the session, listed on the Jobs screen: the app's own
+    /// runs, queued until their GPUs are free, and supervised commands.
+    pub supervisor: JobSupervisor,
+    /// Runs submitted to `supervisor` that haven't been given their GPUs yet.
+    gated_runs: Vec<(JobId, GatedRun)>,
+    attention: Attention,
+    /// Finished jobs already signalled.
+    announced_jobs: HashSet<JobId>,
+    /// Jobs signalled as possibly hung, until they are not any more.
+    announced_hangs: HashSet<JobId>,
+    pub jobs_status: Option<String>,
+    /// The jobs of the training, inference, validation and ensemble going
+    /// on now, which hold their GPUs until they end.
+    training_job: Option<JobId>,
+    inference_job: Option<JobId>,
+    validation_job: Option<JobId>,
+    ensemble_job: Option<JobId>,
+    /// The run whose log the Log screen shows when opened from Jobs.
+    attached_job: Option<JobId>,
+    job_log_pane: LogPane,
+    /// py-spy stack dump being taken; yields the job name and the dump.
+    stack_dump: Option<Task<(String, String)>>,
+    /// The latest py-spy dump, shown under the job list.
+    pub last_dump: Option<(String, String)>,
+    /// Settings on the Inference setup screen, for the selected model.
+    pub inference_form: Option<InferenceConfig>,
+    /// Name being typed for "save current settings as preset".
+    pub preset_name: Option<TextInput>,
+    pub inference_status: Option<String>,
+    /// Input folder being typed before its files are listed.
+    pub input_folder_prompt: Option<TextInput>,
+    pub config_path_prompt: Option<TextInput>,
+    pub store_dir_prompt: Option<TextInput>,
+    pub checkpoints: Vec<CheckpointInfo>,
+    /// Browsing for the focused path prompt, opened with Ctrl+O.
+    pub file_browser: Option<FileBrowser>,
+    inference: Option<Task<InferenceResult>>,
+    inference_rx: Option<mpsc::UnboundedReceiver<(Severity, String)>>,
+    /// What the running or last inference printed.
+    pub inference_log: Arc<Mutex<LogBuffer>>,
+    /// The latest tqdm bar of the inference run.
+    pub inference_step: Arc<Mutex<Option<StepProgress>>>,
+    inference_log_pane: LogPane,
+    /// Inference runs of this session, oldest first.
+    pub inference_results: Vec<InferenceResult>,
+    pub validation_form: Option<ValidationConfig>,
+    pub valid_path_prompt: Option<TextInput>,
+    validation: Option<Task<Vec<ValidationResult>>>,
+    validation_rx: Option<mpsc::UnboundedReceiver<ValidationProgress>>,
+    pub validation_progress: Option<ValidationProgress>,
+    pub validation_results: Vec<ValidationResult>,
+    pub validation_table: DataTable<ValidationResult>,
+    /// The inference input folder's audio files, rescanned when the folder
+    /// changes and before each run.
+    pub input_preview: Option<InputPreview>,
+    pub input_files_table: DataTable<AudioEntry>,
+    pub output_listing: Option<OutputListing>,
+    pub output_status: Option<String>,
+    /// A stem to open with the player once the terminal is handed over.
+    player_request: Option<PathBuf>,
+    pub validation_status: Option<String>,
+    pub ensemble_form: EnsembleConfig,
+    /// A stem file to add to the ensemble, being typed.
+    pub ensemble_file_prompt: Option<TextInput>,
+    pub ensemble_weights_prompt: Option<TextInput>,
+    pub ensemble_output_prompt: Option<TextInput>,
+    ensemble: Option<Task<String>>,
+    pub ensemble_log: Arc<Mutex<LogBuffer>>,
+    ensemble_log_pane: LogPane,
+    pub ensemble_status: Option<String>,
+    pub rename_prompt: Option<TextInput>,
+    /// The output folder and what renaming its stems would do.
+    pub rename_plan: Option<(PathBuf, RenamePlan)>,
+    /// Output folder being typed before its stems are summed back.
+    pub verify_prompt: Option<TextInput>,
+    /// Extra inference.py arguments being edited, quoted as in a shell.
+    pub extra_args_prompt: Option<TextInput>,
+    /// The stem-sum check running on an output folder.
+    verification: Option<Task<(PathBuf, ReconstructionReport)>>,
+    pub reconstruction: Option<(PathBuf, ReconstructionReport)>,
+    pub reconstruction_table: DataTable<TrackReconstruction>,
+    pub track_selection: Option<TrackSelection>,
+    /// Glob being typed on the track selection screen.
+    pub exclude_prompt: Option<TextInput>,
+    /// Destructive operations of this session; `u` undoes the newest.
+    pub undo: UndoJournal,
+    pub trash_items: Vec<TrashedItem>,
+    pub trash_status: Option<String>,
+    pub recent_status: Option<String>,
+    /// Shown on the bottom row until the next key, e.g. what `u` undid.
+    pub notice: Option<String>,
+    /// Something that went wrong but needn't stop anything, shown on the
+    /// bottom row for `WARNING_DURATION` from when it was raised.
+    pub warning: Option<(String, Instant)>,
+    /// How the last long job ended, across the top until the next key.
+    pub banner: Option<Notification>,
+    /// A failed action, shown in a modal that takes every key until it is
+    /// dismissed; see `report_error`.
+    pub error: Option<ErrorState>,
+    /// Checkpoint path being typed for the highlighted model.
+    pub checkpoint_prompt: Option<TextInput>,
+    /// Config path being typed for the bound model.
+    pub model_config_prompt: Option<TextInput>,
+    /// Search typed after `/` on Model Selection; the list shows only the
+    /// matching models while it is open.
+    pub model_filter: Option<TextInput>,
+    pub model_status: Option<String>,
+    /// Where to write the settings bundle, being typed.
+    pub export_prompt: Option<TextInput>,
+    /// Which settings bundle to import, being typed.
+    pub import_prompt: Option<TextInput>,
+    /// The python interpreter, being typed.
+    pub python_prompt: Option<TextInput>,
+    pub import_preview: Option<(ConfigBundle, ImportPlan)>,
+    pub run_template: Option<RunTemplate>,
+    /// The training config being edited on the Config screen.
+    pub config_form: Option<ConfigForm>,
+    /// The Config screen field being edited.
+    pub config_field: Option<TextInput>,
+    pub config_form_status: Option<String>,
+    /// The model YAML being edited from the Config screen.
+    pub model_config: Option<ModelConfigEditor>,
+    /// The value of the highlighted model config key, being edited.
+    pub model_config_field: Option<TextInput>,
+    pub model_config_status: Option<String>,
+    /// Follows the configs and results folders, when `watch_files` is on.
+    file_watcher: Option<FileWatcher>,
+    /// A YAML config to diff being typed: the first of the two, then the
+    /// one to compare it with.
+    pub diff_prompt: Option<TextInput>,
+    diff_first: Option<PathBuf>,
+    /// The two configs compared and how they differ.
+    pub config_diff: Option<(PathBuf, PathBuf, Vec<DiffEntry>)>,
+    /// Progress of the run started from the Training screen, oldest first.
+    /// Kept after the run ends until the screen is left.
+    pub training_history: TrainingHistory,
+    training_rx: Option<mpsc::UnboundedReceiver<TrainingProgress>>,
+    training: Option<Task<TrainingEnd>>,
+    /// Cancelled to leave the run going after the TUI quits.
+    training_detach: Option<CancelToken>,
+    /// Hang watchdog of the run started from the Training screen.
+    training_watchdog: Option<Arc<Mutex<Watchdog>>>,
+    /// Whether the parser recognizes the output of the run started or
+    /// followed from the Training screen.
+    training_parse_health: Option<Arc<Mutex<ParseMonitor>>>,
+    /// Log file or results folder being typed, of a run started elsewhere.
+    pub monitor_prompt: Option<TextInput>,
+    /// The log of a run the TUI didn't start, followed onto the Training
+    /// screen like a run of its own; cancelling only stops reading it.
+    monitor: Option<(PathBuf, Task<()>)>,
+    monitor_rx: Option<mpsc::UnboundedReceiver<TailEvent>>,
+    /// Output of the running or last run, filled by the manager's readers.
+    training_log: Arc<Mutex<LogBuffer>>,
+    /// The latest tqdm bar of the training run: its place in the epoch.
+    pub training_step: Arc<Mutex<Option<StepProgress>>>,
+    /// What the last `t` found wrong with the training config; warnings
+    /// shown once start with the next `t`.
+    pub training_issues: Vec<ConfigIssue>,
+    training_log_pane: LogPane,
+    pub training_status: Option<String>,
+    /// Training runs to go one after another, saved on every change.
+    pub training_queue: TrainingQueue,
+    /// The queued run training now, by its place in the queue.
+    queue_run: Option<usize>,
+    pub queue_status: Option<String>,
+    /// Inputs to separate one after another, saved on every change.
+    pub inference_batch: InferenceBatch,
+    /// The batch item running now, by its place in the batch.
+    batch_run: Option<usize>,
+    /// When this session's batch started its first item.
+    batch_started: Option<Instant>,
+    pub batch_status: Option<String>,
+    /// Folder or file being added to the batch.
+    pub batch_prompt: Option<TextInput>,
+    /// Sweep spec file being typed on the Training screen.
+    pub sweep_prompt: Option<TextInput>,
+    /// Epochs the run is set to take, for the finish estimate: the model
+    /// config's `training.num_epochs`, or entered with `n`.
+    pub planned_epochs: Option<usize>,
+    /// Free space under results_path and dataset sizes, by path.
+    pub storage: StorageCache,
+    storage_check: Option<Task<StorageReport>>,
+    /// SHA-256 of a checkpoint from the Checkpoints screen (`s`), with its
+    /// name.
+    checkpoint_hash: Option<Task<(String, String)>>,
+    pub checkpoint_status: Option<String>,
+    /// `t` was pressed while the storage check ran; training starts once
+    /// it is done.
+    start_after_storage: bool,
+    pub epochs_prompt: Option<TextInput>,
+    /// The sweep shown on the Sweep screen, by name.
+    pub sweep: Option<String>,
+    pub sweep_table: DataTable<SweepResult>,
+    /// Polls the GPUs while training or inference runs.
+    gpu_monitor: Option<GpuMonitor>,
+    pub gpu_reading: GpuReading,
+    /// The GPUs listed on the Devices screen.
+    pub gpu_devices: Vec<GpuDevice>,
+    pub devices_status: Option<String>,
+    /// The `remote_hosts` entry training runs on; here when unset.
+    pub training_host: Option<String>,
+    pub inference_host: Option<String>,
+    pub hosts_status: Option<String>,
+    host_test: Option<Task<String>>,
+    /// The highlighted field of the duplicated run, being edited.
+    pub template_field: Option<TextInput>,
+    /// Where to save the duplicated run's config, being typed.
+    pub template_save: Option<TextInput>,
+    pub template_status: Option<String>,
+    /// `--dashboard`: watches runs without starting, changing or saving
+    /// anything, so it can run next to the instance that owns them.
+    pub read_only: bool,
+    pub dashboard: Option<Dashboard>,
+    pub dashboard_times: TimeDisplay,
+    pub mix: Option<MixPreview>,
+    pub mix_status: Option<String>,
+    /// The mixdown being written.
+    mix_render: Option<Task<PathBuf>>,
+    /// The checkpoint being downloaded, by name, bound once it is in place.
+    download: Option<(String, Task<ModelBinding>)>,
+    /// The resume checkpoint last offered, so it is offered only once.
+    resume_offered: Option<PathBuf>,
+    pub download_status: Option<String>,
+    /// Bake-offs being measured again; yields how many were.
+    revalidation: Option<Task<usize>>,
+    confirm: Option<(String, ConfirmAction)>,
+    /// The command line of a run about to start: Enter runs it, `c`
+    /// copies it, Esc cancels.
+    launch_preview: Option<(String, Launch)>,
+    /// Training held back because other MSST processes are on its GPUs:
+    /// the question, the run, and a free GPU to offer instead.
+    gpu_prompt: Option<(String, TrainingConfig, Option<usize>)>,
+    /// The last environment check; see `preflight`.
+    pub preflight: Option<PreflightReport>,
+    preflight_check: Option<Task<PreflightReport>>,
+    /// The first launch of the session, waiting for the environment check.
+    preflight_launch: Option<(String, Launch)>,
+    pub preflight_status: Option<String>,
+    /// Asked before quitting or leaving a screen while its run goes on.
+    pub leave_prompt: Option<Leaving>,
+    /// Quit once training and inference have ended, as chosen in the
+    /// `leave_prompt`.
+    pub quit_when_stopped: bool,
+    /// How the runs waited for on quitting ended; printed on the way out.
+    pub quit_notes: Vec<String>,
+    /// Lists drawn in the last frame, for mouse clicks.
+    layout: RefCell<LayoutMap>,
+    clicks: ClickTracker,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl App {
@@ -37,285 +621,1731 @@ impl App {
         App {
             screen: Screen::Home,
             selected_index: 0,
-            previous_screen: None,
+            nav_stack: vec![],
             help_visible: false,
             selected_model: None,
             should_quit: false,
+            plain: false,
+            config: AppConfig::default(),
+            scripts: Scripts::default(),
+            project_root: std::env::current_dir().unwrap_or_default(),
+            config_status: None,
+            quarantined: vec![],
+            preview: None,
+            preview_error: None,
+            scratch_status: None,
+            scratch_usage: None,
+            parser_rules: ParserRules::builtin(),
+            parser_error: None,
+            parser_input: TextInput::multi_line(),
+            history: None,
+            history_table: DataTable::new(history_columns()).with_row_style(|entry: &HistoryEntry, styles| {
+                if entry.valid_set_stale {
+                    styles.warning
+                } else {
+                    ratatui::style::Style::default()
+                }
+            }),
+            history_filter: TextInput::single_line(),
+            history_filter_active: false,
+            notes_editor: None,
+            import_root: None,
+            label_editor: None,
+            history_status: None,
+            run_detail: None,
+            pull_prompt: None,
+            pull_source: None,
+            pull: None,
+            pull_progress: Arc::new(Mutex::new(None)),
+            pull_job: None,
+            pull_status: None,
+            compare_first: None,
+            comparison: None,
+            comparison_table: DataTable::new(comparison_columns()).with_row_style(|row: &SdrDelta, styles| match row.delta() {
+                Some(delta) if delta > 0.0 => styles.success,
+                Some(delta) if delta < 0.0 => styles.error,
+                _ => styles.text,
+            }),
+            bake_off: None,
+            bake_off_table: DataTable::new(vec![]),
+            bake_off_sides: (0, 1),
+            tasks: vec![],
+            shutdown: CancelToken::new(),
+            supervisor: JobSupervisor::default(),
+            gated_runs: vec![],
+            attention: Attention::new(),
+            announced_jobs: HashSet::new(),
+            announced_hangs: HashSet::new(),
+            jobs_status: None,
+            training_job: None,
+            inference_job: None,
+            validation_job: None,
+            ensemble_job: None,
+            attached_job: None,
+            job_log_pane: LogPane::new(),
+            stack_dump: None,
+            last_dump: None,
+            inference_form: None,
+            preset_name: None,
+            inference_status: None,
+            input_folder_prompt: None,
+            config_path_prompt: None,
+            store_dir_prompt: None,
+            checkpoints: vec![],
+            file_browser: None,
+            inference: None,
+            inference_rx: None,
+            inference_log: Arc::new(Mutex::new(LogBuffer::new())),
+            inference_step: Arc::new(Mutex::new(None)),
+            inference_log_pane: LogPane::new(),
+            inference_results: vec![],
+            validation_form: None,
+            valid_path_prompt: None,
+            validation: None,
+            validation_rx: None,
+            validation_progress: None,
+            validation_results: vec![],
+            validation_table: DataTable::new(validation_columns()),
+            input_preview: None,
+            input_files_table: DataTable::new(input_file_columns()),
+            output_listing: None,
+            output_status: None,
+            player_request: None,
+            validation_status: None,
+            ensemble_form: EnsembleConfig::default(),
+            ensemble_file_prompt: None,
+            ensemble_weights_prompt: None,
+            ensemble_output_prompt: None,
+            ensemble: None,
+            ensemble_log: Arc::new(Mutex::new(LogBuffer::new())),
+            ensemble_log_pane: LogPane::new(),
+            ensemble_status: None,
+            rename_prompt: None,
+            rename_plan: None,
+            verify_prompt: None,
+            extra_args_prompt: None,
+            verification: None,
+            reconstruction: None,
+            reconstruction_table: DataTable::new(reconstruction_columns()).with_row_style(|track: &TrackReconstruction, styles| {
+                if track.flagged {
+                    styles.error
+                } else {
+                    ratatui::style::Style::default()
+                }
+            }),
+            track_selection: None,
+            exclude_prompt: None,
+            undo: UndoJournal::new(),
+            trash_items: vec![],
+            trash_status: None,
+            recent_status: None,
+            notice: None,
+            warning: None,
+            banner: None,
+            error: None,
+            checkpoint_prompt: None,
+            model_config_prompt: None,
+            model_filter: None,
+            model_status: None,
+            export_prompt: None,
+            python_prompt: None,
+            import_prompt: None,
+            import_preview: None,
+            run_template: None,
+            config_form: None,
+            config_field: None,
+            config_form_status: None,
+            model_config: None,
+            model_config_field: None,
+            model_config_status: None,
+            file_watcher: None,
+            diff_prompt: None,
+            diff_first: None,
+            config_diff: None,
+            training_history: TrainingHistory::new(),
+            training_rx: None,
+            training: None,
+            training_detach: None,
+            training_watchdog: None,
+            training_parse_health: None,
+            monitor_prompt: None,
+            monitor: None,
+            monitor_rx: None,
+            training_log: Arc::new(Mutex::new(LogBuffer::new())),
+            training_step: Arc::new(Mutex::new(None)),
+            training_issues: vec![],
+            training_log_pane: LogPane::new(),
+            training_queue: TrainingQueue::new(),
+            queue_run: None,
+            queue_status: None,
+            inference_batch: InferenceBatch::new(),
+            batch_run: None,
+            batch_started: None,
+            batch_status: None,
+            batch_prompt: None,
+            sweep_prompt: None,
+            planned_epochs: None,
+            storage: StorageCache::default(),
+            storage_check: None,
+            checkpoint_hash: None,
+            checkpoint_status: None,
+            start_after_storage: false,
+            epochs_prompt: None,
+            sweep: None,
+            sweep_table: DataTable::new(sweep_columns()),
+            training_status: None,
+            gpu_monitor: None,
+            gpu_reading: GpuReading::Pending,
+            gpu_devices: vec![],
+            devices_status: None,
+            training_host: None,
+            inference_host: None,
+            hosts_status: None,
+            host_test: None,
+            template_field: None,
+            template_save: None,
+            template_status: None,
+            read_only: false,
+            dashboard: None,
+            dashboard_times: TimeDisplay::default(),
+            mix: None,
+            mix_status: None,
+            mix_render: None,
+            download: None,
+            resume_offered: None,
+            download_status: None,
+            revalidation: None,
+            confirm: None,
+            launch_preview: None,
+            gpu_prompt: None,
+            preflight: None,
+            preflight_check: None,
+            preflight_launch: None,
+            preflight_status: None,
+            leave_prompt: None,
+            quit_when_stopped: false,
+            quit_notes: vec![],
+            layout: RefCell::new(LayoutMap::new()),
+            clicks: ClickTracker::new(),
         }
     }
 
     pub fn run(&mut self) -> io::Result<()> {
+        self.start();
+        if self.plain || self.config.plain {
+            return self.run_plain();
+        }
+
+        // Puts the terminal back however this returns, panics included.
+        let mut guard = TerminalGuard::enter(true)?;
         let backend = CrosstermBackend::new(io::stdout());
         let mut terminal = Terminal::new(backend)?;
-        
-        if let Err(e) = enable_raw_mode() {
-            eprintln!("Failed to enable raw mode: {}", e);
-            return Err(e);
-        }
-        
-        if let Err(e) = execute!(io::stdout(), EnterAlternateScreen) {
-            eprintln!("Failed to enter alternate screen: {}", e);
-            let _ = disable_raw_mode();
-            return Err(e);
-        }
 
         let result = loop {
+            guard.set_mouse(self.config.mouse);
             terminal.draw(|f| {
                 self.draw(f);
             })?;
 
             if event::poll(Duration::from_millis(100))? {
-                if let Event::Key(key) = event::read()? {
-                    if key.kind == KeyEventKind::Press {
-                        if self.help_visible {
-                            self.help_visible = false;
-                        } else {
-                            match key.code {
-                                KeyCode::Char('q') => {
-                                    self.should_quit = true;
-                                }
-                                KeyCode::Char('h') => {
-                                    self.help_visible = true;
-                                }
-                                KeyCode::Enter => {
-                                    self.handle_enter();
-                                }
-                                KeyCode::Up => {
-                                    self.handle_up();
-                                }
-                                KeyCode::Down => {
-                                    self.handle_down();
-                                }
-                                KeyCode::Esc => {
-                                    self.handle_esc();
-                                }
-                                _ => {}
-                            }
-                        }
+                match event::read()? {
+                    // Repainted from scratch at the new size right away,
+                    // rather than on the next pass.
+                    Event::Resize(_, _) => {
+                        terminal.autoresize()?;
+                        terminal.clear()?;
+                        continue;
                     }
+                    event => self.handle_event(event),
                 }
             }
 
+            if let Some(stem) = self.player_request.take() {
+                guard.suspend(|| self.run_player(&stem))?;
+                terminal.clear()?;
+            }
+
+            self.tick();
+
             if self.should_quit {
                 break Ok(());
             }
         };
 
-        let _ = disable_raw_mode();
-        let _ = execute!(io::stdout(), LeaveAlternateScreen);
+        let _ = self.attention.clear(&mut io::stdout());
+        drop(guard);
+        self.quit();
         
         result
     }
 
-    fn draw(&self, f: &mut Frame) {
-        if self.help_visible {
-            self.draw_help(f);
-        } else {
-            match self.screen {
-                Screen::Home => self.draw_home(f),
-                Screen::ModelSelection => self.draw_model_selection(f),
-                Screen::Config => self.draw_config(f),
-                Screen::Training => self.draw_training(f),
-                Screen::Inference => self.draw_inference(f),
-                Screen::Validation => self.draw_validation(f),
+    /// Plain mode: nothing is drawn in place. Whatever changed on the
+    /// screen since the last pass is printed as new lines, which a screen
+    /// reader reads out; keys work as in the normal UI.
+    fn run_plain(&mut self) -> io::Result<()> {
+        let mut guard = TerminalGuard::enter(false)?;
+        let mut announcer = Announcer::new();
+
+        let result = loop {
+            let mut out = io::stdout();
+            for line in announcer.update(plain::render(self)) {
+                // Raw mode: a bare newline doesn't return the cursor.
+                write!(out, "{}\r\n", line)?;
             }
-        }
-    }
+            out.flush()?;
 
-    fn draw_help(&self, f: &mut Frame) {
-        let title = Paragraph::new("Help")
-            .block(Block::default().borders(Borders::ALL))
-            .style(ratatui::style::Style::default().fg(ratatui::style::Color::Cyan));
-
-        let help_text = Paragraph::new(
-            "Keyboard Shortcuts:\n\
-             \n\
-             q - Quit\n\
-             h - Show this help\n\
-             Enter - Select\n\
-             Arrow Up/Down - Navigate\n\
-             Esc - Go back\n\
-             \n\
-             Press any key to dismiss..."
-        )
-        .wrap(Wrap { trim: false });
+            if event::poll(Duration::from_millis(100))? {
+                self.handle_event(event::read()?);
+            }
 
-        let chunks = ratatui::layout::Layout::default()
-            .direction(ratatui::layout::Direction::Vertical)
-            .margin(1)
-            .constraints([
-                ratatui::layout::Constraint::Length(3),
-                ratatui::layout::Constraint::Min(10),
-            ])
-            .split(f.size());
+            if let Some(stem) = self.player_request.take() {
+                guard.suspend(|| self.run_player(&stem))?;
+            }
 
-        f.render_widget(title, chunks[0]);
-        f.render_widget(help_text, chunks[1]);
+            self.tick();
+
+            if self.should_quit {
+                break Ok(());
+            }
+        };
+
+        let _ = self.attention.clear(&mut io::stdout());
+        drop(guard);
+        self.quit();
+        result
     }
 
-    fn draw_home(&self, f: &mut Frame) {
-        let title = Paragraph::new("Music Source Separation TUI")
-            .block(Block::default().borders(Borders::ALL))
-            .style(ratatui::style::Style::default().fg(ratatui::style::Color::Cyan));
-
-        let menu_items = vec![
-            "1. Model Selection",
-            "2. Configuration",
-            "3. Training",
-            "4. Inference",
-            "5. Validation",
-            "q. Quit",
-            "h. Help",
-        ];
+    /// Once per pass of the main loop: drops finished tasks, moves the job
+    /// queue along, and puts what changed in the window title and bell.
+    fn tick(&mut self) {
+        if self.warning.as_ref().is_some_and(|(_, raised)| raised.elapsed() >= WARNING_DURATION) {
+            self.warning = None;
+        }
+        self.poll_file_watcher();
+        let mut events = vec![];
+        for task in self.tasks.iter().filter(|task| task.is_finished()) {
+            events.push(if task.is_cancelled() {
+                AttentionEvent::Progress(format!("{} cancelled", task.name))
+            } else {
+                AttentionEvent::Done(task.name.clone())
+            });
+        }
+        self.tasks.retain(|task| !task.is_finished());
+        self.supervisor.tick();
+        self.start_granted_runs();
+        if let Some(dashboard) = self.dashboard.as_mut() {
+            dashboard.refresh(&self.parser_rules);
+        }
+        if let Some(outcome) = self.download.as_mut().and_then(|(_, task)| task.try_join()) {
+            let name = self.download.take().map(|(name, _)| name).unwrap_or_default();
+            self.download_status = Some(match outcome {
+                Ok(TaskOutcome::Completed(binding)) => {
+                    self.config.bind_model(binding);
+                    self.save_model_choice(&format!("Bind {}", name));
+                    format!("Downloaded and bound {}: {}", name, self.model_status.as_deref().unwrap_or_default())
+                }
+                Ok(TaskOutcome::Cancelled(_)) => format!("Download of {} stopped; Enter resumes it", name),
+                Err(e) => format!("Download of {} failed: {:#}", name, e),
+            });
+        }
+        if let Some(outcome) = self.revalidation.as_mut().and_then(Task::try_join) {
+            self.revalidation = None;
+            // Finished bake-offs are saved one by one, so even a failed or
+            // cancelled batch has something to show.
+            self.reload_history();
+            let mut status = match outcome {
+                Ok(TaskOutcome::Completed(count)) => format!("Re-validated {} bake-off(s) against the current valid set", count),
+                Ok(TaskOutcome::Cancelled(_)) => "Re-validation cancelled".to_string(),
+                Err(e) => format!("Re-validation failed: {:#}", e),
+            };
+            let training = self.stale_entries(RunKind::Training);
+            if training > 0 {
+                status.push_str(&format!("; {} training run(s) need valid.py run on their checkpoints", training));
+            }
+            self.history_status = Some(status);
+        }
+        if let Some(outcome) = self.mix_render.as_mut().and_then(Task::try_join) {
+            self.mix_render = None;
+            self.mix_status = Some(match outcome {
+                Ok(TaskOutcome::Completed(path)) => format!("Wrote {}; the blend is kept in the sidecar", path.display()),
+                Ok(TaskOutcome::Cancelled(_)) => "Mixdown cancelled".to_string(),
+                Err(e) => format!("Mixdown failed: {:#}", e),
+            });
+        }
 
-        let list_items: Vec<ListItem> = menu_items
-            .iter()
-            .enumerate()
-            .map(|(i, item)| {
-                if i == self.selected_index {
-                    ListItem::new(*item)
-                        .style(ratatui::style::Style::default()
-                            .fg(ratatui::style::Color::Yellow)
-                            .add_modifier(ratatui::style::Modifier::BOLD))
-                } else {
-                    ListItem::new(*item)
-                        .style(ratatui::style::Style::default()
-                            .fg(ratatui::style::Color::White))
+        self.update_gpu_monitor();
+        self.receive_training_progress();
+        self.receive_monitor_events();
+        self.receive_inference_output();
+        self.receive_validation_progress();
+        if let Some(outcome) = self.validation.as_mut().and_then(Task::try_join) {
+            self.validation = None;
+            self.validation_rx = None;
+            let exit = match &outcome {
+                Ok(TaskOutcome::Completed(_)) => Ok(Some(0)),
+                Ok(TaskOutcome::Cancelled(_)) => Ok(None),
+                Err(e) => Err(format!("{:#}", e)),
+            };
+            self.validation_status = Some(match outcome {
+                Ok(TaskOutcome::Completed(results)) => {
+                    self.validation_table.set_rows(results.clone());
+                    let summary = validation_summary(&results);
+                    self.validation_results = results;
+                    format!("Validation finished: {}", summary)
                 }
-            })
-            .collect();
+                Ok(TaskOutcome::Cancelled(_)) => "Validation stopped".to_string(),
+                Err(e) => format!("Validation failed: {:#}", e),
+            });
+            let job = self.validation_job.take();
+            if let Some(status) = self.validation_status.clone() {
+                let ended = if status.starts_with("Validation finished") { "finished" } else if status.starts_with("Validation failed") { "failed" } else { "stopped" };
+                if let Err(e) = self.notify_finished("Validation", ended, &status, self.job_duration(job)) {
+                    self.warn(format!("{:#}", e));
+                }
+            }
+            if let (Some(id), Some(status)) = (job, &self.validation_status) {
+                self.supervisor.finish(id, exit, status);
+            }
+        }
+        if let Some(outcome) = self.inference.as_mut().and_then(Task::try_join) {
+            self.inference = None;
+            self.receive_inference_output();
+            self.inference_rx = None;
+            let finished = match &outcome {
+                Ok(TaskOutcome::Completed(result)) => Some(Ok(result.clone())),
+                Ok(TaskOutcome::Cancelled(_)) => None,
+                Err(e) => Some(Err(format!("{:#}", e))),
+            };
+            let exit = match &finished {
+                Some(Ok(result)) if result.success => Ok(Some(0)),
+                Some(Ok(result)) => Err(result.error_message.clone().unwrap_or_else(|| "inference failed".to_string())),
+                Some(Err(e)) => Err(e.clone()),
+                None => Ok(None),
+            };
+            self.inference_status = Some(match outcome {
+                Ok(TaskOutcome::Completed(result)) => {
+                    let mut line = inference_result_line(&result);
+                    if result.success {
+                        line.push_str(" (w lists the stems)");
+                        // E.g. the results manifest couldn't be written.
+                        if let Some(error) = &result.error_message {
+                            self.warn(error.clone());
+                        }
+                    }
+                    self.inference_results.push(result);
+                    line
+                }
+                Ok(TaskOutcome::Cancelled(_)) => "Inference stopped".to_string(),
+                // A batch goes on with its next input; its screen lists the failures.
+                Err(e) if self.batch_run.is_some() => format!("Inference failed: {:#}", e),
+                Err(e) => {
+                    self.report_error("Inference failed", &e);
+                    format!("Inference failed: {:#}", e)
+                }
+            });
+            if let (Some(id), Some(status)) = (self.inference_job.take(), &self.inference_status) {
+                self.supervisor.finish(id, exit, status);
+            }
+            if self.quit_when_stopped {
+                self.quit_notes.extend(self.inference_status.clone());
+            }
+            if let Some(index) = self.batch_run.take() {
+                self.finish_batch_item(index, finished);
+            }
+        }
+        if let Some(outcome) = self.training.as_mut().and_then(Task::try_join) {
+            self.training = None;
+            self.training_detach = None;
+            self.training_watchdog = None;
+            // The last updates may have come in after the ones above.
+            self.receive_training_progress();
+            self.training_rx = None;
+            let (success, stopped) = match &outcome {
+                Ok(TaskOutcome::Completed((ProcessState::Exited(status), _, _))) => (status.success(), false),
+                Ok(TaskOutcome::Cancelled(_)) => (false, true),
+                _ => (false, false),
+            };
+            let resources = match &outcome {
+                Ok(TaskOutcome::Completed((_, _, resources)) | TaskOutcome::Cancelled(Some((_, _, resources)))) => resources.clone(),
+                _ => None,
+            };
+            let exit = match &outcome {
+                Ok(TaskOutcome::Completed((ProcessState::Exited(status), _, _))) => Ok(status.code()),
+                Ok(TaskOutcome::Completed((state, _, _))) => Err(state.describe()),
+                Ok(TaskOutcome::Cancelled(_)) => Ok(None),
+                Err(e) => Err(format!("{:#}", e)),
+            };
+            self.training_status = Some(match outcome {
+                Ok(TaskOutcome::Completed((state, _, _))) => format!("Training {}", state.describe()),
+                Ok(TaskOutcome::Cancelled(Some((_, Some(StopOutcome::Forced), _)))) => {
+                    "Training killed: it didn't exit within the grace period, so the last checkpoint may be missing".to_string()
+                }
+                Ok(TaskOutcome::Cancelled(_)) => "Training stopped".to_string(),
+                Err(e) => {
+                    self.report_error("Training failed", &e);
+                    format!("Training failed: {:#}", e)
+                }
+            });
+            if let (Some(status), Some(resources)) = (self.training_status.as_mut(), &resources) {
+                status.push_str(&format!("; {}", resources.one_line()));
+            }
+            let job = self.training_job.take();
+            if let Some(status) = self.training_status.clone() {
+                let ended = if success { "finished" } else if stopped { "stopped" } else { "failed" };
+                if let Err(e) = self.notify_finished("Training", ended, &status, self.job_duration(job)) {
+                    self.warn(format!("{:#}", e));
+                }
+            }
+            if let (Some(id), Some(status)) = (job, &self.training_status) {
+                self.supervisor.finish(id, exit, status);
+            }
+            if self.quit_when_stopped {
+                self.quit_notes.extend(self.training_status.clone());
+            }
+            self.reload_history();
+            if let Some(index) = self.queue_run.take() {
+                let duration = self.job_duration(job).map_or(0, |duration| duration.as_secs());
+                self.finish_queued_run(index, success, stopped, duration);
+                self.refresh_sweep();
+            }
+        }
+        if let Some(outcome) = self.verification.as_mut().and_then(Task::try_join) {
+            self.verification = None;
+            match outcome {
+                Ok(TaskOutcome::Completed((store_dir, report))) => {
+                    self.inference_status = Some(format!("Stem sums checked: {}", report.summary()));
+                    self.reconstruction_table.set_rows(report.tracks.clone());
+                    self.reconstruction = Some((store_dir, report));
+                    if self.screen == Screen::Inference {
+                        self.push_screen(Screen::Reconstruction);
+                    }
+                }
+                Ok(TaskOutcome::Cancelled(_)) => self.inference_status = Some("Stem check cancelled".to_string()),
+                Err(e) => self.inference_status = Some(format!("{:#}", e)),
+            }
+        }
+        if let (Some(id), Some(progress)) = (self.pull_job, self.pull_progress()) {
+            self.supervisor.set_progress(id, progress.label());
+        }
+        if let Some(outcome) = self.pull.as_mut().and_then(|(_, task)| task.try_join()) {
+            let (destination, _) = self.pull.take().unwrap();
+            let status = match outcome {
+                Ok(TaskOutcome::Completed(bytes)) => format!("Pulled {} into {}", file_size(bytes), destination.display()),
+                Ok(TaskOutcome::Cancelled(_)) => "Pull stopped; pulling again picks up where it left off".to_string(),
+                Err(e) => {
+                    self.report_error("Pull failed", &e);
+                    format!("Pull failed: {:#}", e)
+                }
+            };
+            if let Some(id) = self.pull_job.take() {
+                self.supervisor.finish(id, if status.starts_with("Pull failed") { Err(status.clone()) } else { Ok(Some(0)) }, &status);
+            }
+            self.pull_status = Some(status);
+        }
+        if let Some(outcome) = self.ensemble.as_mut().and_then(Task::try_join) {
+            self.ensemble = None;
+            let (exit, status) = match outcome {
+                Ok(TaskOutcome::Completed(output)) => (Ok(Some(0)), format!("Ensemble written to {}", output)),
+                Ok(TaskOutcome::Cancelled(_)) => (Ok(None), "Ensemble stopped".to_string()),
+                Err(e) => (Err(format!("{:#}", e)), format!("Ensemble failed: {:#}", e)),
+            };
+            if let Some(id) = self.ensemble_job.take() {
+                self.supervisor.finish(id, exit, &status);
+            }
+            self.ensemble_status = Some(status);
+        }
+        if let Some(outcome) = self.host_test.as_mut().and_then(Task::try_join) {
+            self.host_test = None;
+            match outcome {
+                Ok(TaskOutcome::Completed(message)) => self.hosts_status = Some(message),
+                Ok(TaskOutcome::Cancelled(_)) => self.hosts_status = Some("Connection test cancelled".to_string()),
+                Err(e) => {
+                    self.hosts_status = None;
+                    self.report_error("Connection test failed", &e);
+                }
+            }
+        }
+        if let Some(outcome) = self.preflight_check.as_mut().and_then(Task::try_join) {
+            self.preflight_check = None;
+            self.finish_environment_check(outcome);
+        }
+        if let Some(outcome) = self.storage_check.as_mut().and_then(Task::try_join) {
+            self.storage_check = None;
+            let start = std::mem::take(&mut self.start_after_storage);
+            match outcome {
+                Ok(TaskOutcome::Completed(report)) => {
+                    self.storage.insert(report);
+                    if start {
+                        self.start_training();
+                    }
+                }
+                Ok(TaskOutcome::Cancelled(_)) => self.training_status = Some("Disk space check cancelled".to_string()),
+                Err(e) => self.training_status = Some(format!("{:#}", e)),
+            }
+        }
+        if let Some(outcome) = self.checkpoint_hash.as_mut().and_then(Task::try_join) {
+            self.checkpoint_hash = None;
+            self.checkpoint_status = Some(match outcome {
+                Ok(TaskOutcome::Completed((name, hash))) => format!("SHA-256 of {}: {}", name, hash),
+                Ok(TaskOutcome::Cancelled(_)) => "Hashing cancelled".to_string(),
+                Err(e) => format!("{:#}", e),
+            });
+        }
+        if let Some(outcome) = self.stack_dump.as_mut().and_then(Task::try_join) {
+            self.stack_dump = None;
+            match outcome {
+                Ok(TaskOutcome::Completed((name, dump))) => {
+                    self.jobs_status = Some(format!("Stack of '{}' dumped", name));
+                    self.last_dump = Some((name, dump));
+                }
+                Ok(TaskOutcome::Cancelled(_)) => self.jobs_status = Some("Stack dump cancelled".to_string()),
+                Err(e) => self.jobs_status = Some(format!("{:#}", e)),
+            }
+        }
 
-        let menu = List::new(list_items)
-            .block(Block::default().borders(Borders::ALL));
+        if let Some(watchdog) = &self.training_watchdog
+            && watchdog.lock().unwrap().check(Instant::now())
+        {
+            events.push(AttentionEvent::Stalled("Training".to_string()));
+        }
+        if let Some(parse_health) = &self.training_parse_health
+            && (self.training.is_some() || self.monitor.is_some())
+        {
+            parse_health.lock().unwrap().check(chrono::Utc::now());
+        }
+        for job in self.supervisor.jobs() {
+            let (id, name, state) = (job.id(), job.name(), job.state());
+            if self.supervisor.is_hung(id) {
+                if self.announced_hangs.insert(id) {
+                    events.push(AttentionEvent::Stalled(name.to_string()));
+                }
+            } else {
+                self.announced_hangs.remove(&id);
+            }
+            // The app's own runs say when they end themselves.
+            if self.supervisor.runs_in_app(id) {
+                continue;
+            }
+            let event = match state {
+                JobState::Finished { exit_code: Some(0) } => AttentionEvent::Done(name.to_string()),
+                JobState::Finished { .. } | JobState::Failed(_) => AttentionEvent::Failed(name.to_string()),
+                _ => continue,
+            };
+            if self.announced_jobs.insert(id) {
+                events.push(event);
+            }
+        }
+        if events.is_empty()
+            && let Some(task) = self.tasks.last()
+        {
+            events.push(AttentionEvent::Progress(format!("{} {}", task.name, task_progress(task))));
+        }
+        let mut out = io::stdout();
+        for event in events {
+            let _ = self.attention.signal(&mut out, &self.config.attention, &event);
+        }        if self.quit_when_stopped && self.training.is_none() && self.inference.is_none() {
+            self.should_quit = true;
+        }
+    }
 
-        let help_text = Paragraph::new("Use arrow keys to navigate, Enter to select")
-            .wrap(Wrap { trim: false });
+    /// Loads the config and does the startup housekeeping. A read-only
+    /// instance leaves the housekeeping to the owner and opens the dashboard.
+    fn start(&mut self) {
+        if self.read_only {
+            self.load_config();
+            self.use_saved_model();
+            self.load_parser_rules();
+            self.open_dashboard();
+            return;
+        }
+        self.check_state_files();
+        self.use_saved_model();
+        self.supervisor = JobSupervisor::new(DeviceAllocator::new(detect_gpus()));
+        match Scratch::from_config(&self.config, &self.project_root).sweep() {
+            Ok(0) => {}
+            Ok(removed) => self.scratch_status = Some(format!("Removed {} orphaned scratch folder(s) at startup", removed)),
+            Err(e) => self.scratch_status = Some(format!("Scratch sweep failed: {:#}", e)),
+        }
+        match Trash::from_config(&self.config, &self.project_root).purge(chrono::Utc::now()) {
+            Ok(report) if report.removed == 0 => {}
+            Ok(report) => self.trash_status = Some(format!(
+                "Purged {} old trash item(s) at startup, {:.1} MB",
+                report.removed,
+                report.freed_bytes as f64 / (1024.0 * 1024.0)
+            )),
+            Err(e) => self.trash_status = Some(format!("Trash purge failed: {:#}", e)),
+        }
+        self.load_parser_rules();
+        self.start_file_watcher();
+        self.supervisor.set_watchdog(self.config.watchdog, self.parser_rules.clone());
+        match TrainingQueue::load(&self.queue_path()) {
+            Ok(queue) => {
+                if queue.pending() > 0 {
+                    self.queue_status = Some(format!("{} queued training run(s) left from the last session; s resumes the queue", queue.pending()));
+                }
+                self.training_queue = queue;
+            }
+            Err(e) => self.queue_status = Some(format!("{:#}", e)),
+        }
+        match InferenceBatch::load(&self.batch_path()) {
+            Ok(batch) => {
+                if batch.next_pending().is_some() {
+                    self.batch_status = Some(format!("Inference batch left from the last session: {}; s resumes it", batch.summary()));
+                }
+                self.inference_batch = batch;
+            }
+            Err(e) => self.batch_status = Some(format!("{:#}", e)),
+        }
+    }
 
-        let chunks = ratatui::layout::Layout::default()
-            .direction(ratatui::layout::Direction::Vertical)
-            .margin(1)
-            .constraints([
-                ratatui::layout::Constraint::Length(3),
-                ratatui::layout::Constraint::Min(10),
-                ratatui::layout::Constraint::Length(3),
-            ])
-            .split(f.size());
+    fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::Key(key) if key.kind == KeyEventKind::Press => self.handle_key(key),
+            Event::Mouse(mouse) if self.config.mouse => self.handle_mouse(mouse),
+            Event::Paste(text) if self.error.is_none() => {
+                if let Some(input) = self.text_input() {
+                    input.insert_str(&text);
+                }
+            }
+            _ => {}
+        }
+    }
 
-        f.render_widget(title, chunks[0]);
-        f.render_widget(menu, chunks[1]);
-        f.render_widget(help_text, chunks[2]);
+    /// The text input that has focus, if any.
+    pub fn text_input(&mut self) -> Option<&mut TextInput> {
+        match self.screen {
+            Screen::ParserTest => Some(&mut self.parser_input),
+            Screen::ModelSelection if self.checkpoint_prompt.is_some() => self.checkpoint_prompt.as_mut(),
+            Screen::ModelSelection if self.model_config_prompt.is_some() => self.model_config_prompt.as_mut(),
+            Screen::ModelSelection => self.model_filter.as_mut(),
+            Screen::Settings => self.export_prompt.as_mut().or(self.import_prompt.as_mut()).or(self.python_prompt.as_mut()),
+            Screen::DuplicateRun => self.template_field.as_mut().or(self.template_save.as_mut()),
+            Screen::Config => self.config_field.as_mut().or(self.diff_prompt.as_mut()),
+            Screen::ModelConfig => self.model_config_field.as_mut(),
+            Screen::Training => self.sweep_prompt.as_mut().or(self.epochs_prompt.as_mut()).or(self.monitor_prompt.as_mut()),
+            Screen::InferenceBatch => self.batch_prompt.as_mut(),
+            Screen::Ensemble => self.ensemble_file_prompt.as_mut()
+                .or(self.ensemble_weights_prompt.as_mut())
+                .or(self.ensemble_output_prompt.as_mut()),
+            Screen::Inference if self.input_folder_prompt.is_some() => self.input_folder_prompt.as_mut(),
+            Screen::Inference | Screen::Validation if self.config_path_prompt.is_some() => self.config_path_prompt.as_mut(),
+            Screen::Validation => self.valid_path_prompt.as_mut(),
+            Screen::Inference if self.store_dir_prompt.is_some() => self.store_dir_prompt.as_mut(),
+            Screen::Inference if self.rename_prompt.is_some() => self.rename_prompt.as_mut(),
+            Screen::Inference if self.verify_prompt.is_some() => self.verify_prompt.as_mut(),
+            Screen::Inference if self.extra_args_prompt.is_some() => self.extra_args_prompt.as_mut(),
+            Screen::Inference | Screen::RunDetail if self.pull_prompt.is_some() => self.pull_prompt.as_mut(),
+            Screen::Inference => self.preset_name.as_mut(),
+            Screen::Tracks => self.exclude_prompt.as_mut(),
+            Screen::History if self.notes_editor.is_some() => self.notes_editor.as_mut(),
+            Screen::History if self.import_root.is_some() => self.import_root.as_mut(),
+            Screen::History if self.label_editor.is_some() => self.label_editor.as_mut(),
+            Screen::History if self.history_filter_active => Some(&mut self.history_filter),
+            _ => None,
+        }
     }
 
-    fn draw_model_selection(&self, f: &mut Frame) {
-        let title = Paragraph::new("Model Selection")
-            .block(Block::default().borders(Borders::ALL))
-            .style(ratatui::style::Style::default().fg(ratatui::style::Color::Cyan));
+    /// What the focused text input is for, with its keys.
+    pub fn prompt_title(&self) -> Option<&'static str> {
+        match self.screen {
+            Screen::ParserTest => Some(self.key_help()),
+            Screen::ModelSelection if self.checkpoint_prompt.is_some() => Some("Checkpoint path (Enter bind, Ctrl+O browse, Esc cancel)"),
+            Screen::ModelSelection if self.model_config_prompt.is_some() => Some("Config path (Enter bind, Ctrl+O browse, Esc cancel)"),
+            Screen::ModelSelection if self.model_filter.is_some() => Some("Search models (Enter select, Esc clear)"),
+            Screen::DuplicateRun if self.template_field.is_some() => Some("New value (Enter set, Esc cancel)"),
+            Screen::Config if self.diff_prompt.is_some() => Some(if self.diff_first.is_none() {
+                "First YAML config to compare (Enter next, Ctrl+O browse, Esc cancel)"
+            } else {
+                "Compare it with (Enter show the differences, Ctrl+O browse, Esc cancel)"
+            }),
+            Screen::Config if self.config_field.is_some() => Some(if self.path_prompt_mode().is_some() {
+                "New value (Enter set, Ctrl+O browse, Esc keep as draft)"
+            } else {
+                "New value (Enter set, Esc keep as draft)"
+            }),
+            Screen::ModelConfig if self.model_config_field.is_some() => Some("New value; lists comma-separated (Enter set, Esc cancel)"),
+            Screen::DuplicateRun if self.template_save.is_some() => Some("Save training config as (Enter save, Esc cancel)"),
+            Screen::Training if self.sweep_prompt.is_some() => Some("Sweep spec, e.g. sweep.yaml with training.lr: [1e-4, 3e-4] (Enter queue the runs, Ctrl+O browse, Esc cancel)"),
+            Screen::Training if self.epochs_prompt.is_some() => Some("Epochs the run takes (Enter set, Esc cancel)"),
+            Screen::Training if self.monitor_prompt.is_some() => Some("Log file or results folder of a run started elsewhere (Enter follow it, Ctrl+O browse, Esc cancel)"),
+            Screen::InferenceBatch if self.batch_prompt.is_some() => Some("Input folder or audio file to add (Enter add, Ctrl+O browse, Esc cancel)"),
+            Screen::Ensemble if self.ensemble_file_prompt.is_some() => Some("Stem file to add (Enter add, Ctrl+O browse, Esc cancel)"),
+            Screen::Ensemble if self.ensemble_weights_prompt.is_some() => Some("Weights, one per file in order, e.g. 1, 1.5 (Enter set, Esc cancel)"),
+            Screen::Ensemble if self.ensemble_output_prompt.is_some() => Some("Combined file to write (Enter set, Esc cancel)"),
+            Screen::Settings if self.export_prompt.is_some() => Some("Export settings bundle to (Enter export, Esc cancel)"),
+            Screen::Settings if self.import_prompt.is_some() => Some("Import settings bundle from (Enter preview, Ctrl+O browse, Esc cancel)"),
+            Screen::Settings if self.python_prompt.is_some() => Some("Python interpreter, empty to look for python3 or python (Enter save, Ctrl+O browse, Esc cancel)"),
+            Screen::Inference if self.input_folder_prompt.is_some() => Some("Input folder (Enter list files, Ctrl+O browse, Esc cancel)"),
+            Screen::Inference | Screen::Validation if self.config_path_prompt.is_some() => Some("Model config path (Enter set, Ctrl+O browse, Esc cancel)"),
+            Screen::Validation if self.valid_path_prompt.is_some() => Some("Validation folder (Enter set, Ctrl+O browse, Esc cancel)"),
+            Screen::Inference if self.store_dir_prompt.is_some() => Some("Output folder (Enter set, Ctrl+O browse, Esc cancel)"),
+            Screen::Inference if self.preset_name.is_some() => Some("Preset name (Enter save, Esc cancel)"),
+            Screen::Inference if self.rename_prompt.is_some() => Some("Rename stems in output folder (Enter preview, Ctrl+O browse, Esc cancel)"),
+            Screen::Inference if self.verify_prompt.is_some() => Some("Verify stem sums in output folder (Enter check, Ctrl+O browse, Esc cancel)"),
+            Screen::Inference if self.extra_args_prompt.is_some() => Some("Extra inference.py arguments, quoted as in a shell (Enter set, Esc cancel)"),
+            Screen::Inference | Screen::RunDetail if self.pull_prompt.is_some() => Some("Pull the results into this folder here (Enter pull, Ctrl+O browse, Esc cancel)"),
+            Screen::Tracks if self.exclude_prompt.is_some() => Some("Exclude files matching, e.g. *_reference.wav (Enter apply, Esc cancel)"),
+            Screen::History if self.notes_editor.is_some() => Some("Notes (Ctrl+S save, Esc cancel)"),
+            Screen::History if self.import_root.is_some() => Some("Import results folder (Enter import, Ctrl+O browse, Esc cancel)"),
+            Screen::History if self.label_editor.is_some() => Some("Label (Enter save, Esc cancel)"),
+            Screen::History if self.history_filter_active => Some("Filter (Enter done, Esc clear)"),
+            _ => None,
+        }
+    }
 
-        let models = ModelType::all_models();
-        let list_items: Vec<ListItem> = models
-            .iter()
-            .enumerate()
-            .map(|(i, m)| {
-                let text = format!("{} - {}", m.name(), m.description());
-                if i == self.selected_index {
-                    ListItem::new(text)
-                        .style(ratatui::style::Style::default()
-                            .fg(ratatui::style::Color::Yellow)
-                            .add_modifier(ratatui::style::Modifier::BOLD))
-                } else {
-                    ListItem::new(text)
-                        .style(ratatui::style::Style::default()
-                            .fg(ratatui::style::Color::White))
-                }
-            })
-            .collect();
+    /// What the focused prompt's file browser picks, for prompts that take
+    /// a path.
+    fn path_prompt_mode(&self) -> Option<PickMode> {
+        let yaml = || PickMode::files(&["yaml", "yml"]);
+        match self.screen {
+            Screen::ModelSelection if self.checkpoint_prompt.is_some() => Some(PickMode::files(&CHECKPOINT_EXTENSIONS)),
+            Screen::ModelSelection if self.model_config_prompt.is_some() => Some(yaml()),
+            Screen::Config if self.diff_prompt.is_some() => Some(yaml()),
+            Screen::Config if self.config_field.is_some() => match FORM_FIELDS.get(self.selected_index) {
+                Some(&"config_path") => Some(yaml()),
+                Some(&"results_path" | &"data_paths" | &"valid_path") => Some(PickMode::Directory),
+                _ => None,
+            },
+            Screen::Training if self.sweep_prompt.is_some() => Some(yaml()),
+            Screen::Training if self.monitor_prompt.is_some() => Some(PickMode::files(&["log", "out", "txt"])),
+            Screen::InferenceBatch if self.batch_prompt.is_some() => Some(PickMode::Directory),
+            Screen::Ensemble if self.ensemble_file_prompt.is_some() => Some(PickMode::files(&["wav", "flac"])),
+            Screen::Settings if self.import_prompt.is_some() => Some(yaml()),
+            Screen::Settings if self.python_prompt.is_some() => Some(PickMode::files(&[])),
+            Screen::Inference if self.input_folder_prompt.is_some() => Some(PickMode::Directory),
+            Screen::Inference | Screen::Validation if self.config_path_prompt.is_some() => Some(yaml()),
+            Screen::Validation if self.valid_path_prompt.is_some() => Some(PickMode::Directory),
+            Screen::Inference if self.store_dir_prompt.is_some() || self.rename_prompt.is_some() || self.verify_prompt.is_some() => {
+                Some(PickMode::Directory)
+            }
+            Screen::History if self.import_root.is_some() => Some(PickMode::Directory),
+            Screen::Inference | Screen::RunDetail if self.pull_prompt.is_some() => Some(PickMode::Directory),
+            _ => None,
+        }
+    }
 
-        let list = List::new(list_items)
-            .block(Block::default().borders(Borders::ALL));
+    /// The Config screen's data_paths field, which takes a comma-separated
+    /// list: picked folders are added to it.
+    fn prompt_takes_a_list(&self) -> bool {
+        self.screen == Screen::Config && self.config_field.is_some() && FORM_FIELDS.get(self.selected_index) == Some(&"data_paths")
+    }
 
-        let chunks = ratatui::layout::Layout::default()
-            .direction(ratatui::layout::Direction::Vertical)
-            .margin(1)
-            .constraints([
-                ratatui::layout::Constraint::Length(3),
-                ratatui::layout::Constraint::Min(10),
-            ])
-            .split(f.size());
+    /// Opens the file browser at the focused prompt's path.
+    fn browse_for_prompt(&mut self) {
+        let Some(mode) = self.path_prompt_mode() else {
+            return;
+        };
+        let text = self.focused_input().map(|input| input.text().to_string()).unwrap_or_default();
+        let start = if self.prompt_takes_a_list() { text.rsplit(',').next().unwrap_or_default() } else { &text };
+        self.file_browser = Some(FileBrowser::open(&self.project_path(start.trim()), mode));
+    }
 
-        f.render_widget(title, chunks[0]);
-        f.render_widget(list, chunks[1]);
+    fn use_picked_path(&mut self, path: &Path) {
+        let path = path.to_string_lossy();
+        let list = self.prompt_takes_a_list();
+        if let Some(input) = self.text_input() {
+            let kept = input.text().trim().trim_end_matches(',').to_string();
+            if list && !kept.is_empty() {
+                input.set_text(&format!("{}, {}", kept, path));
+            } else {
+                input.set_text(&path);
+            }
+        }
+        if self.screen == Screen::Inference && self.input_folder_prompt.is_some() {
+            self.list_input_files();
+        }
     }
 
-    fn draw_config(&self, f: &mut Frame) {
-        let title = Paragraph::new("Configuration")
-            .block(Block::default().borders(Borders::ALL))
-            .style(ratatui::style::Style::default().fg(ratatui::style::Color::Cyan));
+    /// The question waiting for y/n, if any.
+    pub fn pending_confirmation(&self) -> Option<&str> {
+        self.confirm.as_ref().map(|(message, _)| message.as_str())
+    }
 
-        let text = Paragraph::new("Configuration management - Coming soon!")
-            .wrap(Wrap { trim: false });
+    /// The command waiting for Run / Copy / Cancel, if any.
+    pub fn pending_launch(&self) -> Option<&str> {
+        self.launch_preview.as_ref().map(|(command, _)| command.as_str())
+    }
 
-        let chunks = ratatui::layout::Layout::default()
-            .direction(ratatui::layout::Direction::Vertical)
-            .margin(1)
-            .constraints([
-                ratatui::layout::Constraint::Length(3),
-                ratatui::layout::Constraint::Min(10),
-            ])
-            .split(f.size());
+    /// Read-only twin of `text_input`.
+    pub fn focused_input(&self) -> Option<&TextInput> {
+        match self.screen {
+            Screen::ParserTest => Some(&self.parser_input),
+            Screen::ModelSelection => self.checkpoint_prompt.as_ref()
+                .or(self.model_config_prompt.as_ref())
+                .or(self.model_filter.as_ref()),
+            Screen::Settings => self.export_prompt.as_ref().or(self.import_prompt.as_ref()).or(self.python_prompt.as_ref()),
+            Screen::DuplicateRun => self.template_field.as_ref().or(self.template_save.as_ref()),
+            Screen::Config => self.config_field.as_ref().or(self.diff_prompt.as_ref()),
+            Screen::ModelConfig => self.model_config_field.as_ref(),
+            Screen::Training => self.sweep_prompt.as_ref().or(self.epochs_prompt.as_ref()).or(self.monitor_prompt.as_ref()),
+            Screen::InferenceBatch => self.batch_prompt.as_ref(),
+            Screen::Ensemble => self.ensemble_file_prompt.as_ref()
+                .or(self.ensemble_weights_prompt.as_ref())
+                .or(self.ensemble_output_prompt.as_ref()),
+            Screen::Inference => self.input_folder_prompt.as_ref()
+                .or(self.config_path_prompt.as_ref())
+                .or(self.store_dir_prompt.as_ref())
+                .or(self.rename_prompt.as_ref())
+                .or(self.verify_prompt.as_ref())
+                .or(self.extra_args_prompt.as_ref())
+                .or(self.pull_prompt.as_ref())
+                .or(self.preset_name.as_ref()),
+            Screen::RunDetail => self.pull_prompt.as_ref(),
+            Screen::Tracks => self.exclude_prompt.as_ref(),
+            Screen::Validation => self.config_path_prompt.as_ref().or(self.valid_path_prompt.as_ref()),
+            Screen::History if self.notes_editor.is_some() => self.notes_editor.as_ref(),
+            Screen::History if self.import_root.is_some() => self.import_root.as_ref(),
+            Screen::History if self.label_editor.is_some() => self.label_editor.as_ref(),
+            Screen::History if self.history_filter_active => Some(&self.history_filter),
+            _ => None,
+        }
+    }
 
-        f.render_widget(title, chunks[0]);
-        f.render_widget(text, chunks[1]);
+    /// Shows a background task's progress in the status row until it ends.
+    pub fn watch_task(&mut self, task: TaskMonitor) {
+        self.tasks.push(task);
     }
 
-    fn draw_training(&self, f: &mut Frame) {
-        let title = Paragraph::new("Training")
-            .block(Block::default().borders(Borders::ALL))
-            .style(ratatui::style::Style::default().fg(ratatui::style::Color::Cyan));
+    /// Starts background work that is cancelled at quit, and watches it.
+    pub fn spawn_task<T: Send + 'static>(
+        &mut self,
+        name: &str,
+        on_cancel: OnCancel,
+        work: impl FnOnce(&TaskContext) -> anyhow::Result<T> + Send + 'static,
+    ) -> Task<T> {
+        let task = Task::spawn_under(&self.shutdown, name, on_cancel, work);
+        self.watch_task(task.monitor());
+        task
+    }
 
-        let text = Paragraph::new("Training interface - Coming soon!")
-            .wrap(Wrap { trim: false });
+    /// Stops everything still going, each part within its share of
+    /// `budget`: cancels background tasks, stops the preview and the
+    /// dashboard, gives jobs SIGTERM and then SIGKILL once their time is up.
+    /// Tasks that don't stop in time are left to end with the process.
+    pub fn shut_down(&mut self, budget: &ShutdownBudget) -> ShutdownReport {
+        self.shutdown.cancel();
+        for task in &self.tasks {
+            task.cancel();
+        }
+        self.preview = None;
+        self.mix = None;
+        self.dashboard = None;
 
-        let chunks = ratatui::layout::Layout::default()
-            .direction(ratatui::layout::Direction::Vertical)
-            .margin(1)
-            .constraints([
-                ratatui::layout::Constraint::Length(3),
-                ratatui::layout::Constraint::Min(10),
-            ])
-            .split(f.size());
+        let mut report = ShutdownReport::default();
+        self.supervisor.stop_all();
+        report.step("Jobs", budget.jobs, &mut self.supervisor, |jobs| {
+            jobs.tick();
+            jobs.is_idle()
+        }, JobSupervisor::kill_all);
+        report.step("Background tasks", budget.tasks, &mut self.tasks, |tasks| tasks.iter().all(TaskMonitor::is_finished), |tasks| {
+            tasks.iter().filter(|task| !task.is_finished()).map(|task| task.name.clone()).collect()
+        });
+        report
+    }
 
-        f.render_widget(title, chunks[0]);
-        f.render_widget(text, chunks[1]);
+    /// Shuts down once the terminal is back to normal, so what had to be
+    /// forced is the last thing printed.
+    fn quit(&mut self) {
+        if !self.read_only
+            && let Err(e) = self.config_manager().save_if_changed(&self.config)
+        {
+            eprintln!("Config not saved: {:#}", e);
+        }
+        if !self.supervisor.is_idle() {
+            eprintln!("Stopping running jobs...");
+        }
+        for line in self.shut_down(&ShutdownBudget::default()).forced_lines() {
+            eprintln!("Shutdown: {}", line);
+        }
+        for note in &self.quit_notes {
+            eprintln!("{}", note);
+        }
     }
 
-    fn draw_inference(&self, f: &mut Frame) {
-        let title = Paragraph::new("Inference")
-            .block(Block::default().borders(Borders::ALL))
-            .style(ratatui::style::Style::default().fg(ratatui::style::Color::Cyan));
+    fn training_running(&self) -> bool {
+        self.training.as_ref().is_some_and(|task| !task.is_finished()) && !self.training_stopping()
+    }
 
-        let text = Paragraph::new("Inference interface - Coming soon!")
-            .wrap(Wrap { trim: false });
+    fn inference_running(&self) -> bool {
+        self.inference.as_ref().is_some_and(|task| !task.is_finished())
+    }
 
-        let chunks = ratatui::layout::Layout::default()
-            .direction(ratatui::layout::Direction::Vertical)
-            .margin(1)
-            .constraints([
-                ratatui::layout::Constraint::Length(3),
-                ratatui::layout::Constraint::Min(10),
-            ])
-            .split(f.size());
+    /// Shows a failed action in the error modal, with the context chain of
+    /// `error` as the detail.
+    pub fn report_error(&mut self, title: &str, error: &anyhow::Error) {
+        self.error = Some(ErrorState::new(title, error));
+    }
 
-        f.render_widget(title, chunks[0]);
-        f.render_widget(text, chunks[1]);
+    /// A failure that needn't stop anything, shown on the bottom row for a
+    /// few seconds.
+    pub fn warn(&mut self, message: String) {
+        self.warning = Some((message, Instant::now()));
     }
 
-    fn draw_validation(&self, f: &mut Frame) {
-        let title = Paragraph::new("Validation")
-            .block(Block::default().borders(Borders::ALL))
-            .style(ratatui::style::Style::default().fg(ratatui::style::Color::Cyan));
+    /// Tells the user a long job has ended, however they are looking: the
+    /// bell, the window title if that is on, the banner, and
+    /// `notify_command` if one is set, whose arguments are returned once it
+    /// is started. `status` is "finished", "failed" or "stopped".
+    fn notify_finished(&mut self, job: &str, status: &str, summary: &str, duration: Option<Duration>) -> anyhow::Result<Option<String>> {
+        let notification = Notification {
+            status: status.to_string(),
+            job: job.to_string(),
+            model: self.selected_model.as_ref().or(self.config.selected_model.as_ref()).map(|model| model.name().to_string()).unwrap_or_default(),
+            summary: summary.to_string(),
+            duration,
+        };
+        let event = if notification.failed() { AttentionEvent::Failed(job.to_string()) } else { AttentionEvent::Done(job.to_string()) };
+        // Rings whatever the attention settings say; these jobs take long enough.
+        let settings = AttentionSettings { bell: true, ..self.config.attention.clone() };
+        let _ = self.attention.signal(&mut io::stdout(), &settings, &event);
+        let sent = match &self.config.notify_command {
+            Some(template) => notify::send(template, &notification).map(Some),
+            None => Ok(None),
+        };
+        self.banner = Some(notification);
+        sent
+    }
 
-        let text = Paragraph::new("Validation interface - Coming soon!")
-            .wrap(Wrap { trim: false });
+    /// How long the job `id` has been running.
+    fn job_duration(&self, id: Option<JobId>) -> Option<Duration> {
+        let started_at = self.supervisor.get(id?)?.started_at()?;
+        (chrono::Local::now() - started_at).to_std().ok()
+    }
+
+    /// Lists a run that needs no GPU, e.g. a pull, as a job from now on.
+    fn begin_job(&mut self, kind: JobKind, label: &str, monitor: TaskMonitor, log: Arc<Mutex<LogBuffer>>) -> JobId {
+        let spec = JobSpec {
+            kind,
+            name: format!("{} {}", kind.name(), label),
+            command: None,
+            requirement: DeviceRequirement::None,
+            log_path: None,
+            results_dir: None,
+        };
+        self.supervisor.begin(spec, monitor, log)
+    }
+
+    /// Sends a made-up finished run through `notify_finished`, so the
+    /// command can be tried without waiting for one.
+    fn test_notification(&mut self) {
+        let sent = self.notify_finished("Test", "finished", "This is what a finished run looks like", Some(Duration::from_secs(5400)));
+        self.config_status = Some(match sent {
+            Ok(Some(command)) => format!("Test notification sent: {}", command),
+            Ok(None) => format!("Test notification: no notify_command in {}, so only the bell and banner", DEFAULT_CONFIG_FILE),
+            Err(e) => format!("Test notification failed: {:#}", e),
+        });
+    }
+
+    /// Quits, or first asks what to do with a run that is still going.
+    /// Asked again while waiting for runs to stop, it quits right away.
+    fn request_quit(&mut self) {
+        if !self.quit_when_stopped && (self.training_running() || self.inference_running()) {
+            self.leave_prompt = Some(Leaving::Quit);
+        } else {
+            self.should_quit = true;
+        }
+    }
+
+    /// The question while `leave_prompt` is open, and its keys.
+    pub fn leave_question(&self) -> Option<(String, &'static str)> {
+        let leaving = self.leave_prompt?;
+        let training = match leaving {
+            Leaving::Quit => self.training_running(),
+            Leaving::Screen => self.screen == Screen::Training,
+        };
+        let mut question = if training { "Training is still running." } else { "Inference is still running." }.to_string();
+        if leaving == Leaving::Quit && self.inference_running() {
+            question.push_str(if training {
+                " Inference is too; it can't go on without the TUI, so it is stopped either way."
+            } else {
+                " It can't go on without the TUI."
+            });
+        }
+        let keys = match leaving {
+            Leaving::Quit if training => "k: keep training running in the background and quit    s: stop gracefully and quit    Esc: cancel",
+            Leaving::Quit => "s: stop and quit    Esc: cancel",
+            Leaving::Screen => "k: keep it running in the background    s: stop it gracefully    Esc: cancel",
+        };
+        Some((question, keys))
+    }
+
+    /// The question while `gpu_prompt` is open, and its keys.
+    pub fn gpu_question(&self) -> Option<(String, String)> {
+        let (question, _, alternative) = self.gpu_prompt.as_ref()?;
+        let keys = match alternative {
+            Some(device) => format!("s: start anyway    d: use GPU {} instead    Esc: cancel", device),
+            None => "s: start anyway    Esc: cancel (no other GPU is free; change device_ids on the Config screen)".to_string(),
+        };
+        Some((question.clone(), keys))
+    }
+
+    /// Leaves the run going: behind another screen, or on quitting, on its
+    /// own with its output still logged. Inference can't outlive the TUI,
+    /// so quitting stops it.
+    fn keep_running(&mut self, leaving: Leaving) {
+        match leaving {
+            Leaving::Screen => self.pop_screen(),
+            Leaving::Quit => {
+                if let Some(detach) = &self.training_detach {
+                    detach.cancel();
+                }
+                if let Some(task) = &self.inference {
+                    task.cancel();
+                }
+                self.quit_when_stopped = true;
+            }
+        }
+    }
+
+    /// Stops the run as Ctrl+X does (an interrupt first, for training),
+    /// then leaves; on quitting, only once it has ended.
+    fn stop_running(&mut self, leaving: Leaving) {
+        let (training, inference) = match leaving {
+            Leaving::Quit => (true, true),
+            Leaving::Screen => (self.screen == Screen::Training, self.screen == Screen::Inference),
+        };
+        if let Some(task) = self.training.as_ref().filter(|_| training) {
+            task.cancel();
+        }
+        if let Some(task) = self.inference.as_ref().filter(|_| inference) {
+            task.cancel();
+        }
+        match leaving {
+            Leaving::Quit => self.quit_when_stopped = true,
+            Leaving::Screen => self.pop_screen(),
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        if self.help_visible {
+            self.help_visible = false;
+            return;
+        }
+        self.notice = None;
+        self.banner = None;
+        // Trapped so that a stray Enter can't run the failed action again.
+        if let Some(error) = &self.error {
+            match key.code {
+                KeyCode::Enter | KeyCode::Esc if error.dismissable => self.error = None,
+                KeyCode::Char('q') if !error.dismissable => self.should_quit = true,
+                _ => {}
+            }
+            return;
+        }
+        // Works while typing too, where `h` is text.
+        if key.code == KeyCode::F(1) {
+            self.help_visible = true;
+            return;
+        }
+        if let Some(leaving) = self.leave_prompt.take() {
+            match key.code {
+                KeyCode::Char('k') if leaving == Leaving::Screen || self.training_running() => self.keep_running(leaving),
+                KeyCode::Char('s') => self.stop_running(leaving),
+                KeyCode::Esc | KeyCode::Char('c') => {}
+                _ => self.leave_prompt = Some(leaving),
+            }
+            return;
+        }
+        if let Some((_, action)) = self.confirm.take() {
+            if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                self.confirmed(action);
+            }
+            return;
+        }
+        if let Some((question, mut config, alternative)) = self.gpu_prompt.take() {
+            match (key.code, alternative) {
+                (KeyCode::Char('s'), _) => self.submit_training(config),
+                (KeyCode::Char('d'), Some(device)) => {
+                    config.device_ids = Some(vec![device]);
+                    self.submit_training(config);
+                }
+                (KeyCode::Esc | KeyCode::Char('c'), _) => self.training_status = Some("Training not started".to_string()),
+                _ => self.gpu_prompt = Some((question, config, alternative)),
+            }
+            return;
+        }
+        if let Some((command, launch)) = self.launch_preview.take() {
+            match key.code {
+                KeyCode::Enter | KeyCode::Char('r') => self.launch(&command, launch),
+                KeyCode::Char('c') => {
+                    self.notice = Some(match clipboard::copy(&mut io::stdout(), &command) {
+                        Ok(()) => "Command copied to the clipboard".to_string(),
+                        Err(e) => format!("Copy failed: {}", e),
+                    });
+                    self.launch_preview = Some((command, launch));
+                }
+                KeyCode::Esc | KeyCode::Char('n') => {}
+                _ => self.launch_preview = Some((command, launch)),
+            }
+            return;
+        }
+        if let Some(browser) = self.file_browser.as_mut() {
+            match browser.handle_key(key) {
+                BrowserAction::Picked(path) => {
+                    self.file_browser = None;
+                    self.use_picked_path(&path);
+                }
+                BrowserAction::Cancelled => self.file_browser = None,
+                BrowserAction::Stay => {}
+            }
+            return;
+        }
+
+        if key.code == KeyCode::Char('x') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            if let Some(task) = self.tasks.iter().rev().find(|task| !task.is_cancelled()) {
+                task.cancel();
+            }
+            return;
+        }
+
+        // Text entry takes every key it can use.
+        if self.text_input().is_some() {
+            self.handle_text_key(key);
+            return;
+        }
+
+        // Everything below sees the configured keys as the default ones.
+        let action = self.config.keybindings.action(key);
+        let code = self.config.keybindings.translate(key);
+        if self.screen == Screen::History && code == KeyCode::Enter {
+            self.open_run_detail();
+            return;
+        }
+        if self.screen == Screen::History && self.history_table.handle_key(code) {
+            return;
+        }
+        if self.screen == Screen::Compare && self.comparison_table.handle_key(code) {
+            return;
+        }
+        if self.screen == Screen::BakeOff && self.bake_off_table.handle_key(code) {
+            return;
+        }
+        if self.screen == Screen::Reconstruction && self.reconstruction_table.handle_key(code) {
+            return;
+        }
+        if self.screen == Screen::Validation && self.validation_table.handle_key(code) {
+            return;
+        }
+        if self.screen == Screen::InputFiles && self.input_files_table.handle_key(code) {
+            return;
+        }
+        if self.screen == Screen::Sweep && self.sweep_table.handle_key(code) {
+            return;
+        }
+        if self.handle_log_key(code) {
+            return;
+        }
+        if self.read_only && !self.allowed_read_only(code) {
+            self.notice = Some("Read-only dashboard: key disabled".to_string());
+            return;
+        }
+        if self.screen == Screen::TrainingQueue && key.modifiers.contains(KeyModifiers::SHIFT) && matches!(key.code, KeyCode::Up | KeyCode::Down) {
+            self.move_queued(key.code == KeyCode::Up);
+            return;
+        }
+
+        match action {
+            Some(Action::Quit) => {
+                self.request_quit();
+            }
+            Some(Action::Help) => {
+                self.help_visible = true;
+            }
+            Some(Action::Select) => {
+                self.handle_enter();
+            }
+            Some(Action::Up) => {
+                self.handle_up();
+            }
+            Some(Action::Down) => {
+                self.handle_down();
+            }
+            Some(Action::Back) => {
+                self.handle_esc();
+            }
+            _ if code == KeyCode::Char('u') => {
+                self.undo_last();
+            }
+            _ => {
+                self.handle_screen_key(code);
+            }
+        }
+    }
+
+    /// What the help overlay lists: the keys of the screen, or of the file
+    /// browser over it, from the same line its footer shows, then the keys
+    /// every screen shares, as configured.
+    pub fn help_topic(&self) -> HelpTopic {
+        let topic = match &self.file_browser {
+            Some(browser) => HelpTopic::new("File browser").section("In the browser", help::parse_keys(browser.key_help())),
+            None => HelpTopic::new(&plain::screen_name(self)).section("On this screen", self.screen_help_rows()),
+        };
+        let mut shared = self.config.keybindings.help_rows();
+        shared.push(("F1".to_string(), "Show this help, also while typing".to_string()));
+        topic.section("Everywhere", shared)
+    }
+
+    /// The screen's keys, each one a binding takes first marked as such.
+    fn screen_help_rows(&self) -> Vec<(String, String)> {
+        help::parse_keys(self.key_help())
+            .into_iter()
+            .map(|(keys, description)| {
+                let taken: Vec<String> = self.config.keybindings.shadowed(&format!("{}: {}", keys, description))
+                    .iter()
+                    .map(|(spec, action)| format!("{} is {}", spec, action.name()))
+                    .collect();
+                if taken.is_empty() {
+                    (keys, description)
+                } else {
+                    (keys, format!("{} (not reachable: {})", description, taken.join(", ")))
+                }
+            })
+            .collect()
+    }
+
+    /// A click selects the list item or table row under it and a
+    /// double-click acts as Enter; the wheel scrolls the log pane under it,
+    /// or else moves the selection. Ignored while a prompt or popup is open.
+    pub fn handle_mouse(&mut self, mouse: MouseEvent) {
+        if self.help_visible || self.error.is_some() || self.leave_prompt.is_some() || self.confirm.is_some() || self.launch_preview.is_some()
+            || self.gpu_prompt.is_some() || self.file_browser.is_some() || self.focused_input().is_some()
+        {
+            return;
+        }
+        let (column, row) = (mouse.column, mouse.row);
+        let double = match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => self.clicks.click(column, row, Instant::now()),
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => false,
+            _ => return,
+        };
+        let enter = !self.read_only || self.allowed_read_only(KeyCode::Enter);
+        let scroll = match mouse.kind {
+            MouseEventKind::ScrollUp => Some(KeyCode::Up),
+            MouseEventKind::ScrollDown => Some(KeyCode::Down),
+            _ => None,
+        };
+        if let Some(code) = scroll
+            && self.log_pane().is_some_and(|pane| pane.contains(column, row))
+        {
+            for _ in 0..MOUSE_SCROLL_LINES {
+                self.scroll_log(code);
+            }
+            return;
+        }
+        let table = match self.screen {
+            Screen::History => {
+                let handled = self.history_table.handle_mouse(mouse.kind, column, row, false);
+                if handled && double && enter {
+                    self.open_run_detail();
+                }
+                handled
+            }
+            Screen::Compare => self.comparison_table.handle_mouse(mouse.kind, column, row, double && enter),
+            Screen::BakeOff => self.bake_off_table.handle_mouse(mouse.kind, column, row, double && enter),
+            Screen::Reconstruction => self.reconstruction_table.handle_mouse(mouse.kind, column, row, double && enter),
+            Screen::Validation => self.validation_table.handle_mouse(mouse.kind, column, row, double && enter),
+            Screen::InputFiles => self.input_files_table.handle_mouse(mouse.kind, column, row, double && enter),
+            Screen::Sweep => self.sweep_table.handle_mouse(mouse.kind, column, row, double && enter),
+            _ => false,
+        };
+        if table {
+            return;
+        }
+        match scroll {
+            Some(KeyCode::Up) => self.handle_up(),
+            Some(_) => self.handle_down(),
+            None => {
+                let Some(index) = self.layout.borrow().item_at(column, row) else {
+                    return;
+                };
+                if index > self.max_index() {
+                    return;
+                }
+                self.selected_index = index;
+                if double && enter {
+                    self.handle_enter();
+                }
+            }
+        }
+    }
+
+    /// Keys that only look around, for a read-only instance.
+    fn allowed_read_only(&self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Char('q') | KeyCode::Char('h') | KeyCode::Up | KeyCode::Down | KeyCode::Esc | KeyCode::PageUp | KeyCode::PageDown => true,
+            KeyCode::Enter | KeyCode::Char('1'..='8') => self.screen == Screen::Home,
+            KeyCode::Char('d') => self.screen == Screen::Home,
+            KeyCode::Char('t') => self.screen == Screen::Dashboard,
+            KeyCode::Char('r') => matches!(self.screen, Screen::Dashboard | Screen::History),
+            KeyCode::Char('/') => self.screen == Screen::History,
+            _ => false,
+        }
+    }
+
+    /// Notes a bordered list of `len` items following `selected_index` from
+    /// `first`, for mouse clicks on this frame.
+    fn record_list(&self, area: ratatui::layout::Rect, first: usize, len: usize) {
+        self.layout.borrow_mut().list(area, first, len);
+    }
+
+    /// What the screens draw with, from the configured theme.
+    pub fn styles(&self) -> Styles {
+        Styles::new(&self.config.theme)
+    }
+
+    fn draw(&self, f: &mut Frame) {
+        let styles = self.styles();
+        self.layout.borrow_mut().clear();
+        if widgets::too_small(f.size()) {
+            widgets::render_too_small(f, f.size(), &styles);
+            return;
+        }
+        let (screen, bar) = status_bar::split(f.size());
+        match self.screen {
+            Screen::Home => self.draw_home(f, screen),
+            Screen::ModelSelection => self.draw_model_selection(f, screen),
+            Screen::Config => self.draw_config(f, screen),
+            Screen::Training => self.draw_training(f, screen),
+            Screen::Inference => self.draw_inference(f, screen),
+            Screen::Validation => self.draw_validation(f, screen),
+            Screen::Preview => self.draw_preview(f, screen),
+            Screen::Settings => self.draw_settings(f, screen),
+            Screen::ParserTest => self.draw_parser_test(f, screen),
+            Screen::History => self.draw_history(f, screen),
+            Screen::RunDetail => self.draw_run_detail(f, screen),
+            Screen::Compare => self.draw_comparison(f, screen),
+            Screen::BakeOff => self.draw_bake_off(f, screen),
+            Screen::Tracks => self.draw_tracks(f, screen),
+            Screen::RenameStems => self.draw_rename_stems(f, screen),
+            Screen::Trash => self.draw_trash(f, screen),
+            Screen::ImportBundle => self.draw_import_bundle(f, screen),
+            Screen::DuplicateRun => self.draw_duplicate_run(f, screen),
+            Screen::Dashboard => self.draw_dashboard(f, screen),
+            Screen::Mix => self.draw_mix(f, screen),
+            Screen::Jobs => self.draw_jobs(f, screen),
+            Screen::Reconstruction => self.draw_reconstruction(f, screen),
+            Screen::Checkpoints => self.draw_checkpoints(f, screen),
+            Screen::Log => self.draw_log(f, screen),
+            Screen::Recent => self.draw_recent(f, screen),
+            Screen::TrainingQueue => self.draw_training_queue(f, screen),
+            Screen::InferenceBatch => self.draw_inference_batch(f, screen),
+            Screen::InputFiles => self.draw_input_files(f, screen),
+            Screen::Outputs => self.draw_outputs(f, screen),
+            Screen::Sweep => self.draw_sweep(f, screen),
+            Screen::ModelConfig => self.draw_model_config(f, screen),
+            Screen::Downloads => self.draw_downloads(f, screen),
+            Screen::Devices => self.draw_devices(f, screen),
+            Screen::Environment => self.draw_environment(f, screen),
+            Screen::Hosts => self.draw_hosts(f, screen),
+            Screen::Ensemble => self.draw_ensemble(f, screen),
+            Screen::ConfigDiff => self.draw_config_diff(f, screen),
+        }
+        if self.read_only {
+            let area = f.size();
+            let badge = " READ-ONLY ";
+            let width = (badge.len() as u16).min(area.width);
+            f.render_widget(
+                Paragraph::new(badge).style(styles.badge()),
+                ratatui::layout::Rect { x: area.x + area.width - width, y: area.y, width, height: 1.min(area.height) },
+            );
+        }
+        if let Some(notification) = &self.banner {
+            let area = f.size();
+            let style = if notification.failed() { styles.badge() } else { styles.success.add_modifier(Modifier::BOLD | Modifier::REVERSED) };
+            f.render_widget(
+                Paragraph::new(format!(" {} ", notification.banner())).style(style),
+                ratatui::layout::Rect { height: 1.min(area.height), ..area },
+            );
+        }
+        if let Some(browser) = &self.file_browser {
+            browser.render(f, f.size(), &styles);
+        }
+        if let Some((message, _)) = &self.confirm {
+            let area = f.size();
+            let popup = ratatui::layout::Rect {
+                x: area.width / 8,
+                y: area.height / 2 - 2.min(area.height / 2),
+                width: area.width * 3 / 4,
+                height: 3.min(area.height),
+            };
+            f.render_widget(ratatui::widgets::Clear, popup);
+            f.render_widget(
+                Paragraph::new(message.clone())
+                    .block(Block::default().borders(Borders::ALL).border_style(styles.border).title("Confirm"))
+                    .style(styles.warning),
+                popup,
+            );
+        }
+        if let Some((command, _)) = &self.launch_preview {
+            let area = f.size();
+            let width = area.width * 7 / 8;
+            let height = (command.len() as u16 / width.saturating_sub(2).max(1) + 5).min(area.height);
+            let popup = ratatui::layout::Rect {
+                x: area.width / 16,
+                y: area.height.saturating_sub(height) / 2,
+                width,
+                height,
+            };
+            let title = if self.config.dry_run { "Command (dry run)" } else { "Command" };
+            f.render_widget(ratatui::widgets::Clear, popup);
+            f.render_widget(
+                Paragraph::new(format!("{}\n\nEnter: run    c: copy    Esc: cancel", command))
+                .wrap(Wrap { trim: false })
+                .block(Block::default().borders(Borders::ALL).border_style(styles.border).title(title)),
+                popup,
+            );
+        }
+        if let Some((question, keys)) = self.leave_question() {
+            let area = f.size();
+            let width = area.width * 3 / 4;
+            let height = 6.min(area.height);
+            let popup = ratatui::layout::Rect {
+                x: (area.width - width) / 2,
+                y: (area.height - height) / 2,
+                width,
+                height,
+            };
+            f.render_widget(ratatui::widgets::Clear, popup);
+            f.render_widget(
+                Paragraph::new(format!("{}\n\n{}", question, keys))
+                    .wrap(Wrap { trim: false })
+                    .block(Block::default().borders(Borders::ALL).border_style(styles.border).title("Still running"))
+                    .style(styles.warning),
+                popup,
+            );
+        }
+        if let Some((question, keys)) = self.gpu_question() {
+            let area = f.size();
+            let width = area.width * 3 / 4;
+            let height = 7.min(area.height);
+            let popup = ratatui::layout::Rect {
+                x: (area.width - width) / 2,
+                y: (area.height - height) / 2,
+                width,
+                height,
+            };
+            f.render_widget(ratatui::widgets::Clear, popup);
+            f.render_widget(
+                Paragraph::new(format!("{}\n\n{}", question, keys))
+                    .wrap(Wrap { trim: false })
+                    .block(Block::default().borders(Borders::ALL).border_style(styles.border).title("GPU in use"))
+                    .style(styles.warning),
+                popup,
+            );
+        }
+        if self.help_visible {
+            self.draw_help(f);
+        }
+        if let Some(error) = &self.error {
+            error.render(f, f.size(), &styles);
+        }
+        self.draw_task_row(f, screen);
+        self.status_info().render(f, bar, &styles);
+    }
+
+    /// What the status bar shows: the model, the runs going on, the theme
+    /// and the keys for where the user is.
+    pub fn status_info(&self) -> StatusInfo {
+        let model = self.selected_model.as_ref().or(self.config.selected_model.as_ref());
+        let jobs = self.supervisor.active()
+            .map(|job| {
+                let state = match job.progress() {
+                    _ if job.is_stopping() => "stopping",
+                    Some(progress) => progress,
+                    None => "running",
+                };
+                format!("{}: {}", job.kind().name().to_lowercase(), state)
+            })
+            .collect();
+        StatusInfo {
+            model: model.map(|model| model.name().to_string()),
+            jobs,
+            theme: format!("{:?}", self.config.theme),
+            hints: self.status_hints(),
+        }
+    }
+
+    fn status_hints(&self) -> String {
+        let key = |action| self.config.keybindings.keys(action).first().map(KeySpec::to_string).unwrap_or_default();
+        if let Some(error) = &self.error {
+            error.hint().to_string()
+        } else if self.help_visible {
+            "any key: close help".to_string()
+        } else if self.focused_input().is_some() {
+            "Enter: confirm  Esc: cancel".to_string()
+        } else if self.screen == Screen::Home {
+            format!("{}: help  {}: quit", key(Action::Help), key(Action::Quit))
+        } else {
+            format!("{}: help  {}: back", key(Action::Help), key(Action::Back))
+        }
+    }
+
+    /// Progress of the newest background task on the bottom line of the
+    /// screen, which the screens leave free as margin.
+    fn draw_task_row(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        if area.height < 2 {
+            return;
+        }
+        let row = ratatui::layout::Rect { x: area.x, y: area.y + area.height - 1, width: area.width, height: 1 };
+        let Some(task) = self.tasks.last() else {
+            if let Some(notice) = &self.notice {
+                f.render_widget(Paragraph::new(notice.clone()), row);
+            } else if let Some((warning, _)) = &self.warning {
+                f.render_widget(Paragraph::new(warning.clone()).style(styles.warning), row);
+            }
+            return;
+        };
+
+        let progress = task.progress();
+        let others = match self.tasks.len() {
+            1 => String::new(),
+            n => format!(" (+{} more)", n - 1),
+        };
+        let state = if task.is_cancelled() { "cancelling" } else { "Ctrl+X cancels" };
+        match progress.fraction() {
+            Some(fraction) => {
+                let gauge = ratatui::widgets::Gauge::default()
+                    .gauge_style(styles.progress)
+                    .ratio(fraction)
+                    .label(format!("{} {:.0}%{}  {}", task.name, fraction * 100.0, others, state));
+                f.render_widget(gauge, row);
+            }
+            None => {
+                let text = format!("{}: {} done{}  {}", task.name, progress.done, others, state);
+                f.render_widget(Paragraph::new(text), row);
+            }
+        }
+    }
+
+    /// The keys of the screen, or of the file browser when it is open, in a
+    /// popup over the dimmed screen.
+    fn draw_help(&self, f: &mut Frame) {
+        self.help_topic().render(f, f.size(), &self.styles());
+    }
+
+    fn draw_home(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new("Music Source Separation TUI")
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let list_items: Vec<ListItem> = HOME_MENU
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                if i == self.selected_index {
+                    ListItem::new(*item)
+                        .style(styles.highlight)
+                } else {
+                    ListItem::new(*item)
+                        .style(styles.text)
+                }
+            })
+            .collect();
+
+        let len = list_items.len();
+        let menu = List::new(list_items)
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border));
+
+        let help_text = Paragraph::new(format!("Model: {}\n{}", self.model_summary(), self.key_help()))
+            .wrap(Wrap { trim: false });
+
+        let warnings: Vec<String> = self.quarantined.iter().map(Quarantined::warning).collect();
+        let running: Vec<String> = self.supervisor.active().map(|job| self.supervisor.line(job.id())).collect();
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(10),
+                ratatui::layout::Constraint::Length(if running.is_empty() { 0 } else { 2 + running.len() as u16 }),
+                ratatui::layout::Constraint::Length(if warnings.is_empty() { 0 } else { 2 + 2 * warnings.len() as u16 }),
+                ratatui::layout::Constraint::Length(3),
+            ])
+            .split(area);
+
+        f.render_widget(title, chunks[0]);
+        f.render_widget(menu, chunks[1]);
+        self.record_list(chunks[1], 0, len);
+        if !running.is_empty() {
+            let panel = Paragraph::new(running.join("\n"))
+                .block(Block::default().borders(Borders::ALL).border_style(styles.border).title("Running Jobs (j to attach or stop)"))
+                .style(styles.text);
+            f.render_widget(panel, chunks[2]);
+        }
+        if !warnings.is_empty() {
+            let warning = Paragraph::new(warnings.join("\n"))
+                .wrap(Wrap { trim: false })
+                .block(Block::default().borders(Borders::ALL).border_style(styles.border).title("Quarantined at startup"))
+                .style(styles.error);
+            f.render_widget(warning, chunks[3]);
+        }
+        f.render_widget(help_text, chunks[4]);
+    }
+
+    /// The keys of the current screen, shown when there is no status.
+    pub fn key_help(&self) -> &'static str {
+        screen_key_help(&self.screen)
+    }
+
+    /// The selected model and its bound variant, with what's wrong with the
+    /// binding if its files moved.
+    pub fn model_summary(&self) -> String {
+        let Some(model_type) = self.selected_model.as_ref().or(self.config.selected_model.as_ref()) else {
+            return "none selected".to_string();
+        };
+        match &self.config.model_binding {
+            Some(binding) if binding.model_type == *model_type => {
+                let problems = binding.problems();
+                if problems.is_empty() {
+                    binding.display()
+                } else {
+                    format!("{} ({})", binding.display(), problems.join(", "))
+                }
+            }
+            _ => model_type.name().to_string(),
+        }
+    }
+
+    fn draw_model_selection(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new("Model Selection")
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let models = self.listed_models();
+        let selected = self.selected_model.as_ref().or(self.config.selected_model.as_ref());
+        let list_items: Vec<ListItem> = models
+            .iter()
+            .enumerate()
+            .map(|(i, m)| {
+                let marker = if selected == Some(m) { "*" } else { " " };
+                let text = format!("{} {} - {}", marker, m.name(), m.description());
+                if i == self.selected_index {
+                    ListItem::new(text)
+                        .style(styles.highlight)
+                } else {
+                    ListItem::new(text)
+                        .style(styles.text)
+                }
+            })
+            .collect();
+        let list_items = if list_items.is_empty() {
+            vec![ListItem::new("  No models match").style(styles.text)]
+        } else {
+            list_items
+        };
+
+        let len = list_items.len();
+        let list = List::new(list_items)
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border));
+
+        let footer = format!(
+            "Selected: {}\n{}",
+            self.model_summary(),
+            self.model_status.as_deref().unwrap_or(self.key_help())
+        );
 
         let chunks = ratatui::layout::Layout::default()
             .direction(ratatui::layout::Direction::Vertical)
@@ -323,27 +2353,6193 @@ impl App {
             .constraints([
                 ratatui::layout::Constraint::Length(3),
                 ratatui::layout::Constraint::Min(10),
+                ratatui::layout::Constraint::Length(4),
+            ])
+            .split(area);
+
+        match &self.model_filter {
+            Some(filter) => filter.render(f, chunks[0], self.prompt_title().unwrap_or_default()),
+            None => f.render_widget(title, chunks[0]),
+        }
+        f.render_widget(list, chunks[1]);
+        self.record_list(chunks[1], 0, len);
+        f.render_widget(
+            Paragraph::new(footer)
+                .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+                .wrap(Wrap { trim: false }),
+            chunks[2],
+        );
+
+        if let Some(input) = self.checkpoint_prompt.as_ref().or(self.model_config_prompt.as_ref()) {
+            let popup = ratatui::layout::Rect {
+                x: area.width / 8,
+                y: area.height / 2 - 2.min(area.height / 2),
+                width: area.width * 3 / 4,
+                height: 3.min(area.height),
+            };
+            f.render_widget(ratatui::widgets::Clear, popup);
+            input.render(f, popup, self.prompt_title().unwrap_or_default());
+        }
+    }
+
+    fn draw_config(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let Some(form) = &self.config_form else {
+            return;
+        };
+        let title = Paragraph::new(format!("Configuration: {}", form.path))
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let items: Vec<ListItem> = form.lines()
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let item = ListItem::new(line);
+                if i == self.selected_index {
+                    item.style(styles.highlight)
+                } else {
+                    item
+                }
+            })
+            .collect();
+        let len = items.len();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border).title(format!("Training config for {}", form.config.model_type.name())));
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Length(4),
             ])
-            .split(f.size());
+            .split(area);
+
+        f.render_widget(title, chunks[0]);
+        f.render_widget(list, chunks[1]);
+        self.record_list(chunks[1], 0, len);
+        match self.focused_input() {
+            Some(input) => input.render(f, chunks[2], self.prompt_title().unwrap_or_default()),
+            None => f.render_widget(
+                Paragraph::new("~ marks drafts left with Esc; s sets them and saves").block(Block::default().borders(Borders::ALL).border_style(styles.border)),
+                chunks[2],
+            ),
+        }
+        let footer = self.config_form_status.as_deref().unwrap_or(self.key_help());
+        f.render_widget(
+            Paragraph::new(footer)
+                .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+                .wrap(Wrap { trim: false }),
+            chunks[3],
+        );
+    }
+
+    /// Opens the saved training config, or a new one for the selected
+    /// model. A form already open keeps its drafts.
+    fn open_config_form(&mut self) {
+        if self.config_form.is_some() {
+            return;
+        }
+        let model_type = self.selected_model.clone()
+            .or_else(|| self.config.selected_model.clone())
+            .unwrap_or(ModelType::MelBandRoformer);
+        match ConfigForm::open(&self.project_path(DEFAULT_TRAINING_CONFIG_FILE).to_string_lossy(), model_type) {
+            Ok(form) => {
+                self.config_form = Some(form);
+                self.config_form_status = None;
+                self.auto_select_config();
+            }
+            Err(e) => {
+                self.config_form = None;
+                self.report_error("Training config not loaded", &e);
+            }
+        }
+    }
+
+    /// Points the training config at the model config found in `configs/`
+    /// for the selected model, unless one was set by hand. The path used,
+    /// if any.
+    fn auto_select_config(&mut self) -> Option<String> {
+        let model_type = self.selected_model.clone().or_else(|| self.config.selected_model.clone())?;
+        let discovered = self.discover_config(&model_type);
+        let form = self.config_form.as_mut()?;
+        match discovered {
+            Ok(Some(path)) if form.auto_select_config(&model_type, &path) => {
+                self.config_form_status = Some(format!("config_path auto-selected for {}; Enter on it overrides", model_type.name()));
+                Some(path)
+            }
+            Ok(_) => None,
+            Err(e) => {
+                self.config_form_status = Some(format!("{:#}", e));
+                None
+            }
+        }
+    }
+
+    /// The model config in the root's `configs/` for `model_type`, as a
+    /// path relative to the root like one typed in.
+    fn discover_config(&self, model_type: &ModelType) -> anyhow::Result<Option<String>> {
+        let found = self.config_manager().discover_config(&self.project_path(DEFAULT_CONFIGS_DIR).to_string_lossy(), model_type)?;
+        Ok(found.map(|path| match Path::new(&path).strip_prefix(&self.project_root) {
+            Ok(relative) => relative.to_string_lossy().into_owned(),
+            Err(_) => path,
+        }))
+    }
+
+    fn set_config_field(&mut self) {
+        let (Some(field), Some(form)) = (self.config_field.take(), self.config_form.as_mut()) else {
+            return;
+        };
+        if let Err(e) = form.set(self.selected_index, field.text()) {
+            form.keep_draft(self.selected_index, field.text());
+            self.config_form_status = Some(format!("{:#}", e));
+        }
+    }
+
+    fn save_config_form(&mut self) {
+        let Some(form) = self.config_form.as_mut() else {
+            return;
+        };
+        let path = form.path.clone();
+        let configs = ConfigManager::new(&self.project_root.join(DEFAULT_CONFIG_FILE).to_string_lossy());
+        let saved = Trash::from_config(&self.config, &self.project_root).keep_copy(&self.project_root.join(&path), "training config overwrite")
+            .and_then(|kept| {
+                form.save(&configs)?;
+                Ok(kept)
+            });
+        self.config_form_status = Some(match saved {
+            Ok(kept) => {
+                if let Some(item) = &kept {
+                    self.undo.record(&format!("Overwrite {}", path), UndoAction::RestoreTrashed { trash_id: item.id.clone() });
+                }
+                format!("Saved {}{}", path, undo_hint(kept.is_some()))
+            }
+            Err(e) => {
+                self.report_error(&format!("{} not saved", path), &e);
+                format!("Save failed: {:#}", e)
+            }
+        });
+    }
+
+    /// Opens the model YAML of the Config screen's training config.
+    fn open_model_config(&mut self) {
+        let Some(form) = &self.config_form else {
+            return;
+        };
+        if form.config.config_path.trim().is_empty() {
+            self.config_form_status = Some("Set config_path first".to_string());
+            return;
+        }
+        match ModelConfigEditor::open(&self.project_path(&form.config.config_path)) {
+            Ok(editor) => {
+                self.model_config = Some(editor);
+                self.model_config_status = None;
+                self.push_screen(Screen::ModelConfig);
+            }
+            Err(e) => self.report_error("Model config not loaded", &e),
+        }
+    }
+
+    /// Asks for the two YAML configs to diff, the first one starting as
+    /// the training config's model config.
+    fn start_config_diff(&mut self) {
+        if self.screen == Screen::ConfigDiff {
+            self.pop_screen();
+        }
+        let first = self.config_form.as_ref().map(|form| form.config.config_path.clone()).unwrap_or_default();
+        self.diff_first = None;
+        self.diff_prompt = Some(TextInput::single_line().with_text(&first));
+    }
+
+    /// Enter in the diff prompt: takes the first config and asks for the
+    /// second, starting in the same folder, or diffs the two.
+    fn next_diff_path(&mut self) {
+        let Some(prompt) = self.diff_prompt.take() else {
+            return;
+        };
+        let path = self.project_path(prompt.text().trim());
+        let Some(first) = self.diff_first.take() else {
+            let folder = Path::new(prompt.text().trim()).parent().map(|folder| folder.to_string_lossy().to_string()).filter(|folder| !folder.is_empty());
+            self.diff_prompt = Some(TextInput::single_line().with_text(&folder.map(|folder| format!("{}/", folder)).unwrap_or_default()));
+            self.diff_first = Some(path);
+            return;
+        };
+        match yaml_diff::diff_files(&first, &path) {
+            Ok(entries) => {
+                self.config_diff = Some((first, path, entries));
+                self.push_screen(Screen::ConfigDiff);
+            }
+            Err(e) => self.report_error("Configs not compared", &e),
+        }
+    }
+
+    /// The differences grouped under a line per top-level section.
+    pub fn config_diff_lines(&self) -> Vec<String> {
+        let Some((_, _, entries)) = &self.config_diff else {
+            return vec![];
+        };
+        if entries.is_empty() {
+            return vec!["The two configs are the same".to_string()];
+        }
+        let mut lines = vec![];
+        for (section, entries) in yaml_diff::by_section(entries) {
+            let count = if entries.len() == 1 { "1 difference".to_string() } else { format!("{} differences", entries.len()) };
+            lines.push(format!("{}: {}", section, count));
+            lines.extend(entries.iter().map(|entry| format!("  {}", entry.line())));
+        }
+        lines
+    }
+
+    fn draw_config_diff(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let Some((first, second, _)) = &self.config_diff else {
+            return;
+        };
+        let title = Paragraph::new(format!("Config diff: {} → {}", first.display(), second.display()))
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+        let items: Vec<ListItem> = self.config_diff_lines()
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let style = match line.trim_start().chars().next() {
+                    _ if i == self.selected_index => styles.highlight,
+                    Some('+') if line.starts_with(' ') => styles.success,
+                    Some('-') if line.starts_with(' ') => styles.error,
+                    Some('~') if line.starts_with(' ') => styles.warning,
+                    _ => styles.title,
+                };
+                ListItem::new(line).style(style)
+            })
+            .collect();
+        let len = items.len();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border).title("+ added    - removed    ~ changed"));
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(3),
+            ])
+            .split(area);
+        f.render_widget(title, chunks[0]);
+        f.render_widget(list, chunks[1]);
+        self.record_list(chunks[1], 0, len);
+        f.render_widget(Paragraph::new(self.key_help()).block(Block::default().borders(Borders::ALL).border_style(styles.border)), chunks[2]);
+    }
+
+    /// Enter on the model config tree: folds a section, flips a boolean,
+    /// or starts editing any other value.
+    fn edit_model_config_row(&mut self) {
+        let Some(editor) = self.model_config.as_mut() else {
+            return;
+        };
+        let Some(row) = editor.rows().into_iter().nth(self.selected_index) else {
+            return;
+        };
+        match row.kind {
+            NodeKind::Section => editor.toggle_folded(&row),
+            NodeKind::Fixed => self.model_config_status = Some(format!("{} can only be changed in the file itself", row.dotted())),
+            NodeKind::Leaf if editor.toggle(&row) => self.model_config_status = None,
+            NodeKind::Leaf => {
+                self.model_config_field = Some(TextInput::single_line().with_text(&editor.edit_text(&row)));
+                self.model_config_status = None;
+            }
+        }
+    }
+
+    fn set_model_config_value(&mut self) {
+        let (Some(field), Some(editor)) = (self.model_config_field.take(), self.model_config.as_mut()) else {
+            return;
+        };
+        let Some(row) = editor.rows().into_iter().nth(self.selected_index) else {
+            return;
+        };
+        if let Err(e) = editor.set(&row, field.text()) {
+            self.model_config_status = Some(format!("{:#}", e));
+            self.model_config_field = Some(field);
+        }
+    }
+
+    fn save_model_config(&mut self) {
+        let Some(editor) = self.model_config.as_mut() else {
+            return;
+        };
+        match editor.save() {
+            Ok(backup) => {
+                self.model_config_status = Some(format!("Saved {}; the previous version is {}", editor.path().display(), backup.display()));
+            }
+            Err(e) => {
+                let title = format!("{} not saved", editor.path().display());
+                self.model_config_status = Some(format!("Save failed: {:#}", e));
+                self.report_error(&title, &e);
+            }
+        }
+    }
+
+    /// The tree lines of the model config, for plain mode too.
+    pub fn model_config_lines(&self) -> Vec<String> {
+        self.model_config.as_ref()
+            .map(|editor| editor.rows().iter().map(|row| editor.line(row)).collect())
+            .unwrap_or_default()
+    }
+
+    fn draw_model_config(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let Some(editor) = &self.model_config else {
+            return;
+        };
+        let changed = if editor.is_changed() { " (unsaved)" } else { "" };
+        let title = Paragraph::new(format!("Model config {}{}", editor.path().display(), changed))
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+        let items: Vec<ListItem> = self.model_config_lines()
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let item = ListItem::new(line);
+                if i == self.selected_index { item.style(styles.highlight) } else { item }
+            })
+            .collect();
+        let len = items.len();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border).title("training, audio and inference sections"));
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(3),
+            ])
+            .split(area);
+        f.render_widget(title, chunks[0]);
+        f.render_widget(list, chunks[1]);
+        self.record_list(chunks[1], 0, len);
+        match self.focused_input() {
+            Some(input) => input.render(f, chunks[2], self.prompt_title().unwrap_or_default()),
+            None => f.render_widget(
+                Paragraph::new(self.model_config_status.as_deref().unwrap_or(self.key_help()))
+                    .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+                    .wrap(Wrap { trim: false }),
+                chunks[2],
+            ),
+        }
+    }
+
+    fn draw_training(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let stopping = self.training_stopping();
+        let mut title = match (&self.training, stopping) {
+            (Some(_), true) => "Training (stopping)",
+            (Some(_), false) => "Training (running)",
+            (None, _) => "Training",
+        }.to_string();
+        if let Some(timing) = self.training_timing() {
+            title.push_str(&format!(" - {}", timing));
+        }
+        let warning = self.training_hang_warning();
+        if let Some(warning) = &warning {
+            title.push_str(&format!(" - {}", warning));
+        }
+        let title = Paragraph::new(title)
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(if warning.is_some() { styles.error } else { styles.title });
+
+        let layout = training_chart::training_layout(area, self.gpu_panel_height(), step_height(&self.training_step), LOG_PANE_HEIGHT);
+        f.render_widget(title, layout.title);
+        self.draw_gpu_panel(f, layout.gpu, &styles);
+        draw_step(f, layout.step, "Epoch", &self.training_step, &styles);
+
+        // Log-forward, as on the Dashboard: the notice takes the place of
+        // the missing progress and the log below it gets the room.
+        // Config issues of a run that didn't start still come first.
+        let notice = self.training_parse_notice().filter(|_| self.training.is_some() || self.training_issues.is_empty());
+        let log_forward = notice.is_some();
+        if let Some(notice) = notice {
+            let chunks = ratatui::layout::Layout::default()
+                .direction(ratatui::layout::Direction::Vertical)
+                .constraints([
+                    ratatui::layout::Constraint::Length(notice.len() as u16 + 2),
+                    ratatui::layout::Constraint::Min(LOG_PANE_HEIGHT),
+                ])
+                .split(layout.body.union(layout.log));
+            f.render_widget(
+                Paragraph::new(notice.join("\n"))
+                    .block(Block::default().borders(Borders::ALL).border_style(styles.border).title("Progress not recognized"))
+                    .style(styles.warning)
+                    .wrap(Wrap { trim: false }),
+                chunks[0],
+            );
+            self.training_log_pane.render(f, chunks[1], "Log", &self.training_log.lock().unwrap(), &styles);
+        } else {
+            self.training_log_pane.render(f, layout.log, "Log", &self.training_log.lock().unwrap(), &styles);
+        }
+        let footer = if stopping {
+            "Stopping (saving checkpoint)…"
+        } else {
+            self.training_status.as_deref().unwrap_or(self.key_help())
+        };
+        match self.focused_input() {
+            Some(input) => input.render(f, layout.footer, self.prompt_title().unwrap_or_default()),
+            None => f.render_widget(
+                Paragraph::new(footer).block(Block::default().borders(Borders::ALL).border_style(styles.border)).wrap(Wrap { trim: false }),
+                layout.footer,
+            ),
+        }
+        if log_forward {
+            return;
+        }
+
+        if self.training.is_none() && !self.training_issues.is_empty() {
+            let lines: Vec<Line> = self.training_issues.iter()
+                .map(|issue| {
+                    let (mark, style) = match issue.severity {
+                        lint::Severity::Error => ("✗ ", styles.error),
+                        lint::Severity::Warning => ("! ", styles.warning),
+                    };
+                    Line::from(vec![
+                        Span::styled(mark, style),
+                        Span::styled(issue.field, styles.highlight),
+                        Span::styled(format!(": {}", issue.message), style),
+                    ])
+                })
+                .collect();
+            f.render_widget(
+                Paragraph::new(lines)
+                    .block(Block::default().borders(Borders::ALL).border_style(styles.error).title("Config issues"))
+                    .wrap(Wrap { trim: false }),
+                layout.body,
+            );
+            return;
+        }
+
+        if self.training_history.is_empty() {
+            let text = if self.training.is_some() {
+                "Waiting for the first epoch..."
+            } else {
+                "No training progress yet. Set the paths on the Config screen, then press t to start."
+            };
+            let mut text = match &self.config_form {
+                Some(form) if form.auto_config && self.training.is_none() => {
+                    format!("{}\nModel config: {} (auto-selected)", text, form.config.config_path)
+                }
+                _ => text.to_string(),
+            };
+            for line in self.storage_lines() {
+                text.push('\n');
+                text.push_str(&line);
+            }
+            f.render_widget(
+                Paragraph::new(text).block(Block::default().borders(Borders::ALL).border_style(styles.border)).wrap(Wrap { trim: false }),
+                layout.body,
+            );
+            return;
+        }
+
+        let rows = training_chart::latest_rows(self.training_history.progress())
+            .into_iter()
+            .map(|(name, value)| {
+                // The checkpoint to keep.
+                let row = Row::new(vec![name.to_string(), value]);
+                if name == "Best SDR" { row.style(styles.highlight) } else { row }
+            });
+        let latest = Table::new(rows, [ratatui::layout::Constraint::Length(11), ratatui::layout::Constraint::Min(8)])
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border).title("Latest"));
+        if !layout.chart {
+            f.render_widget(latest, layout.body);
+            return;
+        }
+
+        let body = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Horizontal)
+            .constraints([
+                ratatui::layout::Constraint::Min(30),
+                ratatui::layout::Constraint::Length(40),
+            ])
+            .split(layout.body);
+        let side = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .constraints([
+                ratatui::layout::Constraint::Length(training_chart::LATEST_HEIGHT),
+                ratatui::layout::Constraint::Min(3),
+            ])
+            .split(body[1]);
+
+        draw_loss_chart(f, body[0], self.training_history.progress(), &styles);
+        f.render_widget(latest, side[0]);
+
+        let rows = training_chart::instrument_rows(self.training_history.progress())
+            .into_iter()
+            .map(|row| Row::new(vec![
+                row.name,
+                row.latest.map_or("-".to_string(), |sdr| format!("{:.2}", sdr)),
+                format!("{:.2}", row.best),
+                row.best_epoch.to_string(),
+            ]));
+        let table = Table::new(rows, [
+            ratatui::layout::Constraint::Min(12),
+            ratatui::layout::Constraint::Length(7),
+            ratatui::layout::Constraint::Length(7),
+            ratatui::layout::Constraint::Length(5),
+        ])
+            .header(Row::new(vec!["Instrument", "SDR", "Best", "Epoch"]).style(styles.title))
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border).title("SDR per instrument"));
+        f.render_widget(table, side[1]);
+    }
+
+    /// Disk space and dataset sizes for the Config screen's config, once
+    /// checked; none while training runs.
+    pub fn storage_lines(&self) -> Vec<String> {
+        let Some(form) = self.config_form.as_ref().filter(|_| self.training.is_none()) else {
+            return vec![];
+        };
+        let mut lines = self.storage.lines(&form.config.in_root(&self.project_root), self.planned_epochs);
+        if self.storage_check.is_some() {
+            lines.push("Checking disk space and the dataset…".to_string());
+        }
+        lines
+    }
+
+    /// Shows the command that would train with the config being edited on
+    /// the Config screen, for `launch` to start.
+    fn start_training(&mut self) {
+        if self.training.is_some() || self.is_waiting(JobKind::Train) {
+            self.training_status = Some("Training is already running".to_string());
+            return;
+        }
+        if self.monitor.is_some() {
+            self.training_status = Some("Following a run started elsewhere; m lets go of it first".to_string());
+            return;
+        }
+        self.open_config_form();
+        let Some(form) = &self.config_form else {
+            return;
+        };
+        if form.has_drafts() {
+            self.training_status = Some("The Config screen has drafts (~); set them or save first".to_string());
+            return;
+        }
+        let config = form.config.clone();
+        if let Some(host) = self.remote_host(self.training_host.as_deref()) {
+            self.preview_remote_training(config, &host);
+            return;
+        }
+        let config = config.in_root(&self.project_root);
+        let Some(storage_issues) = self.storage.issues(&config, self.planned_epochs) else {
+            self.check_storage();
+            self.start_after_storage = true;
+            self.training_status = Some("Checking disk space and the dataset first…".to_string());
+            return;
+        };
+        let mut issues = config_check::validate(&config);
+        issues.extend(storage_issues);
+        let seen = issues == self.training_issues;
+        self.training_issues = issues;
+        if has_errors(&self.training_issues) {
+            self.training_status = Some("The config has errors; fix them on the Config screen first".to_string());
+            return;
+        }
+        if !self.training_issues.is_empty() && !seen {
+            self.training_status = Some("The config has warnings; press t again to start anyway".to_string());
+            return;
+        }
+        let runner = self.runner();
+        match runner.python() {
+            Ok(python) => {
+                let command = preview_training_command(&config, &python, &runner.script(&self.scripts.train));
+                self.launch_preview = Some((command, Launch::Training(config)));
+            }
+            Err(e) => self.report_error("Training not started", &e),
+        }
+    }
+
+    /// Like the local checks before a run, with only those that make sense
+    /// for a host whose folders can't be looked at from here.
+    fn preview_remote_training(&mut self, config: TrainingConfig, host: &RemoteHost) {
+        let issues = config_check::validate_remote(&config);
+        let seen = issues == self.training_issues;
+        self.training_issues = issues;
+        if has_errors(&self.training_issues) {
+            self.training_status = Some("The config has errors; fix them on the Config screen first".to_string());
+            return;
+        }
+        if !self.training_issues.is_empty() && !seen {
+            self.training_status = Some("The config has warnings; press t again to start anyway".to_string());
+            return;
+        }
+        let command = host.command(&self.scripts.train, &build_train_command(&config), &remote::run_tag("train")).display();
+        self.launch_preview = Some((command, Launch::Training(config)));
+    }
+
+    /// Asks where to pull `source` to, starting from `destination`.
+    fn offer_pull(&mut self, source: RemoteLocation, destination: &Path) {
+        if self.pull.is_some() {
+            self.pull_status = Some("Already pulling results; j lists the transfer".to_string());
+            return;
+        }
+        self.pull_prompt = Some(TextInput::single_line().with_text(&destination.to_string_lossy()));
+        self.pull_source = Some(source);
+    }
+
+    /// Copies the remote folder into the folder typed, as a job of its own.
+    fn start_pull(&mut self) {
+        let (Some(prompt), Some(source)) = (self.pull_prompt.take(), self.pull_source.take()) else {
+            return;
+        };
+        let tool = match Tool::find() {
+            Ok(tool) => tool,
+            Err(e) => {
+                self.report_error("Results not pulled", &e);
+                return;
+            }
+        };
+        let destination = self.project_path(prompt.text().trim());
+        let log = Arc::new(Mutex::new(LogBuffer::new()));
+        self.pull_progress = Arc::new(Mutex::new(None));
+        let (progress, task_log, task_source, task_destination) = (self.pull_progress.clone(), log.clone(), source.clone(), destination.clone());
+        let task = self.spawn_task("Pulling results", OnCancel::Discard, move |ctx| {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|e| anyhow::anyhow!("Failed to start the transfer runtime: {}", e))?;
+            runtime.block_on(async {
+                tokio::select! {
+                    result = transfer::pull(tool, &task_source, &task_destination, progress, task_log) => result,
+                    _ = async {
+                        while !ctx.is_cancelled() {
+                            tokio::time::sleep(Duration::from_millis(200)).await;
+                        }
+                    } => Err(anyhow::anyhow!("Transfer stopped")),
+                }
+            })
+        });
+        self.pull_job = Some(self.begin_job(JobKind::Transfer, &source.source(), task.monitor(), log));
+        self.pull_status = Some(format!("Pulling {} into {}…", source.source(), destination.display()));
+        self.pull = Some((destination, task));
+    }
+
+    /// The pull going on, as far as rsync has said.
+    pub fn pull_progress(&self) -> Option<TransferProgress> {
+        self.pull.as_ref().and_then(|_| self.pull_progress.lock().unwrap().clone())
+    }
+
+    /// The `remote_hosts` entry called `name`.
+    fn remote_host(&self, name: Option<&str>) -> Option<RemoteHost> {
+        name.and_then(|name| self.config.remote_host(name)).cloned()
+    }
+
+    /// The host choice of the screen the Hosts screen was opened from.
+    fn host_choice(&mut self) -> &mut Option<String> {
+        match self.nav_stack.last() {
+            Some((Screen::Inference, _)) => &mut self.inference_host,
+            _ => &mut self.training_host,
+        }
+    }
+
+    /// The Hosts screen's rows: here, then each remote host, the one runs
+    /// go to marked.
+    pub fn host_lines(&self) -> Vec<String> {
+        let chosen = match self.nav_stack.last() {
+            Some((Screen::Inference, _)) => self.inference_host.as_deref(),
+            _ => self.training_host.as_deref(),
+        };
+        let mark = |on: bool| if on { "[x]" } else { "[ ]" };
+        let mut lines = vec![format!("{} This machine", mark(chosen.is_none()))];
+        lines.extend(self.config.remote_hosts.iter().map(|host| format!("{} {}", mark(chosen == Some(host.name.as_str())), host.describe())));
+        lines
+    }
+
+    fn pick_host(&mut self) {
+        let host = self.selected_index.checked_sub(1).and_then(|index| self.config.remote_hosts.get(index)).cloned();
+        self.hosts_status = Some(match &host {
+            Some(host) => format!("Runs go to {} over ssh", host.describe()),
+            None => "Runs go on this machine".to_string(),
+        });
+        *self.host_choice() = host.map(|host| host.name);
+    }
+
+    /// Checks in the background that the highlighted host answers and has
+    /// the training script.
+    fn test_host(&mut self) {
+        let Some(host) = self.selected_index.checked_sub(1).and_then(|index| self.config.remote_hosts.get(index)).cloned() else {
+            self.hosts_status = Some("This machine needs no connection test".to_string());
+            return;
+        };
+        if self.host_test.is_some() {
+            return;
+        }
+        let script = self.scripts.train.clone();
+        self.hosts_status = Some(format!("Connecting to {}…", host.ssh_destination));
+        self.host_test = Some(self.spawn_task("Testing the ssh connection", OnCancel::Discard, move |_| {
+            remote::test_connection(&host, &script)
+        }));
+    }
+
+    /// The Devices screen's rows: each GPU, marked when it is in
+    /// `device_ids`, then force CPU when there is no GPU or it is on.
+    pub fn device_lines(&self) -> Vec<String> {
+        let Some(form) = &self.config_form else {
+            return vec![];
+        };
+        let config = &form.config;
+        let mark = |on: bool| if on { "[x]" } else { "[ ]" };
+        let min_free = self.config.gpu_min_free_mib();
+        let mut lines: Vec<String> = self.gpu_devices.iter()
+            .map(|gpu| {
+                let on = !config.force_cpu && config.device_ids.as_ref().is_some_and(|ids| ids.contains(&gpu.index));
+                let low = if gpu.low_memory(min_free) { "  (low free memory)" } else { "" };
+                format!("{} {}{}", mark(on), gpu.describe(), low)
+            })
+            .collect();
+        if self.gpu_devices.is_empty() || config.force_cpu {
+            lines.push(format!("{} Force CPU (--force_cpu)", mark(config.force_cpu)));
+        }
+        lines
+    }
+
+    /// Puts the highlighted GPU in `device_ids` or takes it out, or flips
+    /// force CPU. A GPU with little free memory can be picked, with a
+    /// warning.
+    fn toggle_device(&mut self) {
+        let min_free = self.config.gpu_min_free_mib();
+        let Some(form) = self.config_form.as_mut() else {
+            return;
+        };
+        let config = &mut form.config;
+        let Some(gpu) = self.gpu_devices.get(self.selected_index) else {
+            if self.selected_index == self.gpu_devices.len() {
+                config.force_cpu = !config.force_cpu;
+                self.devices_status = Some(if config.force_cpu {
+                    "The run goes on the CPU with --force_cpu".to_string()
+                } else {
+                    "The run goes on the GPUs in device_ids".to_string()
+                });
+            }
+            return;
+        };
+        let mut ids = config.device_ids.clone().unwrap_or_default();
+        let picked = !ids.contains(&gpu.index);
+        if picked {
+            ids.push(gpu.index);
+            ids.sort_unstable();
+        } else {
+            ids.retain(|id| *id != gpu.index);
+        }
+        config.force_cpu = false;
+        config.device_ids = (!ids.is_empty()).then_some(ids);
+        let ids = template::field_value(config, "device_ids");
+        self.devices_status = Some(if picked && gpu.low_memory(min_free) {
+            format!(
+                "device_ids: {}. GPU {} has {:.1} GB free, under {:.1} GB; someone else's job may be using it",
+                ids,
+                gpu.index,
+                gpu.free_mib.unwrap_or_default() as f64 / 1024.0,
+                min_free as f64 / 1024.0,
+            )
+        } else if ids.is_empty() {
+            "No device_ids; train.py uses GPU 0".to_string()
+        } else {
+            format!("device_ids: {}", ids)
+        });
+    }
+
+    /// Reads free space under results_path and walks data_paths in the
+    /// background, for the paths not checked lately.
+    fn check_storage(&mut self) {
+        if self.storage_check.is_some() {
+            return;
+        }
+        let Some(form) = &self.config_form else {
+            return;
+        };
+        let request = self.storage.request(&form.config.in_root(&self.project_root));
+        if request.is_empty() {
+            return;
+        }
+        self.storage_check = Some(self.spawn_task("Checking disk space and the dataset", OnCancel::Discard, move |ctx| {
+            storage::check(&request, ctx)
+        }));
+    }
+
+    /// Offers to go on from the newest checkpoint in the results folder when
+    /// the config doesn't start from one yet, or goes on without asking
+    /// with `auto_resume`. Each checkpoint is offered once.
+    fn offer_resume(&mut self) {
+        if self.training.is_some() || self.monitor.is_some() {
+            return;
+        }
+        self.open_config_form();
+        let Some(form) = &self.config_form else {
+            return;
+        };
+        if form.config.start_checkpoint.as_deref().is_some_and(|path| !path.trim().is_empty()) {
+            return;
+        }
+        let Some(checkpoint) = resume_checkpoint(&self.project_path(&form.config.results_path), form.config.model_type.key()) else {
+            return;
+        };
+        if self.config.auto_resume {
+            self.resume_from(&checkpoint.path);
+        } else if self.resume_offered.as_ref() != Some(&checkpoint.path) {
+            self.resume_offered = Some(checkpoint.path.clone());
+            let question = format!("Resume from {}? (y/n)", checkpoint.label());
+            self.confirm = Some((question, ConfirmAction::Resume(checkpoint.path)));
+        }
+    }
+
+    /// Points the training config's start_checkpoint at `path`; the
+    /// Config screen saves it like any other change.
+    fn resume_from(&mut self, path: &Path) {
+        let Some(form) = self.config_form.as_mut() else {
+            return;
+        };
+        form.config.start_checkpoint = Some(path.to_string_lossy().into_owned());
+        let label = CheckpointInfo::read(path).label();
+        self.training_status = Some(format!("Resuming from {}; t starts the run", label));
+    }
+
+    /// Adds the Config screen's config to the training queue.
+    fn queue_training(&mut self) {
+        self.open_config_form();
+        let Some(form) = &self.config_form else {
+            return;
+        };
+        if form.has_drafts() {
+            self.training_status = Some("The Config screen has drafts (~); set them or save first".to_string());
+            return;
+        }
+        let config = form.config.clone();
+        if has_errors(&config_check::validate(&config.in_root(&self.project_root))) {
+            self.training_status = Some("The config has errors; fix them on the Config screen first".to_string());
+            return;
+        }
+        self.training_queue.push(config);
+        self.training_status = Some(match self.save_queue() {
+            Ok(()) => format!("Added to the queue, {} run(s) waiting; v shows it", self.training_queue.pending()),
+            Err(e) => format!("{:#}", e),
+        });
+    }
+
+    fn queue_path(&self) -> PathBuf {
+        self.project_path(self.config.training_queue_path.as_deref().unwrap_or(DEFAULT_QUEUE_FILE))
+    }
+
+    fn save_queue(&self) -> anyhow::Result<()> {
+        self.training_queue.save(&self.queue_path())
+    }
+
+    /// Starts the first queued run still to go. Runs whose config has
+    /// errors by now are recorded as not started and count as failed.
+    fn start_next_queued(&mut self) {
+        if self.training.is_some() {
+            self.queue_status = Some("Training is already running; the queue starts once it is over".to_string());
+            return;
+        }
+        if self.config.dry_run {
+            self.queue_status = Some("Dry run: the queue doesn't start (d on the Settings screen turns it off)".to_string());
+            return;
+        }
+        loop {
+            let Some(index) = self.training_queue.next_pending() else {
+                self.queue_status = Some(format!("Queue finished: {} run(s)", self.training_queue.runs().len()));
+                return;
+            };
+            let config = self.training_queue.runs()[index].config.clone();
+            let issues = config_check::validate(&config.in_root(&self.project_root));
+            if !has_errors(&issues) {
+                self.queue_run = Some(index);
+                self.submit_run(GatedRun::Training(config));
+                self.queue_status = Some(format!("Run {} of {} started", index + 1, self.training_queue.runs().len()));
+                return;
+            }
+            let errors: Vec<String> = issues.iter().map(ConfigIssue::line).collect();
+            let summary = RunSummary { status: format!("Not started: {}", errors.join("; ")), success: false, final_sdr: None, duration_secs: 0 };
+            let go_on = self.training_queue.record(index, summary);
+            if let Err(e) = self.save_queue() {
+                self.queue_status = Some(format!("{:#}", e));
+                return;
+            }
+            if !go_on {
+                self.queue_status = Some(format!("Queue stopped: run {} has config errors", index + 1));
+                return;
+            }
+        }
+    }
+
+    /// Records how the queued run at `index` ended and starts the next one,
+    /// unless it was stopped by hand, failed under the stop policy, or the
+    /// app is quitting.
+    fn finish_queued_run(&mut self, index: usize, success: bool, stopped: bool, duration_secs: u64) {
+        let summary = RunSummary {
+            status: self.training_status.clone().unwrap_or_default(),
+            success,
+            final_sdr: self.training_history.progress().iter().rev().find_map(|progress| progress.sdr),
+            duration_secs,
+        };
+        let go_on = self.training_queue.record(index, summary);
+        if let Err(e) = self.save_queue() {
+            self.queue_status = Some(format!("{:#}", e));
+            return;
+        }
+        self.queue_status = Some(if stopped {
+            "Queue paused: its run was stopped; s goes on with the next".to_string()
+        } else if !go_on {
+            format!("Queue stopped: run {} failed (f changes what a failure does)", index + 1)
+        } else if self.quit_when_stopped || self.shutdown.is_cancelled() {
+            "Queue paused for quitting; it resumes with s next time".to_string()
+        } else {
+            self.start_next_queued();
+            return;
+        });
+    }
+
+    /// Shift+Up/Down on the queue screen: moves the selected run, keeping
+    /// track of the one training.
+    fn move_queued(&mut self, up: bool) {
+        let from = self.selected_index;
+        let moved = if up { self.training_queue.move_up(from) } else { self.training_queue.move_down(from) };
+        if !moved {
+            return;
+        }
+        let to = if up { from - 1 } else { from + 1 };
+        self.queue_run = self.queue_run.map(|run| if run == from { to } else if run == to { from } else { run });
+        self.selected_index = to;
+        self.queue_status = self.save_queue().err().map(|e| format!("{:#}", e));
+    }
+
+    fn handle_queue_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char('s') => self.start_next_queued(),
+            KeyCode::Char('w') => {
+                let sweep = self.training_queue.runs().get(self.selected_index).and_then(|run| run.sweep.clone());
+                match sweep {
+                    Some(sweep) => self.open_sweep(sweep),
+                    None => self.queue_status = Some("That run is not part of a sweep".to_string()),
+                }
+            }
+            KeyCode::Char('f') => {
+                self.training_queue.on_failure = self.training_queue.on_failure.toggled();
+                self.queue_status = Some(match self.save_queue() {
+                    Ok(()) => format!("On failure: {}", self.training_queue.on_failure.name()),
+                    Err(e) => format!("{:#}", e),
+                });
+            }
+            KeyCode::Char('d') => {
+                if self.queue_run == Some(self.selected_index) {
+                    self.queue_status = Some("That run is training; Ctrl+X stops it first".to_string());
+                    return;
+                }
+                let Some(removed) = self.training_queue.remove(self.selected_index) else {
+                    return;
+                };
+                self.queue_run = self.queue_run.map(|run| if run > self.selected_index { run - 1 } else { run });
+                self.selected_index = self.selected_index.min(self.training_queue.runs().len().saturating_sub(1));
+                self.queue_status = Some(match self.save_queue() {
+                    Ok(()) => format!("Removed {} from the queue", removed.config.model_type.name()),
+                    Err(e) => format!("{:#}", e),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    /// Writes a model config per combination of the typed sweep spec over
+    /// the Config screen's model config, and queues a run of each under
+    /// `<results_path>/<sweep>/<run>`.
+    fn generate_sweep(&mut self) {
+        let Some(prompt) = self.sweep_prompt.take() else {
+            return;
+        };
+        let spec_path = self.project_path(prompt.text().trim());
+        self.open_config_form();
+        let Some(form) = &self.config_form else {
+            return;
+        };
+        if form.has_drafts() {
+            self.training_status = Some("The Config screen has drafts (~); set them or save first".to_string());
+            return;
+        }
+        let base = form.config.in_root(&self.project_root);
+        if has_errors(&config_check::validate(&base)) {
+            self.training_status = Some("The config has errors; fix them on the Config screen first".to_string());
+            return;
+        }
+        let stem = spec_path.file_stem().map_or("sweep".to_string(), |stem| stem.to_string_lossy().into_owned());
+        let name = format!("{}_{}", chrono::Local::now().format("%Y%m%d-%H%M%S"), stem);
+        let dir = Path::new(&base.results_path).join(&name);
+        let max_runs = self.config.max_sweep_runs.unwrap_or(DEFAULT_MAX_SWEEP_RUNS);
+        let runs = match SweepSpec::load(&spec_path).and_then(|spec| sweep::generate(Path::new(&base.config_path), &spec, &dir, max_runs)) {
+            Ok(runs) => runs,
+            Err(e) => {
+                self.training_status = Some(format!("Sweep not queued: {:#}", e));
+                return;
+            }
+        };
+        for run in &runs {
+            let mut config = base.clone();
+            config.config_path = run.config_path.to_string_lossy().into_owned();
+            config.results_path = dir.join(&run.name).to_string_lossy().into_owned();
+            config.label = Some(run.name.clone());
+            self.training_queue.push_sweep_run(config, &name);
+        }
+        self.queue_status = Some(match self.save_queue() {
+            Ok(()) => format!("Queued {} runs of {}, configs in {}; s on the queue screen (v) starts them", runs.len(), name, dir.display()),
+            Err(e) => format!("{:#}", e),
+        });
+        self.open_sweep(name);
+    }
+
+    fn open_sweep(&mut self, name: String) {
+        self.sweep = Some(name);
+        self.refresh_sweep();
+        self.push_screen(Screen::Sweep);
+    }
+
+    fn refresh_sweep(&mut self) {
+        if let Some(name) = &self.sweep {
+            self.sweep_table.set_rows(sweep::results(&self.training_queue, name, self.queue_run));
+        }
+    }
+
+    fn draw_sweep(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new(format!("Sweep {}", self.sweep.as_deref().unwrap_or_default()))
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+        let footer = self.queue_status.as_deref().unwrap_or(self.key_help());
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(3),
+            ])
+            .split(area);
+        f.render_widget(title, chunks[0]);
+        self.sweep_table.render(f, chunks[1], &sweep_summary(self.sweep_table.rows()), &styles);
+        f.render_widget(
+            Paragraph::new(footer).block(Block::default().borders(Borders::ALL).border_style(styles.border)).wrap(Wrap { trim: false }),
+            chunks[2],
+        );
+    }
+
+    /// One line per queued run, the training one marked.
+    pub fn queue_lines(&self) -> Vec<String> {
+        self.training_queue.runs()
+            .iter()
+            .enumerate()
+            .map(|(i, run)| run.line(self.queue_run == Some(i)))
+            .collect()
+    }
+
+    fn draw_training_queue(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new("Training Queue")
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let lines = self.queue_lines();
+        let items: Vec<ListItem> = if lines.is_empty() {
+            vec![ListItem::new("Nothing queued; a on the Training screen adds its config")]
+        } else {
+            lines.into_iter()
+                .enumerate()
+                .map(|(i, line)| {
+                    let item = ListItem::new(line);
+                    if i == self.selected_index { item.style(styles.highlight) } else { item }
+                })
+                .collect()
+        };
+        let len = items.len();
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(styles.border)
+                .title(format!("In order; on failure: {}", self.training_queue.on_failure.name())),
+        );
+        let footer = self.queue_status.as_deref().unwrap_or(self.key_help());
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(3),
+            ])
+            .split(area);
+        f.render_widget(title, chunks[0]);
+        f.render_widget(list, chunks[1]);
+        self.record_list(chunks[1], 0, len);
+        f.render_widget(
+            Paragraph::new(footer).block(Block::default().borders(Borders::ALL).border_style(styles.border)).wrap(Wrap { trim: false }),
+            chunks[2],
+        );
+    }
+
+    /// Remembers the Config screen's file and queues training with `config`.
+    fn submit_training(&mut self, config: TrainingConfig) {
+        if let Some(form) = &self.config_form {
+            self.remember_config(RecentConfig::new(&form.path, RecentKind::Training, config.model_type.clone()));
+        }
+        self.submit_run(GatedRun::Training(config));
+    }
+
+    /// MSST processes already on the GPUs `config` asks for, as the
+    /// question to ask before starting, and a GPU free of them and of the
+    /// supervised jobs to offer instead.
+    fn gpu_conflicts(&self, config: &TrainingConfig) -> Option<(String, Option<usize>)> {
+        let mut manager = TrainingManager::new();
+        manager.set_remote(self.remote_host(self.training_host.as_deref()));
+        let own_pids = self.supervisor.pids();
+        let question = gpu_check::launch_prompt(&manager.gpu_preflight(config, &own_pids))?;
+        let requested = gpu_check::requested_devices(config.device_ids.as_deref());
+        let candidates: Vec<usize> = self.supervisor.allocator().devices().iter()
+            .copied()
+            .filter(|device| !requested.contains(device) && self.supervisor.allocator().holder(*device).is_none())
+            .collect();
+        let busy: Vec<usize> = gpu_check::check(&candidates, &own_pids).iter().map(|conflict| conflict.device).collect();
+        let alternative = candidates.into_iter().find(|device| !busy.contains(device));
+        Some((question, alternative))
+    }
+
+    /// Queues `run` with the supervised jobs, which start it as soon as the
+    /// GPUs it needs are free of other runs and jobs.
+    fn submit_run(&mut self, run: GatedRun) {
+        let (name, requirement) = match &run {
+            GatedRun::Training(config) => {
+                let requirement = if config.force_cpu || self.training_host.is_some() {
+                    DeviceRequirement::None
+                } else {
+                    DeviceRequirement::from_device_ids(config.device_ids.as_deref())
+                };
+                (format!("Training {}", config.model_type.name()), requirement)
+            }
+            GatedRun::Inference(config) => {
+                let requirement = if self.inference_host.is_some() { DeviceRequirement::None } else { DeviceRequirement::AnyGpu };
+                (format!("Inference {}", config.model_type.name()), requirement)
+            }
+            GatedRun::Validation(config) => (format!("Validation {}", config.model_type.name()), DeviceRequirement::AnyGpu),
+        };
+        // Without a GPU there is nothing to wait for.
+        let requirement = if self.supervisor.allocator().devices().is_empty() { DeviceRequirement::None } else { requirement };
+        let kind = run.kind();
+        let id = self.supervisor.submit(JobSpec { kind, name, command: None, requirement, log_path: None, results_dir: None });
+        self.gated_runs.push((id, run));
+        self.supervisor.tick();
+        self.start_granted_runs();
+        if self.gated_runs.iter().any(|(gated, _)| *gated == id) {
+            let status = Some(format!("Queued, {}; a on the Jobs screen starts it anyway", self.supervisor.status(id)));
+            match kind {
+                JobKind::Train => self.training_status = status,
+                JobKind::Infer => self.inference_status = status,
+                _ => self.validation_status = status,
+            }
+        }
+    }
+
+    /// Whether a run of `kind` is queued for its GPUs.
+    fn is_waiting(&self, kind: JobKind) -> bool {
+        self.gated_runs.iter().any(|(_, run)| run.kind() == kind)
+    }
+
+    /// Launches the queued runs the supervised jobs have found GPUs for.
+    fn start_granted_runs(&mut self) {
+        for (id, devices) in self.supervisor.take_started() {
+            let Some(index) = self.gated_runs.iter().position(|(gated, _)| *gated == id) else {
+                continue;
+            };
+            match self.gated_runs.remove(index).1 {
+                GatedRun::Training(mut config) => {
+                    // The GPU it got when any free one would do.
+                    if !devices.is_empty() && !config.force_cpu {
+                        config.device_ids = Some(devices);
+                    }
+                    self.launch_training(id, config);
+                }
+                GatedRun::Inference(config) => self.launch_inference(id, config, &devices),
+                GatedRun::Validation(config) => self.launch_validation(id, config, &devices),
+            }
+        }
+    }
+
+    /// Starts training in the background. Its progress goes to
+    /// `training_history`.
+    fn launch_training(&mut self, id: JobId, config: TrainingConfig) {
+        self.training_issues.clear();
+        // One entered with `n` stands when the model config has none.
+        if let Ok(Some(epochs)) = model_num_epochs(&self.project_path(&config.config_path).to_string_lossy()) {
+            self.planned_epochs = Some(epochs);
+        }
+        let mut manager = TrainingManager::new();
+        manager.set_scripts(&self.scripts);
+        manager.set_runner(self.runner());
+        manager.set_remote(self.remote_host(self.training_host.as_deref()));
+        manager.set_parser_rules(self.parser_rules.clone());
+        manager.set_cost_settings(self.config.cost.clone());
+        if let Some(template) = &self.config.run_name_template {
+            manager.set_run_name_template(template);
+        }
+        manager.set_history_path(&self.history_path());
+        if let Some(secs) = self.config.training_stop_grace_secs {
+            manager.set_stop_grace(Duration::from_secs(secs));
+        }
+
+        self.training_log = manager.log();
+        self.training_step = manager.step_progress();
+        self.training_log_pane = LogPane::new();
+        let detach = manager.detach_handle();
+        self.training_detach = Some(detach.clone());
+        manager.set_watchdog(self.config.watchdog);
+        self.training_watchdog = Some(manager.watchdog());
+        manager.set_parse_health(self.config.parse_health);
+        self.training_parse_health = Some(manager.parse_health());
+
+        let (progress_tx, progress_rx) = mpsc::unbounded_channel();
+        let task = self.spawn_task("Training", OnCancel::KeepPartial, move |ctx| {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|e| anyhow::anyhow!("Failed to start the training runtime: {}", e))?;
+            runtime.block_on(async {
+                let finished = tokio::select! {
+                    result = manager.start_training(&config, progress_tx) => Some(result),
+                    // A detached run is left to hand its output over, even
+                    // when the app cancels everything on its way out.
+                    _ = async {
+                        while !ctx.is_cancelled() || detach.is_cancelled() {
+                            tokio::time::sleep(Duration::from_millis(200)).await;
+                        }
+                    } => None,
+                };
+                let stopped = match finished {
+                    Some(result) => {
+                        result?;
+                        None
+                    }
+                    None => Some(manager.stop_training().await?),
+                };
+                Ok((manager.status().clone(), stopped, manager.resources().cloned()))
+            })
+        });
+        self.supervisor.attach(id, task.monitor(), self.training_log.clone());
+        self.training_job = Some(id);
+        self.training = Some(task);
+        self.training_rx = Some(progress_rx);
+        self.training_history.clear();
+        self.training_status = None;
+    }
+
+    /// Follows the log typed in the monitor prompt from its start, so the
+    /// chart fills in the epochs already done, then as it grows. Only the
+    /// log is read: letting go leaves the process that writes it alone.
+    fn monitor_run(&mut self) {
+        let Some(prompt) = self.monitor_prompt.take() else {
+            return;
+        };
+        let path = match find_training_log(&self.project_path(prompt.text().trim())) {
+            Ok(path) => path,
+            Err(e) => {
+                self.report_error("Run not followed", &e);
+                return;
+            }
+        };
+        let mut manager = TrainingManager::new();
+        manager.set_parser_rules(self.parser_rules.clone());
+        manager.set_history_path(&self.history_path());
+        manager.set_parse_health(self.config.parse_health);
+        self.training_parse_health = Some(manager.parse_health());
+        self.training_log = manager.log();
+        self.training_step = manager.step_progress();
+        self.training_log_pane = LogPane::new();
+        self.training_history.clear();
+        self.planned_epochs = None;
+
+        let (progress_tx, progress_rx) = mpsc::unbounded_channel();
+        let (events_tx, events_rx) = mpsc::unbounded_channel();
+        let log = path.clone();
+        let name = format!("Following {}", path.file_name().map_or(String::new(), |name| name.to_string_lossy().into_owned()));
+        let task = self.spawn_task(&name, OnCancel::KeepPartial, move |ctx| {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|e| anyhow::anyhow!("Failed to start the log reader runtime: {}", e))?;
+            runtime.block_on(async {
+                let stop = CancelToken::new();
+                let attached = async {
+                    let result = manager.attach_log(&log, true, progress_tx, events_tx, stop.clone()).await;
+                    stop.cancel();
+                    result
+                };
+                let cancelled = async {
+                    while !stop.is_cancelled() {
+                        if ctx.is_cancelled() {
+                            stop.cancel();
+                        }
+                        tokio::time::sleep(Duration::from_millis(200)).await;
+                    }
+                };
+                tokio::join!(attached, cancelled).0
+            })
+        });
+        let label = format!("{}, started elsewhere", path.display());
+        self.training_job = Some(self.begin_job(JobKind::Train, &label, task.monitor(), self.training_log.clone()));
+        self.training_rx = Some(progress_rx);
+        self.monitor_rx = Some(events_rx);
+        self.training_status = Some(format!("Following {}; m lets go of it, the run itself is left alone", path.display()));
+        self.monitor = Some((path, task));
+    }
+
+    /// What happened to the followed log file, and the end of following it.
+    fn receive_monitor_events(&mut self) {
+        if let Some(rx) = self.monitor_rx.as_mut() {
+            while let Ok(event) = rx.try_recv() {
+                let note = match event {
+                    TailEvent::Missing => "The log file is gone; waiting for it to come back",
+                    TailEvent::Reappeared => "The log file is back",
+                    TailEvent::Truncated => "The log file was truncated; reading it from the start",
+                    TailEvent::Rotated => "The log file was rotated; reading the new one",
+                    TailEvent::Line(_) => continue,
+                };
+                self.training_log.lock().unwrap().push(note);
+            }
+        }
+        let Some(outcome) = self.monitor.as_mut().and_then(|(_, task)| task.try_join()) else {
+            return;
+        };
+        let (path, _) = self.monitor.take().unwrap();
+        self.receive_training_progress();
+        self.training_rx = None;
+        self.monitor_rx = None;
+        let status = match outcome {
+            Ok(_) => format!("Stopped following {}", path.display()),
+            Err(e) => format!("Following {} failed: {:#}", path.display(), e),
+        };
+        if let Some(id) = self.training_job.take() {
+            self.supervisor.finish(id, Ok(Some(0)), &status);
+        }
+        self.training_status = Some(status);
+    }
+
+    /// Writes the run's metrics to `tui_metrics_<time>.csv` and `.json` in
+    /// the results folder.
+    fn export_metrics(&mut self) {
+        if self.training_history.is_empty() {
+            self.training_status = Some("No metrics to export yet".to_string());
+            return;
+        }
+        let results = self.config_form.as_ref()
+            .map(|form| form.config.results_path.clone())
+            .filter(|path| !path.is_empty())
+            .unwrap_or_else(|| "results".to_string());
+        let results = self.project_path(&results).to_string_lossy().into_owned();
+        let base = Path::new(&results).join(format!("tui_metrics_{}", chrono::Local::now().format("%Y%m%d-%H%M%S")));
+        let (csv, json) = (base.with_extension("csv"), base.with_extension("json"));
+        let written = std::fs::create_dir_all(&results)
+            .map_err(|e| anyhow::anyhow!("Failed to create {}: {}", results, e))
+            .and_then(|()| self.training_history.write_csv(&csv))
+            .and_then(|()| self.training_history.write_json(&json));
+        self.training_status = Some(match written {
+            Ok(()) => format!("Exported {} update(s) to {} and {}", self.training_history.len(), csv.display(), json.display()),
+            Err(e) => {
+                self.report_error("Metrics not exported", &e);
+                format!("Export failed: {:#}", e)
+            }
+        });
+    }
+
+    /// The screen whose log the Training or Inference screen shows, or the
+    /// Log screen was opened from.
+    fn log_screen(&self) -> Option<Screen> {
+        match &self.screen {
+            Screen::Log => self.previous_screen().cloned(),
+            screen @ (Screen::Training | Screen::Inference | Screen::Ensemble) => Some(screen.clone()),
+            _ => None,
+        }
+    }
+
+    /// Scrolls the log pane: PageUp/PageDown and Home/End under Training
+    /// and Inference, Up/Down too on the Log screen.
+    fn handle_log_key(&mut self, code: KeyCode) -> bool {
+        let scroll = match code {
+            KeyCode::PageUp | KeyCode::PageDown | KeyCode::Home | KeyCode::End => true,
+            KeyCode::Up | KeyCode::Down => self.screen == Screen::Log,
+            _ => false,
+        };
+        scroll && self.scroll_log(code)
+    }
+
+    /// Scrolls the log pane the screen shows by `code`, a LogPane key.
+    fn scroll_log(&mut self, code: KeyCode) -> bool {
+        match self.log_screen() {
+            Some(Screen::Training) => {
+                let len = self.training_log.lock().unwrap().len();
+                self.training_log_pane.handle_key(code, len)
+            }
+            Some(Screen::Inference) => {
+                let len = self.inference_log.lock().unwrap().len();
+                self.inference_log_pane.handle_key(code, len)
+            }
+            Some(Screen::Ensemble) => {
+                let len = self.ensemble_log.lock().unwrap().len();
+                self.ensemble_log_pane.handle_key(code, len)
+            }
+            Some(Screen::Jobs) => match self.attached_job().and_then(Job::log) {
+                Some(log) => {
+                    let len = log.lock().unwrap().len();
+                    self.job_log_pane.handle_key(code, len)
+                }
+                None => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// The pane of the log the screen shows.
+    fn log_pane(&self) -> Option<&LogPane> {
+        match self.log_screen()? {
+            Screen::Training => Some(&self.training_log_pane),
+            Screen::Inference => Some(&self.inference_log_pane),
+            Screen::Ensemble => Some(&self.ensemble_log_pane),
+            Screen::Jobs => Some(&self.job_log_pane),
+            _ => None,
+        }
+    }
+
+    /// The latest `count` lines of the log the screen shows, stderr ones
+    /// marked, for plain mode.
+    pub fn log_tail(&self, count: usize) -> Vec<String> {
+        let tail = |log: &LogBuffer| {
+            log.lines()
+                .skip(log.len().saturating_sub(count))
+                .map(|line| match line.severity {
+                    Severity::Error => format!("stderr: {}", line.text),
+                    Severity::Info => line.text.to_string(),
+                })
+                .collect()
+        };
+        match self.log_screen() {
+            Some(Screen::Training) => tail(&self.training_log.lock().unwrap()),
+            Some(Screen::Inference) => tail(&self.inference_log.lock().unwrap()),
+            Some(Screen::Ensemble) => tail(&self.ensemble_log.lock().unwrap()),
+            Some(Screen::Jobs) => self.attached_job().and_then(Job::log).map(|log| tail(&log.lock().unwrap())).unwrap_or_default(),
+            _ => vec![],
+        }
+    }
+
+    fn draw_log(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(3),
+            ])
+            .split(area);
+        match self.log_screen() {
+            Some(Screen::Training) => self.training_log_pane.render(f, chunks[0], "Training log", &self.training_log.lock().unwrap(), &styles),
+            Some(Screen::Ensemble) => self.ensemble_log_pane.render(f, chunks[0], "Ensemble output", &self.ensemble_log.lock().unwrap(), &styles),
+            Some(Screen::Jobs) if let Some(job) = self.attached_job() && let Some(log) = job.log() => {
+                let mut title = self.supervisor.line(job.id());
+                if let Some(started_at) = job.started_at() {
+                    title.push_str(&format!(", started {}", started_at.format("%H:%M:%S")));
+                }
+                self.job_log_pane.render(f, chunks[0], &title, &log.lock().unwrap(), &styles)
+            }
+            _ => self.inference_log_pane.render(f, chunks[0], "Inference output", &self.inference_log.lock().unwrap(), &styles),
+        }
+        f.render_widget(
+            Paragraph::new(self.key_help()).block(Block::default().borders(Borders::ALL).border_style(styles.border)),
+            chunks[1],
+        );
+    }
+
+    /// A row per GPU, or one for a message.
+    fn gpu_panel_height(&self) -> u16 {
+        match &self.gpu_reading {
+            GpuReading::Devices(devices) if !devices.is_empty() => devices.len().min(8) as u16 + 2,
+            _ => 3,
+        }
+    }
+
+    fn draw_gpu_panel(&self, f: &mut Frame, area: ratatui::layout::Rect, styles: &Styles) {
+        let block = Block::default().borders(Borders::ALL).border_style(styles.border).title("GPUs");
+        let devices = match &self.gpu_reading {
+            GpuReading::Devices(devices) if !devices.is_empty() => devices,
+            reading => {
+                let text = match reading {
+                    GpuReading::Pending if self.gpu_monitor.is_some() => "Asking nvidia-smi...",
+                    GpuReading::Pending => "GPU stats show while training or inference runs",
+                    _ => "GPU monitoring unavailable",
+                };
+                f.render_widget(Paragraph::new(text).block(block), area);
+                return;
+            }
+        };
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+        for (gpu, y) in devices.iter().zip(inner.y..inner.bottom()) {
+            let row = ratatui::layout::Rect { y, height: 1, ..inner };
+            let halves = ratatui::layout::Layout::default()
+                .direction(ratatui::layout::Direction::Horizontal)
+                .constraints([ratatui::layout::Constraint::Percentage(60), ratatui::layout::Constraint::Percentage(40)])
+                .split(row);
+            let memory = ratatui::widgets::Gauge::default()
+                .gauge_style(styles.progress)
+                .ratio(gpu.memory_fraction())
+                .label(format!(
+                    "GPU {} {}: {:.1}/{:.1} GB",
+                    gpu.index,
+                    gpu.name,
+                    gpu.memory_used_mib as f64 / 1024.0,
+                    gpu.memory_total_mib as f64 / 1024.0,
+                ));
+            f.render_widget(memory, halves[0]);
+            let utilization = gpu.utilization.unwrap_or(0.0);
+            let label = gpu.utilization.map_or("util n/a".to_string(), |value| format!("util {:.0}%", value));
+            let utilization = ratatui::widgets::Gauge::default()
+                .gauge_style(styles.progress)
+                .ratio((utilization / 100.0).clamp(0.0, 1.0))
+                .label(label);
+            f.render_widget(utilization, halves[1]);
+        }
+    }
+
+    /// Where the run is and how long epochs take; the finish estimate only
+    /// while it runs.
+    pub fn training_timing(&self) -> Option<String> {
+        let planned = self.planned_epochs.filter(|_| self.training.is_some());
+        self.training_history.timing_line(planned, chrono::Local::now())
+    }
+
+    /// E.g. "possibly hung, quiet for 42 min", while the running training
+    /// has printed nothing and its GPU utilization hasn't moved for that long.
+    pub fn training_hang_warning(&self) -> Option<String> {
+        self.training.as_ref()?;
+        self.training_watchdog.as_ref()?.lock().unwrap().warning()
+    }
+
+    /// The unrecognized-progress notice and sample lines, once the run on
+    /// the Training screen has printed plenty without any progress.
+    pub fn training_parse_notice(&self) -> Option<Vec<String>> {
+        self.training_parse_health.as_ref()?.lock().unwrap().notice()
+    }
+
+    /// The last `count` lines of the Training screen's log.
+    pub fn training_log_tail(&self, count: usize) -> Vec<String> {
+        let log = self.training_log.lock().unwrap();
+        log.lines().skip(log.len().saturating_sub(count)).map(|line| line.text.to_string()).collect()
+    }
+
+    /// Whether a stopped run is still in its grace period.
+    pub fn training_stopping(&self) -> bool {
+        self.training.as_ref().is_some_and(|task| task.monitor().is_cancelled())
+    }
+
+    fn receive_training_progress(&mut self) {
+        let (gpu_memory, gpu_utilization) = self.gpu_reading.totals();
+        if let Some(rx) = self.training_rx.as_mut() {
+            while let Ok(mut progress) = rx.try_recv() {
+                // What the log says wins over nvidia-smi.
+                progress.gpu_memory = progress.gpu_memory.or(gpu_memory);
+                progress.gpu_utilization = progress.gpu_utilization.or(gpu_utilization);
+                if let Some(id) = self.training_job {
+                    let mut metric = format!("epoch {}, loss {:.4}", progress.epoch, progress.train_loss);
+                    if let Some(sdr) = progress.sdr {
+                        metric.push_str(&format!(", SDR {:.2}", sdr));
+                    }
+                    self.supervisor.set_progress(id, metric);
+                }
+                self.training_history.push(progress);
+            }
+        }
+    }
+
+    /// Starts the GPU monitor when training or inference starts and stops
+    /// it once neither runs; the last reading stays on screen.
+    fn update_gpu_monitor(&mut self) {
+        if self.training.is_none() && self.inference.is_none() {
+            self.gpu_monitor = None;
+            return;
+        }
+        let monitor = self.gpu_monitor.get_or_insert_with(|| GpuMonitor::start(GPU_POLL_INTERVAL));
+        let reading = monitor.latest();
+        if reading != GpuReading::Pending {
+            self.gpu_reading = reading;
+        }
+    }
+
+    fn draw_inference(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new("Inference")
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let Some(form) = &self.inference_form else {
+            let chunks = ratatui::layout::Layout::default()
+                .direction(ratatui::layout::Direction::Vertical)
+                .margin(1)
+                .constraints([
+                    ratatui::layout::Constraint::Length(3),
+                    ratatui::layout::Constraint::Min(10),
+                ])
+                .split(area);
+            f.render_widget(title, chunks[0]);
+            f.render_widget(Paragraph::new("Select a model first (1. Model Selection)"), chunks[1]);
+            return;
+        };
+
+        let mut lines = inference_lines(form);
+        if let Some(preview) = &self.input_preview {
+            lines.push(format!("Audio:      {} (i lists them)", preview.summary()));
+        }
+        let settings = Paragraph::new(lines.join("\n"))
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border).title("Settings"))
+            .wrap(Wrap { trim: false });
+
+        let presets = self.config.presets(&form.model_type);
+        let items: Vec<ListItem> = if presets.is_empty() {
+            vec![ListItem::new("No presets for this model yet")]
+        } else {
+            presets.iter()
+                .enumerate()
+                .map(|(i, preset)| {
+                    let item = ListItem::new(format!("{}  ({})", preset.name, preset.summary()));
+                    if i == self.selected_index {
+                        item.style(styles.highlight)
+                    } else {
+                        item
+                    }
+                })
+                .collect()
+        };
+        let len = items.len();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border).title(format!("Presets for {}", form.model_type.key())));
+
+        let footer = self.inference_status.as_deref().unwrap_or(self.key_help());
+        let session = self.inference_results.len().min(5) as u16;
+        let log_height = if self.inference.is_none() && self.inference_log.lock().unwrap().is_empty() { 0 } else { LOG_PANE_HEIGHT };
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Length(lines.len() as u16 + 2),
+                ratatui::layout::Constraint::Min(4),
+                ratatui::layout::Constraint::Length(if session > 0 { session + 2 } else { 0 }),
+                ratatui::layout::Constraint::Length(step_height(&self.inference_step)),
+                ratatui::layout::Constraint::Length(log_height),
+                ratatui::layout::Constraint::Length(4),
+            ])
+            .split(area);
+        draw_step(f, chunks[4], "Tracks", &self.inference_step, &styles);
+
+        f.render_widget(title, chunks[0]);
+        f.render_widget(settings, chunks[1]);
+        f.render_widget(list, chunks[2]);
+        self.record_list(chunks[2], 0, len);
+        if log_height > 0 {
+            let title = if self.inference.is_some() { "Output (running)" } else { "Output" };
+            self.inference_log_pane.render(f, chunks[5], title, &self.inference_log.lock().unwrap(), &styles);
+        }
+        if session > 0 {
+            let start = self.inference_results.len() - session as usize;
+            let runs: Vec<ListItem> = self.inference_results[start..]
+                .iter()
+                .map(|result| {
+                    let item = ListItem::new(inference_result_line(result));
+                    if result.success {
+                        item
+                    } else {
+                        item.style(styles.error)
+                    }
+                })
+                .collect();
+            f.render_widget(
+                List::new(runs).block(Block::default().borders(Borders::ALL).border_style(styles.border).title(format!("This session ({})", self.inference_results.len()))),
+                chunks[3],
+            );
+        }
+        f.render_widget(
+            Paragraph::new(footer)
+                .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+                .wrap(Wrap { trim: false }),
+            chunks[6],
+        );
+
+        if let Some(name) = &self.preset_name {
+            let popup = ratatui::layout::Rect {
+                x: area.width / 4,
+                y: area.height / 2 - 2.min(area.height / 2),
+                width: area.width / 2,
+                height: 3.min(area.height),
+            };
+            f.render_widget(ratatui::widgets::Clear, popup);
+            name.render(f, popup, self.prompt_title().unwrap_or_default());
+        }
+        if let Some(folder) = self.input_folder_prompt.as_ref()
+            .or(self.config_path_prompt.as_ref())
+            .or(self.store_dir_prompt.as_ref())
+            .or(self.rename_prompt.as_ref())
+            .or(self.verify_prompt.as_ref())
+            .or(self.extra_args_prompt.as_ref())
+            .or(self.pull_prompt.as_ref())
+        {
+            let popup = ratatui::layout::Rect {
+                x: area.width / 8,
+                y: area.height / 2 - 2.min(area.height / 2),
+                width: area.width * 3 / 4,
+                height: 3.min(area.height),
+            };
+            f.render_widget(ratatui::widgets::Clear, popup);
+            folder.render(f, popup, self.prompt_title().unwrap_or_default());
+        }
+    }
+
+    fn draw_dashboard(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new("Dashboard")
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let runs = self.dashboard.as_ref().map_or(&[][..], |dashboard| &dashboard.runs[..]);
+        let items: Vec<ListItem> = if runs.is_empty() {
+            vec![ListItem::new("No running training owned by another instance")]
+        } else {
+            runs.iter()
+                .enumerate()
+                .map(|(i, run)| {
+                    let item = ListItem::new(run.summary());
+                    if i == self.selected_index {
+                        item.style(styles.highlight)
+                    } else if run.watchdog.is_hung() {
+                        item.style(styles.error)
+                    } else {
+                        item
+                    }
+                })
+                .collect()
+        };
+        let len = items.len();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border).title(format!("Runs ({})", runs.len())));
+
+        let lines = self.dashboard.as_ref()
+            .map(|dashboard| dashboard.log_lines(self.selected_index, self.dashboard_times))
+            .unwrap_or_default();
+        let log_title = match self.dashboard.as_ref().and_then(|dashboard| dashboard.run_dir(self.selected_index)) {
+            Some(dir) => format!("Log of {}", dir.display()),
+            None => "Log".to_string(),
+        };
+
+        // Log-forward: the notice takes the place of the missing progress
+        // and the log below it gets the room.
+        let notice = self.dashboard.as_ref().and_then(|dashboard| dashboard.parse_notice(self.selected_index));
+        let notice_height = notice.as_ref().map_or(0, |notice| notice.len() as u16 + 3);
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Length(2 + runs.len().max(1) as u16),
+                ratatui::layout::Constraint::Length(notice_height),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(3),
+            ])
+            .split(area);
+
+        f.render_widget(title, chunks[0]);
+        f.render_widget(list, chunks[1]);
+        self.record_list(chunks[1], 0, len);
+        if let Some(notice) = notice {
+            f.render_widget(
+                Paragraph::new(notice.join("\n"))
+                    .block(Block::default().borders(Borders::ALL).border_style(styles.border).title("Progress not recognized"))
+                    .style(styles.warning)
+                    .wrap(Wrap { trim: false }),
+                chunks[2],
+            );
+        }
+        f.render_widget(
+            Paragraph::new(lines.join("\n")).block(Block::default().borders(Borders::ALL).border_style(styles.border).title(log_title)),
+            chunks[3],
+        );
+        f.render_widget(Paragraph::new(self.key_help()).wrap(Wrap { trim: false }), chunks[4]);
+    }
+
+    /// Looks for runs owned by live instances, in a history read fresh and
+    /// not saved back.
+    fn open_dashboard(&mut self) {
+        self.dashboard = match RunHistory::load(&self.history_path()) {
+            Ok(mut history) => Some(Dashboard::discover(&mut history, self.config.parse_health, self.config.watchdog)),
+            Err(e) => {
+                self.notice = Some(format!("{:#}", e));
+                None
+            }
+        };
+        self.push_screen(Screen::Dashboard);
+    }
+
+    /// One line per job: what it is doing or waiting for, or once it is
+    /// over, how it ended and how heavy it was.
+    pub fn job_lines(&self) -> Vec<String> {
+        self.supervisor.jobs().iter().map(|job| self.supervisor.line(job.id())).collect()
+    }
+
+    fn selected_job(&self) -> Option<JobId> {
+        self.supervisor.jobs().get(self.selected_index).map(Job::id)
+    }
+
+    /// The run the Log screen shows when opened from Jobs.
+    pub fn attached_job(&self) -> Option<&Job> {
+        self.attached_job.and_then(|id| self.supervisor.get(id))
+    }
+
+    /// Shows the run's live log full-screen; its progress and status are in
+    /// the title.
+    fn attach_job(&mut self, id: JobId) {
+        self.attached_job = Some(id);
+        self.job_log_pane = LogPane::new();
+        self.push_screen(Screen::Log);
+    }
+
+    fn draw_jobs(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new("Jobs")
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let lines = self.job_lines();
+        let items: Vec<ListItem> = if lines.is_empty() {
+            vec![ListItem::new("No jobs this session")]
+        } else {
+            self.supervisor.jobs()
+                .iter()
+                .zip(lines)
+                .enumerate()
+                .map(|(i, (job, line))| {
+                    let mut style = styles.text;
+                    if self.supervisor.is_hung(job.id()) {
+                        style = styles.error;
+                    }
+                    if i == self.selected_index {
+                        style = styles.highlight;
+                    }
+                    ListItem::new(line).style(style)
+                })
+                .collect()
+        };
+        let list = List::new(items).block(Block::default().borders(Borders::ALL).border_style(styles.border).title("Jobs this session"));
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(if self.last_dump.is_some() { 12 } else { 0 }),
+                ratatui::layout::Constraint::Length(3),
+            ])
+            .split(area);
+
+        f.render_widget(title, chunks[0]);
+        f.render_widget(list, chunks[1]);
+        self.record_list(chunks[1], 0, self.supervisor.jobs().len());
+        if let Some((name, dump)) = &self.last_dump {
+            f.render_widget(
+                Paragraph::new(dump.clone()).block(Block::default().borders(Borders::ALL).border_style(styles.border).title(format!("py-spy dump of {}", name))),
+                chunks[2],
+            );
+        }
+        let help = self.jobs_status.as_deref().unwrap_or(self.key_help());
+        f.render_widget(Paragraph::new(help).wrap(Wrap { trim: false }), chunks[3]);
+    }
+
+    fn handle_jobs_key(&mut self, code: KeyCode) {
+        let Some(job) = self.selected_job().and_then(|id| self.supervisor.get(id)) else {
+            return;
+        };
+        let (id, name) = (job.id(), job.name().to_string());
+        match code {
+            KeyCode::Char('a') => {
+                self.jobs_status = Some(match self.supervisor.start_anyway(id) {
+                    Ok(()) => format!("Started '{}' anyway", name),
+                    Err(e) => format!("{:#}", e),
+                });
+                self.start_granted_runs();
+            }
+            KeyCode::Char('p') => self.dump_stack(id, name),
+            KeyCode::Char('k' | 's') => {
+                self.jobs_status = Some(match self.supervisor.stop(id) {
+                    Ok(()) => format!("Stopping job #{} ('{}')", id, name),
+                    Err(e) => format!("{:#}", e),
+                });
+            }
+            KeyCode::Char('z') => {
+                self.jobs_status = Some(match self.supervisor.snooze(id) {
+                    Ok(()) => format!("Hang warning for '{}' snoozed", name),
+                    Err(e) => format!("{:#}", e),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    /// Takes a py-spy stack dump of the job in the background; py-spy can
+    /// take a few seconds to attach.
+    fn dump_stack(&mut self, id: JobId, name: String) {
+        if self.stack_dump.is_some() {
+            self.jobs_status = Some("Already dumping a stack".to_string());
+            return;
+        }
+        let Some(pid) = self.supervisor.pid(id) else {
+            self.jobs_status = Some(format!("'{}' is not running", name));
+            return;
+        };
+        let task = self.spawn_task("py-spy dump", OnCancel::Discard, move |_| {
+            Ok((name, watchdog::py_spy_dump(pid)?))
+        });
+        self.stack_dump = Some(task);
+        self.jobs_status = None;
+    }
+
+    /// Puts `entry` first in the recent configs and saves them.
+    fn remember_config(&mut self, entry: RecentConfig) {
+        self.config.push_recent_config(entry);
+        if let Err(e) = self.config_manager().save_config(&self.config) {
+            self.warn(format!("Recent configs not saved: {:#}", e));
+        }
+    }
+
+    /// Loads the selected recent config: a training config onto the
+    /// Training screen, an inference run's model config into the
+    /// Inference form.
+    fn open_recent_config(&mut self) {
+        let Some(entry) = self.config.recent_configs.get(self.selected_index).cloned() else {
+            return;
+        };
+        if !entry.exists(&self.project_root) {
+            self.recent_status = Some(format!("{} no longer exists; d removes it", entry.path));
+            return;
+        }
+        match entry.kind {
+            RecentKind::Training => {
+                let model_type = entry.model_type.clone().unwrap_or(ModelType::MelBandRoformer);
+                // The file exists, so the form loads it with `load_training_config`.
+                match ConfigForm::open(&self.project_path(&entry.path).to_string_lossy(), model_type) {
+                    Ok(form) => {
+                        self.training_status = Some(format!("Loaded {}; t starts it", entry.path));
+                        self.training_issues.clear();
+                        self.config_form = Some(form);
+                        self.config_form_status = None;
+                        self.push_screen(Screen::Training);
+                    }
+                    Err(e) => self.recent_status = Some(format!("{:#}", e)),
+                }
+            }
+            RecentKind::Inference => {
+                if let Some(model_type) = entry.model_type.clone() {
+                    self.selected_model = Some(model_type);
+                }
+                self.open_inference_setup();
+                let Some(form) = self.inference_form.as_mut() else {
+                    self.recent_status = Some("Select a model first".to_string());
+                    return;
+                };
+                form.config_path = entry.path.clone();
+                self.inference_status = Some(format!("Model config set to {}", entry.path));
+                self.push_screen(Screen::Inference);
+            }
+        }
+    }
+
+    fn remove_recent_config(&mut self) {
+        if self.selected_index >= self.config.recent_configs.len() {
+            return;
+        }
+        let removed = self.config.recent_configs.remove(self.selected_index);
+        self.selected_index = self.selected_index.min(self.config.recent_configs.len().saturating_sub(1));
+        self.recent_status = Some(match self.config_manager().save_config(&self.config) {
+            Ok(()) => format!("Removed {}", removed.path),
+            Err(e) => format!("Removed {} for this session only: {:#}", removed.path, e),
+        });
+    }
+
+    fn draw_recent(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new("Recent Configs")
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let items: Vec<ListItem> = if self.config.recent_configs.is_empty() {
+            vec![ListItem::new("No runs launched yet")]
+        } else {
+            self.config.recent_configs.iter()
+                .enumerate()
+                .map(|(i, entry)| {
+                    let item = ListItem::new(entry.line(&self.project_root));
+                    if i == self.selected_index {
+                        item.style(styles.highlight)
+                    } else if !entry.exists(&self.project_root) {
+                        item.style(styles.dimmed())
+                    } else {
+                        item
+                    }
+                })
+                .collect()
+        };
+        let len = items.len();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border).title("Most recent first"));
+        let footer = self.recent_status.as_deref().unwrap_or(self.key_help());
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(3),
+            ])
+            .split(area);
+        f.render_widget(title, chunks[0]);
+        f.render_widget(list, chunks[1]);
+        self.record_list(chunks[1], 0, len);
+        f.render_widget(
+            Paragraph::new(footer).block(Block::default().borders(Borders::ALL).border_style(styles.border)).wrap(Wrap { trim: false }),
+            chunks[2],
+        );
+    }
+
+    fn draw_downloads(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new("Download Pretrained")
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let weights_dir = self.weights_dir();
+        let items: Vec<ListItem> = self.download_entries()
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let item = ListItem::new(entry.line(&weights_dir));
+                if i == self.selected_index { item.style(styles.highlight) } else { item }
+            })
+            .collect();
+        let len = items.len();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border).title(format!("Into {}", weights_dir.display())));
+        let footer = self.download_status.as_deref().unwrap_or(self.key_help());
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(4),
+            ])
+            .split(area);
+        f.render_widget(title, chunks[0]);
+        f.render_widget(list, chunks[1]);
+        self.record_list(chunks[1], 0, len);
+        f.render_widget(
+            Paragraph::new(footer).block(Block::default().borders(Borders::ALL).border_style(styles.border)).wrap(Wrap { trim: false }),
+            chunks[2],
+        );
+    }
+
+    fn draw_devices(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new("Training Devices")
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let items: Vec<ListItem> = self.device_lines()
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let item = ListItem::new(line);
+                if i == self.selected_index { item.style(styles.highlight) } else { item }
+            })
+            .collect();
+        let len = items.len();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border).title("For the next run; s on the Config screen saves them"));
+        let footer = self.devices_status.as_deref().unwrap_or(self.key_help());
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(4),
+            ])
+            .split(area);
+        f.render_widget(title, chunks[0]);
+        f.render_widget(list, chunks[1]);
+        self.record_list(chunks[1], 0, len);
+        f.render_widget(
+            Paragraph::new(footer).block(Block::default().borders(Borders::ALL).border_style(styles.border)).wrap(Wrap { trim: false }),
+            chunks[2],
+        );
+    }
+
+    fn draw_hosts(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new("Run On")
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let items: Vec<ListItem> = self.host_lines()
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let item = ListItem::new(line);
+                if i == self.selected_index { item.style(styles.highlight) } else { item }
+            })
+            .collect();
+        let len = items.len();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border).title("remote_hosts in tui_config.yaml"));
+        let footer = self.hosts_status.as_deref().unwrap_or(self.key_help());
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(4),
+            ])
+            .split(area);
+        f.render_widget(title, chunks[0]);
+        f.render_widget(list, chunks[1]);
+        self.record_list(chunks[1], 0, len);
+        f.render_widget(
+            Paragraph::new(footer).block(Block::default().borders(Borders::ALL).border_style(styles.border)).wrap(Wrap { trim: false }),
+            chunks[2],
+        );
+    }
+
+    fn draw_environment(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new("Environment Check")
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let lines: Vec<Line> = self.environment_lines()
+            .into_iter()
+            .map(|line| {
+                let style = if line.starts_with('✗') {
+                    styles.error
+                } else if line.starts_with('!') {
+                    styles.warning
+                } else {
+                    styles.text
+                };
+                Line::styled(line, style)
+            })
+            .collect();
+        let footer = self.preflight_status.as_deref().unwrap_or(self.key_help());
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(4),
+            ])
+            .split(area);
+        f.render_widget(title, chunks[0]);
+        f.render_widget(
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).border_style(styles.border)).wrap(Wrap { trim: false }),
+            chunks[1],
+        );
+        f.render_widget(
+            Paragraph::new(footer).block(Block::default().borders(Borders::ALL).border_style(styles.border)).wrap(Wrap { trim: false }),
+            chunks[2],
+        );
+    }
+
+    fn draw_trash(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new("Recently Deleted")
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let items: Vec<ListItem> = if self.trash_items.is_empty() {
+            vec![ListItem::new("The trash is empty")]
+        } else {
+            self.trash_items.iter()
+                .enumerate()
+                .map(|(i, item)| {
+                    let list_item = ListItem::new(trash_item_text(item));
+                    if i == self.selected_index {
+                        list_item.style(styles.highlight)
+                    } else {
+                        list_item
+                    }
+                })
+                .collect()
+        };
+        let len = items.len();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border).title(format!("Trash ({} items)", self.trash_items.len())));
+
+        let undo: Vec<String> = self.undo.recent()
+            .take(3)
+            .map(|entry| format!("{}  {}", entry.at.format("%H:%M:%S"), entry.description))
+            .collect();
+        let undo = if undo.is_empty() {
+            "Nothing to undo this session".to_string()
+        } else {
+            format!("u undoes, newest first:\n{}", undo.join("\n"))
+        };
+        let footer = self.trash_status.as_deref().unwrap_or(self.key_help());
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(6),
+                ratatui::layout::Constraint::Length(4),
+            ])
+            .split(area);
+
+        f.render_widget(title, chunks[0]);
+        f.render_widget(list, chunks[1]);
+        self.record_list(chunks[1], 0, len);
+        f.render_widget(
+            Paragraph::new(undo).block(Block::default().borders(Borders::ALL).border_style(styles.border).title("This session")),
+            chunks[2],
+        );
+        f.render_widget(
+            Paragraph::new(footer)
+                .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+                .wrap(Wrap { trim: false }),
+            chunks[3],
+        );
+    }
+
+    /// The import preview: what would change, and paths that don't exist
+    /// on this machine.
+    pub fn import_lines(&self) -> Vec<String> {
+        let Some((bundle, plan)) = &self.import_preview else {
+            return vec![];
+        };
+        let mut lines = vec![
+            match plan.mode {
+                ImportMode::Merge => "Mode: merge (keeps settings the bundle doesn't have)".to_string(),
+                ImportMode::Replace => "Mode: replace (the bundle's settings replace these)".to_string(),
+            },
+            match bundle.exported_at {
+                Some(at) => format!("Exported {}", at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")),
+                None => "A plain tui_config.yaml".to_string(),
+            },
+            String::new(),
+        ];
+        if plan.changes.is_empty() {
+            lines.push("Nothing would change".to_string());
+        } else {
+            lines.push("Changes:".to_string());
+            lines.extend(plan.changes.iter().map(|change| format!("  {}", change)));
+        }
+        if plan.parser_rules.is_some() {
+            lines.push("  parser rules file: written from the bundle".to_string());
+        }
+        if !plan.missing_paths.is_empty() {
+            lines.push(String::new());
+            lines.push("Not found on this machine (imported as they are):".to_string());
+            lines.extend(plan.missing_paths.iter().map(|path| format!("  {}", path)));
+        }
+        lines
+    }
+
+    /// The duplicated run's fields, changed ones marked, and what is
+    /// missing on disk.
+    pub fn template_lines(&self) -> Vec<String> {
+        let Some(template) = &self.run_template else {
+            return vec![];
+        };
+        let mut lines = vec![format!("Derived from run {}", template.source())];
+        lines.extend(template.lines());
+        lines
+    }
+
+    fn draw_duplicate_run(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new("Duplicate Run")
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let lines = self.template_lines();
+        let (heading, fields) = lines.split_first().map_or(("", &[][..]), |(heading, fields)| (heading.as_str(), fields));
+        let items: Vec<ListItem> = fields.iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let item = ListItem::new(line.as_str());
+                if i == self.selected_index {
+                    item.style(styles.highlight)
+                } else {
+                    item
+                }
+            })
+            .collect();
+        let len = items.len();
+        let list = List::new(items).block(Block::default().borders(Borders::ALL).border_style(styles.border).title(heading));
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Length(4),
+            ])
+            .split(area);
+
+        f.render_widget(title, chunks[0]);
+        f.render_widget(list, chunks[1]);
+        self.record_list(chunks[1], 0, len);
+        match self.focused_input() {
+            Some(input) => input.render(f, chunks[2], self.prompt_title().unwrap_or_default()),
+            None => f.render_widget(
+                Paragraph::new("* marks fields changed from the original run").block(Block::default().borders(Borders::ALL).border_style(styles.border)),
+                chunks[2],
+            ),
+        }
+        let footer = self.template_status.as_deref().unwrap_or(self.key_help());
+        f.render_widget(
+            Paragraph::new(footer)
+                .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+                .wrap(Wrap { trim: false }),
+            chunks[3],
+        );
+    }
+
+    fn draw_import_bundle(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new("Import Settings")
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let body = Paragraph::new(self.import_lines().join("\n"))
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .wrap(Wrap { trim: false });
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(3),
+            ])
+            .split(area);
+
+        f.render_widget(title, chunks[0]);
+        f.render_widget(body, chunks[1]);
+        f.render_widget(
+            Paragraph::new(self.key_help()).block(Block::default().borders(Borders::ALL).border_style(styles.border)),
+            chunks[2],
+        );
+    }
+
+    fn draw_tracks(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let Some(selection) = &self.track_selection else {
+            return;
+        };
+        let title = Paragraph::new(format!("Input files: {}", selection.folder().display()))
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let items: Vec<ListItem> = if selection.tracks().is_empty() {
+            vec![ListItem::new("No input files in this folder")]
+        } else {
+            selection.tracks().iter()
+                .enumerate()
+                .map(|(i, track)| {
+                    let length = track.duration_secs.map_or("?".to_string(), format_clock);
+                    let item = ListItem::new(format!(
+                        "[{}] {}  {}",
+                        if track.selected { "x" } else { " " },
+                        track.name(),
+                        length
+                    ));
+                    if i == self.selected_index {
+                        item.style(styles.highlight)
+                    } else {
+                        item
+                    }
+                })
+                .collect()
+        };
+        let len = items.len();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border).title(selection.confirmation()));
+
+        let patterns = if selection.patterns().is_empty() {
+            "(none)".to_string()
+        } else {
+            selection.patterns().join("  ")
+        };
+        let footer = format!(
+            "Excluded by pattern: {}\n{}",
+            patterns,
+            self.inference_status.as_deref().unwrap_or(self.key_help())
+        );
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(4),
+            ])
+            .split(area);
+
+        f.render_widget(title, chunks[0]);
+        f.render_widget(list, chunks[1]);
+        self.record_list(chunks[1], 0, len);
+        f.render_widget(
+            Paragraph::new(footer)
+                .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+                .wrap(Wrap { trim: false }),
+            chunks[2],
+        );
+
+        if let Some(pattern) = &self.exclude_prompt {
+            let popup = ratatui::layout::Rect {
+                x: area.width / 4,
+                y: area.height / 2 - 2.min(area.height / 2),
+                width: area.width / 2,
+                height: 3.min(area.height),
+            };
+            f.render_widget(ratatui::widgets::Clear, popup);
+            pattern.render(f, popup, self.prompt_title().unwrap_or_default());
+        }
+    }
+
+    fn draw_validation(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new(if self.validation.is_some() { "Validation (running)" } else { "Validation" })
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Length(6),
+                ratatui::layout::Constraint::Length(if self.validation.is_some() { 3 } else { 0 }),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(4),
+            ])
+            .split(area);
+        f.render_widget(title, chunks[0]);
+
+        let Some(form) = &self.validation_form else {
+            f.render_widget(Paragraph::new("Select a model first (1. Model Selection)"), chunks[1]);
+            return;
+        };
+        f.render_widget(
+            Paragraph::new(validation_lines(form).join("\n")).block(Block::default().borders(Borders::ALL).border_style(styles.border).title("Settings")),
+            chunks[1],
+        );
+        if self.validation.is_some() {
+            let (ratio, label) = match self.validation_progress {
+                Some(progress) => (progress.ratio(), format!("{}/{} files", progress.done, progress.total)),
+                None => (0.0, "Starting valid.py...".to_string()),
+            };
+            f.render_widget(
+                ratatui::widgets::Gauge::default()
+                    .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+                    .gauge_style(styles.progress)
+                    .ratio(ratio)
+                    .label(label),
+                chunks[2],
+            );
+        }
+        self.validation_table.render(f, chunks[3], &validation_summary(&self.validation_results), &styles);
+
+        match self.focused_input() {
+            Some(input) => input.render(f, chunks[4], self.prompt_title().unwrap_or_default()),
+            None => f.render_widget(
+                Paragraph::new(self.validation_status.as_deref().unwrap_or(self.key_help()))
+                    .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+                    .wrap(Wrap { trim: false }),
+                chunks[4],
+            ),
+        }
+    }
+
+    /// Starts a fresh form when the selected model changed since last
+    /// time, filled from the inference settings or the model's binding.
+    fn open_validation_setup(&mut self) {
+        let Some(model_type) = self.selected_model.clone().or_else(|| self.config.selected_model.clone()) else {
+            self.validation_form = None;
+            return;
+        };
+        if self.validation_form.as_ref().is_some_and(|form| form.model_type == model_type) {
+            return;
+        }
+        let mut inference = match &self.inference_form {
+            Some(form) if form.model_type == model_type => form.clone(),
+            _ => InferenceConfig::new(model_type.clone()),
+        };
+        if let Some(binding) = &self.config.model_binding {
+            binding.fill_inference(&mut inference);
+        }
+        let valid_path = self.config_form.as_ref()
+            .and_then(|form| form.config.valid_path.clone())
+            .unwrap_or_default();
+        self.validation_form = Some(ValidationConfig {
+            model_type,
+            config_path: inference.config_path,
+            start_checkpoint: inference.start_checkpoint,
+            valid_path,
+        });
+        self.validation_status = None;
+    }
+
+    /// Runs valid.py in the background; Esc on the screen stops it.
+    fn run_validation(&mut self) {
+        if self.validation.is_some() || self.is_waiting(JobKind::Validate) {
+            self.validation_status = Some("Validation is already running".to_string());
+            return;
+        }
+        let Some(form) = &self.validation_form else {
+            return;
+        };
+        let missing: Vec<&str> = [
+            ("config", &form.config_path),
+            ("checkpoint", &form.start_checkpoint),
+            ("validation folder", &form.valid_path),
+        ]
+        .into_iter()
+        .filter(|(_, value)| value.trim().is_empty())
+        .map(|(name, _)| name)
+        .collect();
+        if !missing.is_empty() {
+            self.validation_status = Some(format!("Set the {} first", missing.join(", ")));
+            return;
+        }
+        let issues = config_check::validate_validation(&form.in_root(&self.project_root));
+        if has_errors(&issues) {
+            self.validation_status = Some(issues.iter().map(ConfigIssue::line).collect::<Vec<_>>().join("; "));
+            return;
+        }
+        self.submit_run(GatedRun::Validation(form.clone()));
+    }
+
+    fn launch_validation(&mut self, id: JobId, config: ValidationConfig, devices: &[usize]) {
+        let mut manager = ValidationManager::new();
+        manager.set_scripts(&self.scripts);
+        manager.set_runner(self.runner_on(devices));
+        let log = manager.log();
+        let (progress_tx, progress_rx) = mpsc::unbounded_channel();
+        let task = self.spawn_task("Validation", OnCancel::Discard, move |ctx| {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|e| anyhow::anyhow!("Failed to start the validation runtime: {}", e))?;
+            runtime.block_on(async {
+                let finished = tokio::select! {
+                    result = manager.run_validation(&config, progress_tx) => Some(result),
+                    _ = async {
+                        while !ctx.is_cancelled() {
+                            tokio::time::sleep(Duration::from_millis(200)).await;
+                        }
+                    } => None,
+                };
+                match finished {
+                    Some(result) => result,
+                    None => {
+                        manager.stop_validation().await?;
+                        Ok(vec![])
+                    }
+                }
+            })
+        });
+        self.supervisor.attach(id, task.monitor(), log);
+        self.validation_job = Some(id);
+        self.validation = Some(task);
+        self.validation_rx = Some(progress_rx);
+        self.validation_progress = None;
+        self.validation_results.clear();
+        self.validation_table.set_rows(vec![]);
+        self.validation_status = None;
+    }
+
+    fn receive_validation_progress(&mut self) {
+        if let Some(rx) = self.validation_rx.as_mut() {
+            while let Ok(progress) = rx.try_recv() {
+                if let Some(id) = self.validation_job {
+                    self.supervisor.set_progress(id, format!("{}/{} files", progress.done, progress.total));
+                }
+                self.validation_progress = Some(progress);
+            }
+        }
+    }
+
+    fn draw_ensemble(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new(if self.ensemble.is_some() { "Ensemble (running)" } else { "Ensemble" })
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let items: Vec<ListItem> = ensemble_file_lines(&self.ensemble_form)
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let item = ListItem::new(line);
+                if i == self.selected_index { item.style(styles.highlight) } else { item }
+            })
+            .collect();
+        let len = items.len();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border).title("Stem files"));
+        let log_height = if self.ensemble.is_none() && self.ensemble_log.lock().unwrap().is_empty() { 0 } else { LOG_PANE_HEIGHT };
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Length(5),
+                ratatui::layout::Constraint::Min(4),
+                ratatui::layout::Constraint::Length(log_height),
+                ratatui::layout::Constraint::Length(4),
+            ])
+            .split(area);
+        f.render_widget(title, chunks[0]);
+        f.render_widget(
+            Paragraph::new(ensemble_lines(&self.ensemble_form).join("\n")).block(Block::default().borders(Borders::ALL).border_style(styles.border).title("Settings")),
+            chunks[1],
+        );
+        f.render_widget(list, chunks[2]);
+        self.record_list(chunks[2], 0, len);
+        if log_height > 0 {
+            self.ensemble_log_pane.render(f, chunks[3], "Ensemble output", &self.ensemble_log.lock().unwrap(), &styles);
+        }
+
+        match self.focused_input() {
+            Some(input) => input.render(f, chunks[4], self.prompt_title().unwrap_or_default()),
+            None => f.render_widget(
+                Paragraph::new(self.ensemble_status.as_deref().unwrap_or(self.key_help()))
+                    .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+                    .wrap(Wrap { trim: false }),
+                chunks[4],
+            ),
+        }
+    }
+
+    fn handle_ensemble_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char('a') => self.ensemble_file_prompt = Some(TextInput::single_line()),
+            KeyCode::Char('d') if self.selected_index < self.ensemble_form.files.len() => {
+                let form = &mut self.ensemble_form;
+                let file = form.files.remove(self.selected_index);
+                if self.selected_index < form.weights.len() {
+                    form.weights.remove(self.selected_index);
+                }
+                self.selected_index = self.selected_index.min(form.files.len().saturating_sub(1));
+                self.ensemble_status = Some(format!("Dropped {}", file));
+            }
+            KeyCode::Char('w') => {
+                let weights = self.ensemble_form.weights.iter().map(f64::to_string).collect::<Vec<_>>().join(", ");
+                self.ensemble_weights_prompt = Some(TextInput::single_line().with_text(&weights));
+            }
+            KeyCode::Char('t') => {
+                self.ensemble_form.ensemble_type = ensemble::next_type(&self.ensemble_form.ensemble_type).to_string();
+            }
+            KeyCode::Char('o') => {
+                self.ensemble_output_prompt = Some(TextInput::single_line().with_text(&self.ensemble_form.output_path));
+            }
+            KeyCode::Char('r') => self.run_ensemble(),
+            _ => {}
+        }
+    }
+
+    /// Adds `file` with a weight of 1.
+    fn add_ensemble_file(&mut self, file: &str) {
+        if file.is_empty() {
+            return;
+        }
+        if !self.project_path(file).is_file() {
+            self.ensemble_status = Some(format!("Input not found: {}", file));
+            return;
+        }
+        self.ensemble_form.files.push(file.to_string());
+        self.ensemble_form.weights.push(1.0);
+        self.selected_index = self.ensemble_form.files.len() - 1;
+        self.ensemble_status = None;
+    }
+
+    fn set_ensemble_weights(&mut self, text: &str) {
+        let files = self.ensemble_form.files.len();
+        self.ensemble_status = Some(match ensemble::parse_weights(text) {
+            Ok(weights) if weights.len() != files => {
+                format!("{} weights for {} files; give one weight per file", weights.len(), files)
+            }
+            Ok(weights) => {
+                self.ensemble_form.weights = weights;
+                "Weights set".to_string()
+            }
+            Err(e) => format!("{:#}", e),
+        });
+    }
+
+    /// Runs ensemble.py in the background; Esc on the screen stops it.
+    fn run_ensemble(&mut self) {
+        if self.ensemble.is_some() {
+            self.ensemble_status = Some("The ensemble is already running".to_string());
+            return;
+        }
+        if let Err(e) = ensemble::validate(&self.ensemble_form) {
+            self.ensemble_status = Some(format!("{:#}", e));
+            return;
+        }
+        let config = self.ensemble_form.clone();
+        let label = config.output_path.clone();
+        let mut manager = EnsembleManager::new();
+        manager.set_runner(self.runner());
+        let log = manager.log();
+        let task = self.spawn_task("Ensemble", OnCancel::Discard, move |ctx| {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|e| anyhow::anyhow!("Failed to start the ensemble runtime: {}", e))?;
+            runtime.block_on(async {
+                let finished = tokio::select! {
+                    result = manager.run_ensemble(&config) => Some(result),
+                    _ = async {
+                        while !ctx.is_cancelled() {
+                            tokio::time::sleep(Duration::from_millis(200)).await;
+                        }
+                    } => None,
+                };
+                match finished {
+                    Some(result) => result,
+                    None => {
+                        manager.stop_ensemble().await?;
+                        Ok(String::new())
+                    }
+                }
+            })
+        });
+        self.ensemble_job = Some(self.begin_job(JobKind::Ensemble, &label, task.monitor(), log.clone()));
+        self.ensemble = Some(task);
+        self.ensemble_log = log;
+        self.ensemble_log_pane = LogPane::new();
+        self.ensemble_status = Some(format!("Combining {} files into {}…", self.ensemble_form.files.len(), label));
+    }
+
+    fn draw_preview(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new("Stem Preview")
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let text = self.preview_text();
+
+        let body = Paragraph::new(text)
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .wrap(Wrap { trim: false });
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(10),
+            ])
+            .split(area);
+
+        f.render_widget(title, chunks[0]);
+        f.render_widget(body, chunks[1]);
+    }
+
+    pub fn preview_text(&self) -> String {
+        match (&self.preview, &self.preview_error) {
+            (_, Some(error)) => format!("Preview unavailable:\n{}", error),
+            (Some(player), None) => {
+                let controls = player.controls();
+                let side = controls.side();
+                let duration = player.duration_secs()
+                    .map(format_clock)
+                    .unwrap_or_else(|| "?".to_string());
+                format!(
+                    "{}  {}\n\
+                     {}  {}\n\
+                     \n\
+                     Playing: {}{}\n\
+                     Position: {} / {}\n\
+                     Stem gain: {:+.1} dB\n\
+                     \n\
+                     {}",
+                    player.labels[0],
+                    player.mixture_path.display(),
+                    player.labels[1],
+                    player.stem_path.display(),
+                    player.side_label(side),
+                    if controls.is_paused() { " (paused)" } else { "" },
+                    format_clock(player.position_secs()),
+                    duration,
+                    self.preview_gain_db(&player.stem_name),
+                    self.key_help(),
+                )
+            }
+            (None, None) => "No stem loaded".to_string(),
+        }
+    }
+
+    fn draw_settings(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new("Settings")
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let lines = self.settings_lines();
+        let body = Paragraph::new(lines.join("\n"))
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .wrap(Wrap { trim: false });
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(10),
+            ])
+            .split(area);
+
+        f.render_widget(title, chunks[0]);
+        f.render_widget(body, chunks[1]);
+    }
+
+    pub fn settings_lines(&self) -> Vec<String> {
+        let scratch = Scratch::from_config(&self.config, &self.project_root);
+        let usage = match &self.scratch_usage {
+            Some(Ok(usage)) => format!(
+                "{} folder(s), {} orphaned, {:.1} MB",
+                usage.dirs,
+                usage.orphaned,
+                usage.bytes as f64 / (1024.0 * 1024.0)
+            ),
+            Some(Err(e)) => format!("unavailable ({})", e),
+            None => "not measured".to_string(),
+        };
+        let mut lines = vec![
+            format!("Scratch folder: {}", scratch.root().display()),
+            format!("Scratch usage:  {}", usage),
+            String::new(),
+            format!("Log parser rules: {} active", self.parser_rules.rules().len()),
+            format!("Scripts:        {}, {}, {} (names to look for in {})", self.scripts.train, self.scripts.inference, self.scripts.validation, settings_path().display()),
+            format!("Scripts folder: {}", self.config.scripts_dir.as_deref().unwrap_or("project root")),
+            format!("Python:         {}", python_line(&self.config)),
+            format!("Trash folder:   {}", Trash::from_config(&self.config, &self.project_root).root().display()),
+            format!("Theme:          {:?}", self.config.theme),
+            format!("Dry run:        {}", if self.config.dry_run { "on (runs only show their command)" } else { "off" }),
+            format!("Mouse:          {}", if self.config.mouse { "on (Shift+drag selects text in most terminals)" } else { "off" }),
+            format!(
+                "Attention:      bell {}, window title {} (attention in {})",
+                if self.config.attention.bell { "on" } else { "off" },
+                if self.config.attention.title { "on" } else { "off" },
+                DEFAULT_CONFIG_FILE
+            ),
+            format!("Notify command: {}", self.config.notify_command.as_deref().unwrap_or("none (bell and banner only)")),
+            String::new(),
+            self.key_help().to_string(),
+        ];
+        if let Some(error) = &self.parser_error {
+            lines.push(String::new());
+            lines.push(format!("Custom parser rules not loaded, using built-ins:\n{}", error));
+        }
+        if let Some(status) = &self.scratch_status {
+            lines.push(String::new());
+            lines.push(status.clone());
+        }
+        if let Some(status) = &self.config_status {
+            lines.push(String::new());
+            lines.push(status.clone());
+        }
+        if let Some(status) = &self.trash_status {
+            lines.push(String::new());
+            lines.push(status.clone());
+        }
+        lines
+    }
+
+    fn draw_parser_test(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new("Test Log Parser")
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let lines = self.parser_matches();
+        let results = Paragraph::new(lines.join("\n"))
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border).title("Matches"))
+            .wrap(Wrap { trim: false });
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Percentage(50),
+                ratatui::layout::Constraint::Min(5),
+            ])
+            .split(area);
+
+        f.render_widget(title, chunks[0]);
+        self.parser_input.render(f, chunks[1], self.prompt_title().unwrap_or_default());
+        f.render_widget(results, chunks[2]);
+    }
+
+    /// What the parser rules make of each line typed on the parser test
+    /// screen.
+    pub fn parser_matches(&self) -> Vec<String> {
+        let mut lines = vec![];
+        for report in self.parser_rules.explain(self.parser_input.text()) {
+            if report.matches.is_empty() {
+                lines.push(format!("{:>4}  no match", report.line_number));
+                continue;
+            }
+            for rule_match in report.matches {
+                let values: Vec<String> = rule_match.values.iter()
+                    .map(|(field, value)| format!("{}={}", field.key(), value))
+                    .collect();
+                lines.push(format!("{:>4}  {}: {}", report.line_number, rule_match.rule, values.join(", ")));
+            }
+        }
+        if let Some(error) = &self.parser_error {
+            lines.push(String::new());
+            lines.push(format!("Custom rules not loaded: {}", error));
+        }
+        lines
+    }
+
+    fn draw_history(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new("Run History")
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(3),
+            ])
+            .split(area);
+
+        f.render_widget(title, chunks[0]);
+        if self.history_filter_active {
+            self.history_filter.render(f, chunks[1], self.prompt_title().unwrap_or_default());
+        } else {
+            let filter = Paragraph::new(self.history_filter.text().to_string())
+                .block(Block::default().borders(Borders::ALL).border_style(styles.border).title("Filter (/)"));
+            f.render_widget(filter, chunks[1]);
+        }
+
+        let total = self.history.as_ref().map_or(0, |history| history.entries().len());
+        let mut title = format!("Runs ({} of {})", self.history_table.len(), total);
+        if let Some(month) = self.month_cost() {
+            title.push_str(&format!(", this month {}", month));
+        }
+        if let Some(stale) = self.stale_summary() {
+            title.push_str(&format!(", {}", stale));
+        }
+        self.history_table.render(f, chunks[2], &title, &styles);
+
+        let footer = self.history_status.as_deref().unwrap_or(self.key_help());
+        f.render_widget(
+            Paragraph::new(footer)
+                .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+                .wrap(Wrap { trim: false }),
+            chunks[3],
+        );
+
+        if let Some(editor) = &self.notes_editor {
+            let popup = ratatui::layout::Rect {
+                x: area.width / 8,
+                y: area.height / 4,
+                width: area.width * 3 / 4,
+                height: (area.height / 2).max(5),
+            };
+            f.render_widget(ratatui::widgets::Clear, popup);
+            editor.render(f, popup, self.prompt_title().unwrap_or_default());
+        }
+
+        if let Some(input) = self.import_root.as_ref().or(self.label_editor.as_ref()) {
+            let popup = ratatui::layout::Rect {
+                x: area.width / 8,
+                y: area.height / 2 - 2.min(area.height / 2),
+                width: area.width * 3 / 4,
+                height: 3.min(area.height),
+            };
+            f.render_widget(ratatui::widgets::Clear, popup);
+            input.render(f, popup, self.prompt_title().unwrap_or_default());
+        }
+    }
+
+    fn draw_run_detail(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let Some(detail) = &self.run_detail else {
+            return;
+        };
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Length(detail.lines.len() as u16 + 2),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(3),
+            ])
+            .split(area);
+        f.render_widget(
+            Paragraph::new(format!("Run: {}", detail.entry.name))
+                .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+                .style(styles.title),
+            chunks[0],
+        );
+        f.render_widget(
+            Paragraph::new(detail.lines.join("\n"))
+                .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+                .wrap(Wrap { trim: false }),
+            chunks[1],
+        );
+        let progress = detail.metrics.progress();
+        if progress.is_empty() {
+            f.render_widget(
+                Paragraph::new("No metrics to chart").block(Block::default().borders(Borders::ALL).border_style(styles.border)),
+                chunks[2],
+            );
+        } else {
+            let charts = ratatui::layout::Layout::default()
+                .direction(ratatui::layout::Direction::Horizontal)
+                .constraints([ratatui::layout::Constraint::Percentage(50), ratatui::layout::Constraint::Percentage(50)])
+                .split(chunks[2]);
+            draw_loss_chart(f, charts[0], progress, &styles);
+            let sdr = training_chart::sdr_points(progress);
+            draw_epoch_chart(f, charts[1], "SDR", &[("mean SDR", &sdr, styles.highlight)], 2, &styles);
+        }
+        match self.pull_progress() {
+            Some(progress) => f.render_widget(
+                ratatui::widgets::Gauge::default()
+                    .block(Block::default().borders(Borders::ALL).border_style(styles.border).title("Pulling results"))
+                    .gauge_style(styles.highlight)
+                    .ratio(progress.ratio())
+                    .label(progress.label()),
+                chunks[3],
+            ),
+            None => f.render_widget(
+                Paragraph::new(self.pull_status.as_deref().unwrap_or(self.key_help()))
+                    .block(Block::default().borders(Borders::ALL).border_style(styles.border)),
+                chunks[3],
+            ),
+        }
+        if let Some(folder) = &self.pull_prompt {
+            let popup = ratatui::layout::Rect {
+                x: area.width / 8,
+                y: area.height / 2 - 2.min(area.height / 2),
+                width: area.width * 3 / 4,
+                height: 3.min(area.height),
+            };
+            f.render_widget(ratatui::widgets::Clear, popup);
+            folder.render(f, popup, self.prompt_title().unwrap_or_default());
+        }
+    }
+
+    fn draw_bake_off(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let Some((_, report)) = &self.bake_off else {
+            return;
+        };
+        let title = Paragraph::new(format!("Bake-off: {}", report.name))
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let lines = self.bake_off_models(report);
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Length(lines.len() as u16 + 2),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(3),
+            ])
+            .split(area);
+
+        f.render_widget(title, chunks[0]);
+        f.render_widget(
+            Paragraph::new(lines.join("\n")).block(Block::default().borders(Borders::ALL).border_style(styles.border).title("Models")),
+            chunks[1],
+        );
+        self.bake_off_table.render(f, chunks[2], "Stems", &styles);
+        f.render_widget(
+            Paragraph::new(self.key_help())
+                .block(Block::default().borders(Borders::ALL).border_style(styles.border)),
+            chunks[3],
+        );
+    }
+
+    /// A line per bake-off candidate, marked with the side it plays on.
+    pub fn bake_off_models(&self, report: &BakeOffReport) -> Vec<String> {
+        report.outcomes.iter()
+            .enumerate()
+            .map(|(i, outcome)| {
+                let side = match (i == self.bake_off_sides.0, i == self.bake_off_sides.1) {
+                    (true, true) => "AB",
+                    (true, false) => "A ",
+                    (false, true) => " B",
+                    (false, false) => "  ",
+                };
+                let seconds = outcome.seconds.map(format_clock).unwrap_or_else(|| "-".to_string());
+                let sdr = outcome.sdr.map(|sdr| format!("SDR {:.2} dB", sdr)).unwrap_or_default();
+                let status = match (&outcome.error, outcome.success) {
+                    (Some(error), _) => error.clone(),
+                    (None, false) => "failed".to_string(),
+                    (None, true) => String::new(),
+                };
+                format!("{} {}. {:<40} {:>7}  {:<14} {}", side, i + 1, outcome.candidate.key(), seconds, sdr, status)
+            })
+            .collect()
+    }
+
+    /// Loads the config, first moving aside the state files that can't be
+    /// loaded so that nothing later trips over them.
+    fn check_state_files(&mut self) {
+        let now = chrono::Local::now();
+        let config = self.config_manager();
+        self.quarantined.extend(integrity::check(
+            "App config",
+            &self.project_path(DEFAULT_CONFIG_FILE),
+            "settings, presets and model bindings are back to defaults",
+            now,
+            |_| config.load_config().map(drop),
+        ));
+        self.load_config();
+        self.quarantined.extend(integrity::check(
+            "Run history",
+            &self.history_path(),
+            "earlier runs are missing from the history until imported again",
+            now,
+            |path| RunHistory::load(path).map(drop),
+        ));
+        if let Some(error) = self.quarantined.iter().find_map(Quarantined::unrecoverable) {
+            self.error = Some(ErrorState::fatal("Startup stopped", &error));
+        }
+    }
+
+    fn load_config(&mut self) {
+        match self.config_manager().load_config() {
+            Ok(config) => self.config = config,
+            Err(e) => self.config_status = Some(format!("App config not loaded, using defaults: {:#}", e)),
+        }
+        let hidden = self.hidden_screen_keys();
+        if !hidden.is_empty() {
+            self.warn(format!("Keybindings take over screen keys: {}", hidden.join("; ")));
+        }
+    }
+
+    /// E.g. "k (up) on Jobs, Inference": screen keys no longer reachable
+    /// because a binding takes them first.
+    fn hidden_screen_keys(&self) -> Vec<String> {
+        let mut hidden: Vec<(KeySpec, Action, Vec<&str>)> = vec![];
+        for screen in &Screen::ALL {
+            for (spec, action) in self.config.keybindings.shadowed(screen_key_help(screen)) {
+                match hidden.iter_mut().find(|(taken, by, _)| *taken == spec && *by == action) {
+                    Some((_, _, screens)) => screens.push(plain::screen_title(screen)),
+                    None => hidden.push((spec, action, vec![plain::screen_title(screen)])),
+                }
+            }
+        }
+        hidden.into_iter()
+            .map(|(spec, action, screens)| format!("{} ({}) on {}", spec, action.name(), screens.join(", ")))
+            .collect()
+    }
+
+    /// Starts on the saved model unless `--model` chose one.
+    fn use_saved_model(&mut self) {
+        if self.selected_model.is_none() {
+            self.selected_model = self.config.selected_model.clone();
+        }
+    }
+
+    /// Saves the app config, keeping the previous file in the trash so `u`
+    /// can bring it back. Returns whether there was one to keep.
+    fn save_config(&mut self, what: &str) -> anyhow::Result<bool> {
+        let kept = Trash::from_config(&self.config, &self.project_root).keep_copy(&self.project_path(DEFAULT_CONFIG_FILE), "config overwrite")?;
+        self.config_manager().save_config(&self.config)?;
+        if let Some(item) = kept {
+            self.undo.record(&format!("{} in {}", what, DEFAULT_CONFIG_FILE), UndoAction::RestoreTrashed { trash_id: item.id });
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Takes back the newest destructive operation of the session.
+    fn undo_last(&mut self) {
+        let Some(entry) = self.undo.pop() else {
+            self.notice = Some("Nothing to undo".to_string());
+            return;
+        };
+        let undone = match entry.action {
+            UndoAction::RestoreTrashed { trash_id } => self.restore_trashed(&trash_id),
+            UndoAction::Requeue(removed) => {
+                self.supervisor.requeue(removed);
+                Ok(())
+            }
+        };
+        self.notice = Some(match undone {
+            Ok(()) => format!("Undone: {}", entry.description),
+            Err(e) => format!("Could not undo {}: {:#}", entry.description, e),
+        });
+        if self.screen == Screen::Trash {
+            self.refresh_trash();
+        }
+    }
+
+    /// Restores a trash item, reloading the app config if that is what
+    /// came back.
+    fn restore_trashed(&mut self, id: &str) -> anyhow::Result<()> {
+        let item = Trash::from_config(&self.config, &self.project_root).restore(id)?;
+        self.undo.forget_trashed(id);
+        if item.original == self.project_path(DEFAULT_CONFIG_FILE) {
+            self.load_config();
+        }
+        if let Some(rules) = &self.config.parser_rules_path
+            && self.project_path(rules) == item.original
+        {
+            self.load_parser_rules();
+        }
+        Ok(())
+    }
+
+    fn export_bundle(&mut self) {
+        let Some(prompt) = self.export_prompt.take() else {
+            return;
+        };
+        let path = self.project_path(prompt.text().trim());
+        let exported = ConfigBundle::export(&self.config, &self.project_root).and_then(|bundle| bundle.save(&path));
+        self.config_status = Some(match exported {
+            Ok(()) => format!("Exported settings to {} (secrets as ${{VAR}} placeholders)", path.display()),
+            Err(e) => {
+                self.report_error("Settings not exported", &e);
+                format!("Export failed: {:#}", e)
+            }
+        });
+    }
+
+    fn preview_import(&mut self) {
+        let Some(prompt) = self.import_prompt.take() else {
+            return;
+        };
+        let planned = ConfigBundle::load(&self.project_path(prompt.text().trim())).and_then(|bundle| {
+            let plan = ImportPlan::new(&self.config, &bundle, ImportMode::Merge, &self.project_root)?;
+            Ok((bundle, plan))
+        });
+        match planned {
+            Ok(preview) => {
+                self.import_preview = Some(preview);
+                self.push_screen(Screen::ImportBundle);
+            }
+            Err(e) => self.config_status = Some(format!("Import failed: {:#}", e)),
+        }
+    }
+
+    fn switch_import_mode(&mut self) {
+        let Some((bundle, plan)) = &self.import_preview else {
+            return;
+        };
+        let mode = match plan.mode {
+            ImportMode::Merge => ImportMode::Replace,
+            ImportMode::Replace => ImportMode::Merge,
+        };
+        match ImportPlan::new(&self.config, bundle, mode, &self.project_root) {
+            Ok(plan) => self.import_preview = Some((bundle.clone(), plan)),
+            Err(e) => self.config_status = Some(format!("{:#}", e)),
+        }
+    }
+
+    fn apply_import(&mut self) {
+        let Some((_, plan)) = self.import_preview.take() else {
+            return;
+        };
+        self.config = plan.result.clone();
+        let mut status = match self.save_config("Import settings") {
+            Ok(undoable) => format!("Imported {} setting(s){}", plan.changes.len(), undo_hint(undoable)),
+            Err(e) => format!("Imported for this session only: {:#}", e),
+        };
+        match plan.write_parser_rules(&Trash::from_config(&self.config, &self.project_root), &self.project_root) {
+            Ok(Some(kept)) => self.undo.record("Import parser rules", UndoAction::RestoreTrashed { trash_id: kept.id }),
+            Ok(None) => {}
+            Err(e) => status.push_str(&format!("; parser rules not written: {:#}", e)),
+        }
+        if !plan.missing_paths.is_empty() {
+            status.push_str(&format!("; {} path(s) to fix up", plan.missing_paths.len()));
+        }
+        self.load_parser_rules();
+        self.config_status = Some(status);
+        self.import_preview = None;
+        self.back_to(Screen::Settings);
+    }
+
+    fn refresh_trash(&mut self) {
+        match Trash::from_config(&self.config, &self.project_root).items() {
+            Ok(items) => self.trash_items = items,
+            Err(e) => {
+                self.trash_items = vec![];
+                self.trash_status = Some(format!("{:#}", e));
+            }
+        }
+        self.selected_index = self.selected_index.min(self.trash_items.len().saturating_sub(1));
+    }
+
+    fn confirmed(&mut self, action: ConfirmAction) {
+        let trash = Trash::from_config(&self.config, &self.project_root);
+        self.trash_status = Some(match action {
+            ConfirmAction::DiscardModelConfig => {
+                self.model_config = None;
+                self.model_config_status = None;
+                self.pop_screen();
+                return;
+            }
+            ConfirmAction::RunWithoutAudio => {
+                self.preview_inference_launch();
+                return;
+            }
+            ConfirmAction::Resume(path) => {
+                self.resume_from(&path);
+                return;
+            }
+            ConfirmAction::DownloadUnverified(entry) => {
+                self.download_entry(entry);
+                return;
+            }
+            ConfirmAction::DeleteTrashed(id) => match trash.delete(&id) {
+                Ok(()) => {
+                    self.undo.forget_trashed(&id);
+                    "Deleted for good".to_string()
+                }
+                Err(e) => format!("{:#}", e),
+            },
+            ConfirmAction::EmptyTrash => match trash.empty() {
+                Ok(report) => {
+                    for item in &self.trash_items {
+                        self.undo.forget_trashed(&item.id);
+                    }
+                    format!("Emptied the trash, {} item(s)", report.removed)
+                }
+                Err(e) => format!("{:#}", e),
+            },
+        });
+        self.refresh_trash();
+    }
+
+    /// What Model Selection lists: every model, or the ones matching the
+    /// search while it is open.
+    pub fn listed_models(&self) -> Vec<ModelType> {
+        ModelType::filter(self.model_filter.as_ref().map_or("", |filter| filter.text()))
+    }
+
+    /// The binding, if it is for the model highlighted on Model Selection.
+    fn highlighted_binding(&self) -> Option<&ModelBinding> {
+        let highlighted = self.listed_models().into_iter().nth(self.selected_index)?;
+        self.config.model_binding.as_ref().filter(|binding| binding.model_type == highlighted)
+    }
+
+    /// Switches to the next theme, redrawn right away and saved.
+    fn set_python_executable(&mut self) {
+        let Some(prompt) = self.python_prompt.take() else {
+            return;
+        };
+        let python = prompt.text().trim().to_string();
+        self.config.python_executable = (!python.is_empty()).then_some(python);
+        let found = self.runner().python();
+        self.config_status = Some(match (self.save_config("Python interpreter"), found) {
+            (Ok(undoable), Ok(python)) => format!("Python interpreter {}, saved{}", python, undo_hint(undoable)),
+            (Ok(undoable), Err(e)) => format!("Saved{}, but runs won't start: {:#}", undo_hint(undoable), e),
+            (Err(e), _) => format!("Python interpreter not saved: {:#}", e),
+        });
+    }
+
+    /// Says `what` was saved, or warns that it holds for this session only.
+    fn announce_saved(&mut self, what: &str) {
+        match self.save_config(what) {
+            Ok(undoable) => self.notice = Some(format!("{}, saved{}", what, undo_hint(undoable))),
+            Err(e) => self.warn(format!("{} for this session only: {:#}", what, e)),
+        }
+    }
+
+    fn cycle_theme(&mut self) {
+        self.config.theme = self.config.theme.next();
+        let what = format!("Theme {:?}", self.config.theme);
+        self.announce_saved(&what);
+    }
+
+    fn toggle_mouse(&mut self) {
+        self.config.mouse = !self.config.mouse;
+        let what = format!("Mouse {}", if self.config.mouse { "on" } else { "off" });
+        self.announce_saved(&what);
+    }
+
+    fn toggle_dry_run(&mut self) {
+        self.config.dry_run = !self.config.dry_run;
+        let what = format!("Dry run {}", if self.config.dry_run { "on" } else { "off" });
+        self.announce_saved(&what);
+    }
+
+    /// Keeps the app and the saved config on the same model, and drops an
+    /// inference form started for another one.
+    fn save_model_choice(&mut self, what: &str) {
+        self.selected_model = self.config.selected_model.clone();
+        if self.inference_form.as_ref().is_some_and(|form| Some(&form.model_type) != self.selected_model.as_ref()) {
+            self.inference_form = None;
+        }
+        let problems = self.config.model_binding.as_ref().map(ModelBinding::problems).unwrap_or_default();
+        self.open_config_form();
+        let auto_config = self.auto_select_config()
+            .or_else(|| self.config_form.as_ref().filter(|form| form.auto_config).map(|form| form.config.config_path.clone()));
+        let mut status = match self.save_config(what) {
+            Ok(undoable) if problems.is_empty() => format!("Saved{}", undo_hint(undoable)),
+            Ok(undoable) => format!("Saved{}; {}", undo_hint(undoable), problems.join(", ")),
+            Err(e) => format!("Model kept for this session only: {:#}", e),
+        };
+        if let Some(path) = auto_config {
+            status.push_str(&format!("; training config uses {} (auto-selected)", path));
+        }
+        self.model_status = Some(status);
+    }
+
+    /// Binds the registry variant after the bound one for the highlighted
+    /// architecture, or its first.
+    fn bind_next_variant(&mut self) {
+        let Some(model_type) = self.listed_models().into_iter().nth(self.selected_index) else {
+            return;
+        };
+        let variants = variants_for(&model_type);
+        if variants.is_empty() {
+            self.model_status = Some(format!("No pretrained {} in the registry; k binds a checkpoint by path", model_type.name()));
+            return;
+        }
+        let current = self.highlighted_binding()
+            .and_then(|binding| variants.iter().position(|variant| binding.label.as_deref() == Some(variant.label)));
+        let next = &variants[current.map_or(0, |index| (index + 1) % variants.len())];
+        let binding = ModelBinding::from_variant(next, &self.weights_dir());
+        self.config.bind_model(binding);
+        self.save_model_choice(&format!("Bind {}", next.label));
+    }
+
+    /// The built-in registry's checkpoints, then `downloads` from the config.
+    pub fn download_entries(&self) -> Vec<DownloadEntry> {
+        download::entries(&self.config.downloads)
+    }
+
+    /// Downloads the highlighted checkpoint in the background and binds it
+    /// once it is in place, asking first when it can't be verified. Ctrl+X
+    /// stops it; Enter again resumes it.
+    fn start_download(&mut self) {
+        if let Some((name, _)) = &self.download {
+            self.download_status = Some(format!("Still downloading {}; Ctrl+X stops it", name));
+            return;
+        }
+        let Some(entry) = self.download_entries().into_iter().nth(self.selected_index) else {
+            return;
+        };
+        if entry.sha256.is_none() {
+            let question = format!("No checksum is known for {}, so the download can't be verified. Download it anyway? (y/n)", entry.name);
+            self.confirm = Some((question, ConfirmAction::DownloadUnverified(entry)));
+            return;
+        }
+        self.download_entry(entry);
+    }
+
+    fn download_entry(&mut self, entry: DownloadEntry) {
+        let weights_dir = self.weights_dir();
+        let name = entry.name.clone();
+        self.download_status = Some(format!("Downloading {} into {}", entry.weights_url, weights_dir.display()));
+        let task = self.spawn_task(&format!("Download {}", name), OnCancel::Discard, move |ctx| download::fetch(&entry, &weights_dir, ctx));
+        self.download = Some((name, task));
+    }
+
+    fn bind_checkpoint(&mut self) {
+        let Some(prompt) = self.checkpoint_prompt.take() else {
+            return;
+        };
+        let path = prompt.text().trim().to_string();
+        let Some(model_type) = self.listed_models().into_iter().nth(self.selected_index) else {
+            return;
+        };
+        if path.is_empty() {
+            return;
+        }
+        let binding = match self.highlighted_binding().cloned() {
+            Some(mut binding) => {
+                binding.set_checkpoint(&path);
+                binding
+            }
+            None => ModelBinding::manual(model_type, &path),
+        };
+        self.config.bind_model(binding);
+        self.save_model_choice("Bind checkpoint");
+    }
+
+    fn bind_config(&mut self) {
+        let Some(prompt) = self.model_config_prompt.take() else {
+            return;
+        };
+        let Some(mut binding) = self.highlighted_binding().cloned() else {
+            return;
+        };
+        binding.config = prompt.text().trim().to_string();
+        self.config.bind_model(binding);
+        self.save_model_choice("Bind config");
+    }
+
+    /// Starts a fresh form when the selected model changed since last time.
+    fn open_inference_setup(&mut self) {
+        let Some(model_type) = self.selected_model.clone().or_else(|| self.config.selected_model.clone()) else {
+            self.inference_form = None;
+            return;
+        };
+        if self.inference_form.as_ref().is_none_or(|form| form.model_type != model_type) {
+            let mut form = InferenceConfig::new(model_type.clone());
+            if let Some(binding) = &self.config.model_binding {
+                binding.fill_inference(&mut form);
+            }
+            self.inference_form = Some(form);
+        }
+        self.inference_status = None;
+        let discovered = self.discover_config(&model_type);
+        if let Some(form) = self.inference_form.as_mut().filter(|form| form.config_path.is_empty())
+            && let Ok(Some(path)) = discovered
+        {
+            self.inference_status = Some(format!("Model config auto-selected: {}", path));
+            form.config_path = path;
+        }
+        self.refresh_input_preview();
+    }
+
+    /// Rescans the inference input folder, keeping whether subfolders are
+    /// read; no preview while the folder is unset or can't be read.
+    fn refresh_input_preview(&mut self) {
+        let recursive = self.input_preview.as_ref().is_some_and(|preview| preview.recursive);
+        self.input_preview = self.inference_form.as_ref()
+            .filter(|form| !form.input_folder.trim().is_empty())
+            .and_then(|form| InputPreview::scan(&self.project_path(&form.input_folder), recursive, &form.excluded_inputs).ok());
+        self.input_files_table.set_rows(self.input_preview.as_ref().map(|preview| preview.files.clone()).unwrap_or_default());
+    }
+
+    /// Lists what the last successful run wrote, or the output folder when
+    /// nothing has run yet this session.
+    fn open_outputs(&mut self) {
+        let Some(form) = &self.inference_form else {
+            return;
+        };
+        let store_dir = self.inference_results.iter()
+            .rev()
+            .find(|result| result.success)
+            .map_or(form.store_dir.clone(), |result| result.output_dir.clone());
+        if store_dir.trim().is_empty() {
+            self.inference_status = Some("Set the output folder first (o)".to_string());
+            return;
+        }
+        if self.list_outputs(&self.project_path(&store_dir)) {
+            self.push_screen(Screen::Outputs);
+        }
+    }
+
+    /// Scans `store_dir` against the instruments of the form's model config.
+    fn list_outputs(&mut self, store_dir: &Path) -> bool {
+        let expected = self.inference_form.as_ref()
+            .and_then(|form| model_instruments(&self.project_path(&form.config_path).to_string_lossy()).ok())
+            .unwrap_or_default();
+        match OutputListing::scan(store_dir, &expected) {
+            Ok(listing) => {
+                self.output_status = None;
+                self.selected_index = self.selected_index.min(listing.lines().len().saturating_sub(1));
+                self.output_listing = Some(listing);
+                true
+            }
+            Err(e) => {
+                self.inference_status = Some(format!("{:#}", e));
+                false
+            }
+        }
+    }
+
+    /// Runs the player on `stem` and waits for it; the terminal is the
+    /// player's meanwhile.
+    fn run_player(&mut self, stem: &Path) {
+        let command = self.config.player_command().to_string();
+        let Some(mut player) = player_command(&command, stem) else {
+            self.output_status = Some("player_command in tui_config.yaml is empty".to_string());
+            return;
+        };
+        self.output_status = Some(match player.status() {
+            Ok(status) if status.success() => format!("Opened {}", stem.display()),
+            Ok(status) => format!("{} exited with {}", command, status),
+            Err(e) => format!("Failed to run {}: {} (set player_command in tui_config.yaml)", command, e),
+        });
+    }
+
+    fn draw_outputs(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let Some(listing) = &self.output_listing else {
+            return;
+        };
+        let title = Paragraph::new(format!("Outputs: {}", listing.store_dir.display()))
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let lines = listing.lines();
+        let items: Vec<ListItem> = if lines.is_empty() {
+            vec![ListItem::new("No stems in this folder")]
+        } else {
+            lines.into_iter()
+                .enumerate()
+                .map(|(i, line)| {
+                    let item = ListItem::new(line.text);
+                    if i == self.selected_index {
+                        item.style(styles.highlight)
+                    } else if line.flagged {
+                        item.style(styles.error)
+                    } else {
+                        item
+                    }
+                })
+                .collect()
+        };
+        let len = items.len();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border).title(listing.summary()));
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(3),
+            ])
+            .split(area);
+
+        f.render_widget(title, chunks[0]);
+        f.render_widget(list, chunks[1]);
+        self.record_list(chunks[1], 0, len);
+        f.render_widget(
+            Paragraph::new(self.output_status.as_deref().unwrap_or(self.key_help()))
+                .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+                .wrap(Wrap { trim: false }),
+            chunks[2],
+        );
+    }
+
+    fn draw_input_files(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let Some(preview) = &self.input_preview else {
+            return;
+        };
+        let subfolders = if preview.recursive { " and its subfolders" } else { "" };
+        let title = Paragraph::new(format!("Input files: {}{}", preview.folder.display(), subfolders))
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(3),
+            ])
+            .split(area);
+
+        f.render_widget(title, chunks[0]);
+        self.input_files_table.render(f, chunks[1], &preview.summary(), &styles);
+        f.render_widget(Paragraph::new(self.key_help()).wrap(Wrap { trim: false }), chunks[2]);
+    }
+
+    fn apply_preset(&mut self) {
+        let Some(form) = self.inference_form.as_mut() else {
+            return;
+        };
+        let Some(preset) = self.config.presets(&form.model_type).get(self.selected_index) else {
+            return;
+        };
+        let warnings = preset.apply(form);
+        self.inference_status = Some(if warnings.is_empty() {
+            format!("Applied preset '{}'", preset.name)
+        } else {
+            warnings.join("\n")
+        });
+    }
+
+    fn save_preset(&mut self) {
+        let (Some(form), Some(name)) = (self.inference_form.as_ref(), self.preset_name.as_ref()) else {
+            return;
+        };
+        let name = name.text().trim().to_string();
+        if name.is_empty() {
+            return;
+        }
+        let model_type = form.model_type.clone();
+        self.config.save_preset(&model_type, InferencePreset::from_config(&name, form));
+        self.preset_name = None;
+        self.inference_status = Some(match self.save_config(&format!("Save preset '{}'", name)) {
+            Ok(undoable) => format!("Saved preset '{}' for {}{}", name, model_type.key(), undo_hint(undoable)),
+            Err(e) => format!("Preset kept for this session only: {:#}", e),
+        });
+    }
+
+    fn list_input_files(&mut self) {
+        let Some(prompt) = self.input_folder_prompt.take() else {
+            return;
+        };
+        let folder = self.project_path(prompt.text().trim());
+        self.select_input_files(&folder);
+    }
+
+    fn select_input_files(&mut self, folder: &Path) {
+        match TrackSelection::scan(folder, self.config.exclude_patterns(folder)) {
+            Ok(selection) => {
+                self.track_selection = Some(selection);
+                self.inference_status = None;
+                self.push_screen(Screen::Tracks);
+            }
+            Err(e) => self.inference_status = Some(format!("{:#}", e)),
+        }
+    }
+
+    /// Asks for the input folder with the file browser open over the
+    /// prompt, from the form's input folder; Esc in the browser leaves the
+    /// path to be typed.
+    fn open_folder_browser(&mut self) {
+        let Some(form) = &self.inference_form else {
+            return;
+        };
+        self.input_folder_prompt = Some(TextInput::single_line().with_text(&form.input_folder));
+        self.inference_status = None;
+        self.browse_for_prompt();
+    }
+
+    /// `path` under the project root unless it is absolute: relative paths
+    /// in the settings, the configs and the prompts are the root's, wherever
+    /// the TUI was started from.
+    pub fn project_path(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.project_root.join(path)
+    }
+
+    /// Where pretrained checkpoints are downloaded to.
+    fn weights_dir(&self) -> PathBuf {
+        self.project_path(self.config.weights_dir())
+    }
+
+    /// The app settings in the project root.
+    fn config_manager(&self) -> ConfigManager {
+        ConfigManager::new(&self.project_path(DEFAULT_CONFIG_FILE).to_string_lossy())
+    }
+
+    /// How the scripts are launched, in the project root.
+    fn runner(&self) -> RunnerEnv {
+        RunnerEnv::from_config(&self.config).in_root(&self.project_root)
+    }
+
+    /// `runner` limited to the GPUs the job supervisor gave a run; all of
+    /// them when it gave none.
+    fn runner_on(&self, devices: &[usize]) -> RunnerEnv {
+        match devices {
+            [] => self.runner(),
+            devices => self.runner().on_devices(devices),
+        }
+    }
+
+    /// The training results folder from the Config screen, "results" by
+    /// default.
+    fn results_root(&self) -> String {
+        self.config_form.as_ref()
+            .map(|form| form.config.results_path.clone())
+            .filter(|path| !path.is_empty())
+            .unwrap_or_else(|| "results".to_string())
+    }
+
+    /// Watches the configs and results folders, unless `watch_files` is off.
+    fn start_file_watcher(&mut self) {
+        if !self.config.watch_files() {
+            return;
+        }
+        match FileWatcher::new() {
+            Ok(watcher) => self.file_watcher = Some(watcher),
+            Err(e) => self.warn(format!("Lists won't follow file changes: {:#}", e)),
+        }
+    }
+
+    /// Keeps the watched results folder the one the Config screen sets,
+    /// and refreshes the lists of files under a folder once its changes
+    /// settle.
+    fn poll_file_watcher(&mut self) {
+        let results = self.results_root();
+        let Some(watcher) = self.file_watcher.as_mut() else {
+            return;
+        };
+        let mut errors = vec![];
+        for (what, folder) in [(Watched::Configs, DEFAULT_CONFIGS_DIR), (Watched::Results, results.as_str())] {
+            if let Err(e) = watcher.watch(what, &self.project_root.join(folder)) {
+                errors.push(format!("{:#}", e));
+            }
+        }
+        let (changed, watch_errors) = watcher.poll(Instant::now());
+        errors.extend(watch_errors);
+        for error in errors {
+            self.warn(format!("File watcher: {}", error));
+        }
+        if changed.is_empty() {
+            return;
+        }
+        if let Some(browser) = self.file_browser.as_mut() {
+            browser.reload();
+        }
+        if changed.contains(&Watched::Results) {
+            if self.screen == Screen::Checkpoints {
+                let roots = [self.project_path(self.results_root()), self.weights_dir()];
+                self.checkpoints = list_checkpoints(&roots);
+                self.selected_index = self.selected_index.min(self.max_index());
+            }
+            if self.history.is_some() {
+                self.reload_history();
+            }
+        }
+    }
+
+    /// Hashes the selected checkpoint in the background, e.g. to fill in
+    /// the `sha256` of a `downloads` entry.
+    fn hash_checkpoint(&mut self) {
+        if self.checkpoint_hash.is_some() {
+            self.checkpoint_status = Some("Already hashing a checkpoint; Ctrl+X cancels it".to_string());
+            return;
+        }
+        let Some(checkpoint) = self.checkpoints.get(self.selected_index) else {
+            return;
+        };
+        let (path, name) = (checkpoint.path.clone(), checkpoint.file_name.clone());
+        self.checkpoint_status = Some(format!("Hashing {}…", name));
+        self.checkpoint_hash = Some(self.spawn_task(&format!("Hashing {}", name), OnCancel::Discard, move |ctx| {
+            Ok((name, checksum::sha256_file(&path, ctx)?))
+        }));
+    }
+
+    /// Checkpoints under the training results folder and the pretrained
+    /// weights folder.
+    fn list_checkpoints(&mut self) {
+        let results = self.results_root();
+        let roots = [self.project_path(&results), self.weights_dir()];
+        self.checkpoints = list_checkpoints(&roots);
+        if self.checkpoints.is_empty() {
+            let status = Some(format!(
+                "No .ckpt, .th or .pt files under {} or {}",
+                results,
+                self.config.weights_dir().display()
+            ));
+            match self.screen {
+                Screen::Validation => self.validation_status = status,
+                _ => self.inference_status = status,
+            }
+            return;
+        }
+        self.inference_status = None;
+        if self.checkpoint_hash.is_none() {
+            self.checkpoint_status = None;
+        }
+        self.push_screen(Screen::Checkpoints);
+    }
+
+    /// Shows the command that would run inference with the form as it is,
+    /// for `launch` to start.
+    fn run_inference(&mut self) {
+        if self.inference.is_some() {
+            self.inference_status = Some("Inference is already running".to_string());
+            return;
+        }
+        let Some(form) = &self.inference_form else {
+            return;
+        };
+        let missing: Vec<&str> = [
+            ("config", &form.config_path),
+            ("checkpoint", &form.start_checkpoint),
+            ("input folder", &form.input_folder),
+            ("output folder", &form.store_dir),
+        ]
+        .into_iter()
+        .filter(|(_, value)| value.trim().is_empty())
+        .map(|(name, _)| name)
+        .collect();
+        if !missing.is_empty() {
+            self.inference_status = Some(format!("Set the {} first", missing.join(", ")));
+            return;
+        }
+        // The paths are the host's, so there is nothing here to check.
+        if self.inference_host.is_some() {
+            self.preview_inference_launch();
+            return;
+        }
+        let issues = config_check::validate_inference(&form.in_root(&self.project_root));
+        let lines: Vec<String> = issues.iter().map(ConfigIssue::line).collect();
+        self.inference_status = (!lines.is_empty()).then(|| lines.join("; "));
+        if has_errors(&issues) {
+            return;
+        }
+        self.refresh_input_preview();
+        if let Some(preview) = self.input_preview.as_ref().filter(|preview| preview.is_empty()) {
+            let question = format!("There are {}. Run inference anyway? (y/n)", preview.summary());
+            self.confirm = Some((question, ConfirmAction::RunWithoutAudio));
+            return;
+        }
+        self.preview_inference_launch();
+    }
+
+    fn preview_inference_launch(&mut self) {
+        let Some(form) = &self.inference_form else {
+            return;
+        };
+        let config = form.clone();
+        if let Some(host) = self.remote_host(self.inference_host.as_deref()) {
+            let command = host.command(&self.scripts.inference, &build_inference_command(&config), &remote::run_tag("inference")).display();
+            self.launch_preview = Some((command, Launch::Inference(config)));
+            return;
+        }
+        let runner = self.runner();
+        match runner.python() {
+            Ok(python) => {
+                let command = preview_inference_command(&config, &python, &runner.script(&self.scripts.inference));
+                self.launch_preview = Some((command, Launch::Inference(config)));
+            }
+            Err(e) => self.report_error("Inference not started", &e),
+        }
+    }
+
+    fn batch_path(&self) -> PathBuf {
+        self.project_path(self.config.inference_batch_path.as_deref().unwrap_or(DEFAULT_BATCH_FILE))
+    }
+
+    fn save_batch(&self) -> anyhow::Result<()> {
+        self.inference_batch.save(&self.batch_path())
+    }
+
+    /// Starts the first batch item still to go with the Inference screen's
+    /// settings, or the ones the batch last ran with. Items that can't run,
+    /// e.g. a missing folder, are recorded as failed and skipped.
+    fn start_next_batch_item(&mut self) {
+        if self.inference.is_some() {
+            self.batch_status = Some("Inference is already running; s again once it is over".to_string());
+            return;
+        }
+        if self.config.dry_run {
+            self.batch_status = Some("Dry run: the batch doesn't start (d on the Settings screen turns it off)".to_string());
+            return;
+        }
+        if let Some(form) = &self.inference_form {
+            self.inference_batch.settings = Some(form.clone());
+        }
+        let Some(base) = self.inference_batch.settings.clone() else {
+            self.batch_status = Some("Select a model and set up the Inference screen first".to_string());
+            return;
+        };
+        let missing: Vec<&str> = [("config", &base.config_path), ("checkpoint", &base.start_checkpoint), ("output folder", &base.store_dir)]
+            .into_iter()
+            .filter(|(_, value)| value.trim().is_empty())
+            .map(|(name, _)| name)
+            .collect();
+        if !missing.is_empty() {
+            self.batch_status = Some(format!("Set the {} on the Inference screen first", missing.join(", ")));
+            return;
+        }
+        loop {
+            let Some(index) = self.inference_batch.next_pending() else {
+                self.batch_status = Some(match self.save_batch() {
+                    Ok(()) => format!("Batch finished: {}", self.inference_batch.summary()),
+                    Err(e) => format!("{:#}", e),
+                });
+                if let Some(started) = self.batch_started.take() {
+                    let failed = self.inference_batch.items().iter().any(|item| item.failed());
+                    let summary = format!("Batch finished: {}", self.inference_batch.summary());
+                    if let Err(e) = self.notify_finished("Inference batch", if failed { "failed" } else { "finished" }, &summary, Some(started.elapsed())) {
+                        self.warn(format!("{:#}", e));
+                    }
+                }
+                return;
+            };
+            let item = &self.inference_batch.items()[index];
+            let output = Path::new(&base.store_dir).join(item.name()).to_string_lossy().into_owned();
+            let config = match item.config(&base) {
+                Ok(config) => config,
+                Err(e) => {
+                    self.inference_batch.record_failure(index, &output, format!("{:#}", e));
+                    continue;
+                }
+            };
+            let issues = config_check::validate_inference(&config.in_root(&self.project_root));
+            if has_errors(&issues) {
+                let errors: Vec<String> = issues.iter().map(ConfigIssue::line).collect();
+                self.inference_batch.record_failure(index, &output, errors.join("; "));
+                continue;
+            }
+            if let Err(e) = self.save_batch() {
+                self.batch_status = Some(format!("{:#}", e));
+                return;
+            }
+            self.batch_run = Some(index);
+            self.batch_started.get_or_insert_with(Instant::now);
+            self.submit_run(GatedRun::Inference(config));
+            self.batch_status = Some(format!("Item {} of {} started", index + 1, self.inference_batch.items().len()));
+            return;
+        }
+    }
+
+    /// Records how the batch item at `index` went and starts the next one,
+    /// failed or not, unless it was stopped by hand or the app is quitting.
+    fn finish_batch_item(&mut self, index: usize, finished: Option<Result<InferenceResult, String>>) {
+        match finished {
+            Some(Ok(result)) => self.inference_batch.record(index, result),
+            Some(Err(error)) => {
+                let output = self.inference_batch.settings.as_ref()
+                    .zip(self.inference_batch.items().get(index))
+                    .map(|(base, item)| Path::new(&base.store_dir).join(item.name()).to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                self.inference_batch.record_failure(index, &output, error);
+            }
+            None => {
+                self.batch_status = Some("Batch paused: its item was stopped; s goes on with it".to_string());
+                return;
+            }
+        }
+        if let Err(e) = self.save_batch() {
+            self.batch_status = Some(format!("{:#}", e));
+            return;
+        }
+        if self.quit_when_stopped || self.shutdown.is_cancelled() {
+            self.batch_status = Some("Batch paused for quitting; it resumes with s next time".to_string());
+            return;
+        }
+        self.start_next_batch_item();
+    }
+
+    fn add_batch_item(&mut self) {
+        let Some(prompt) = self.batch_prompt.take() else {
+            return;
+        };
+        let input = prompt.text().trim();
+        if input.is_empty() {
+            return;
+        }
+        if !self.project_path(input).exists() {
+            self.batch_status = Some(format!("{} doesn't exist", input));
+            self.batch_prompt = Some(prompt);
+            return;
+        }
+        if !self.inference_batch.push(input) {
+            self.batch_status = Some(format!("{} is already in the batch", input));
+            return;
+        }
+        self.selected_index = self.inference_batch.items().len() - 1;
+        self.batch_status = Some(match self.save_batch() {
+            Ok(()) => format!("Added {}", input),
+            Err(e) => format!("{:#}", e),
+        });
+    }
+
+    fn handle_batch_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char('a') => {
+                let folder = self.inference_form.as_ref().map(|form| form.input_folder.clone()).unwrap_or_default();
+                self.batch_prompt = Some(TextInput::single_line().with_text(&folder));
+                self.batch_status = None;
+            }
+            KeyCode::Char('s') => self.start_next_batch_item(),
+            KeyCode::Char('x') => {
+                let count = self.inference_batch.retry_failed();
+                if count == 0 {
+                    self.batch_status = Some("Nothing failed".to_string());
+                } else if self.inference.is_some() {
+                    self.batch_status = Some(match self.save_batch() {
+                        Ok(()) => format!("{} failed item(s) go again after the running one", count),
+                        Err(e) => format!("{:#}", e),
+                    });
+                } else {
+                    self.start_next_batch_item();
+                }
+            }
+            KeyCode::Char('d') => {
+                if self.batch_run == Some(self.selected_index) {
+                    self.batch_status = Some("That item is running; Ctrl+X stops it first".to_string());
+                    return;
+                }
+                let Some(removed) = self.inference_batch.remove(self.selected_index) else {
+                    return;
+                };
+                self.batch_run = self.batch_run.map(|run| if run > self.selected_index { run - 1 } else { run });
+                self.selected_index = self.selected_index.min(self.inference_batch.items().len().saturating_sub(1));
+                self.batch_status = Some(match self.save_batch() {
+                    Ok(()) => format!("Removed {} from the batch", removed.input),
+                    Err(e) => format!("{:#}", e),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    /// A line per batch item: name, duration, status and output folder.
+    pub fn batch_lines(&self) -> Vec<String> {
+        self.inference_batch.items()
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let mut cells = item.cells();
+                if self.batch_run == Some(i) {
+                    cells[2] = "running".to_string();
+                }
+                cells.into_iter().filter(|cell| !cell.is_empty()).collect::<Vec<_>>().join(", ")
+            })
+            .collect()
+    }
+
+    fn draw_inference_batch(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new(format!("Inference Batch - {}", self.inference_batch.summary()))
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let rows: Vec<Row> = self.inference_batch.items()
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let mut cells = item.cells();
+                if self.batch_run == Some(i) {
+                    cells[2] = "running".to_string();
+                }
+                let row = Row::new(cells.to_vec());
+                if i == self.selected_index {
+                    row.style(styles.highlight)
+                } else if item.failed() {
+                    row.style(styles.error)
+                } else {
+                    row
+                }
+            })
+            .collect();
+        let empty = rows.is_empty();
+        let table = Table::new(rows, [
+            ratatui::layout::Constraint::Length(24),
+            ratatui::layout::Constraint::Length(9),
+            ratatui::layout::Constraint::Min(20),
+            ratatui::layout::Constraint::Min(20),
+        ])
+            .header(Row::new(vec!["Item", "Duration", "Status", "Output"]).style(styles.title))
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border)
+                .title(if empty { "Nothing yet; a adds an input folder or audio file" } else { "In order, each into its own output subfolder" }));
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(3),
+            ])
+            .split(area);
+        f.render_widget(title, chunks[0]);
+        f.render_widget(table, chunks[1]);
+        // Below the header row.
+        let rows = ratatui::layout::Rect { y: chunks[1].y + 1, height: chunks[1].height.saturating_sub(1), ..chunks[1] };
+        self.record_list(rows, 0, self.inference_batch.items().len());
+        match self.focused_input() {
+            Some(input) => input.render(f, chunks[2], self.prompt_title().unwrap_or_default()),
+            None => f.render_widget(
+                Paragraph::new(self.batch_status.as_deref().unwrap_or(self.key_help()))
+                    .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+                    .wrap(Wrap { trim: false }),
+                chunks[2],
+            ),
+        }
+    }
+
+    /// Runs inference in the background; the output shows on the Inference
+    /// screen as it is printed.
+    fn launch_inference(&mut self, id: JobId, config: InferenceConfig, devices: &[usize]) {
+        self.remember_config(RecentConfig::new(&config.config_path, RecentKind::Inference, config.model_type.clone()));
+        let mut manager = InferenceManager::new();
+        manager.set_scripts(&self.scripts);
+        manager.set_runner(self.runner_on(devices));
+        manager.set_remote(self.remote_host(self.inference_host.as_deref()));
+        manager.set_scratch(Scratch::from_config(&self.config, &self.project_root));
+        let (output_tx, output_rx) = mpsc::unbounded_channel();
+        manager.set_output(output_tx);
+        self.inference_step = manager.step_progress();
+
+        let task = self.spawn_task("Inference", OnCancel::Discard, move |ctx| {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|e| anyhow::anyhow!("Failed to start the inference runtime: {}", e))?;
+            runtime.block_on(async {
+                let result = tokio::select! {
+                    result = manager.run_inference(&config) => result,
+                    _ = async {
+                        while !ctx.is_cancelled() {
+                            tokio::time::sleep(Duration::from_millis(200)).await;
+                        }
+                    } => Err(anyhow::anyhow!("Inference stopped")),
+                };
+                // A remote run isn't ended by dropping its ssh.
+                if ctx.is_cancelled() {
+                    manager.stop_inference().await?;
+                }
+                result
+            })
+        });
+        self.inference_log = Arc::new(Mutex::new(LogBuffer::new()));
+        self.supervisor.attach(id, task.monitor(), self.inference_log.clone());
+        self.inference_job = Some(id);
+        self.inference = Some(task);
+        self.inference_rx = Some(output_rx);
+        self.inference_log_pane = LogPane::new();
+        self.inference_status = Some("Running inference (Ctrl+X stops it)".to_string());
+    }
+
+    /// Starts the previewed run, or with `dry_run` set only logs `command`.
+    fn launch(&mut self, command: &str, launch: Launch) {
+        let remote = match launch {
+            Launch::Training(_) => self.training_host.is_some(),
+            Launch::Inference(_) => self.inference_host.is_some(),
+        };
+        let checked = remote || self.preflight.as_ref()
+            .is_some_and(|report| report.passed() && report.model_type.as_ref() == Some(launch.model_type()));
+        if !self.config.dry_run && !checked {
+            let status = Some("Checking the python environment first…".to_string());
+            match launch {
+                Launch::Training(_) => self.training_status = status,
+                Launch::Inference(_) => self.inference_status = status,
+            }
+            let model_type = launch.model_type().clone();
+            self.preflight_launch = Some((command.to_string(), launch));
+            self.check_environment_for(Some(model_type));
+            return;
+        }
+        if !self.config.dry_run {
+            match launch {
+                Launch::Training(config) => match self.gpu_conflicts(&config) {
+                    Some((question, alternative)) => self.gpu_prompt = Some((question, config, alternative)),
+                    None => self.submit_training(config),
+                },
+                Launch::Inference(config) => self.submit_run(GatedRun::Inference(config)),
+            }
+            return;
+        }
+        let status = "Dry run: nothing started (d on the Settings screen turns it off)".to_string();
+        match launch {
+            Launch::Training(_) => {
+                let mut log = LogBuffer::new();
+                log.push(&format!("Dry run: {}", command));
+                self.training_log = Arc::new(Mutex::new(log));
+                self.training_log_pane = LogPane::new();
+                self.training_status = Some(status);
+            }
+            Launch::Inference(_) => {
+                let mut log = LogBuffer::new();
+                log.push(&format!("Dry run: {}", command));
+                self.inference_log = Arc::new(Mutex::new(log));
+                self.inference_log_pane = LogPane::new();
+                self.inference_status = Some(status);
+            }
+        }
+    }
+
+    /// Checks the python environment for the selected model in the
+    /// background.
+    fn check_environment(&mut self) {
+        let model_type = self.selected_model.clone().or_else(|| self.config.selected_model.clone());
+        self.check_environment_for(model_type);
+    }
+
+    fn check_environment_for(&mut self, model_type: Option<ModelType>) {
+        if self.preflight_check.is_some() {
+            return;
+        }
+        let runner = self.runner();
+        let python = match runner.python() {
+            Ok(python) => python,
+            Err(e) => {
+                self.preflight_launch = None;
+                self.report_error("Environment not checked", &e);
+                return;
+            }
+        };
+        self.preflight_status = Some(format!("Checking {}…", python));
+        self.preflight_check = Some(self.spawn_task("Checking the python environment", OnCancel::Discard, move |_| {
+            preflight::run(&python, model_type.as_ref(), &runner)
+        }));
+    }
+
+    /// A finished check starts the launch waiting for it if nothing
+    /// required is missing, and otherwise shows the checklist instead.
+    fn finish_environment_check(&mut self, outcome: anyhow::Result<TaskOutcome<PreflightReport>>) {
+        let launch = self.preflight_launch.take();
+        let report = match outcome {
+            Ok(TaskOutcome::Completed(report)) => report,
+            Ok(TaskOutcome::Cancelled(_)) => {
+                self.preflight_status = Some("Environment check cancelled".to_string());
+                return;
+            }
+            Err(e) => {
+                self.preflight_status = None;
+                self.report_error("Environment check failed", &e);
+                return;
+            }
+        };
+        self.preflight_status = Some(match report.install_hint() {
+            Some(hint) => format!("{}. {}", report.summary(), hint),
+            None => report.summary(),
+        });
+        let passed = report.passed();
+        self.preflight = Some(report);
+        match launch {
+            Some((command, launch)) if passed => self.launch(&command, launch),
+            Some((_, launch)) => {
+                let status = Some("Not started: the python environment is missing required packages".to_string());
+                match launch {
+                    Launch::Training(_) => self.training_status = status,
+                    Launch::Inference(_) => self.inference_status = status,
+                }
+                self.push_screen(Screen::Environment);
+            }
+            None => {}
+        }
+    }
+
+    /// The checklist of the last environment check.
+    pub fn environment_lines(&self) -> Vec<String> {
+        let Some(report) = &self.preflight else {
+            let line = if self.preflight_check.is_some() { "Checking…" } else { "Not checked yet; r checks" };
+            return vec![line.to_string()];
+        };
+        let model = report.model_type.as_ref().map_or("none selected, so only the base packages".to_string(), |model| model.name().to_string());
+        let mut lines = vec![format!("Python: {}", report.python), format!("Model: {}", model)];
+        lines.extend(report.items.iter().map(CheckItem::line));
+        lines.extend(report.install_hint().map(|hint| format!("Install: {}", hint)));
+        lines
+    }
+
+    fn receive_inference_output(&mut self) {
+        if let Some(rx) = self.inference_rx.as_mut() {
+            let mut log = self.inference_log.lock().unwrap();
+            while let Ok((severity, line)) = rx.try_recv() {
+                match severity {
+                    Severity::Info => log.push(&line),
+                    Severity::Error => log.push_error(&line),
+                };
+            }
+        }
+    }
+
+    fn draw_checkpoints(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new(self.checkpoint_status.as_deref().unwrap_or("Checkpoints"))
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+        let items: Vec<ListItem> = self.checkpoints.iter()
+            .enumerate()
+            .map(|(i, checkpoint)| {
+                let item = ListItem::new(checkpoint_line(checkpoint));
+                if i == self.selected_index {
+                    item.style(styles.highlight)
+                } else {
+                    item
+                }
+            })
+            .collect();
+        let len = items.len();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border).title(format!("{} found, best SDR first", self.checkpoints.len())));
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(3),
+            ])
+            .split(area);
+        f.render_widget(title, chunks[0]);
+        f.render_widget(list, chunks[1]);
+        self.record_list(chunks[1], 0, len);
+        f.render_widget(Paragraph::new(self.key_help()).block(Block::default().borders(Borders::ALL).border_style(styles.border)), chunks[2]);
+    }
+
+    fn preview_stem_renames(&mut self) {
+        let (Some(prompt), Some(form)) = (self.rename_prompt.take(), &self.inference_form) else {
+            return;
+        };
+        let store_dir = self.project_path(prompt.text().trim());
+        let naming = form.stem_naming.clone().unwrap_or_default();
+        match ResultsManifest::load(&store_dir) {
+            Ok(Some(manifest)) => {
+                let plan = stem_names::plan(&store_dir, &manifest, &naming);
+                self.inference_status = None;
+                self.rename_plan = Some((store_dir, plan));
+                self.push_screen(Screen::RenameStems);
+            }
+            Ok(None) => self.inference_status = Some(format!("No results manifest in {}", store_dir.display())),
+            Err(e) => self.inference_status = Some(format!("{:#}", e)),
+        }
+    }
+
+    fn apply_stem_renames(&mut self) {
+        let Some((store_dir, plan)) = &self.rename_plan else {
+            return;
+        };
+        let applied = ResultsManifest::load(store_dir)
+            .and_then(|manifest| manifest.ok_or_else(|| anyhow::anyhow!("The results manifest is gone")))
+            .and_then(|mut manifest| stem_names::apply(store_dir, &mut manifest, plan));
+        match applied {
+            Ok(count) => {
+                self.inference_status = Some(format!("Renamed {} stem(s) in {}", count, store_dir.display()));
+                self.rename_plan = None;
+                self.back_to(Screen::Inference);
+            }
+            Err(e) => self.inference_status = Some(format!("{:#}", e)),
+        }
+    }
+
+    fn draw_rename_stems(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let Some((store_dir, plan)) = &self.rename_plan else {
+            return;
+        };
+        let title = Paragraph::new(format!("Rename stems: {}", store_dir.display()))
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let lines = plan.lines();
+        let items: Vec<ListItem> = if lines.is_empty() {
+            vec![ListItem::new("Every stem already has its new name")]
+        } else {
+            lines.iter()
+                .enumerate()
+                .map(|(i, line)| {
+                    let mut style = ratatui::style::Style::default();
+                    if i < plan.collisions.len() {
+                        style = styles.error;
+                    }
+                    if i == self.selected_index {
+                        style = styles.highlight;
+                    }
+                    ListItem::new(line.as_str()).style(style)
+                })
+                .collect()
+        };
+        let len = items.len();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border).title(rename_summary(plan)));
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(3),
+            ])
+            .split(area);
+
+        f.render_widget(title, chunks[0]);
+        f.render_widget(list, chunks[1]);
+        self.record_list(chunks[1], 0, len);
+        let footer = self.inference_status.as_deref().unwrap_or(self.key_help());
+        f.render_widget(Paragraph::new(footer).wrap(Wrap { trim: false }), chunks[2]);
+    }
+
+    /// Sums the stems in the prompted output folder back in the background,
+    /// with the form's threshold or the default.
+    fn verify_stems(&mut self) {
+        let (Some(prompt), Some(form)) = (self.verify_prompt.take(), &self.inference_form) else {
+            return;
+        };
+        if self.verification.is_some() {
+            self.inference_status = Some("Already checking stems".to_string());
+            return;
+        }
+        let store_dir = self.project_path(prompt.text().trim());
+        let threshold = form.reconstruction_threshold_db.unwrap_or(reconstruction::DEFAULT_THRESHOLD_DB);
+        let manifest = match ResultsManifest::load(&store_dir) {
+            Ok(Some(manifest)) => manifest,
+            Ok(None) => {
+                self.inference_status = Some(format!("No results manifest in {}", store_dir.display()));
+                return;
+            }
+            Err(e) => {
+                self.inference_status = Some(format!("{:#}", e));
+                return;
+            }
+        };
+        let task = self.spawn_task("Checking stem sums", OnCancel::Discard, move |_| {
+            let report = reconstruction::verify(&store_dir, &manifest, threshold)?;
+            Ok((store_dir, report))
+        });
+        self.verification = Some(task);
+        self.inference_status = None;
+    }
+
+    fn draw_reconstruction(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let Some((store_dir, report)) = &self.reconstruction else {
+            return;
+        };
+        let title = Paragraph::new(format!("Stem sums: {}", store_dir.display()))
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(3),
+            ])
+            .split(area);
+
+        f.render_widget(title, chunks[0]);
+        self.reconstruction_table.render(f, chunks[1], &report.summary(), &styles);
+        f.render_widget(Paragraph::new(self.key_help()).wrap(Wrap { trim: false }), chunks[2]);
+    }
+
+    fn exclude_tracks(&mut self) {
+        let (Some(prompt), Some(selection)) = (self.exclude_prompt.take(), self.track_selection.as_mut()) else {
+            return;
+        };
+        let pattern = prompt.text().trim().to_string();
+        let matched = selection.exclude(&pattern);
+        self.inference_status = Some(format!("{} matched {} files", pattern, matched));
+    }
+
+    fn remember_patterns(&mut self) {
+        let Some(selection) = &self.track_selection else {
+            return;
+        };
+        self.config.set_exclude_patterns(selection.folder(), selection.patterns());
+        let count = selection.patterns().len();
+        self.inference_status = Some(match self.save_config("Remember exclude patterns") {
+            Ok(undoable) => format!("Remembered {} patterns for this folder{}", count, undo_hint(undoable)),
+            Err(e) => format!("Patterns kept for this session only: {:#}", e),
+        });
+    }
+
+    /// Hands the selection to the inference settings and goes back to them.
+    fn use_track_selection(&mut self) {
+        let (Some(selection), Some(form)) = (self.track_selection.take(), self.inference_form.as_mut()) else {
+            return;
+        };
+        form.input_folder = selection.folder().to_string_lossy().to_string();
+        form.excluded_inputs = selection.excluded_names();
+        self.inference_status = Some(selection.confirmation());
+        self.refresh_input_preview();
+        self.back_to(Screen::Inference);
+    }
+
+    fn load_parser_rules(&mut self) {
+        let path = self.config.parser_rules_path.as_deref().map(Path::new);
+        match ParserRules::load_or_builtin(path, &self.project_root) {
+            Ok(rules) => {
+                self.parser_rules = rules;
+                self.parser_error = None;
+            }
+            Err(e) => {
+                self.parser_rules = ParserRules::builtin();
+                self.parser_error = Some(format!("{:#}", e));
+            }
+        }
+    }
+
+    fn history_path(&self) -> PathBuf {
+        self.project_path(self.config.history_path.as_deref().unwrap_or(DEFAULT_HISTORY_FILE))
+    }
+
+    fn reload_history(&mut self) {
+        match RunHistory::load(&self.history_path()) {
+            Ok(mut history) => {
+                history.refresh();
+                if !self.read_only {
+                    self.history_status = history.save().err().map(|e| format!("{:#}", e));
+                }
+                self.history = Some(history);
+            }
+            Err(e) => {
+                self.history = None;
+                self.history_status = Some(format!("{:#}", e));
+            }
+        }
+        self.apply_history_filter();
+    }
+
+    /// What the priced runs started this month cost, e.g. "42.10 USD".
+    pub fn month_cost(&self) -> Option<String> {
+        let totals = monthly_totals(self.history.as_ref()?.entries(), chrono::Local::now());
+        (!totals.is_empty()).then(|| {
+            totals.iter().map(|(currency, amount)| format_amount(*amount, currency)).collect::<Vec<_>>().join(" + ")
+        })
+    }
+
+    fn apply_history_filter(&mut self) {
+        let rows = match &self.history {
+            Some(history) => history.filter(self.history_filter.text())
+                .into_iter()
+                .map(|index| history.entries()[index].clone())
+                .collect(),
+            None => vec![],
+        };
+        self.history_table.set_rows(rows);
+    }
+
+    fn save_notes(&mut self) {
+        let (Some(editor), Some(history)) = (self.notes_editor.as_ref(), self.history.as_mut()) else {
+            return;
+        };
+        let Some(index) = self.history_table.selected().and_then(|entry| history.position(entry)) else {
+            return;
+        };
+        match history.set_notes(index, editor.text()) {
+            Ok(()) => {
+                self.notes_editor = None;
+                self.history_status = Some("Notes saved".to_string());
+                self.apply_history_filter();
+            }
+            Err(e) => self.history_status = Some(format!("Failed to save notes: {:#}", e)),
+        }
+    }
+
+    fn import_results(&mut self) {
+        let Some(root) = self.import_root.take() else {
+            return;
+        };
+        let Some(history) = self.history.as_mut() else {
+            return;
+        };
+        let imported = history.import(&self.project_root.join(root.text().trim()))
+            .and_then(|summary| history.save().map(|()| summary));
+        self.history_status = Some(match imported {
+            Ok(summary) => format!(
+                "Imported {} runs ({} already listed, {} need a model: select and press m)",
+                summary.added, summary.known, summary.needs_review
+            ),
+            Err(e) => format!("Import failed: {:#}", e),
+        });
+        self.apply_history_filter();
+    }
+
+    /// Moves the selected imported entry to the next model, its own
+    /// candidates first. An entry still needing review starts at the first.
+    fn cycle_imported_model(&mut self) {
+        let Some(history) = self.history.as_mut() else {
+            return;
+        };
+        let Some(index) = self.history_table.selected().and_then(|entry| history.position(entry)) else {
+            return;
+        };
+        let entry = &history.entries()[index];
+        let Some(imported) = &entry.imported else {
+            self.history_status = Some("Not an imported run; m fixes up imported entries".to_string());
+            return;
+        };
+        let mut order = imported.model_candidates.clone();
+        let others: Vec<ModelType> = ModelType::all_models().into_iter().filter(|model| !order.contains(model)).collect();
+        order.extend(others);
+        let next = if imported.needs_review() {
+            0
+        } else {
+            order.iter().position(|model| *model == entry.model_type).map_or(0, |i| (i + 1) % order.len())
+        };
+        let model = order[next].clone();
+        self.history_status = Some(match history.set_model(index, model.clone()) {
+            Ok(()) => format!("Model set to {}", model.key()),
+            Err(e) => format!("{:#}", e),
+        });
+        self.apply_history_filter();
+    }
+
+    fn save_label(&mut self) {
+        let (Some(editor), Some(history)) = (self.label_editor.as_ref(), self.history.as_mut()) else {
+            return;
+        };
+        let Some(index) = self.history_table.selected().and_then(|entry| history.position(entry)) else {
+            return;
+        };
+        match history.set_label(index, editor.text()) {
+            Ok(()) => {
+                self.label_editor = None;
+                self.history_status = Some("Label saved".to_string());
+                self.apply_history_filter();
+            }
+            Err(e) => self.history_status = Some(format!("Failed to save label: {:#}", e)),
+        }
+    }
+
+    /// Entries of a kind measured against a valid set that has changed.
+    pub fn stale_entries(&self, kind: RunKind) -> usize {
+        self.history.as_ref().map_or(0, |history| {
+            history.entries().iter().filter(|entry| entry.kind == kind && entry.valid_set_stale).count()
+        })
+    }
+
+    /// E.g. "3 on a stale valid set (*)", when any are.
+    pub fn stale_summary(&self) -> Option<String> {
+        let stale = self.stale_entries(RunKind::Training) + self.stale_entries(RunKind::BakeOff);
+        (stale > 0).then(|| format!("{} on a stale valid set (*)", stale))
+    }
+
+    /// Measures every bake-off again whose reference folder changed since,
+    /// or that predates stamping, against the folder as it is now. Training
+    /// runs are only counted: their checkpoints need valid.py.
+    fn revalidate_stale(&mut self) {
+        if self.revalidation.is_some() {
+            self.history_status = Some("Already re-validating".to_string());
+            return;
+        }
+        let Some(history) = self.history.as_mut() else {
+            return;
+        };
+        let mut dirs = vec![];
+        for index in 0..history.entries().len() {
+            let entry = &history.entries()[index];
+            if entry.kind == RunKind::BakeOff
+                && (entry.valid_set_stale || entry.valid_set.is_none())
+                && let Ok(dir) = history.locate(index)
+            {
+                dirs.push(dir);
+            }
+        }
+        if dirs.is_empty() {
+            self.history_status = Some("No bake-off is measured against a stale valid set".to_string());
+            return;
+        }
+        let task = self.spawn_task("Re-validating bake-offs", OnCancel::Discard, move |ctx| {
+            ctx.set_total(dirs.len() as u64);
+            let mut count = 0;
+            for dir in &dirs {
+                let mut report = BakeOffReport::load(dir)?;
+                // Bake-offs run without a reference have nothing to re-measure.
+                if report.reference_folder.is_some() {
+                    bakeoff::revalidate(dir, &mut report, ctx)?;
+                    count += 1;
+                }
+                ctx.advance(1);
+            }
+            Ok(count)
+        });
+        self.revalidation = Some(task);
+        self.history_status = None;
+    }
+
+    /// Opens the selected history entry with the metrics its run recorded.
+    fn open_run_detail(&mut self) {
+        let Some(history) = self.history.as_mut() else {
+            return;
+        };
+        let Some(index) = self.history_table.selected().and_then(|entry| history.position(entry)) else {
+            return;
+        };
+        match history.detail(index) {
+            Ok(detail) => {
+                self.run_detail = Some(detail);
+                self.push_screen(Screen::RunDetail);
+            }
+            Err(e) => self.history_status = Some(format!("{:#}", e)),
+        }
+    }
+
+    /// Marks the selected run, or compares it with the one marked before.
+    fn compare_runs(&mut self) {
+        let Some(history) = self.history.as_mut() else {
+            return;
+        };
+        let Some(selected) = self.history_table.selected().cloned() else {
+            return;
+        };
+        let first = match self.compare_first.take() {
+            Some(first) if first.name != selected.name || first.started_at != selected.started_at => first,
+            Some(_) => {
+                self.history_status = Some("Comparison cleared".to_string());
+                return;
+            }
+            None => {
+                self.history_status = Some(format!("{} marked; c on another run compares the two", selected.name));
+                self.compare_first = Some(selected);
+                return;
+            }
+        };
+        let (Some(a), Some(b)) = (history.position(&first), history.position(&selected)) else {
+            self.history_status = Some(format!("{} is no longer in the history", first.name));
+            return;
+        };
+        match history.detail(a).and_then(|a| Ok((a, history.detail(b)?))) {
+            Ok((a, b)) => {
+                let comparison = RunComparison::new(a, b);
+                self.comparison_table.set_rows(comparison.rows.clone());
+                self.comparison = Some(comparison);
+                self.history_status = None;
+                self.push_screen(Screen::Compare);
+            }
+            Err(e) => self.history_status = Some(format!("{:#}", e)),
+        }
+    }
+
+    fn draw_comparison(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let Some(comparison) = &self.comparison else {
+            return;
+        };
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(3),
+            ])
+            .split(area);
+        f.render_widget(
+            Paragraph::new(format!("A: {}    B: {}", comparison.a.entry.name, comparison.b.entry.name))
+                .block(Block::default().borders(Borders::ALL).border_style(styles.border).title("Compare runs"))
+                .style(styles.title),
+            chunks[0],
+        );
+        let body = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Horizontal)
+            .constraints([ratatui::layout::Constraint::Min(30), ratatui::layout::Constraint::Length(44)])
+            .split(chunks[1]);
+        let a = training_chart::sdr_points(comparison.a.metrics.progress());
+        let b = training_chart::sdr_points(comparison.b.metrics.progress());
+        draw_epoch_chart(f, body[0], "Mean SDR", &[("A", &a, styles.progress), ("B", &b, styles.warning)], 2, &styles);
+        let title = if comparison.per_instrument() { "Best SDR (B - A)" } else { "Best SDR (B - A), no SDR per instrument in one run" };
+        self.comparison_table.render(f, body[1], title, &styles);
+        f.render_widget(
+            Paragraph::new(self.key_help()).block(Block::default().borders(Borders::ALL).border_style(styles.border)),
+            chunks[2],
+        );
+    }
+
+    fn open_bake_off(&mut self) {
+        let Some(history) = self.history.as_mut() else {
+            return;
+        };
+        let Some(index) = self.history_table.selected().and_then(|entry| history.position(entry)) else {
+            return;
+        };
+        if history.entries()[index].kind != RunKind::BakeOff {
+            self.history_status = Some("Not a bake-off; o opens bake-off entries".to_string());
+            return;
+        }
+        let opened = history.locate(index)
+            .and_then(|dir| BakeOffReport::load(&dir).map(|report| (dir, report)));
+        match opened {
+            Ok((dir, report)) => {
+                self.bake_off_table = DataTable::new(bake_off_columns(&report));
+                self.bake_off_table.set_rows(pair_stems(&dir, &report));
+                self.bake_off_sides = (0, 1.min(report.outcomes.len().saturating_sub(1)));
+                self.bake_off = Some((dir, report));
+                self.push_screen(Screen::BakeOff);
+            }
+            Err(e) => self.history_status = Some(format!("{:#}", e)),
+        }
+    }
+
+    fn duplicate_run(&mut self) {
+        let Some(history) = self.history.as_mut() else {
+            return;
+        };
+        let Some(index) = self.history_table.selected().and_then(|entry| history.position(entry)) else {
+            return;
+        };
+        if history.entries()[index].kind != RunKind::Training {
+            self.history_status = Some("Only training runs can be duplicated".to_string());
+            return;
+        }
+        let template = history.locate(index)
+            .and_then(|dir| RunDir::load(&dir))
+            .and_then(|run| RunTemplate::from_run(&run));
+        match template {
+            Ok(template) => {
+                // Flag moved datasets and deleted checkpoints now, not at launch.
+                let missing = template.missing_files(&self.project_root);
+                self.template_status = (!missing.is_empty()).then(|| format!("Missing: {}", missing.join("; ")));
+                self.run_template = Some(template);
+                self.push_screen(Screen::DuplicateRun);
+            }
+            Err(e) => self.history_status = Some(format!("{:#}", e)),
+        }
+    }
+
+    fn set_template_field(&mut self) {
+        let (Some(field), Some(template)) = (self.template_field.take(), self.run_template.as_mut()) else {
+            return;
+        };
+        self.template_status = match template.set(self.selected_index, field.text()) {
+            Ok(()) => {
+                let missing = template.missing_files(&self.project_root);
+                (!missing.is_empty()).then(|| format!("Missing: {}", missing.join("; ")))
+            }
+            Err(e) => Some(format!("{:#}", e)),
+        };
+    }
+
+    fn save_template(&mut self) {
+        let (Some(prompt), Some(template)) = (self.template_save.take(), self.run_template.as_ref()) else {
+            return;
+        };
+        let path = self.project_path(prompt.text().trim()).to_string_lossy().into_owned();
+        let saved = Trash::from_config(&self.config, &self.project_root).keep_copy(Path::new(&path), "training config overwrite")
+            .and_then(|kept| {
+                self.config_manager().save_training_config(&path, &template.config)?;
+                Ok(kept)
+            });
+        self.template_status = Some(match saved {
+            Ok(kept) => {
+                if let Some(item) = &kept {
+                    self.undo.record(&format!("Overwrite {}", path), UndoAction::RestoreTrashed { trash_id: item.id.clone() });
+                }
+                format!("Saved {} (derived from {}){}", path, template.source(), undo_hint(kept.is_some()))
+            }
+            Err(e) => format!("Save failed: {:#}", e),
+        });
+    }
+
+    fn play_bake_off_pair(&mut self) {
+        let (Some((_, report)), Some(row)) = (&self.bake_off, self.bake_off_table.selected()) else {
+            return;
+        };
+        let (a, b) = self.bake_off_sides;
+        let side = |index: usize| {
+            let path = row.outputs.get(index).cloned().flatten()?;
+            Some((path, report.outcomes[index].candidate.key()))
+        };
+        let (Some((a_path, a_name)), Some((b_path, b_name))) = (side(a), side(b)) else {
+            self.preview_error = Some(format!("{} / {}: not produced by both candidates", row.track, row.stem));
+            self.preview = None;
+            self.push_screen(Screen::Preview);
+            return;
+        };
+        let gain = self.preview_gain_db(&row.stem);
+        match PreviewPlayer::compare(&row.stem, (&a_path, &a_name), (&b_path, &b_name), gain) {
+            Ok(player) => {
+                self.preview = Some(player);
+                self.preview_error = None;
+            }
+            Err(e) => {
+                self.preview = None;
+                self.preview_error = Some(format!("{:#}", e));
+            }
+        }
+        self.push_screen(Screen::Preview);
+    }
+
+    fn mix_bake_off_track(&mut self) {
+        let (Some((dir, report)), Some(row)) = (&self.bake_off, self.bake_off_table.selected()) else {
+            return;
+        };
+        let Some(outcome) = report.outcomes.get(self.bake_off_sides.0) else {
+            return;
+        };
+        let (store_dir, track) = (dir.join(&outcome.store_dir), row.track.clone());
+        self.open_mix(&store_dir, &track);
+    }
+
+    pub fn open_mix(&mut self, store_dir: &Path, track: &str) {
+        self.mix = None;
+        match MixPreview::open(store_dir, track) {
+            Ok(mix) => {
+                self.mix_status = mix.playback_error.as_ref().map(|e| format!("Silent: {}", e));
+                self.mix = Some(mix);
+            }
+            Err(e) => self.mix_status = Some(format!("{:#}", e)),
+        }
+        self.push_screen(Screen::Mix);
+    }
+
+    fn handle_mix_key(&mut self, code: KeyCode) {
+        let Some(mix) = &self.mix else {
+            return;
+        };
+        let (controls, stem) = (mix.controls(), self.selected_index);
+        match code {
+            KeyCode::Char('+') | KeyCode::Char('=') => controls.set_gain_db(stem, controls.gain_db(stem) + PREVIEW_GAIN_STEP_DB),
+            KeyCode::Char('-') => controls.set_gain_db(stem, controls.gain_db(stem) - PREVIEW_GAIN_STEP_DB),
+            KeyCode::Char('m') => controls.toggle_mute(stem),
+            KeyCode::Char('s') => controls.toggle_solo(stem),
+            KeyCode::Char(' ') => controls.set_paused(!controls.is_paused()),
+            KeyCode::Left => mix.seek_by(-PREVIEW_SEEK_SECS),
+            KeyCode::Right => mix.seek_by(PREVIEW_SEEK_SECS),
+            KeyCode::Char('w') if self.mix_render.is_none() => {
+                let (store_dir, track, settings) = (mix.store_dir.clone(), mix.track.clone(), mix.settings());
+                let name = format!("Mixdown of {}", track);
+                let task = self.spawn_task(&name, OnCancel::Discard, move |ctx| mixdown::render(&store_dir, &track, &settings, ctx));
+                self.mix_render = Some(task);
+                self.mix_status = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// The mix screen's text above the stems: track, position and status.
+    pub fn mix_lines(&self) -> Vec<String> {
+        let Some(mix) = &self.mix else {
+            return vec![self.mix_status.clone().unwrap_or_else(|| "No track loaded".to_string())];
+        };
+        let duration = mix.duration_secs().map(format_clock).unwrap_or_else(|| "?".to_string());
+        let mut lines = vec![
+            format!("Track: {} in {}", mix.track, mix.store_dir.display()),
+            format!(
+                "Position: {} / {}{}",
+                format_clock(mix.position_secs()),
+                duration,
+                if mix.controls().is_paused() { " (paused)" } else { "" }
+            ),
+        ];
+        lines.extend(self.mix_status.clone());
+        lines
+    }
+
+    fn draw_mix(&self, f: &mut Frame, area: Rect) {
+        let styles = self.styles();
+        let title = Paragraph::new("Stem Mix")
+            .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+            .style(styles.title);
+
+        let items: Vec<ListItem> = self.mix.as_ref()
+            .map(|mix| {
+                (0..mix.names.len())
+                    .map(|stem| {
+                        let item = ListItem::new(mix.stem_line(stem));
+                        if stem == self.selected_index {
+                            item.style(styles.highlight)
+                        } else {
+                            item
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let len = items.len();
+        let stems = List::new(items).block(Block::default().borders(Borders::ALL).border_style(styles.border).title("Stems"));
+
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([
+                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Length(5),
+                ratatui::layout::Constraint::Min(5),
+                ratatui::layout::Constraint::Length(3),
+            ])
+            .split(area);
+
+        f.render_widget(title, chunks[0]);
+        f.render_widget(
+            Paragraph::new(self.mix_lines().join("\n"))
+                .block(Block::default().borders(Borders::ALL).border_style(styles.border))
+                .wrap(Wrap { trim: false }),
+            chunks[1],
+        );
+        f.render_widget(stems, chunks[2]);
+        self.record_list(chunks[2], 0, len);
+        f.render_widget(Paragraph::new(self.key_help()).wrap(Wrap { trim: false }), chunks[3]);
+    }
+
+    fn refresh_scratch_usage(&mut self) {
+        self.scratch_usage = Some(Scratch::from_config(&self.config, &self.project_root).usage().map_err(|e| format!("{:#}", e)));
+    }
+
+    pub fn open_preview(&mut self, store_dir: &Path, stem_path: &Path) {
+        self.preview = None;
+        let stem_name = stem_path.file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        match PreviewPlayer::open(store_dir, stem_path, self.preview_gain_db(&stem_name)) {
+            Ok(player) => {
+                self.preview = Some(player);
+                self.preview_error = None;
+            }
+            Err(e) => {
+                self.preview_error = Some(format!("{:#}", e));
+            }
+        }
+        self.push_screen(Screen::Preview);
+    }
+
+    fn preview_gain_db(&self, stem_name: &str) -> f32 {
+        self.config.preview_gains.get(stem_name).copied().unwrap_or(0.0)
+    }
+
+    fn adjust_preview_gain(&mut self, delta_db: f32) {
+        let Some(player) = &self.preview else {
+            return;
+        };
+        let gain = self.config.preview_gains
+            .entry(player.stem_name.clone())
+            .or_insert(0.0);
+        *gain += delta_db;
+        player.controls().set_stem_gain_db(*gain);
+    }
+
+    fn handle_text_key(&mut self, key: KeyEvent) {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        if ctrl && key.code == KeyCode::Char('o') && self.path_prompt_mode().is_some() {
+            self.browse_for_prompt();
+            return;
+        }
+        if self.screen == Screen::ParserTest {
+            match key.code {
+                KeyCode::Esc => self.handle_esc(),
+                KeyCode::Char('l') if ctrl => self.parser_input.clear(),
+                _ => {
+                    self.parser_input.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(field) = self.config_field.as_mut() {
+            match key.code {
+                KeyCode::Esc => {
+                    if let (Some(field), Some(form)) = (self.config_field.take(), self.config_form.as_mut()) {
+                        form.keep_draft(self.selected_index, field.text());
+                    }
+                }
+                KeyCode::Enter => self.set_config_field(),
+                _ => {
+                    field.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(path) = self.diff_prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => {
+                    self.diff_prompt = None;
+                    self.diff_first = None;
+                }
+                KeyCode::Enter => self.next_diff_path(),
+                _ => {
+                    path.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(field) = self.template_field.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.template_field = None,
+                KeyCode::Enter => self.set_template_field(),
+                _ => {
+                    field.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(path) = self.template_save.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.template_save = None,
+                KeyCode::Enter => self.save_template(),
+                _ => {
+                    path.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(field) = self.model_config_field.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.model_config_field = None,
+                KeyCode::Enter => self.set_model_config_value(),
+                _ => {
+                    field.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(path) = self.sweep_prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.sweep_prompt = None,
+                KeyCode::Enter => self.generate_sweep(),
+                _ => {
+                    path.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(input) = self.batch_prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.batch_prompt = None,
+                KeyCode::Enter => self.add_batch_item(),
+                _ => {
+                    input.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(input) = self.monitor_prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.monitor_prompt = None,
+                KeyCode::Enter => self.monitor_run(),
+                _ => {
+                    input.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(epochs) = self.epochs_prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.epochs_prompt = None,
+                KeyCode::Enter => match epochs.text().trim().parse::<usize>() {
+                    Ok(epochs) if epochs > 0 => {
+                        self.planned_epochs = Some(epochs);
+                        self.epochs_prompt = None;
+                        self.training_status = None;
+                    }
+                    _ => self.training_status = Some("The epoch count must be a whole number above 0".to_string()),
+                },
+                _ => {
+                    epochs.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(path) = self.export_prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.export_prompt = None,
+                KeyCode::Enter => self.export_bundle(),
+                _ => {
+                    path.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(path) = self.import_prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.import_prompt = None,
+                KeyCode::Enter => self.preview_import(),
+                _ => {
+                    path.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(path) = self.python_prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.python_prompt = None,
+                KeyCode::Enter => self.set_python_executable(),
+                _ => {
+                    path.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(path) = self.checkpoint_prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.checkpoint_prompt = None,
+                KeyCode::Enter => self.bind_checkpoint(),
+                _ => {
+                    path.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(path) = self.model_config_prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.model_config_prompt = None,
+                KeyCode::Enter => self.bind_config(),
+                _ => {
+                    path.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(filter) = self.model_filter.as_mut() {
+            match key.code {
+                KeyCode::Esc => {
+                    self.model_filter = None;
+                    self.selected_index = 0;
+                }
+                KeyCode::Enter => self.handle_enter(),
+                KeyCode::Up => self.handle_up(),
+                KeyCode::Down => self.handle_down(),
+                _ => {
+                    if filter.handle_key(key) {
+                        self.selected_index = 0;
+                    }
+                }
+            }
+            return;
+        }
+        if let Some(name) = self.preset_name.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.preset_name = None,
+                KeyCode::Enter => self.save_preset(),
+                _ => {
+                    name.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(folder) = self.input_folder_prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.input_folder_prompt = None,
+                KeyCode::Enter => self.list_input_files(),
+                _ => {
+                    folder.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(path) = self.config_path_prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.config_path_prompt = None,
+                KeyCode::Enter => {
+                    let path = self.config_path_prompt.take().map(|path| path.text().trim().to_string()).unwrap_or_default();
+                    if self.screen == Screen::Validation {
+                        if let Some(form) = self.validation_form.as_mut() {
+                            form.config_path = path;
+                        }
+                    } else if let Some(form) = self.inference_form.as_mut() {
+                        form.config_path = path;
+                    }
+                }
+                _ => {
+                    path.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(file) = self.ensemble_file_prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.ensemble_file_prompt = None,
+                KeyCode::Enter => {
+                    if let Some(file) = self.ensemble_file_prompt.take() {
+                        self.add_ensemble_file(file.text().trim());
+                    }
+                }
+                _ => {
+                    file.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(weights) = self.ensemble_weights_prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.ensemble_weights_prompt = None,
+                KeyCode::Enter => {
+                    if let Some(weights) = self.ensemble_weights_prompt.take() {
+                        self.set_ensemble_weights(weights.text());
+                    }
+                }
+                _ => {
+                    weights.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(output) = self.ensemble_output_prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.ensemble_output_prompt = None,
+                KeyCode::Enter => {
+                    if let Some(output) = self.ensemble_output_prompt.take() {
+                        self.ensemble_form.output_path = output.text().trim().to_string();
+                    }
+                }
+                _ => {
+                    output.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(folder) = self.valid_path_prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.valid_path_prompt = None,
+                KeyCode::Enter => {
+                    if let (Some(folder), Some(form)) = (self.valid_path_prompt.take(), self.validation_form.as_mut()) {
+                        form.valid_path = folder.text().trim().to_string();
+                    }
+                }
+                _ => {
+                    folder.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(folder) = self.store_dir_prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.store_dir_prompt = None,
+                KeyCode::Enter => {
+                    if let (Some(folder), Some(form)) = (self.store_dir_prompt.take(), self.inference_form.as_mut()) {
+                        form.store_dir = folder.text().trim().to_string();
+                    }
+                }
+                _ => {
+                    folder.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(folder) = self.rename_prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.rename_prompt = None,
+                KeyCode::Enter => self.preview_stem_renames(),
+                _ => {
+                    folder.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(folder) = self.verify_prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.verify_prompt = None,
+                KeyCode::Enter => self.verify_stems(),
+                _ => {
+                    folder.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(folder) = self.pull_prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.pull_prompt = None,
+                KeyCode::Enter => self.start_pull(),
+                _ => {
+                    folder.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(args) = self.extra_args_prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.extra_args_prompt = None,
+                KeyCode::Enter => {
+                    if let (Some(args), Some(form)) = (self.extra_args_prompt.take(), self.inference_form.as_mut()) {
+                        match shell_words::split(args.text()) {
+                            Ok(words) => form.extra_args = words,
+                            Err(e) => self.inference_status = Some(format!("{:#}", e)),
+                        }
+                    }
+                }
+                _ => {
+                    args.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(pattern) = self.exclude_prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.exclude_prompt = None,
+                KeyCode::Enter => self.exclude_tracks(),
+                _ => {
+                    pattern.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(editor) = self.notes_editor.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.notes_editor = None,
+                KeyCode::Char('s') if ctrl => self.save_notes(),
+                _ => {
+                    editor.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(root) = self.import_root.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.import_root = None,
+                KeyCode::Enter => self.import_results(),
+                _ => {
+                    root.handle_key(key);
+                }
+            }
+            return;
+        }
+        if let Some(label) = self.label_editor.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.label_editor = None,
+                KeyCode::Enter => self.save_label(),
+                _ => {
+                    label.handle_key(key);
+                }
+            }
+            return;
+        }
+        match key.code {
+            KeyCode::Esc => {
+                self.history_filter.clear();
+                self.history_filter_active = false;
+                self.apply_history_filter();
+            }
+            KeyCode::Enter => self.history_filter_active = false,
+            _ => {
+                if self.history_filter.handle_key(key) {
+                    self.apply_history_filter();
+                }
+            }
+        }
+    }
+
+    fn handle_screen_key(&mut self, code: KeyCode) {
+        if self.screen == Screen::Home && code == KeyCode::Char('d') {
+            self.open_dashboard();
+            return;
+        }
+        if self.screen == Screen::Home
+            && let KeyCode::Char(digit @ '1'..='8') = code
+        {
+            self.selected_index = digit as usize - '1' as usize;
+            self.open_home_item(self.selected_index);
+            return;
+        }
+        if self.screen == Screen::Home && code == KeyCode::Char('e') {
+            self.open_home_item(8);
+            return;
+        }
+        if self.screen == Screen::Environment && code == KeyCode::Char('r') {
+            self.check_environment();
+            return;
+        }
+        if self.screen == Screen::Home && code == KeyCode::Char('r') {
+            self.recent_status = None;
+            self.push_screen(Screen::Recent);
+            return;
+        }
+        if self.screen == Screen::Recent && code == KeyCode::Char('d') {
+            self.remove_recent_config();
+            return;
+        }
+        if self.screen == Screen::Home && code == KeyCode::Char('j') {
+            self.push_screen(Screen::Jobs);
+            return;
+        }
+        if self.screen == Screen::Jobs {
+            self.handle_jobs_key(code);
+            return;
+        }
+        if self.screen == Screen::RunDetail && code == KeyCode::Char('p') {
+            match self.run_detail.as_ref().map(|detail| (detail.remote.clone(), detail.folder.clone())) {
+                Some((Some(source), folder)) => {
+                    let destination = folder.as_deref().and_then(Path::parent).map_or(self.project_root.clone(), |parent| self.project_path(parent));
+                    self.offer_pull(source, &destination);
+                }
+                Some((None, _)) => self.pull_status = Some("This run went on this machine; there is nothing to pull".to_string()),
+                None => {}
+            }
+            return;
+        }
+        if self.screen == Screen::Inference && code == KeyCode::Char('P') {
+            let host = self.remote_host(self.inference_host.as_deref());
+            match (host, self.inference_form.as_ref().map(|form| form.store_dir.clone())) {
+                (Some(host), Some(store_dir)) if !store_dir.trim().is_empty() => {
+                    let destination = self.project_path(&store_dir).parent().map_or(self.project_root.clone(), Path::to_path_buf);
+                    self.offer_pull(host.location(&store_dir), &destination);
+                }
+                (Some(_), _) => self.inference_status = Some("Set the output folder first".to_string()),
+                (None, _) => self.inference_status = Some("Inference runs on this machine; H picks a remote host to pull from".to_string()),
+            }
+            return;
+        }
+        if self.screen == Screen::Training && code == KeyCode::Char('t') {
+            self.start_training();
+            return;
+        }
+        if self.screen == Screen::Training && code == KeyCode::Char('a') {
+            self.queue_training();
+            return;
+        }
+        if self.screen == Screen::Training && code == KeyCode::Char('g') {
+            self.open_config_form();
+            if self.config_form.is_some() {
+                self.gpu_devices = gpu::list_devices();
+                self.devices_status = None;
+                self.push_screen(Screen::Devices);
+            }
+            return;
+        }
+        if matches!(self.screen, Screen::Training | Screen::Inference) && code == KeyCode::Char('H') {
+            self.hosts_status = self.config.remote_hosts.is_empty()
+                .then(|| "No remote_hosts in tui_config.yaml; add one to run over ssh".to_string());
+            self.push_screen(Screen::Hosts);
+            return;
+        }
+        if self.screen == Screen::Ensemble {
+            self.handle_ensemble_key(code);
+            return;
+        }
+        if self.screen == Screen::Hosts {
+            if code == KeyCode::Char('c') {
+                self.test_host();
+            }
+            return;
+        }
+        if self.screen == Screen::Devices {
+            match code {
+                KeyCode::Char(' ') => self.toggle_device(),
+                KeyCode::Char('r') => {
+                    self.gpu_devices = gpu::list_devices();
+                    self.devices_status = Some(format!("{} GPU(s) found", self.gpu_devices.len()));
+                }
+                _ => {}
+            }
+            return;
+        }
+        if self.screen == Screen::Training && code == KeyCode::Char('v') {
+            self.push_screen(Screen::TrainingQueue);
+            return;
+        }
+        if self.screen == Screen::Training && code == KeyCode::Char('w') {
+            self.sweep_prompt = Some(TextInput::single_line().with_text("sweep.yaml"));
+            return;
+        }
+        if self.screen == Screen::Training && code == KeyCode::Char('m') {
+            match &self.monitor {
+                Some((_, task)) => task.cancel(),
+                None if self.training.is_some() => self.training_status = Some("Training is running; there is no room to follow another run".to_string()),
+                None => {
+                    let results = self.config_form.as_ref().map(|form| form.config.results_path.clone()).unwrap_or_default();
+                    self.monitor_prompt = Some(TextInput::single_line().with_text(&results));
+                }
+            }
+            return;
+        }
+        if self.screen == Screen::Training && code == KeyCode::Char('n') {
+            let epochs = self.planned_epochs.map(|epochs| epochs.to_string()).unwrap_or_default();
+            self.epochs_prompt = Some(TextInput::single_line().with_text(&epochs));
+            return;
+        }
+        if self.screen == Screen::Sweep && code == KeyCode::Char('v') {
+            self.push_screen(Screen::TrainingQueue);
+            return;
+        }
+        if self.screen == Screen::TrainingQueue {
+            self.handle_queue_key(code);
+            return;
+        }
+        if self.screen == Screen::Inference && code == KeyCode::Char('b') {
+            self.push_screen(Screen::InferenceBatch);
+            return;
+        }
+        if self.screen == Screen::InferenceBatch {
+            self.handle_batch_key(code);
+            return;
+        }
+        if self.screen == Screen::Training && code == KeyCode::Char('e') {
+            self.export_metrics();
+            return;
+        }
+        if matches!(self.screen, Screen::Training | Screen::Inference) && code == KeyCode::Char('l') {
+            self.push_screen(Screen::Log);
+            return;
+        }
+        if self.screen == Screen::Dashboard {
+            match code {
+                KeyCode::Char('r') => self.open_dashboard(),
+                KeyCode::Char('t') => self.dashboard_times = self.dashboard_times.toggled(),
+                _ => {}
+            }
+            return;
+        }
+        if self.screen == Screen::History {
+            match code {
+                KeyCode::Char('/') => self.history_filter_active = true,
+                KeyCode::Char('n') => {
+                    if let Some(entry) = self.history_table.selected() {
+                        self.notes_editor = Some(TextInput::multi_line().with_text(&entry.notes));
+                        self.history_status = None;
+                    }
+                }
+                KeyCode::Char('r') => self.reload_history(),
+                KeyCode::Char('o') => self.open_bake_off(),
+                KeyCode::Char('c') => self.compare_runs(),
+                KeyCode::Char('v') => self.revalidate_stale(),
+                KeyCode::Char('d') => self.duplicate_run(),
+                KeyCode::Char('i') => {
+                    self.import_root = Some(TextInput::single_line());
+                    self.history_status = None;
+                }
+                KeyCode::Char('m') => self.cycle_imported_model(),
+                KeyCode::Char('l') => match self.history_table.selected() {
+                    Some(entry) if entry.kind == RunKind::Imported => {
+                        self.label_editor = Some(TextInput::single_line().with_text(entry.label.as_deref().unwrap_or_default()));
+                        self.history_status = None;
+                    }
+                    Some(_) => self.history_status = Some("Not an imported run; l fixes up imported entries".to_string()),
+                    None => {}
+                },
+                _ => {}
+            }
+            return;
+        }
+        if matches!(self.screen, Screen::Config | Screen::ConfigDiff) && code == KeyCode::Char('d') {
+            self.start_config_diff();
+            return;
+        }
+        if self.screen == Screen::Config && code == KeyCode::Char('m') {
+            self.open_model_config();
+            return;
+        }
+        if self.screen == Screen::ModelConfig && code == KeyCode::Char('s') {
+            self.save_model_config();
+            return;
+        }
+        if self.screen == Screen::Config && code == KeyCode::Char('s') {
+            self.save_config_form();
+            return;
+        }
+        if self.screen == Screen::DuplicateRun && code == KeyCode::Char('s') {
+            if let Some(template) = &self.run_template {
+                self.template_save = Some(TextInput::single_line().with_text(&format!("train_{}.yaml", template.source())));
+                self.template_status = None;
+            }
+            return;
+        }
+        if self.screen == Screen::ModelSelection {
+            let bound = self.highlighted_binding().cloned();
+            match code {
+                KeyCode::Char('/') => {
+                    self.model_filter = Some(TextInput::single_line());
+                    self.selected_index = 0;
+                    self.model_status = None;
+                }
+                KeyCode::Char('p') => self.bind_next_variant(),
+                KeyCode::Char('d') => {
+                    self.download_status = None;
+                    self.push_screen(Screen::Downloads);
+                }
+                KeyCode::Char('k') => {
+                    let path = bound.map(|binding| binding.checkpoint).unwrap_or_default();
+                    self.checkpoint_prompt = Some(TextInput::single_line().with_text(&path));
+                    self.model_status = None;
+                }
+                KeyCode::Char('g') => match bound {
+                    Some(binding) => {
+                        self.model_config_prompt = Some(TextInput::single_line().with_text(&binding.config));
+                        self.model_status = None;
+                    }
+                    None => self.model_status = Some("Bind a checkpoint first (p or k)".to_string()),
+                },
+                _ => {}
+            }
+            return;
+        }
+        if self.screen == Screen::BakeOff {
+            let count = self.bake_off.as_ref().map_or(0, |(_, report)| report.outcomes.len()).max(1);
+            match code {
+                KeyCode::Char('a') => self.bake_off_sides.0 = (self.bake_off_sides.0 + 1) % count,
+                KeyCode::Char('b') => self.bake_off_sides.1 = (self.bake_off_sides.1 + 1) % count,
+                KeyCode::Char('p') => self.play_bake_off_pair(),
+                KeyCode::Char('x') => self.mix_bake_off_track(),
+                _ => {}
+            }
+            return;
+        }
+        if self.screen == Screen::Mix {
+            self.handle_mix_key(code);
+            return;
+        }
+        if self.screen == Screen::Inference && code == KeyCode::Char('s') && self.inference_form.is_some() {
+            self.preset_name = Some(TextInput::single_line());
+            self.inference_status = None;
+            return;
+        }
+        if self.screen == Screen::Inference && code == KeyCode::Char('n') {
+            if let Some(form) = &self.inference_form {
+                self.rename_prompt = Some(TextInput::single_line().with_text(&form.store_dir));
+                self.inference_status = None;
+            }
+            return;
+        }
+        if self.screen == Screen::Inference && code == KeyCode::Char('v') {
+            if let Some(form) = &self.inference_form {
+                self.verify_prompt = Some(TextInput::single_line().with_text(&form.store_dir));
+                self.inference_status = None;
+            }
+            return;
+        }
+        if self.screen == Screen::Outputs && code == KeyCode::Char('r') {
+            if let Some(listing) = &self.output_listing {
+                let store_dir = listing.store_dir.clone();
+                self.list_outputs(&store_dir);
+            }
+            return;
+        }
+        if self.screen == Screen::Checkpoints && code == KeyCode::Char('s') {
+            self.hash_checkpoint();
+            return;
+        }
+        if self.screen == Screen::Outputs && code == KeyCode::Char('p') {
+            if let Some(listing) = &self.output_listing
+                && let Some(stem) = listing.lines().into_iter().nth(self.selected_index).and_then(|line| line.stem)
+            {
+                let store_dir = listing.store_dir.clone();
+                self.open_preview(&store_dir, &stem);
+            }
+            return;
+        }
+        if self.screen == Screen::InputFiles && code == KeyCode::Char('r') {
+            if let Some(preview) = self.input_preview.as_mut() {
+                preview.recursive = !preview.recursive;
+            }
+            self.refresh_input_preview();
+            return;
+        }
+        if self.screen == Screen::RenameStems && code == KeyCode::Char('y') {
+            self.apply_stem_renames();
+            return;
+        }
+        if self.screen == Screen::Inference && self.inference_form.is_some() {
+            match code {
+                KeyCode::Char('f') => {
+                    self.open_folder_browser();
+                    return;
+                }
+                KeyCode::Char('c') => {
+                    let form = self.inference_form.as_ref().unwrap();
+                    self.config_path_prompt = Some(TextInput::single_line().with_text(&form.config_path));
+                    self.inference_status = None;
+                    return;
+                }
+                KeyCode::Char('o') => {
+                    let form = self.inference_form.as_ref().unwrap();
+                    self.store_dir_prompt = Some(TextInput::single_line().with_text(&form.store_dir));
+                    self.inference_status = None;
+                    return;
+                }
+                KeyCode::Char('k') => {
+                    self.list_checkpoints();
+                    return;
+                }
+                KeyCode::Char('x') => {
+                    let form = self.inference_form.as_ref().unwrap();
+                    self.extra_args_prompt = Some(TextInput::single_line().with_text(&shell_words::join(&form.extra_args)));
+                    self.inference_status = None;
+                    return;
+                }
+                KeyCode::Char('r') => {
+                    self.run_inference();
+                    return;
+                }
+                KeyCode::Char('w') => {
+                    self.open_outputs();
+                    return;
+                }
+                KeyCode::Char('i') => {
+                    self.refresh_input_preview();
+                    if self.input_preview.is_some() {
+                        self.push_screen(Screen::InputFiles);
+                    } else {
+                        self.inference_status = Some("Set an input folder that exists first (f)".to_string());
+                    }
+                    return;
+                }
+                _ => {}
+            }
+        }
+        if self.screen == Screen::Validation && self.validation_form.is_some() {
+            match code {
+                KeyCode::Char('c') => {
+                    let form = self.validation_form.as_ref().unwrap();
+                    self.config_path_prompt = Some(TextInput::single_line().with_text(&form.config_path));
+                }
+                KeyCode::Char('p') => {
+                    let form = self.validation_form.as_ref().unwrap();
+                    self.valid_path_prompt = Some(TextInput::single_line().with_text(&form.valid_path));
+                }
+                KeyCode::Char('k') => self.list_checkpoints(),
+                KeyCode::Char('r') => self.run_validation(),
+                _ => {}
+            }
+            return;
+        }
+        if self.screen == Screen::Tracks {
+            match code {
+                KeyCode::Char(' ') => {
+                    if let Some(selection) = self.track_selection.as_mut() {
+                        selection.toggle(self.selected_index);
+                    }
+                }
+                KeyCode::Char('x') => {
+                    self.exclude_prompt = Some(TextInput::single_line());
+                    self.inference_status = None;
+                }
+                KeyCode::Char('a') => {
+                    if let Some(selection) = self.track_selection.as_mut() {
+                        selection.select_all();
+                        self.inference_status = None;
+                    }
+                }
+                KeyCode::Char('w') => self.remember_patterns(),
+                _ => {}
+            }
+            return;
+        }
+        if self.screen == Screen::Settings && code == KeyCode::Char('e') {
+            self.export_prompt = Some(TextInput::single_line().with_text(DEFAULT_BUNDLE_FILE));
+            return;
+        }
+        if self.screen == Screen::Settings && code == KeyCode::Char('i') {
+            self.import_prompt = Some(TextInput::single_line().with_text(DEFAULT_BUNDLE_FILE));
+            return;
+        }
+        if self.screen == Screen::Settings && code == KeyCode::Char('x') {
+            let python = self.config.python_executable.clone().unwrap_or_default();
+            self.python_prompt = Some(TextInput::single_line().with_text(&python));
+            return;
+        }
+        if self.screen == Screen::ImportBundle {
+            match code {
+                KeyCode::Tab => self.switch_import_mode(),
+                KeyCode::Char('y') => self.apply_import(),
+                _ => {}
+            }
+            return;
+        }
+        if self.screen == Screen::Settings && code == KeyCode::Char('m') {
+            self.cycle_theme();
+            return;
+        }
+        if self.screen == Screen::Settings && code == KeyCode::Char('d') {
+            self.toggle_dry_run();
+            return;
+        }
+        if self.screen == Screen::Settings && code == KeyCode::Char('o') {
+            self.toggle_mouse();
+            return;
+        }
+        if self.screen == Screen::Settings && code == KeyCode::Char('n') {
+            self.test_notification();
+            return;
+        }
+        if self.screen == Screen::Settings && code == KeyCode::Char('t') {
+            self.trash_status = None;
+            self.refresh_trash();
+            self.push_screen(Screen::Trash);
+            return;
+        }
+        if self.screen == Screen::Trash {
+            match code {
+                KeyCode::Char('d') => {
+                    if let Some(item) = self.trash_items.get(self.selected_index) {
+                        let action = format!("Delete {} for good", item.original.display());
+                        self.confirm = Some((confirmation(&action, false), ConfirmAction::DeleteTrashed(item.id.clone())));
+                    }
+                }
+                KeyCode::Char('e') if !self.trash_items.is_empty() => {
+                    let action = format!("Empty the trash ({} items)", self.trash_items.len());
+                    self.confirm = Some((confirmation(&action, false), ConfirmAction::EmptyTrash));
+                }
+                _ => {}
+            }
+            return;
+        }
+        if self.screen == Screen::Settings && code == KeyCode::Char('p') {
+            self.load_parser_rules();
+            self.push_screen(Screen::ParserTest);
+            return;
+        }
+        if self.screen == Screen::Settings && code == KeyCode::Char('c') {
+            self.scratch_status = Some(match Scratch::from_config(&self.config, &self.project_root).clean() {
+                Ok(removed) => format!("Removed {} orphaned scratch folder(s)", removed),
+                Err(e) => format!("Cleanup failed: {:#}", e),
+            });
+            self.refresh_scratch_usage();
+            return;
+        }
+        if self.screen != Screen::Preview {
+            return;
+        }
 
-        f.render_widget(title, chunks[0]);
-        f.render_widget(text, chunks[1]);
+        match code {
+            KeyCode::Tab => {
+                if let Some(player) = &self.preview {
+                    player.toggle_side();
+                }
+            }
+            KeyCode::Char(' ') => {
+                if let Some(player) = &self.preview {
+                    player.toggle_pause();
+                }
+            }
+            KeyCode::Left => {
+                if let Some(player) = &self.preview {
+                    player.seek_by(-PREVIEW_SEEK_SECS);
+                }
+            }
+            KeyCode::Right => {
+                if let Some(player) = &self.preview {
+                    player.seek_by(PREVIEW_SEEK_SECS);
+                }
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') => self.adjust_preview_gain(PREVIEW_GAIN_STEP_DB),
+            KeyCode::Char('-') => self.adjust_preview_gain(-PREVIEW_GAIN_STEP_DB),
+            _ => {}
+        }
     }
 
-    fn show_help(&self) {
+    /// Opens the Home menu entry at `index`, as Enter or its digit does.
+    fn open_home_item(&mut self, index: usize) {
+        let screen = match index {
+            0 => Screen::ModelSelection,
+            1 => Screen::Config,
+            2 => Screen::Training,
+            3 => Screen::Inference,
+            4 => Screen::Validation,
+            5 => Screen::History,
+            6 => Screen::Settings,
+            7 => Screen::Ensemble,
+            8 => {
+                self.push_screen(Screen::Environment);
+                self.check_environment();
+                return;
+            }
+            9 => {
+                self.request_quit();
+                return;
+            }
+            _ => {
+                self.help_visible = true;
+                return;
+            }
+        };
+        if screen == Screen::Settings {
+            self.refresh_scratch_usage();
+        }
+        if screen == Screen::History {
+            self.reload_history();
+        }
+        if screen == Screen::Inference {
+            self.open_inference_setup();
+        }
+        if screen == Screen::Config {
+            self.open_config_form();
+        }
+        if screen == Screen::Validation {
+            self.open_validation_setup();
+        }
+        let training = screen == Screen::Training;
+        self.push_screen(screen);
+        if training {
+            self.offer_resume();
+            self.check_storage();
+        }
     }
 
     fn handle_enter(&mut self) {
         match self.screen {
-            Screen::Home => {
-                self.previous_screen = Some(Screen::Home);
-                self.screen = Screen::ModelSelection;
-                self.selected_index = 0;
+            Screen::Home => self.open_home_item(self.selected_index),
+            Screen::Outputs => {
+                let stem = self.output_listing.as_ref()
+                    .and_then(|listing| listing.lines().into_iter().nth(self.selected_index))
+                    .and_then(|line| line.stem);
+                if let Some(stem) = stem {
+                    self.output_status = Some(format!("Opening {}…", stem.display()));
+                    self.player_request = Some(stem);
+                }
             }
+            Screen::Jobs => match self.selected_job().and_then(|id| self.supervisor.get(id)) {
+                Some(job) if job.log().is_some() => self.attach_job(job.id()),
+                Some(job) => self.jobs_status = Some(format!("'{}' has no log to show here", job.name())),
+                None => {}
+            },
             Screen::ModelSelection => {
-                let models = ModelType::all_models();
-                if self.selected_index < models.len() {
-                    self.selected_model = Some(models[self.selected_index].clone());
+                let Some(model_type) = self.listed_models().into_iter().nth(self.selected_index) else {
+                    return;
+                };
+                if self.model_filter.take().is_some() {
+                    self.selected_index = ModelType::all_models().iter().position(|model| *model == model_type).unwrap_or(0);
+                }
+                self.config.select_model(model_type);
+                self.save_model_choice("Select model");
+            }
+            Screen::Inference => self.apply_preset(),
+            Screen::Hosts => self.pick_host(),
+            Screen::ModelConfig => self.edit_model_config_row(),
+            Screen::Config => {
+                if let Some(form) = &self.config_form {
+                    self.config_field = Some(TextInput::single_line().with_text(&form.edit_text(self.selected_index)));
+                    self.config_form_status = None;
+                }
+            }
+            Screen::Tracks => self.use_track_selection(),
+            Screen::Checkpoints => {
+                let Some(checkpoint) = self.checkpoints.get(self.selected_index) else {
+                    return;
+                };
+                let path = checkpoint.path.to_string_lossy().to_string();
+                let status = Some(format!("Checkpoint: {}", path));
+                match self.previous_screen() {
+                    Some(Screen::Validation) => {
+                        if let Some(form) = self.validation_form.as_mut() {
+                            form.start_checkpoint = path;
+                        }
+                        self.validation_status = status;
+                    }
+                    _ => {
+                        if let Some(form) = self.inference_form.as_mut() {
+                            form.start_checkpoint = path;
+                        }
+                        self.inference_status = status;
+                    }
+                }
+                self.checkpoints.clear();
+                self.pop_screen();
+            }
+            Screen::DuplicateRun => {
+                if let Some(template) = &self.run_template {
+                    self.template_field = Some(TextInput::single_line().with_text(&template.value(self.selected_index)));
+                    self.template_status = None;
+                }
+            }
+            Screen::Recent => self.open_recent_config(),
+            Screen::Downloads => self.start_download(),
+            Screen::Trash => {
+                if let Some(item) = self.trash_items.get(self.selected_index) {
+                    let id = item.id.clone();
+                    self.trash_status = Some(match self.restore_trashed(&id) {
+                        Ok(()) => "Restored".to_string(),
+                        Err(e) => format!("{:#}", e),
+                    });
+                    self.refresh_trash();
                 }
             }
             _ => {}
@@ -357,26 +8553,1297 @@ impl App {
     }
 
     fn handle_down(&mut self) {
-        let max_index = match self.screen {
-            Screen::Home => 6,
-            Screen::ModelSelection => ModelType::all_models().len() - 1,
-            _ => 0,
-        };
-        if self.selected_index < max_index {
+        if self.selected_index < self.max_index() {
             self.selected_index += 1;
         }
     }
 
-    fn handle_esc(&mut self) {
+    /// The last index `selected_index` can take on the current screen.
+    fn max_index(&self) -> usize {
         match self.screen {
-            Screen::ModelSelection | Screen::Config | Screen::Training | Screen::Inference | Screen::Validation => {
-                self.previous_screen = Some(self.screen.clone());
-                self.screen = Screen::Home;
+            Screen::Home => HOME_MENU.len() - 1,
+            Screen::ModelSelection => self.listed_models().len().saturating_sub(1),
+            Screen::Inference => self.inference_form.as_ref()
+                .map_or(0, |form| self.config.presets(&form.model_type).len().saturating_sub(1)),
+            Screen::Tracks => self.track_selection.as_ref()
+                .map_or(0, |selection| selection.tracks().len().saturating_sub(1)),
+            Screen::Checkpoints => self.checkpoints.len().saturating_sub(1),
+            Screen::Trash => self.trash_items.len().saturating_sub(1),
+            Screen::Recent => self.config.recent_configs.len().saturating_sub(1),
+            Screen::Downloads => self.download_entries().len().saturating_sub(1),
+            Screen::Devices => self.device_lines().len().saturating_sub(1),
+            Screen::Hosts => self.config.remote_hosts.len(),
+            Screen::Ensemble => self.ensemble_form.files.len().saturating_sub(1),
+            Screen::TrainingQueue => self.training_queue.runs().len().saturating_sub(1),
+            Screen::InferenceBatch => self.inference_batch.items().len().saturating_sub(1),
+            Screen::Sweep => 0,
+            Screen::ModelConfig => self.model_config.as_ref().map_or(0, |editor| editor.rows().len().saturating_sub(1)),
+            Screen::DuplicateRun => template::FIELDS.len() - 1,
+            Screen::Config => FORM_FIELDS.len() - 1,
+            Screen::ConfigDiff => self.config_diff_lines().len().saturating_sub(1),
+            Screen::Dashboard => self.dashboard.as_ref().map_or(0, |dashboard| dashboard.runs.len().saturating_sub(1)),
+            Screen::Mix => self.mix.as_ref().map_or(0, |mix| mix.names.len().saturating_sub(1)),
+            Screen::Jobs => self.supervisor.jobs().len().saturating_sub(1),
+            Screen::RenameStems => self.rename_plan.as_ref().map_or(0, |(_, plan)| plan.lines().len().saturating_sub(1)),
+            Screen::Outputs => self.output_listing.as_ref().map_or(0, |listing| listing.lines().len().saturating_sub(1)),
+            _ => 0,
+        }
+    }
+
+    /// Goes to `screen`; Esc comes back to the current one with its
+    /// selection.
+    fn push_screen(&mut self, screen: Screen) {
+        self.nav_stack.push((self.screen.clone(), self.selected_index));
+        self.screen = screen;
+        self.selected_index = 0;
+    }
+
+    /// Back to the screen this one was opened from, or Home.
+    fn pop_screen(&mut self) {
+        (self.screen, self.selected_index) = self.nav_stack.pop().unwrap_or((Screen::Home, 0));
+    }
+
+    /// Back to `screen` where it is on the stack, skipping the screens
+    /// opened from it; to a fresh `screen` if it isn't.
+    fn back_to(&mut self, screen: Screen) {
+        match self.nav_stack.iter().rposition(|(entry, _)| *entry == screen) {
+            Some(at) => {
+                self.nav_stack.truncate(at + 1);
+                self.pop_screen();
+            }
+            None => {
+                self.screen = screen;
                 self.selected_index = 0;
             }
-            Screen::Home => {
-                self.should_quit = true;
+        }
+    }
+
+    /// The screen Esc goes back to.
+    pub fn previous_screen(&self) -> Option<&Screen> {
+        self.nav_stack.last().map(|(screen, _)| screen)
+    }
+
+    fn handle_esc(&mut self) {
+        match self.screen {
+            Screen::BakeOff => self.bake_off = None,
+            Screen::RunDetail => self.run_detail = None,
+            Screen::Compare => self.comparison = None,
+            Screen::DuplicateRun => {
+                self.run_template = None;
+                self.template_status = None;
+            }
+            Screen::Mix => {
+                self.mix = None;
+                self.mix_status = None;
+            }
+            Screen::Dashboard => self.dashboard = None,
+            Screen::Jobs => self.jobs_status = None,
+            Screen::ModelConfig if self.model_config.as_ref().is_some_and(ModelConfigEditor::is_changed) => {
+                let path = self.model_config.as_ref().map(|editor| editor.path().display().to_string()).unwrap_or_default();
+                self.confirm = Some((format!("Discard the unsaved changes to {}? (y/n)", path), ConfirmAction::DiscardModelConfig));
+                return;
+            }
+            Screen::ModelConfig => {
+                self.model_config = None;
+                self.model_config_status = None;
+            }
+            Screen::ImportBundle => {
+                self.import_preview = None;
+                self.config_status = Some("Import cancelled".to_string());
+            }
+            Screen::Tracks => {
+                self.track_selection = None;
+                self.inference_status = None;
+            }
+            Screen::Checkpoints => {
+                self.checkpoints.clear();
+                self.inference_status = None;
+            }
+            Screen::Validation if self.validation.is_some() => {
+                if let Some(task) = &self.validation {
+                    task.cancel();
+                }
+                self.validation_status = Some("Stopping validation".to_string());
+                return;
+            }
+            Screen::Ensemble if self.ensemble.is_some() => {
+                if let Some(task) = &self.ensemble {
+                    task.cancel();
+                }
+                self.ensemble_status = Some("Stopping the ensemble".to_string());
+                return;
+            }
+            Screen::Reconstruction => self.reconstruction = None,
+            Screen::RenameStems => {
+                self.rename_plan = None;
+                self.inference_status = Some("Nothing renamed".to_string());
+            }
+            Screen::Preview => {
+                self.preview = None;
+                self.preview_error = None;
+            }
+            Screen::Training if self.training_running() => {
+                self.leave_prompt = Some(Leaving::Screen);
+                return;
+            }
+            Screen::Inference if self.inference_running() => {
+                self.leave_prompt = Some(Leaving::Screen);
+                return;
+            }
+            // A finished run's numbers stay until the screen is left.
+            Screen::Training if self.training.is_none() => {
+                self.training_history.clear();
+                self.training_status = None;
+            }
+            Screen::Home if self.nav_stack.is_empty() => {
+                self.request_quit();
+                return;
+            }
+            _ => {}
+        }
+        self.pop_screen();
+    }
+}
+
+/// The key line of `screen`'s footer, which the help overlay lists too.
+pub fn screen_key_help(screen: &Screen) -> &'static str {
+    match screen {
+        Screen::Home => "Up/Down: choose    Enter or 1-8: open    e: check the python environment    r: recent configs    d: watch runs    j: jobs",
+        Screen::Jobs => "Up/Down: choose job    Enter: attach to a run's log    k/s: stop the job    a: start a queued job anyway    p: py-spy stack dump    z: snooze hang warning    Esc: back",
+        Screen::ModelSelection => "Enter: select    /: search    p: bind next pretrained variant    d: download pretrained…    k: bind checkpoint    g: set bound config",
+        Screen::Downloads => "Enter: download and bind    Ctrl+X: stop (Enter resumes)    Esc: back",
+        Screen::Devices => "Space: use or drop the device    r: look for GPUs again    Esc: back",
+        Screen::Environment => "r: check again    Esc: back",
+        Screen::Hosts => "Up/Down, Enter: run there    c: test the connection    Esc: back",
+        Screen::Ensemble => "a: add a stem file    d: drop the selected one    w: weights    t: ensemble type    o: output file    r: run    PgUp/PgDn, Home/End: scroll output    Esc: back (stops a run)",
+        Screen::Inference => "Enter: apply preset    c/k/f/o: set config, checkpoint, input files, output folder    i: list the input audio files    r: run    w: stems of the last run    b: batch of folders and files    s: save current settings as preset    n: rename stems in an output folder    v: verify stem sums    x: extra inference.py arguments    H: run on a remote host    P: pull the output folder from it    PgUp/PgDn, Home/End: scroll output    l: full-screen output    Esc: back",
+        Screen::Checkpoints => "Up/Down: choose    Enter: use this checkpoint    s: its SHA-256    Esc: back",
+        Screen::Log => "Up/Down, PgUp/PgDn: scroll    Home/End: first line, follow the latest    Esc: back",
+        Screen::RenameStems => "y: apply renames    Esc: back",
+        Screen::Reconstruction => "Up/Down: choose track    Enter: details    </>/s: sort    Esc: back",
+        Screen::Tracks => "Space: toggle    x: exclude pattern    a: select all    w: remember patterns for this folder    Enter: use selection    Esc: back",
+        Screen::Recent => "Enter: load config    d: remove from the list    Esc: back",
+        Screen::Outputs => "Up/Down: choose stem    Enter: open it with the player    p: A/B it against the mixture    r: list again    Esc: back",
+        Screen::InputFiles => "Up/Down: choose file    </>/s: sort    r: include subfolders or not    Esc: back",
+        Screen::InferenceBatch => "Up/Down: choose item    a: add a folder or file    d: remove    s: start or resume    x: retry the failed ones    Esc: back",
+        Screen::TrainingQueue => "Up/Down: choose run    Shift+Up/Down: move it    d: remove    s: start or resume    f: stop or go on after a failure    w: its sweep    Esc: back",
+        Screen::Sweep => "Up/Down, </> and s: results table    Enter: details    v: queue    Esc: back",
+        Screen::Trash => "Enter: restore    d: delete for good    e: empty trash    u: undo last    Esc: back",
+        Screen::History => "/: filter    n: edit notes    c: compare (c on one run, then on another)    o: open bake-off    d: duplicate run    i: import folder    m/l: fix model/label    v: re-validate stale    r: reload    Enter: details    s: sort    Esc: back",
+        Screen::DuplicateRun => "Up/Down: choose field    Enter: edit    s: save as training config    Esc: back",
+        Screen::RunDetail => "p: pull the results from the remote host    Esc: back to the history",
+        Screen::Compare => "Up/Down, </> and s: SDR table    Enter: details    Esc: back",
+        Screen::BakeOff => "a/b: model on side A/B    p: play A/B    x: mix side A's stems    Enter: details    Esc: back",
+        Screen::Mix => "Up/Down: stem    +/-: gain    m: mute    s: solo    Space: pause    Left/Right: seek    w: write mixdown    Esc: back",
+        Screen::Preview => "Tab: switch A/B    Space: pause    Left/Right: seek    +/-: stem gain",
+        Screen::Settings => "c: remove orphaned scratch folders    p: test log parser    t: recently deleted    m: theme    d: dry run    o: mouse    n: test notification    x: python interpreter    e/i: export/import settings    Esc: back",
+        Screen::ImportBundle => "Tab: switch merge/replace    y: import    Esc: cancel",
+        Screen::ParserTest => "Type or paste log lines    Ctrl+L: clear    Esc: back",
+        Screen::Dashboard => "Up/Down: choose run    t: absolute/relative times    r: look for runs again    Esc: back",
+        Screen::Config => "Up/Down: choose field    Enter: edit    s: save    m: edit the model config    d: diff two YAML configs    Esc: back",
+        Screen::ConfigDiff => "Up/Down: move    d: diff two other configs    Esc: back",
+        Screen::ModelConfig => "Up/Down: choose key    Enter: edit, toggle true/false, fold a section    s: save (backs the file up)    Esc: back",
+        Screen::Training => "t: start training with the Config screen's config    g: pick GPUs    a: add it to the queue    w: queue a sweep    v: queue    H: run on a remote host    n: planned epochs    Ctrl+X: stop    m: follow a run started elsewhere, again to let go    e: export metrics    PgUp/PgDn, Home/End: scroll log    l: full-screen log    Esc: back",
+        Screen::Validation => "c/k/p: set config, checkpoint, validation folder    r: run    Up/Down, </> and s: results table    Esc: back (stops a run)",
+    }
+}
+
+fn comparison_columns() -> Vec<Column<SdrDelta>> {
+    let sdr = |value: Option<f64>| value.map_or("-".to_string(), |sdr| format!("{:.2}", sdr));
+    vec![
+        Column::new("Instrument", 12, |row: &SdrDelta| row.name.clone()),
+        Column::new("A", 7, move |row: &SdrDelta| sdr(row.a)).sort_by(|row| SortKey::from(row.a)),
+        Column::new("B", 7, move |row: &SdrDelta| sdr(row.b)).sort_by(|row| SortKey::from(row.b)),
+        Column::new("Delta", 7, |row: &SdrDelta| row.delta().map_or("-".to_string(), |delta| format!("{:+.2}", delta)))
+            .sort_by(|row| SortKey::from(row.delta())),
+    ]
+}
+
+fn history_columns() -> Vec<Column<HistoryEntry>> {
+    vec![
+        Column::new("Started", 16, |entry: &HistoryEntry| {
+            entry.started_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string()
+        })
+        .sort_by(|entry| SortKey::from(entry.started_at.to_rfc3339())),
+        Column::new("Name", 30, |entry: &HistoryEntry| entry.name.clone())
+            .sort_by(|entry| SortKey::from(entry.name.as_str())),
+        Column::new("Label", 14, |entry: &HistoryEntry| entry.label.clone().unwrap_or_default())
+            .sort_by(|entry| entry.label.as_deref().map_or(SortKey::Missing, SortKey::from)),
+        Column::new("Model", 18, |entry: &HistoryEntry| match entry.kind {
+            RunKind::Training => entry.model_type.key().to_string(),
+            RunKind::BakeOff => format!("bake-off: {}", entry.models.join(" vs ")),
+            RunKind::Imported if entry.imported.as_ref().is_some_and(|imported| imported.needs_review()) => "?".to_string(),
+            RunKind::Imported => entry.model_type.key().to_string(),
+        })
+        .sort_by(|entry| SortKey::from(entry.model_type.key())),
+        Column::new("Status", 10, |entry: &HistoryEntry| entry.status()),
+        Column::new("Epochs", 6, |entry: &HistoryEntry| entry.epochs_completed.map(|epochs| epochs.to_string()).unwrap_or_default())
+            .sort_by(|entry| SortKey::from(entry.epochs_completed.map(|epochs| epochs as f64))),
+        Column::new("SDR", 7, |entry: &HistoryEntry| {
+            entry.sdr()
+                .map(|sdr| format!("{:.2}{}", sdr, if entry.valid_set_stale { "*" } else { "" }))
+                .unwrap_or_default()
+        })
+            .sort_by(|entry| SortKey::from(entry.sdr())),
+        // Running entries are priced up to now, so their cost counts up.
+        Column::new("Cost", 12, |entry: &HistoryEntry| {
+            entry.cost.as_ref().zip(entry.cost_amount())
+                .map(|(cost, amount)| format_amount(amount, &cost.currency))
+                .unwrap_or_default()
+        })
+        .sort_by(|entry| SortKey::from(entry.cost_amount())),
+        Column::new("SDR/cost", 8, |entry: &HistoryEntry| {
+            sdr_value(entry).map(|value| format!("{:.2}", value)).unwrap_or_default()
+        })
+        .sort_by(|entry| SortKey::from(sdr_value(entry))),
+        Column::new("GPU/RSS peak", 18, |entry: &HistoryEntry| {
+            entry.resources.as_ref().map(ResourceSummary::short).unwrap_or_default()
+        })
+        .sort_by(|entry| SortKey::from(entry.resources.as_ref().and_then(|resources| resources.peak_gpu_mib).map(|mib| mib as f64))),
+        Column::new("Notes", 30, |entry: &HistoryEntry| entry.notes.replace('\n', " / ")),
+    ]
+}
+
+/// E.g. "3 to rename, 1 unchanged, 1 collision(s): ...".
+pub fn rename_summary(plan: &RenamePlan) -> String {
+    let mut summary = format!("{} to rename, {} unchanged", plan.renames.len(), plan.unchanged);
+    if !plan.collisions.is_empty() {
+        summary.push_str(&format!(", {} collision(s): fix the naming rules before applying", plan.collisions.len()));
+    }
+    summary
+}
+
+fn sdr_value(entry: &HistoryEntry) -> Option<f64> {
+    entry.sdr().zip(entry.cost_amount()).and_then(|(sdr, amount)| sdr_per_cost(sdr, amount))
+}
+
+/// Track and stem, then a column per candidate marking which produced it.
+/// Model, config, checkpoint and validation folder, one per line.
+pub fn validation_lines(form: &ValidationConfig) -> Vec<String> {
+    let or_unset = |value: &str| if value.is_empty() { "(not set)".to_string() } else { value.to_string() };
+    vec![
+        format!("Model:      {}", form.model_type.name()),
+        format!("Config:     {}", or_unset(&form.config_path)),
+        format!("Checkpoint: {}", or_unset(&form.start_checkpoint)),
+        format!("Valid set:  {}", or_unset(&form.valid_path)),
+    ]
+}
+
+pub fn ensemble_lines(form: &EnsembleConfig) -> Vec<String> {
+    let or_unset = |value: &str| if value.is_empty() { "(not set)".to_string() } else { value.to_string() };
+    vec![
+        format!("Type:   {}", form.ensemble_type),
+        format!("Output: {}", or_unset(&form.output_path)),
+        format!("Files:  {}", form.files.len()),
+    ]
+}
+
+/// A line per stem file with its weight, e.g. "scnet/song_vocals.wav (weight 1.5)".
+pub fn ensemble_file_lines(form: &EnsembleConfig) -> Vec<String> {
+    form.files.iter()
+        .enumerate()
+        .map(|(i, file)| match form.weights.get(i) {
+            Some(weight) => format!("{} (weight {})", file, weight),
+            None => format!("{} (no weight)", file),
+        })
+        .collect()
+}
+
+/// E.g. "4 instruments, mean SDR 8.12 dB, mean SI-SDR 7.90 dB".
+pub fn validation_summary(results: &[ValidationResult]) -> String {
+    match validation::mean(results) {
+        (Some(sdr), si_sdr) => format!(
+            "{} instruments, mean SDR {:.2} dB{}",
+            results.len(),
+            sdr,
+            si_sdr.map(|si_sdr| format!(", mean SI-SDR {:.2} dB", si_sdr)).unwrap_or_default()
+        ),
+        _ => "No results yet".to_string(),
+    }
+}
+
+fn sweep_columns() -> Vec<Column<SweepResult>> {
+    vec![
+        Column::new("Run", 24, |row: &SweepResult| row.name.clone())
+            .sort_by(|row| SortKey::from(row.name.as_str())),
+        Column::new("SDR", 9, |row: &SweepResult| row.final_sdr.map_or("-".to_string(), |sdr| format!("{:.2}", sdr)))
+            .sort_by(|row| SortKey::from(row.final_sdr)),
+        Column::new("Time", 8, |row: &SweepResult| row.duration_secs.map_or("-".to_string(), |secs| format!("{}h {:02}m", secs / 3600, secs / 60 % 60)))
+            .sort_by(|row| SortKey::from(row.duration_secs.map(|secs| secs as f64))),
+        Column::new("Status", 30, |row: &SweepResult| row.status.clone()),
+    ]
+}
+
+/// E.g. "4 of 6 runs done, best lr3e-4_bs8 at 9.12 dB".
+fn sweep_summary(rows: &[SweepResult]) -> String {
+    let done = rows.iter().filter(|row| row.duration_secs.is_some()).count();
+    let best = rows.iter()
+        .filter_map(|row| row.final_sdr.map(|sdr| (row, sdr)))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b));
+    match best {
+        Some((row, sdr)) => format!("{} of {} runs done, best {} at {:.2} dB", done, rows.len(), row.name, sdr),
+        None => format!("{} of {} runs done", done, rows.len()),
+    }
+}
+
+fn validation_columns() -> Vec<Column<ValidationResult>> {
+    vec![
+        Column::new("Instrument", 20, |row: &ValidationResult| row.instrument.clone())
+            .sort_by(|row| SortKey::from(row.instrument.as_str())),
+        Column::new("SDR", 9, |row: &ValidationResult| format!("{:.2}", row.sdr))
+            .sort_by(|row| SortKey::from(Some(row.sdr))),
+        Column::new("SI-SDR", 9, |row: &ValidationResult| row.si_sdr.map_or("-".to_string(), |si_sdr| format!("{:.2}", si_sdr)))
+            .sort_by(|row| SortKey::from(row.si_sdr)),
+    ]
+}
+
+fn input_file_columns() -> Vec<Column<AudioEntry>> {
+    vec![
+        Column::new("File", 40, |row: &AudioEntry| row.name.clone())
+            .sort_by(|row| SortKey::from(row.name.as_str())),
+        Column::new("Size", 8, AudioEntry::size_text)
+            .sort_by(|row| SortKey::from(Some(row.size as f64))),
+        Column::new("Duration", 9, AudioEntry::duration_text)
+            .sort_by(|row| SortKey::from(row.duration_secs())),
+        Column::new("Rate", 9, AudioEntry::rate_text)
+            .sort_by(|row| SortKey::from(row.info.map(|info| info.sample_rate as f64))),
+    ]
+}
+
+fn reconstruction_columns() -> Vec<Column<TrackReconstruction>> {
+    vec![
+        Column::new("Track", 30, |row: &TrackReconstruction| row.track.clone())
+            .sort_by(|row| SortKey::from(row.track.as_str())),
+        Column::new("Error", 10, TrackReconstruction::error_text)
+            .sort_by(|row| SortKey::from(row.error_db)),
+        Column::new("Status", 9, |row: &TrackReconstruction| if row.flagged { "FLAGGED" } else { "ok" }.to_string()),
+        Column::new("Note", 50, |row: &TrackReconstruction| row.note.clone().unwrap_or_default()),
+    ]
+}
+
+fn bake_off_columns(report: &BakeOffReport) -> Vec<Column<StemComparison>> {
+    let mut columns = vec![
+        Column::new("Track", 30, |row: &StemComparison| row.track.clone())
+            .sort_by(|row| SortKey::from(row.track.as_str())),
+        Column::new("Stem", 12, |row: &StemComparison| row.stem.clone())
+            .sort_by(|row| SortKey::from(row.stem.as_str())),
+    ];
+    for index in 0..report.outcomes.len() {
+        columns.push(Column::new(&(index + 1).to_string(), 3, move |row: &StemComparison| {
+            if row.outputs.get(index).is_some_and(Option::is_some) { "✓" } else { "-" }.to_string()
+        }));
+    }
+    columns
+}
+
+/// The Inference screen's summary of `form`.
+pub fn inference_lines(form: &InferenceConfig) -> Vec<String> {
+    let or_unset = |value: &str| if value.is_empty() { "(not set)".to_string() } else { value.to_string() };
+    let mut lines = vec![
+        format!("Model:      {}", form.model_type.name()),
+        format!("Config:     {}", or_unset(&form.config_path)),
+        format!("Checkpoint: {}", or_unset(&form.start_checkpoint)),
+        match form.excluded_inputs.len() {
+            0 => format!("Input:      {}", or_unset(&form.input_folder)),
+            excluded => format!("Input:      {} ({} files left out)", form.input_folder, excluded),
+        },
+        format!("Output:     {}", or_unset(&form.store_dir)),
+        String::new(),
+    ];
+    lines.extend(form.options_summary());
+    lines
+}
+
+/// The interpreter runs use and where it came from, or why there is none.
+fn python_line(config: &AppConfig) -> String {
+    match (RunnerEnv::from_config(config).python(), &config.python_executable) {
+        (Ok(python), Some(_)) => python,
+        (Ok(python), None) => format!("{} (found on PATH)", python),
+        (Err(e), _) => format!("{:#}", e),
+    }
+}
+
+/// A checkpoint in the picker: its name, metadata columns, then the folder
+/// it is in.
+pub fn checkpoint_line(checkpoint: &CheckpointInfo) -> String {
+    let folder = checkpoint.path.parent().map_or(String::new(), |parent| parent.display().to_string());
+    format!("{}  {}  {}", checkpoint.file_name, checkpoint.metadata_line(), folder)
+}
+
+/// E.g. "ok in 12.3 s: songs -> separated", or "failed: Process crashed (exit 1)".
+pub fn inference_result_line(result: &InferenceResult) -> String {
+    let duration = result.duration.map(|secs| format!(" in {:.1} s", secs)).unwrap_or_default();
+    let outcome = if result.success { "ok" } else { "failed" };
+    let mut line = format!("{}{}: {} -> {}", outcome, duration, result.input_file, result.output_dir);
+    if let Some(error) = result.error_line() {
+        line.push_str(&format!("; {}", error));
+    }
+    line
+}
+
+pub fn trash_item_text(item: &TrashedItem) -> String {
+    format!(
+        "{}  {:<18} {}  ({:.1} MB){}",
+        item.trashed_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+        item.operation,
+        item.original.display(),
+        item.bytes as f64 / (1024.0 * 1024.0),
+        if item.kept_copy { "  previous version" } else { "" }
+    )
+}
+
+fn undo_hint(undoable: bool) -> &'static str {
+    if undoable { " (u undoes)" } else { "" }
+}
+
+/// A task's progress in whole tens of percent, so that plain mode and the
+/// window title change ten times over a long task, not hundreds.
+/// Room for the tqdm gauge, none before the first bar.
+fn step_height(step: &Mutex<Option<StepProgress>>) -> u16 {
+    if step.lock().unwrap().is_some() { 3 } else { 0 }
+}
+
+/// Train loss, and validation loss once there is any, over epochs.
+fn draw_loss_chart(f: &mut Frame, area: Rect, history: &[TrainingProgress], styles: &Styles) {
+    let train = training_chart::train_loss_points(history);
+    let valid = training_chart::valid_loss_points(history);
+    draw_epoch_chart(f, area, "Loss", &[("train loss", &train, styles.progress), ("valid loss", &valid, styles.warning)], 3, styles);
+}
+
+/// A chart line: its name, (epoch, value) points and style.
+type Series<'a> = (&'a str, &'a [(f64, f64)], Style);
+
+/// A line per series over epochs; empty series are left out.
+fn draw_epoch_chart(f: &mut Frame, area: Rect, title: &str, series: &[Series], digits: usize, styles: &Styles) {
+    let all: Vec<(f64, f64)> = series.iter().flat_map(|(_, points, _)| points.iter().copied()).collect();
+    let (x, y) = training_chart::bounds(&all);
+    let datasets = series.iter()
+        .filter(|(_, points, _)| !points.is_empty())
+        .map(|(name, points, style)| Dataset::default()
+            .name(*name)
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(*style)
+            .data(points))
+        .collect();
+    let labels = |bounds: [f64; 2], digits: usize| {
+        [bounds[0], (bounds[0] + bounds[1]) / 2.0, bounds[1]]
+            .iter()
+            .map(|value| Span::raw(format!("{:.*}", digits, value)))
+            .collect::<Vec<_>>()
+    };
+    let chart = Chart::new(datasets)
+        .block(Block::default().borders(Borders::ALL).border_style(styles.border).title(title))
+        .x_axis(Axis::default().title("epoch").bounds(x).labels(labels(x, 0)))
+        .y_axis(Axis::default().bounds(y).labels(labels(y, digits)));
+    f.render_widget(chart, area);
+}
+
+/// The latest tqdm bar as a gauge, with its counts and ETA.
+fn draw_step(f: &mut Frame, area: ratatui::layout::Rect, title: &str, step: &Mutex<Option<StepProgress>>, styles: &Styles) {
+    let Some(step) = *step.lock().unwrap() else {
+        return;
+    };
+    let gauge = ratatui::widgets::Gauge::default()
+        .block(Block::default().borders(Borders::ALL).border_style(styles.border).title(title))
+        .gauge_style(styles.progress)
+        .ratio(step.ratio())
+        .label(step.label());
+    f.render_widget(gauge, area);
+}
+
+pub fn task_progress(task: &TaskMonitor) -> String {
+    match task.progress().fraction() {
+        Some(fraction) => format!("{}%", (fraction * 10.0).floor() as u32 * 10),
+        None => "running".to_string(),
+    }
+}
+
+pub fn format_clock(secs: f64) -> String {
+    let total = secs.max(0.0) as u64;
+    format!("{}:{:02}", total / 60, total % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::write_wav;
+    use crate::model::CommandSpec;
+    use crate::parse_health::ParseHealthSettings;
+    use crate::plain::render;
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_key(KeyEvent::from(code));
+    }
+
+    #[test]
+    fn esc_goes_back_one_screen_with_its_selection() {
+        let mut app = App::new();
+        for _ in 0..6 {
+            press(&mut app, KeyCode::Down);
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.screen, Screen::Settings);
+        press(&mut app, KeyCode::Char('p'));
+        press(&mut app, KeyCode::Char('q'));
+        assert!(!app.should_quit, "q is typed into the parser test input");
+        assert_eq!(app.screen, Screen::ParserTest);
+
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.screen, Screen::Settings);
+        press(&mut app, KeyCode::Esc);
+        assert_eq!((app.screen.clone(), app.selected_index), (Screen::Home, 6));
+        assert!(!app.should_quit);
+        press(&mut app, KeyCode::Esc);
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn digits_open_home_entries_and_slash_searches_models() {
+        let mut app = App::new();
+        press(&mut app, KeyCode::Char('7'));
+        assert_eq!(app.screen, Screen::Settings);
+        press(&mut app, KeyCode::Esc);
+        assert_eq!((app.screen.clone(), app.selected_index), (Screen::Home, 6));
+        press(&mut app, KeyCode::Char('1'));
+        assert_eq!(app.screen, Screen::ModelSelection);
+
+        press(&mut app, KeyCode::Char('/'));
+        for c in "scnet".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        assert_eq!(app.listed_models(), [ModelType::ScNet, ModelType::ScNetTran, ModelType::ScNetMasked]);
+        for _ in 0..5 {
+            press(&mut app, KeyCode::Down);
+        }
+        assert_eq!(app.selected_index, 2, "Down stops at the last match");
+        let lines = render(&app);
+        assert!(lines.contains(&"Selected: 3. SCNet Masked - Masked variant".to_string()), "{:?}", lines);
+
+        press(&mut app, KeyCode::Esc);
+        assert_eq!((app.screen.clone(), app.selected_index), (Screen::ModelSelection, 0));
+        assert_eq!(app.listed_models(), ModelType::all_models());
+    }
+
+    #[test]
+    fn configured_keys_navigate_and_show_in_the_help() {
+        let mut app = App::new();
+        app.config.keybindings = serde_yaml::from_str("down: [down, j]\nbottom: G\nquit: ctrl+q\n").unwrap();
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(app.selected_index, 1);
+        press(&mut app, KeyCode::Char('q'));
+        assert!(!app.should_quit, "q was bound elsewhere");
+        press(&mut app, KeyCode::Char('h'));
+        let lines = render(&app);
+        assert!(lines.contains(&"down / j - Move down".to_string()), "{:?}", lines);
+        assert!(lines.contains(&"G - Go to the bottom".to_string()), "{:?}", lines);
+        assert!(lines.contains(&"j - jobs (not reachable: j is down)".to_string()), "{:?}", lines);
+        press(&mut app, KeyCode::Char('x'));
+        app.handle_key(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL));
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn the_mouse_wheel_moves_the_selection_unless_a_prompt_is_open() {
+        let mut app = App::new();
+        let mouse = |kind| MouseEvent { kind, column: 5, row: 5, modifiers: KeyModifiers::NONE };
+        app.handle_mouse(mouse(MouseEventKind::ScrollDown));
+        app.handle_mouse(mouse(MouseEventKind::ScrollDown));
+        app.handle_mouse(mouse(MouseEventKind::ScrollUp));
+        assert_eq!(app.selected_index, 1);
+        app.handle_mouse(mouse(MouseEventKind::Down(MouseButton::Left)));
+        assert_eq!(app.selected_index, 1, "nothing drawn there");
+
+        press(&mut app, KeyCode::Char('4'));
+        press(&mut app, KeyCode::Char('b'));
+        press(&mut app, KeyCode::Char('a'));
+        app.handle_mouse(mouse(MouseEventKind::ScrollDown));
+        assert!(app.batch_prompt.is_some());
+        assert_eq!(app.selected_index, 0);
+    }
+
+    #[test]
+    fn queued_runs_are_reordered_removed_and_skipped_on_config_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("queue.yaml");
+        let mut app = App::new();
+        app.config.training_queue_path = Some(path.to_string_lossy().into_owned());
+        for label in ["a", "b", "c"] {
+            let mut config = TrainingConfig::new(ModelType::ScNet);
+            config.label = Some(label.to_string());
+            config.results_path = dir.path().to_string_lossy().into_owned();
+            app.training_queue.push(config);
+        }
+        press(&mut app, KeyCode::Char('3'));
+        press(&mut app, KeyCode::Char('v'));
+        assert_eq!(app.screen, Screen::TrainingQueue);
+        press(&mut app, KeyCode::Down);
+        app.handle_key(KeyEvent::new(KeyCode::Up, KeyModifiers::SHIFT));
+        assert_eq!(app.selected_index, 0);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Char('d'));
+        let lines = render(&app);
+        assert!(lines.contains(&"Selected: 2. SCNet (a): waiting -".to_string()), "{:?}", lines);
+
+        press(&mut app, KeyCode::Char('s'));
+        assert_eq!(app.queue_status.as_deref(), Some("Queue stopped: run 1 has config errors"));
+        let saved = TrainingQueue::load(&path).unwrap();
+        let labels: Vec<_> = saved.runs().iter().map(|run| run.config.label.clone().unwrap()).collect();
+        assert_eq!(labels, ["b", "a"]);
+        assert!(saved.runs()[0].summary.as_ref().unwrap().status.starts_with("Not started: "));
+        assert_eq!(saved.next_pending(), Some(1));
+    }
+
+    #[test]
+    fn batch_items_are_added_from_the_inference_screen_and_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("batch.yaml");
+        let mut app = App::new();
+        app.config.inference_batch_path = Some(path.to_string_lossy().into_owned());
+        press(&mut app, KeyCode::Char('4'));
+        press(&mut app, KeyCode::Char('b'));
+        assert_eq!(app.screen, Screen::InferenceBatch);
+        for _ in 0..2 {
+            press(&mut app, KeyCode::Char('a'));
+            for c in dir.path().to_string_lossy().chars() {
+                press(&mut app, KeyCode::Char(c));
+            }
+            press(&mut app, KeyCode::Enter);
+        }
+        assert_eq!(app.inference_batch.items().len(), 1);
+        assert!(app.batch_status.as_deref().unwrap().ends_with("is already in the batch"));
+
+        let lines = render(&app);
+        let name = dir.path().file_name().unwrap().to_string_lossy();
+        assert!(lines.contains(&format!("Selected: 1. {}, -, waiting", name)), "{:?}", lines);
+        press(&mut app, KeyCode::Char('s'));
+        assert_eq!(app.batch_status.as_deref(), Some("Select a model and set up the Inference screen first"));
+        assert_eq!(InferenceBatch::load(&path).unwrap().items().len(), 1);
+    }
+
+    #[test]
+    fn ctrl_o_browses_for_the_prompt_path() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("album").join("disc 1")).unwrap();
+        let mut app = App::new();
+        app.config.inference_batch_path = Some(dir.path().join("batch.yaml").to_string_lossy().into_owned());
+        press(&mut app, KeyCode::Char('4'));
+        press(&mut app, KeyCode::Char('b'));
+        press(&mut app, KeyCode::Char('a'));
+        for c in dir.path().to_string_lossy().chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        app.handle_key(KeyEvent::new(KeyCode::Char('o'), KeyModifiers::CONTROL));
+        let lines = render(&app);
+        assert!(lines.contains(&"Selected: 1 of 2: ..".to_string()), "{:?}", lines);
+
+        for code in [KeyCode::Char('a'), KeyCode::Char('l'), KeyCode::Enter, KeyCode::Char(' ')] {
+            press(&mut app, code);
+        }
+        assert!(app.file_browser.is_none());
+        let album = dir.path().join("album").canonicalize().unwrap();
+        assert_eq!(app.batch_prompt.as_ref().unwrap().text(), album.to_string_lossy());
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.inference_batch.items().len(), 1);
+    }
+
+    #[test]
+    fn help_lists_the_keys_of_the_screen_or_the_file_browser_over_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = App::new();
+        app.config.inference_batch_path = Some(dir.path().join("batch.yaml").to_string_lossy().into_owned());
+        app.screen = Screen::Training;
+        press(&mut app, KeyCode::Char('h'));
+        let lines = render(&app);
+        assert_eq!(lines[..3], ["Screen: Help for Training", "On this screen:", "t - start training with the Config screen's config"]);
+        assert!(lines.contains(&"e - export metrics".to_string()), "{:?}", lines);
+        assert!(lines.contains(&"Everywhere:".to_string()) && lines.contains(&"h - Show this help".to_string()), "{:?}", lines);
+        press(&mut app, KeyCode::Char('e'));
+        assert!(!app.help_visible);
+        assert!(app.training_status.is_none(), "the key only closed the help");
+
+        app.screen = Screen::InferenceBatch;
+        press(&mut app, KeyCode::Char('a'));
+        app.handle_key(KeyEvent::new(KeyCode::Char('o'), KeyModifiers::CONTROL));
+        press(&mut app, KeyCode::F(1));
+        let lines = render(&app);
+        assert_eq!(lines[..2], ["Screen: Help for File browser", "In the browser:"]);
+        assert!(lines.contains(&"Backspace - go up".to_string()), "{:?}", lines);
+        press(&mut app, KeyCode::Esc);
+        assert!(app.file_browser.is_some(), "Esc closed the help, not the browser");
+    }
+
+    #[test]
+    fn an_input_folder_without_audio_asks_before_running() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("input");
+        std::fs::create_dir_all(input.join("disc 2")).unwrap();
+        write_wav(&input.join("disc 2").join("a.wav"), 44_100, &[vec![0.0; 44_100]]).unwrap();
+        std::fs::write(dir.path().join("config.yaml"), "audio: {}\n").unwrap();
+        std::fs::write(dir.path().join("model.ckpt"), b"").unwrap();
+        let mut form = InferenceConfig::new(ModelType::ScNet);
+        form.config_path = dir.path().join("config.yaml").to_string_lossy().into_owned();
+        form.start_checkpoint = dir.path().join("model.ckpt").to_string_lossy().into_owned();
+        form.input_folder = input.to_string_lossy().into_owned();
+        form.store_dir = dir.path().join("out").to_string_lossy().into_owned();
+        let mut app = App::new();
+        app.inference_form = Some(form);
+        app.screen = Screen::Inference;
+
+        press(&mut app, KeyCode::Char('r'));
+        let question = app.pending_confirmation().unwrap();
+        assert!(question.starts_with("There are no .wav, .flac or .mp3 files in "), "{}", question);
+        press(&mut app, KeyCode::Char('n'));
+        assert!(app.pending_launch().is_none());
+
+        press(&mut app, KeyCode::Char('i'));
+        press(&mut app, KeyCode::Char('r'));
+        let lines = render(&app);
+        assert!(lines.contains(&"Summary: 1 files, 0.0 minutes".to_string()), "{:?}", lines);
+        assert!(lines.iter().any(|line| line.contains("a.wav") && line.contains("44100 Hz")), "{:?}", lines);
+        press(&mut app, KeyCode::Esc);
+        assert!(render(&app).contains(&"Audio: 1 files, 0.0 minutes".to_string()));
+    }
+
+    #[test]
+    fn the_input_folder_is_picked_in_the_file_browser_or_typed() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("disc 2")).unwrap();
+        write_wav(&dir.path().join("disc 2").join("a.wav"), 44_100, &[vec![0.0; 44_100]]).unwrap();
+        let mut form = InferenceConfig::new(ModelType::ScNet);
+        form.input_folder = dir.path().to_string_lossy().into_owned();
+        let mut app = App::new();
+        app.inference_form = Some(form);
+        app.screen = Screen::Inference;
+
+        press(&mut app, KeyCode::Char('f'));
+        assert_eq!(app.file_browser.as_ref().unwrap().dir(), dir.path().canonicalize().unwrap());
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Char(' '));
+        assert!(app.file_browser.is_none());
+        assert_eq!(app.screen, Screen::Tracks);
+        assert_eq!(app.track_selection.as_ref().unwrap().folder(), dir.path().canonicalize().unwrap().join("disc 2"));
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.screen, Screen::Inference);
+        assert!(app.input_folder_prompt.is_none());
+
+        press(&mut app, KeyCode::Char('f'));
+        press(&mut app, KeyCode::Esc);
+        assert!(app.file_browser.is_none());
+        assert_eq!(app.prompt_title(), Some("Input folder (Enter list files, Ctrl+O browse, Esc cancel)"));
+    }
+
+    #[test]
+    fn outputs_list_stems_by_track_and_flag_missing_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let store_dir = dir.path().join("out");
+        std::fs::create_dir_all(store_dir.join("song")).unwrap();
+        std::fs::write(store_dir.join("song").join("vocals.wav"), b"").unwrap();
+        std::fs::write(dir.path().join("config.yaml"), "training:\n  instruments: [vocals, bass]\n").unwrap();
+        let mut form = InferenceConfig::new(ModelType::ScNet);
+        form.config_path = dir.path().join("config.yaml").to_string_lossy().into_owned();
+        form.store_dir = store_dir.to_string_lossy().into_owned();
+        let mut app = App::new();
+        app.inference_form = Some(form);
+        app.screen = Screen::Inference;
+
+        press(&mut app, KeyCode::Char('w'));
+        assert_eq!(app.screen, Screen::Outputs);
+        let lines = render(&app);
+        assert!(lines.contains(&"Summary: 1 tracks, 1 stems, 1 with stems missing".to_string()), "{:?}", lines);
+        assert!(lines.contains(&"song (1 stems, missing bass)".to_string()), "{:?}", lines);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert!(app.output_status.as_deref().unwrap().starts_with("Opening "));
+
+        // Without a results manifest the mixture isn't known.
+        press(&mut app, KeyCode::Char('p'));
+        assert_eq!(app.screen, Screen::Preview);
+        let lines = render(&app);
+        assert!(lines.iter().any(|line| line.starts_with("No results manifest in ")), "{:?}", lines);
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.screen, Screen::Outputs);
+    }
+
+    #[test]
+    fn model_config_is_edited_from_the_config_screen() {
+        let dir = tempfile::tempdir().unwrap();
+        let model_config = dir.path().join("config_scnet.yaml");
+        std::fs::write(&model_config, "training:\n  batch_size: 2\n  use_amp: true\nmodel:\n  dim: 384\n").unwrap();
+        let mut app = App::new();
+        let mut form = ConfigForm::open(&dir.path().join("training.yaml").to_string_lossy(), ModelType::ScNet).unwrap();
+        form.config.config_path = model_config.to_string_lossy().into_owned();
+        app.config_form = Some(form);
+        app.screen = Screen::Config;
+
+        press(&mut app, KeyCode::Char('m'));
+        assert_eq!(app.screen, Screen::ModelConfig);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Char('8'));
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        let lines = render(&app);
+        assert_eq!(lines[1..4], ["- training:", "batch_size: 8", "use_amp: false"]);
+
+        press(&mut app, KeyCode::Esc);
+        assert!(app.pending_confirmation().is_some_and(|question| question.starts_with("Discard the unsaved changes")));
+        press(&mut app, KeyCode::Char('n'));
+        press(&mut app, KeyCode::Char('s'));
+        let saved = std::fs::read_to_string(&model_config).unwrap();
+        assert!(saved.contains("batch_size: 8") && saved.contains("dim: 384"), "{}", saved);
+        let backups = std::fs::read_dir(dir.path()).unwrap().filter(|entry| {
+            entry.as_ref().unwrap().file_name().to_string_lossy().ends_with(".bak")
+        });
+        assert_eq!(backups.count(), 1);
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.screen, Screen::Config);
+    }
+
+    #[test]
+    fn a_failed_action_shows_a_modal_that_takes_keys_until_dismissed() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = App::new();
+        let mut form = ConfigForm::open(&dir.path().join("training.yaml").to_string_lossy(), ModelType::ScNet).unwrap();
+        form.config.config_path = dir.path().join("missing.yaml").to_string_lossy().into_owned();
+        app.config_form = Some(form);
+        app.screen = Screen::Config;
+
+        press(&mut app, KeyCode::Char('m'));
+        assert_eq!(app.screen, Screen::Config);
+        let lines = render(&app);
+        assert!(lines.contains(&"Error: Model config not loaded".to_string()), "{:?}", lines);
+        assert!(lines.iter().any(|line| line.starts_with("Detail: Caused by: ")), "{:?}", lines);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Char('q'));
+        assert_eq!(app.selected_index, 0);
+        assert!(!app.should_quit);
+
+        press(&mut app, KeyCode::Enter);
+        assert!(app.error.is_none());
+        assert!(app.config_field.is_none(), "the Enter only dismissed the modal");
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.selected_index, 1);
+    }
+
+    #[test]
+    fn a_checkpoint_is_hashed_as_a_task_that_ctrl_x_cancels() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model_scnet_ep_3_sdr_9.1000.ckpt");
+        std::fs::write(&path, vec![0u8; 1 << 20]).unwrap();
+        let mut app = App::new();
+        app.checkpoints = vec![CheckpointInfo::read(&path)];
+        app.screen = Screen::Checkpoints;
+
+        press(&mut app, KeyCode::Char('s'));
+        let lines = render(&app);
+        assert!(lines.contains(&"Status: Hashing model_scnet_ep_3_sdr_9.1000.ckpt…".to_string()), "{:?}", lines);
+        let task = app.tasks.last().unwrap().clone();
+        assert_eq!(task.name, "Hashing model_scnet_ep_3_sdr_9.1000.ckpt");
+        press(&mut app, KeyCode::Char('s'));
+        assert_eq!(app.checkpoint_status.as_deref(), Some("Already hashing a checkpoint; Ctrl+X cancels it"));
+        app.handle_key(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL));
+        assert!(task.is_cancelled());
+    }
+
+    #[test]
+    fn running_jobs_show_on_home_and_attach_from_the_jobs_screen() {
+        let mut app = App::new();
+        let task = Task::spawn("Validation", OnCancel::Discard, |ctx| {
+            while !ctx.is_cancelled() {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+            Ok(())
+        });
+        let log = Arc::new(Mutex::new(LogBuffer::new()));
+        log.lock().unwrap().push("Instr SDR vocals: 9.1000");
+        let spec = JobSpec { kind: JobKind::Validate, name: "Validation SCNet".into(), command: None, requirement: DeviceRequirement::None, log_path: None, results_dir: None };
+        let id = app.supervisor.begin(spec, task.monitor(), log);
+        app.supervisor.set_progress(id, "3/10 files".to_string());
+        assert!(render(&app).contains(&"Running: #1 Validation SCNet: running - 3/10 files".to_string()));
+
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.screen, Screen::Log);
+        let lines = render(&app);
+        assert!(lines.contains(&"Instr SDR vocals: 9.1000".to_string()), "{:?}", lines);
+
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('k'));
+        assert!(task.monitor().is_cancelled());
+        assert_eq!(app.jobs_status.as_deref(), Some("Stopping job #1 ('Validation SCNet')"));
+        let lines = render(&app);
+        assert!(lines.iter().any(|line| line.contains("#1 Validation SCNet: stopping")), "{:?}", lines);
+        task.join().unwrap();
+    }
+
+    #[test]
+    fn a_run_waits_in_the_job_queue_for_its_gpu_unless_started_anyway() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config.yaml");
+        std::fs::write(&config, "audio: {}\n").unwrap();
+        let checkpoint = dir.path().join("model.ckpt");
+        std::fs::write(&checkpoint, "").unwrap();
+        let mut app = App::new();
+        app.project_root = dir.path().to_path_buf();
+        app.supervisor = JobSupervisor::new(DeviceAllocator::new(vec![0]));
+        let spec = JobSpec { kind: JobKind::Train, name: "train".into(), command: None, requirement: DeviceRequirement::AnyGpu, log_path: None, results_dir: None };
+        app.supervisor.submit(spec);
+        app.supervisor.tick();
+        app.validation_form = Some(ValidationConfig {
+            model_type: ModelType::ScNet,
+            config_path: config.to_string_lossy().into_owned(),
+            start_checkpoint: checkpoint.to_string_lossy().into_owned(),
+            valid_path: dir.path().to_string_lossy().into_owned(),
+        });
+        app.screen = Screen::Validation;
+
+        press(&mut app, KeyCode::Char('r'));
+        assert_eq!(app.validation_status.as_deref(), Some("Queued, waiting for GPU; a on the Jobs screen starts it anyway"));
+        let validation = &app.supervisor.jobs()[1];
+        assert_eq!(validation.name(), "Validation SCNet");
+        let validation = validation.id();
+
+        app.screen = Screen::Jobs;
+        app.selected_index = 1;
+        press(&mut app, KeyCode::Char('a'));
+        assert_eq!(app.jobs_status.as_deref(), Some("Started 'Validation SCNet' anyway"));
+        assert_eq!(app.supervisor.status(validation), "running on GPU 0 (started anyway)");
+        assert_eq!(app.validation_status, None, "launched");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_finished_job_lists_how_heavy_it_was() {
+        let mut app = App::new();
+        let mut command = CommandSpec::new("sh");
+        command.arg("-c").arg("exit 0");
+        let id = app.supervisor.submit(JobSpec { kind: JobKind::Command, name: "convert".into(), command: Some(command), requirement: DeviceRequirement::None, log_path: None, results_dir: None });
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        while app.supervisor.resources(id).is_none() {
+            assert!(std::time::Instant::now() < deadline, "{}", app.supervisor.status(id));
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            app.supervisor.tick();
+        }
+        app.screen = Screen::Jobs;
+        let lines = render(&app);
+        assert!(lines.iter().any(|line| line.contains("convert: done (peak RSS ")), "{:?}", lines);
+    }
+
+    #[test]
+    fn a_run_started_elsewhere_is_followed_from_its_results_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = App::new();
+        let mut form = ConfigForm::open(&dir.path().join("training.yaml").to_string_lossy(), ModelType::ScNet).unwrap();
+        form.config.results_path = dir.path().to_string_lossy().into_owned();
+        app.config_form = Some(form);
+        app.screen = Screen::Training;
+        app.config.history_path = Some(dir.path().join("history.json").to_string_lossy().into_owned());
+
+        press(&mut app, KeyCode::Char('m'));
+        assert_eq!(app.monitor_prompt.as_ref().unwrap().text(), dir.path().to_string_lossy());
+        press(&mut app, KeyCode::Enter);
+        assert!(render(&app).contains(&"Error: Run not followed".to_string()));
+        press(&mut app, KeyCode::Esc);
+
+        std::fs::create_dir(dir.path().join("logs")).unwrap();
+        std::fs::write(dir.path().join("logs/stdout.log"), "Train epoch: 0 Learning rate: 9e-05\nTraining loss: 7.123e-02\n").unwrap();
+        press(&mut app, KeyCode::Char('m'));
+        press(&mut app, KeyCode::Enter);
+        let status = format!("Status: Following {}; m lets go of it, the run itself is left alone", dir.path().join("logs/stdout.log").display());
+        assert!(render(&app).contains(&status), "{:?}", render(&app));
+        let job = app.supervisor.get(1).unwrap();
+        assert!(job.is_active());
+        let log = job.log().unwrap().clone();
+        for _ in 0..100 {
+            if log.lock().unwrap().len() == 2 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        assert_eq!(log.lock().unwrap().len(), 2, "the lines written before it was followed are read");
+
+        press(&mut app, KeyCode::Char('t'));
+        assert_eq!(app.training_status.as_deref(), Some("Following a run started elsewhere; m lets go of it first"));
+        press(&mut app, KeyCode::Char('m'));
+        assert!(app.monitor_prompt.is_none(), "m let go rather than asking for another log");
+    }
+
+    #[test]
+    fn a_followed_run_with_unrecognized_output_shows_its_raw_log() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("stdout.log"), "step 1 ok\nstep 2 ok\nstep 3 ok\n").unwrap();
+        let mut app = App::new();
+        app.screen = Screen::Training;
+        app.config.history_path = Some(dir.path().join("history.json").to_string_lossy().into_owned());
+        app.config.parse_health = ParseHealthSettings { after_minutes: 0, after_lines: 3 };
+
+        press(&mut app, KeyCode::Char('m'));
+        app.monitor_prompt.as_mut().unwrap().set_text(&dir.path().join("stdout.log").to_string_lossy());
+        press(&mut app, KeyCode::Enter);
+        for _ in 0..100 {
+            if app.training_parse_notice().is_some() {
+                break;
             }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        let lines = render(&app);
+        assert!(lines.iter().any(|line| line.starts_with("Notice: Receiving output but can't parse progress (3 lines")), "{:?}", lines);
+        assert!(lines.contains(&"Notice: step 3 ok".to_string()), "the unmatched sample");
+        assert!(lines.contains(&"Log: step 1 ok".to_string()), "{:?}", lines);
+        press(&mut app, KeyCode::Char('m'));
+    }
+
+    #[test]
+    fn opening_training_offers_to_resume_from_the_newest_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let run = dir.path().join("2026-10-17_scnet");
+        std::fs::create_dir(&run).unwrap();
+        std::fs::write(run.join("model_scnet_ep_4_sdr_7.0000.ckpt"), "").unwrap();
+        let mut app = App::new();
+        let mut form = ConfigForm::open(&dir.path().join("training.yaml").to_string_lossy(), ModelType::ScNet).unwrap();
+        form.config.results_path = dir.path().to_string_lossy().into_owned();
+        app.config_form = Some(form);
+
+        app.selected_index = 2;
+        press(&mut app, KeyCode::Enter);
+        assert!(render(&app).contains(&"Confirm: Resume from model_scnet_ep_4_sdr_7.0000.ckpt (epoch 4)? (y/n)".to_string()));
+        press(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.config_form.as_ref().unwrap().config.start_checkpoint, None);
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Enter);
+        assert!(app.pending_confirmation().is_none(), "a declined checkpoint isn't offered again");
+
+        std::fs::write(run.join("last_scnet.ckpt"), "").unwrap();
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Char('y'));
+        let checkpoint = run.join("last_scnet.ckpt").to_string_lossy().into_owned();
+        assert_eq!(app.config_form.as_ref().unwrap().config.start_checkpoint, Some(checkpoint));
+        assert_eq!(app.training_status.as_deref(), Some("Resuming from last_scnet.ckpt; t starts the run"));
+    }
+
+    #[test]
+    fn the_environment_check_lists_missing_packages_with_an_install_hint() {
+        let mut app = App::new();
+        app.screen = Screen::Environment;
+        assert!(render(&app).contains(&"Not checked yet; r checks".to_string()));
+
+        let packages = crate::preflight::packages(Some(&ModelType::HtDemucs));
+        let output = "package torch 2.3.1\npackage yaml 6.0\npackage ml_collections 0.1\nmissing demucs ModuleNotFoundError\ncuda True 12.1\n";
+        app.preflight = Some(crate::preflight::PreflightReport {
+            python: "/opt/venv/bin/python".to_string(),
+            model_type: Some(ModelType::HtDemucs),
+            items: crate::preflight::parse(output, &packages),
+        });
+        let lines = render(&app);
+        for line in ["Model: Demucs4HT", "✗ demucs: ModuleNotFoundError", "✓ CUDA available (CUDA 12.1)", "Install: /opt/venv/bin/python -m pip install demucs"] {
+            assert!(lines.contains(&line.to_string()), "{} in {:?}", line, lines);
+        }
+    }
+
+    #[test]
+    fn training_goes_to_a_remote_host_over_ssh() {
+        let dir = tempfile::tempdir().unwrap();
+        let model_config = dir.path().join("config_scnet.yaml");
+        std::fs::write(&model_config, "training:\n  num_epochs: 10\n").unwrap();
+        let mut app = App::new();
+        app.config.remote_hosts = vec![crate::remote::RemoteHost {
+            name: "gpu-box".to_string(),
+            ssh_destination: "ubuntu@10.0.0.5".to_string(),
+            project_root: "/srv/msst".to_string(),
+            python: "python3".to_string(),
+        }];
+        let mut form = ConfigForm::open(&dir.path().join("training.yaml").to_string_lossy(), ModelType::ScNet).unwrap();
+        form.config.config_path = model_config.to_string_lossy().to_string();
+        form.config.data_paths = vec!["/data/musdb/train".to_string()];
+        app.config_form = Some(form);
+        app.screen = Screen::Training;
+
+        press(&mut app, KeyCode::Char('H'));
+        assert_eq!(app.screen, Screen::Hosts);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.training_host.as_deref(), Some("gpu-box"));
+        let lines = render(&app);
+        assert!(lines.contains(&"Selected: 2. [x] gpu-box (ubuntu@10.0.0.5:/srv/msst)".to_string()), "{:?}", lines);
+        assert!(lines.contains(&"Status: Runs go to gpu-box (ubuntu@10.0.0.5:/srv/msst) over ssh".to_string()), "{:?}", lines);
+
+        // The dataset folder is only on the host, so it isn't looked for here.
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('t'));
+        let command = app.pending_launch().unwrap();
+        assert!(command.starts_with("ssh -o BatchMode=yes -o ConnectTimeout=10 ubuntu@10.0.0.5 'cd /srv/msst && MSST_TUI_RUN_TAG="), "{}", command);
+        assert!(command.contains("exec python3 train.py --model_type scnet"), "{}", command);
+    }
+
+    #[test]
+    fn a_remote_run_offers_to_pull_its_results() {
+        let project = tempfile::tempdir().unwrap();
+        let results = project.path().join("results");
+        let model_config = project.path().join("model.yaml");
+        std::fs::write(&model_config, "audio: {}\n").unwrap();
+        let mut run = crate::run_dir::RunDir::create(
+            &results.join("run-1"),
+            &model_config.to_string_lossy(),
+            None,
+            ModelType::ScNet,
+            crate::model::CommandSpec::new("ssh"),
+        )
+        .unwrap();
+        run.metadata.remote = Some(crate::remote::RemoteLocation {
+            host: "gpu-box".to_string(),
+            ssh_destination: "ubuntu@10.0.0.5".to_string(),
+            path: "/srv/msst/results/run-1".to_string(),
+        });
+        run.save().unwrap();
+        let mut history = crate::history::RunHistory::load(&project.path().join("history.json")).unwrap();
+        history.record(&run);
+
+        let mut app = App::new();
+        app.run_detail = Some(history.detail(0).unwrap());
+        app.screen = Screen::RunDetail;
+        assert!(render(&app).contains(&"Remote: ubuntu@10.0.0.5:/srv/msst/results/run-1 on gpu-box".to_string()));
+        press(&mut app, KeyCode::Char('p'));
+        let lines = render(&app);
+        assert!(lines.contains(&format!("Text: {}", results.display())), "{:?}", lines);
+        press(&mut app, KeyCode::Esc);
+        assert!(app.pull_prompt.is_none());
+
+        app.run_detail.as_mut().unwrap().remote = None;
+        press(&mut app, KeyCode::Char('p'));
+        assert_eq!(app.pull_status.as_deref(), Some("This run went on this machine; there is nothing to pull"));
+    }
+
+    #[test]
+    fn two_configs_are_diffed_by_section() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("config_scnet.yaml");
+        let second = dir.path().join("config_scnet_large.yaml");
+        std::fs::write(&first, "audio:\n  chunk_size: 485100\ntraining:\n  lr: 0.0005\n  batch_size: 8\n").unwrap();
+        std::fs::write(&second, "audio:\n  chunk_size: 485100\ntraining:\n  lr: 0.0005\n  batch_size: 4\ninference:\n  num_overlap: 4\n").unwrap();
+        let mut app = App::new();
+        app.screen = Screen::Config;
+        press(&mut app, KeyCode::Char('d'));
+        app.diff_prompt.as_mut().unwrap().set_text(&first.to_string_lossy());
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.diff_prompt.as_ref().unwrap().text(), format!("{}/", dir.path().display()));
+        app.diff_prompt.as_mut().unwrap().set_text(&second.to_string_lossy());
+        press(&mut app, KeyCode::Enter);
+
+        assert_eq!(app.screen, Screen::ConfigDiff);
+        let lines = render(&app);
+        assert_eq!(
+            lines[1..],
+            [
+                "training: 1 difference",
+                "  ~ training.batch_size: 8 → 4",
+                "inference: 1 difference",
+                "  + inference: {1 key}",
+                "Selected: training: 1 difference",
+                "Keys: Up/Down: move d: diff two other configs Esc: back",
+            ]
+        );
+    }
+
+    #[test]
+    fn an_ensemble_rejects_mismatched_weights_and_missing_inputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let stems: Vec<_> = ["scnet_vocals.wav", "roformer_vocals.wav"].iter().map(|name| dir.path().join(name)).collect();
+        for stem in &stems {
+            std::fs::write(stem, b"RIFF").unwrap();
         }
+        let mut app = App::new();
+        press(&mut app, KeyCode::Char('8'));
+        assert_eq!(app.screen, Screen::Ensemble);
+        for stem in &stems {
+            press(&mut app, KeyCode::Char('a'));
+            app.ensemble_file_prompt.as_mut().unwrap().set_text(&stem.to_string_lossy());
+            press(&mut app, KeyCode::Enter);
+        }
+        press(&mut app, KeyCode::Char('t'));
+        let lines = render(&app);
+        assert!(lines.contains(&"Type: median_wave".to_string()), "{:?}", lines);
+        assert!(lines.contains(&format!("Selected: 2. {} (weight 1)", stems[1].display())), "{:?}", lines);
+
+        press(&mut app, KeyCode::Char('w'));
+        app.ensemble_weights_prompt.as_mut().unwrap().set_text("1, 2, 3");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.ensemble_status.as_deref(), Some("3 weights for 2 files; give one weight per file"));
+        assert_eq!(app.ensemble_form.weights, [1.0, 1.0]);
+
+        std::fs::remove_file(&stems[0]).unwrap();
+        press(&mut app, KeyCode::Char('r'));
+        assert_eq!(app.ensemble_status.as_deref(), Some(format!("Input not found: {}", stems[0].display()).as_str()));
+        assert!(app.tasks.is_empty());
+    }
+
+    #[test]
+    fn gpus_are_picked_for_device_ids_or_the_run_forced_onto_the_cpu() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = App::new();
+        app.config_form = Some(ConfigForm::open(&dir.path().join("training.yaml").to_string_lossy(), ModelType::ScNet).unwrap());
+        app.screen = Screen::Training;
+        press(&mut app, KeyCode::Char('g'));
+        assert_eq!(app.screen, Screen::Devices);
+
+        app.gpu_devices = vec![
+            GpuDevice { index: 0, name: "RTX 4090".to_string(), free_mib: Some(20480) },
+            GpuDevice { index: 1, name: "RTX 4090".to_string(), free_mib: Some(512) },
+        ];
+        press(&mut app, KeyCode::Char(' '));
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Char(' '));
+        assert_eq!(app.config_form.as_ref().unwrap().config.device_ids, Some(vec![0, 1]));
+        let lines = render(&app);
+        assert!(lines.contains(&"Selected: 2. [x] GPU 1 RTX 4090, 0.5 GB free  (low free memory)".to_string()), "{:?}", lines);
+        assert_eq!(
+            app.devices_status.as_deref(),
+            Some("device_ids: 0,1. GPU 1 has 0.5 GB free, under 2.0 GB; someone else's job may be using it")
+        );
+
+        app.gpu_devices.clear();
+        app.selected_index = 0;
+        press(&mut app, KeyCode::Char(' '));
+        assert!(render(&app).contains(&"Selected: 1. [x] Force CPU (--force_cpu)".to_string()));
+        let args = crate::training::build_train_command(&app.config_form.as_ref().unwrap().config);
+        assert!(args.contains(&"--force_cpu".to_string()) && !args.contains(&"--device_ids".to_string()));
+    }
+
+    #[test]
+    fn pretrained_downloads_list_the_registry_and_the_users_own() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = App::new();
+        app.config.weights_dir = Some(dir.path().to_string_lossy().into_owned());
+        app.config.downloads.push(DownloadEntry {
+            name: "my crowd".to_string(),
+            model_type: ModelType::MelBandRoformer,
+            config: "https://example.com/crowd.yaml".to_string(),
+            weights_url: "https://example.com/releases/crowd.ckpt?raw=1".to_string(),
+            sha256: Some("9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08".to_string()),
+        });
+        std::fs::write(dir.path().join("crowd.ckpt.part"), "half").unwrap();
+        std::fs::write(dir.path().join("MelBandRoformer.ckpt"), "weights").unwrap();
+        app.screen = Screen::ModelSelection;
+
+        press(&mut app, KeyCode::Char('d'));
+        let lines = render(&app);
+        assert!(lines.contains(&"Screen: Download Pretrained".to_string()), "{:?}", lines);
+        assert!(lines.contains(&"Selected: 1. Band Split RoFormer — viperx 1297 (unverified)".to_string()), "{:?}", lines);
+        assert!(lines.iter().any(|line| line.ends_with("— KimberleyJensen vocals (downloaded, unverified)")), "{:?}", lines);
+        assert!(lines.iter().any(|line| line.ends_with("— my crowd (partly downloaded)")), "{:?}", lines);
+        let binding = app.download_entries().last().unwrap().binding(app.config.weights_dir());
+        assert_eq!(binding.checkpoint, dir.path().join("crowd.ckpt").to_string_lossy());
+        assert_eq!(binding.config, dir.path().join("crowd.yaml").to_string_lossy());
+
+        press(&mut app, KeyCode::Enter);
+        let question = app.pending_confirmation().unwrap();
+        assert!(question.starts_with("No checksum is known for viperx 1297"), "{}", question);
+        press(&mut app, KeyCode::Char('n'));
+        assert!(app.download.is_none(), "an unverified download waits for a yes");
     }
 }
@@ -0,0 +1,110 @@
+use chrono::{DateTime, Local};
+use std::collections::VecDeque;
+
+use crate::jobs::RemovedJob;
+
+/// Operations kept for undo; older ones fall off.
+pub const UNDO_DEPTH: usize = 20;
+
+/// How to take a destructive operation back.
+#[derive(Debug, Clone)]
+pub enum UndoAction {
+    /// Put a trashed file or folder back; see `Trash::restore`.
+    RestoreTrashed { trash_id: String },
+    /// Put a job back in the queue.
+    Requeue(RemovedJob),
+}
+
+#[derive(Debug, Clone)]
+pub struct UndoEntry {
+    pub description: String,
+    pub at: DateTime<Local>,
+    pub action: UndoAction,
+}
+
+/// The destructive operations of this session, newest last. Only lives as
+/// long as the app; the trash outlives it, so trashed items can still be
+/// restored from the "recently deleted" view after a restart.
+#[derive(Debug, Clone, Default)]
+pub struct UndoJournal {
+    entries: VecDeque<UndoEntry>,
+}
+
+impl UndoJournal {
+    pub fn new() -> Self {
+        UndoJournal::default()
+    }
+
+    pub fn record(&mut self, description: &str, action: UndoAction) {
+        if self.entries.len() == UNDO_DEPTH {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(UndoEntry { description: description.to_string(), at: Local::now(), action });
+    }
+
+    /// Takes the newest operation off the journal, to be undone.
+    pub fn pop(&mut self) -> Option<UndoEntry> {
+        self.entries.pop_back()
+    }
+
+    /// Newest first.
+    pub fn recent(&self) -> impl Iterator<Item = &UndoEntry> {
+        self.entries.iter().rev()
+    }
+
+    /// Drops entries for a trash item restored or deleted some other way.
+    pub fn forget_trashed(&mut self, id: &str) {
+        self.entries.retain(|entry| !matches!(&entry.action, UndoAction::RestoreTrashed { trash_id } if trash_id == id));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Text for a confirmation dialog, saying plainly whether `u` can take the
+/// action back.
+pub fn confirmation(action: &str, undoable: bool) -> String {
+    if undoable {
+        format!("{}? It can be undone with u. (y/n)", action)
+    } else {
+        format!("{}? This cannot be undone. (y/n)", action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::{DeviceAllocator, DeviceRequirement, JobKind, JobSpec, JobSupervisor};
+    use crate::model::CommandSpec;
+
+    #[test]
+    fn removed_jobs_go_back_in_their_place() {
+        let mut supervisor = JobSupervisor::new(DeviceAllocator::new(vec![]));
+        let spec = |name: &str| JobSpec {
+            kind: JobKind::Command,
+            name: name.into(),
+            command: Some(CommandSpec::new("python")),
+            requirement: DeviceRequirement::AnyGpu,
+            log_path: None,
+            results_dir: None,
+        };
+        let first = supervisor.submit(spec("first"));
+        let second = supervisor.submit(spec("second"));
+        supervisor.submit(spec("third"));
+
+        let mut journal = UndoJournal::new();
+        let removed = supervisor.cancel(second).unwrap().unwrap();
+        journal.record("Remove 'second' from the queue", UndoAction::Requeue(removed));
+        journal.record("Overwrite config", UndoAction::RestoreTrashed { trash_id: "x".into() });
+        journal.forget_trashed("x");
+
+        let Some(UndoEntry { action: UndoAction::Requeue(removed), .. }) = journal.pop() else {
+            panic!("expected the queue removal");
+        };
+        supervisor.requeue(removed);
+        assert!(journal.is_empty());
+        let names: Vec<(u64, &str)> = supervisor.jobs().iter().map(|job| (job.id(), job.name())).collect();
+        assert_eq!(names, [(first, "first"), (second, "second"), (3, "third")]);
+    }
+}
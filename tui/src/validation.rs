@@ -0,0 +1,313 @@
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::sync::mpsc;
+
+use crate::log_buffer::LogBuffer;
+use crate::model::{CommandSpec, ValidationConfig, ValidationResult};
+use crate::process::ProcessState;
+use crate::project::Scripts;
+use crate::runner::RunnerEnv;
+use crate::training::piped_output;
+
+/// Summary lines of valid.py, in any of the forms it has printed:
+/// "Instr SDR vocals: 8.23", "Instr vocals sdr: 8.23 (Std: 1.2)".
+const INSTRUMENT_METRIC: &str = r"(?i)^\s*instr(?:ument)?\s+(?P<a>[\w.-]+)\s+(?P<b>[\w.-]+)\s*:\s*(?P<value>[-+]?(?:\d+\.?\d*|\.\d+)(?:[eE][-+]?\d+)?)";
+
+/// The files count of a tqdm bar, e.g. " 40%|####      | 4/10 [00:12<00:18, ...]".
+const PROGRESS_BAR: &str = r"%\|[^|]*\|\s*(?P<done>\d+)/(?P<total>\d+)";
+
+/// Metric names valid.py can print; the other word of a summary line is
+/// the instrument.
+const METRICS: [&str; 8] = ["sdr", "si_sdr", "l1_freq", "neg_log_wmse", "aura_stft", "aura_mrstft", "bleedless", "fullness"];
+
+/// How many validation files are done.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ValidationProgress {
+    pub done: usize,
+    pub total: usize,
+}
+
+impl ValidationProgress {
+    pub fn ratio(&self) -> f64 {
+        if self.total == 0 { 0.0 } else { (self.done as f64 / self.total as f64).min(1.0) }
+    }
+}
+
+/// Picks the per-instrument metrics and the file progress out of valid.py
+/// output. Lines it doesn't know are ignored.
+#[derive(Debug, Clone)]
+pub struct ValidationParser {
+    results: Vec<ValidationResult>,
+    metric: Regex,
+    bar: Regex,
+}
+
+impl Default for ValidationParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ValidationParser {
+    pub fn new() -> Self {
+        ValidationParser {
+            results: vec![],
+            metric: Regex::new(INSTRUMENT_METRIC).expect("built-in instrument metric pattern"),
+            bar: Regex::new(PROGRESS_BAR).expect("built-in progress bar pattern"),
+        }
+    }
+
+    /// Feeds one line, or one carriage-return segment of a tqdm line;
+    /// returns the progress if it was a bar.
+    pub fn push(&mut self, line: &str) -> Option<ValidationProgress> {
+        if let Some(captures) = self.bar.captures(line) {
+            return Some(ValidationProgress {
+                done: captures["done"].parse().ok()?,
+                total: captures["total"].parse().ok()?,
+            });
+        }
+        let captures = self.metric.captures(line)?;
+        let value: f64 = captures["value"].parse().ok()?;
+        let (a, b) = (&captures["a"], &captures["b"]);
+        let (instrument, metric) = if is_metric(a) { (b, a.to_lowercase()) } else { (a, b.to_lowercase()) };
+        let index = match self.results.iter().position(|result| result.instrument == instrument) {
+            Some(index) => index,
+            None => {
+                self.results.push(ValidationResult { instrument: instrument.to_string(), sdr: f64::NAN, si_sdr: None });
+                self.results.len() - 1
+            }
+        };
+        match metric.as_str() {
+            "sdr" => self.results[index].sdr = value,
+            "si_sdr" => self.results[index].si_sdr = Some(value),
+            _ => {}
+        }
+        None
+    }
+
+    /// Instruments with an SDR, in the order valid.py printed them.
+    pub fn finish(self) -> Vec<ValidationResult> {
+        self.results.into_iter().filter(|result| !result.sdr.is_nan()).collect()
+    }
+}
+
+fn is_metric(word: &str) -> bool {
+    METRICS.contains(&word.to_lowercase().as_str())
+}
+
+/// Mean SDR and, when every instrument has one, mean SI-SDR.
+pub fn mean(results: &[ValidationResult]) -> (Option<f64>, Option<f64>) {
+    if results.is_empty() {
+        return (None, None);
+    }
+    let count = results.len() as f64;
+    let sdr = results.iter().map(|result| result.sdr).sum::<f64>() / count;
+    let si_sdr = results.iter()
+        .map(|result| result.si_sdr)
+        .sum::<Option<f64>>()
+        .map(|sum| sum / count);
+    (Some(sdr), si_sdr)
+}
+
+pub struct ValidationManager {
+    process: Option<tokio::process::Child>,
+    state: ProcessState,
+    script: String,
+    runner: RunnerEnv,
+    /// What valid.py printed other than its progress bar.
+    log: Arc<Mutex<LogBuffer>>,
+}
+
+impl Default for ValidationManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ValidationManager {
+    pub fn new() -> Self {
+        ValidationManager {
+            process: None,
+            state: ProcessState::NotStarted,
+            script: Scripts::default().validation,
+            runner: RunnerEnv::default(),
+            log: Arc::new(Mutex::new(LogBuffer::new())),
+        }
+    }
+
+    pub fn log(&self) -> Arc<Mutex<LogBuffer>> {
+        self.log.clone()
+    }
+
+    pub fn set_scripts(&mut self, scripts: &Scripts) {
+        self.script = scripts.validation.clone();
+    }
+
+    pub fn set_runner(&mut self, runner: RunnerEnv) {
+        self.runner = runner;
+    }
+
+    /// Runs valid.py to the end, sending the file progress to `progress_tx`,
+    /// and returns its per-instrument metrics.
+    pub async fn run_validation(
+        &mut self,
+        config: &ValidationConfig,
+        progress_tx: mpsc::UnboundedSender<ValidationProgress>,
+    ) -> Result<Vec<ValidationResult>> {
+        let config = &config.in_root(self.runner.root());
+        let python = self.runner.python()?;
+        let spawned = self.runner.in_project(validation_command(config, &python, &self.runner.script(&self.script))).command()
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn();
+        let mut child = match spawned {
+            Ok(child) => child,
+            Err(e) => {
+                self.state = ProcessState::Failed(e.to_string());
+                return Err(e).context("Failed to spawn validation process");
+            }
+        };
+        self.state = ProcessState::Running;
+        let (stdout, stderr) = piped_output(&mut child, "validation")?;
+        self.process = Some(child);
+
+        // tqdm draws on stderr, the summary goes to stdout.
+        let stdout_task = tokio::spawn(read_output(stdout, self.log.clone(), progress_tx.clone()));
+        let stderr_task = tokio::spawn(read_output(stderr, self.log.clone(), progress_tx));
+        let mut results = stdout_task.await.context("stdout task failed")??;
+        results.extend(stderr_task.await.context("stderr task failed")??);
+
+        let status = match self.process.as_mut() {
+            Some(child) => match child.wait().await {
+                Ok(status) => status,
+                Err(e) => {
+                    self.state = ProcessState::Failed(e.to_string());
+                    return Err(e).context("Failed to wait for validation process");
+                }
+            },
+            None => bail!("Validation was stopped"),
+        };
+        self.state = ProcessState::Exited(status);
+        self.process = None;
+        if !status.success() {
+            bail!("{} exited with code {}", self.script, status.code().unwrap_or(-1));
+        }
+        if results.is_empty() {
+            bail!("No per-instrument SDR found in the output of {}", self.script);
+        }
+        Ok(results)
+    }
+
+    pub async fn stop_validation(&mut self) -> Result<()> {
+        if let Some(mut child) = self.process.take() {
+            child.kill().await.context("Failed to stop validation process")?;
+            self.state.update(Some(&mut child));
+        }
+        Ok(())
+    }
+
+    pub fn status(&mut self) -> &ProcessState {
+        self.state.update(self.process.as_mut());
+        &self.state
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.process.is_some()
+    }
+}
+
+/// Invocation of the validation `script` (valid.py upstream) by `python`.
+pub fn validation_command(config: &ValidationConfig, python: &str, script: &str) -> CommandSpec {
+    let mut cmd = CommandSpec::new(python);
+    cmd.arg(script)
+        .arg("--model_type")
+        .arg(config.model_type.key())
+        .arg("--config_path")
+        .arg(&config.config_path)
+        .arg("--start_check_point")
+        .arg(&config.start_checkpoint)
+        .arg("--valid_path")
+        .arg(&config.valid_path);
+    cmd
+}
+
+/// Reads one output stream to the end. tqdm redraws with carriage returns,
+/// so those split lines too, or the bar would only show once it is full.
+/// Lines other than the bar go to `log`.
+pub async fn read_output(
+    reader: impl AsyncRead + Unpin,
+    log: Arc<Mutex<LogBuffer>>,
+    progress_tx: mpsc::UnboundedSender<ValidationProgress>,
+) -> Result<Vec<ValidationResult>> {
+    let mut parser = ValidationParser::new();
+    let mut segments = BufReader::new(reader).split(b'\r');
+    while let Some(segment) = segments.next_segment().await.context("Failed to read validation output")? {
+        for line in String::from_utf8_lossy(&segment).lines() {
+            match parser.push(line) {
+                Some(progress) => {
+                    let _ = progress_tx.send(progress);
+                }
+                None if !line.trim().is_empty() => {
+                    log.lock().unwrap().push(line);
+                }
+                None => {}
+            }
+        }
+    }
+    Ok(parser.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_both_summary_forms() {
+        let mut parser = ValidationParser::new();
+        let output = "\
+Instr SDR vocals: 8.1234 Debug: 50
+Instr SDR other: -1.5 Debug: 50
+SDR Avg: 3.3117
+Instr vocals si_sdr: 7.5 (Std: 1.1)
+Instr vocals l1_freq: 40.2
+Instr drums: n/a
+";
+        for line in output.lines() {
+            assert_eq!(parser.push(line), None);
+        }
+        assert_eq!(
+            parser.push(" 40%|####      | 4/10 [00:12<00:18,  3.01s/it]"),
+            Some(ValidationProgress { done: 4, total: 10 })
+        );
+        let results = parser.finish();
+        assert_eq!(results, [
+            ValidationResult { instrument: "vocals".into(), sdr: 8.1234, si_sdr: Some(7.5) },
+            ValidationResult { instrument: "other".into(), sdr: -1.5, si_sdr: None },
+        ]);
+        assert_eq!(mean(&results), (Some(3.3117), None));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn tqdm_redraws_stream_as_progress() {
+        let mut child = tokio::process::Command::new("sh")
+            .args(["-c", "printf ' 50%%|#    | 1/2 [00:01<00:01]\\r100%%|##| 2/2 [00:02<00:00]\\nInstr vocals sdr: 5.5\\n'"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let log = Arc::new(Mutex::new(LogBuffer::new()));
+        let results = read_output(stdout, log.clone(), tx).await.unwrap();
+        child.wait().await.unwrap();
+        assert_eq!(log.lock().unwrap().len(), 1, "only the summary line is logged");
+
+        assert_eq!(rx.recv().await, Some(ValidationProgress { done: 1, total: 2 }));
+        assert_eq!(rx.recv().await.map(|progress| progress.ratio()), Some(1.0));
+        assert_eq!(results, [ValidationResult { instrument: "vocals".into(), sdr: 5.5, si_sdr: None }]);
+    }
+}
@@ -0,0 +1,110 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// The validation folder a result was measured against, and what was in it
+/// at the time.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ValidSetStamp {
+    pub path: String,
+    pub fingerprint: String,
+}
+
+impl ValidSetStamp {
+    pub fn take(path: &str) -> Result<Self> {
+        Ok(ValidSetStamp { path: path.to_string(), fingerprint: fingerprint(Path::new(path))? })
+    }
+
+    /// Whether the folder holds something else now. A folder that can't be
+    /// read isn't called stale: there is nothing to compare against.
+    pub fn is_stale(&self, current: Option<&str>) -> bool {
+        current.is_some_and(|current| current != self.fingerprint)
+    }
+}
+
+/// Hash of the relative path and size of every file under `dir`, sorted.
+/// No audio is read, so hours of tracks take milliseconds. Hidden files
+/// (`.DS_Store` and the like) are left out.
+pub fn fingerprint(dir: &Path) -> Result<String> {
+    let mut files = vec![];
+    collect(dir, "", &mut files)?;
+    files.sort();
+    let mut hasher = Sha256::new();
+    for (path, size) in &files {
+        hasher.update(format!("{}\t{}\n", path, size));
+    }
+    Ok(hasher.finalize()[..8].iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+fn collect(dir: &Path, prefix: &str, files: &mut Vec<(String, u64)>) -> Result<()> {
+    let entries = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read validation folder {}", dir.display()))?;
+    for entry in entries {
+        let entry = entry.with_context(|| format!("Failed to read validation folder {}", dir.display()))?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+        let relative = format!("{}{}", prefix, name);
+        let metadata = entry.metadata()
+            .with_context(|| format!("Failed to read {}", entry.path().display()))?;
+        if metadata.is_dir() {
+            collect(&entry.path(), &format!("{}/", relative), files)?;
+        } else {
+            files.push((relative, metadata.len()));
+        }
+    }
+    Ok(())
+}
+
+/// Current fingerprints by folder, each folder hashed once however many
+/// results point at it.
+#[derive(Debug, Default)]
+pub struct Fingerprints(HashMap<String, Option<String>>);
+
+impl Fingerprints {
+    pub fn current(&mut self, path: &str) -> Option<&str> {
+        self.0.entry(path.to_string())
+            .or_insert_with(|| fingerprint(Path::new(path)).ok())
+            .as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint_follows_names_and_sizes_only() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("song")).unwrap();
+        fs::write(dir.path().join("song/vocals.wav"), "abcd").unwrap();
+        let first = fingerprint(dir.path()).unwrap();
+
+        // Same size, other bytes: not decoded, so not noticed.
+        fs::write(dir.path().join("song/vocals.wav"), "wxyz").unwrap();
+        fs::write(dir.path().join(".DS_Store"), "x").unwrap();
+        assert_eq!(fingerprint(dir.path()).unwrap(), first);
+
+        fs::create_dir(dir.path().join("new_song")).unwrap();
+        fs::write(dir.path().join("new_song/vocals.wav"), "abcd").unwrap();
+        let added = fingerprint(dir.path()).unwrap();
+        assert_ne!(added, first);
+        fs::write(dir.path().join("new_song/vocals.wav"), "abcde").unwrap();
+        assert_ne!(fingerprint(dir.path()).unwrap(), added);
+    }
+
+    #[test]
+    fn only_a_different_fingerprint_is_stale() {
+        let stamp = ValidSetStamp { path: "valid".to_string(), fingerprint: "0011".to_string() };
+        assert!(!stamp.is_stale(Some("0011")));
+        assert!(stamp.is_stale(Some("2233")));
+        assert!(!stamp.is_stale(None));
+
+        let mut fingerprints = Fingerprints::default();
+        assert_eq!(fingerprints.current("/nonexistent/valid"), None);
+    }
+}
@@ -0,0 +1,226 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use crate::parser::{ParserRules, ProgressField};
+
+/// How long a job may go without output or a change in GPU utilization
+/// before it is called possibly hung.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct WatchdogSettings {
+    pub enabled: bool,
+    pub quiet_minutes: u64,
+    /// Until the first epoch is over, for dataset caching and the like,
+    /// which can be silent for a long while.
+    pub first_epoch_minutes: u64,
+}
+
+impl Default for WatchdogSettings {
+    fn default() -> Self {
+        WatchdogSettings { enabled: true, quiet_minutes: 30, first_epoch_minutes: 120 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WatchState {
+    Watching,
+    /// Quiet for longer than the threshold.
+    PossiblyHung { quiet_for: Duration },
+    /// Warned about and put off until `until`; activity ends it early.
+    Snoozed { until: Instant },
+}
+
+/// Inactivity timer of one job. Every method takes the time, so tests can
+/// drive it with a made-up clock.
+#[derive(Debug, Clone)]
+pub struct Watchdog {
+    settings: WatchdogSettings,
+    last_activity: Instant,
+    /// First epoch number seen; a different one means it is over.
+    first_epoch: Option<usize>,
+    first_epoch_done: bool,
+    utilization: Option<f64>,
+    state: WatchState,
+}
+
+impl Watchdog {
+    pub fn new(settings: WatchdogSettings, started_at: Instant) -> Self {
+        Watchdog {
+            settings,
+            last_activity: started_at,
+            first_epoch: None,
+            first_epoch_done: false,
+            utilization: None,
+            state: WatchState::Watching,
+        }
+    }
+
+    /// A line of output at `at`, with the epoch it reported, if any.
+    pub fn output(&mut self, epoch: Option<usize>, at: Instant) {
+        if let Some(epoch) = epoch {
+            match self.first_epoch {
+                None => self.first_epoch = Some(epoch),
+                Some(first) if first != epoch => self.first_epoch_done = true,
+                Some(_) => {}
+            }
+        }
+        self.activity(at);
+    }
+
+    /// A line of output at `at`, its epoch found with `rules`.
+    pub fn line(&mut self, rules: &ParserRules, line: &str, at: Instant) {
+        let epoch = rules.parse_line(line).into_iter()
+            .find(|(field, _)| *field == ProgressField::Epoch)
+            .map(|(_, value)| value as usize);
+        self.output(epoch, at);
+    }
+
+    /// GPU utilization sampled at `at`. Only a change counts: a deadlocked
+    /// job sits at the same figure, often 0% or 100%.
+    pub fn utilization(&mut self, percent: f64, at: Instant) {
+        let changed = self.utilization.is_some_and(|last| last != percent);
+        self.utilization = Some(percent);
+        if changed {
+            self.activity(at);
+        }
+    }
+
+    /// Re-evaluates at `now`. Returns true when the job has just become
+    /// possibly hung, for a one-off notification.
+    pub fn check(&mut self, now: Instant) -> bool {
+        if !self.settings.enabled {
+            return false;
+        }
+        let quiet_for = now.saturating_duration_since(self.last_activity);
+        match self.state {
+            WatchState::Snoozed { until } if now < until => false,
+            WatchState::PossiblyHung { .. } => {
+                self.state = WatchState::PossiblyHung { quiet_for };
+                false
+            }
+            _ if quiet_for >= self.threshold() => {
+                self.state = WatchState::PossiblyHung { quiet_for };
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Puts the warning off for another full threshold.
+    pub fn snooze(&mut self, now: Instant) {
+        self.state = WatchState::Snoozed { until: now + self.threshold() };
+    }
+
+    pub fn state(&self) -> WatchState {
+        self.state
+    }
+
+    pub fn is_hung(&self) -> bool {
+        matches!(self.state, WatchState::PossiblyHung { .. })
+    }
+
+    /// E.g. "possibly hung, quiet for 42 min", while it is.
+    pub fn warning(&self) -> Option<String> {
+        match self.state {
+            WatchState::PossiblyHung { quiet_for } => Some(format!("possibly hung, quiet for {} min", quiet_for.as_secs() / 60)),
+            _ => None,
+        }
+    }
+
+    pub fn first_epoch_done(&self) -> bool {
+        self.first_epoch_done
+    }
+
+    /// The quiet time allowed now: the longer one until the first epoch is
+    /// over.
+    pub fn threshold(&self) -> Duration {
+        let minutes = if self.first_epoch_done { self.settings.quiet_minutes } else { self.settings.first_epoch_minutes };
+        Duration::from_secs(minutes * 60)
+    }
+
+    fn activity(&mut self, at: Instant) {
+        self.last_activity = self.last_activity.max(at);
+        self.state = WatchState::Watching;
+    }
+}
+
+/// Python stack of every thread of `pid`, from `py-spy dump`.
+pub fn py_spy_dump(pid: u32) -> Result<String> {
+    let output = match Command::new("py-spy").args(["dump", "--pid", &pid.to_string()]).output() {
+        Ok(output) => output,
+        Err(e) if e.kind() == ErrorKind::NotFound => bail!("py-spy is not installed (pip install py-spy)"),
+        Err(e) => return Err(e).context("Failed to run py-spy"),
+    };
+    if !output.status.success() {
+        bail!("py-spy failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn watchdog() -> (Watchdog, Instant) {
+        let start = Instant::now();
+        (Watchdog::new(WatchdogSettings { enabled: true, quiet_minutes: 10, first_epoch_minutes: 60 }, start), start)
+    }
+
+    #[test]
+    fn first_epoch_gets_the_longer_threshold() {
+        let (mut watchdog, start) = watchdog();
+        watchdog.output(Some(0), start + MINUTE);
+        // Caching the dataset: quiet well past the normal threshold.
+        assert!(!watchdog.check(start + 50 * MINUTE));
+        assert_eq!(watchdog.state(), WatchState::Watching);
+        assert!(watchdog.check(start + 61 * MINUTE));
+        assert_eq!(watchdog.state(), WatchState::PossiblyHung { quiet_for: 60 * MINUTE });
+        assert_eq!(watchdog.warning().as_deref(), Some("possibly hung, quiet for 60 min"));
+
+        // The next epoch clears it and the normal threshold applies.
+        watchdog.line(&ParserRules::builtin(), "Train epoch: 1 Learning rate: 9e-05", start + 70 * MINUTE);
+        assert!(watchdog.first_epoch_done());
+        assert!(!watchdog.is_hung());
+        assert!(!watchdog.check(start + 79 * MINUTE));
+        assert!(watchdog.check(start + 80 * MINUTE));
+        // Fires once per hang; later checks only update the quiet time.
+        assert!(!watchdog.check(start + 90 * MINUTE));
+        assert_eq!(watchdog.state(), WatchState::PossiblyHung { quiet_for: 20 * MINUTE });
+    }
+
+    #[test]
+    fn only_a_utilization_change_counts_as_activity() {
+        let (mut watchdog, start) = watchdog();
+        watchdog.output(Some(3), start);
+        watchdog.output(Some(4), start);
+        watchdog.utilization(100.0, start + MINUTE);
+        watchdog.utilization(100.0, start + 9 * MINUTE);
+        assert!(watchdog.check(start + 10 * MINUTE), "a pinned GPU is no sign of life");
+
+        watchdog.utilization(97.0, start + 12 * MINUTE);
+        assert_eq!(watchdog.state(), WatchState::Watching);
+        assert!(!watchdog.check(start + 21 * MINUTE));
+        assert!(watchdog.check(start + 22 * MINUTE));
+    }
+
+    #[test]
+    fn snooze_puts_the_warning_off_for_a_threshold() {
+        let (mut watchdog, start) = watchdog();
+        watchdog.output(Some(1), start);
+        watchdog.output(Some(2), start);
+        assert!(watchdog.check(start + 10 * MINUTE));
+        watchdog.snooze(start + 15 * MINUTE);
+        assert!(!watchdog.check(start + 24 * MINUTE));
+        assert_eq!(watchdog.state(), WatchState::Snoozed { until: start + 25 * MINUTE });
+        assert!(watchdog.check(start + 25 * MINUTE), "still quiet once the snooze is up");
+
+        // Turned off, it never fires.
+        let mut off = Watchdog::new(WatchdogSettings { enabled: false, ..Default::default() }, start);
+        assert!(!off.check(start + 1000 * MINUTE));
+    }
+}
@@ -0,0 +1,481 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Alignment, Rect},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
+    Frame,
+};
+use std::cell::Cell;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::table::scroll_offset;
+use crate::theme::Styles;
+
+/// The smallest terminal the screens are laid out for.
+pub const MIN_WIDTH: u16 = 60;
+pub const MIN_HEIGHT: u16 = 18;
+
+pub fn too_small(area: Rect) -> bool {
+    area.width < MIN_WIDTH || area.height < MIN_HEIGHT
+}
+
+/// Drawn instead of the screen while `too_small` holds.
+pub fn render_too_small(f: &mut Frame, area: Rect, styles: &Styles) {
+    let text = format!(
+        "Terminal too small ({}x{}): resize to at least {}x{}",
+        area.width, area.height, MIN_WIDTH, MIN_HEIGHT
+    );
+    let middle = Rect { y: area.y + area.height.saturating_sub(1) / 2, height: area.height.min(area.height / 2 + 1), ..area };
+    f.render_widget(Paragraph::new(text).alignment(Alignment::Center).wrap(Wrap { trim: true }).style(styles.warning), middle);
+}
+
+/// A failure shown in a modal over the screen until it is dismissed,
+/// taking every key meanwhile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorState {
+    pub title: String,
+    /// The error and each context it was wrapped in, outermost first.
+    pub detail: Vec<String>,
+    /// False for errors the app can't go on after: only quitting is left.
+    pub dismissable: bool,
+}
+
+impl ErrorState {
+    pub fn new(title: &str, error: &anyhow::Error) -> Self {
+        ErrorState { title: title.to_string(), detail: error.chain().map(|cause| cause.to_string()).collect(), dismissable: true }
+    }
+
+    pub fn fatal(title: &str, error: &anyhow::Error) -> Self {
+        ErrorState { dismissable: false, ..Self::new(title, error) }
+    }
+
+    /// The keys that close the modal, or quit when it can't be closed.
+    pub fn hint(&self) -> &'static str {
+        if self.dismissable { "Enter/Esc: dismiss" } else { "q: quit" }
+    }
+
+    /// E.g. "Failed to read config.yaml", then "Caused by: No such file".
+    pub fn lines(&self) -> Vec<String> {
+        self.detail.iter().enumerate()
+            .map(|(i, cause)| if i == 0 { cause.clone() } else { format!("Caused by: {}", cause) })
+            .collect()
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect, styles: &Styles) {
+        let width = area.width * 3 / 4;
+        let text = self.lines().join("\n");
+        // Wrapped lines, roughly, plus the border and the hint.
+        let inner = width.saturating_sub(2).max(1) as usize;
+        let rows: usize = self.lines().iter().map(|line| line.chars().count().max(1).div_ceil(inner)).sum();
+        let height = (rows as u16 + 2).clamp(5, area.height.max(5)).min(area.height);
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+        f.render_widget(Clear, popup);
+        f.render_widget(
+            Paragraph::new(text)
+                .wrap(Wrap { trim: false })
+                .block(Block::default().borders(Borders::ALL).border_style(styles.error).title(self.title.as_str()).title_bottom(self.hint()))
+                .style(styles.text),
+            popup,
+        );
+    }
+}
+
+/// What a `FileBrowser` picks.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PickMode {
+    /// A folder: only folders are listed, Space picks the one shown.
+    Directory,
+    /// A file with one of these extensions, lowercase without the dot; any
+    /// file when empty.
+    File(Vec<String>),
+}
+
+impl PickMode {
+    pub fn files(extensions: &[&str]) -> Self {
+        PickMode::File(extensions.iter().map(|ext| ext.to_string()).collect())
+    }
+
+    fn lists(&self, name: &str, is_dir: bool) -> bool {
+        match self {
+            _ if is_dir => true,
+            PickMode::Directory => false,
+            PickMode::File(extensions) => {
+                extensions.is_empty()
+                    || Path::new(name).extension()
+                        .and_then(|ext| ext.to_str())
+                        .is_some_and(|ext| extensions.contains(&ext.to_lowercase()))
+            }
+        }
+    }
+}
+
+/// What a key did to the browser.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BrowserAction {
+    Stay,
+    Picked(PathBuf),
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct BrowserEntry {
+    name: String,
+    is_dir: bool,
+}
+
+/// Picks a path one folder at a time instead of typing it, for any prompt
+/// that takes one. The screen that opened it keeps it in the App state and
+/// gets the path back from `handle_key`.
+///
+/// Keys: Up/Down/PgUp/PgDn/Home/End move, Enter opens a folder or picks a
+/// file, Space picks the folder shown (directory mode), Backspace goes up,
+/// `.` shows or hides hidden entries, any other character narrows the list
+/// with a fuzzy filter and Esc clears it, then cancels.
+#[derive(Debug, Clone)]
+pub struct FileBrowser {
+    dir: PathBuf,
+    mode: PickMode,
+    entries: Vec<BrowserEntry>,
+    show_hidden: bool,
+    filter: String,
+    selected: usize,
+    offset: Cell<usize>,
+    /// Why the folder couldn't be listed, e.g. permission denied.
+    error: Option<String>,
+}
+
+impl FileBrowser {
+    /// Opens at `start`: the folder of a file, the nearest folder that
+    /// exists above a missing path, or the current folder when empty.
+    pub fn open(start: &Path, mode: PickMode) -> Self {
+        let mut dir = if start.as_os_str().is_empty() { Path::new(".") } else { start };
+        while !dir.is_dir() {
+            dir = match dir.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+        }
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        let mut browser = FileBrowser {
+            dir: PathBuf::new(),
+            mode,
+            entries: vec![],
+            show_hidden: false,
+            filter: String::new(),
+            selected: 0,
+            offset: Cell::new(0),
+            error: None,
+        };
+        browser.read(dir);
+        if start.is_file()
+            && let Some(name) = start.file_name()
+        {
+            let name = name.to_string_lossy();
+            browser.selected = browser.visible().iter().position(|entry| entry.name == name).unwrap_or(0);
+        }
+        browser
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Lists the folder again, e.g. after files appeared in it, keeping
+    /// the filter and the selected entry if it is still there.
+    pub fn reload(&mut self) {
+        let selected = self.visible().get(self.selected).map(|entry| entry.name.clone());
+        let filter = std::mem::take(&mut self.filter);
+        self.read(self.dir.clone());
+        self.filter = filter;
+        self.selected = selected
+            .and_then(|name| self.visible().iter().position(|entry| entry.name == name))
+            .unwrap_or(0);
+    }
+
+    fn read(&mut self, dir: PathBuf) {
+        self.filter.clear();
+        self.selected = 0;
+        self.offset.set(0);
+        self.entries.clear();
+        self.error = None;
+        match fs::read_dir(&dir) {
+            Ok(read) => {
+                self.entries = read.flatten()
+                    .map(|entry| BrowserEntry {
+                        name: entry.file_name().to_string_lossy().into_owned(),
+                        is_dir: entry.path().is_dir(),
+                    })
+                    .filter(|entry| self.mode.lists(&entry.name, entry.is_dir))
+                    .collect();
+                self.entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())));
+            }
+            Err(e) => self.error = Some(format!("Can't list {}: {}", dir.display(), e)),
+        }
+        self.dir = dir;
+    }
+
+    /// "..", unless filtering, then what the filter and the hidden toggle
+    /// leave, folders first.
+    fn visible(&self) -> Vec<BrowserEntry> {
+        let parent = self.dir.parent()
+            .filter(|_| self.filter.is_empty())
+            .map(|_| BrowserEntry { name: "..".to_string(), is_dir: true });
+        parent.into_iter()
+            .chain(self.entries.iter()
+                .filter(|entry| self.show_hidden || !entry.name.starts_with('.'))
+                .filter(|entry| fuzzy_match(&self.filter, &entry.name))
+                .cloned())
+            .collect()
+    }
+
+    fn go_up(&mut self) {
+        if let Some(parent) = self.dir.parent() {
+            let left = self.dir.file_name().map(|name| name.to_string_lossy().into_owned());
+            self.read(parent.to_path_buf());
+            if let Some(left) = left {
+                self.selected = self.visible().iter().position(|entry| entry.name == left).unwrap_or(0);
+            }
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> BrowserAction {
+        let last = self.visible().len().saturating_sub(1);
+        match key.code {
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = (self.selected + 1).min(last),
+            KeyCode::PageUp => self.selected = self.selected.saturating_sub(10),
+            KeyCode::PageDown => self.selected = (self.selected + 10).min(last),
+            KeyCode::Home => self.selected = 0,
+            KeyCode::End => self.selected = last,
+            KeyCode::Esc if !self.filter.is_empty() => {
+                self.filter.clear();
+                self.selected = 0;
+            }
+            KeyCode::Esc => return BrowserAction::Cancelled,
+            KeyCode::Backspace if self.filter.pop().is_some() => self.selected = 0,
+            KeyCode::Backspace => self.go_up(),
+            KeyCode::Enter => match self.visible().into_iter().nth(self.selected) {
+                Some(entry) if entry.name == ".." => self.go_up(),
+                Some(entry) if entry.is_dir => self.read(self.dir.join(entry.name)),
+                Some(entry) => return BrowserAction::Picked(self.dir.join(entry.name)),
+                None => {}
+            },
+            KeyCode::Char(' ') if self.mode == PickMode::Directory && self.error.is_none() => {
+                return BrowserAction::Picked(self.dir.clone());
+            }
+            // A folder that can't be listed can't be picked either, and
+            // Space doesn't filter in directory mode.
+            KeyCode::Char(' ') if self.mode == PickMode::Directory => {}
+            KeyCode::Char('.') => {
+                self.show_hidden = !self.show_hidden;
+                self.selected = 0;
+            }
+            KeyCode::Char(c) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                self.filter.push(c);
+                self.selected = 0;
+            }
+            _ => {}
+        }
+        BrowserAction::Stay
+    }
+
+    /// The browser's keys, in the footer and the help overlay.
+    pub fn key_help(&self) -> &'static str {
+        match self.mode {
+            PickMode::Directory => "Space: pick this folder    Enter: open a folder    Backspace: go up    .: show hidden    type: filter    Esc: cancel",
+            PickMode::File(_) => "Enter: open a folder or pick a file    Backspace: go up    .: show hidden    type: filter    Esc: cancel",
+        }
+    }
+
+    fn lines(&self) -> Vec<String> {
+        self.visible().into_iter()
+            .map(|entry| if entry.is_dir && entry.name != ".." { format!("{}/", entry.name) } else { entry.name })
+            .collect()
+    }
+
+    /// Drawn over the middle of `area`.
+    pub fn render(&self, f: &mut Frame, area: Rect, styles: &Styles) {
+        let popup = Rect {
+            x: area.x + area.width / 8,
+            y: area.y + area.height / 8,
+            width: area.width * 3 / 4,
+            height: area.height * 3 / 4,
+        };
+        let mut title = self.dir.display().to_string();
+        if !self.filter.is_empty() {
+            title.push_str(&format!("  filter: {}", self.filter));
+        }
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(styles.border)
+            .title(title)
+            .title_bottom(self.key_help());
+        let height = block.inner(popup).height as usize;
+
+        let mut items = vec![];
+        if let Some(error) = &self.error {
+            items.push(ListItem::new(error.clone()).style(styles.error));
+        }
+        let rows = height.saturating_sub(items.len());
+        let offset = scroll_offset(self.selected, self.offset.get(), rows);
+        self.offset.set(offset);
+        items.extend(self.lines()
+            .into_iter()
+            .enumerate()
+            .skip(offset)
+            .take(rows)
+            .map(|(i, line)| {
+                let item = ListItem::new(line);
+                if i == self.selected { item.style(styles.highlight) } else { item }
+            }));
+        f.render_widget(Clear, popup);
+        f.render_widget(List::new(items).block(block), popup);
+    }
+
+    /// The browser as lines for plain mode.
+    pub fn plain_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("Browsing: {}", self.dir.display())];
+        if !self.filter.is_empty() {
+            lines.push(format!("Filter: {}", self.filter));
+        }
+        if let Some(error) = &self.error {
+            lines.push(format!("Error: {}", error));
+        }
+        let entries = self.lines();
+        let count = entries.len();
+        lines.extend(entries.into_iter().enumerate().map(|(i, entry)| format!("{}. {}", i + 1, entry)));
+        if let Some(selected) = self.lines().get(self.selected) {
+            lines.push(format!("Selected: {} of {}: {}", self.selected + 1, count, selected));
+        }
+        lines.push(format!("Keys: {}", self.key_help()));
+        lines
+    }
+}
+
+/// Whether the characters of `filter` appear in `name` in order, ignoring
+/// case: "vl" matches "valid".
+fn fuzzy_match(filter: &str, name: &str) -> bool {
+    let mut name = name.chars().flat_map(char::to_lowercase);
+    filter.chars().flat_map(char::to_lowercase).all(|wanted| name.any(|c| c == wanted))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ratatui::{backend::TestBackend, Terminal};
+
+    #[test]
+    fn a_small_terminal_asks_to_be_resized() {
+        let mut terminal = Terminal::new(TestBackend::new(40, 10)).unwrap();
+        assert!(too_small(terminal.size().unwrap()));
+        terminal.draw(|f| render_too_small(f, f.size(), &Styles::default())).unwrap();
+        let buffer = terminal.backend().buffer();
+        let text: String = (0..10).map(|y| (0..40).map(|x| buffer.get(x, y).symbol()).collect::<String>()).collect::<Vec<_>>().join("\n");
+        assert!(text.contains("Terminal too small (40x10):"), "{}", text);
+        assert!(text.contains("60x18"), "{}", text);
+        assert!(!too_small(Rect { x: 0, y: 0, width: MIN_WIDTH, height: MIN_HEIGHT }));
+        assert!(too_small(Rect { x: 0, y: 0, width: 200, height: MIN_HEIGHT - 1 }));
+    }
+
+    #[test]
+    fn an_error_shows_its_whole_context_chain() {
+        let error = anyhow::anyhow!("No such file or directory").context("Failed to read configs/config_vocals.yaml").context("Failed to load the training config");
+        let state = ErrorState::new("Config not loaded", &error);
+        assert_eq!(state.lines(), [
+            "Failed to load the training config",
+            "Caused by: Failed to read configs/config_vocals.yaml",
+            "Caused by: No such file or directory",
+        ]);
+
+        let mut terminal = Terminal::new(TestBackend::new(60, 18)).unwrap();
+        terminal.draw(|f| state.render(f, f.size(), &Styles::default())).unwrap();
+        let buffer = terminal.backend().buffer();
+        let text: String = (0..18).map(|y| (0..60).map(|x| buffer.get(x, y).symbol()).collect::<String>()).collect::<Vec<_>>().join("\n");
+        assert!(text.contains("Config not loaded"), "{}", text);
+        assert!(text.contains("Caused by: No such file"), "{}", text);
+        assert!(text.contains("Enter/Esc: dismiss"), "{}", text);
+    }
+
+    fn press(browser: &mut FileBrowser, codes: &[KeyCode]) -> BrowserAction {
+        codes.iter().fold(BrowserAction::Stay, |_, code| browser.handle_key(KeyEvent::from(*code)))
+    }
+
+    fn tree() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for folder in ["train", "valid", ".cache", "Validation extra"] {
+            fs::create_dir(dir.path().join(folder)).unwrap();
+        }
+        for file in ["a.yaml", "b.YML", "notes.txt", ".hidden.yaml"] {
+            fs::write(dir.path().join(file), "").unwrap();
+        }
+        fs::write(dir.path().join("valid").join("mix.wav"), "").unwrap();
+        dir
+    }
+
+    #[test]
+    fn folders_come_first_and_are_picked_with_space() {
+        let dir = tree();
+        let root = dir.path().canonicalize().unwrap();
+        let mut browser = FileBrowser::open(&root.join("missing/deeper"), PickMode::Directory);
+        assert_eq!(browser.dir(), root);
+        assert_eq!(browser.lines(), ["..", "train/", "valid/", "Validation extra/"]);
+
+        press(&mut browser, &[KeyCode::Char('.')]);
+        assert_eq!(browser.lines()[1], ".cache/");
+        press(&mut browser, &[KeyCode::Char('.'), KeyCode::Char('v'), KeyCode::Char('l'), KeyCode::Char('x')]);
+        assert_eq!(browser.lines(), ["Validation extra/"], "fuzzy, and .. goes too");
+        press(&mut browser, &[KeyCode::Backspace]);
+        assert_eq!(browser.lines(), ["valid/", "Validation extra/"]);
+        press(&mut browser, &[KeyCode::Enter]);
+        assert_eq!(browser.dir(), root.join("valid"));
+        assert_eq!(browser.lines(), [".."], "files aren't listed for a folder");
+        assert_eq!(press(&mut browser, &[KeyCode::Char(' ')]), BrowserAction::Picked(root.join("valid")));
+
+        press(&mut browser, &[KeyCode::Backspace]);
+        assert_eq!((browser.dir(), browser.lines()[browser.selected].as_str()), (root.as_path(), "valid/"));
+        assert_eq!(press(&mut browser, &[KeyCode::Char('q'), KeyCode::Esc, KeyCode::Esc]), BrowserAction::Cancelled);
+    }
+
+    #[test]
+    fn files_are_filtered_by_extension_and_picked_with_enter() {
+        let dir = tree();
+        let root = dir.path().canonicalize().unwrap();
+        let mut browser = FileBrowser::open(&root.join("b.YML"), PickMode::files(&["yaml", "yml"]));
+        assert_eq!(browser.lines(), ["..", "train/", "valid/", "Validation extra/", "a.yaml", "b.YML"]);
+        assert_eq!(browser.selected, 5, "starts on the file given");
+        assert_eq!(press(&mut browser, &[KeyCode::Up, KeyCode::Enter]), BrowserAction::Picked(root.join("a.yaml")));
+        assert_eq!(press(&mut browser, &[KeyCode::Char(' ')]), BrowserAction::Stay);
+
+        // A config written from elsewhere shows up, the selection stays put.
+        press(&mut browser, &[KeyCode::Esc, KeyCode::Down, KeyCode::Down, KeyCode::Down, KeyCode::Down]);
+        assert_eq!(browser.lines()[browser.selected], "a.yaml");
+        fs::write(root.join("0_first.yaml"), "").unwrap();
+        browser.reload();
+        assert_eq!(browser.lines(), ["..", "train/", "valid/", "Validation extra/", "0_first.yaml", "a.yaml", "b.YML"]);
+        assert_eq!(browser.lines()[browser.selected], "a.yaml");
+    }
+
+    #[test]
+    fn a_folder_that_cant_be_listed_shows_an_error() {
+        let dir = tree();
+        let root = dir.path().canonicalize().unwrap();
+        let mut browser = FileBrowser::open(&root, PickMode::Directory);
+        fs::remove_dir(root.join("train")).unwrap();
+        press(&mut browser, &[KeyCode::Down, KeyCode::Enter]);
+        assert!(browser.error().unwrap().starts_with("Can't list "), "{:?}", browser.error());
+        assert_eq!(browser.lines(), [".."]);
+        assert_eq!(press(&mut browser, &[KeyCode::Char(' ')]), BrowserAction::Stay);
+        press(&mut browser, &[KeyCode::Enter]);
+        assert_eq!((browser.dir(), browser.error()), (root.as_path(), None));
+    }
+}
@@ -0,0 +1,209 @@
+use anyhow::{Context, Result};
+use serde_yaml::Value;
+use std::fs;
+use std::path::Path;
+
+/// How a value differs between the old and the new YAML.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Added(Value),
+    Removed(Value),
+    Changed { old: Value, new: Value },
+    /// A sequence that got longer or shorter; its items are diffed by
+    /// index as well.
+    Length { old: usize, new: usize },
+}
+
+/// One difference, at the keys leading to it from the root. Sequence
+/// indices are kept as e.g. "[2]".
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffEntry {
+    pub path: Vec<String>,
+    pub change: Change,
+}
+
+impl DiffEntry {
+    /// The top-level key the entry is under.
+    pub fn section(&self) -> &str {
+        self.path.first().map_or("(top level)", String::as_str)
+    }
+
+    /// E.g. "training.instruments[1]".
+    pub fn dotted(&self) -> String {
+        let mut dotted = String::new();
+        for key in &self.path {
+            if !dotted.is_empty() && !key.starts_with('[') {
+                dotted.push('.');
+            }
+            dotted.push_str(key);
+        }
+        if dotted.is_empty() { "(root)".to_string() } else { dotted }
+    }
+
+    /// E.g. "~ training.lr: 0.0001 → 0.0003" or "+ training.ema: 0.999".
+    pub fn line(&self) -> String {
+        match &self.change {
+            Change::Added(value) => format!("+ {}: {}", self.dotted(), short(value)),
+            Change::Removed(value) => format!("- {}: {}", self.dotted(), short(value)),
+            Change::Changed { old, new } if type_name(old) != type_name(new) => {
+                format!("~ {}: {} → {} ({} → {})", self.dotted(), short(old), short(new), type_name(old), type_name(new))
+            }
+            Change::Changed { old, new } => format!("~ {}: {} → {}", self.dotted(), short(old), short(new)),
+            Change::Length { old, new } => format!("~ {}: {} → {} items", self.dotted(), old, new),
+        }
+    }
+}
+
+/// The differences from `old` to `new`: maps by key, in the order of the
+/// old file and then the keys only the new one has, sequences by index.
+pub fn yaml_diff(old: &Value, new: &Value) -> Vec<DiffEntry> {
+    let mut entries = vec![];
+    diff(&mut vec![], old, new, &mut entries);
+    entries
+}
+
+fn diff(path: &mut Vec<String>, old: &Value, new: &Value, entries: &mut Vec<DiffEntry>) {
+    match (old, new) {
+        (Value::Mapping(old), Value::Mapping(new)) => {
+            for (key, old_value) in old {
+                path.push(key_text(key));
+                match new.get(key) {
+                    Some(new_value) => diff(path, old_value, new_value, entries),
+                    None => entries.push(DiffEntry { path: path.clone(), change: Change::Removed(old_value.clone()) }),
+                }
+                path.pop();
+            }
+            for (key, new_value) in new.iter().filter(|(key, _)| !old.contains_key(*key)) {
+                path.push(key_text(key));
+                entries.push(DiffEntry { path: path.clone(), change: Change::Added(new_value.clone()) });
+                path.pop();
+            }
+        }
+        (Value::Sequence(old), Value::Sequence(new)) => {
+            if old.len() != new.len() {
+                entries.push(DiffEntry { path: path.clone(), change: Change::Length { old: old.len(), new: new.len() } });
+            }
+            for index in 0..old.len().max(new.len()) {
+                path.push(format!("[{}]", index));
+                match (old.get(index), new.get(index)) {
+                    (Some(old), Some(new)) => diff(path, old, new, entries),
+                    (Some(old), None) => entries.push(DiffEntry { path: path.clone(), change: Change::Removed(old.clone()) }),
+                    (None, Some(new)) => entries.push(DiffEntry { path: path.clone(), change: Change::Added(new.clone()) }),
+                    (None, None) => {}
+                }
+                path.pop();
+            }
+        }
+        (old, new) if old != new => entries.push(DiffEntry { path: path.clone(), change: Change::Changed { old: old.clone(), new: new.clone() } }),
+        _ => {}
+    }
+}
+
+/// The differences between two YAML files.
+pub fn diff_files(old: &Path, new: &Path) -> Result<Vec<DiffEntry>> {
+    let read = |path: &Path| -> Result<Value> {
+        let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_yaml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+    };
+    Ok(yaml_diff(&read(old)?, &read(new)?))
+}
+
+/// `entries` by top-level section, sections in the order they first come.
+pub fn by_section(entries: &[DiffEntry]) -> Vec<(&str, Vec<&DiffEntry>)> {
+    let mut sections: Vec<(&str, Vec<&DiffEntry>)> = vec![];
+    for entry in entries {
+        match sections.iter_mut().find(|(section, _)| *section == entry.section()) {
+            Some((_, grouped)) => grouped.push(entry),
+            None => sections.push((entry.section(), vec![entry])),
+        }
+    }
+    sections
+}
+
+fn key_text(key: &Value) -> String {
+    match key {
+        Value::String(text) => text.clone(),
+        key => short(key),
+    }
+}
+
+/// A value on one line: scalars as written, short lists of scalars in
+/// brackets, anything bigger by its size.
+fn short(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(flag) => flag.to_string(),
+        Value::Number(number) => number.to_string(),
+        Value::String(text) => text.clone(),
+        Value::Sequence(items) if items.len() <= 8 && items.iter().all(|item| !matches!(item, Value::Sequence(_) | Value::Mapping(_))) => {
+            format!("[{}]", items.iter().map(short).collect::<Vec<_>>().join(", "))
+        }
+        Value::Sequence(items) => format!("[{} items]", items.len()),
+        Value::Mapping(map) if map.len() == 1 => "{1 key}".to_string(),
+        Value::Mapping(map) => format!("{{{} keys}}", map.len()),
+        Value::Tagged(tagged) => format!("{} {}", tagged.tag, short(&tagged.value)),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(number) if number.is_f64() => "float",
+        Value::Number(_) => "int",
+        Value::String(_) => "string",
+        Value::Sequence(_) => "list",
+        Value::Mapping(_) => "map",
+        Value::Tagged(_) => "tagged",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(old: &str, new: &str) -> Vec<String> {
+        let old: Value = serde_yaml::from_str(old).unwrap();
+        let new: Value = serde_yaml::from_str(new).unwrap();
+        yaml_diff(&old, &new).iter().map(DiffEntry::line).collect()
+    }
+
+    #[test]
+    fn nested_maps_and_sequences_diff_by_key_and_index() {
+        let old = "training:\n  lr: 0.0001\n  optimizer:\n    name: adam\n    betas: [0.9, 0.99]\n  instruments: [vocals, other]\naudio:\n  chunk_size: 485100\n";
+        let new = "training:\n  lr: 0.0003\n  optimizer:\n    name: adam\n    betas: [0.9, 0.999]\n  instruments: [vocals, drums, other]\n  ema: 0.999\naudio:\n  chunk_size: 485100\n";
+        assert_eq!(
+            lines(old, new),
+            [
+                "~ training.lr: 0.0001 → 0.0003",
+                "~ training.optimizer.betas[1]: 0.99 → 0.999",
+                "~ training.instruments: 2 → 3 items",
+                "~ training.instruments[1]: other → drums",
+                "+ training.instruments[2]: other",
+                "+ training.ema: 0.999",
+            ]
+        );
+        assert!(lines(old, old).is_empty());
+    }
+
+    #[test]
+    fn type_changes_and_missing_sections_are_called_out() {
+        let old = "training:\n  num_epochs: 100\n  grad_clip: 0\ninference:\n  batch_size: 1\n  num_overlap: 4\n";
+        let new = "training:\n  num_epochs: 100.0\n  grad_clip: '0'\naugmentations:\n  enable: true\n";
+        assert_eq!(
+            lines(old, new),
+            [
+                "~ training.num_epochs: 100 → 100.0 (int → float)",
+                "~ training.grad_clip: 0 → 0 (int → string)",
+                "- inference: {2 keys}",
+                "+ augmentations: {1 key}",
+            ]
+        );
+
+        let old: Value = serde_yaml::from_str(old).unwrap();
+        let new: Value = serde_yaml::from_str(new).unwrap();
+        let entries = yaml_diff(&old, &new);
+        let sections: Vec<(&str, usize)> = by_section(&entries).iter().map(|(section, entries)| (*section, entries.len())).collect();
+        assert_eq!(sections, [("training", 2), ("inference", 1), ("augmentations", 1)]);
+    }
+}